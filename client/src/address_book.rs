@@ -0,0 +1,303 @@
+pub use dns_label_resolver::*;
+pub use error::*;
+
+mod dns_label_resolver;
+mod error;
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use moka::future::Cache;
+use tonlib_core::TonAddress;
+
+pub const DEFAULT_ADDRESS_BOOK_CACHE_CAPACITY: u64 = 10_000;
+pub const DEFAULT_ADDRESS_BOOK_CACHE_TIME_TO_LIVE: Duration = Duration::from_secs(3600);
+
+lazy_static! {
+    /// Labels of system contracts of the masterchain, as set in config params 0-2,
+    /// and of the zero address used to burn coins.
+    pub static ref SYSTEM_CONTRACT_LABELS: Vec<(TonAddress, &'static str)> = vec![
+        (TonAddress::new(-1, &[0x55; 32]), "Config"),
+        (TonAddress::new(-1, &[0x33; 32]), "Elector"),
+        (TonAddress::new(-1, &[0; 32]), "System"),
+        (TonAddress::NULL, "Burn"),
+    ];
+}
+
+/// Source of address labels, e.g. a database of exchange wallets.
+#[async_trait]
+pub trait LabelResolver: Send + Sync {
+    /// Returns the label of `address`, `None` if the address is unknown to the source.
+    async fn resolve_label(&self, address: &TonAddress)
+        -> Result<Option<String>, AddressBookError>;
+}
+
+/// Maps addresses to human-readable labels, e.g. for logs or explorer-style output.
+///
+/// Static labels, including [`SYSTEM_CONTRACT_LABELS`], are returned by `label` right away.
+/// `resolve` also asks the resolvers in the order they're added and caches their answers,
+/// including unknown addresses. Failed resolvers are skipped and asked again next time.
+#[derive(Clone)]
+pub struct AddressBook {
+    labels: HashMap<TonAddress, String>,
+    resolvers: Vec<Arc<dyn LabelResolver>>,
+    cache: Cache<TonAddress, Option<String>>,
+}
+
+impl AddressBook {
+    /// Creates an address book knowing the system contracts.
+    pub fn new() -> AddressBook {
+        let mut book = Self::empty();
+        for (address, label) in SYSTEM_CONTRACT_LABELS.iter() {
+            book.with_label(address, label);
+        }
+        book
+    }
+
+    /// Creates an address book without any labels.
+    pub fn empty() -> AddressBook {
+        AddressBook {
+            labels: HashMap::new(),
+            resolvers: vec![],
+            cache: Cache::builder()
+                .max_capacity(DEFAULT_ADDRESS_BOOK_CACHE_CAPACITY)
+                .time_to_live(DEFAULT_ADDRESS_BOOK_CACHE_TIME_TO_LIVE)
+                .build(),
+        }
+    }
+
+    pub fn with_label(&mut self, address: &TonAddress, label: &str) -> &mut Self {
+        self.labels.insert(address.clone(), label.to_string());
+        self
+    }
+
+    /// Adds labels of a JSON object mapping addresses in any format to labels, e.g.
+    /// `{"EQCD39VS5jcptHL8vMjEXrzGaRcCVYto7HUn4bpAOg8xqB2N": "TON Foundation"}`.
+    pub fn with_labels_from_json(&mut self, json: &str) -> Result<&mut Self, AddressBookError> {
+        let labels: HashMap<String, String> = serde_json::from_str(json)?;
+        for (address, label) in labels {
+            let parsed = address
+                .parse()
+                .map_err(|error| AddressBookError::InvalidAddress { address, error })?;
+            self.labels.insert(parsed, label);
+        }
+        Ok(self)
+    }
+
+    /// Adds labels of a JSON file, see `with_labels_from_json`.
+    pub fn with_labels_from_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+    ) -> Result<&mut Self, AddressBookError> {
+        let json = std::fs::read_to_string(path)?;
+        self.with_labels_from_json(&json)
+    }
+
+    pub fn with_resolver(&mut self, resolver: Arc<dyn LabelResolver>) -> &mut Self {
+        self.resolvers.push(resolver);
+        self
+    }
+
+    pub fn with_cache(&mut self, capacity: u64, time_to_live: Duration) -> &mut Self {
+        self.cache = Cache::builder()
+            .max_capacity(capacity)
+            .time_to_live(time_to_live)
+            .build();
+        self
+    }
+
+    /// Returns the static label of `address`, without asking the resolvers.
+    pub fn label(&self, address: &TonAddress) -> Option<&str> {
+        self.labels.get(address).map(String::as_str)
+    }
+
+    /// Returns the label of `address`, asking the resolvers if there's no static label.
+    pub async fn resolve(&self, address: &TonAddress) -> Option<String> {
+        if let Some(label) = self.label(address) {
+            return Some(label.to_string());
+        }
+        if self.resolvers.is_empty() {
+            return None;
+        }
+        if let Some(label) = self.cache.get(address).await {
+            return label;
+        }
+        let mut failed = false;
+        for resolver in self.resolvers.iter() {
+            match resolver.resolve_label(address).await {
+                Ok(Some(label)) => {
+                    self.cache
+                        .insert(address.clone(), Some(label.clone()))
+                        .await;
+                    return Some(label);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    log::warn!("Failed to resolve label of {}: {}", address, e);
+                    failed = true;
+                }
+            }
+        }
+        if !failed {
+            self.cache.insert(address.clone(), None).await;
+        }
+        None
+    }
+
+    /// Returns `address` displayed with its static label.
+    pub fn display<'a>(&'a self, address: &'a TonAddress) -> DisplayAddress<'a> {
+        DisplayAddress::new(address, self.label(address).map(Cow::Borrowed))
+    }
+
+    /// Returns `address` displayed with its label, asking the resolvers if needed.
+    pub async fn resolve_display<'a>(&self, address: &'a TonAddress) -> DisplayAddress<'a> {
+        let label = self.resolve(address).await;
+        DisplayAddress::new(address, label.map(Cow::Owned))
+    }
+}
+
+impl Default for AddressBook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Address formatted as `label (address)` by both `Display` and `Debug`,
+/// or as the address alone if it has no label.
+#[derive(Clone, PartialEq, Eq)]
+pub struct DisplayAddress<'a> {
+    address: &'a TonAddress,
+    label: Option<Cow<'a, str>>,
+}
+
+impl<'a> DisplayAddress<'a> {
+    pub fn new(address: &'a TonAddress, label: Option<Cow<'a, str>>) -> DisplayAddress<'a> {
+        DisplayAddress { address, label }
+    }
+
+    pub fn address(&self) -> &TonAddress {
+        self.address
+    }
+
+    pub fn label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+}
+
+impl fmt::Display for DisplayAddress<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "{} ({})", label, self.address),
+            None => write!(f, "{}", self.address),
+        }
+    }
+}
+
+impl fmt::Debug for DisplayAddress<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use tonlib_core::TonAddress;
+
+    use super::{AddressBook, AddressBookError, LabelResolver};
+    use crate::client::TonClientError;
+
+    struct TestResolver {
+        calls: AtomicUsize,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl LabelResolver for TestResolver {
+        async fn resolve_label(
+            &self,
+            address: &TonAddress,
+        ) -> Result<Option<String>, AddressBookError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.fail {
+                return Err(TonClientError::InternalError("unavailable".to_string()).into());
+            }
+            Ok((address.workchain == 0).then(|| format!("resolved-{}", address.hash_part[0])))
+        }
+    }
+
+    #[test]
+    fn test_address_book_static_labels() -> anyhow::Result<()> {
+        let mut book = AddressBook::new();
+        book.with_labels_from_json(
+            r#"{"0:0101010101010101010101010101010101010101010101010101010101010101": "Exchange"}"#,
+        )?;
+        let elector = TonAddress::new(-1, &[0x33; 32]);
+        assert_eq!(book.label(&elector), Some("Elector"));
+        assert_eq!(
+            book.display(&elector).to_string(),
+            format!("Elector ({})", elector)
+        );
+
+        let exchange = TonAddress::new(0, &[1; 32]);
+        assert_eq!(
+            format!("{:?}", book.display(&exchange)),
+            format!("Exchange ({})", exchange)
+        );
+        let unknown = TonAddress::new(0, &[2; 32]);
+        assert_eq!(book.display(&unknown).to_string(), unknown.to_string());
+
+        assert!(matches!(
+            book.with_labels_from_json(r#"{"invalid": "Label"}"#),
+            Err(AddressBookError::InvalidAddress { .. })
+        ));
+        assert!(AddressBook::empty().label(&elector).is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_address_book_resolvers() {
+        let failing = Arc::new(TestResolver {
+            calls: AtomicUsize::new(0),
+            fail: true,
+        });
+        let resolver = Arc::new(TestResolver {
+            calls: AtomicUsize::new(0),
+            fail: false,
+        });
+        let mut book = AddressBook::new();
+        book.with_resolver(failing.clone())
+            .with_resolver(resolver.clone());
+
+        let address = TonAddress::new(0, &[7; 32]);
+        assert_eq!(book.resolve(&address).await, Some("resolved-7".to_string()));
+        assert_eq!(
+            book.resolve_display(&address).await.label(),
+            Some("resolved-7")
+        );
+        assert_eq!(resolver.calls.load(Ordering::SeqCst), 1);
+
+        // Static labels aren't resolved
+        assert_eq!(
+            book.resolve(&TonAddress::NULL).await,
+            Some("Burn".to_string())
+        );
+        assert_eq!(resolver.calls.load(Ordering::SeqCst), 1);
+
+        // Unknown addresses are asked again while a resolver fails
+        let unknown = TonAddress::new(1, &[7; 32]);
+        assert_eq!(book.resolve(&unknown).await, None);
+        assert_eq!(book.resolve(&unknown).await, None);
+        assert_eq!(resolver.calls.load(Ordering::SeqCst), 3);
+        assert_eq!(failing.calls.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use tokio::sync::OnceCell;
+use tonlib_core::TonAddress;
+
+use crate::address_book::{AddressBookError, LabelResolver};
+use crate::contract::{DnsItemContract, DnsResolver, TonContractError, TonContractFactory};
+
+/// Labels addresses with TON DNS domains.
+///
+/// TON DNS has no reverse records, so two kinds of addresses are labeled:
+/// - wallets of the domains added with `with_domain`, once the domain resolves to the wallet,
+///   so that a label can't be claimed by anyone but the owner of the domain;
+/// - DNS items, i.e. domain NFTs, whose `get_full_domain` returns their domain.
+pub struct DnsLabelResolver {
+    factory: TonContractFactory,
+    resolver: DnsResolver,
+    domains: Vec<String>,
+    wallets: OnceCell<HashMap<TonAddress, String>>,
+}
+
+impl DnsLabelResolver {
+    pub fn new(factory: &TonContractFactory, resolver: &DnsResolver) -> DnsLabelResolver {
+        DnsLabelResolver {
+            factory: factory.clone(),
+            resolver: resolver.clone(),
+            domains: vec![],
+            wallets: OnceCell::new(),
+        }
+    }
+
+    /// Creates a resolver of the mainnet domains, see [`DnsResolver::mainnet`].
+    pub fn mainnet(factory: &TonContractFactory) -> DnsLabelResolver {
+        Self::new(factory, &DnsResolver::mainnet(factory))
+    }
+
+    /// Adds a domain labeling its wallet. Domains are resolved once, on the first lookup.
+    pub fn with_domain(&mut self, domain: &str) -> &mut Self {
+        self.domains.push(domain.to_string());
+        self.wallets = OnceCell::new();
+        self
+    }
+
+    async fn resolve_wallets(&self) -> Result<HashMap<TonAddress, String>, TonContractError> {
+        let mut wallets = HashMap::new();
+        for domain in self.domains.iter() {
+            match self.resolver.resolve_wallet(domain).await? {
+                Some(wallet) => {
+                    wallets.entry(wallet).or_insert_with(|| domain.clone());
+                }
+                None => log::debug!("Domain {} has no wallet record", domain),
+            }
+        }
+        Ok(wallets)
+    }
+}
+
+#[async_trait]
+impl LabelResolver for DnsLabelResolver {
+    async fn resolve_label(
+        &self,
+        address: &TonAddress,
+    ) -> Result<Option<String>, AddressBookError> {
+        let wallets = self
+            .wallets
+            .get_or_try_init(|| self.resolve_wallets())
+            .await?;
+        if let Some(domain) = wallets.get(address) {
+            return Ok(Some(domain.clone()));
+        }
+        match self.factory.get_contract(address).get_full_domain().await {
+            Ok(domain) => Ok(Some(domain)),
+            // The label may be found once the network is available again
+            Err(TonContractError::ClientError(e)) if e.is_retryable() => Err(e.into()),
+            // Any other contract, or no contract at all
+            Err(_) => Ok(None),
+        }
+    }
+}
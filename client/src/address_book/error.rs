@@ -0,0 +1,26 @@
+use thiserror::Error;
+use tonlib_core::TonAddressParseError;
+
+use crate::client::TonClientError;
+use crate::contract::TonContractError;
+
+#[derive(Debug, Error)]
+pub enum AddressBookError {
+    #[error("Invalid address {address} ({error})")]
+    InvalidAddress {
+        address: String,
+        error: TonAddressParseError,
+    },
+
+    #[error("IO error ({0})")]
+    Io(#[from] std::io::Error),
+
+    #[error("Serde_json Error ({0})")]
+    SerdeJsonError(#[from] serde_json::Error),
+
+    #[error("TonClientError ({0})")]
+    ClientError(#[from] TonClientError),
+
+    #[error("TonContractError ({0})")]
+    ContractError(#[from] TonContractError),
+}
@@ -1,35 +1,100 @@
 use std::fs;
 use std::ops::Deref;
 use std::path::Path;
-use std::sync::Arc;
-use std::thread::JoinHandle;
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock, Weak};
+use std::time::{Duration, Instant};
 
+pub use account_info::*;
+pub use account_state_stream::*;
+pub use archive_routing::*;
 use async_trait::async_trait;
+pub use block_context::*;
 pub use block_functions::*;
 pub use block_stream::*;
 pub use builder::*;
+pub use caching_client::*;
 pub use callback::*;
+pub use circuit_breaker::*;
+pub use clock::*;
 pub use connection::*;
+pub use connection_stats::*;
+pub use correlation::*;
 pub use error::*;
+use futures::future::join_all;
+pub use get_method_functions::*;
+pub use http_provider::*;
 pub use interface::*;
+#[cfg(feature = "liteapi")]
+pub use lite_client::*;
+pub use masterchain_block_stream::*;
+pub use message_batch::*;
+pub use message_functions::*;
+pub use metrics_callback::*;
+#[cfg(feature = "metrics")]
+pub use metrics_recorder::*;
+#[cfg(feature = "testing")]
+pub use mock_client::*;
+pub use notification_stream::*;
+pub use priority::*;
+pub use provider::*;
+use rand::seq::SliceRandom;
 use rand::Rng;
+pub use retrying_client::*;
 use serde::{Deserialize, Serialize};
-use tokio::sync::Mutex;
-use tokio_retry::strategy::FixedInterval;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, Mutex};
 use tokio_retry::RetryIf;
+pub use traffic_capture::*;
+pub use transaction_stream::*;
 pub use types::*;
 
+use crate::client::archive_routing::{needs_archive, probe_archive, ArchiveQuery, ArchiveState};
+use crate::client::trace::instrument_invoke;
 use crate::tl::*;
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod keys;
+
+mod account_info;
+mod account_state_stream;
+mod archive_routing;
+mod block_context;
 mod block_functions;
 mod block_stream;
 mod builder;
+mod caching_client;
 mod callback;
+mod circuit_breaker;
+mod clock;
 mod connection;
+mod connection_stats;
+mod correlation;
 mod error;
+mod get_method_functions;
+mod http_provider;
 mod interface;
+mod masterchain_block_stream;
+mod message_batch;
+mod message_functions;
+mod metrics_callback;
+#[cfg(feature = "metrics")]
+mod metrics_recorder;
+#[cfg(feature = "testing")]
+mod mock_client;
+mod notification_stream;
+mod priority;
+mod provider;
+mod rate_limiter;
+mod retrying_client;
+mod trace;
+mod traffic_capture;
+mod transaction_stream;
 mod types;
 
+#[cfg(feature = "liteapi")]
+mod lite_client;
 #[cfg(feature = "liteapi")]
 mod recent_init_block;
 
@@ -45,6 +110,29 @@ pub enum ConnectionCheck {
     Archive,
 }
 
+/// Strategy of choosing a pool connection for a request
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum PoolDispatch {
+    /// Random connection for every request.
+    Random,
+    /// Connections in turn.
+    RoundRobin,
+    /// The one with lower rolling latency, weighted by its error rate, of two random
+    /// connections, see `TonClient::connection_stats`.
+    LeastLatency,
+    /// The one with fewer requests in flight of two random connections.
+    LeastPending,
+}
+
+/// Settings of choosing pool connections for requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct PoolRouting {
+    pub dispatch: PoolDispatch,
+    pub circuit_breaker: Option<CircuitBreakerConfig>,
+    pub archive_routing: Option<ArchiveRoutingConfig>,
+}
+
 pub struct TonClient {
     inner: Arc<Inner>,
 }
@@ -52,6 +140,13 @@ pub struct TonClient {
 struct Inner {
     retry_strategy: RetryStrategy,
     connections: Vec<PoolConnection>,
+    dispatch: PoolDispatch,
+    next_connection: AtomicUsize,
+    notification_sender: broadcast::Sender<Arc<TonNotification>>,
+    circuit_breaker: Option<CircuitBreakerConfig>,
+    archive_routing: Option<ArchiveRoutingConfig>,
+    /// Last masterchain seqno seen in responses, `0` if unknown.
+    last_mc_seqno: AtomicI32,
 }
 
 impl TonClient {
@@ -63,6 +158,76 @@ impl TonClient {
         callback: Arc<dyn TonConnectionCallback>,
         connection_check: ConnectionCheck,
     ) -> Result<TonClient, TonClientError> {
+        Self::new_with_dispatch(
+            pool_size,
+            params,
+            retry_strategy,
+            callback,
+            connection_check,
+            PoolDispatch::Random,
+        )
+        .await
+    }
+
+    /// Creates a new TonClient choosing pool connections according to `dispatch`
+    pub async fn new_with_dispatch(
+        pool_size: usize,
+        params: &TonConnectionParams,
+        retry_strategy: &RetryStrategy,
+        callback: Arc<dyn TonConnectionCallback>,
+        connection_check: ConnectionCheck,
+        dispatch: PoolDispatch,
+    ) -> Result<TonClient, TonClientError> {
+        Self::new_with_circuit_breaker(
+            pool_size,
+            params,
+            retry_strategy,
+            callback,
+            connection_check,
+            dispatch,
+            None,
+        )
+        .await
+    }
+
+    /// Same as `new_with_dispatch`, but also trips connections failing repeatedly,
+    /// see `CircuitBreakerConfig`.
+    pub async fn new_with_circuit_breaker(
+        pool_size: usize,
+        params: &TonConnectionParams,
+        retry_strategy: &RetryStrategy,
+        callback: Arc<dyn TonConnectionCallback>,
+        connection_check: ConnectionCheck,
+        dispatch: PoolDispatch,
+        circuit_breaker: Option<CircuitBreakerConfig>,
+    ) -> Result<TonClient, TonClientError> {
+        let routing = PoolRouting {
+            dispatch,
+            circuit_breaker,
+            archive_routing: None,
+        };
+        Self::new_with_routing(
+            pool_size,
+            params,
+            retry_strategy,
+            callback,
+            connection_check,
+            routing,
+            SYSTEM_CLOCK.clone(),
+        )
+        .await
+    }
+
+    pub(crate) async fn new_with_routing(
+        pool_size: usize,
+        params: &TonConnectionParams,
+        retry_strategy: &RetryStrategy,
+        callback: Arc<dyn TonConnectionCallback>,
+        connection_check: ConnectionCheck,
+        routing: PoolRouting,
+        clock: Arc<dyn Clock>,
+    ) -> Result<TonClient, TonClientError> {
+        let (notification_sender, _) = broadcast::channel(params.notification_queue_length);
         let patched_params = if params.update_init_block {
             patch_init_block(params).await?
         } else {
@@ -81,16 +246,28 @@ impl TonClient {
                 conn_params.keystore_dir = Some(path_str)
             };
             let entry = PoolConnection {
-                params: conn_params,
+                params: RwLock::new(conn_params),
                 callback: callback.clone(),
+                clock: clock.clone(),
                 conn: Mutex::new(None),
                 connection_check: connection_check.clone(),
+                notification_sender: notification_sender.clone(),
+                breaker: CircuitBreaker::default(),
+                stats: RequestStats::default(),
+                detect_archive: routing.archive_routing.is_some(),
+                archive: ArchiveState::default(),
             };
             connections.push(entry);
         }
         let inner = Inner {
             retry_strategy: retry_strategy.clone(),
             connections,
+            dispatch: routing.dispatch,
+            next_connection: AtomicUsize::new(0),
+            notification_sender,
+            circuit_breaker: routing.circuit_breaker,
+            archive_routing: routing.archive_routing,
+            last_mc_seqno: AtomicI32::new(0),
         };
         Ok(TonClient {
             inner: Arc::new(inner),
@@ -105,14 +282,49 @@ impl TonClient {
         Self::builder().build().await
     }
 
+    /// Spawns a task checking established pool connections with `TonConnection::health_check`
+    /// every `interval`.
+    ///
+    /// A connection failing the check is dropped from the pool, so the next request on it
+    /// establishes a new one, while retries of failed requests go to other connections.
+    /// The task exits once all clones of the client are dropped.
+    ///
+    /// Fails with `TonClientError::InternalError` if `interval` is zero.
+    pub fn start_health_checks(&self, interval: Duration) -> Result<(), TonClientError> {
+        if interval.is_zero() {
+            return Err(TonClientError::InternalError(
+                "Health check interval must be positive".to_string(),
+            ));
+        }
+        let weak_inner: Weak<Inner> = Arc::downgrade(&self.inner);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            ticker.tick().await; // The first tick completes immediately
+            loop {
+                ticker.tick().await;
+                let inner = match weak_inner.upgrade() {
+                    Some(inner) => inner,
+                    None => break,
+                };
+                join_all(inner.connections.iter().map(|e| e.check_health())).await;
+            }
+        });
+        Ok(())
+    }
+
     #[allow(clippy::let_and_return)]
     async fn retrying_invoke(
         &self,
         function: &TonFunction,
     ) -> Result<(TonConnection, TonResult), TonClientError> {
-        let fi = FixedInterval::from_millis(self.inner.retry_strategy.interval_ms);
-        let strategy = fi.take(self.inner.retry_strategy.max_retries);
-        let result = RetryIf::spawn(strategy, || self.do_invoke(function), retry_condition).await;
+        let retry_strategy = &self.inner.retry_strategy;
+        let result = RetryIf::spawn(
+            retry_strategy.intervals(),
+            || self.do_invoke(function),
+            |e: &TonClientError| retry_strategy.is_retryable(e),
+        )
+        .await;
         result
     }
 
@@ -120,25 +332,279 @@ impl TonClient {
         &self,
         function: &TonFunction,
     ) -> Result<(TonConnection, TonResult), TonClientError> {
-        let item = self.random_item();
-        let conn = item.get_connection().await?;
-        let res = conn.invoke(function).await;
-        match res {
-            Ok(result) => Ok((conn, result)),
+        let query = match self.inner.archive_routing {
+            Some(config) => ArchiveQuery::of(function).map(|query| (config, query)),
+            None => None,
+        };
+        let (config, query) = match query {
+            Some(query) => query,
+            None => return self.invoke_at(self.next_available_index(), function).await,
+        };
+        let last_mc_seqno = self.inner.last_mc_seqno.load(Ordering::Relaxed);
+        let historical = query.is_historical(last_mc_seqno, config.recent_seqno_lag);
+        let index = if historical {
+            match self.next_archive_index(None) {
+                Some(index) => index,
+                None => {
+                    return Err(TonClientError::ArchiveUnavailable {
+                        method: function.into(),
+                        message: format!(
+                            "block is more than {} masterchain blocks old",
+                            config.recent_seqno_lag
+                        ),
+                    })
+                }
+            }
+        } else {
+            self.next_available_index()
+        };
+        match self.invoke_at(index, function).await {
+            Err(e)
+                if needs_archive(&e)
+                    && self.inner.connections[index].archive.get() != Some(true) =>
+            {
+                match self.next_archive_index(Some(index)) {
+                    Some(archive_index) => self.invoke_at(archive_index, function).await,
+                    None if historical || query == ArchiveQuery::Transactions => {
+                        Err(TonClientError::ArchiveUnavailable {
+                            method: function.into(),
+                            message: e.to_string(),
+                        })
+                    }
+                    None => Err(e),
+                }
+            }
+            res => res,
+        }
+    }
+
+    async fn invoke_at(
+        &self,
+        index: usize,
+        function: &TonFunction,
+    ) -> Result<(TonConnection, TonResult), TonClientError> {
+        let item = &self.inner.connections[index];
+        let _pending = item.stats.start();
+        let res = match item.get_connection().await {
+            Ok(conn) => {
+                let started = Instant::now();
+                let res = conn.invoke(function).await;
+                let failed = matches!(&res, Err(e) if is_connection_failure(e));
+                item.stats.record(started.elapsed(), failed);
+                res.map(|result| (conn, result))
+            }
             Err(error) => Err(error),
+        };
+        if let Some(config) = &self.inner.circuit_breaker {
+            if item
+                .breaker
+                .record(res.as_ref().err(), config.failure_threshold)
+            {
+                self.trip(index, config.cooldown);
+            }
         }
+        let last_mc_seqno = match (function, &res) {
+            (_, Ok((_, TonResult::BlocksMasterchainInfo(info)))) => Some(info.last.seqno),
+            (TonFunction::Sync {}, Ok((_, TonResult::BlockIdExt(id)))) => Some(id.seqno),
+            _ => None,
+        };
+        if let Some(seqno) = last_mc_seqno {
+            self.inner.last_mc_seqno.fetch_max(seqno, Ordering::Relaxed);
+        }
+        res
     }
 
-    #[allow(clippy::let_and_return)]
-    fn random_item(&self) -> &PoolConnection {
-        let i = {
-            let mut rng = rand::thread_rng();
-            rng.gen_range(0..self.inner.connections.len())
+    /// Returns the index of a connection to an archive node other than `exclude`,
+    /// preferring connections known to be archival over not yet probed ones.
+    fn next_archive_index(&self, exclude: Option<usize>) -> Option<usize> {
+        let start = self.next_index();
+        let connections = &self.inner.connections;
+        let len = connections.len();
+        let candidates = move || {
+            (0..len)
+                .map(move |offset| (start + offset) % len)
+                .filter(move |i| Some(*i) != exclude && !connections[*i].breaker.is_open())
+        };
+        candidates()
+            .find(|i| connections[*i].archive.get() == Some(true))
+            .or_else(|| candidates().find(|i| connections[*i].archive.get().is_none()))
+    }
+
+    fn next_item(&self) -> &PoolConnection {
+        &self.inner.connections[self.next_available_index()]
+    }
+
+    /// Returns the index of the next connection skipping tripped ones,
+    /// unless all of them are tripped.
+    fn next_available_index(&self) -> usize {
+        let index = self.next_index();
+        if self.inner.circuit_breaker.is_none() {
+            return index;
+        }
+        let len = self.inner.connections.len();
+        (0..len)
+            .map(|offset| (index + offset) % len)
+            .find(|i| !self.inner.connections[*i].breaker.is_open())
+            .unwrap_or(index)
+    }
+
+    /// Re-initializes the tripped connection with `index` after `cooldown`, retrying
+    /// every `cooldown` until it succeeds or the client is dropped.
+    fn trip(&self, index: usize, cooldown: Duration) {
+        log::warn!(
+            "Circuit breaker tripped for pool connection {}, re-initializing in {:?}",
+            index,
+            cooldown
+        );
+        let weak_inner: Weak<Inner> = Arc::downgrade(&self.inner);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(cooldown).await;
+                let inner = match weak_inner.upgrade() {
+                    Some(inner) => inner,
+                    None => break,
+                };
+                let entry = &inner.connections[index];
+                match entry.reinitialize().await {
+                    Ok(()) => {
+                        entry.breaker.close();
+                        log::info!("Pool connection {} re-initialized", index);
+                        break;
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to re-initialize pool connection {}: {}", index, e)
+                    }
+                }
+            }
+        });
+    }
+
+    fn next_index(&self) -> usize {
+        let len = self.inner.connections.len();
+        match self.inner.dispatch {
+            PoolDispatch::Random => {
+                let mut rng = rand::thread_rng();
+                rng.gen_range(0..len)
+            }
+            PoolDispatch::RoundRobin => {
+                self.inner.next_connection.fetch_add(1, Ordering::Relaxed) % len
+            }
+            PoolDispatch::LeastLatency => self.better_of_two_index(RequestStats::latency_score),
+            PoolDispatch::LeastPending => self.better_of_two_index(|stats| stats.pending() as f64),
+        }
+    }
+
+    /// Returns the index of the connection with the lower `score` of two random connections
+    /// not tripped by the circuit breaker.
+    ///
+    /// Comparing two random connections rather than all of them keeps slightly worse
+    /// connections in use, so their statistics stay fresh, and spreads bursts of requests.
+    fn better_of_two_index<F: Fn(&RequestStats) -> f64>(&self, score: F) -> usize {
+        let connections = &self.inner.connections;
+        let mut candidates: Vec<usize> = (0..connections.len())
+            .filter(|i| !connections[*i].breaker.is_open())
+            .collect();
+        if candidates.is_empty() {
+            candidates = (0..connections.len()).collect();
+        }
+        let mut rng = rand::thread_rng();
+        candidates
+            .choose_multiple(&mut rng, 2)
+            .copied()
+            .min_by(|a, b| {
+                let (a, b) = (score(&connections[*a].stats), score(&connections[*b].stats));
+                a.total_cmp(&b)
+            })
+            .unwrap_or(0)
+    }
+
+    /// Returns request statistics of the pool connections, in the order of the pool.
+    pub fn connection_stats(&self) -> Vec<PoolConnectionStats> {
+        self.inner
+            .connections
+            .iter()
+            .map(|entry| entry.stats.snapshot())
+            .collect()
+    }
+
+    /// Returns a handle of this client sending requests with `priority`, e.g. `High` for
+    /// sending messages while `Low` bulk scans are issued on the same pool.
+    ///
+    /// Retries of a failed request are sent with the same priority.
+    pub fn with_priority(&self, priority: RequestPriority) -> PriorityClient<TonClient> {
+        PriorityClient::new(self.clone(), priority)
+    }
+
+    /// Subscribes to notifications of all pool connections.
+    ///
+    /// Notifications are forwarded from a connection once it's established,
+    /// so notifications sent during its initialization are not included.
+    pub fn subscribe(&self) -> TonNotificationReceiver {
+        self.inner.notification_sender.subscribe()
+    }
+
+    /// Same as `subscribe`, but returns a stream that skips notifications missed by lagging.
+    pub fn subscribe_stream(&self) -> TonNotificationStream {
+        TonNotificationStream::new(self.subscribe())
+    }
+
+    /// Same as `subscribe_stream`, but yields only notifications accepted by `filter`,
+    /// e.g. `TonNotification::is_sync_done`.
+    ///
+    /// See `TonNotificationStream` for the implications of an expensive filter.
+    pub fn subscribe_filtered<F>(&self, filter: F) -> TonNotificationStream
+    where
+        F: Fn(&TonNotification) -> bool + Send + Sync + 'static,
+    {
+        TonNotificationStream::new_filtered(self.subscribe(), filter)
+    }
+
+    /// Re-initializes all connections of the pool with `config`.
+    ///
+    /// Replacement connections are established in parallel and each of them takes over as soon
+    /// as it's ready, so the pool keeps serving requests on the old connections until then.
+    /// Requests already in flight on an old connection complete normally.
+    pub async fn reconfigure(&self, config: &str) -> Result<(), TonClientError> {
+        let config = match self.inner.connections.first() {
+            Some(entry) => {
+                let mut params = entry.params();
+                params.config = config.to_string();
+                if params.update_init_block {
+                    patch_init_block(&params).await?.config
+                } else {
+                    params.config
+                }
+            }
+            None => return Ok(()),
         };
-        let entry = &self.inner.connections[i];
-        entry
+        let results = join_all(
+            self.inner
+                .connections
+                .iter()
+                .map(|entry| entry.reconfigure(&config)),
+        )
+        .await;
+        results.into_iter().collect::<Result<Vec<_>, _>>()?;
+        Ok(())
+    }
+
+    /// Fetches network config from `url` (e.g. `config::MAINNET_CONFIG_URL`)
+    /// and re-initializes all connections of the pool with it.
+    pub async fn reconfigure_from_url(&self, url: &str) -> Result<(), TonClientError> {
+        let config = crate::config::fetch_config(url).await?;
+        self.reconfigure(&config).await
     }
 
+    /// Sets log verbosity of the tonlib C library for all clients of the process.
+    ///
+    /// * `0`: fatal errors only
+    /// * `1`: errors
+    /// * `2`: warnings
+    /// * `3`: info
+    /// * `4`: debug
+    /// * `5` and above: verbose debug output
+    ///
+    /// Can be called before any connection is created.
     pub fn set_log_verbosity_level(verbosity_level: u32) {
         TlTonClient::set_log_verbosity_level(verbosity_level)
     }
@@ -147,7 +613,7 @@ impl TonClient {
 #[async_trait]
 impl TonClientInterface for TonClient {
     async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
-        let item = self.random_item();
+        let item = self.next_item();
         let conn = item.get_connection().await?;
         Ok(conn)
     }
@@ -156,7 +622,8 @@ impl TonClientInterface for TonClient {
         &self,
         function: &TonFunction,
     ) -> Result<(TonConnection, TonResult), TonClientError> {
-        self.retrying_invoke(function).await
+        let method: &'static str = function.into();
+        instrument_invoke(method, self.retrying_invoke(function)).await
     }
 }
 
@@ -168,22 +635,6 @@ impl Clone for TonClient {
     }
 }
 
-fn maybe_error_code(error: &TonClientError) -> Option<i32> {
-    if let TonClientError::TonlibError { code, .. } = error {
-        Some(*code)
-    } else {
-        None
-    }
-}
-
-fn retry_condition(error: &TonClientError) -> bool {
-    if let Some(code) = maybe_error_code(error) {
-        code == 500
-    } else {
-        false
-    }
-}
-
 #[cfg(not(feature = "liteapi"))]
 async fn patch_init_block(
     params: &TonConnectionParams,
@@ -237,39 +688,423 @@ async fn patch_init_block(
 }
 
 struct PoolConnection {
-    params: TonConnectionParams,
+    params: RwLock<TonConnectionParams>,
     callback: Arc<dyn TonConnectionCallback>,
-    conn: Mutex<Option<(TonConnection, JoinHandle<()>)>>,
+    clock: Arc<dyn Clock>,
+    conn: Mutex<Option<(TonConnection, RunLoopHandle)>>,
     connection_check: ConnectionCheck,
+    notification_sender: broadcast::Sender<Arc<TonNotification>>,
+    breaker: CircuitBreaker,
+    stats: RequestStats,
+    detect_archive: bool,
+    archive: ArchiveState,
 }
 
 impl PoolConnection {
     async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
         let mut guard = self.conn.lock().await;
         match guard.deref() {
-            Some((conn, join_handle)) => {
-                if join_handle.is_finished() {
-                    // TODO: This is temporary implementation.
-                    // At the moment, only report dead connections, in the future need to recover
-                    log::warn!("Returning dead connection: {:?}", conn.tag());
+            Some((conn, join_handle)) if !join_handle.is_finished() => Ok(conn.clone()),
+            maybe_dead => {
+                if let Some((conn, _)) = maybe_dead {
+                    log::warn!("Replacing dead connection {}", conn.tag());
                 }
-                Ok(conn.clone())
-            }
-            None => {
-                let (conn, join_handle) = match self.connection_check {
-                    ConnectionCheck::None => {
-                        TonConnection::connect_joinable(&self.params, self.callback.clone()).await?
-                    }
-                    ConnectionCheck::Health => {
-                        TonConnection::connect_healthy(&self.params, self.callback.clone()).await?
-                    }
-                    ConnectionCheck::Archive => {
-                        TonConnection::connect_archive(&self.params, self.callback.clone()).await?
-                    }
-                };
+                let (conn, join_handle) = self.connect(&self.params()).await?;
                 *guard = Some((conn.clone(), join_handle));
                 Ok(conn)
             }
         }
     }
+
+    /// Drops the established connection if it fails `TonConnection::health_check`.
+    async fn check_health(&self) {
+        let conn = match self.conn.lock().await.deref() {
+            Some((conn, _)) => conn.clone(),
+            None => return,
+        };
+        if let Err(e) = conn.health_check().await {
+            let mut guard = self.conn.lock().await;
+            // The connection might have been replaced during the check
+            if matches!(guard.deref(), Some((c, _)) if c.tag() == conn.tag()) {
+                log::warn!("Dropping unhealthy connection {}: {}", conn.tag(), e);
+                *guard = None;
+            }
+        }
+    }
+
+    /// Establishes a new connection with the stored params and replaces the current one with it.
+    async fn reinitialize(&self) -> Result<(), TonClientError> {
+        let (conn, join_handle) = self.connect(&self.params()).await?;
+        let mut guard = self.conn.lock().await;
+        if let Some((old_conn, _)) = guard.replace((conn, join_handle)) {
+            log::info!(
+                "Connection {} replaced after re-initialization",
+                old_conn.tag()
+            );
+        }
+        Ok(())
+    }
+
+    /// Establishes a connection with `config` and replaces the current one with it.
+    async fn reconfigure(&self, config: &str) -> Result<(), TonClientError> {
+        let mut params = self.params();
+        params.config = config.to_string();
+        let (conn, join_handle) = self.connect(&params).await?;
+        let mut guard = self.conn.lock().await;
+        *self.params.write().unwrap() = params;
+        if let Some((old_conn, _)) = guard.replace((conn, join_handle)) {
+            log::info!(
+                "Connection {} replaced after reconfiguration",
+                old_conn.tag()
+            );
+        }
+        Ok(())
+    }
+
+    async fn connect(
+        &self,
+        params: &TonConnectionParams,
+    ) -> Result<(TonConnection, RunLoopHandle), TonClientError> {
+        let (callback, clock) = (self.callback.clone(), self.clock.clone());
+        let (conn, join_handle) = match self.connection_check {
+            ConnectionCheck::None => {
+                TonConnection::connect_joinable_with_clock(params, callback, clock).await?
+            }
+            ConnectionCheck::Health => {
+                TonConnection::connect_healthy(params, callback, clock).await?
+            }
+            ConnectionCheck::Archive => {
+                TonConnection::connect_archive(params, callback, clock).await?
+            }
+        };
+        let is_archive = match self.connection_check {
+            ConnectionCheck::Archive => Some(true),
+            _ if self.detect_archive => Self::detect_archive(&conn).await,
+            _ => None,
+        };
+        self.archive.set(is_archive);
+        if !params.warmup.is_empty() {
+            conn.warm_up(&params.warmup).await;
+        }
+        self.forward_notifications(&conn);
+        Ok((conn, join_handle))
+    }
+
+    /// Probes `conn` for archival state, `None` if the probe failed.
+    async fn detect_archive(conn: &TonConnection) -> Option<bool> {
+        let result = match conn.sync().await {
+            Ok(_) => probe_archive(conn).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(is_archive) => {
+                log::info!("Connection {} archival state: {}", conn.tag(), is_archive);
+                Some(is_archive)
+            }
+            Err(e) => {
+                log::warn!(
+                    "Failed to probe connection {} for archival state: {}",
+                    conn.tag(),
+                    e
+                );
+                None
+            }
+        }
+    }
+
+    /// Forwards notifications of `conn` to pool subscribers until `conn` is dropped.
+    fn forward_notifications(&self, conn: &TonConnection) {
+        let mut receiver = conn.subscribe();
+        let sender = self.notification_sender.clone();
+        let callback = self.callback.clone();
+        let tag = conn.tag().to_string();
+        tokio::spawn(async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(notification) => {
+                        // Fails only if there are no pool subscribers
+                        let _ = sender.send(notification);
+                    }
+                    Err(RecvError::Lagged(n)) => callback.on_notification_lagged(&tag, n),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    fn params(&self) -> TonConnectionParams {
+        self.params.read().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use async_trait::async_trait;
+
+    use super::{
+        ArchiveRoutingConfig, CircuitBreakerConfig, ConnectionCheck, PoolDispatch, PoolRouting,
+        RetryStrategy, RetryingTonClient, TonClient, TonClientError, TonClientInterface,
+        TonConnection, TonConnectionParams, NOOP_CONNECTION_CALLBACK, SYSTEM_CLOCK,
+    };
+    use crate::tl::{TlError, TonFunction, TonResult};
+
+    async fn new_client(dispatch: PoolDispatch) -> TonClient {
+        let params = TonConnectionParams {
+            update_init_block: false,
+            ..Default::default()
+        };
+        TonClient::new_with_dispatch(
+            3,
+            &params,
+            &RetryStrategy::default(),
+            NOOP_CONNECTION_CALLBACK.clone(),
+            ConnectionCheck::None,
+            dispatch,
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_round_robin_dispatch() {
+        let client = new_client(PoolDispatch::RoundRobin).await;
+        let indices: Vec<_> = (0..7).map(|_| client.next_index()).collect();
+        assert_eq!(indices, vec![0, 1, 2, 0, 1, 2, 0]);
+    }
+
+    #[tokio::test]
+    async fn test_least_loaded_dispatch() {
+        let client = new_client(PoolDispatch::LeastPending).await;
+        let pending = [
+            client.inner.connections[0].stats.start(),
+            client.inner.connections[0].stats.start(),
+            client.inner.connections[1].stats.start(),
+        ];
+        let indices: Vec<_> = (0..20).map(|_| client.next_index()).collect();
+        // The busiest connection never wins a comparison
+        assert!(!indices.contains(&0));
+        assert_eq!(client.connection_stats()[0].pending, 2);
+        drop(pending);
+        assert_eq!(
+            client
+                .connection_stats()
+                .iter()
+                .map(|s| s.pending)
+                .sum::<usize>(),
+            0
+        );
+
+        let client = new_client(PoolDispatch::LeastLatency).await;
+        for (index, millis) in [(0, 300), (1, 100), (2, 200)] {
+            client.inner.connections[index]
+                .stats
+                .record(Duration::from_millis(millis), false);
+        }
+        let indices: Vec<_> = (0..20).map(|_| client.next_index()).collect();
+        // The slowest connection never wins a comparison
+        assert!(!indices.contains(&0));
+        assert_eq!(
+            client.connection_stats()[1].latency,
+            Some(Duration::from_millis(100))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_skips_tripped_connections() {
+        let params = TonConnectionParams {
+            update_init_block: false,
+            ..Default::default()
+        };
+        let config = CircuitBreakerConfig {
+            failure_threshold: 1,
+            ..Default::default()
+        };
+        let client = TonClient::new_with_circuit_breaker(
+            3,
+            &params,
+            &RetryStrategy::default(),
+            NOOP_CONNECTION_CALLBACK.clone(),
+            ConnectionCheck::None,
+            PoolDispatch::RoundRobin,
+            Some(config),
+        )
+        .await
+        .unwrap();
+        let error = TonClientError::TonlibError {
+            method: "test",
+            code: 651,
+            message: "LITE_SERVER_NOTREADY".to_string(),
+        };
+        assert!(client.inner.connections[1]
+            .breaker
+            .record(Some(&error), config.failure_threshold));
+        let indices: Vec<_> = (0..4).map(|_| client.next_available_index()).collect();
+        assert_eq!(indices, vec![0, 2, 2, 0]);
+
+        client.inner.connections[1].breaker.close();
+        assert_eq!(client.next_available_index(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_archive_routing_prefers_archive_connections() {
+        let params = TonConnectionParams {
+            update_init_block: false,
+            ..Default::default()
+        };
+        let routing = PoolRouting {
+            dispatch: PoolDispatch::RoundRobin,
+            circuit_breaker: None,
+            archive_routing: Some(ArchiveRoutingConfig::default()),
+        };
+        let client = TonClient::new_with_routing(
+            3,
+            &params,
+            &RetryStrategy::default(),
+            NOOP_CONNECTION_CALLBACK.clone(),
+            ConnectionCheck::None,
+            routing,
+            SYSTEM_CLOCK.clone(),
+        )
+        .await
+        .unwrap();
+        let connections = &client.inner.connections;
+        // Not yet probed connections are candidates until an archive one is known
+        assert_eq!(client.next_archive_index(Some(0)), Some(1));
+        connections[0].archive.set(Some(false));
+        connections[2].archive.set(Some(true));
+        assert_eq!(client.next_archive_index(None), Some(2));
+        assert_eq!(client.next_archive_index(None), Some(2));
+        connections[1].archive.set(Some(false));
+        assert_eq!(client.next_archive_index(Some(2)), None);
+    }
+
+    /// Fails the `n`-th request with `error(n)` unless it returns `None`.
+    struct FlakyClient {
+        connection: TonConnection,
+        error: Box<dyn Fn(usize) -> Option<TonClientError> + Send + Sync>,
+        attempts: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl TonClientInterface for FlakyClient {
+        async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+            Ok(self.connection.clone())
+        }
+
+        async fn invoke_on_connection(
+            &self,
+            _function: &TonFunction,
+        ) -> Result<(TonConnection, TonResult), TonClientError> {
+            match (self.error)(self.attempts.fetch_add(1, Ordering::SeqCst)) {
+                Some(error) => Err(error),
+                None => Ok((self.connection.clone(), TonResult::Ok {})),
+            }
+        }
+    }
+
+    fn flaky_client<F>(error: F) -> (FlakyClient, Arc<AtomicUsize>)
+    where
+        F: Fn(usize) -> Option<TonClientError> + Send + Sync + 'static,
+    {
+        let connection = TonConnection::new(
+            NOOP_CONNECTION_CALLBACK.clone(),
+            &TonConnectionParams::default(),
+        )
+        .unwrap();
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let client = FlakyClient {
+            connection,
+            error: Box::new(error),
+            attempts: attempts.clone(),
+        };
+        (client, attempts)
+    }
+
+    fn tonlib_error(code: i32) -> TonClientError {
+        TonClientError::TonlibError {
+            method: "test",
+            code,
+            message: "test".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_strategy() {
+        let retry_strategy = RetryStrategy {
+            interval_ms: 1,
+            max_retries: 3,
+            // 651 is retried as TonlibErrorKind::NotReady
+            retryable_codes: vec![500],
+            exponential_backoff: true,
+            ..Default::default()
+        };
+        let intervals: Vec<_> = retry_strategy.intervals().map(|d| d.as_millis()).collect();
+        assert_eq!(intervals, vec![1, 2, 4]);
+
+        // Fails twice with retryable codes, then succeeds after the 1 and 2 ms backoffs
+        let (client, attempts) = flaky_client(|attempt| match attempt {
+            0 => Some(tonlib_error(500)),
+            1 => Some(tonlib_error(651)),
+            _ => None,
+        });
+        let client = RetryingTonClient::new(client, &retry_strategy);
+        let started = Instant::now();
+        let result = client.invoke(&TonFunction::GetLogVerbosityLevel {}).await;
+        assert!(matches!(result, Ok(TonResult::Ok {})));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+        assert!(started.elapsed() >= Duration::from_millis(3));
+
+        let (client, attempts) = flaky_client(|_| Some(tonlib_error(500)));
+        let client = RetryingTonClient::new(client, &retry_strategy);
+        let result = client.invoke(&TonFunction::GetLogVerbosityLevel {}).await;
+        assert!(matches!(
+            result,
+            Err(TonClientError::TonlibError { code: 500, .. })
+        ));
+        assert_eq!(attempts.load(Ordering::SeqCst), 4);
+
+        let pass_through: [fn() -> TonClientError; 3] = [
+            || tonlib_error(400),
+            || TlError::from(CString::new("a\0b").unwrap_err()).into(),
+            || TonClientError::InternalError("test".to_string()),
+        ];
+        for error in pass_through {
+            let (client, attempts) = flaky_client(move |_| Some(error()));
+            let client = RetryingTonClient::new(client, &retry_strategy);
+            let result = client.invoke(&TonFunction::GetLogVerbosityLevel {}).await;
+            assert!(result.is_err());
+            assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        }
+        assert!(retry_strategy.is_retryable(&TonClientError::ConnectionClosed { method: "test" }));
+        let timeout = TonClientError::Timeout {
+            method: "test",
+            elapsed: Duration::from_secs(1),
+        };
+        assert!(!retry_strategy.is_retryable(&timeout));
+        let retry_strategy = RetryStrategy {
+            retry_timeouts: true,
+            ..retry_strategy
+        };
+        assert!(retry_strategy.is_retryable(&timeout));
+    }
+
+    #[tokio::test]
+    async fn test_health_check_skips_unestablished_connections() {
+        let client = new_client(PoolDispatch::RoundRobin).await;
+        for entry in client.inner.connections.iter() {
+            entry.check_health().await;
+            assert!(entry.conn.lock().await.is_none());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_random_dispatch() {
+        let client = new_client(PoolDispatch::Random).await;
+        assert!((0..100).all(|_| client.next_index() < 3));
+    }
 }
@@ -0,0 +1,185 @@
+use tonlib_core::cell::{ArcCell, BagOfCells};
+use tonlib_core::TonAddress;
+
+use crate::client::TonClientError;
+use crate::tl::{BlockIdExt, InternalTransactionId, RawFullAccountState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccountStatus {
+    /// Account with deployed code.
+    Active,
+    /// Account without code, either never deployed or deleted.
+    Uninit,
+    /// Account frozen due to unpaid storage fees.
+    Frozen,
+}
+
+/// State of an account along with the parts specific to its status, see `AccountInfo::state`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccountInfoState {
+    Uninit,
+    /// Deployed account, `data` is `None` for a contract without persistent data.
+    Active {
+        code: ArcCell,
+        data: Option<ArcCell>,
+    },
+    /// Account frozen due to unpaid storage fees, it's unfrozen by a message carrying
+    /// the state init with hash `state_hash`.
+    Frozen {
+        state_hash: Vec<u8>,
+    },
+}
+
+/// Account state with parsed code and data cells.
+///
+/// Tonlib doesn't expose the storage stat of an account, so the storage fees due can't be
+/// estimated from it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountInfo {
+    pub address: TonAddress,
+    pub balance: i64,
+    pub status: AccountStatus,
+    pub last_transaction_id: InternalTransactionId,
+    /// Code of an active account.
+    pub code: Option<ArcCell>,
+    /// Data of an active account.
+    pub data: Option<ArcCell>,
+    /// State hash of a frozen account.
+    pub frozen_hash: Option<Vec<u8>>,
+    pub block_id: BlockIdExt,
+    pub sync_utime: i64,
+}
+
+impl AccountInfo {
+    pub fn from_raw(
+        address: &TonAddress,
+        state: &RawFullAccountState,
+    ) -> Result<AccountInfo, TonClientError> {
+        let code = parse_optional_boc(&state.code)?;
+        let data = parse_optional_boc(&state.data)?;
+        let frozen_hash = (!state.frozen_hash.is_empty()).then(|| state.frozen_hash.clone());
+        let status = if code.is_some() {
+            AccountStatus::Active
+        } else if frozen_hash.is_some() {
+            AccountStatus::Frozen
+        } else {
+            AccountStatus::Uninit
+        };
+        Ok(AccountInfo {
+            address: address.clone(),
+            balance: state.balance,
+            status,
+            last_transaction_id: state.last_transaction_id.clone(),
+            code,
+            data,
+            frozen_hash,
+            block_id: state.block_id.clone(),
+            sync_utime: state.sync_utime,
+        })
+    }
+
+    /// Returns the typed state of the account.
+    pub fn state(&self) -> AccountInfoState {
+        match (&self.status, &self.code, &self.frozen_hash) {
+            (AccountStatus::Active, Some(code), _) => AccountInfoState::Active {
+                code: code.clone(),
+                data: self.data.clone(),
+            },
+            (AccountStatus::Frozen, _, Some(state_hash)) => AccountInfoState::Frozen {
+                state_hash: state_hash.clone(),
+            },
+            _ => AccountInfoState::Uninit,
+        }
+    }
+}
+
+fn parse_optional_boc(boc: &[u8]) -> Result<Option<ArcCell>, TonClientError> {
+    if boc.is_empty() {
+        return Ok(None);
+    }
+    let boc = BagOfCells::parse(boc)?;
+    Ok(Some(boc.single_root()?.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use tonlib_core::cell::{BagOfCells, CellBuilder, TonCellError};
+    use tonlib_core::TonAddress;
+
+    use super::{AccountInfo, AccountInfoState, AccountStatus};
+    use crate::client::TonClientError;
+    use crate::tl::{BlockIdExt, InternalTransactionId, RawFullAccountState};
+
+    fn raw_state(code: Vec<u8>, data: Vec<u8>, frozen_hash: Vec<u8>) -> RawFullAccountState {
+        RawFullAccountState {
+            balance: 1_000_000_000,
+            code,
+            data,
+            last_transaction_id: InternalTransactionId {
+                lt: 42,
+                hash: vec![1; 32],
+            },
+            block_id: BlockIdExt {
+                workchain: -1,
+                shard: i64::MIN,
+                seqno: 100,
+                root_hash: vec![2; 32],
+                file_hash: vec![3; 32],
+            },
+            frozen_hash,
+            sync_utime: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn test_account_info_from_raw() -> Result<(), TonClientError> {
+        let user_friendly = "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR";
+        let address = TonAddress::from_str(user_friendly)?;
+        assert_eq!(TonAddress::from_str(&address.to_hex())?, address);
+
+        let code = CellBuilder::new().store_u8(8, 1)?.build()?;
+        let data = CellBuilder::new().store_u8(8, 2)?.build()?;
+        let serialize = |cell| -> Result<Vec<u8>, TonCellError> {
+            BagOfCells::from_root(cell).serialize(false)
+        };
+        let state = raw_state(serialize(code.clone())?, serialize(data.clone())?, vec![]);
+        let info = AccountInfo::from_raw(&address, &state)?;
+        assert_eq!(info.address, address);
+        assert_eq!(info.status, AccountStatus::Active);
+        assert_eq!(info.balance, 1_000_000_000);
+        assert_eq!(info.last_transaction_id.lt, 42);
+        assert_eq!(info.code.as_deref(), Some(&code));
+        assert_eq!(info.data.as_deref(), Some(&data));
+        assert_eq!(info.frozen_hash, None);
+        assert_eq!(info.block_id.seqno, 100);
+        assert_eq!(
+            info.state(),
+            AccountInfoState::Active {
+                code: code.clone().into(),
+                data: Some(data.clone().into()),
+            }
+        );
+
+        let info = AccountInfo::from_raw(&address, &raw_state(vec![], vec![], vec![]))?;
+        assert_eq!(info.status, AccountStatus::Uninit);
+        assert_eq!(info.code, None);
+        assert_eq!(info.state(), AccountInfoState::Uninit);
+
+        let info = AccountInfo::from_raw(&address, &raw_state(vec![], vec![], vec![7; 32]))?;
+        assert_eq!(info.status, AccountStatus::Frozen);
+        assert_eq!(info.frozen_hash, Some(vec![7; 32]));
+        assert_eq!(
+            info.state(),
+            AccountInfoState::Frozen {
+                state_hash: vec![7; 32]
+            }
+        );
+
+        assert!(
+            AccountInfo::from_raw(&address, &raw_state(vec![1, 2, 3], vec![], vec![])).is_err()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::Duration;
+
+use futures::future::try_join_all;
+use tokio::time;
+use tonlib_core::TonAddress;
+
+use crate::client::{BlockStream, TonBlockFunctions, TonClientError, TonClientInterface};
+use crate::tl::RawFullAccountState;
+
+#[derive(Debug, Clone)]
+pub struct AccountStateChange {
+    pub address: TonAddress,
+    /// State observed on the previous change, `None` on the first observation.
+    pub previous: Option<RawFullAccountState>,
+    pub state: RawFullAccountState,
+}
+
+/// Allows to watch a set of accounts for state changes.
+///
+/// The result of `next` call is the next account whose balance, code or data changed.
+/// The first observed state of every account is reported as a change with `previous` set to `None`.
+///
+/// Accounts are polled only when a new masterchain block appears. After the first
+/// observation the transaction ids of the new blocks are compared with the last transaction
+/// of every account first, and only states of accounts with newer transactions are fetched.
+pub struct AccountStateStream<C: TonClientInterface + Clone> {
+    client: C,
+    addresses: Vec<TonAddress>,
+    poll_interval: Duration,
+    last_mc_seqno: Option<i32>,
+    /// Blocks after the first observation, `None` before it.
+    block_stream: Option<BlockStream<C>>,
+    /// Accounts with new transactions whose states weren't fetched yet.
+    outdated: HashSet<TonAddress>,
+    states: HashMap<TonAddress, RawFullAccountState>,
+    pending: VecDeque<AccountStateChange>,
+}
+
+impl<C: TonClientInterface + Clone> AccountStateStream<C> {
+    pub fn new(
+        client: &C,
+        addresses: &[TonAddress],
+        poll_interval: Duration,
+    ) -> AccountStateStream<C> {
+        AccountStateStream {
+            client: client.clone(),
+            addresses: addresses.to_vec(),
+            poll_interval,
+            last_mc_seqno: None,
+            block_stream: None,
+            outdated: Default::default(),
+            states: Default::default(),
+            pending: Default::default(),
+        }
+    }
+
+    /// Retrieves the next account state change.
+    ///
+    /// If no account changed yet, the returned future resolves when a change is detected.
+    pub async fn next(&mut self) -> Result<AccountStateChange, TonClientError> {
+        loop {
+            if let Some(change) = self.pending.pop_front() {
+                return Ok(change);
+            }
+            self.poll().await?;
+            if self.pending.is_empty() {
+                time::sleep(self.poll_interval).await;
+            }
+        }
+    }
+
+    async fn poll(&mut self) -> Result<(), TonClientError> {
+        let (_, masterchain_info) = self.client.get_masterchain_info().await?;
+        let mc_seqno = masterchain_info.last.seqno;
+        if self.last_mc_seqno == Some(mc_seqno) {
+            return Ok(());
+        }
+        let addresses = match self.block_stream.as_mut() {
+            None => self.addresses.clone(),
+            Some(block_stream) => {
+                let updated = updated_accounts(&self.client, block_stream, mc_seqno, &self.states);
+                match updated.await {
+                    Ok(updated) => self.outdated.extend(updated),
+                    // The blocks might have been consumed, check all accounts instead
+                    Err(e) => {
+                        log::warn!("[AccountStateStream] Could not read transactions: {}", e);
+                        self.outdated.extend(self.addresses.iter().cloned());
+                    }
+                }
+                self.addresses
+                    .iter()
+                    .filter(|a| self.outdated.contains(*a))
+                    .cloned()
+                    .collect()
+            }
+        };
+        let futures: Vec<_> = addresses
+            .iter()
+            .map(|address| self.client.get_raw_account_state(address))
+            .collect();
+        let states = try_join_all(futures).await?;
+        self.outdated.clear();
+        for (address, state) in addresses.iter().zip(states) {
+            let previous = self.states.get(address);
+            let changed = match previous {
+                None => true,
+                Some(prev) => {
+                    prev.last_transaction_id != state.last_transaction_id
+                        && (prev.balance != state.balance
+                            || prev.code != state.code
+                            || prev.data != state.data)
+                }
+            };
+            if changed {
+                let previous = self.states.insert(address.clone(), state.clone());
+                self.pending.push_back(AccountStateChange {
+                    address: address.clone(),
+                    previous,
+                    state,
+                });
+            }
+        }
+        if self.block_stream.is_none() {
+            // Transactions of the current block might be missing from the states read
+            self.block_stream = Some(BlockStream::new(&self.client, mc_seqno));
+        }
+        self.last_mc_seqno = Some(mc_seqno);
+        Ok(())
+    }
+}
+
+/// Returns the watched accounts with transactions newer than their last observed one in the
+/// blocks up to `mc_seqno`.
+async fn updated_accounts<C: TonClientInterface + Clone>(
+    client: &C,
+    block_stream: &mut BlockStream<C>,
+    mc_seqno: i32,
+    states: &HashMap<TonAddress, RawFullAccountState>,
+) -> Result<HashSet<TonAddress>, TonClientError> {
+    let mut updated = HashSet::new();
+    while block_stream.next_seqno() <= mc_seqno {
+        let block = block_stream.next().await?;
+        let mut blocks = block.shards;
+        blocks.push(block.master_shard);
+        for (_, tx_ids) in client.get_shards_tx_ids(&blocks).await? {
+            for tx_id in tx_ids {
+                let newer = states.get(&tx_id.address).is_some_and(|state| {
+                    tx_id.internal_transaction_id.lt > state.last_transaction_id.lt
+                });
+                if newer {
+                    updated.insert(tx_id.address);
+                }
+            }
+        }
+    }
+    Ok(updated)
+}
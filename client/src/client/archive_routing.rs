@@ -0,0 +1,211 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use crate::client::{TonClientError, TonClientInterface, TonConnection, TonlibErrorKind};
+use crate::tl::{BlockId, TonFunction};
+
+pub const DEFAULT_ARCHIVE_RECENT_SEQNO_LAG: u32 = 10_000;
+
+/// Settings of routing historical queries to archive nodes of the pool.
+///
+/// Every pool connection is probed for archival state once it's established, by looking up
+/// the very first masterchain block. Queries for masterchain blocks more than `recent_seqno_lag`
+/// blocks behind the last known masterchain block go to archive connections only.
+///
+/// Other queries involving blocks or transactions, e.g. `raw.getTransactions` with an old `lt`,
+/// are sent as usual and repeated on an archive connection if a non-archive one reports
+/// the block is not in its database. Without an archive connection in the pool such queries
+/// fail with `TonClientError::ArchiveUnavailable`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ArchiveRoutingConfig {
+    pub recent_seqno_lag: u32,
+}
+
+impl Default for ArchiveRoutingConfig {
+    fn default() -> Self {
+        ArchiveRoutingConfig {
+            recent_seqno_lag: DEFAULT_ARCHIVE_RECENT_SEQNO_LAG,
+        }
+    }
+}
+
+/// Query that may require archival state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ArchiveQuery {
+    /// Query of account transactions or of account state at a transaction.
+    Transactions,
+    /// Query of a block with known seqno.
+    Block { workchain: i32, seqno: i32 },
+    /// Query of a block looked up by `lt` or `utime`.
+    BlockLookup,
+}
+
+impl ArchiveQuery {
+    pub(crate) fn of(function: &TonFunction) -> Option<ArchiveQuery> {
+        let query = match function {
+            TonFunction::RawGetTransactions { .. }
+            | TonFunction::RawGetTransactionsV2 { .. }
+            | TonFunction::RawGetAccountStateByTransaction { .. }
+            | TonFunction::SmcLoadByTransaction { .. } => ArchiveQuery::Transactions,
+            TonFunction::BlocksGetShards { id }
+            | TonFunction::BlocksGetTransactions { id, .. }
+            | TonFunction::BlocksGetTransactionsExt { id, .. }
+            | TonFunction::GetBlockHeader { id }
+            | TonFunction::BlocksGetShardBlockProof { id, .. } => ArchiveQuery::Block {
+                workchain: id.workchain,
+                seqno: id.seqno,
+            },
+            TonFunction::BlocksLookupBlock { mode, id, .. } if mode & 1 != 0 => {
+                ArchiveQuery::Block {
+                    workchain: id.workchain,
+                    seqno: id.seqno,
+                }
+            }
+            TonFunction::BlocksLookupBlock { .. } => ArchiveQuery::BlockLookup,
+            TonFunction::BlocksGetMasterchainBlockSignatures { seqno } => ArchiveQuery::Block {
+                workchain: -1,
+                seqno: *seqno,
+            },
+            TonFunction::WithBlock { id, .. } => ArchiveQuery::Block {
+                workchain: id.workchain,
+                seqno: id.seqno,
+            },
+            _ => return None,
+        };
+        Some(query)
+    }
+
+    /// Whether the query is known to target a block older than `recent_seqno_lag` masterchain
+    /// blocks, given the last known masterchain seqno.
+    pub(crate) fn is_historical(&self, last_mc_seqno: i32, recent_seqno_lag: u32) -> bool {
+        match self {
+            ArchiveQuery::Block { workchain, seqno } if *workchain == -1 && last_mc_seqno > 0 => {
+                (*seqno as i64) + (recent_seqno_lag as i64) < last_mc_seqno as i64
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Whether `error` returned by a non-archive node means the query needs archival state.
+pub(crate) fn needs_archive(error: &TonClientError) -> bool {
+    error.tonlib_error_kind() == Some(TonlibErrorKind::BlockNotFound)
+}
+
+const ARCHIVE_UNKNOWN: u8 = 0;
+const ARCHIVE_YES: u8 = 1;
+const ARCHIVE_NO: u8 = 2;
+
+/// Whether a pool connection holds archival state, unknown until it's probed.
+#[derive(Debug, Default)]
+pub(crate) struct ArchiveState(AtomicU8);
+
+impl ArchiveState {
+    pub(crate) fn get(&self) -> Option<bool> {
+        match self.0.load(Ordering::Acquire) {
+            ARCHIVE_YES => Some(true),
+            ARCHIVE_NO => Some(false),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn set(&self, is_archive: Option<bool>) {
+        let value = match is_archive {
+            Some(true) => ARCHIVE_YES,
+            Some(false) => ARCHIVE_NO,
+            None => ARCHIVE_UNKNOWN,
+        };
+        self.0.store(value, Ordering::Release)
+    }
+}
+
+/// Checks whether `conn` is connected to an archive node by looking up the very first
+/// masterchain block, `conn` is expected to be synchronized.
+pub(crate) async fn probe_archive(conn: &TonConnection) -> Result<bool, TonClientError> {
+    let first_block = BlockId {
+        workchain: -1,
+        shard: i64::MIN,
+        seqno: 1,
+    };
+    match conn.lookup_block(1, &first_block, 0, 0).await {
+        Ok(_) => Ok(true),
+        Err(e) if needs_archive(&e) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{needs_archive, ArchiveQuery};
+    use crate::client::TonClientError;
+    use crate::tl::{BlockId, BlockIdExt, TonFunction};
+
+    fn block_header(workchain: i32, seqno: i32) -> TonFunction {
+        TonFunction::GetBlockHeader {
+            id: BlockIdExt {
+                workchain,
+                shard: i64::MIN,
+                seqno,
+                root_hash: vec![0; 32],
+                file_hash: vec![0; 32],
+            },
+        }
+    }
+
+    #[test]
+    fn test_archive_query_is_historical() {
+        let query = ArchiveQuery::of(&block_header(-1, 100)).unwrap();
+        assert!(query.is_historical(1_000, 500));
+        assert!(!query.is_historical(600, 500));
+        // Unknown last seqno
+        assert!(!query.is_historical(0, 500));
+        // Seqnos of shard blocks are not comparable with masterchain ones
+        let query = ArchiveQuery::of(&block_header(0, 100)).unwrap();
+        assert!(!query.is_historical(1_000, 500));
+
+        let lookup_by_lt = TonFunction::BlocksLookupBlock {
+            mode: 2,
+            id: BlockId {
+                workchain: -1,
+                shard: i64::MIN,
+                seqno: 0,
+            },
+            lt: 1_000,
+            utime: 0,
+        };
+        assert_eq!(
+            ArchiveQuery::of(&lookup_by_lt),
+            Some(ArchiveQuery::BlockLookup)
+        );
+        assert_eq!(ArchiveQuery::of(&TonFunction::Sync {}), None);
+
+        let pinned = TonFunction::WithBlock {
+            id: BlockIdExt {
+                workchain: -1,
+                shard: i64::MIN,
+                seqno: 100,
+                root_hash: vec![0; 32],
+                file_hash: vec![0; 32],
+            },
+            function: Box::new(TonFunction::Sync {}),
+        };
+        assert!(ArchiveQuery::of(&pinned).unwrap().is_historical(1_000, 500));
+    }
+
+    #[test]
+    fn test_needs_archive() {
+        let not_in_db = TonClientError::TonlibError {
+            method: "raw.getTransactions",
+            code: 500,
+            message: "LITE_SERVER_UNKNOWN: block is not in db".to_string(),
+        };
+        let not_ready = TonClientError::TonlibError {
+            method: "raw.getTransactions",
+            code: 651,
+            message: "LITE_SERVER_NOTREADY".to_string(),
+        };
+        assert!(needs_archive(&not_in_db));
+        assert!(!needs_archive(&not_ready));
+    }
+}
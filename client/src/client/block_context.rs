@@ -0,0 +1,173 @@
+use async_trait::async_trait;
+use tonlib_core::TonAddress;
+
+use crate::client::{
+    run_loaded_contract_request, GetMethodRequest, TonClientError, TonClientInterface,
+    TonConnection,
+};
+use crate::contract::TonContractError;
+use crate::tl::{BlockIdExt, RawTransactions, TonFunction, TonResult};
+use crate::types::{TonMethodId, TvmStackEntry, TvmSuccess};
+
+/// Client pinned to a block, see `TonBlockFunctions::at_block`.
+///
+/// Every function invoked through the context is wrapped into `withBlock`, so account states,
+/// get-method results and transactions read by a series of calls are consistent with the state
+/// of the same block rather than with the ever advancing last block.
+pub struct BlockContext<'a, C: TonClientInterface + ?Sized> {
+    client: &'a C,
+    block_id: BlockIdExt,
+}
+
+impl<'a, C: TonClientInterface + ?Sized> BlockContext<'a, C> {
+    pub fn new(client: &'a C, block_id: &BlockIdExt) -> BlockContext<'a, C> {
+        BlockContext {
+            client,
+            block_id: block_id.clone(),
+        }
+    }
+
+    pub fn block_id(&self) -> &BlockIdExt {
+        &self.block_id
+    }
+
+    /// Wraps `function` into `withBlock`, a function already pinned to a block is re-pinned.
+    pub fn pin(&self, function: &TonFunction) -> TonFunction {
+        let function = match function {
+            TonFunction::WithBlock { function, .. } => function.as_ref(),
+            f => f,
+        };
+        TonFunction::WithBlock {
+            id: self.block_id.clone(),
+            function: Box::new(function.clone()),
+        }
+    }
+
+    /// Runs a get-method of the contract at `address` in its state at the block.
+    pub async fn run_get_method<M: Into<TonMethodId>>(
+        &self,
+        address: &TonAddress,
+        method: M,
+        stack: &[TvmStackEntry],
+    ) -> Result<TvmSuccess, TonContractError> {
+        let request = GetMethodRequest::new(address, method, stack);
+        let state = self.smc_load(address).await?;
+        let result = run_loaded_contract_request(&state, address, &request).await;
+        if let Err(e) = state.forget().await {
+            log::warn!("Failed to forget contract {}: {}", address, e);
+        }
+        result
+    }
+
+    /// Returns up to `count` last transactions of the account made up to the block,
+    /// starting with the most recent.
+    pub async fn get_transactions(
+        &self,
+        address: &TonAddress,
+        count: usize,
+    ) -> Result<RawTransactions, TonClientError> {
+        let state = self.get_raw_account_state(address).await?;
+        self.get_raw_transactions_v2(address, &state.last_transaction_id, count, false)
+            .await
+    }
+}
+
+#[async_trait]
+impl<'a, C: TonClientInterface + ?Sized> TonClientInterface for BlockContext<'a, C> {
+    async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+        self.client.get_connection().await
+    }
+
+    async fn invoke_on_connection(
+        &self,
+        function: &TonFunction,
+    ) -> Result<(TonConnection, TonResult), TonClientError> {
+        self.client.invoke_on_connection(&self.pin(function)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use tonlib_core::TonAddress;
+
+    use super::BlockContext;
+    use crate::client::{
+        TonClientError, TonClientInterface, TonConnection, TonConnectionParams,
+        NOOP_CONNECTION_CALLBACK,
+    };
+    use crate::tl::{BlockIdExt, TonFunction, TonResult};
+
+    /// Records functions it's invoked with and answers `ok` to all of them.
+    struct RecordingClient {
+        connection: TonConnection,
+        functions: Mutex<Vec<TonFunction>>,
+    }
+
+    #[async_trait]
+    impl TonClientInterface for RecordingClient {
+        async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+            Ok(self.connection.clone())
+        }
+
+        async fn invoke_on_connection(
+            &self,
+            function: &TonFunction,
+        ) -> Result<(TonConnection, TonResult), TonClientError> {
+            self.functions.lock().unwrap().push(function.clone());
+            Ok((self.connection.clone(), TonResult::Ok {}))
+        }
+    }
+
+    fn block_id(seqno: i32) -> BlockIdExt {
+        BlockIdExt {
+            workchain: -1,
+            shard: i64::MIN,
+            seqno,
+            root_hash: vec![0; 32],
+            file_hash: vec![0; 32],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_block_context_pins_functions() {
+        let client = RecordingClient {
+            connection: TonConnection::new(
+                NOOP_CONNECTION_CALLBACK.clone(),
+                &TonConnectionParams::default(),
+            )
+            .unwrap(),
+            functions: Mutex::new(vec![]),
+        };
+        let context = BlockContext::new(&client, &block_id(100));
+        // Unexpected result of the mock, only the sent function matters
+        assert!(context
+            .get_raw_account_state(&TonAddress::NULL)
+            .await
+            .is_err());
+        let repinned = BlockContext::new(&context, &block_id(200));
+        assert!(repinned.invoke(&TonFunction::Sync {}).await.is_ok());
+
+        let functions = client.functions.lock().unwrap();
+        match &functions[0] {
+            TonFunction::WithBlock { id, function } => {
+                assert_eq!(id, &block_id(100));
+                assert!(matches!(
+                    function.as_ref(),
+                    TonFunction::RawGetAccountState { .. }
+                ));
+            }
+            f => panic!("Unexpected function: {:?}", f),
+        }
+        // The inner context pins the function again to its own block
+        assert_eq!(
+            functions[1],
+            TonFunction::WithBlock {
+                id: block_id(100),
+                function: Box::new(TonFunction::Sync {}),
+            }
+        );
+    }
+}
@@ -1,9 +1,11 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
 use async_trait::async_trait;
 use futures::future::try_join_all;
 use futures::FutureExt;
 use tonlib_core::TonAddress;
 
-use crate::client::{TonClientError, TonClientInterface, TxId};
+use crate::client::{BlockContext, TonClientError, TonClientInterface, TxId};
 use crate::tl::{
     BlockIdExt, BlocksAccountTransactionId, BlocksTransactions, RawTransaction,
     NULL_BLOCKS_ACCOUNT_TRANSACTION_ID,
@@ -12,6 +14,12 @@ use crate::tl::{
 /// High-level functions for working with blocks & shards
 #[async_trait]
 pub trait TonBlockFunctions: TonClientInterface + Send + Sync {
+    /// Returns the client pinned to `block_id`, functions invoked through it read the state
+    /// of the block.
+    fn at_block(&self, block_id: &BlockIdExt) -> BlockContext<'_, Self> {
+        BlockContext::new(self, block_id)
+    }
+
     /// Returns the list of all transaction IDs in specified shard.
     async fn get_shard_tx_ids(&self, shard_id: &BlockIdExt) -> Result<Vec<TxId>, TonClientError> {
         let mut after: BlocksAccountTransactionId = NULL_BLOCKS_ACCOUNT_TRANSACTION_ID.clone();
@@ -92,6 +100,40 @@ pub trait TonBlockFunctions: TonClientInterface + Send + Sync {
         let txs: Vec<_> = try_join_all(f).await?;
         Ok(txs)
     }
+
+    /// Returns generation time of the latest masterchain block.
+    ///
+    /// Unlike the local clock, this value is consistent with the time validators use
+    /// to check `valid_until` of external messages.
+    async fn get_masterchain_time(&self) -> Result<u32, TonClientError> {
+        let (conn, info) = self.get_masterchain_info().await?;
+        let header = conn.get_block_header(&info.last).await?;
+        u32::try_from(header.gen_utime).map_err(|_| {
+            TonClientError::InternalError(format!("Invalid gen_utime: {}", header.gen_utime))
+        })
+    }
+
+    /// Returns the expiration time for a message to be valid for `ttl` since the masterchain time.
+    async fn get_message_expire_at(&self, ttl: Duration) -> Result<u32, TonClientError> {
+        let now = self.get_masterchain_time().await?;
+        Ok(now.saturating_add(ttl.as_secs() as u32))
+    }
+
+    /// Returns the number of seconds the time of a lite server (`liteServer.getInfo`) is ahead
+    /// of the local clock, negative if it's behind.
+    ///
+    /// ```ignore
+    /// let mut policy = ExpirationPolicy::ttl(Duration::from_secs(120));
+    /// policy.with_clock_offset(client.get_clock_offset().await?);
+    /// let builder = TonTransactionBuilder::with_expiration_policy(&wallet, seqno, &policy);
+    /// ```
+    async fn get_clock_offset(&self) -> Result<i64, TonClientError> {
+        let info = self.lite_server_get_info().await?;
+        let local = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| TonClientError::InternalError(format!("Invalid local time: {}", e)))?;
+        Ok(info.now - local.as_secs() as i64)
+    }
 }
 
 impl<T> TonBlockFunctions for T where T: TonClientInterface + Send + Sync {}
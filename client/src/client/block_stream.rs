@@ -2,10 +2,12 @@ use std::collections::HashSet;
 use std::time::Duration;
 
 use futures::future::try_join_all;
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
 use tokio::time;
 
-use crate::client::{TonClientError, TonClientInterface, TonConnection};
-use crate::tl::{BlockId, BlockIdExt, BlocksHeader, BlocksShards};
+use crate::client::{TonBlockFunctions, TonClientError, TonClientInterface, TonConnection};
+use crate::tl::{BlockId, BlockIdExt, BlocksHeader, BlocksShards, RawTransaction};
 
 #[derive(Debug, Clone)]
 pub struct BlockStreamItem {
@@ -13,6 +15,15 @@ pub struct BlockStreamItem {
     pub shards: Vec<BlockIdExt>,
 }
 
+/// Block of `BlockStream` together with the transactions of the masterchain block and
+/// of every shard block.
+#[derive(Debug, Clone)]
+pub struct BlockWithTxs {
+    pub block: BlockStreamItem,
+    /// Transactions by block, the masterchain block comes first.
+    pub transactions: Vec<(BlockIdExt, Vec<RawTransaction>)>,
+}
+
 /// Allows to sequentially retrieve all shards in all workchains.
 ///
 /// The result of `next` call is the height of next masterchain block together with
@@ -33,6 +44,14 @@ impl<C: TonClientInterface + Clone> BlockStream<C> {
         }
     }
 
+    /// Returns the seqno of the masterchain block retrieved by the next `next` call.
+    ///
+    /// Persisting this value after processing a block and passing it to `new` resumes
+    /// the stream without skipping or repeating blocks.
+    pub fn next_seqno(&self) -> i32 {
+        self.next_seqno
+    }
+
     /// Retrieves the next masterchain block together with all shards finalized in this block
     ///
     /// If the next block is not yet available, the returned future resolves when it's added to masterchain.
@@ -92,6 +111,46 @@ impl<C: TonClientInterface + Clone> BlockStream<C> {
         })
     }
 
+    /// Same as `next`, but also retrieves all transactions of the returned blocks.
+    ///
+    /// On error the block is not consumed, so the next call retrieves it again.
+    pub async fn next_with_transactions(&mut self) -> Result<BlockWithTxs, TonClientError> {
+        let next_seqno = self.next_seqno;
+        let prev_block_set = self.prev_block_set.clone();
+        let result = self.load_with_transactions().await;
+        if result.is_err() {
+            self.next_seqno = next_seqno;
+            self.prev_block_set = prev_block_set;
+        }
+        result
+    }
+
+    async fn load_with_transactions(&mut self) -> Result<BlockWithTxs, TonClientError> {
+        let block = self.next().await?;
+        let mut blocks = Vec::with_capacity(block.shards.len() + 1);
+        blocks.push(block.master_shard.clone());
+        blocks.extend(block.shards.iter().cloned());
+        let transactions = self.client.get_shards_transactions(&blocks).await?;
+        Ok(BlockWithTxs {
+            block,
+            transactions,
+        })
+    }
+
+    /// Converts into an endless stream of blocks with transactions, see `next_with_transactions`.
+    ///
+    /// A failed block is yielded as an error and retried on the next poll of the stream.
+    pub fn into_stream(self) -> BoxStream<'static, Result<BlockWithTxs, TonClientError>>
+    where
+        C: 'static,
+    {
+        stream::unfold(self, |mut block_stream| async move {
+            let item = block_stream.next_with_transactions().await;
+            Some((item, block_stream))
+        })
+        .boxed()
+    }
+
     async fn get_block_headers(
         &self,
         conn: &TonConnection,
@@ -0,0 +1,119 @@
+use std::future::Future;
+use std::sync::Arc;
+
+use tokio::runtime::{Builder, Handle, Runtime};
+use tonlib_core::TonAddress;
+
+use crate::client::{
+    TonClientError, TonClientInterface, TonConnection, TonConnectionCallback, TonConnectionParams,
+};
+use crate::contract::SmcHandle;
+use crate::tl::{KeyStoreType, OptionsInfo, SmcRunResult, TonFunction, TonResult, TvmStackEntry};
+use crate::types::TonMethodId;
+
+mod ton_client;
+pub use ton_client::*;
+
+/// `TonConnection` with blocking methods.
+///
+/// Every call is driven to completion on an internal current-thread runtime.
+/// Calling any method from within an async context returns `TonClientError::InternalError`
+/// instead of blocking the executor. For the same reason the connection must not be dropped
+/// inside an async context.
+pub struct BlockingTonConnection {
+    connection: TonConnection,
+    runtime: Runtime,
+}
+
+impl BlockingTonConnection {
+    /// Creates a new uninitialized BlockingTonConnection.
+    pub fn new(
+        callback: Arc<dyn TonConnectionCallback>,
+        params: &TonConnectionParams,
+    ) -> Result<BlockingTonConnection, TonClientError> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        let connection = TonConnection::new(callback, params)?;
+        Ok(BlockingTonConnection {
+            connection,
+            runtime,
+        })
+    }
+
+    /// Creates a new initialized BlockingTonConnection.
+    pub fn connect(
+        params: &TonConnectionParams,
+        callback: Arc<dyn TonConnectionCallback>,
+    ) -> Result<BlockingTonConnection, TonClientError> {
+        check_blocking_allowed()?;
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        let connection = runtime.block_on(TonConnection::connect(params, callback))?;
+        Ok(BlockingTonConnection {
+            connection,
+            runtime,
+        })
+    }
+
+    /// Returns the underlying connection, e.g. to pass it to async code running elsewhere.
+    pub fn connection(&self) -> &TonConnection {
+        &self.connection
+    }
+
+    pub fn init(
+        &self,
+        config: &str,
+        blockchain_name: Option<&str>,
+        use_callbacks_for_network: bool,
+        ignore_cache: bool,
+        keystore_type: KeyStoreType,
+    ) -> Result<OptionsInfo, TonClientError> {
+        self.block_on(self.connection.init(
+            config,
+            blockchain_name,
+            use_callbacks_for_network,
+            ignore_cache,
+            keystore_type,
+        ))?
+    }
+
+    pub fn invoke(&self, function: &TonFunction) -> Result<TonResult, TonClientError> {
+        self.block_on(self.connection.invoke(function))?
+    }
+
+    /// Loads the contract at `address`, the handle is to be released with `smc_forget_handle`
+    /// since it can't be forgotten on drop outside of tokio runtime.
+    pub fn smc_load(&self, address: &TonAddress) -> Result<SmcHandle, TonClientError> {
+        self.block_on(self.connection.smc_load(address))?
+    }
+
+    pub fn smc_forget_handle(&self, handle: &SmcHandle) -> Result<(), TonClientError> {
+        self.block_on(handle.forget())?
+    }
+
+    pub fn smc_forget(&self, id: i64) -> Result<(), TonClientError> {
+        self.block_on(self.connection.smc_forget(id))?
+    }
+
+    pub fn smc_run_get_method(
+        &self,
+        id: i64,
+        method: &TonMethodId,
+        stack: &[TvmStackEntry],
+    ) -> Result<SmcRunResult, TonClientError> {
+        self.block_on(self.connection.smc_run_get_method(id, method, stack))?
+    }
+
+    fn block_on<F: Future>(&self, future: F) -> Result<F::Output, TonClientError> {
+        check_blocking_allowed()?;
+        Ok(self.runtime.block_on(future))
+    }
+}
+
+fn check_blocking_allowed() -> Result<(), TonClientError> {
+    if Handle::try_current().is_ok() {
+        Err(TonClientError::InternalError(
+            "Blocking API can't be used from within an async context".to_string(),
+        ))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+use std::future::Future;
+
+use tokio::runtime::{Builder, Runtime};
+use tonlib_core::TonAddress;
+
+use super::check_blocking_allowed;
+use crate::client::{
+    AccountInfo, GetMethodRequest, SentMessage, TonClient as AsyncTonClient, TonClientBuilder,
+    TonClientError, TonClientInterface, TonGetMethodFunctions,
+};
+use crate::contract::TonContractError;
+use crate::tl::{
+    BlockIdExt, BlocksAccountTransactionId, BlocksBlockSignatures, BlocksMasterchainInfo,
+    BlocksOutMsgQueueSizes, BlocksShardBlockProof, BlocksTransactions, FullAccountState,
+    InternalTransactionId, RawFullAccountState, RawTransactions, TonFunction, TonResult,
+};
+use crate::types::{TonMethodId, TvmStackEntry, TvmSuccess};
+
+/// `TonClient` with blocking methods, e.g. for CLI tools and code without a tokio runtime.
+///
+/// The pool and its background tasks run on an internal current-thread runtime, which only
+/// makes progress while a method is being called. Like `BlockingTonConnection`, the client
+/// can't be used or dropped within an async context.
+pub struct TonClient {
+    client: AsyncTonClient,
+    runtime: Runtime,
+}
+
+impl TonClient {
+    /// Creates a client with the pool built by `builder`.
+    pub fn new(builder: &TonClientBuilder) -> Result<TonClient, TonClientError> {
+        check_blocking_allowed()?;
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        let client = runtime.block_on(builder.build())?;
+        Ok(TonClient { client, runtime })
+    }
+
+    pub fn default() -> Result<TonClient, TonClientError> {
+        Self::new(&AsyncTonClient::builder())
+    }
+
+    /// Returns the underlying async client, e.g. to call functions without blocking
+    /// equivalent with `block_on`.
+    pub fn client(&self) -> &AsyncTonClient {
+        &self.client
+    }
+
+    /// Drives `future` to completion on the runtime of the client.
+    pub fn block_on<F: Future>(&self, future: F) -> Result<F::Output, TonClientError> {
+        check_blocking_allowed()?;
+        Ok(self.runtime.block_on(future))
+    }
+
+    pub fn invoke(&self, function: &TonFunction) -> Result<TonResult, TonClientError> {
+        self.block_on(self.client.invoke(function))?
+    }
+
+    pub fn get_masterchain_info(&self) -> Result<BlocksMasterchainInfo, TonClientError> {
+        let (_, info) = self.block_on(self.client.get_masterchain_info())??;
+        Ok(info)
+    }
+
+    pub fn get_account_info(&self, address: &str) -> Result<AccountInfo, TonClientError> {
+        self.block_on(self.client.get_account_info(address))?
+    }
+
+    pub fn get_account_state(
+        &self,
+        address: &TonAddress,
+    ) -> Result<FullAccountState, TonClientError> {
+        self.block_on(self.client.get_account_state(address))?
+    }
+
+    pub fn get_raw_account_state(
+        &self,
+        address: &TonAddress,
+    ) -> Result<RawFullAccountState, TonClientError> {
+        self.block_on(self.client.get_raw_account_state(address))?
+    }
+
+    pub fn get_raw_transactions_v2(
+        &self,
+        address: &TonAddress,
+        from_transaction_id: &InternalTransactionId,
+        count: usize,
+        try_decode_messages: bool,
+    ) -> Result<RawTransactions, TonClientError> {
+        self.block_on(self.client.get_raw_transactions_v2(
+            address,
+            from_transaction_id,
+            count,
+            try_decode_messages,
+        ))?
+    }
+
+    pub fn send_message(&self, body: &[u8]) -> Result<SentMessage, TonClientError> {
+        self.block_on(self.client.send_message(body))?
+    }
+
+    pub fn send_raw_message_return_hash(&self, body: &[u8]) -> Result<Vec<u8>, TonClientError> {
+        self.block_on(self.client.send_raw_message_return_hash(body))?
+    }
+
+    pub fn lookup_block_by_seqno(
+        &self,
+        workchain: i32,
+        shard: i64,
+        seqno: i32,
+    ) -> Result<BlockIdExt, TonClientError> {
+        self.block_on(self.client.lookup_block_by_seqno(workchain, shard, seqno))?
+    }
+
+    pub fn lookup_block_by_lt(
+        &self,
+        workchain: i32,
+        shard: i64,
+        lt: i64,
+    ) -> Result<BlockIdExt, TonClientError> {
+        self.block_on(self.client.lookup_block_by_lt(workchain, shard, lt))?
+    }
+
+    pub fn lookup_block_by_utime(
+        &self,
+        workchain: i32,
+        shard: i64,
+        utime: i32,
+    ) -> Result<BlockIdExt, TonClientError> {
+        self.block_on(self.client.lookup_block_by_utime(workchain, shard, utime))?
+    }
+
+    pub fn get_block_transactions(
+        &self,
+        block_id: &BlockIdExt,
+        mode: u32,
+        count: u32,
+        after: &BlocksAccountTransactionId,
+    ) -> Result<BlocksTransactions, TonClientError> {
+        self.block_on(
+            self.client
+                .get_block_transactions(block_id, mode, count, after),
+        )?
+    }
+
+    pub fn get_masterchain_block_signatures(
+        &self,
+        seqno: i32,
+    ) -> Result<BlocksBlockSignatures, TonClientError> {
+        self.block_on(self.client.get_masterchain_block_signatures(seqno))?
+    }
+
+    pub fn get_shard_block_proof(
+        &self,
+        block_id: &BlockIdExt,
+        from: Option<&BlockIdExt>,
+    ) -> Result<BlocksShardBlockProof, TonClientError> {
+        self.block_on(self.client.get_shard_block_proof(block_id, from))?
+    }
+
+    /// Returns the sizes of outbound message queues of all shards, or of `(workchain, shard)`.
+    pub fn get_out_msg_queue_sizes(
+        &self,
+        shard: Option<(i32, i64)>,
+    ) -> Result<BlocksOutMsgQueueSizes, TonClientError> {
+        self.block_on(self.client.get_out_msg_queue_sizes(shard))?
+    }
+
+    /// Runs a get-method on the latest state of the contract at `address`.
+    ///
+    /// A non-zero exit code fails with `TonContractError::TvmRunError`.
+    pub fn run_get_method<M: Into<TonMethodId>>(
+        &self,
+        address: &TonAddress,
+        method: M,
+        stack: &[TvmStackEntry],
+    ) -> Result<TvmSuccess, TonContractError> {
+        let request = GetMethodRequest::new(address, method, stack);
+        let results = self.block_on(self.client.run_get_methods_batch(&[request], 1))?;
+        results
+            .into_iter()
+            .next()
+            .expect("a result for every request")
+    }
+}
@@ -1,10 +1,14 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use super::TonConnectionCallback;
 use crate::client::{
-    error, ConnectionCheck, MultiConnectionCallback, RetryStrategy, TonClient, TonConnectionParams,
-    LOGGING_CONNECTION_CALLBACK, NOOP_CONNECTION_CALLBACK,
+    error, ArchiveRoutingConfig, CircuitBreakerConfig, Clock, ConnectionCheck, ConnectionWarmup,
+    MultiConnectionCallback, PoolDispatch, PoolRouting, RetryStrategy, TonClient,
+    TonConnectionParams, TrafficCapture, LOGGING_CONNECTION_CALLBACK, NOOP_CONNECTION_CALLBACK,
+    SYSTEM_CLOCK,
 };
+use crate::config::Network;
 
 pub struct TonClientBuilder {
     pool_size: usize,
@@ -12,6 +16,11 @@ pub struct TonClientBuilder {
     retry_strategy: RetryStrategy,
     callback: Arc<dyn TonConnectionCallback>,
     connection_check: ConnectionCheck,
+    dispatch: PoolDispatch,
+    health_check_interval: Option<Duration>,
+    circuit_breaker: Option<CircuitBreakerConfig>,
+    archive_routing: Option<ArchiveRoutingConfig>,
+    clock: Arc<dyn Clock>,
 }
 
 impl TonClientBuilder {
@@ -22,6 +31,11 @@ impl TonClientBuilder {
             retry_strategy: RetryStrategy::default(),
             callback: LOGGING_CONNECTION_CALLBACK.clone(),
             connection_check: ConnectionCheck::None,
+            dispatch: PoolDispatch::Random,
+            health_check_interval: None,
+            circuit_breaker: None,
+            archive_routing: None,
+            clock: SYSTEM_CLOCK.clone(),
         }
     }
 
@@ -40,6 +54,11 @@ impl TonClientBuilder {
         self
     }
 
+    /// Sets the config of `network`, see `Network::config`.
+    pub fn with_network(&mut self, network: &Network) -> &mut Self {
+        self.with_config(network.config())
+    }
+
     pub fn with_retry_strategy(&mut self, retry_strategy: &RetryStrategy) -> &mut Self {
         self.retry_strategy = retry_strategy.clone();
         self
@@ -80,15 +99,65 @@ impl TonClientBuilder {
         self
     }
 
+    pub fn with_dispatch(&mut self, dispatch: PoolDispatch) -> &mut Self {
+        self.dispatch = dispatch;
+        self
+    }
+
+    /// Sets contracts to pre-load on every pool connection before it's used for requests.
+    pub fn with_connection_warmup(&mut self, warmup: &ConnectionWarmup) -> &mut Self {
+        self.connection_params.warmup = warmup.clone();
+        self
+    }
+
+    /// Makes the client check its connections every `interval` and replace unhealthy ones,
+    /// see `TonClient::start_health_checks`. `build` fails if `interval` is zero.
+    pub fn with_health_check_interval(&mut self, interval: Duration) -> &mut Self {
+        self.health_check_interval = Some(interval);
+        self
+    }
+
+    /// Makes the client take connections failing repeatedly out of rotation and re-initialize them,
+    /// see `CircuitBreakerConfig`.
+    pub fn with_circuit_breaker(&mut self, config: &CircuitBreakerConfig) -> &mut Self {
+        self.circuit_breaker = Some(*config);
+        self
+    }
+
+    /// Makes connections of the client measure request durations and wait for the rate limit
+    /// with `clock`, see `TonConnection::new_with_clock`.
+    pub fn with_clock(&mut self, clock: Arc<dyn Clock>) -> &mut Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Makes the client probe its connections for archival state and send historical queries
+    /// to archive nodes only, see `ArchiveRoutingConfig`.
+    pub fn with_archive_routing(&mut self, config: &ArchiveRoutingConfig) -> &mut Self {
+        self.archive_routing = Some(*config);
+        self
+    }
+
     pub async fn build(&self) -> Result<TonClient, error::TonClientError> {
-        TonClient::new(
+        let routing = PoolRouting {
+            dispatch: self.dispatch,
+            circuit_breaker: self.circuit_breaker,
+            archive_routing: self.archive_routing,
+        };
+        let client = TonClient::new_with_routing(
             self.pool_size,
             &self.connection_params,
             &self.retry_strategy,
             self.callback.clone(),
             self.connection_check.clone(),
+            routing,
+            self.clock.clone(),
         )
-        .await
+        .await?;
+        if let Some(interval) = self.health_check_interval {
+            client.start_health_checks(interval)?;
+        }
+        Ok(client)
     }
 }
 
@@ -97,3 +166,229 @@ impl Default for TonClientBuilder {
         Self::new()
     }
 }
+
+/// Builder of `TonConnectionParams`, fields that are not set keep the values of
+/// `TonConnectionParams::default()`.
+#[derive(Debug, Clone, Default)]
+pub struct TonConnectionParamsBuilder {
+    params: TonConnectionParams,
+}
+
+impl TonConnectionParamsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_config(&mut self, config: &str) -> &mut Self {
+        self.params.config = config.to_string();
+        self
+    }
+
+    /// Sets the config of `network`, see `Network::config`.
+    pub fn with_network(&mut self, network: &Network) -> &mut Self {
+        self.with_config(network.config())
+    }
+
+    pub fn with_blockchain_name(&mut self, blockchain_name: &str) -> &mut Self {
+        self.params.blockchain_name = Some(blockchain_name.to_string());
+        self
+    }
+
+    /// Restricts the connection to liteservers of the config with given indices.
+    pub fn with_liteserver_indices(&mut self, indices: &[usize]) -> &mut Self {
+        self.params.liteserver_indices = Some(indices.to_vec());
+        self
+    }
+
+    /// Makes `connect` verify the connection with up to `attempts` liteservers,
+    /// see `TonConnectionParams::connect_probe_attempts`.
+    pub fn with_connect_probe_attempts(&mut self, attempts: usize) -> &mut Self {
+        self.params.connect_probe_attempts = Some(attempts);
+        self
+    }
+
+    pub fn with_callbacks_for_network(&mut self, use_callbacks_for_network: bool) -> &mut Self {
+        self.params.use_callbacks_for_network = use_callbacks_for_network;
+        self
+    }
+
+    pub fn with_ignore_cache(&mut self, ignore_cache: bool) -> &mut Self {
+        self.params.ignore_cache = ignore_cache;
+        self
+    }
+
+    pub fn with_keystore_dir(&mut self, keystore_dir: &str) -> &mut Self {
+        self.params.keystore_dir = Some(keystore_dir.to_string());
+        self
+    }
+
+    pub fn without_keystore(&mut self) -> &mut Self {
+        self.params.keystore_dir = None;
+        self
+    }
+
+    pub fn with_notification_queue_length(&mut self, length: usize) -> &mut Self {
+        self.params.notification_queue_length = length;
+        self
+    }
+
+    pub fn with_notification_queue_high_watermark(&mut self, high_watermark: usize) -> &mut Self {
+        self.params.notification_queue_high_watermark = Some(high_watermark);
+        self
+    }
+
+    pub fn with_request_queue_high_watermark(&mut self, high_watermark: usize) -> &mut Self {
+        self.params.request_queue_high_watermark = Some(high_watermark);
+        self
+    }
+
+    /// Sets the maximum number of simultaneous requests, `0` for no limit.
+    pub fn with_concurrency_limit(&mut self, concurrency_limit: usize) -> &mut Self {
+        self.params.concurrency_limit = concurrency_limit;
+        self
+    }
+
+    /// Limits the rate of requests to `max_rps` per second, allowing bursts of `burst` requests.
+    pub fn with_rate_limit(&mut self, max_rps: u32, burst: u32) -> &mut Self {
+        self.params.max_rps = Some(max_rps);
+        self.params.rate_limit_burst = Some(burst);
+        self
+    }
+
+    pub fn with_update_init_block(&mut self, update_init_block: bool) -> &mut Self {
+        self.params.update_init_block = update_init_block;
+        self
+    }
+
+    pub fn with_request_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.params.request_timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_slow_request_threshold(&mut self, threshold: Duration) -> &mut Self {
+        self.params.slow_request_threshold = Some(threshold);
+        self
+    }
+
+    pub fn with_max_request_age(
+        &mut self,
+        max_age: Duration,
+        reap_interval: Duration,
+    ) -> &mut Self {
+        self.params.max_request_age = Some(max_age);
+        self.params.request_reap_interval = reap_interval;
+        self
+    }
+
+    /// Enables reconnecting after `error_threshold` failed receive calls in a row.
+    pub fn with_reconnect(&mut self, error_threshold: usize) -> &mut Self {
+        self.params.reconnect = true;
+        self.params.reconnect_error_threshold = error_threshold;
+        self
+    }
+
+    /// Enables reconnecting once nothing was received from tonlib for `timeout`
+    /// while requests were in flight.
+    pub fn with_stall_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.params.stall_timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_run_loop_on_blocking_pool(&mut self, run_loop_on_blocking_pool: bool) -> &mut Self {
+        self.params.run_loop_on_blocking_pool = run_loop_on_blocking_pool;
+        self
+    }
+
+    pub fn with_warmup(&mut self, warmup: &ConnectionWarmup) -> &mut Self {
+        self.params.warmup = warmup.clone();
+        self
+    }
+
+    pub fn with_log_verbosity_level(&mut self, log_verbosity_level: u32) -> &mut Self {
+        self.params.log_verbosity_level = Some(log_verbosity_level);
+        self
+    }
+
+    pub fn with_traffic_capture(&mut self, traffic_capture: &TrafficCapture) -> &mut Self {
+        self.params.traffic_capture = Some(traffic_capture.clone());
+        self
+    }
+
+    pub fn build(&self) -> TonConnectionParams {
+        self.params.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::TonConnectionParamsBuilder;
+    use crate::client::{
+        TonConnectionParams, DEFAULT_CONNECTION_CONCURRENCY_LIMIT, DEFAULT_CONNECTION_PARAMS,
+        DEFAULT_NOTIFICATION_QUEUE_LENGTH,
+    };
+    use crate::config::{Network, MAINNET_CONFIG, TESTNET_CONFIG};
+
+    #[test]
+    fn test_connection_params_builder_defaults() {
+        let params = TonConnectionParams::builder().build();
+        assert_eq!(params, *DEFAULT_CONNECTION_PARAMS);
+        assert_eq!(params.config, MAINNET_CONFIG);
+        assert_eq!(params.blockchain_name, None);
+        assert_eq!(params.keystore_dir, None);
+        assert!(!params.use_callbacks_for_network);
+        assert!(!params.ignore_cache);
+        assert_eq!(
+            params.notification_queue_length,
+            DEFAULT_NOTIFICATION_QUEUE_LENGTH
+        );
+        assert_eq!(
+            params.concurrency_limit,
+            DEFAULT_CONNECTION_CONCURRENCY_LIMIT
+        );
+        assert!(params.update_init_block);
+        assert_eq!(params.request_timeout, None);
+        assert_eq!(params.max_rps, None);
+        assert!(!params.reconnect);
+    }
+
+    #[test]
+    fn test_connection_params_builder() {
+        let params = TonConnectionParamsBuilder::new()
+            .with_config(TESTNET_CONFIG)
+            .with_blockchain_name("testnet")
+            .with_keystore_dir("/tmp/keystore")
+            .with_ignore_cache(true)
+            .with_request_timeout(Duration::from_secs(5))
+            .with_reconnect(3)
+            .with_stall_timeout(Duration::from_secs(30))
+            .with_rate_limit(10, 20)
+            .build();
+        let expected = TonConnectionParams {
+            config: TESTNET_CONFIG.to_string(),
+            blockchain_name: Some("testnet".to_string()),
+            keystore_dir: Some("/tmp/keystore".to_string()),
+            ignore_cache: true,
+            request_timeout: Some(Duration::from_secs(5)),
+            reconnect: true,
+            reconnect_error_threshold: 3,
+            stall_timeout: Some(Duration::from_secs(30)),
+            max_rps: Some(10),
+            rate_limit_burst: Some(20),
+            ..Default::default()
+        };
+        assert_eq!(params, expected);
+
+        let params = TonConnectionParamsBuilder::new()
+            .with_keystore_dir("/tmp/keystore")
+            .without_keystore()
+            .build();
+        assert_eq!(params.keystore_dir, None);
+
+        let params = TonConnectionParamsBuilder::new()
+            .with_network(&Network::Testnet)
+            .build();
+        assert_eq!(params.config, TESTNET_CONFIG);
+    }
+}
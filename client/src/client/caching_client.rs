@@ -0,0 +1,461 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+use moka::future::Cache;
+use moka::Expiry;
+use serde::{Deserialize, Serialize};
+use tonlib_core::TonAddress;
+
+use crate::client::{
+    client_run_get_method, TonClientError, TonClientInterface, TonConnection, TonProvider,
+};
+use crate::contract::TonContractError;
+use crate::tl::{
+    BlocksMasterchainInfo, RawFullAccountState, TonFunction, TonResult,
+    TvmStackEntry as TlTvmStackEntry,
+};
+use crate::types::{TonMethodId, TvmStackEntry, TvmSuccess};
+
+pub const DEFAULT_READ_CACHE_CAPACITY: u64 = 10_000;
+
+/// Cached result of a read request.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
+pub enum ReadCacheValue {
+    Result(TonResult),
+    GetMethod(TvmSuccess),
+}
+
+/// Storage of `CachingTonClient`, e.g. a shared cache of several processes.
+///
+/// Keys are strings built from the request, including the block or transaction it's pinned to,
+/// values are serializable.
+#[async_trait]
+pub trait TonReadCache: Send + Sync {
+    async fn get(&self, key: &str) -> Option<ReadCacheValue>;
+
+    /// Stores `value`, which must not be returned by `get` after `ttl`.
+    async fn insert(&self, key: String, value: ReadCacheValue, ttl: Duration);
+}
+
+/// In-memory cache of a bounded number of entries. When full, entries are admitted and
+/// evicted by the TinyLFU policy of `moka`, which keeps the most frequently used ones.
+#[derive(Clone)]
+pub struct MemoryReadCache {
+    cache: Cache<String, (ReadCacheValue, Duration)>,
+}
+
+impl MemoryReadCache {
+    pub fn new(capacity: u64) -> MemoryReadCache {
+        let cache = Cache::builder()
+            .max_capacity(capacity)
+            .expire_after(EntryTtl)
+            .build();
+        MemoryReadCache { cache }
+    }
+
+    pub fn entry_count(&self) -> u64 {
+        self.cache.entry_count()
+    }
+}
+
+impl Default for MemoryReadCache {
+    fn default() -> Self {
+        MemoryReadCache::new(DEFAULT_READ_CACHE_CAPACITY)
+    }
+}
+
+#[async_trait]
+impl TonReadCache for MemoryReadCache {
+    async fn get(&self, key: &str) -> Option<ReadCacheValue> {
+        self.cache.get(key).await.map(|(value, _)| value)
+    }
+
+    async fn insert(&self, key: String, value: ReadCacheValue, ttl: Duration) {
+        self.cache.insert(key, (value, ttl)).await
+    }
+}
+
+struct EntryTtl;
+
+impl Expiry<String, (ReadCacheValue, Duration)> for EntryTtl {
+    fn expire_after_create(
+        &self,
+        _key: &String,
+        value: &(ReadCacheValue, Duration),
+        _created_at: Instant,
+    ) -> Option<Duration> {
+        Some(value.1)
+    }
+}
+
+/// Time to live of cached results by kind of request, `None` disables caching of the kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadCachePolicy {
+    /// `blocks.getMasterchainInfo`.
+    pub masterchain_info_ttl: Option<Duration>,
+    /// `getConfigParam` and `getConfigAll` at the latest block.
+    pub config_ttl: Option<Duration>,
+    /// Latest account states, `raw.getAccountState` and `getAccountState`.
+    pub account_state_ttl: Option<Duration>,
+    /// Get-methods at the latest account state, run by `TonProvider::run_get_method`.
+    ///
+    /// Not cached by default: results of get-methods such as `seqno` change with every
+    /// transaction, and a stale seqno makes the next external message fail.
+    pub get_method_ttl: Option<Duration>,
+    /// Requests of a given block or transaction, whose results never change, e.g.
+    /// `blocks.getShards`, `raw.getTransactions` or any read request wrapped in `withBlock`.
+    pub pinned_ttl: Option<Duration>,
+}
+
+impl Default for ReadCachePolicy {
+    fn default() -> Self {
+        ReadCachePolicy {
+            masterchain_info_ttl: Some(Duration::from_secs(1)),
+            config_ttl: Some(Duration::from_secs(60)),
+            account_state_ttl: Some(Duration::from_secs(1)),
+            get_method_ttl: None,
+            pinned_ttl: Some(Duration::from_secs(3600)),
+        }
+    }
+}
+
+impl ReadCachePolicy {
+    /// Returns the time to live of the result of `function`, `None` if it's not cached.
+    ///
+    /// Requests referring to contracts loaded by `smc.load` aren't cached, since ids of loaded
+    /// contracts are reused by a connection after reconnecting.
+    pub fn ttl(&self, function: &TonFunction) -> Option<Duration> {
+        match function {
+            TonFunction::BlocksGetMasterchainInfo {} => self.masterchain_info_ttl,
+            TonFunction::GetConfigParam { .. } | TonFunction::GetConfigAll { .. } => {
+                self.config_ttl
+            }
+            TonFunction::RawGetAccountState { .. } | TonFunction::GetAccountState { .. } => {
+                self.account_state_ttl
+            }
+            TonFunction::RawGetAccountStateByTransaction { .. }
+            | TonFunction::RawGetTransactions { .. }
+            | TonFunction::RawGetTransactionsV2 { .. }
+            | TonFunction::BlocksGetShards { .. }
+            | TonFunction::BlocksGetTransactions { .. }
+            | TonFunction::BlocksGetTransactionsExt { .. }
+            | TonFunction::GetBlockHeader { .. }
+            | TonFunction::BlocksGetMasterchainBlockSignatures { .. }
+            | TonFunction::BlocksGetShardBlockProof { from: Some(_), .. }
+            | TonFunction::SmcGetLibraries { .. } => self.pinned_ttl,
+            TonFunction::BlocksLookupBlock { mode, .. } if mode & 1 != 0 => self.pinned_ttl,
+            // Any read request in `withBlock` is pinned to the block
+            TonFunction::WithBlock { function, .. }
+                if ReadCachePolicy::default().ttl(function).is_some() =>
+            {
+                self.pinned_ttl
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Wrapper of any `TonClientInterface` serving repeated read requests from a cache,
+/// e.g. config params or account states read in a hot loop.
+///
+/// Requests are cached as defined by `ReadCachePolicy`, failed requests aren't cached.
+/// Concurrent identical requests are sent once, the others wait for its result.
+/// Results served from the cache are returned with a connection of `get_connection`.
+#[derive(Clone)]
+pub struct CachingTonClient<C> {
+    client: C,
+    cache: Arc<dyn TonReadCache>,
+    policy: ReadCachePolicy,
+    in_flight: Arc<DashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl<C: TonClientInterface> CachingTonClient<C> {
+    /// Caches results in a `MemoryReadCache` of default capacity.
+    pub fn new(client: C, policy: ReadCachePolicy) -> CachingTonClient<C> {
+        CachingTonClient::with_cache(client, policy, Arc::new(MemoryReadCache::default()))
+    }
+
+    pub fn with_cache(
+        client: C,
+        policy: ReadCachePolicy,
+        cache: Arc<dyn TonReadCache>,
+    ) -> CachingTonClient<C> {
+        CachingTonClient {
+            client,
+            cache,
+            policy,
+            in_flight: Arc::new(DashMap::new()),
+        }
+    }
+
+    /// Returns the wrapped client, e.g. to make a request bypassing the cache.
+    pub fn inner(&self) -> &C {
+        &self.client
+    }
+
+    pub fn policy(&self) -> &ReadCachePolicy {
+        &self.policy
+    }
+
+    /// Returns the cached value of `key` or stores the value produced by `load` for `ttl`.
+    async fn get_or_load<T, E, Fut>(
+        &self,
+        key: &str,
+        ttl: Duration,
+        load: Fut,
+        to_value: fn(&T) -> ReadCacheValue,
+    ) -> Result<Lookup<T>, E>
+    where
+        Fut: Future<Output = Result<T, E>>,
+    {
+        if let Some(value) = self.cache.get(key).await {
+            return Ok(Lookup::Hit(value));
+        }
+        let lock = self.in_flight.entry(key.to_string()).or_default().clone();
+        let result = {
+            let _guard = lock.lock().await;
+            // The result of an identical request might have been stored while waiting
+            match self.cache.get(key).await {
+                Some(value) => Ok(Lookup::Hit(value)),
+                None => match load.await {
+                    Ok(loaded) => {
+                        let value = to_value(&loaded);
+                        self.cache.insert(key.to_string(), value, ttl).await;
+                        Ok(Lookup::Loaded(loaded))
+                    }
+                    Err(e) => Err(e),
+                },
+            }
+        };
+        drop(lock);
+        self.in_flight
+            .remove_if(key, |_, lock| Arc::strong_count(lock) == 1);
+        result
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
+enum Lookup<T> {
+    Hit(ReadCacheValue),
+    Loaded(T),
+}
+
+#[async_trait]
+impl<C: TonClientInterface> TonClientInterface for CachingTonClient<C> {
+    async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+        self.client.get_connection().await
+    }
+
+    async fn invoke_on_connection(
+        &self,
+        function: &TonFunction,
+    ) -> Result<(TonConnection, TonResult), TonClientError> {
+        let key = match self.policy.ttl(function) {
+            Some(ttl) => serde_json::to_string(function).ok().map(|key| (key, ttl)),
+            None => None,
+        };
+        let (key, ttl) = match key {
+            Some(key) => key,
+            None => return self.client.invoke_on_connection(function).await,
+        };
+        let load = self.client.invoke_on_connection(function);
+        let to_value =
+            |(_, result): &(TonConnection, TonResult)| ReadCacheValue::Result(result.clone());
+        match self.get_or_load(&key, ttl, load, to_value).await? {
+            Lookup::Hit(ReadCacheValue::Result(result)) => {
+                Ok((self.client.get_connection().await?, result))
+            }
+            Lookup::Hit(_) => self.client.invoke_on_connection(function).await,
+            Lookup::Loaded(loaded) => Ok(loaded),
+        }
+    }
+}
+
+#[async_trait]
+impl<C: TonClientInterface> TonProvider for CachingTonClient<C> {
+    async fn get_masterchain_info(&self) -> Result<BlocksMasterchainInfo, TonClientError> {
+        let (_, info) = TonClientInterface::get_masterchain_info(self).await?;
+        Ok(info)
+    }
+
+    async fn get_raw_account_state(
+        &self,
+        address: &TonAddress,
+    ) -> Result<RawFullAccountState, TonClientError> {
+        TonClientInterface::get_raw_account_state(self, address).await
+    }
+
+    async fn run_get_method(
+        &self,
+        address: &TonAddress,
+        method: &TonMethodId,
+        stack: &[TvmStackEntry],
+    ) -> Result<TvmSuccess, TonContractError> {
+        let load = client_run_get_method(&self.client, address, method, stack);
+        let (key, ttl) = match (
+            get_method_key(address, method, stack),
+            self.policy.get_method_ttl,
+        ) {
+            (Some(key), Some(ttl)) => (key, ttl),
+            _ => return load.await,
+        };
+        let to_value = |result: &TvmSuccess| ReadCacheValue::GetMethod(result.clone());
+        match self.get_or_load(&key, ttl, load, to_value).await? {
+            Lookup::Hit(ReadCacheValue::GetMethod(result)) => Ok(result),
+            Lookup::Hit(_) => client_run_get_method(&self.client, address, method, stack).await,
+            Lookup::Loaded(result) => Ok(result),
+        }
+    }
+
+    async fn send_raw_message_return_hash(&self, body: &[u8]) -> Result<Vec<u8>, TonClientError> {
+        TonClientInterface::send_raw_message_return_hash(self, body).await
+    }
+}
+
+/// Returns the key of a get-method, `None` if the stack can't be converted to TL.
+fn get_method_key(
+    address: &TonAddress,
+    method: &TonMethodId,
+    stack: &[TvmStackEntry],
+) -> Option<String> {
+    let stack = stack
+        .iter()
+        .map(TlTvmStackEntry::try_from)
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+    let function = TonFunction::SmcRunGetMethod {
+        id: 0,
+        method: method.into(),
+        stack,
+    };
+    let function = serde_json::to_string(&function).ok()?;
+    Some(format!("getMethod:{}:{}", address.to_hex(), function))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+
+    use super::{CachingTonClient, ReadCachePolicy};
+    use crate::client::{
+        TonClientError, TonClientInterface, TonConnection, TonConnectionParams,
+        NOOP_CONNECTION_CALLBACK,
+    };
+    use crate::tl::{AccountAddress, BlockIdExt, TonFunction, TonResult};
+
+    /// Counts requests, failing those of `getConfigAll`.
+    struct CountingClient {
+        connection: TonConnection,
+        requests: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl TonClientInterface for CountingClient {
+        async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+            Ok(self.connection.clone())
+        }
+
+        async fn invoke_on_connection(
+            &self,
+            function: &TonFunction,
+        ) -> Result<(TonConnection, TonResult), TonClientError> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            match function {
+                TonFunction::GetConfigAll { .. } => Err(TonClientError::TonlibError {
+                    method: "getConfigAll",
+                    code: 500,
+                    message: "LITE_SERVER_UNKNOWN".to_string(),
+                }),
+                _ => Ok((self.connection.clone(), TonResult::Ok {})),
+            }
+        }
+    }
+
+    fn caching_client(
+        policy: ReadCachePolicy,
+    ) -> (CachingTonClient<CountingClient>, Arc<AtomicUsize>) {
+        let connection = TonConnection::new(
+            NOOP_CONNECTION_CALLBACK.clone(),
+            &TonConnectionParams::default(),
+        )
+        .unwrap();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let client = CountingClient {
+            connection,
+            requests: requests.clone(),
+        };
+        (CachingTonClient::new(client, policy), requests)
+    }
+
+    #[tokio::test]
+    async fn test_caching_client_serves_repeated_reads() {
+        let (client, requests) = caching_client(ReadCachePolicy::default());
+        let function = TonFunction::GetConfigParam { mode: 0, param: 34 };
+        let (first, second) = tokio::join!(client.invoke(&function), client.invoke(&function));
+        assert!(matches!(first, Ok(TonResult::Ok {})));
+        assert!(matches!(second, Ok(TonResult::Ok {})));
+        client.invoke(&function).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        client
+            .invoke(&TonFunction::GetConfigParam { mode: 0, param: 15 })
+            .await
+            .unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        // Neither failed nor non-read requests are cached
+        for _ in 0..2 {
+            assert!(client
+                .invoke(&TonFunction::GetConfigAll { mode: 0 })
+                .await
+                .is_err());
+            client.invoke(&TonFunction::Sync {}).await.unwrap();
+        }
+        assert_eq!(requests.load(Ordering::SeqCst), 6);
+    }
+
+    #[tokio::test]
+    async fn test_caching_client_expires_results() {
+        let policy = ReadCachePolicy {
+            masterchain_info_ttl: Some(Duration::from_millis(50)),
+            account_state_ttl: None,
+            ..Default::default()
+        };
+        let (client, requests) = caching_client(policy);
+        let function = TonFunction::BlocksGetMasterchainInfo {};
+        client.invoke(&function).await.unwrap();
+        client.invoke(&function).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        client.invoke(&function).await.unwrap();
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        assert_eq!(policy.ttl(&TonFunction::Sync {}), None);
+        let account_state = TonFunction::RawGetAccountState {
+            account_address: AccountAddress {
+                account_address: String::new(),
+            },
+        };
+        assert_eq!(policy.ttl(&account_state), None);
+        let pinned = TonFunction::WithBlock {
+            id: BlockIdExt {
+                workchain: -1,
+                shard: i64::MIN,
+                seqno: 1,
+                root_hash: vec![0; 32],
+                file_hash: vec![0; 32],
+            },
+            function: Box::new(account_state),
+        };
+        assert_eq!(policy.ttl(&pinned), policy.pinned_ttl);
+    }
+}
@@ -3,15 +3,21 @@ use std::time::Duration;
 
 use lazy_static::lazy_static;
 
-use crate::client::TonClientError;
-use crate::tl::{TonFunction, TonNotification, TonResult};
+use crate::client::{correlation_id, TonClientError};
+use crate::tl::{SyncState, TonFunction, TonNotification, TonResult};
 
 /// The callback methods invoked by TonConnection
+///
+/// Callbacks of a request can get its correlation id with `correlation_id`.
 #[allow(unused_variables)]
 pub trait TonConnectionCallback: Send + Sync {
     /// Method `on_invoke` gets called **before** invoking tonlib.
     fn on_invoke(&self, tag: &str, request_id: u32, function: &TonFunction) {}
 
+    /// Method `on_invoke_raw` gets called instead of `on_invoke` **before** invoking tonlib
+    /// with `TonConnection::invoke_raw_json`.
+    fn on_invoke_raw(&self, tag: &str, request_id: u32, method: &str, params: &serde_json::Value) {}
+
     /// Method `on_invoke_result` gets called in two scenarios:
     ///
     /// - **after** receiving invoke result from tonlib and **before** sending result to the caller.
@@ -30,13 +36,55 @@ pub trait TonConnectionCallback: Send + Sync {
     /// Method `on_cancelled_invoke` gets called when attempt to send an invoke result is failed  
     ///
     /// Typically this happens when the corresponding future (async fn invoke_on_connection) is cancelled  
+    ///
+    /// A request whose future is dropped before its result is received, e.g. one cancelled with
+    /// `TonConnection::invoke_cancellable`, is removed right away: `on_invoke_result` gets called
+    /// with `TonClientError::Cancelled`, followed by `on_cancelled_invoke`.
     fn on_cancelled_invoke(&self, tag: &str, request_id: u32, method: &str, duration: &Duration) {}
 
+    /// Method `on_invoke_timeout` gets called when no invoke result is received within
+    /// `request_timeout`.
+    ///
+    /// A result received from tonlib after the timeout is discarded.
+    fn on_invoke_timeout(&self, tag: &str, request_id: u32, method: &str, duration: &Duration) {}
+
+    /// Method `on_slow_invoke` gets called after `on_invoke_result` when the request took
+    /// longer than `slow_request_threshold`.
+    fn on_slow_invoke(&self, tag: &str, request_id: u32, method: &str, duration: &Duration) {}
+
+    /// Method `on_request_queue_high_watermark` gets called **after** sending a request
+    /// once the number of requests in flight reaches `request_queue_high_watermark`.
+    ///
+    /// It isn't called again until a request is sent with fewer requests in flight.
+    fn on_request_queue_high_watermark(&self, tag: &str, pending: usize) {}
+
     /// Method `on_notification` gets called upon receiving valid notification from tonlib.
     ///
     /// A tonlib notification doesn't have corresponding request and thus no `request_id`.
     fn on_notification(&self, tag: &str, notification: &TonNotification) {}
 
+    /// Method `on_notification_queue_high_watermark` gets called **after** sending a notification
+    /// if the number of queued notifications is at or above `notification_queue_high_watermark`.
+    ///
+    /// Reaching `capacity` means that lagging subscribers start losing notifications.
+    fn on_notification_queue_high_watermark(&self, tag: &str, len: usize, capacity: usize) {}
+
+    /// Method `on_notification_dropped` gets called **before** sending a notification to a full
+    /// queue, which overwrites the oldest notification not yet received by a lagging subscriber.
+    ///
+    /// `total` is the number of notifications dropped since the connection was created.
+    fn on_notification_dropped(&self, tag: &str, total: u64) {}
+
+    /// Method `on_notification_lagged` gets called when a notification stream of the connection,
+    /// e.g. of `subscribe_stream`, fell behind and skipped `skipped` notifications
+    /// overwritten in the queue.
+    fn on_notification_lagged(&self, tag: &str, skipped: u64) {}
+
+    /// Method `on_sync_state_changed` gets called **before** `on_notification` when tonlib
+    /// reports a sync state different from the previous one while it catches up with
+    /// the masterchain.
+    fn on_sync_state_changed(&self, tag: &str, sync_state: &SyncState) {}
+
     /// Method `on_ton_result_parse_error` gets called upon receiving message from tonlib
     /// that couldn't be parsed.
     ///
@@ -58,6 +106,23 @@ pub trait TonConnectionCallback: Send + Sync {
 
     /// Method `on_connection_loop_exit` gets called when new connection loop stops and connection is dropped
     fn on_connection_loop_exit(&self, tag: &str) {}
+
+    /// Method `on_init_completed` gets called when tonlib accepted the init function of
+    /// `TonConnection::init`, `reconnected` is whether it was re-sent after `on_reconnect`
+    /// or `on_connection_stalled`.
+    fn on_init_completed(&self, tag: &str, reconnected: bool) {}
+
+    /// Method `on_connection_established` gets called when `TonConnection::connect` returns
+    /// an initialized connection, after `health_check` if `connect_probe_attempts` is set.
+    fn on_connection_established(&self, tag: &str) {}
+
+    /// Method `on_reconnect` gets called when the connection replaces its tonlib client
+    /// after `consecutive_errors` failed receive calls in a row.
+    fn on_reconnect(&self, tag: &str, consecutive_errors: usize) {}
+
+    /// Method `on_connection_stalled` gets called when the connection replaces its tonlib client
+    /// after receiving nothing for `stalled_for` while requests were in flight.
+    fn on_connection_stalled(&self, tag: &str, stalled_for: &Duration) {}
 }
 
 /// An implementation of TonConnectionCallback that does nothing
@@ -80,21 +145,25 @@ impl TonConnectionCallback for LoggingConnectionCallback {
         match result {
             Ok(r) => {
                 log::trace!(
-                    "[{}] Invoke successful, request_id: {}, method: {}, elapsed: {:?}: {}",
+                    "[{}] Invoke successful, request_id: {}, method: {}, elapsed: {:?}{}: {}",
                     tag,
                     request_id,
                     method,
                     duration,
+                    correlation_suffix(),
                     r.to_string()
                 );
             }
+            // Reported by on_cancelled_invoke
+            Err(TonClientError::Cancelled { .. }) => {}
             Err(e) => {
                 log::warn!(
-                    "[{}] Invocation error: request_id: {:?}, method: {}, elapsed: {:?}: {}",
+                    "[{}] Invocation error: request_id: {:?}, method: {}, elapsed: {:?}{}: {}",
                     tag,
                     request_id,
                     method,
                     duration,
+                    correlation_suffix(),
                     e
                 );
             }
@@ -103,18 +172,78 @@ impl TonConnectionCallback for LoggingConnectionCallback {
 
     fn on_cancelled_invoke(&self, tag: &str, request_id: u32, method: &str, duration: &Duration) {
         log::warn!(
-            "[{}] Error sending invoke result, receiver already closed. method: {} request_id: {}, elapsed: {:?}",
+            "[{}] Error sending invoke result, receiver already closed. method: {} request_id: {}, elapsed: {:?}{}",
             tag,
             method,
             request_id,
             duration,
+            correlation_suffix(),
        );
     }
 
+    fn on_invoke_timeout(&self, tag: &str, request_id: u32, method: &str, duration: &Duration) {
+        log::warn!(
+            "[{}] Invoke timed out. method: {} request_id: {}, elapsed: {:?}{}",
+            tag,
+            method,
+            request_id,
+            duration,
+            correlation_suffix(),
+        );
+    }
+
+    fn on_slow_invoke(&self, tag: &str, request_id: u32, method: &str, duration: &Duration) {
+        log::warn!(
+            "[{}] Slow invoke. method: {} request_id: {}, elapsed: {:?}{}",
+            tag,
+            method,
+            request_id,
+            duration,
+            correlation_suffix(),
+        );
+    }
+
+    fn on_request_queue_high_watermark(&self, tag: &str, pending: usize) {
+        log::warn!(
+            "[{}] Request queue is filling up: {} in flight",
+            tag,
+            pending
+        );
+    }
+
     fn on_notification(&self, tag: &str, notification: &TonNotification) {
         log::trace!("[{}] Sending notification: {:?}", tag, notification);
     }
 
+    fn on_notification_queue_high_watermark(&self, tag: &str, len: usize, capacity: usize) {
+        log::warn!(
+            "[{}] Notification queue is filling up: {} of {} queued",
+            tag,
+            len,
+            capacity
+        );
+    }
+
+    fn on_notification_dropped(&self, tag: &str, total: u64) {
+        log::warn!(
+            "[{}] Notification queue is full, oldest notification dropped, {} dropped in total",
+            tag,
+            total
+        );
+    }
+
+    fn on_notification_lagged(&self, tag: &str, skipped: u64) {
+        log::warn!(
+            "[{}] Notification subscriber lagged, {} notifications skipped",
+            tag,
+            skipped
+        );
+    }
+
+    fn on_sync_state_changed(&self, tag: &str, sync_state: &SyncState) {
+        log::debug!("[{}] Sync state changed: {:?}", tag, sync_state);
+    }
+
     fn on_ton_result_parse_error(
         &self,
         tag: &str,
@@ -133,9 +262,44 @@ impl TonConnectionCallback for LoggingConnectionCallback {
         log::info!("[{}] Starting event loop", tag);
     }
 
+    fn on_reconnect(&self, tag: &str, consecutive_errors: usize) {
+        log::warn!(
+            "[{}] Reconnecting after {} consecutive errors",
+            tag,
+            consecutive_errors
+        );
+    }
+
+    fn on_connection_stalled(&self, tag: &str, stalled_for: &Duration) {
+        log::warn!(
+            "[{}] Reconnecting after receiving nothing for {:?}",
+            tag,
+            stalled_for
+        );
+    }
+
     fn on_connection_loop_exit(&self, tag: &str) {
         log::info!("[{}] Exiting event loop", tag);
     }
+
+    fn on_init_completed(&self, tag: &str, reconnected: bool) {
+        if reconnected {
+            log::info!("[{}] Connection re-initialized", tag);
+        } else {
+            log::debug!("[{}] Connection initialized", tag);
+        }
+    }
+
+    fn on_connection_established(&self, tag: &str) {
+        log::info!("[{}] Connection established", tag);
+    }
+}
+
+/// Returns `, correlation_id: <id>` for log lines of a request with a correlation id.
+fn correlation_suffix() -> String {
+    correlation_id()
+        .map(|id| format!(", correlation_id: {}", id))
+        .unwrap_or_default()
 }
 
 /// An implementation of TonConnectionCallback that invokes corresponding functions on
@@ -157,6 +321,12 @@ impl TonConnectionCallback for MultiConnectionCallback {
         }
     }
 
+    fn on_invoke_raw(&self, tag: &str, request_id: u32, method: &str, params: &serde_json::Value) {
+        for c in self.callbacks.iter() {
+            c.on_invoke_raw(tag, request_id, method, params)
+        }
+    }
+
     fn on_invoke_result(
         &self,
         tag: &str,
@@ -176,12 +346,54 @@ impl TonConnectionCallback for MultiConnectionCallback {
         }
     }
 
+    fn on_invoke_timeout(&self, tag: &str, request_id: u32, method: &str, duration: &Duration) {
+        for c in self.callbacks.iter() {
+            c.on_invoke_timeout(tag, request_id, method, duration)
+        }
+    }
+
+    fn on_slow_invoke(&self, tag: &str, request_id: u32, method: &str, duration: &Duration) {
+        for c in self.callbacks.iter() {
+            c.on_slow_invoke(tag, request_id, method, duration)
+        }
+    }
+
+    fn on_request_queue_high_watermark(&self, tag: &str, pending: usize) {
+        for c in self.callbacks.iter() {
+            c.on_request_queue_high_watermark(tag, pending)
+        }
+    }
+
     fn on_notification(&self, tag: &str, notification: &TonNotification) {
         for c in self.callbacks.iter() {
             c.on_notification(tag, notification)
         }
     }
 
+    fn on_notification_queue_high_watermark(&self, tag: &str, len: usize, capacity: usize) {
+        for c in self.callbacks.iter() {
+            c.on_notification_queue_high_watermark(tag, len, capacity)
+        }
+    }
+
+    fn on_notification_dropped(&self, tag: &str, total: u64) {
+        for c in self.callbacks.iter() {
+            c.on_notification_dropped(tag, total)
+        }
+    }
+
+    fn on_notification_lagged(&self, tag: &str, skipped: u64) {
+        for c in self.callbacks.iter() {
+            c.on_notification_lagged(tag, skipped)
+        }
+    }
+
+    fn on_sync_state_changed(&self, tag: &str, sync_state: &SyncState) {
+        for c in self.callbacks.iter() {
+            c.on_sync_state_changed(tag, sync_state)
+        }
+    }
+
     fn on_ton_result_parse_error(
         &self,
         tag: &str,
@@ -210,6 +422,30 @@ impl TonConnectionCallback for MultiConnectionCallback {
             c.on_connection_loop_exit(tag)
         }
     }
+
+    fn on_init_completed(&self, tag: &str, reconnected: bool) {
+        for c in self.callbacks.iter() {
+            c.on_init_completed(tag, reconnected)
+        }
+    }
+
+    fn on_connection_established(&self, tag: &str) {
+        for c in self.callbacks.iter() {
+            c.on_connection_established(tag)
+        }
+    }
+
+    fn on_reconnect(&self, tag: &str, consecutive_errors: usize) {
+        for c in self.callbacks.iter() {
+            c.on_reconnect(tag, consecutive_errors)
+        }
+    }
+
+    fn on_connection_stalled(&self, tag: &str, stalled_for: &Duration) {
+        for c in self.callbacks.iter() {
+            c.on_connection_stalled(tag, stalled_for)
+        }
+    }
 }
 
 lazy_static! {
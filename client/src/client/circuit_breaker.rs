@@ -0,0 +1,106 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::client::TonClientError;
+
+pub const DEFAULT_CIRCUIT_BREAKER_FAILURE_THRESHOLD: usize = 5;
+pub const DEFAULT_CIRCUIT_BREAKER_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Settings of the circuit breaker of pool connections.
+///
+/// A connection failing `failure_threshold` requests in a row is tripped: it's taken out of
+/// rotation, so requests go to other connections of the pool, and re-initialized with its
+/// stored params after `cooldown`. Re-initialization is retried every `cooldown` until it
+/// succeeds.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CircuitBreakerConfig {
+    pub failure_threshold: usize,
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        CircuitBreakerConfig {
+            failure_threshold: DEFAULT_CIRCUIT_BREAKER_FAILURE_THRESHOLD,
+            cooldown: DEFAULT_CIRCUIT_BREAKER_COOLDOWN,
+        }
+    }
+}
+
+/// Returns `true` for errors indicating that the connection itself is unhealthy,
+/// as opposed to errors of a particular request, e.g. a missing account.
+pub(crate) fn is_connection_failure(error: &TonClientError) -> bool {
+    matches!(error, TonClientError::TlError(_)) || error.is_retryable()
+}
+
+/// State of the circuit breaker of a single pool connection.
+#[derive(Debug, Default)]
+pub(crate) struct CircuitBreaker {
+    consecutive_failures: AtomicUsize,
+    open: AtomicBool,
+}
+
+impl CircuitBreaker {
+    /// Whether the connection is tripped and awaits re-initialization.
+    pub(crate) fn is_open(&self) -> bool {
+        self.open.load(Ordering::Acquire)
+    }
+
+    /// Records the outcome of a request, returns `true` if the breaker has just been tripped.
+    pub(crate) fn record(&self, error: Option<&TonClientError>, failure_threshold: usize) -> bool {
+        match error {
+            Some(e) if is_connection_failure(e) => {
+                let failures = self.consecutive_failures.fetch_add(1, Ordering::AcqRel) + 1;
+                failures >= failure_threshold && !self.open.swap(true, Ordering::AcqRel)
+            }
+            _ => {
+                self.consecutive_failures.store(0, Ordering::Release);
+                false
+            }
+        }
+    }
+
+    /// Puts the re-initialized connection back into rotation.
+    pub(crate) fn close(&self) {
+        self.consecutive_failures.store(0, Ordering::Release);
+        self.open.store(false, Ordering::Release);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CircuitBreaker;
+    use crate::client::TonClientError;
+
+    #[test]
+    fn test_circuit_breaker_trips_on_consecutive_failures() {
+        let breaker = CircuitBreaker::default();
+        let not_ready = TonClientError::TonlibError {
+            method: "test",
+            code: 651,
+            message: "LITE_SERVER_NOTREADY".to_string(),
+        };
+        let not_found = TonClientError::TonlibError {
+            method: "test",
+            code: 500,
+            message: "account not found".to_string(),
+        };
+        assert!(!breaker.record(Some(&not_ready), 2));
+        // Errors of a request don't count and reset the streak
+        assert!(!breaker.record(Some(&not_found), 2));
+        assert!(!breaker.record(Some(&not_ready), 2));
+        assert!(!breaker.is_open());
+        assert!(breaker.record(Some(&not_ready), 2));
+        assert!(breaker.is_open());
+        // Tripped only once
+        assert!(!breaker.record(Some(&not_ready), 2));
+
+        breaker.close();
+        assert!(!breaker.is_open());
+        assert!(!breaker.record(Some(&not_ready), 2));
+        assert!(!breaker.record(None, 2));
+        assert!(!breaker.record(Some(&not_ready), 2));
+    }
+}
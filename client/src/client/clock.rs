@@ -0,0 +1,124 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use tokio::sync::Notify;
+
+/// Source of monotonic time used by `TonConnection` to measure request durations
+/// and to wait for the rate limit.
+///
+/// Replacing the clock allows to test timing logic without waiting for wall-clock time.
+#[async_trait]
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+
+    /// Waits until `duration` passes according to this clock.
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await
+    }
+}
+
+/// Clock backed by `Instant::now()`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Clock that only moves forward when explicitly advanced.
+///
+/// `sleep` completes once the clock is advanced past the end of the sleep.
+#[derive(Debug)]
+pub struct ManualClock {
+    start: Instant,
+    elapsed: Mutex<Duration>,
+    advanced: Notify,
+}
+
+impl ManualClock {
+    pub fn new() -> ManualClock {
+        ManualClock {
+            start: Instant::now(),
+            elapsed: Mutex::new(Duration::ZERO),
+            advanced: Notify::new(),
+        }
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap() += duration;
+        self.advanced.notify_waiters();
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + *self.elapsed.lock().unwrap()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        let deadline = self.now() + duration;
+        loop {
+            // Registered before checking the time, so that an advance in between isn't missed
+            let advanced = self.advanced.notified();
+            if self.now() >= deadline {
+                return;
+            }
+            advanced.await;
+        }
+    }
+}
+
+lazy_static! {
+    pub static ref SYSTEM_CLOCK: Arc<dyn Clock> = Arc::new(SystemClock);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use super::{Clock, ManualClock};
+
+    #[test]
+    fn test_manual_clock_advance() {
+        let clock = ManualClock::new();
+        let start = clock.now();
+        assert_eq!(clock.now(), start);
+        clock.advance(Duration::from_secs(30));
+        clock.advance(Duration::from_millis(500));
+        assert_eq!(
+            clock.now().duration_since(start),
+            Duration::from_millis(30_500)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_manual_clock_sleep() {
+        let clock = Arc::new(ManualClock::new());
+        let sleeping = tokio::spawn({
+            let clock = clock.clone();
+            async move { clock.sleep(Duration::from_secs(10)).await }
+        });
+        tokio::task::yield_now().await;
+        clock.advance(Duration::from_secs(5));
+        tokio::task::yield_now().await;
+        assert!(!sleeping.is_finished());
+        clock.advance(Duration::from_secs(5));
+        tokio::time::timeout(Duration::from_secs(1), sleeping)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+}
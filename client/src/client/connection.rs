@@ -1,44 +1,112 @@
-use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::{Arc, Weak};
+use std::borrow::Cow;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
 use std::thread;
-use std::thread::JoinHandle;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use dashmap::DashMap;
-use tokio::sync::{broadcast, oneshot, Semaphore, SemaphorePermit};
+use futures::future::{self, join_all};
+use futures::{Stream, StreamExt};
+use serde_json::Value;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::oneshot::error::TryRecvError;
+use tokio::sync::{broadcast, mpsc, oneshot, Notify};
+use tonlib_core::cell::{ArcCell, BagOfCells, Cell, StateInit, TonCellError};
+use tonlib_core::message::{ExternalInMessage, TonMessage};
+use tonlib_core::TonAddress;
 
+use crate::client::archive_routing::probe_archive;
+use crate::client::priority::{PriorityPermit, PrioritySemaphore};
+use crate::client::rate_limiter::RateLimiter;
+use crate::client::trace::{enter_connection_span, RequestSpan};
 use crate::client::{
-    TonClientError, TonClientInterface, TonConnectionCallback, TonConnectionParams,
-    TonNotificationReceiver,
+    request_priority, Clock, ConnectionStats, ConnectionWarmup, HealthStatus, PriorityClient,
+    RequestPriority, TlTrafficDirection, TlTrafficRecord, TonClientError, TonClientInterface,
+    TonConnectionCallback, TonConnectionParams, TonNotificationReceiver, TonNotificationStream,
+    TonReliableNotificationReceiver, TrafficRecorder, SYSTEM_CLOCK,
 };
+use crate::config::{liteserver_count, select_liteservers};
 use crate::tl::{
-    BlockId, Config, KeyStoreType, Options, OptionsInfo, SmcRunResult, TlTonClient, TonFunction,
-    TonNotification, TonResult, TonResultDiscriminants, TvmStackEntry,
+    result_from_value, AccountAddress, BlockIdExt, Config, ExportedKey, InputKey, Key,
+    KeyStoreType, Options, OptionsInfo, QueryFees, QueryInfo, SmcRunResult, SyncState, TlError,
+    TlTonClient, TonFunction, TonNotification, TonResult, TonResultDiscriminants, TvmStackEntry,
 };
 use crate::types::TonMethodId;
 
 pub const DEFAULT_NOTIFICATION_QUEUE_LENGTH: usize = 10000;
 pub const DEFAULT_CONNECTION_CONCURRENCY_LIMIT: usize = 100;
 pub const DEFAULT_UPDATE_INIT_BLOCK: bool = true;
+pub const DEFAULT_REQUEST_REAP_INTERVAL: Duration = Duration::from_secs(10);
+pub const DEFAULT_RECONNECT_ERROR_THRESHOLD: usize = 10;
+pub const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+/// Maximum time `TonConnection::close` waits for tonlib to confirm closing the client.
+pub const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+/// Interval of retrying to send a notification to a full reliable subscription.
+/// Method of errors of raw requests, see `invoke_raw_json`, errors only take static methods.
+const RAW_REQUEST_METHOD: &str = "raw";
 
 struct RequestData {
-    method: &'static str,
+    /// Owned for raw requests, whose methods are given at runtime.
+    method: Cow<'static, str>,
     send_time: Instant,
     sender: oneshot::Sender<Result<TonResult, TonClientError>>,
+    span: RequestSpan,
+    /// Whether the result is passed as `TonResult::Raw`, see `invoke_raw_json`.
+    raw: bool,
 }
 
 type RequestMap = DashMap<u32, RequestData>;
 type TonNotificationSender = broadcast::Sender<Arc<TonNotification>>;
 
 struct Inner {
-    tl_client: TlTonClient,
+    tag: String,
+    /// Shared with the run loop, which destroys the client once it exits.
+    tl_client: Arc<RwLock<TlTonClient>>,
+    /// Set when the connection is dropped, makes the run loop exit.
+    /// Also set by the run loop once it has exited, e.g. after a panic.
+    closed: Arc<AtomicBool>,
+    /// Notified when `close` sets `closed`, wakes up `send_reliable` waiting for a subscriber.
+    close_notify: Notify,
+    /// Whether the last sync state reported by tonlib is in progress.
+    sync_in_progress: AtomicBool,
+    /// Function the connection was initialized with, re-sent after reconnecting.
+    init_function: Mutex<Option<TonFunction>>,
+    /// Number of times the tonlib client has been replaced by reconnecting.
+    generation: AtomicU64,
     counter: AtomicU32,
     request_map: RequestMap,
     notification_sender: TonNotificationSender,
+    /// Number of notifications overwritten in a full queue before a subscriber received them.
+    dropped_notifications: AtomicU64,
+    /// Senders of `subscribe_reliable`, which wait for free space instead of dropping.
+    reliable_senders: Mutex<Vec<mpsc::Sender<Arc<TonNotification>>>>,
     callback: Arc<dyn TonConnectionCallback>,
-    _notification_receiver: TonNotificationReceiver,
-    semaphore: Option<Semaphore>,
+    clock: Arc<dyn Clock>,
+    notification_queue_capacity: usize,
+    notification_queue_high_watermark: Option<usize>,
+    request_queue_high_watermark: Option<usize>,
+    /// Set once `request_queue_high_watermark` is reached, until a request is sent below it.
+    request_queue_above_watermark: AtomicBool,
+    /// Last sync state reported to `on_sync_state_changed`.
+    sync_state: Mutex<Option<SyncState>>,
+    request_timeout: Option<Duration>,
+    slow_request_threshold: Option<Duration>,
+    max_request_age: Option<Duration>,
+    request_reap_interval: Duration,
+    reconnect_error_threshold: Option<usize>,
+    stall_timeout: Option<Duration>,
+    concurrency_limit: usize,
+    semaphore: Option<PrioritySemaphore>,
+    rate_limiter: Option<RateLimiter>,
+    traffic_recorder: RwLock<Option<Arc<dyn TrafficRecorder>>>,
+}
+
+impl Inner {
+    fn traffic_recorder(&self) -> Option<Arc<dyn TrafficRecorder>> {
+        self.traffic_recorder.read().unwrap().clone()
+    }
 }
 
 pub struct TonConnection {
@@ -57,48 +125,121 @@ impl TonConnection {
         callback: Arc<dyn TonConnectionCallback>,
         params: &TonConnectionParams,
     ) -> Result<TonConnection, TonClientError> {
-        Self::new_joinable(callback, params).map(|r| r.0)
+        Self::new_joinable(callback, params, SYSTEM_CLOCK.clone()).map(|r| r.0)
+    }
+
+    /// Creates a new uninitialized TonConnection measuring request durations with `clock`.
+    ///
+    /// # Errors
+    ///
+    /// Returns error to capture any failure to create thread at system level
+    pub fn new_with_clock(
+        callback: Arc<dyn TonConnectionCallback>,
+        params: &TonConnectionParams,
+        clock: Arc<dyn Clock>,
+    ) -> Result<TonConnection, TonClientError> {
+        Self::new_joinable(callback, params, clock).map(|r| r.0)
     }
 
     pub fn tag(&self) -> &str {
-        self.inner.tl_client.get_tag()
+        self.inner.tag.as_str()
     }
 
-    /// Creates a new uninitialized TonConnection together with its `JoinHandle`.
+    /// Returns a handle of this connection sending requests with `priority`.
+    pub fn with_priority(&self, priority: RequestPriority) -> PriorityClient<TonConnection> {
+        PriorityClient::new(self.clone(), priority)
+    }
+
+    /// Creates a new uninitialized TonConnection together with the handle of its run loop.
     ///
     /// # Errors
     ///
-    /// Returns error to capture any failure to create thread at system level
+    /// Returns error to capture any failure to create thread at system level, or if
+    /// `run_loop_on_blocking_pool` is set outside of a tokio runtime
     pub(crate) fn new_joinable(
         callback: Arc<dyn TonConnectionCallback>,
         params: &TonConnectionParams,
-    ) -> Result<(TonConnection, JoinHandle<()>), TonClientError> {
+        clock: Arc<dyn Clock>,
+    ) -> Result<(TonConnection, RunLoopHandle), TonClientError> {
         let tag = format!(
             "ton-conn-{}",
             CONNECTION_COUNTER.fetch_add(1, Ordering::SeqCst)
         );
-        let (sender, receiver) =
+        // No receiver is retained here: otherwise the queue would never drain and
+        // `notification_queue_len` would be stuck at capacity.
+        let (sender, _) =
             broadcast::channel::<Arc<TonNotification>>(params.notification_queue_length);
         let concurrency_limit = params.concurrency_limit;
         let semaphore = if concurrency_limit != 0 {
-            Some(Semaphore::new(params.concurrency_limit))
+            Some(PrioritySemaphore::new(params.concurrency_limit))
         } else {
             None
         };
+        let rate_limiter = params.max_rps.map(|max_rps| {
+            let burst = params.rate_limit_burst.unwrap_or(max_rps);
+            RateLimiter::new(max_rps, burst, clock.now())
+        });
+        let traffic_recorder = params
+            .traffic_capture
+            .as_ref()
+            .map(|capture| capture.recorder())
+            .transpose()?;
+        if let Some(verbosity_level) = params.log_verbosity_level {
+            TlTonClient::set_log_verbosity_level(verbosity_level);
+        }
+        let tl_client = Arc::new(RwLock::new(TlTonClient::new(tag.as_str())));
+        let closed = Arc::new(AtomicBool::new(false));
         let inner = Inner {
-            tl_client: TlTonClient::new(tag.as_str()),
+            tag: tag.clone(),
+            tl_client: tl_client.clone(),
+            closed: closed.clone(),
+            close_notify: Notify::new(),
+            sync_in_progress: AtomicBool::new(false),
+            init_function: Mutex::new(None),
+            generation: AtomicU64::new(0),
             counter: AtomicU32::new(0),
             request_map: RequestMap::new(),
             notification_sender: sender,
+            dropped_notifications: AtomicU64::new(0),
+            reliable_senders: Mutex::new(vec![]),
             callback,
-            _notification_receiver: receiver,
+            clock,
+            notification_queue_capacity: params.notification_queue_length,
+            notification_queue_high_watermark: params.notification_queue_high_watermark,
+            request_queue_high_watermark: params.request_queue_high_watermark,
+            request_queue_above_watermark: AtomicBool::new(false),
+            sync_state: Mutex::new(None),
+            request_timeout: params.request_timeout,
+            slow_request_threshold: params.slow_request_threshold,
+            max_request_age: params.max_request_age,
+            request_reap_interval: params.request_reap_interval,
+            reconnect_error_threshold: if params.reconnect {
+                Some(params.reconnect_error_threshold)
+            } else {
+                None
+            },
+            stall_timeout: params.stall_timeout,
+            concurrency_limit,
             semaphore,
+            rate_limiter,
+            traffic_recorder: RwLock::new(traffic_recorder),
         };
         let inner_arc = Arc::new(inner);
         let inner_weak: Weak<Inner> = Arc::downgrade(&inner_arc);
-        let thread_builder = thread::Builder::new().name(tag.clone());
         let callback = inner_arc.callback.clone();
-        let join_handle = thread_builder.spawn(|| run_loop(tag, inner_weak, callback))?;
+        let join_handle = if params.run_loop_on_blocking_pool {
+            let handle = tokio::runtime::Handle::try_current().map_err(|e| {
+                TonClientError::InternalError(format!("Can't spawn blocking run loop: {}", e))
+            })?;
+            RunLoopHandle::Task(
+                handle.spawn_blocking(|| run_loop(tag, inner_weak, tl_client, closed, callback)),
+            )
+        } else {
+            let thread_builder = thread::Builder::new().name(tag.clone());
+            RunLoopHandle::Thread(
+                thread_builder.spawn(|| run_loop(tag, inner_weak, tl_client, closed, callback))?,
+            )
+        };
         let conn = TonConnection { inner: inner_arc };
         Ok((conn, join_handle))
     }
@@ -111,12 +252,88 @@ impl TonConnection {
         Self::connect_joinable(params, callback).await.map(|r| r.0)
     }
 
+    /// Creates a new initialized TonConnection measuring request durations with `clock`,
+    /// see `new_with_clock`.
+    pub async fn connect_with_clock(
+        params: &TonConnectionParams,
+        callback: Arc<dyn TonConnectionCallback>,
+        clock: Arc<dyn Clock>,
+    ) -> Result<TonConnection, TonClientError> {
+        Self::connect_joinable_with_clock(params, callback, clock)
+            .await
+            .map(|r| r.0)
+    }
+
     /// Creates a new initialized TonConnection
+    ///
+    /// With `connect_probe_attempts` set, the connection is verified with `health_check`
+    /// and re-created on the next liteserver on failure. The error of the last attempt
+    /// is returned once all attempts fail.
     pub async fn connect_joinable(
         params: &TonConnectionParams,
         callback: Arc<dyn TonConnectionCallback>,
-    ) -> Result<(TonConnection, JoinHandle<()>), TonClientError> {
-        let (conn, join_handle) = Self::new_joinable(callback, params)?;
+    ) -> Result<(TonConnection, RunLoopHandle), TonClientError> {
+        Self::connect_joinable_with_clock(params, callback, SYSTEM_CLOCK.clone()).await
+    }
+
+    /// Same as `connect_joinable`, measuring request durations with `clock`.
+    pub async fn connect_joinable_with_clock(
+        params: &TonConnectionParams,
+        callback: Arc<dyn TonConnectionCallback>,
+        clock: Arc<dyn Clock>,
+    ) -> Result<(TonConnection, RunLoopHandle), TonClientError> {
+        let attempts = match params.connect_probe_attempts {
+            Some(attempts) => attempts.max(1),
+            None => {
+                let indices = params.liteserver_indices.as_deref();
+                let (conn, join_handle) =
+                    Self::connect_to(params, indices, callback, clock).await?;
+                conn.inner.callback.on_connection_established(conn.tag());
+                return Ok((conn, join_handle));
+            }
+        };
+        let candidates: Vec<usize> = match &params.liteserver_indices {
+            Some(indices) => indices.clone(),
+            None => (0..liteserver_count(&params.config)?).collect(),
+        };
+        if candidates.is_empty() {
+            return Err(TonClientError::InternalError(
+                "No liteservers selected".to_string(),
+            ));
+        }
+        let mut last_error = None;
+        for attempt in 0..attempts {
+            let index = candidates[attempt % candidates.len()];
+            let result =
+                Self::connect_to(params, Some(&[index]), callback.clone(), clock.clone()).await;
+            let error = match result {
+                Ok((conn, join_handle)) => match conn.health_check().await {
+                    Ok(_) => {
+                        conn.inner.callback.on_connection_established(conn.tag());
+                        return Ok((conn, join_handle));
+                    }
+                    Err(e) => e,
+                },
+                Err(e) => e,
+            };
+            log::info!(
+                "Connection attempt {} to liteserver {} failed: {}",
+                attempt + 1,
+                index,
+                error
+            );
+            last_error = Some(error);
+        }
+        Err(last_error.unwrap()) // At least one attempt is made
+    }
+
+    async fn connect_to(
+        params: &TonConnectionParams,
+        liteserver_indices: Option<&[usize]>,
+        callback: Arc<dyn TonConnectionCallback>,
+        clock: Arc<dyn Clock>,
+    ) -> Result<(TonConnection, RunLoopHandle), TonClientError> {
+        let (conn, join_handle) = Self::new_joinable(callback, params, clock)?;
         let keystore_type = if let Some(directory) = &params.keystore_dir {
             KeyStoreType::Directory {
                 directory: directory.clone(),
@@ -124,9 +341,13 @@ impl TonConnection {
         } else {
             KeyStoreType::InMemory
         };
+        let config = match liteserver_indices {
+            Some(indices) => select_liteservers(&params.config, indices)?,
+            None => params.config.clone(),
+        };
         let _ = conn
             .init(
-                params.config.as_str(),
+                config.as_str(),
                 params.blockchain_name.as_deref(),
                 params.use_callbacks_for_network,
                 params.ignore_cache,
@@ -139,18 +360,14 @@ impl TonConnection {
     pub(crate) async fn connect_archive(
         params: &TonConnectionParams,
         callback: Arc<dyn TonConnectionCallback>,
-    ) -> Result<(TonConnection, JoinHandle<()>), TonClientError> {
+        clock: Arc<dyn Clock>,
+    ) -> Result<(TonConnection, RunLoopHandle), TonClientError> {
         // connect to other node until it will be able to fetch the very first block
         loop {
-            let (conn, join_handle) = Self::connect_joinable(params, callback.clone()).await?;
-            let info = BlockId {
-                workchain: -1,
-                shard: i64::MIN,
-                seqno: 1,
-            };
+            let (conn, join_handle) =
+                Self::connect_joinable_with_clock(params, callback.clone(), clock.clone()).await?;
             conn.sync().await?;
-            let r = conn.lookup_block(1, &info, 0, 0).await;
-            if r.is_ok() {
+            if matches!(probe_archive(&conn).await, Ok(true)) {
                 break Ok((conn, join_handle));
             } else {
                 log::info!("Dropping connection to non-archive node");
@@ -161,11 +378,12 @@ impl TonConnection {
     pub(crate) async fn connect_healthy(
         params: &TonConnectionParams,
         callback: Arc<dyn TonConnectionCallback>,
-    ) -> Result<(TonConnection, JoinHandle<()>), TonClientError> {
+        clock: Arc<dyn Clock>,
+    ) -> Result<(TonConnection, RunLoopHandle), TonClientError> {
         // connect to other node until it will be able to fetch the very first block
         loop {
             let (conn, join_handle) =
-                TonConnection::connect_joinable(params, callback.clone()).await?;
+                Self::connect_joinable_with_clock(params, callback.clone(), clock.clone()).await?;
             let info_result = conn.get_masterchain_info().await;
             match info_result {
                 Ok((_, info)) => {
@@ -203,9 +421,13 @@ impl TonConnection {
                 keystore_type,
             },
         };
+        *self.inner.init_function.lock().unwrap() = Some(func.clone());
         let result = self.invoke(&func).await?;
         match result {
-            TonResult::OptionsInfo(options_info) => Ok(options_info),
+            TonResult::OptionsInfo(options_info) => {
+                self.inner.callback.on_init_completed(self.tag(), false);
+                Ok(options_info)
+            }
             r => Err(TonClientError::unexpected_ton_result(
                 TonResultDiscriminants::OptionsInfo,
                 r,
@@ -213,10 +435,130 @@ impl TonConnection {
         }
     }
 
+    /// Fetches account states and runs get-methods listed in `warmup` to fill tonlib caches.
+    ///
+    /// Failures are logged and otherwise ignored.
+    pub async fn warm_up(&self, warmup: &ConnectionWarmup) {
+        for address in warmup.accounts.iter() {
+            if let Err(e) = self.get_raw_account_state(address).await {
+                log::warn!("[{}] Warmup of {} failed: {}", self.tag(), address, e);
+            }
+        }
+        for (address, method) in warmup.get_methods.iter() {
+            let method_id = TonMethodId::from(method.clone());
+            let result = match self.smc_load(address).await {
+                Ok(state) => {
+                    let result = self.smc_run_get_method(state.id, &method_id, &[]).await;
+                    let _ = state.forget().await;
+                    result.map(|_| ())
+                }
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                log::warn!(
+                    "[{}] Warmup of {} {} failed: {}",
+                    self.tag(),
+                    address,
+                    method_id,
+                    e
+                );
+            }
+        }
+    }
+
     pub fn subscribe(&self) -> TonNotificationReceiver {
         self.inner.notification_sender.subscribe()
     }
 
+    /// Same as `subscribe`, but returns a stream that skips notifications missed by lagging.
+    pub fn subscribe_stream(&self) -> TonNotificationStream {
+        self.subscribe_filtered(|_| true)
+    }
+
+    /// Same as `subscribe_stream`, but yields only notifications accepted by `filter`,
+    /// e.g. `TonNotification::is_sync_done`.
+    ///
+    /// See `TonNotificationStream` for the implications of an expensive filter.
+    pub fn subscribe_filtered<F>(&self, filter: F) -> TonNotificationStream
+    where
+        F: Fn(&TonNotification) -> bool + Send + Sync + 'static,
+    {
+        let tag = self.tag().to_string();
+        let callback = self.inner.callback.clone();
+        TonNotificationStream::new_with_lag_handler(self.subscribe(), filter, move |skipped| {
+            callback.on_notification_lagged(&tag, skipped)
+        })
+    }
+
+    /// Returns a stream of sync states reported by tonlib while it catches up with
+    /// the masterchain, see `wait_until_synced`.
+    pub fn subscribe_sync_state(&self) -> impl Stream<Item = SyncState> + Send + Unpin {
+        self.subscribe_filtered(TonNotification::is_sync_state)
+            .filter_map(|n| future::ready(n.sync_state().cloned()))
+    }
+
+    /// Waits until tonlib has caught up with the masterchain, e.g. before sending the first
+    /// requests of a fresh connection, which would otherwise wait for the sync or fail.
+    ///
+    /// Returns the last masterchain block once synced, fails with `TonClientError::Timeout`
+    /// if the sync doesn't complete within `timeout`. Progress is reported by
+    /// `subscribe_sync_state`.
+    pub async fn wait_until_synced(&self, timeout: Duration) -> Result<BlockIdExt, TonClientError> {
+        let start = self.inner.clock.now();
+        let result = self
+            .invoke_cancellable(&TonFunction::Sync {}, tokio::time::sleep(timeout))
+            .await;
+        match result {
+            Ok(TonResult::BlockIdExt(block_id)) => {
+                self.inner.sync_in_progress.store(false, Ordering::Relaxed);
+                Ok(block_id)
+            }
+            Ok(r) => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::BlockIdExt,
+                r,
+            )),
+            Err(TonClientError::Cancelled { method }) => Err(TonClientError::Timeout {
+                method,
+                elapsed: self.inner.clock.now().duration_since(start),
+            }),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Subscribes to notifications with backpressure instead of dropping them.
+    ///
+    /// Once `capacity` notifications are queued for the subscriber, the run loop waits until
+    /// the subscriber receives one, which also delays results of all requests of the connection.
+    /// Thus the subscriber has to receive notifications steadily, or drop the receiver.
+    ///
+    /// Fails with `TonClientError::InternalError` if `capacity` is zero.
+    pub fn subscribe_reliable(
+        &self,
+        capacity: usize,
+    ) -> Result<TonReliableNotificationReceiver, TonClientError> {
+        if capacity == 0 {
+            return Err(TonClientError::InternalError(
+                "Capacity of a reliable subscription must be positive".to_string(),
+            ));
+        }
+        let (sender, receiver) = mpsc::channel(capacity);
+        self.inner.reliable_senders.lock().unwrap().push(sender);
+        Ok(receiver)
+    }
+
+    /// Returns the number of notifications not yet received by the slowest subscriber.
+    ///
+    /// Once this value reaches `notification_queue_length`, the oldest notifications get
+    /// overwritten and lagging subscribers observe `RecvError::Lagged`.
+    pub fn notification_queue_len(&self) -> usize {
+        self.inner.notification_sender.len()
+    }
+
+    /// Returns the capacity of the notification queue.
+    pub fn notification_queue_capacity(&self) -> usize {
+        self.inner.notification_queue_capacity
+    }
+
     pub async fn smc_run_get_method(
         &self,
         id: i64,
@@ -238,64 +580,616 @@ impl TonConnection {
         }
     }
 
-    async fn limit_rate(&self) -> Result<Option<SemaphorePermit>, TonClientError> {
-        Ok(if let Some(semaphore) = &self.inner.semaphore {
-            Some(
-                semaphore
-                    .acquire()
-                    .await
-                    .map_err(|_| TonClientError::InternalError("AcquireError".to_string()))?,
-            )
-        } else {
-            None
-        })
+    /// Creates a query sending an external message with `body` to `destination`.
+    ///
+    /// Empty `init_code` and `init_data` create a query without state init. The query is
+    /// stored by the tonlib client of this connection, so it can be used only with it,
+    /// and should be released with `query_forget`.
+    pub async fn raw_create_query(
+        &self,
+        destination: &TonAddress,
+        init_code: &[u8],
+        init_data: &[u8],
+        body: &[u8],
+    ) -> Result<QueryInfo, TonClientError> {
+        let func = TonFunction::RawCreateQuery {
+            destination: AccountAddress {
+                account_address: destination.to_hex(),
+            },
+            init_code: init_code.to_vec(),
+            init_data: init_data.to_vec(),
+            body: body.to_vec(),
+        };
+        let result = self.invoke(&func).await?;
+        match result {
+            TonResult::QueryInfo(result) => Ok(result),
+            r => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::QueryInfo,
+                r,
+            )),
+        }
     }
-}
 
-#[async_trait]
-impl TonClientInterface for TonConnection {
-    async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
-        Ok(self.clone())
+    /// Estimates fees of a query created by `raw_create_query`.
+    ///
+    /// * `ignore_chksig`: Skip signature checks, e.g. for bodies signed with a dummy key.
+    pub async fn estimate_fee(
+        &self,
+        query_id: i64,
+        ignore_chksig: bool,
+    ) -> Result<QueryFees, TonClientError> {
+        let func = TonFunction::QueryEstimateFees {
+            id: query_id,
+            ignore_chksig,
+        };
+        let result = self.invoke(&func).await?;
+        match result {
+            TonResult::QueryFees(result) => Ok(result),
+            r => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::QueryFees,
+                r,
+            )),
+        }
     }
 
-    async fn invoke_on_connection(
+    /// Sends the external message of a query created by `raw_create_query`.
+    ///
+    /// Errors reporting that the message was rejected are returned as
+    /// `TonClientError::ExternalMessageRejected`.
+    pub async fn query_send(&self, query_id: i64) -> Result<(), TonClientError> {
+        let func = TonFunction::QuerySend { id: query_id };
+        self.invoke(&func)
+            .await
+            .map_err(TonClientError::into_external_message_error)?
+            .expect_ok()
+    }
+
+    pub async fn query_forget(&self, query_id: i64) -> Result<(), TonClientError> {
+        let func = TonFunction::QueryForget { id: query_id };
+        self.invoke(&func).await?.expect_ok()
+    }
+
+    /// Estimates fees of an external message with `body` sent to `destination`.
+    ///
+    /// Creates a temporary query with `raw_create_query` and forgets it afterwards.
+    pub async fn estimate_message_fee(
+        &self,
+        destination: &TonAddress,
+        init_code: &[u8],
+        init_data: &[u8],
+        body: &[u8],
+        ignore_chksig: bool,
+    ) -> Result<QueryFees, TonClientError> {
+        let query = self
+            .raw_create_query(destination, init_code, init_data, body)
+            .await?;
+        let fees = self.estimate_fee(query.id, ignore_chksig).await;
+        if let Err(e) = self.query_forget(query.id).await {
+            log::warn!(
+                "[{}] Failed to forget query {}: {}",
+                self.tag(),
+                query.id,
+                e
+            );
+        }
+        fees
+    }
+
+    /// Estimates fees of a prepared external message, e.g. created by
+    /// `TonWallet::create_external_message`, before broadcasting it.
+    ///
+    /// The destination, state init and body are taken from the message, see
+    /// `estimate_message_fee`.
+    pub async fn estimate_fees(
+        &self,
+        message: &Cell,
+        ignore_chksig: bool,
+    ) -> Result<QueryFees, TonClientError> {
+        let message = ExternalInMessage::parse(message)
+            .map_err(|e| TonClientError::InternalError(format!("Invalid message: {}", e)))?;
+        let (init_code, init_data) = match &message.state_init {
+            Some(state_init) => {
+                let state_init = StateInit::try_from(state_init.as_ref())?;
+                (to_boc(state_init.code)?, to_boc(state_init.data)?)
+            }
+            None => (vec![], vec![]),
+        };
+        let body = to_boc(Some(message.body))?;
+        self.estimate_message_fee(&message.dest, &init_code, &init_data, &body, ignore_chksig)
+            .await
+    }
+
+    /// Creates a new key with a random mnemonic and stores it in the keystore of the connection,
+    /// encrypted with `local_password`.
+    pub async fn create_new_key(
+        &self,
+        local_password: &[u8],
+        mnemonic_password: &[u8],
+        random_extra_seed: &[u8],
+    ) -> Result<Key, TonClientError> {
+        let func = TonFunction::CreateNewKey {
+            local_password: local_password.to_vec(),
+            mnemonic_password: mnemonic_password.to_vec(),
+            random_extra_seed: random_extra_seed.to_vec(),
+        };
+        self.invoke_key_function(&func).await
+    }
+
+    /// Imports the key derived from a mnemonic without password into the keystore
+    /// of the connection, encrypted with `local_password`.
+    ///
+    /// The key is kept only in memory or also written to the keystore directory, depending on
+    /// the `KeyStoreType` the connection was initialized with. Note that trace logging
+    /// of tonlib requests includes the mnemonic.
+    pub async fn import_key_from_mnemonic(
+        &self,
+        words: &[String],
+        local_password: &[u8],
+    ) -> Result<Key, TonClientError> {
+        let func = TonFunction::ImportKey {
+            local_password: local_password.to_vec(),
+            mnemonic_password: vec![],
+            exported_key: ExportedKey {
+                word_list: words.to_vec(),
+            },
+        };
+        self.invoke_key_function(&func).await
+    }
+
+    /// Returns the mnemonic of a key in the keystore of the connection.
+    pub async fn export_key(
+        &self,
+        key: &Key,
+        local_password: &[u8],
+    ) -> Result<Vec<String>, TonClientError> {
+        let func = TonFunction::ExportKey {
+            input_key: InputKey::Regular {
+                key: key.clone(),
+                local_password: local_password.to_vec(),
+            },
+        };
+        let result = self.invoke(&func).await?;
+        match result {
+            TonResult::ExportedKey(exported_key) => Ok(exported_key.word_list),
+            r => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::ExportedKey,
+                r,
+            )),
+        }
+    }
+
+    /// Removes a key from the keystore of the connection.
+    pub async fn delete_key(&self, key: &Key) -> Result<(), TonClientError> {
+        let func = TonFunction::DeleteKey { key: key.clone() };
+        self.invoke(&func).await?.expect_ok()
+    }
+
+    async fn invoke_key_function(&self, func: &TonFunction) -> Result<Key, TonClientError> {
+        let result = self.invoke(func).await?;
+        match result {
+            TonResult::Key(key) => Ok(key),
+            r => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::Key,
+                r,
+            )),
+        }
+    }
+
+    /// Sends all `functions` before awaiting any result and returns the results in input order.
+    ///
+    /// The batch takes one concurrency permit per function, but never more than
+    /// `concurrency_limit`, so a batch larger than the limit doesn't wait forever.
+    pub async fn invoke_batch(
+        &self,
+        functions: &[TonFunction],
+    ) -> Vec<Result<TonResult, TonClientError>> {
+        let permits = functions.len().min(self.inner.concurrency_limit) as u32;
+        // take the semaphore to limit number of simultaneous invokes being processed
+        let _permit = match self.limit_rate_many(permits, functions.len() as u32).await {
+            Ok(permit) => permit,
+            Err(e) => {
+                return functions
+                    .iter()
+                    .map(|_| Err(TonClientError::InternalError(e.to_string())))
+                    .collect()
+            }
+        };
+        let requests: Vec<_> = functions.iter().map(|f| self.send_request(f)).collect();
+        let futures = requests
+            .into_iter()
+            .map(|(request_id, rx)| self.await_result(request_id, rx));
+        join_all(futures).await
+    }
+
+    /// Invokes `function`, abandoning the request once `cancel` completes,
+    /// e.g. `CancellationToken::cancelled()` of tokio-util or a `oneshot::Receiver`.
+    ///
+    /// On cancellation the request is removed right away, `on_cancelled_invoke` gets called
+    /// and `TonClientError::Cancelled` is returned. A result produced by tonlib afterwards
+    /// is discarded. If the result arrives at the same time as the cancellation, the result wins.
+    pub async fn invoke_cancellable<C>(
         &self,
         function: &TonFunction,
-    ) -> Result<(TonConnection, TonResult), TonClientError> {
-        self.limit_rate().await?; // take the semaphore to limit number of simultaneous invokes being processed
+        cancel: C,
+    ) -> Result<TonResult, TonClientError>
+    where
+        C: Future<Output = ()>,
+    {
+        let _permit = self.limit_rate().await?; // take the semaphore to limit number of simultaneous invokes being processed
+        let method: &'static str = function.into();
+        let (cnt, rx) = self.send_request(function);
+        tokio::select! {
+            biased;
+            result = self.await_result(cnt, rx) => result,
+            // The request is removed once the future awaiting its result is dropped
+            _ = cancel => Err(TonClientError::Cancelled { method }),
+        }
+    }
+
+    /// Invokes `function` waiting for its result for at most `timeout`, overriding
+    /// `TonConnectionParams::request_timeout` for this request.
+    ///
+    /// Fails with `TonClientError::Timeout` once `timeout` elapses, the result produced by
+    /// tonlib afterwards is discarded.
+    pub async fn invoke_with_timeout(
+        &self,
+        function: &TonFunction,
+        timeout: Duration,
+    ) -> Result<TonResult, TonClientError> {
+        let _permit = self.limit_rate().await?; // take the semaphore to limit number of simultaneous invokes being processed
+        let (cnt, rx) = self.send_request(function);
+        self.await_result_with_timeout(cnt, rx, Some(timeout)).await
+    }
+
+    /// Invokes a tonlib function not covered by `TonFunction` yet, e.g. one added in a newer
+    /// tonlib, passing `params` as its fields and returning the received JSON object.
+    ///
+    /// `params` must be a JSON object, or `null` for a function without fields. The request is
+    /// limited, correlated and timed out like any other, an error returned by tonlib fails with
+    /// `TonClientError::TonlibError`. Errors report the method as `raw`, callbacks get `method`.
+    pub async fn invoke_raw_json(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, TonClientError> {
+        let _permit = self.limit_rate().await?; // take the semaphore to limit number of simultaneous invokes being processed
+        let (cnt, rx) = self.send_raw_request(method, &params);
+        match self.await_result(cnt, rx).await? {
+            TonResult::Raw(json) => Ok(serde_json::from_str(&json).map_err(TlError::from)?),
+            r => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::Raw,
+                r,
+            )),
+        }
+    }
+
+    /// Checks that the connection is answered by the network, e.g. for readiness probes.
+    ///
+    /// Requests the masterchain info and fails with `TonClientError::Timeout` if no result
+    /// is received within `HEALTH_CHECK_TIMEOUT`. A connection whose run loop has exited,
+    /// e.g. after a panic in a callback, fails right away with
+    /// `TonClientError::ConnectionClosed`.
+    pub async fn health_check(&self) -> Result<HealthStatus, TonClientError> {
+        let function = TonFunction::BlocksGetMasterchainInfo {};
+        let method: &'static str = (&function).into();
+        if self.is_closed() {
+            return Err(TonClientError::ConnectionClosed { method });
+        }
+        let start = self.inner.clock.now();
+        let result = self
+            .invoke_cancellable(&function, tokio::time::sleep(HEALTH_CHECK_TIMEOUT))
+            .await;
+        let latency = self.inner.clock.now().duration_since(start);
+        match result {
+            Ok(TonResult::BlocksMasterchainInfo(info)) => Ok(HealthStatus {
+                synced: !self.inner.sync_in_progress.load(Ordering::Relaxed),
+                masterchain_seqno: info.last.seqno,
+                latency,
+            }),
+            Ok(r) => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::BlocksMasterchainInfo,
+                r,
+            )),
+            Err(TonClientError::Cancelled { method }) => Err(TonClientError::Timeout {
+                method,
+                elapsed: latency,
+            }),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Closes the connection for all its clones at once.
+    ///
+    /// Tonlib is asked to close the client, bypassing the concurrency and rate limits so that
+    /// closing isn't held up by unanswered requests, then requests in flight are completed with
+    /// `TonClientError::ConnectionClosed` and the run loop exits shortly after, which can be
+    /// awaited with the `RunLoopHandle` returned by `connect_joinable`. Requests made after
+    /// closing fail with `TonClientError::ConnectionClosed` right away.
+    pub async fn close(&self) {
+        if self.is_closed() {
+            return;
+        }
+        let (cnt, rx) = self.send_request(&TonFunction::Close {});
+        if let Err(e) = self
+            .await_result_with_timeout(cnt, rx, Some(CLOSE_TIMEOUT))
+            .await
+        {
+            log::debug!("[{}] Error closing tonlib client: {}", self.tag(), e);
+        }
+        self.inner.closed.store(true, Ordering::Release);
+        self.inner.close_notify.notify_waiters();
+        fail_in_flight_requests(&self.inner.request_map, |method| {
+            TonClientError::ConnectionClosed { method }
+        });
+        log::info!("[{}] Connection closed", self.tag());
+    }
+
+    /// Checks if the connection has been closed with `close` or its run loop has exited.
+    pub fn is_closed(&self) -> bool {
+        self.inner.closed.load(Ordering::Acquire)
+    }
+
+    /// Returns the number of times the connection has reconnected, replacing its tonlib client.
+    ///
+    /// State kept by tonlib, e.g. contracts loaded by `smc.load`, is lost on reconnecting and
+    /// their ids are reused by the new client, so they're only valid within one generation.
+    pub fn generation(&self) -> u64 {
+        self.inner.generation.load(Ordering::Acquire)
+    }
+
+    /// Returns the number of requests awaiting a result from tonlib, e.g. to pick the least
+    /// busy of several connections.
+    pub fn in_flight(&self) -> usize {
+        self.inner.request_map.len()
+    }
+
+    /// Returns a snapshot of request counters.
+    pub fn stats(&self) -> ConnectionStats {
+        ConnectionStats {
+            tag: self.inner.tag.clone(),
+            in_flight: self.in_flight(),
+            total_invocations: self.inner.counter.load(Ordering::Relaxed),
+            dropped_notifications: self.inner.dropped_notifications.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Replaces the recorder of the TL traffic, e.g. to start capturing on a live connection,
+    /// `None` stops capturing. See `TonConnectionParams::traffic_capture`.
+    pub fn set_traffic_recorder(&self, recorder: Option<Arc<dyn TrafficRecorder>>) {
+        *self.inner.traffic_recorder.write().unwrap() = recorder;
+    }
+
+    /// Returns up to `count` latest records of the TL traffic, oldest first, e.g. to log them
+    /// once a request fails.
+    ///
+    /// Records are only kept by recorders such as `TrafficCapture::RingBuffer`, otherwise
+    /// nothing is returned.
+    ///
+    /// ```ignore
+    /// if conn.get_masterchain_info().await.is_err() {
+    ///     for record in conn.captured_traffic(20) {
+    ///         log::warn!("{}", serde_json::to_string(&record)?);
+    ///     }
+    /// }
+    /// ```
+    pub fn captured_traffic(&self, count: usize) -> Vec<TlTrafficRecord> {
+        match self.inner.traffic_recorder() {
+            Some(recorder) => recorder.latest(count),
+            None => vec![],
+        }
+    }
+
+    async fn limit_rate(&self) -> Result<Option<PriorityPermit<'_>>, TonClientError> {
+        self.limit_rate_many(1, 1).await
+    }
+
+    /// Waits until `requests` requests may be sent according to `max_rps`, then takes
+    /// `permits` permits of the concurrency limit, which are held until the returned
+    /// permit is dropped.
+    ///
+    /// Permits are granted in the order of `request_priority` of the current task.
+    async fn limit_rate_many(
+        &self,
+        permits: u32,
+        requests: u32,
+    ) -> Result<Option<PriorityPermit<'_>>, TonClientError> {
+        if let Some(rate_limiter) = &self.inner.rate_limiter {
+            rate_limiter
+                .acquire(requests, self.inner.clock.as_ref())
+                .await;
+        }
+        Ok(match &self.inner.semaphore {
+            Some(semaphore) => Some(semaphore.acquire_many(permits, request_priority()).await),
+            None => None,
+        })
+    }
+
+    /// Registers the request and sends `function` to tonlib.
+    ///
+    /// A failure to send is reported through the returned receiver.
+    fn send_request(
+        &self,
+        function: &TonFunction,
+    ) -> (u32, oneshot::Receiver<Result<TonResult, TonClientError>>) {
+        let method: &'static str = function.into();
+        self.register_and_send(
+            method.into(),
+            false,
+            |cnt| self.inner.callback.on_invoke(self.tag(), cnt, function),
+            || serde_json::to_value(function).unwrap_or_default(),
+            |tl_client, extra| tl_client.send(function, extra),
+        )
+    }
+
+    /// Registers the request and sends raw `method` with `params` to tonlib.
+    fn send_raw_request(
+        &self,
+        method: &str,
+        params: &Value,
+    ) -> (u32, oneshot::Receiver<Result<TonResult, TonClientError>>) {
+        self.register_and_send(
+            Cow::Owned(method.to_string()),
+            true,
+            |cnt| {
+                self.inner
+                    .callback
+                    .on_invoke_raw(self.tag(), cnt, method, params)
+            },
+            || {
+                let mut request = params.as_object().cloned().unwrap_or_default();
+                request.insert("@type".to_string(), Value::from(method));
+                Value::Object(request)
+            },
+            |tl_client, extra| tl_client.send_raw(method, params, extra),
+        )
+    }
+
+    /// Registers a request of `method`, calls `on_invoke` with its id and sends it with `send`.
+    ///
+    /// `request` returns the JSON of the request, it's only called if the traffic is captured.
+    fn register_and_send<I, R, S>(
+        &self,
+        method: Cow<'static, str>,
+        raw: bool,
+        on_invoke: I,
+        request: R,
+        send: S,
+    ) -> (u32, oneshot::Receiver<Result<TonResult, TonClientError>>)
+    where
+        I: FnOnce(u32),
+        R: FnOnce() -> Value,
+        S: FnOnce(&TlTonClient, &str) -> Result<(), TlError>,
+    {
         let cnt = self.inner.counter.fetch_add(1, Ordering::SeqCst);
         let extra = cnt.to_string();
         let (tx, rx) = oneshot::channel::<Result<TonResult, TonClientError>>();
+        if self.is_closed() {
+            let method = error_method(&method);
+            let _ = tx.send(Err(TonClientError::ConnectionClosed { method }));
+            return (cnt, rx);
+        }
+        // Recorded before sending, so that the result can't be recorded first
+        if let Some(recorder) = self.inner.traffic_recorder() {
+            let record = TlTrafficRecord::new(
+                self.tag(),
+                TlTrafficDirection::Outgoing,
+                Some(cnt),
+                Some(&method),
+                request(),
+            );
+            recorder.record(&record);
+        }
+        let span = RequestSpan::new(self.tag(), cnt, &method);
+        span.in_scope(|| on_invoke(cnt));
         let data = RequestData {
-            method: function.into(),
-            send_time: Instant::now(),
+            method,
+            send_time: self.inner.clock.now(),
             sender: tx,
+            span,
+            raw,
         };
         self.inner.request_map.insert(cnt, data);
-        self.inner
-            .callback
-            .on_invoke(self.inner.tl_client.get_tag(), cnt, function);
+        if let Some(high_watermark) = self.inner.request_queue_high_watermark {
+            let pending = self.inner.request_map.len();
+            let above_watermark = &self.inner.request_queue_above_watermark;
+            if pending < high_watermark {
+                above_watermark.store(false, Ordering::Relaxed);
+            } else if !above_watermark.swap(true, Ordering::Relaxed) {
+                self.inner
+                    .callback
+                    .on_request_queue_high_watermark(self.tag(), pending);
+            }
+        }
 
-        let res = self.inner.tl_client.send(function, extra.as_str());
+        let res = send(&self.inner.tl_client.read().unwrap(), extra.as_str());
         if let Err(e) = res {
             let (_, data) = self.inner.request_map.remove(&cnt).unwrap();
-            let tag = self.inner.tl_client.get_tag();
-            let duration = Instant::now().duration_since(data.send_time);
+            let tag = self.tag();
+            let duration = self.inner.clock.now().duration_since(data.send_time);
             let res = Err(TonClientError::TlError(e));
-            self.inner
-                .callback
-                .on_invoke_result(tag, cnt, data.method, &duration, &res);
+            data.span.record_result(&duration, &res);
+            data.span.in_scope(|| {
+                self.inner
+                    .callback
+                    .on_invoke_result(tag, cnt, &data.method, &duration, &res)
+            });
             data.sender.send(res).unwrap(); // Send should always succeed, so something went terribly wrong
         }
-        let maybe_result = rx.await;
-        let result = match maybe_result {
-            Ok(result) => result,
-            Err(_) => {
-                return Err(TonClientError::InternalError(
-                    "Sender dropped without sending".to_string(),
-                ));
-            }
+        (cnt, rx)
+    }
+
+    /// Awaits the result of a request sent with `send_request`, respecting `request_timeout`.
+    async fn await_result(
+        &self,
+        cnt: u32,
+        rx: oneshot::Receiver<Result<TonResult, TonClientError>>,
+    ) -> Result<TonResult, TonClientError> {
+        self.await_result_with_timeout(cnt, rx, self.inner.request_timeout)
+            .await
+    }
+
+    /// Awaits the result of a request sent with `send_request` for at most `timeout`.
+    ///
+    /// If the returned future is dropped before completion, the request is removed right away
+    /// and `on_cancelled_invoke` gets called.
+    async fn await_result_with_timeout(
+        &self,
+        cnt: u32,
+        mut rx: oneshot::Receiver<Result<TonResult, TonClientError>>,
+        timeout: Option<Duration>,
+    ) -> Result<TonResult, TonClientError> {
+        let _abandoned = AbandonedRequestGuard {
+            conn: self,
+            request_id: cnt,
         };
+        let maybe_result = match timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, &mut rx).await {
+                Ok(r) => r,
+                Err(_) => match self.inner.request_map.remove(&cnt) {
+                    Some((_, data)) => {
+                        let tag = self.tag();
+                        let elapsed = self.inner.clock.now().duration_since(data.send_time);
+                        let error = TonClientError::Timeout {
+                            method: error_method(&data.method),
+                            elapsed,
+                        };
+                        data.span.record_duration(&elapsed);
+                        data.span.record_error(&error);
+                        data.span.in_scope(|| {
+                            self.inner
+                                .callback
+                                .on_invoke_timeout(tag, cnt, &data.method, &elapsed)
+                        });
+                        return Err(error);
+                    }
+                    // The result has been received concurrently with the timeout
+                    None => rx.await,
+                },
+            },
+            None => rx.await,
+        };
+        match maybe_result {
+            Ok(result) => result,
+            Err(_) => Err(TonClientError::InternalError(
+                "Sender dropped without sending".to_string(),
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl TonClientInterface for TonConnection {
+    async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+        Ok(self.clone())
+    }
+
+    async fn invoke_on_connection(
+        &self,
+        function: &TonFunction,
+    ) -> Result<(TonConnection, TonResult), TonClientError> {
+        let _permit = self.limit_rate().await?; // take the semaphore to limit number of simultaneous invokes being processed
+        let (cnt, rx) = self.send_request(function);
+        let result = self.await_result(cnt, rx).await;
         result.map(|r| (self.clone(), r))
     }
 }
@@ -307,27 +1201,176 @@ impl Clone for TonConnection {
     }
 }
 
+/// Removes a request whose result is no longer awaited, e.g. because the future awaiting it
+/// has been dropped, so that it doesn't stay in the request map until tonlib answers.
+struct AbandonedRequestGuard<'a> {
+    conn: &'a TonConnection,
+    request_id: u32,
+}
+
+impl Drop for AbandonedRequestGuard<'_> {
+    fn drop(&mut self) {
+        let inner = &self.conn.inner;
+        // Already removed if the request has completed or timed out
+        if let Some((_, data)) = inner.request_map.remove(&self.request_id) {
+            let elapsed = inner.clock.now().duration_since(data.send_time);
+            let res = Err(TonClientError::Cancelled {
+                method: error_method(&data.method),
+            });
+            data.span.record_result(&elapsed, &res);
+            data.span.in_scope(|| {
+                let callback = &inner.callback;
+                callback.on_invoke_result(
+                    &inner.tag,
+                    self.request_id,
+                    &data.method,
+                    &elapsed,
+                    &res,
+                );
+                callback.on_cancelled_invoke(&inner.tag, self.request_id, &data.method, &elapsed);
+            });
+        }
+    }
+}
+
+/// Handle of a connection run loop, running either on a dedicated thread or on the
+/// blocking pool of the tokio runtime, see `TonConnectionParams::run_loop_on_blocking_pool`.
+pub enum RunLoopHandle {
+    Thread(thread::JoinHandle<()>),
+    Task(tokio::task::JoinHandle<()>),
+}
+
+impl RunLoopHandle {
+    /// Checks if the run loop has exited, which happens shortly after all
+    /// clones of the connection are dropped.
+    pub fn is_finished(&self) -> bool {
+        match self {
+            RunLoopHandle::Thread(handle) => handle.is_finished(),
+            RunLoopHandle::Task(handle) => handle.is_finished(),
+        }
+    }
+
+    /// Blocks the current thread until the run loop exits.
+    pub fn join(self) -> thread::Result<()> {
+        match self {
+            RunLoopHandle::Thread(handle) => handle.join(),
+            RunLoopHandle::Task(handle) => {
+                futures::executor::block_on(handle).map_err(|e| match e.try_into_panic() {
+                    Ok(payload) => payload,
+                    Err(e) => Box::new(e.to_string()),
+                })
+            }
+        }
+    }
+}
+
 static NOT_AVAILABLE: &str = "N/A";
 
+impl Drop for Inner {
+    fn drop(&mut self) {
+        self.closed.store(true, Ordering::Release);
+        // Wake up the run loop waiting in `receive`, static requests are answered right away
+        let wake_up = TonFunction::GetLogVerbosityLevel {};
+        if let Err(e) = self.tl_client.read().unwrap().send(&wake_up, "") {
+            log::debug!("[{}] Failed to wake up run loop: {}", self.tag, e);
+        }
+        fail_in_flight_requests(&self.request_map, |method| {
+            TonClientError::ConnectionClosed { method }
+        });
+    }
+}
+
+/// Serializes `cell` for tonlib, an absent cell is passed as empty bytes.
+fn to_boc(cell: Option<ArcCell>) -> Result<Vec<u8>, TonCellError> {
+    match cell {
+        Some(cell) => BagOfCells::new(&[cell]).serialize(true),
+        None => Ok(vec![]),
+    }
+}
+
 /// Client run loop
-fn run_loop(tag: String, weak_inner: Weak<Inner>, callback: Arc<dyn TonConnectionCallback>) {
+///
+/// The loop holds `weak_inner` upgraded only while processing a received result,
+/// so that the connection can be dropped while the loop waits in `receive`.
+fn run_loop(
+    tag: String,
+    weak_inner: Weak<Inner>,
+    tl_client: Arc<RwLock<TlTonClient>>,
+    closed: Arc<AtomicBool>,
+    callback: Arc<dyn TonConnectionCallback>,
+) {
+    let _span = enter_connection_span(&tag);
+    let _closed_on_exit = ClosedOnExit(closed.clone());
     callback.on_connection_loop_start(&tag);
 
+    let mut last_reap_time: Option<Instant> = None;
+    let mut consecutive_errors: usize = 0;
+    let mut stall_detector = StallDetector::default();
+    let mut pending_init: Option<oneshot::Receiver<Result<TonResult, TonClientError>>> = None;
     loop {
+        if closed.load(Ordering::Acquire) {
+            break;
+        }
+        let recv = tl_client.read().unwrap().receive_value(1.0);
         if let Some(inner) = weak_inner.upgrade() {
-            let recv = inner.tl_client.receive(1.0);
-            if let Some((ton_result, maybe_extra)) = recv {
+            if let Some(max_age) = inner.max_request_age {
+                let now = inner.clock.now();
+                let last_reap = *last_reap_time.get_or_insert(now);
+                if now.duration_since(last_reap) >= inner.request_reap_interval {
+                    reap_stale_requests(
+                        &tag,
+                        &inner.request_map,
+                        inner.clock.as_ref(),
+                        max_age,
+                        callback.as_ref(),
+                    );
+                    last_reap_time = Some(now);
+                }
+            }
+            if let Some(receiver) = pending_init.as_mut() {
+                match receiver.try_recv() {
+                    Ok(Ok(_)) => {
+                        callback.on_init_completed(&tag, true);
+                        pending_init = None;
+                    }
+                    Ok(Err(e)) => {
+                        log::warn!("[{}] Connection re-initialization failed: {}", tag, e);
+                        pending_init = None;
+                    }
+                    Err(TryRecvError::Empty) => {}
+                    Err(TryRecvError::Closed) => pending_init = None,
+                }
+            }
+            let received = recv.is_some();
+            if let Some((value, maybe_extra)) = recv {
                 let maybe_request_id = if let Some(s) = &maybe_extra {
                     s.parse::<u32>().ok()
                 } else {
                     None
                 };
                 let maybe_data = maybe_request_id.and_then(|i| inner.request_map.remove(&i));
+                if let Some(recorder) = inner.traffic_recorder() {
+                    let data = maybe_data.as_ref().map(|d| &d.1);
+                    let record =
+                        incoming_record(&tag, maybe_request_id, data, &value, inner.clock.now());
+                    recorder.record(&record);
+                }
+                let raw = maybe_data.as_ref().is_some_and(|d| d.1.raw);
+                let ton_result = value.and_then(|v| result_from_value(v, raw));
+                // A late result isn't known to be raw, so it may fail to parse as `TonResult`
+                let late = maybe_request_id.is_some() && maybe_data.is_none();
+                if !late {
+                    if ton_result.is_err() {
+                        consecutive_errors += 1;
+                    } else {
+                        consecutive_errors = 0;
+                    }
+                }
                 let result: Result<TonResult, TonClientError> = match ton_result {
                     Ok(TonResult::Error { code, message }) => {
                         let method = maybe_data
                             .as_ref()
-                            .map(|d| d.1.method)
+                            .map(|d| error_method(&d.1.method))
                             .unwrap_or(NOT_AVAILABLE);
                         Err(TonClientError::TonlibError {
                             method,
@@ -342,21 +1385,33 @@ fn run_loop(tag: String, weak_inner: Weak<Inner>, callback: Arc<dyn TonConnectio
                 if let Some((_, data)) = maybe_data {
                     // Found corresponding request, reply to it
                     let request_id = maybe_request_id.unwrap(); // Can't be empty if data is not empty
-                    let now = Instant::now();
+                    let now = inner.clock.now();
                     let duration = now.duration_since(data.send_time);
-                    callback.on_invoke_result(&tag, request_id, data.method, &duration, &result);
-
-                    if data.sender.send(result).is_err() {
-                        callback.on_cancelled_invoke(&tag, request_id, data.method, &duration);
-                    }
+                    let RequestData {
+                        method,
+                        sender,
+                        span,
+                        ..
+                    } = data;
+                    span.record_result(&duration, &result);
+                    span.in_scope(|| {
+                        callback.on_invoke_result(&tag, request_id, &method, &duration, &result);
+                        if matches!(inner.slow_request_threshold, Some(t) if duration > t) {
+                            callback.on_slow_invoke(&tag, request_id, &method, &duration);
+                        }
+                        if sender.send(result).is_err() {
+                            callback.on_cancelled_invoke(&tag, request_id, &method, &duration);
+                        }
+                    });
+                } else if let Some(request_id) = maybe_request_id {
+                    // Request has timed out, nobody awaits the result
+                    log::debug!("[{}] Discarding late result of request {}", tag, request_id);
                 } else {
                     // No request data, attempt to parse notification. Errors are ignored here.
                     if let Ok(r) = result {
                         let maybe_notification = TonNotification::from_result(&r);
                         if let Some(n) = maybe_notification {
-                            callback.on_notification(&tag, &n);
-                            // The call might only fail if there are no receivers, so just ignore the result
-                            let _ = inner.notification_sender.send(Arc::new(n));
+                            send_notification(&tag, &inner, callback.as_ref(), n);
                         } else {
                             let extra = maybe_extra.as_deref();
                             callback.on_ton_result_parse_error(&tag, extra, &r);
@@ -366,9 +1421,838 @@ fn run_loop(tag: String, weak_inner: Weak<Inner>, callback: Arc<dyn TonConnectio
             } else {
                 callback.on_idle(tag.as_str())
             }
+            if let Some(threshold) = inner.reconnect_error_threshold {
+                if consecutive_errors >= threshold {
+                    callback.on_reconnect(&tag, consecutive_errors);
+                    pending_init = reconnect(&tag, &inner, callback.as_ref(), |method| {
+                        TonClientError::Reconnecting { method }
+                    });
+                    consecutive_errors = 0;
+                }
+            }
+            if let Some(timeout) = inner.stall_timeout {
+                let now = inner.clock.now();
+                let idle = inner.request_map.is_empty();
+                if let Some(stalled_for) = stall_detector.check(now, received, idle, timeout) {
+                    callback.on_connection_stalled(&tag, &stalled_for);
+                    pending_init = reconnect(&tag, &inner, callback.as_ref(), |method| {
+                        TonClientError::ConnectionReset { method }
+                    });
+                    consecutive_errors = 0;
+                }
+            }
         } else {
-            callback.on_connection_loop_exit(tag.as_str());
             break;
         }
     }
+    // Destroy the tonlib client before reporting the exit
+    drop(tl_client);
+    callback.on_connection_loop_exit(tag.as_str());
+}
+
+/// Creates the record of a result of the request with `data`, or of a notification.
+fn incoming_record(
+    tag: &str,
+    request_id: Option<u32>,
+    data: Option<&RequestData>,
+    value: &Result<Value, TlError>,
+    now: Instant,
+) -> TlTrafficRecord {
+    let payload = value.as_ref().cloned().unwrap_or_default();
+    let method = data.map(|d| d.method.as_ref());
+    let mut record = TlTrafficRecord::new(
+        tag,
+        TlTrafficDirection::Incoming,
+        request_id,
+        method,
+        payload,
+    );
+    record.duration = data.map(|d| now.duration_since(d.send_time));
+    record.error = value.as_ref().err().map(|e| e.to_string());
+    record
+}
+
+/// Sends `notification` to subscribers, counting notifications lost in a full queue.
+fn send_notification(
+    tag: &str,
+    inner: &Inner,
+    callback: &dyn TonConnectionCallback,
+    notification: TonNotification,
+) {
+    let in_progress = !notification.is_sync_done();
+    inner.sync_in_progress.store(in_progress, Ordering::Relaxed);
+    if let Some(sync_state) = notification.sync_state() {
+        let mut last_sync_state = inner.sync_state.lock().unwrap();
+        if last_sync_state.as_ref() != Some(sync_state) {
+            *last_sync_state = Some(sync_state.clone());
+            callback.on_sync_state_changed(tag, sync_state);
+        }
+    }
+    callback.on_notification(tag, &notification);
+    let sender = &inner.notification_sender;
+    // The channel rounds its capacity up to a power of two, once that many notifications are
+    // queued the send overwrites the oldest one not yet received by the slowest subscriber.
+    let buffer_len = inner.notification_queue_capacity.next_power_of_two();
+    if sender.receiver_count() > 0 && sender.len() >= buffer_len {
+        let dropped = inner.dropped_notifications.fetch_add(1, Ordering::Relaxed) + 1;
+        callback.on_notification_dropped(tag, dropped);
+    }
+    let notification = Arc::new(notification);
+    send_reliable(inner, &notification);
+    // The call might only fail if there are no receivers, so just ignore the result
+    let _ = sender.send(notification);
+    if let Some(high_watermark) = inner.notification_queue_high_watermark {
+        let len = sender.len();
+        if len >= high_watermark {
+            callback.on_notification_queue_high_watermark(
+                tag,
+                len,
+                inner.notification_queue_capacity,
+            );
+        }
+    }
+}
+
+/// Sends `notification` to reliable subscribers, waiting while their queues are full.
+///
+/// Stops waiting once the connection is closed, subscribers that dropped their receivers
+/// are removed.
+fn send_reliable(inner: &Inner, notification: &Arc<TonNotification>) {
+    let senders = inner.reliable_senders.lock().unwrap().clone();
+    let mut has_closed = false;
+    for sender in senders {
+        let pending = match sender.try_send(notification.clone()) {
+            Ok(()) => continue,
+            Err(TrySendError::Full(n)) => n,
+            Err(TrySendError::Closed(_)) => {
+                has_closed = true;
+                continue;
+            }
+        };
+        // The run loop is a blocking thread, so it may block on sending
+        let sent = futures::executor::block_on(async {
+            let closed = inner.close_notify.notified();
+            tokio::pin!(closed);
+            // Registers for `notify_waiters` before checking the flag
+            closed.as_mut().enable();
+            if inner.closed.load(Ordering::Acquire) {
+                return None;
+            }
+            tokio::select! {
+                result = sender.send(pending) => Some(result.is_ok()),
+                _ = closed => None,
+            }
+        });
+        match sent {
+            Some(true) => {}
+            Some(false) => has_closed = true,
+            None => return,
+        }
+    }
+    if has_closed {
+        inner
+            .reliable_senders
+            .lock()
+            .unwrap()
+            .retain(|s| !s.is_closed());
+    }
+}
+
+/// Sets the flag when dropped, so that the exit of the run loop is visible even after a panic.
+struct ClosedOnExit(Arc<AtomicBool>);
+
+impl Drop for ClosedOnExit {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Release);
+    }
+}
+
+/// Tracks the time since tonlib last returned anything while requests were in flight.
+#[derive(Debug, Default)]
+struct StallDetector {
+    last_activity: Option<Instant>,
+}
+
+impl StallDetector {
+    /// Returns how long the connection has stalled once that's at least `timeout`, resetting
+    /// the detector. `received` is whether tonlib returned a result, `idle` is whether no
+    /// requests are in flight, time spent idle doesn't count.
+    fn check(
+        &mut self,
+        now: Instant,
+        received: bool,
+        idle: bool,
+        timeout: Duration,
+    ) -> Option<Duration> {
+        let last_activity = *self.last_activity.get_or_insert(now);
+        if received || idle {
+            self.last_activity = Some(now);
+            return None;
+        }
+        let stalled_for = now.duration_since(last_activity);
+        if stalled_for >= timeout {
+            self.last_activity = Some(now);
+            Some(stalled_for)
+        } else {
+            None
+        }
+    }
+}
+
+/// Replaces the tonlib client with a fresh one and re-sends the stashed init function.
+///
+/// Requests in flight are completed with the error produced by `error` for their method.
+/// Returns the receiver of the init result, `None` if the connection was never initialized.
+fn reconnect(
+    tag: &str,
+    inner: &Inner,
+    callback: &dyn TonConnectionCallback,
+    error: fn(&'static str) -> TonClientError,
+) -> Option<oneshot::Receiver<Result<TonResult, TonClientError>>> {
+    *inner.tl_client.write().unwrap() = TlTonClient::new(tag);
+    inner.generation.fetch_add(1, Ordering::AcqRel);
+    fail_in_flight_requests(&inner.request_map, error);
+
+    let init_function = inner.init_function.lock().unwrap().clone()?;
+    let request_id = inner.counter.fetch_add(1, Ordering::SeqCst);
+    let (tx, rx) = oneshot::channel();
+    let method: &'static str = (&init_function).into();
+    let span = RequestSpan::new(tag, request_id, method);
+    span.in_scope(|| callback.on_invoke(tag, request_id, &init_function));
+    let data = RequestData {
+        method: method.into(),
+        send_time: inner.clock.now(),
+        sender: tx,
+        span,
+        raw: false,
+    };
+    inner.request_map.insert(request_id, data);
+    let res = inner
+        .tl_client
+        .read()
+        .unwrap()
+        .send(&init_function, request_id.to_string().as_str());
+    if let Err(e) = res {
+        inner.request_map.remove(&request_id);
+        log::warn!("[{}] Error sending init after reconnect: {}", tag, e);
+        return None;
+    }
+    Some(rx)
+}
+
+/// Returns the method of a request reported by its errors.
+fn error_method(method: &Cow<'static, str>) -> &'static str {
+    match method {
+        Cow::Borrowed(method) => method,
+        Cow::Owned(_) => RAW_REQUEST_METHOD,
+    }
+}
+
+/// Completes all requests awaiting a result with the error produced by `error` for their method.
+fn fail_in_flight_requests(request_map: &RequestMap, error: fn(&'static str) -> TonClientError) {
+    let in_flight: Vec<u32> = request_map.iter().map(|entry| *entry.key()).collect();
+    for request_id in in_flight {
+        if let Some((_, data)) = request_map.remove(&request_id) {
+            let res = Err(error(error_method(&data.method)));
+            // The caller might be gone already, nothing to do then
+            let _ = data.sender.send(res);
+        }
+    }
+}
+
+/// Removes requests sent more than `max_age` ago and completes them with `TonClientError::Timeout`.
+fn reap_stale_requests(
+    tag: &str,
+    request_map: &RequestMap,
+    clock: &dyn Clock,
+    max_age: Duration,
+    callback: &dyn TonConnectionCallback,
+) {
+    let now = clock.now();
+    let stale: Vec<u32> = request_map
+        .iter()
+        .filter(|entry| now.duration_since(entry.send_time) > max_age)
+        .map(|entry| *entry.key())
+        .collect();
+    for request_id in stale {
+        if let Some((_, data)) = request_map.remove(&request_id) {
+            let elapsed = now.duration_since(data.send_time);
+            let RequestData {
+                method,
+                sender,
+                span,
+                ..
+            } = data;
+            let res = Err(TonClientError::Timeout {
+                method: error_method(&method),
+                elapsed,
+            });
+            span.record_result(&elapsed, &res);
+            span.in_scope(|| {
+                callback.on_invoke_timeout(tag, request_id, &method, &elapsed);
+                if sender.send(res).is_err() {
+                    callback.on_cancelled_invoke(tag, request_id, &method, &elapsed);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use futures::StreamExt;
+    use tokio::sync::oneshot;
+
+    use super::{
+        fail_in_flight_requests, reap_stale_requests, send_notification, RequestData, RequestMap,
+        StallDetector, TonConnection,
+    };
+    use crate::client::trace::RequestSpan;
+    use crate::client::{
+        correlation_id, with_correlation_id, Clock, ManualClock, NoopConnectionCallback,
+        RingBufferTrafficRecorder, TlTrafficDirection, TonClientError, TonClientInterface,
+        TonConnectionCallback, TonConnectionParams, TrafficCapture, NOOP_CONNECTION_CALLBACK,
+        SYSTEM_CLOCK,
+    };
+    use crate::tl::{SyncState, TonFunction, TonNotification, TonResult, UpdateSyncState};
+
+    #[derive(Default)]
+    struct CorrelationRecordingCallback {
+        events: Mutex<Vec<(&'static str, u32, Option<String>)>>,
+    }
+
+    impl CorrelationRecordingCallback {
+        fn record(&self, event: &'static str, request_id: u32) {
+            let id = correlation_id().map(|id| id.to_string());
+            self.events.lock().unwrap().push((event, request_id, id));
+        }
+    }
+
+    impl TonConnectionCallback for CorrelationRecordingCallback {
+        fn on_invoke(&self, _tag: &str, request_id: u32, _function: &TonFunction) {
+            self.record("invoke", request_id)
+        }
+
+        fn on_invoke_timeout(&self, _tag: &str, request_id: u32, _method: &str, _: &Duration) {
+            self.record("timeout", request_id)
+        }
+    }
+
+    /// Registers `function` like `send_request` without sending it, so that it stays in the
+    /// request map: tonlib answers any request, even a static one like `GetLogVerbosityLevel`.
+    fn send_unanswered(
+        conn: &TonConnection,
+        function: &TonFunction,
+    ) -> (u32, oneshot::Receiver<Result<TonResult, TonClientError>>) {
+        let method: &'static str = function.into();
+        conn.register_and_send(
+            method.into(),
+            false,
+            |cnt| conn.inner.callback.on_invoke(conn.tag(), cnt, function),
+            || serde_json::to_value(function).unwrap_or_default(),
+            |_, _| Ok(()),
+        )
+    }
+
+    #[test]
+    fn test_reap_stale_requests() {
+        let clock = ManualClock::new();
+        let request_map = RequestMap::new();
+        let (stale_tx, mut stale_rx) = oneshot::channel();
+        request_map.insert(
+            1,
+            RequestData {
+                method: "stale".into(),
+                send_time: clock.now(),
+                sender: stale_tx,
+                span: RequestSpan::new("test", 0, "stale"),
+                raw: false,
+            },
+        );
+        clock.advance(Duration::from_secs(50));
+        let (fresh_tx, mut fresh_rx) = oneshot::channel();
+        request_map.insert(
+            2,
+            RequestData {
+                method: "fresh".into(),
+                send_time: clock.now(),
+                sender: fresh_tx,
+                span: RequestSpan::new("test", 0, "fresh"),
+                raw: false,
+            },
+        );
+        clock.advance(Duration::from_secs(20));
+
+        let max_age = Duration::from_secs(60);
+        reap_stale_requests(
+            "test",
+            &request_map,
+            &clock,
+            max_age,
+            NOOP_CONNECTION_CALLBACK.as_ref(),
+        );
+
+        assert!(!request_map.contains_key(&1));
+        assert!(request_map.contains_key(&2));
+        match stale_rx.try_recv() {
+            Ok(Err(TonClientError::Timeout { method, elapsed })) => {
+                assert_eq!(method, "stale");
+                assert_eq!(elapsed, Duration::from_secs(70));
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+        assert!(fresh_rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_fail_in_flight_requests() {
+        let clock = ManualClock::new();
+        let request_map = RequestMap::new();
+        let (tx, mut rx) = oneshot::channel();
+        request_map.insert(
+            1,
+            RequestData {
+                method: "in_flight".into(),
+                send_time: clock.now(),
+                sender: tx,
+                span: RequestSpan::new("test", 0, "in_flight"),
+                raw: false,
+            },
+        );
+        let (dropped_tx, dropped_rx) = oneshot::channel();
+        drop(dropped_rx);
+        request_map.insert(
+            2,
+            RequestData {
+                method: "cancelled".into(),
+                send_time: clock.now(),
+                sender: dropped_tx,
+                span: RequestSpan::new("test", 0, "cancelled"),
+                raw: false,
+            },
+        );
+
+        fail_in_flight_requests(&request_map, |method| TonClientError::Reconnecting {
+            method,
+        });
+
+        assert!(request_map.is_empty());
+        match rx.try_recv() {
+            Ok(Err(TonClientError::Reconnecting { method })) => assert_eq!(method, "in_flight"),
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_stall_detector() {
+        let clock = ManualClock::new();
+        let timeout = Duration::from_secs(30);
+        let mut detector = StallDetector::default();
+        assert_eq!(detector.check(clock.now(), false, true, timeout), None);
+
+        // Time without requests in flight isn't a stall
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(detector.check(clock.now(), false, true, timeout), None);
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(detector.check(clock.now(), false, false, timeout), None);
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(detector.check(clock.now(), true, false, timeout), None);
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(detector.check(clock.now(), false, false, timeout), None);
+        clock.advance(Duration::from_secs(15));
+        assert_eq!(
+            detector.check(clock.now(), false, false, timeout),
+            Some(Duration::from_secs(35))
+        );
+        // Reset after reporting a stall
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(detector.check(clock.now(), false, false, timeout), None);
+    }
+
+    #[test]
+    fn test_connection_with_log_verbosity_level() {
+        let params = TonConnectionParams {
+            log_verbosity_level: Some(1),
+            ..Default::default()
+        };
+        let conn = TonConnection::new(NOOP_CONNECTION_CALLBACK.clone(), &params);
+        assert!(conn.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_connection_captures_traffic() {
+        let params = TonConnectionParams {
+            traffic_capture: Some(TrafficCapture::RingBuffer { capacity: 16 }),
+            ..Default::default()
+        };
+        let conn = TonConnection::new(NOOP_CONNECTION_CALLBACK.clone(), &params).unwrap();
+        let (request_id, _rx) = conn.send_request(&TonFunction::GetLogVerbosityLevel {});
+        let records = conn.captured_traffic(16);
+        let outgoing = records
+            .iter()
+            .find(|r| r.direction == TlTrafficDirection::Outgoing)
+            .unwrap();
+        assert_eq!(outgoing.request_id, Some(request_id));
+        assert_eq!(outgoing.method.as_deref(), Some("GetLogVerbosityLevel"));
+        assert_eq!(outgoing.payload["@type"], "getLogVerbosityLevel");
+
+        // The recorder can be replaced at any time, raw requests are recorded as sent
+        conn.set_traffic_recorder(Some(Arc::new(RingBufferTrafficRecorder::new(16))));
+        let params = serde_json::json!({"tag": "adnl"});
+        let (request_id, _rx) = conn.send_raw_request("getLogTagVerbosityLevel", &params);
+        let records = conn.captured_traffic(16);
+        let outgoing: Vec<_> = records
+            .iter()
+            .filter(|r| r.direction == TlTrafficDirection::Outgoing)
+            .collect();
+        assert_eq!(outgoing.len(), 1);
+        assert_eq!(outgoing[0].request_id, Some(request_id));
+        assert_eq!(
+            outgoing[0].payload,
+            serde_json::json!({"@type": "getLogTagVerbosityLevel", "tag": "adnl"})
+        );
+
+        conn.set_traffic_recorder(None);
+        assert!(conn.captured_traffic(16).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_drop_closes_in_flight_requests() {
+        let params = TonConnectionParams::default();
+        let callback = NOOP_CONNECTION_CALLBACK.clone();
+        let (conn, run_loop) =
+            TonConnection::new_joinable(callback, &params, SYSTEM_CLOCK.clone()).unwrap();
+        let (_, rx) = send_unanswered(&conn, &TonFunction::GetLogVerbosityLevel {});
+        drop(conn);
+
+        let result = tokio::time::timeout(Duration::from_millis(500), rx)
+            .await
+            .unwrap()
+            .unwrap();
+        match result {
+            Err(TonClientError::ConnectionClosed { method }) => {
+                assert_eq!(method, "GetLogVerbosityLevel")
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+        let deadline = Instant::now() + Duration::from_millis(500);
+        while !run_loop.is_finished() {
+            assert!(Instant::now() < deadline, "Run loop didn't exit");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        run_loop.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_invoke_passes_correlation_id_to_callbacks() {
+        let params = TonConnectionParams {
+            request_timeout: Some(Duration::from_millis(50)),
+            ..Default::default()
+        };
+        let callback = Arc::new(CorrelationRecordingCallback::default());
+        let conn = TonConnection::new(callback.clone(), &params).unwrap();
+        let function = TonFunction::GetLogVerbosityLevel {};
+        let invoke = || async {
+            let (request_id, rx) = send_unanswered(&conn, &function);
+            conn.await_result(request_id, rx).await
+        };
+
+        let result = with_correlation_id("http-42", invoke()).await;
+        assert!(matches!(result, Err(TonClientError::Timeout { .. })));
+        let result = invoke().await;
+        assert!(matches!(result, Err(TonClientError::Timeout { .. })));
+
+        let tagged = Some("http-42".to_string());
+        assert_eq!(
+            *callback.events.lock().unwrap(),
+            vec![
+                ("invoke", 0, tagged.clone()),
+                ("timeout", 0, tagged),
+                ("invoke", 1, None),
+                ("timeout", 1, None),
+            ]
+        );
+    }
+
+    struct PanickingCallback {}
+
+    impl TonConnectionCallback for PanickingCallback {
+        fn on_connection_loop_start(&self, _tag: &str) {
+            panic!("Callback failure");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_health_check_fails_after_run_loop_exit() {
+        let params = TonConnectionParams::default();
+        let callback = Arc::new(PanickingCallback {});
+        let (conn, run_loop) =
+            TonConnection::new_joinable(callback, &params, SYSTEM_CLOCK.clone()).unwrap();
+        let deadline = Instant::now() + Duration::from_millis(500);
+        while !run_loop.is_finished() {
+            assert!(Instant::now() < deadline, "Run loop didn't exit");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(run_loop.join().is_err());
+
+        let result = tokio::time::timeout(Duration::from_millis(100), conn.health_check())
+            .await
+            .unwrap();
+        match result {
+            Err(TonClientError::ConnectionClosed { method }) => {
+                assert_eq!(method, "BlocksGetMasterchainInfo")
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_and_stats() {
+        let params = TonConnectionParams {
+            request_timeout: Some(Duration::from_millis(100)),
+            ..Default::default()
+        };
+        let conn = TonConnection::new(NOOP_CONNECTION_CALLBACK.clone(), &params).unwrap();
+        assert_eq!(conn.in_flight(), 0);
+        assert_eq!(conn.stats().total_invocations, 0);
+
+        let (request_id, rx) = send_unanswered(&conn, &TonFunction::GetLogVerbosityLevel {});
+        assert_eq!(conn.in_flight(), 1);
+
+        let result = conn.await_result(request_id, rx).await;
+        assert!(matches!(result, Err(TonClientError::Timeout { .. })));
+        let stats = conn.stats();
+        assert_eq!(stats.tag, conn.tag());
+        assert_eq!(stats.in_flight, 0);
+        assert_eq!(stats.total_invocations, 1);
+    }
+
+    #[tokio::test]
+    async fn test_close_fails_requests_and_stops_run_loop() {
+        let params = TonConnectionParams::default();
+        let callback = NOOP_CONNECTION_CALLBACK.clone();
+        let (conn, run_loop) =
+            TonConnection::new_joinable(callback, &params, SYSTEM_CLOCK.clone()).unwrap();
+        let (request_id, rx) = send_unanswered(&conn, &TonFunction::GetLogVerbosityLevel {});
+        assert!(!conn.is_closed());
+
+        conn.close().await;
+        assert!(conn.is_closed());
+        match conn.await_result(request_id, rx).await {
+            Err(TonClientError::ConnectionClosed { method }) => {
+                assert_eq!(method, "GetLogVerbosityLevel")
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+        let result = conn.invoke(&TonFunction::GetLogVerbosityLevel {}).await;
+        assert!(matches!(
+            result,
+            Err(TonClientError::ConnectionClosed { .. })
+        ));
+        assert_eq!(conn.in_flight(), 0);
+
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while !run_loop.is_finished() {
+            assert!(Instant::now() < deadline, "Run loop didn't exit");
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        run_loop.join().unwrap();
+    }
+
+    #[derive(Default)]
+    struct CancelRecordingCallback {
+        cancelled: Mutex<Vec<u32>>,
+    }
+
+    impl TonConnectionCallback for CancelRecordingCallback {
+        fn on_cancelled_invoke(&self, _tag: &str, request_id: u32, _method: &str, _: &Duration) {
+            self.cancelled.lock().unwrap().push(request_id);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_invoke_with_timeout_overrides_params() {
+        let params = TonConnectionParams::default();
+        let conn = TonConnection::new(NOOP_CONNECTION_CALLBACK.clone(), &params).unwrap();
+        let (request_id, rx) = send_unanswered(&conn, &TonFunction::GetLogVerbosityLevel {});
+        let result = tokio::time::timeout(
+            Duration::from_millis(500),
+            conn.await_result_with_timeout(request_id, rx, Some(Duration::from_millis(50))),
+        )
+        .await
+        .unwrap();
+        match result {
+            Err(TonClientError::Timeout { method, elapsed }) => {
+                assert_eq!(method, "GetLogVerbosityLevel");
+                assert!(elapsed >= Duration::from_millis(50));
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+        assert_eq!(conn.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_dropped_invoke_removes_request() {
+        let params = TonConnectionParams::default();
+        let callback = Arc::new(CancelRecordingCallback::default());
+        let conn = TonConnection::new(callback.clone(), &params).unwrap();
+
+        let (request_id, rx) = send_unanswered(&conn, &TonFunction::GetLogVerbosityLevel {});
+        assert_eq!(conn.in_flight(), 1);
+        let dropped =
+            tokio::time::timeout(Duration::from_millis(20), conn.await_result(request_id, rx))
+                .await;
+        assert!(dropped.is_err());
+        assert_eq!(conn.in_flight(), 0);
+        assert_eq!(*callback.cancelled.lock().unwrap(), vec![0]);
+    }
+
+    #[derive(Default)]
+    struct DropRecordingCallback {
+        totals: Mutex<Vec<u64>>,
+    }
+
+    impl TonConnectionCallback for DropRecordingCallback {
+        fn on_notification_dropped(&self, _tag: &str, total: u64) {
+            self.totals.lock().unwrap().push(total);
+        }
+    }
+
+    #[test]
+    fn test_send_notification_counts_dropped() {
+        let params = TonConnectionParams {
+            notification_queue_length: 2,
+            ..Default::default()
+        };
+        let callback = DropRecordingCallback::default();
+        let conn = TonConnection::new(NOOP_CONNECTION_CALLBACK.clone(), &params).unwrap();
+        let notification = TonNotification::UpdateSyncState(UpdateSyncState {
+            sync_state: SyncState::Done,
+        });
+        // Nothing is lost without subscribers
+        for _ in 0..5 {
+            send_notification(conn.tag(), &conn.inner, &callback, notification.clone());
+        }
+        assert_eq!(conn.stats().dropped_notifications, 0);
+
+        let _receiver = conn.subscribe();
+        for _ in 0..5 {
+            send_notification(conn.tag(), &conn.inner, &callback, notification.clone());
+        }
+        assert_eq!(conn.stats().dropped_notifications, 3);
+        assert_eq!(*callback.totals.lock().unwrap(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_send_notification_reliable() {
+        let params = TonConnectionParams {
+            notification_queue_length: 2,
+            ..Default::default()
+        };
+        let conn = TonConnection::new(NOOP_CONNECTION_CALLBACK.clone(), &params).unwrap();
+        let notification = |current_seqno| {
+            TonNotification::UpdateSyncState(UpdateSyncState {
+                sync_state: SyncState::InProgress {
+                    from_seqno: 0,
+                    to_seqno: 10,
+                    current_seqno,
+                },
+            })
+        };
+        assert!(conn.subscribe_reliable(0).is_err());
+        let mut receiver = conn.subscribe_reliable(2).unwrap();
+        let dropped = conn.subscribe_reliable(1).unwrap();
+        drop(dropped);
+        let sender = {
+            let conn = conn.clone();
+            thread::spawn(move || {
+                for i in 0..5 {
+                    send_notification(
+                        conn.tag(),
+                        &conn.inner,
+                        &NoopConnectionCallback {},
+                        notification(i),
+                    );
+                }
+            })
+        };
+        // The sender waits for the receiver instead of dropping notifications
+        for i in 0..5 {
+            assert_eq!(receiver.blocking_recv().as_deref(), Some(&notification(i)));
+        }
+        sender.join().unwrap();
+        assert_eq!(conn.inner.reliable_senders.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_sync_state() {
+        let conn =
+            TonConnection::new(NOOP_CONNECTION_CALLBACK.clone(), &Default::default()).unwrap();
+        let stream = conn.subscribe_sync_state();
+        let in_progress = SyncState::InProgress {
+            from_seqno: 0,
+            to_seqno: 10,
+            current_seqno: 5,
+        };
+        for sync_state in [in_progress.clone(), SyncState::Done] {
+            let notification = TonNotification::UpdateSyncState(UpdateSyncState { sync_state });
+            send_notification(
+                conn.tag(),
+                &conn.inner,
+                &NoopConnectionCallback {},
+                notification,
+            );
+        }
+        let states: Vec<SyncState> = stream.take(2).collect().await;
+        assert_eq!(states, vec![in_progress, SyncState::Done]);
+    }
+
+    #[derive(Default)]
+    struct LifecycleRecordingCallback {
+        sync_states: Mutex<Vec<SyncState>>,
+        pending: Mutex<Vec<usize>>,
+    }
+
+    impl TonConnectionCallback for LifecycleRecordingCallback {
+        fn on_request_queue_high_watermark(&self, _tag: &str, pending: usize) {
+            self.pending.lock().unwrap().push(pending);
+        }
+
+        fn on_sync_state_changed(&self, _tag: &str, sync_state: &SyncState) {
+            self.sync_states.lock().unwrap().push(sync_state.clone());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_callbacks() {
+        let params = TonConnectionParams {
+            request_queue_high_watermark: Some(2),
+            ..Default::default()
+        };
+        let callback = Arc::new(LifecycleRecordingCallback::default());
+        let conn = TonConnection::new(callback.clone(), &params).unwrap();
+        let _pending: Vec<_> = (0..3)
+            .map(|_| send_unanswered(&conn, &TonFunction::GetLogVerbosityLevel {}))
+            .collect();
+        // Called once on reaching the watermark, not for the request above it
+        assert_eq!(*callback.pending.lock().unwrap(), vec![2]);
+        // and again once the queue has dropped below it
+        conn.inner.request_map.clear();
+        let _pending: Vec<_> = (0..2)
+            .map(|_| send_unanswered(&conn, &TonFunction::GetLogVerbosityLevel {}))
+            .collect();
+        assert_eq!(*callback.pending.lock().unwrap(), vec![2, 2]);
+
+        for _ in 0..2 {
+            let notification = TonNotification::UpdateSyncState(UpdateSyncState {
+                sync_state: SyncState::Done,
+            });
+            send_notification(conn.tag(), &conn.inner, callback.as_ref(), notification);
+        }
+        assert_eq!(*callback.sync_states.lock().unwrap(), vec![SyncState::Done]);
+    }
 }
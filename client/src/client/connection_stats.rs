@@ -0,0 +1,141 @@
+use std::sync::atomic::{AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Weight of a new sample in the rolling averages.
+const EWMA_WEIGHT: f64 = 0.2;
+/// How much slower an always failing connection is considered by `PoolDispatch::LeastLatency`.
+const ERROR_RATE_PENALTY: f64 = 10.0;
+const ERROR_RATE_SCALE: f64 = 1_000_000.0;
+
+/// Snapshot of the request statistics of a pool connection, see `TonClient::connection_stats`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoolConnectionStats {
+    /// Rolling average latency of successful requests, `None` until the first one completes.
+    pub latency: Option<Duration>,
+    /// Rolling share of failed requests, from `0.0` to `1.0`.
+    pub error_rate: f64,
+    /// Number of requests in flight.
+    pub pending: usize,
+}
+
+/// Request statistics of a single pool connection.
+#[derive(Debug, Default)]
+pub(crate) struct RequestStats {
+    /// Rolling average latency in microseconds, `0` if unknown.
+    latency_micros: AtomicU64,
+    /// Rolling error rate in millionths.
+    error_rate: AtomicU32,
+    pending: AtomicUsize,
+}
+
+impl RequestStats {
+    /// Counts a request in flight until the returned guard is dropped.
+    pub(crate) fn start(&self) -> PendingRequest<'_> {
+        self.pending.fetch_add(1, Ordering::AcqRel);
+        PendingRequest(self)
+    }
+
+    /// Records the outcome of a request, `latency` is taken into account for successful ones.
+    pub(crate) fn record(&self, latency: Duration, failed: bool) {
+        if !failed {
+            let sample = (latency.as_micros() as u64).max(1);
+            let update = |current: u64| match current {
+                0 => Some(sample),
+                current => Some(ewma(current as f64, sample as f64) as u64),
+            };
+            let _ = self
+                .latency_micros
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, update);
+        }
+        let sample = if failed { ERROR_RATE_SCALE } else { 0.0 };
+        let _ = self
+            .error_rate
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |current| {
+                Some(ewma(current as f64, sample).round() as u32)
+            });
+    }
+
+    pub(crate) fn snapshot(&self) -> PoolConnectionStats {
+        let latency = match self.latency_micros.load(Ordering::Acquire) {
+            0 => None,
+            micros => Some(Duration::from_micros(micros)),
+        };
+        PoolConnectionStats {
+            latency,
+            error_rate: self.error_rate.load(Ordering::Acquire) as f64 / ERROR_RATE_SCALE,
+            pending: self.pending.load(Ordering::Acquire),
+        }
+    }
+
+    /// Expected latency of the next request, lower is better.
+    ///
+    /// Connections without completed requests score best, so every connection is tried.
+    /// Connections whose requests have only failed so far score worst.
+    pub(crate) fn latency_score(&self) -> f64 {
+        let stats = self.snapshot();
+        match stats.latency {
+            Some(latency) => latency.as_secs_f64() * (1.0 + ERROR_RATE_PENALTY * stats.error_rate),
+            None if stats.error_rate > 0.0 => f64::INFINITY,
+            None => 0.0,
+        }
+    }
+
+    pub(crate) fn pending(&self) -> usize {
+        self.pending.load(Ordering::Acquire)
+    }
+}
+
+pub(crate) struct PendingRequest<'a>(&'a RequestStats);
+
+impl Drop for PendingRequest<'_> {
+    fn drop(&mut self) {
+        self.0.pending.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+fn ewma(current: f64, sample: f64) -> f64 {
+    current + EWMA_WEIGHT * (sample - current)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::RequestStats;
+
+    #[test]
+    fn test_connection_stats() {
+        let stats = RequestStats::default();
+        assert_eq!(stats.snapshot().latency, None);
+        assert_eq!(stats.latency_score(), 0.0);
+
+        let pending = stats.start();
+        assert_eq!(stats.pending(), 1);
+        stats.record(Duration::from_millis(100), false);
+        drop(pending);
+        assert_eq!(stats.pending(), 0);
+        assert_eq!(stats.snapshot().latency, Some(Duration::from_millis(100)));
+
+        stats.record(Duration::from_millis(200), false);
+        assert_eq!(stats.snapshot().latency, Some(Duration::from_millis(120)));
+        let score = stats.latency_score();
+
+        // Failures don't affect latency, but make the connection look slower
+        stats.record(Duration::from_secs(10), true);
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.latency, Some(Duration::from_millis(120)));
+        assert!((snapshot.error_rate - 0.2).abs() < 1e-6);
+        assert!(stats.latency_score() > score);
+    }
+
+    #[test]
+    fn test_failing_connection_scores_worst() {
+        let stats = RequestStats::default();
+        stats.record(Duration::from_millis(5), true);
+        assert_eq!(stats.snapshot().latency, None);
+        assert_eq!(stats.latency_score(), f64::INFINITY);
+
+        stats.record(Duration::from_millis(100), false);
+        assert!(stats.latency_score().is_finite());
+    }
+}
@@ -0,0 +1,63 @@
+use std::cell::RefCell;
+use std::future::Future;
+use std::sync::Arc;
+
+tokio::task_local! {
+    static TASK_CORRELATION_ID: Arc<str>;
+}
+
+thread_local! {
+    static CALLBACK_CORRELATION_ID: RefCell<Option<Arc<str>>> = const { RefCell::new(None) };
+}
+
+/// Runs `future` with a correlation id attached to every tonlib request it invokes.
+///
+/// The id is captured when a request is sent and is available to all connection callbacks
+/// of that request through `correlation_id`, also when they run on the connection thread.
+/// With the `tracing` feature it is recorded as `correlation_id` field of the request span.
+/// The id is not sent to tonlib, whose `@extra` remains the numeric request id.
+///
+/// Like any task-local value the id is not inherited by tasks spawned from `future`.
+pub async fn with_correlation_id<F: Future>(id: impl Into<Arc<str>>, future: F) -> F::Output {
+    TASK_CORRELATION_ID.scope(id.into(), future).await
+}
+
+/// Returns the correlation id of the current request.
+///
+/// Inside a connection callback this is the id of the request the callback is called for,
+/// elsewhere the id set by the enclosing `with_correlation_id`, if any.
+pub fn correlation_id() -> Option<Arc<str>> {
+    CALLBACK_CORRELATION_ID
+        .with(|id| id.borrow().clone())
+        .or_else(|| TASK_CORRELATION_ID.try_with(|id| id.clone()).ok())
+}
+
+/// Makes `id` the result of `correlation_id` while `f` runs.
+pub(crate) fn in_correlation_scope<T>(id: Option<&Arc<str>>, f: impl FnOnce() -> T) -> T {
+    let previous = CALLBACK_CORRELATION_ID.with(|current| current.replace(id.cloned()));
+    let result = f();
+    CALLBACK_CORRELATION_ID.with(|current| *current.borrow_mut() = previous);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{correlation_id, in_correlation_scope, with_correlation_id};
+
+    #[tokio::test]
+    async fn test_correlation_id_scopes() {
+        assert_eq!(correlation_id(), None);
+        with_correlation_id("http-1", async {
+            assert_eq!(correlation_id().as_deref(), Some("http-1"));
+            let other: Arc<str> = Arc::from("http-2");
+            in_correlation_scope(Some(&other), || {
+                assert_eq!(correlation_id().as_deref(), Some("http-2"));
+            });
+            assert_eq!(correlation_id().as_deref(), Some("http-1"));
+        })
+        .await;
+        assert_eq!(correlation_id(), None);
+    }
+}
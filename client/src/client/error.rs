@@ -1,6 +1,8 @@
 use std::io;
+use std::time::Duration;
 
 use thiserror::Error;
+use tonlib_core::cell::TonCellError;
 use tonlib_core::TonAddressParseError;
 
 use crate::tl::{TlError, TonResult, TonResultDiscriminants};
@@ -17,6 +19,53 @@ pub enum TonClientError {
         message: String,
     },
 
+    /// Error returned by a liteserver to `LiteClient`.
+    #[error("Liteserver error (Method: {method}, code: {code}, message: {message})")]
+    LiteServerError {
+        method: &'static str,
+        code: i32,
+        message: String,
+    },
+
+    #[error("External message rejected (Method: {method}, code: {code}, message: {message})")]
+    ExternalMessageRejected {
+        method: &'static str,
+        code: i32,
+        message: String,
+    },
+
+    #[error("Timeout (Method: {method}, elapsed: {elapsed:?})")]
+    Timeout {
+        method: &'static str,
+        elapsed: Duration,
+    },
+
+    #[error("Cancelled (Method: {method})")]
+    Cancelled { method: &'static str },
+
+    #[error("Connection closed (Method: {method})")]
+    ConnectionClosed { method: &'static str },
+
+    #[error("Connection is reconnecting (Method: {method})")]
+    Reconnecting { method: &'static str },
+
+    #[error("Connection reset after tonlib stopped responding (Method: {method})")]
+    ConnectionReset { method: &'static str },
+
+    #[error("Archive node unavailable (Method: {method}, message: {message})")]
+    ArchiveUnavailable {
+        method: &'static str,
+        message: String,
+    },
+
+    #[error("HTTP error (Method: {method}, status: {status:?}, message: {message})")]
+    HttpError {
+        method: &'static str,
+        /// HTTP status of the response, `None` if the request failed before getting one.
+        status: Option<u16>,
+        message: String,
+    },
+
     #[error("Unexpected TonResult (Actual: {actual}, expected: {expected})")]
     UnexpectedTonResult {
         actual: TonResultDiscriminants,
@@ -31,6 +80,9 @@ pub enum TonClientError {
 
     #[error("TonAddressParseError: ({0})")]
     TonAddressParseError(#[from] TonAddressParseError),
+
+    #[error("TonCellError: ({0})")]
+    TonCellError(#[from] TonCellError),
 }
 
 impl TonClientError {
@@ -43,4 +95,314 @@ impl TonClientError {
             expected,
         }
     }
+
+    /// Returns the kind of a `TonlibError`, `LiteServerError` or `ExternalMessageRejected`,
+    /// `None` for other errors. Liteserver errors are classified like those relayed by tonlib.
+    pub fn tonlib_error_kind(&self) -> Option<TonlibErrorKind> {
+        match self {
+            TonClientError::TonlibError { code, message, .. }
+            | TonClientError::LiteServerError { code, message, .. }
+            | TonClientError::ExternalMessageRejected { code, message, .. } => {
+                Some(TonlibErrorKind::classify(*code, message))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns `true` for errors expected to go away when the request is repeated,
+    /// possibly on another connection.
+    ///
+    /// Unlike `RetryStrategy::is_retryable` it doesn't depend on configuration.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            TonClientError::Timeout { .. }
+            | TonClientError::ConnectionClosed { .. }
+            | TonClientError::Reconnecting { .. }
+            | TonClientError::ConnectionReset { .. } => true,
+            TonClientError::HttpError { status, .. } => {
+                status.is_none_or(|status| status == 429 || status >= 500)
+            }
+            e => e
+                .tonlib_error_kind()
+                .is_some_and(|kind| kind.is_transient()),
+        }
+    }
+
+    /// Returns the TVM exit code reported by a tonlib error, e.g. of an external message
+    /// rejected by the contract, `None` if there's no exit code in the message.
+    pub fn exit_code(&self) -> Option<i32> {
+        match self {
+            TonClientError::TonlibError { message, .. }
+            | TonClientError::ExternalMessageRejected { message, .. } => parse_exit_code(message),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the sent external message was already known to the lite server,
+    /// so it's to be processed and sending it again is pointless.
+    pub fn is_duplicate_message(&self) -> bool {
+        self.tonlib_error_kind() == Some(TonlibErrorKind::DuplicateMessage)
+    }
+
+    /// Converts a tonlib error reporting a malformed, duplicate or otherwise not applicable
+    /// external message into `ExternalMessageRejected`, other errors are returned as is.
+    pub fn into_external_message_error(self) -> TonClientError {
+        match self {
+            TonClientError::TonlibError {
+                method,
+                code,
+                message,
+            } if is_external_message_rejection(&message) => {
+                TonClientError::ExternalMessageRejected {
+                    method,
+                    code,
+                    message,
+                }
+            }
+            e => e,
+        }
+    }
+}
+
+/// Category of a tonlib error, derived from its code and message.
+///
+/// Tonlib reports most lite server failures with code `500` and a message prefixed with the lite
+/// server error name, e.g. `LITE_SERVER_NOTREADY`, so both are taken into account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TonlibErrorKind {
+    /// Lite server isn't synchronized yet.
+    NotReady,
+    /// Request to the lite server timed out.
+    Timeout,
+    /// Requested block is unknown to the lite server, e.g. not yet applied or pruned.
+    BlockNotFound,
+    /// Lite server rejected the request because of rate limiting or overload.
+    LiteServerBusy,
+    /// Requested account doesn't exist.
+    AccountNotFound,
+    /// Request was cancelled by tonlib.
+    Cancelled,
+    /// External message was already sent, e.g. through another lite server.
+    DuplicateMessage,
+    /// External message can't be applied to the account, e.g. rejected by the contract.
+    ExternalMessageRejected,
+    /// TVM execution failed with an exit code, see `TonClientError::exit_code`.
+    TvmExecutionFailed,
+    Other,
+}
+
+impl TonlibErrorKind {
+    pub fn classify(code: i32, message: &str) -> TonlibErrorKind {
+        let message = message.to_lowercase();
+        let contains_any = |patterns: &[&str]| patterns.iter().any(|p| message.contains(p));
+        if message.contains("duplicate message") {
+            TonlibErrorKind::DuplicateMessage
+        } else if message.contains("external message") {
+            TonlibErrorKind::ExternalMessageRejected
+        } else if contains_any(&["not in db", "block not found", "block is not applied"]) {
+            TonlibErrorKind::BlockNotFound
+        } else if contains_any(&["account not found", "account is not initialized"]) {
+            TonlibErrorKind::AccountNotFound
+        } else if code == 429 || contains_any(&["ratelimit", "too many requests", "overloaded"]) {
+            TonlibErrorKind::LiteServerBusy
+        } else if code == 651 || contains_any(&["lite_server_notready", "not ready"]) {
+            TonlibErrorKind::NotReady
+        } else if code == 652 || contains_any(&["timeout", "timed out"]) {
+            TonlibErrorKind::Timeout
+        } else if code == 653 || message.contains("cancelled") {
+            TonlibErrorKind::Cancelled
+        } else if parse_exit_code(&message).is_some() {
+            TonlibErrorKind::TvmExecutionFailed
+        } else {
+            TonlibErrorKind::Other
+        }
+    }
+
+    /// Returns `true` for kinds expected to go away when the request is repeated,
+    /// possibly on another lite server.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            TonlibErrorKind::NotReady | TonlibErrorKind::Timeout | TonlibErrorKind::LiteServerBusy
+        )
+    }
+}
+
+const EXTERNAL_MESSAGE_REJECTION_PATTERNS: [&str; 3] = [
+    "external message",
+    "duplicate message",
+    "failed to deserialize",
+];
+
+const EXIT_CODE_PATTERNS: [&str; 3] = ["exitcode=", "exit code", "exit_code"];
+
+/// Parses an exit code following one of `EXIT_CODE_PATTERNS`,
+/// e.g. `exitcode=33` or `exit code: -14`.
+fn parse_exit_code(message: &str) -> Option<i32> {
+    let message = message.to_lowercase();
+    EXIT_CODE_PATTERNS.iter().find_map(|pattern| {
+        let start = message.find(pattern)? + pattern.len();
+        let rest = message[start..].trim_start_matches([' ', ':', '=']);
+        let end = rest
+            .char_indices()
+            .find(|(i, c)| !(c.is_ascii_digit() || (*i == 0 && *c == '-')))
+            .map_or(rest.len(), |(i, _)| i);
+        rest[..end].parse().ok()
+    })
+}
+
+fn is_external_message_rejection(message: &str) -> bool {
+    let message = message.to_lowercase();
+    EXTERNAL_MESSAGE_REJECTION_PATTERNS
+        .iter()
+        .any(|pattern| message.contains(pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TonClientError, TonlibErrorKind};
+
+    fn tonlib_error(message: &str) -> TonClientError {
+        TonClientError::TonlibError {
+            method: "RawSendMessage",
+            code: 500,
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_into_external_message_error() {
+        let error = tonlib_error(
+            "LITE_SERVER_UNKNOWN: cannot apply external message to current state : \
+            External message was not accepted",
+        )
+        .into_external_message_error();
+        assert!(matches!(
+            error,
+            TonClientError::ExternalMessageRejected {
+                method: "RawSendMessage",
+                code: 500,
+                ..
+            }
+        ));
+        let error =
+            tonlib_error("Failed to deserialize bag of cells").into_external_message_error();
+        assert!(matches!(
+            error,
+            TonClientError::ExternalMessageRejected { .. }
+        ));
+        let error =
+            tonlib_error("LITE_SERVER_UNKNOWN: duplicate message").into_external_message_error();
+        assert!(error.is_duplicate_message());
+        assert!(matches!(
+            error,
+            TonClientError::ExternalMessageRejected { .. }
+        ));
+        let error = tonlib_error("LITE_SERVER_NETWORK timeout").into_external_message_error();
+        assert!(matches!(error, TonClientError::TonlibError { .. }));
+    }
+
+    #[test]
+    fn test_tonlib_error_kind() {
+        let kind = |message: &str| tonlib_error(message).tonlib_error_kind();
+        assert_eq!(
+            kind("LITE_SERVER_NOTREADY: block is not in db"),
+            Some(TonlibErrorKind::BlockNotFound)
+        );
+        assert_eq!(
+            kind("LITE_SERVER_UNKNOWN: block is not applied"),
+            Some(TonlibErrorKind::BlockNotFound)
+        );
+        assert_eq!(
+            kind("LITE_SERVER_NOTREADY: server is not synchronized"),
+            Some(TonlibErrorKind::NotReady)
+        );
+        assert_eq!(
+            kind("LITE_SERVER_NETWORK: adnl query timeout"),
+            Some(TonlibErrorKind::Timeout)
+        );
+        assert_eq!(
+            kind("LITE_SERVER_RATELIMIT: too many requests"),
+            Some(TonlibErrorKind::LiteServerBusy)
+        );
+        assert_eq!(
+            kind("LITE_SERVER_UNKNOWN: account not found"),
+            Some(TonlibErrorKind::AccountNotFound)
+        );
+        assert_eq!(
+            kind("INVALID_ACCOUNT_ADDRESS"),
+            Some(TonlibErrorKind::Other)
+        );
+        assert_eq!(
+            kind(
+                "LITE_SERVER_UNKNOWN: cannot apply external message to current state : \
+                External message was not accepted"
+            ),
+            Some(TonlibErrorKind::ExternalMessageRejected)
+        );
+        assert_eq!(
+            kind("LITE_SERVER_UNKNOWN: duplicate message"),
+            Some(TonlibErrorKind::DuplicateMessage)
+        );
+        assert_eq!(
+            kind("LITE_SERVER_UNKNOWN: cannot run get method: exit code 11"),
+            Some(TonlibErrorKind::TvmExecutionFailed)
+        );
+
+        assert_eq!(
+            TonlibErrorKind::classify(651, ""),
+            TonlibErrorKind::NotReady
+        );
+        assert_eq!(TonlibErrorKind::classify(652, ""), TonlibErrorKind::Timeout);
+        assert_eq!(
+            TonlibErrorKind::classify(653, ""),
+            TonlibErrorKind::Cancelled
+        );
+        assert_eq!(
+            TonlibErrorKind::classify(429, ""),
+            TonlibErrorKind::LiteServerBusy
+        );
+        assert!(TonlibErrorKind::Timeout.is_transient());
+        assert!(!TonlibErrorKind::BlockNotFound.is_transient());
+
+        let error = TonClientError::InternalError("test".to_string());
+        assert_eq!(error.tonlib_error_kind(), None);
+    }
+
+    #[test]
+    fn test_exit_code() {
+        let error = tonlib_error(
+            "LITE_SERVER_UNKNOWN: cannot apply external message to current state : \
+            External message was not accepted\nCannot run message on account: inbound external \
+            message rejected by transaction 1A2B: exitcode=33, steps=22, gas_used=0",
+        );
+        assert_eq!(error.exit_code(), Some(33));
+        let error = tonlib_error("cannot run get method: exit code: -14");
+        assert_eq!(error.exit_code(), Some(-14));
+        assert_eq!(tonlib_error("LITE_SERVER_NOTREADY").exit_code(), None);
+        assert_eq!(tonlib_error("exit code unknown").exit_code(), None);
+    }
+
+    #[test]
+    fn test_is_retryable() {
+        assert!(tonlib_error("LITE_SERVER_NOTREADY").is_retryable());
+        assert!(tonlib_error("LITE_SERVER_RATELIMIT: too many requests").is_retryable());
+        assert!(!tonlib_error("LITE_SERVER_UNKNOWN: block is not in db").is_retryable());
+        assert!(!tonlib_error("cannot apply external message to current state").is_retryable());
+        let error = TonClientError::Timeout {
+            method: "test",
+            elapsed: std::time::Duration::from_secs(1),
+        };
+        assert!(error.is_retryable());
+        assert!(TonClientError::ConnectionReset { method: "test" }.is_retryable());
+        let http_error = |status| TonClientError::HttpError {
+            method: "test",
+            status,
+            message: "test".to_string(),
+        };
+        assert!(http_error(None).is_retryable());
+        assert!(http_error(Some(503)).is_retryable());
+        assert!(!http_error(Some(401)).is_retryable());
+        assert!(!TonClientError::InternalError("test".to_string()).is_retryable());
+    }
 }
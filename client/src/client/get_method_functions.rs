@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use tonlib_core::TonAddress;
+
+use crate::client::TonClientInterface;
+use crate::contract::{SmcHandle, TonContractError, TonContractState};
+use crate::tl::TvmStackEntry as TlTvmStackEntry;
+use crate::types::{TonMethodId, TvmStackEntry, TvmSuccess};
+
+pub const DEFAULT_GET_METHOD_BATCH_CONCURRENCY: usize = 16;
+
+/// Get-method call of `run_get_methods_batch`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetMethodRequest {
+    pub address: TonAddress,
+    pub method: TonMethodId,
+    pub stack: Vec<TvmStackEntry>,
+}
+
+impl GetMethodRequest {
+    pub fn new<M: Into<TonMethodId>>(
+        address: &TonAddress,
+        method: M,
+        stack: &[TvmStackEntry],
+    ) -> GetMethodRequest {
+        GetMethodRequest {
+            address: address.clone(),
+            method: method.into(),
+            stack: stack.to_vec(),
+        }
+    }
+}
+
+/// High-level functions for running get-methods of many contracts
+#[async_trait]
+pub trait TonGetMethodFunctions: TonClientInterface + Send + Sync {
+    /// Runs get-methods of `requests` with at most `concurrency` contracts loaded at a time
+    /// and returns the results in the order of `requests`.
+    ///
+    /// Requests to the same address are run on a single `smc.load` of the latest account state,
+    /// which is released with `smc.forget` afterwards. Different contracts are loaded
+    /// independently, so they're spread across connections of a pool.
+    ///
+    /// A non-zero exit code fails the request with `TonContractError::TvmRunError`, as
+    /// `TonContractInterface::run_get_method` does. If loading a contract with several
+    /// requests fails, all of them fail with `TonContractError::SharedClientError`.
+    async fn run_get_methods_batch(
+        &self,
+        requests: &[GetMethodRequest],
+        concurrency: usize,
+    ) -> Vec<Result<TvmSuccess, TonContractError>> {
+        // Groups own their requests, so that the futures don't borrow `requests`
+        let mut groups: Vec<(TonAddress, Vec<(usize, GetMethodRequest)>)> = vec![];
+        let mut group_indices: HashMap<&TonAddress, usize> = HashMap::new();
+        for (i, request) in requests.iter().enumerate() {
+            let group = *group_indices.entry(&request.address).or_insert_with(|| {
+                groups.push((request.address.clone(), vec![]));
+                groups.len() - 1
+            });
+            groups[group].1.push((i, request.clone()));
+        }
+
+        let mut results: Vec<Option<Result<TvmSuccess, TonContractError>>> =
+            (0..requests.len()).map(|_| None).collect();
+        let mut group_results = futures::stream::iter(groups)
+            .map(|(address, group)| run_contract_requests(self, address, group))
+            .buffer_unordered(concurrency.max(1));
+        while let Some(group_result) = group_results.next().await {
+            for (i, result) in group_result {
+                results[i] = Some(result);
+            }
+        }
+        results
+            .into_iter()
+            .map(|r| r.expect("every request belongs to a group"))
+            .collect()
+    }
+}
+
+impl<T> TonGetMethodFunctions for T where T: TonClientInterface + Send + Sync {}
+
+/// Runs `requests`, all of them to `address`, on a single loaded contract, returning results
+/// with the indices of their requests.
+async fn run_contract_requests<C: TonClientInterface + ?Sized>(
+    client: &C,
+    address: TonAddress,
+    requests: Vec<(usize, GetMethodRequest)>,
+) -> Vec<(usize, Result<TvmSuccess, TonContractError>)> {
+    let state = match client.smc_load(&address).await {
+        Ok(state) => state,
+        Err(e) if requests.len() == 1 => return vec![(requests[0].0, Err(e.into()))],
+        Err(e) => {
+            let error = Arc::new(e);
+            return requests
+                .iter()
+                .map(|(i, _)| (*i, Err(TonContractError::SharedClientError(error.clone()))))
+                .collect();
+        }
+    };
+    let mut results = Vec::with_capacity(requests.len());
+    for (i, request) in requests {
+        let result = run_loaded_contract_request(&state, &address, &request).await;
+        results.push((i, result));
+    }
+    if let Err(e) = state.forget().await {
+        log::warn!("Failed to forget contract {}: {}", address, e);
+    }
+    results
+}
+
+pub(crate) async fn run_loaded_contract_request(
+    state: &SmcHandle,
+    address: &TonAddress,
+    request: &GetMethodRequest,
+) -> Result<TvmSuccess, TonContractError> {
+    let stack_parse_error = |error| TonContractError::TvmStackParseError {
+        method: request.method.clone(),
+        address: address.clone(),
+        error,
+    };
+    let stack = request
+        .stack
+        .iter()
+        .map(TlTvmStackEntry::try_from)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(stack_parse_error)?;
+    let run_result = state
+        .conn
+        .smc_run_get_method(state.id, &request.method, &stack)
+        .await?;
+    let result = TvmSuccess::try_from(&run_result).map_err(stack_parse_error)?;
+    TonContractState::raise_exit_error(address, &request.method, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use tonlib_core::TonAddress;
+
+    use super::{GetMethodRequest, TonGetMethodFunctions};
+    use crate::client::{
+        TonClientError, TonClientInterface, TonConnection, TonConnectionParams,
+        NOOP_CONNECTION_CALLBACK,
+    };
+    use crate::contract::TonContractError;
+    use crate::tl::{TonFunction, TonResult};
+
+    /// Fails every `smc.load` with the address in the error message.
+    struct MockClient {
+        connection: TonConnection,
+    }
+
+    #[async_trait]
+    impl TonClientInterface for MockClient {
+        async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+            Ok(self.connection.clone())
+        }
+
+        async fn invoke_on_connection(
+            &self,
+            function: &TonFunction,
+        ) -> Result<(TonConnection, TonResult), TonClientError> {
+            match function {
+                TonFunction::SmcLoad { account_address } => Err(TonClientError::TonlibError {
+                    method: "smc.load",
+                    code: 500,
+                    message: account_address.account_address.clone(),
+                }),
+                f => panic!("Unexpected function: {:?}", f),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_get_methods_batch_keeps_order() {
+        let client = MockClient {
+            connection: TonConnection::new(
+                NOOP_CONNECTION_CALLBACK.clone(),
+                &TonConnectionParams::default(),
+            )
+            .unwrap(),
+        };
+        let first = TonAddress::new(0, &[1; 32]);
+        let second = TonAddress::new(0, &[2; 32]);
+        let requests = vec![
+            GetMethodRequest::new(&first, "seqno", &[]),
+            GetMethodRequest::new(&second, "seqno", &[]),
+            GetMethodRequest::new(&first, "get_public_key", &[]),
+        ];
+        let results = client.run_get_methods_batch(&requests, 2).await;
+        assert_eq!(results.len(), 3);
+        let error = |result: &Result<_, TonContractError>| match result {
+            Err(TonContractError::SharedClientError(e)) => Some(e.clone()),
+            Err(TonContractError::ClientError(TonClientError::TonlibError { message, .. })) => {
+                assert_eq!(message, &second.to_hex());
+                None
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        };
+        // smc.load fails once per address, with the same error for all its requests
+        let shared = error(&results[0]).unwrap();
+        assert!(matches!(
+            shared.as_ref(),
+            TonClientError::TonlibError { message, .. } if message == &first.to_hex()
+        ));
+        assert!(error(&results[1]).is_none());
+        assert!(Arc::ptr_eq(&shared, &error(&results[2]).unwrap()));
+    }
+}
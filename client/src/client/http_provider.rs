@@ -0,0 +1,364 @@
+use std::str::FromStr;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use num_bigint::BigInt;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tonlib_core::cell::{BagOfCells, CellSlice};
+use tonlib_core::TonAddress;
+
+use crate::client::{TonClientError, TonProvider};
+use crate::contract::{TonContractError, TonContractState};
+use crate::tl::{BlocksMasterchainInfo, RawExtMessageInfo, RawFullAccountState};
+use crate::types::{StackParseError, TonMethodId, TvmStackEntry, TvmSuccess};
+
+/// JSON-RPC endpoint of toncenter API v2 for mainnet.
+pub const TONCENTER_MAINNET_URL: &str = "https://toncenter.com/api/v2/jsonRPC";
+/// JSON-RPC endpoint of toncenter API v2 for testnet.
+pub const TONCENTER_TESTNET_URL: &str = "https://testnet.toncenter.com/api/v2/jsonRPC";
+
+const DEFAULT_HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Provider backed by the JSON-RPC endpoint of toncenter API v2 or a compatible HTTP API,
+/// e.g. as a fallback of `TonClient` when liteservers are unreachable, see `FallbackProvider`.
+///
+/// Errors reported by the API are returned as `TonClientError::TonlibError`, as the API
+/// forwards them from tonlib, failed requests and server errors (codes 500 to 504) as
+/// retryable `TonClientError::HttpError`.
+pub struct HttpProvider {
+    client: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+}
+
+impl HttpProvider {
+    /// Creates a provider sending requests to the JSON-RPC endpoint `url`, e.g. `TONCENTER_MAINNET_URL`.
+    pub fn new(url: &str) -> Result<HttpProvider, TonClientError> {
+        Self::with_timeout(url, DEFAULT_HTTP_TIMEOUT)
+    }
+
+    /// Creates a provider failing requests which take longer than `timeout`.
+    pub fn with_timeout(url: &str, timeout: Duration) -> Result<HttpProvider, TonClientError> {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .map_err(|e| TonClientError::InternalError(format!("Fail to build client: {}", e)))?;
+        Ok(HttpProvider {
+            client,
+            url: url.to_string(),
+            api_key: None,
+        })
+    }
+
+    /// Sets the key sent in `X-API-Key` header, requests without it have a low rate limit.
+    pub fn with_api_key(&mut self, api_key: &str) -> &mut Self {
+        self.api_key = Some(api_key.to_string());
+        self
+    }
+
+    async fn call<R: DeserializeOwned>(
+        &self,
+        method: &'static str,
+        params: Value,
+    ) -> Result<R, TonClientError> {
+        let request = json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+        });
+        let mut builder = self
+            .client
+            .post(&self.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(request.to_string());
+        if let Some(api_key) = &self.api_key {
+            builder = builder.header("X-API-Key", api_key);
+        }
+        let http_error = |status: Option<u16>, message: String| TonClientError::HttpError {
+            method,
+            status,
+            message,
+        };
+        let response = builder
+            .send()
+            .await
+            .map_err(|e| http_error(e.status().map(|s| s.as_u16()), e.to_string()))?;
+        let status = response.status().as_u16();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| http_error(Some(status), e.to_string()))?;
+        response_result(method, status, body)
+    }
+}
+
+/// Server errors of the API, e.g. 504 if the liteserver behind it timed out, are returned as
+/// `TonClientError::HttpError` to be retried as failed requests.
+fn response_result<R: DeserializeOwned>(
+    method: &'static str,
+    status: u16,
+    body: String,
+) -> Result<R, TonClientError> {
+    match serde_json::from_str::<RpcResponse>(&body) {
+        Ok(RpcResponse {
+            ok: true,
+            result: Some(result),
+            ..
+        }) => serde_json::from_value(result).map_err(|e| {
+            TonClientError::InternalError(format!("Unexpected {} result: {}", method, e))
+        }),
+        Ok(RpcResponse {
+            error: Some(message),
+            code,
+            ..
+        }) => {
+            let code = code.unwrap_or(status as i32);
+            if (500..=504).contains(&code) {
+                Err(TonClientError::HttpError {
+                    method,
+                    status: Some(code as u16),
+                    message,
+                })
+            } else {
+                Err(TonClientError::TonlibError {
+                    method,
+                    code,
+                    message,
+                })
+            }
+        }
+        _ => Err(TonClientError::HttpError {
+            method,
+            status: Some(status),
+            message: body,
+        }),
+    }
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    ok: bool,
+    result: Option<Value>,
+    error: Option<String>,
+    code: Option<i32>,
+}
+
+/// Result of `runGetMethod` with the stack in the legacy toncenter format.
+#[derive(Deserialize)]
+struct RunGetMethodResult {
+    gas_used: i32,
+    exit_code: i32,
+    stack: Vec<Value>,
+}
+
+#[async_trait]
+impl TonProvider for HttpProvider {
+    async fn get_masterchain_info(&self) -> Result<BlocksMasterchainInfo, TonClientError> {
+        self.call("getMasterchainInfo", json!({})).await
+    }
+
+    async fn get_raw_account_state(
+        &self,
+        address: &TonAddress,
+    ) -> Result<RawFullAccountState, TonClientError> {
+        let params = json!({ "address": address.to_hex() });
+        self.call("getAddressInformation", params).await
+    }
+
+    async fn run_get_method(
+        &self,
+        address: &TonAddress,
+        method: &TonMethodId,
+        stack: &[TvmStackEntry],
+    ) -> Result<TvmSuccess, TonContractError> {
+        let stack_parse_error = |error| TonContractError::TvmStackParseError {
+            method: method.clone(),
+            address: address.clone(),
+            error,
+        };
+        let stack = stack
+            .iter()
+            .map(stack_entry_to_json)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(stack_parse_error)?;
+        let method_json = match method {
+            TonMethodId::Number(id) => json!(id),
+            TonMethodId::Name(name) => json!(name),
+        };
+        let params = json!({
+            "address": address.to_hex(),
+            "method": method_json,
+            "stack": stack,
+        });
+        let result: RunGetMethodResult = self.call("runGetMethod", params).await?;
+        let stack = result
+            .stack
+            .iter()
+            .map(stack_entry_from_json)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(stack_parse_error)?;
+        let result = TvmSuccess {
+            vm_log: None,
+            vm_exit_code: result.exit_code,
+            stack,
+            missing_library: None,
+            gas_used: result.gas_used,
+        };
+        TonContractState::raise_exit_error(address, method, result)
+    }
+
+    async fn send_raw_message_return_hash(&self, body: &[u8]) -> Result<Vec<u8>, TonClientError> {
+        let params = json!({ "boc": STANDARD.encode(body) });
+        let result: RawExtMessageInfo = self.call("sendBocReturnHash", params).await?;
+        Ok(result.hash)
+    }
+}
+
+/// Converts `entry` to the stack format of toncenter `runGetMethod`, e.g. `["num", "0x1"]`.
+fn stack_entry_to_json(entry: &TvmStackEntry) -> Result<Value, StackParseError> {
+    let boc = |cell| -> Result<String, StackParseError> {
+        Ok(STANDARD.encode(BagOfCells::from_root(cell).serialize(false)?))
+    };
+    let value = match entry {
+        TvmStackEntry::Int64(value) => json!(["num", hex_number(&BigInt::from(*value))]),
+        TvmStackEntry::Int257(value) => json!(["num", hex_number(value)]),
+        TvmStackEntry::Cell(cell) => json!(["tvm.Cell", boc(cell.as_ref().clone())?]),
+        TvmStackEntry::Slice(slice) => json!(["tvm.Slice", boc(slice.into_cell()?)?]),
+        entry => {
+            return Err(StackParseError::InvalidEntryValue(format!(
+                "{} can't be passed to toncenter",
+                entry
+            )))
+        }
+    };
+    Ok(value)
+}
+
+/// Converts a result stack entry of toncenter `runGetMethod`, entries other than numbers,
+/// cells and slices are returned as `TvmStackEntry::Unsupported`.
+fn stack_entry_from_json(value: &Value) -> Result<TvmStackEntry, StackParseError> {
+    let invalid = || StackParseError::InvalidEntryValue(format!("invalid entry: {}", value));
+    let entry_value = value.get(1);
+    let boc = || -> Result<Vec<u8>, StackParseError> {
+        let bytes = entry_value
+            .and_then(|v| v["bytes"].as_str())
+            .ok_or_else(invalid)?;
+        STANDARD.decode(bytes).map_err(|_| invalid())
+    };
+    let entry = match value.get(0).and_then(Value::as_str).ok_or_else(invalid)? {
+        "num" => {
+            let number = entry_value.and_then(Value::as_str).ok_or_else(invalid)?;
+            TvmStackEntry::number(parse_hex_number(number).ok_or_else(invalid)?)
+        }
+        "null" => TvmStackEntry::Null,
+        "cell" => TvmStackEntry::cell(&boc()?)?,
+        "slice" => {
+            let cell = BagOfCells::parse(&boc()?)?.single_root()?.clone();
+            TvmStackEntry::Slice(CellSlice::full_cell(cell.as_ref().clone())?)
+        }
+        _ => TvmStackEntry::Unsupported,
+    };
+    Ok(entry)
+}
+
+fn hex_number(value: &BigInt) -> String {
+    match value.sign() {
+        num_bigint::Sign::Minus => format!("-0x{:x}", value.magnitude()),
+        _ => format!("0x{:x}", value.magnitude()),
+    }
+}
+
+fn parse_hex_number(value: &str) -> Option<BigInt> {
+    let (negative, value) = match value.strip_prefix('-') {
+        Some(value) => (true, value),
+        None => (false, value),
+    };
+    let number = match value.strip_prefix("0x") {
+        Some(hex) => BigInt::parse_bytes(hex.as_bytes(), 16)?,
+        None => BigInt::from_str(value).ok()?,
+    };
+    Some(if negative { -number } else { number })
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigInt;
+    use serde_json::json;
+    use tonlib_core::cell::CellBuilder;
+
+    use super::{response_result, stack_entry_from_json, stack_entry_to_json};
+    use crate::client::TonClientError;
+    use crate::types::TvmStackEntry;
+
+    #[test]
+    fn test_response_errors() {
+        let result = |status, body: &str| {
+            response_result::<u32>("test", status, body.to_string()).unwrap_err()
+        };
+        let body = r#"{"ok": false, "error": "LITE_SERVER_NOTREADY", "code": 500}"#;
+        let error = result(200, body);
+        assert!(matches!(
+            error,
+            TonClientError::HttpError {
+                status: Some(500),
+                ..
+            }
+        ));
+        assert!(error.is_retryable());
+        let error = result(504, r#"{"ok": false, "error": "timeout"}"#);
+        assert!(error.is_retryable());
+        let body = r#"{"ok": false, "error": "cannot apply external message", "code": 400}"#;
+        let error = result(200, body);
+        assert!(matches!(
+            error,
+            TonClientError::TonlibError { code: 400, .. }
+        ));
+        assert!(!error.is_retryable());
+        assert_eq!(
+            response_result::<u32>("test", 200, r#"{"ok": true, "result": 7}"#.to_string())
+                .unwrap(),
+            7
+        );
+    }
+
+    #[test]
+    fn test_toncenter_stack() -> anyhow::Result<()> {
+        let big = BigInt::from(u64::MAX) * 16u32;
+        assert_eq!(
+            stack_entry_to_json(&TvmStackEntry::Int64(-255))?,
+            json!(["num", "-0xff"])
+        );
+        assert_eq!(
+            stack_entry_to_json(&TvmStackEntry::Int257(big.clone()))?,
+            json!(["num", "0xffffffffffffffff0"])
+        );
+        assert!(stack_entry_to_json(&TvmStackEntry::Null).is_err());
+
+        assert_eq!(
+            stack_entry_from_json(&json!(["num", "-0xff"]))?,
+            TvmStackEntry::Int64(-255)
+        );
+        assert_eq!(
+            stack_entry_from_json(&json!(["num", "0xffffffffffffffff0"]))?,
+            TvmStackEntry::Int257(big)
+        );
+
+        let cell = CellBuilder::new().store_u32(32, 7)?.build()?;
+        let json = stack_entry_to_json(&TvmStackEntry::Cell(cell.clone().into()))?;
+        let entry = json!(["cell", { "bytes": json[1] }]);
+        assert_eq!(
+            stack_entry_from_json(&entry)?,
+            TvmStackEntry::Cell(cell.into())
+        );
+        assert_eq!(
+            stack_entry_from_json(&json!(["list", { "elements": [] }]))?,
+            TvmStackEntry::Unsupported
+        );
+        Ok(())
+    }
+}
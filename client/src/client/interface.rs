@@ -1,14 +1,21 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
+use tonlib_core::cell::BagOfCells;
+use tonlib_core::config::ConfigParam;
 use tonlib_core::TonAddress;
 
 use super::{SmcLibraryQueryExt, SmcLibraryResult, SmcLibraryResultExt, TonLibraryId};
-use crate::client::{TonClientError, TonConnection};
-use crate::contract::LoadedSmcState;
+use crate::client::{
+    AccountInfo, MasterchainBlockStream, SentMessage, TonClientError, TonConnection,
+};
+use crate::contract::SmcHandle;
 use crate::tl::{
-    AccountAddress, BlockId, BlockIdExt, BlocksAccountTransactionId, BlocksHeader,
-    BlocksMasterchainInfo, BlocksShards, BlocksTransactions, BlocksTransactionsExt, ConfigInfo,
-    FullAccountState, InternalTransactionId, LiteServerInfo, RawFullAccountState, RawTransactions,
-    TonFunction, TonResult, TonResultDiscriminants, TvmCell,
+    AccountAddress, BlockId, BlockIdExt, BlocksAccountTransactionId, BlocksBlockSignatures,
+    BlocksHeader, BlocksMasterchainInfo, BlocksOutMsgQueueSizes, BlocksShardBlockProof,
+    BlocksShards, BlocksTransactions, BlocksTransactionsExt, ConfigInfo, FullAccountState,
+    InternalTransactionId, LiteServerInfo, RawFullAccountState, RawTransactions, TonFunction,
+    TonResult, TonResultDiscriminants, TvmCell,
 };
 
 #[async_trait]
@@ -43,6 +50,15 @@ pub trait TonClientInterface: Send + Sync {
         }
     }
 
+    /// Returns the state of the account with parsed code and data.
+    ///
+    /// `address` is accepted in both raw and user-friendly forms.
+    async fn get_account_info(&self, address: &str) -> Result<AccountInfo, TonClientError> {
+        let address: TonAddress = address.parse()?;
+        let state = self.get_raw_account_state(&address).await?;
+        AccountInfo::from_raw(&address, &state)
+    }
+
     async fn get_raw_account_state_by_transaction(
         &self,
         account_address: &TonAddress,
@@ -114,14 +130,20 @@ pub trait TonClientInterface: Send + Sync {
         let func = TonFunction::RawSendMessage {
             body: body.to_vec(),
         };
-        self.invoke(&func).await?.expect_ok()
+        self.invoke(&func)
+            .await
+            .map_err(TonClientError::into_external_message_error)?
+            .expect_ok()
     }
 
     async fn send_raw_message_return_hash(&self, body: &[u8]) -> Result<Vec<u8>, TonClientError> {
         let func = TonFunction::RawSendMessageReturnHash {
             body: body.to_vec(),
         };
-        let result = self.invoke(&func).await?;
+        let result = self
+            .invoke(&func)
+            .await
+            .map_err(TonClientError::into_external_message_error)?;
         match result {
             TonResult::RawExtMessageInfo(info) => Ok(info.hash),
             r => Err(TonClientError::unexpected_ton_result(
@@ -131,6 +153,28 @@ pub trait TonClientInterface: Send + Sync {
         }
     }
 
+    /// Sends an external message with `body` and returns its hash.
+    ///
+    /// Unlike `send_raw_message_return_hash`, a message that had already been sent isn't
+    /// an error: it's to be processed anyway, so the hash is returned with `duplicate` set.
+    /// Messages rejected by the lite server fail with `TonClientError::ExternalMessageRejected`.
+    async fn send_message(&self, body: &[u8]) -> Result<SentMessage, TonClientError> {
+        match self.send_raw_message_return_hash(body).await {
+            Ok(hash) => Ok(SentMessage {
+                hash,
+                duplicate: false,
+            }),
+            Err(e) if e.is_duplicate_message() => {
+                let hash = BagOfCells::parse(body)?.single_root()?.cell_hash();
+                Ok(SentMessage {
+                    hash: hash.to_vec(),
+                    duplicate: true,
+                })
+            }
+            Err(e) => Err(e),
+        }
+    }
+
     async fn sync(&self) -> Result<(TonConnection, BlockIdExt), TonClientError> {
         let func = TonFunction::Sync {};
         let (conn, result) = self.invoke_on_connection(&func).await?;
@@ -162,10 +206,7 @@ pub trait TonClientInterface: Send + Sync {
         }
     }
 
-    async fn smc_load(
-        &self,
-        account_address: &TonAddress,
-    ) -> Result<LoadedSmcState, TonClientError> {
+    async fn smc_load(&self, account_address: &TonAddress) -> Result<SmcHandle, TonClientError> {
         let func = TonFunction::SmcLoad {
             account_address: AccountAddress {
                 account_address: account_address.to_hex(),
@@ -173,10 +214,7 @@ pub trait TonClientInterface: Send + Sync {
         };
         let (conn, result) = self.invoke_on_connection(&func).await?;
         match result {
-            TonResult::SmcInfo(smc_info) => Ok(LoadedSmcState {
-                conn,
-                id: smc_info.id,
-            }),
+            TonResult::SmcInfo(smc_info) => Ok(SmcHandle::new(conn, smc_info.id)),
             r => Err(TonClientError::unexpected_ton_result(
                 TonResultDiscriminants::SmcInfo,
                 r,
@@ -187,7 +225,7 @@ pub trait TonClientInterface: Send + Sync {
         &self,
         address: &TonAddress,
         tx_id: &InternalTransactionId,
-    ) -> Result<LoadedSmcState, TonClientError> {
+    ) -> Result<SmcHandle, TonClientError> {
         let func = TonFunction::SmcLoadByTransaction {
             account_address: AccountAddress {
                 account_address: address.to_hex(),
@@ -196,10 +234,7 @@ pub trait TonClientInterface: Send + Sync {
         };
         let (conn, result) = self.invoke_on_connection(&func).await?;
         match result {
-            TonResult::SmcInfo(smc_info) => Ok(LoadedSmcState {
-                conn,
-                id: smc_info.id,
-            }),
+            TonResult::SmcInfo(smc_info) => Ok(SmcHandle::new(conn, smc_info.id)),
             r => Err(TonClientError::unexpected_ton_result(
                 TonResultDiscriminants::SmcInfo,
                 r,
@@ -207,10 +242,16 @@ pub trait TonClientInterface: Send + Sync {
         }
     }
 
-    async fn smc_forget(&self, id: i64) -> Result<TonResult, TonClientError> {
+    async fn smc_forget(&self, id: i64) -> Result<(), TonClientError> {
         let func = TonFunction::SmcForget { id };
         let result = self.invoke(&func).await?;
-        Ok(result)
+        match result {
+            TonResult::Ok {} => Ok(()),
+            r => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::Ok,
+                r,
+            )),
+        }
     }
 
     async fn smc_get_code(&self, id: i64) -> Result<TvmCell, TonClientError> {
@@ -297,6 +338,15 @@ pub trait TonClientInterface: Send + Sync {
         }
     }
 
+    /// Returns a stream of new masterchain blocks polled every `poll_interval`,
+    /// see `MasterchainBlockStream`. Use `BlockStream` to get shard blocks as well.
+    fn block_stream(&self, poll_interval: Duration) -> MasterchainBlockStream
+    where
+        Self: Clone + Sized + 'static,
+    {
+        MasterchainBlockStream::new(self, poll_interval)
+    }
+
     async fn get_block_shards(
         &self,
         block_id: &BlockIdExt,
@@ -340,6 +390,53 @@ pub trait TonClientInterface: Send + Sync {
         }
     }
 
+    /// Finds block by workchain, shard and seqno, i.e. `lookup_block` with mode `1`.
+    async fn lookup_block_by_seqno(
+        &self,
+        workchain: i32,
+        shard: i64,
+        seqno: i32,
+    ) -> Result<BlockIdExt, TonClientError> {
+        let block_id = BlockId {
+            workchain,
+            shard,
+            seqno,
+        };
+        self.lookup_block(1, &block_id, 0, 0).await
+    }
+
+    /// Finds the block of workchain and shard containing logical time `lt`,
+    /// i.e. `lookup_block` with mode `2`.
+    async fn lookup_block_by_lt(
+        &self,
+        workchain: i32,
+        shard: i64,
+        lt: i64,
+    ) -> Result<BlockIdExt, TonClientError> {
+        let block_id = BlockId {
+            workchain,
+            shard,
+            seqno: 0,
+        };
+        self.lookup_block(2, &block_id, lt, 0).await
+    }
+
+    /// Finds the block of workchain and shard generated at `utime`,
+    /// i.e. `lookup_block` with mode `4`.
+    async fn lookup_block_by_utime(
+        &self,
+        workchain: i32,
+        shard: i64,
+        utime: i32,
+    ) -> Result<BlockIdExt, TonClientError> {
+        let block_id = BlockId {
+            workchain,
+            shard,
+            seqno: 0,
+        };
+        self.lookup_block(4, &block_id, 0, utime).await
+    }
+
     /// Returns up to specified number of ids of transactions in specified block.
     ///
     /// * `block_id`: ID of the block to retrieve transactions for (either masterchain or shard).
@@ -423,6 +520,67 @@ pub trait TonClientInterface: Send + Sync {
         }
     }
 
+    /// Returns the validator signatures of the masterchain block with `seqno`.
+    async fn get_masterchain_block_signatures(
+        &self,
+        seqno: i32,
+    ) -> Result<BlocksBlockSignatures, TonClientError> {
+        let func = TonFunction::BlocksGetMasterchainBlockSignatures { seqno };
+        let result = self.invoke(&func).await?;
+        match result {
+            TonResult::BlocksBlockSignatures(signatures) => Ok(signatures),
+            r => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::BlocksBlockSignatures,
+                r,
+            )),
+        }
+    }
+
+    /// Returns the proof of shard block `block_id` being included in the masterchain.
+    ///
+    /// * `from`: Masterchain block to build the proof from, the latest one if `None`.
+    async fn get_shard_block_proof(
+        &self,
+        block_id: &BlockIdExt,
+        from: Option<&BlockIdExt>,
+    ) -> Result<BlocksShardBlockProof, TonClientError> {
+        let func = TonFunction::BlocksGetShardBlockProof {
+            id: block_id.clone(),
+            mode: if from.is_some() { 1 } else { 0 },
+            from: from.cloned(),
+        };
+        let result = self.invoke(&func).await?;
+        match result {
+            TonResult::BlocksShardBlockProof(proof) => Ok(proof),
+            r => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::BlocksShardBlockProof,
+                r,
+            )),
+        }
+    }
+
+    /// Returns the sizes of outbound message queues of the latest shard blocks.
+    ///
+    /// * `shard`: Workchain and shard to return the size for, all shards if `None`.
+    async fn get_out_msg_queue_sizes(
+        &self,
+        shard: Option<(i32, i64)>,
+    ) -> Result<BlocksOutMsgQueueSizes, TonClientError> {
+        let (mode, wc, shard) = match shard {
+            Some((wc, shard)) => (1, wc, shard),
+            None => (0, 0, 0),
+        };
+        let func = TonFunction::BlocksGetOutMsgQueueSizes { mode, wc, shard };
+        let result = self.invoke(&func).await?;
+        match result {
+            TonResult::BlocksOutMsgQueueSizes(sizes) => Ok(sizes),
+            r => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::BlocksOutMsgQueueSizes,
+                r,
+            )),
+        }
+    }
+
     async fn get_config_param(&self, mode: u32, param: u32) -> Result<ConfigInfo, TonClientError> {
         let func = TonFunction::GetConfigParam { mode, param };
         let result = self.invoke(&func).await?;
@@ -447,6 +605,15 @@ pub trait TonClientInterface: Send + Sync {
         }
     }
 
+    /// Fetches config param `param` and decodes it into [`ConfigParam`], see
+    /// [`ConfigParam::parse`] for the supported params.
+    async fn get_parsed_config_param(&self, param: u32) -> Result<ConfigParam, TonClientError> {
+        let config_info = self.get_config_param(0, param).await?;
+        let boc = BagOfCells::parse(&config_info.config.bytes)?;
+        let cell = boc.single_root()?;
+        Ok(ConfigParam::parse(param, cell)?)
+    }
+
     async fn get_log_verbosity_level(&self) -> Result<u32, TonClientError> {
         let func = TonFunction::GetLogVerbosityLevel {};
         let result = self.invoke(&func).await?;
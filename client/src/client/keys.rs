@@ -0,0 +1,153 @@
+//! Keys kept in the keystore of tonlib.
+//!
+//! Secrets of the keys are stored by tonlib encrypted with a local password, so that the
+//! application has to hold only [`Key`], i.e. the public key and the encrypted secret.
+//! Note that every connection of a pool has its own keystore, so keys must be used with
+//! the connection they were created on.
+use async_trait::async_trait;
+use tonlib_core::message::TonMessageError;
+use tonlib_core::mnemonic::KeyPair;
+use tonlib_core::wallet::{Signature, Signer};
+use tonlib_core::TonHash;
+use zeroize::Zeroizing;
+
+use crate::client::{TonClientError, TonClientInterface, TonConnection};
+use crate::tl::{ExportedKey, InputKey, Key, TonFunction, TonResult, TonResultDiscriminants};
+
+/// Keystore of a connection, encrypting keys with the local password.
+#[derive(Clone)]
+pub struct TonKeyStore {
+    connection: TonConnection,
+    local_password: Vec<u8>,
+}
+
+impl TonKeyStore {
+    pub fn new(connection: TonConnection, local_password: &[u8]) -> TonKeyStore {
+        TonKeyStore {
+            connection,
+            local_password: local_password.to_vec(),
+        }
+    }
+
+    pub fn connection(&self) -> &TonConnection {
+        &self.connection
+    }
+
+    /// Creates a new key with a random mnemonic protected with `mnemonic_password`.
+    pub async fn create_new_key(
+        &self,
+        mnemonic_password: &[u8],
+        random_extra_seed: &[u8],
+    ) -> Result<Key, TonClientError> {
+        let func = TonFunction::CreateNewKey {
+            local_password: self.local_password.clone(),
+            mnemonic_password: mnemonic_password.to_vec(),
+            random_extra_seed: random_extra_seed.to_vec(),
+        };
+        self.invoke_key_function(&func).await
+    }
+
+    /// Imports the key derived from the mnemonic `words` protected with `mnemonic_password`.
+    pub async fn import_key(
+        &self,
+        words: &[String],
+        mnemonic_password: &[u8],
+    ) -> Result<Key, TonClientError> {
+        let func = TonFunction::ImportKey {
+            local_password: self.local_password.clone(),
+            mnemonic_password: mnemonic_password.to_vec(),
+            exported_key: ExportedKey {
+                word_list: words.to_vec(),
+            },
+        };
+        self.invoke_key_function(&func).await
+    }
+
+    /// Returns the mnemonic of the key.
+    pub async fn export_key(&self, key: &Key) -> Result<Vec<String>, TonClientError> {
+        self.connection.export_key(key, &self.local_password).await
+    }
+
+    pub async fn delete_key(&self, key: &Key) -> Result<(), TonClientError> {
+        self.connection.delete_key(key).await
+    }
+
+    /// Signs `data` with the key.
+    ///
+    /// tonlib has no function signing arbitrary data, so the private key is exported
+    /// unencrypted with `exportUnencryptedKey` and `data` is signed locally. The key buffers
+    /// of this function are zeroized once signed, but the key also passes through the JSON
+    /// result of tonlib and its deserialization, which aren't zeroized.
+    pub async fn sign_with_key(&self, key: &Key, data: &[u8]) -> Result<Signature, TonClientError> {
+        let func = TonFunction::ExportUnencryptedKey {
+            input_key: self.input_key(key),
+        };
+        let seed = match self.connection.invoke(&func).await? {
+            TonResult::ExportedUnencryptedKey(exported) => Zeroizing::new(exported.data),
+            r => {
+                return Err(TonClientError::unexpected_ton_result(
+                    TonResultDiscriminants::ExportedUnencryptedKey,
+                    r,
+                ))
+            }
+        };
+        if seed.len() != 32 {
+            return Err(TonClientError::InternalError(format!(
+                "Invalid private key size: {}",
+                seed.len()
+            )));
+        }
+        let secret_key = Zeroizing::new(KeyPair::from_seed(&seed).secret_key);
+        nacl_sign(data, &secret_key)
+    }
+
+    /// Returns a signer of wallet messages using the key.
+    pub fn signer(&self, key: &Key) -> TonKeyStoreSigner {
+        TonKeyStoreSigner {
+            keystore: self.clone(),
+            key: key.clone(),
+        }
+    }
+
+    pub fn input_key(&self, key: &Key) -> InputKey {
+        InputKey::Regular {
+            key: key.clone(),
+            local_password: self.local_password.clone(),
+        }
+    }
+
+    async fn invoke_key_function(&self, func: &TonFunction) -> Result<Key, TonClientError> {
+        match self.connection.invoke(func).await? {
+            TonResult::Key(key) => Ok(key),
+            r => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::Key,
+                r,
+            )),
+        }
+    }
+}
+
+fn nacl_sign(data: &[u8], secret_key: &[u8]) -> Result<Signature, TonClientError> {
+    let sig = nacl::sign::signature(data, secret_key)
+        .map_err(|e| TonClientError::InternalError(format!("Signing failed: {}", e.message)))?;
+    sig.try_into().map_err(|sig: Vec<u8>| {
+        TonClientError::InternalError(format!("Invalid signature size: {}", sig.len()))
+    })
+}
+
+/// Signer of wallet messages with a key of [`TonKeyStore`].
+#[derive(Clone)]
+pub struct TonKeyStoreSigner {
+    keystore: TonKeyStore,
+    key: Key,
+}
+
+#[async_trait]
+impl Signer for TonKeyStoreSigner {
+    async fn sign(&self, hash: &TonHash) -> Result<Signature, TonMessageError> {
+        self.keystore
+            .sign_with_key(&self.key, hash.as_slice())
+            .await
+            .map_err(|e| TonMessageError::SignerError(e.to_string()))
+    }
+}
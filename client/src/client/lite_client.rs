@@ -0,0 +1,710 @@
+use std::error::Error;
+use std::net::{Ipv4Addr, SocketAddrV4};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use adnl::AdnlPeer;
+use async_trait::async_trait;
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio::time::timeout;
+use tokio_tower::multiplex::Client;
+use ton_liteapi::layers::{WrapMessagesLayer, WrapService};
+use ton_liteapi::peer::LitePeer;
+use ton_liteapi::tl::adnl::Message;
+use ton_liteapi::tl::common::{AccountId, BlockIdExt as BlockIdExtLite, Int256};
+use ton_liteapi::tl::request::{
+    GetAccountState, GetBlock, LookupBlock, Request, RunSmcMethod, SendMessage,
+    WaitMasterchainSeqno, WrappedRequest,
+};
+use ton_liteapi::tl::response::{AccountState, BlockData, BlockHeader, MasterchainInfo, Response};
+use ton_liteapi::types::LiteError;
+use tonlib_core::block::Block;
+use tonlib_core::cell::{ArcCell, BagOfCells, Cell, CellParser, TonCellError};
+use tonlib_core::constants::{MASTERCHAIN_ID, SHARD_FULL};
+use tonlib_core::proof::verify_account_state_proof;
+use tonlib_core::TonAddress;
+use tower::{Service, ServiceBuilder, ServiceExt};
+
+use crate::client::{TonClientError, TonProvider};
+use crate::config::{LiteEndpoint, TonConfig};
+use crate::contract::{TonContractError, TonContractState};
+use crate::tl::{BlockIdExt, BlocksMasterchainInfo, InternalTransactionId, RawFullAccountState};
+use crate::types::{build_vm_stack, parse_vm_stack, TonMethodId, TvmStackEntry, TvmSuccess};
+
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+const REQ_TIMEOUT: Duration = Duration::from_secs(10);
+/// Mode of `liteServer.runSmcMethod` returning the result stack without proofs.
+const RUN_METHOD_MODE_RESULT: u32 = 1 << 2;
+
+type ConnService =
+    WrapService<Client<LitePeer<AdnlPeer<TcpStream>>, Box<dyn Error + Sync + Send>, Message>>;
+
+/// Client of a single liteserver speaking the lite API over ADNL, without tonlibjson.
+///
+/// Supports a subset of queries: masterchain info, account states, get-methods, blocks and
+/// sending messages. Responses are not checked against proofs, i.e. the liteserver is trusted,
+/// except for raw account states, whose last transaction is read from the proof of the state.
+///
+/// It implements `TonProvider`, so contract wrappers work with it through `ProviderContract`.
+/// It doesn't implement `TonClientInterface`, which is bound to tonlib connections, use
+/// `TonClient` for everything else. Errors of the liteserver are returned as
+/// `TonClientError::LiteServerError`.
+///
+/// The connection is established on the first request and re-established after a failure,
+/// requests are sent one at a time.
+pub struct LiteClient {
+    connection: Mutex<LiteConnection>,
+}
+
+/// State of an account returned by `LiteClient::get_account_state`.
+#[derive(Debug, Clone)]
+pub struct LiteAccountState {
+    /// Masterchain block the state is taken at.
+    pub block_id: BlockIdExt,
+    /// Shardchain block containing the account.
+    pub shard_block_id: BlockIdExt,
+    /// Root cell of `Account`, `None` if the liteserver returned an empty state.
+    pub state: Option<ArcCell>,
+}
+
+impl LiteClient {
+    /// Creates a client of the liteserver with index `liteserver_index` in the network `config`.
+    pub fn new(config: &str, liteserver_index: usize) -> Result<LiteClient, TonClientError> {
+        let ton_config = TonConfig::from_json(config)
+            .map_err(|e| TonClientError::InternalError(format!("Fail to parse config: {}", e)))?;
+        let endpoint = ton_config
+            .liteservers
+            .get(liteserver_index)
+            .cloned()
+            .ok_or_else(|| {
+                TonClientError::InternalError(format!(
+                    "No liteserver with index {} in config",
+                    liteserver_index
+                ))
+            })?;
+        let connection = LiteConnection::new(endpoint)
+            .map_err(|e| TonClientError::InternalError(format!("Invalid liteserver: {}", e)))?;
+        Ok(LiteClient {
+            connection: Mutex::new(connection),
+        })
+    }
+
+    pub async fn get_masterchain_info(&self) -> Result<BlocksMasterchainInfo, TonClientError> {
+        const METHOD: &str = "liteServer.getMasterchainInfo";
+        let info = self.connection.lock().await.get_mc_info().await;
+        let info = info.map_err(|e| lite_error(METHOD, e))?;
+        Ok(BlocksMasterchainInfo {
+            last: block_id_from_lite(&info.last),
+            state_root_hash: info.state_root_hash.0.to_vec(),
+            init: BlockIdExt {
+                workchain: info.init.workchain,
+                shard: SHARD_FULL as i64,
+                seqno: 0,
+                root_hash: info.init.root_hash.0.to_vec(),
+                file_hash: info.init.file_hash.0.to_vec(),
+            },
+        })
+    }
+
+    /// Returns the state of `address` at the last masterchain block.
+    pub async fn get_account_state(
+        &self,
+        address: &TonAddress,
+    ) -> Result<LiteAccountState, TonClientError> {
+        let state = self.query_account_state(address).await?;
+        let root = if state.state.is_empty() {
+            None
+        } else {
+            Some(BagOfCells::parse(&state.state)?.single_root()?.clone())
+        };
+        Ok(LiteAccountState {
+            block_id: block_id_from_lite(&state.id),
+            shard_block_id: block_id_from_lite(&state.shardblk),
+            state: root,
+        })
+    }
+
+    async fn query_account_state(
+        &self,
+        address: &TonAddress,
+    ) -> Result<AccountState, TonClientError> {
+        const METHOD: &str = "liteServer.getAccountState";
+        let mut connection = self.connection.lock().await;
+        async {
+            let last = connection.get_mc_info().await?.last;
+            let request = Request::GetAccountState(GetAccountState {
+                id: last,
+                account: account_id(address),
+            });
+            match connection.execute(request).await? {
+                Response::AccountState(state) => Ok(state),
+                response => Err(response_error(response)),
+            }
+        }
+        .await
+        .map_err(|e| lite_error(METHOD, e))
+    }
+
+    /// Runs get-method `method` of `address` at the last masterchain block.
+    ///
+    /// The liteserver doesn't report the VM log and gas usage, so `vm_log` is `None`
+    /// and `gas_used` is zero.
+    pub async fn run_get_method(
+        &self,
+        address: &TonAddress,
+        method: &TonMethodId,
+        stack: &[TvmStackEntry],
+    ) -> Result<TvmSuccess, TonClientError> {
+        const METHOD: &str = "liteServer.runSmcMethod";
+        let params = build_vm_stack(stack)
+            .and_then(|cell| Ok(BagOfCells::from_root(cell).serialize(false)?))
+            .map_err(|e| TonClientError::InternalError(format!("Invalid stack: {}", e)))?;
+        let mut connection = self.connection.lock().await;
+        let result = async {
+            let last = connection.get_mc_info().await?.last;
+            let request = Request::RunSmcMethod(RunSmcMethod {
+                mode: RUN_METHOD_MODE_RESULT,
+                id: last,
+                account: account_id(address),
+                method_id: method.to_id() as u64,
+                params,
+            });
+            match connection.execute(request).await? {
+                Response::RunMethodResult(result) => Ok(result),
+                response => Err(response_error(response)),
+            }
+        }
+        .await
+        .map_err(|e| lite_error(METHOD, e))?;
+        let stack = match result.result {
+            Some(boc) => parse_vm_stack(BagOfCells::parse(&boc)?.single_root()?)
+                .map_err(|e| TonClientError::InternalError(format!("Invalid stack: {}", e)))?,
+            None => vec![],
+        };
+        Ok(TvmSuccess {
+            vm_log: None,
+            vm_exit_code: result.exit_code,
+            stack,
+            missing_library: None,
+            gas_used: 0,
+        })
+    }
+
+    /// Returns the block `block_id` downloaded with `liteServer.getBlock`.
+    ///
+    /// Only the root hash of the block is checked against `block_id`.
+    pub async fn get_block(&self, block_id: &BlockIdExt) -> Result<Block, TonClientError> {
+        const METHOD: &str = "liteServer.getBlock";
+        let id = block_id_to_lite(block_id)?;
+        let request = Request::GetBlock(GetBlock { id: id.clone() });
+        let mut connection = self.connection.lock().await;
+        let data = match connection.execute(request).await {
+            Ok(Response::BlockData(block)) => block.data,
+            Ok(response) => return Err(lite_error(METHOD, response_error(response))),
+            Err(e) => return Err(lite_error(METHOD, e)),
+        };
+        let boc = BagOfCells::parse(&data)?;
+        let root = boc.single_root()?;
+        if root.cell_hash().as_slice() != block_id.root_hash.as_slice() {
+            return Err(TonClientError::InternalError(format!(
+                "{}: root hash of the block doesn't match {}",
+                METHOD, id
+            )));
+        }
+        Ok(Block::parse(root)?)
+    }
+
+    /// Sends the external message serialized as `boc`, returns the status reported by the liteserver.
+    pub async fn send_message(&self, boc: &[u8]) -> Result<u32, TonClientError> {
+        const METHOD: &str = "liteServer.sendMessage";
+        let request = Request::SendMessage(SendMessage { body: boc.to_vec() });
+        let mut connection = self.connection.lock().await;
+        match connection.execute(request).await {
+            Ok(Response::SendMsgStatus(status)) => Ok(status.status),
+            Ok(response) => Err(lite_error(METHOD, response_error(response))),
+            Err(e) => Err(lite_error(METHOD, e)),
+        }
+    }
+
+    /// Returns the unix time of the liteserver, e.g. to set the clock offset of
+    /// `ExpirationPolicy`.
+    pub async fn get_time(&self) -> Result<u32, TonClientError> {
+        const METHOD: &str = "liteServer.getTime";
+        let mut connection = self.connection.lock().await;
+        match connection.execute(Request::GetTime).await {
+            Ok(Response::CurrentTime(time)) => Ok(time.now),
+            Ok(response) => Err(lite_error(METHOD, response_error(response))),
+            Err(e) => Err(lite_error(METHOD, e)),
+        }
+    }
+
+    /// Returns the number of seconds the time of the liteserver is ahead of the local clock,
+    /// negative if it's behind.
+    ///
+    /// ```ignore
+    /// let mut policy = ExpirationPolicy::ttl(Duration::from_secs(120));
+    /// policy.with_clock_offset(lite_client.get_clock_offset().await?);
+    /// ```
+    pub async fn get_clock_offset(&self) -> Result<i64, TonClientError> {
+        let now = self.get_time().await?;
+        let local = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_err(|e| TonClientError::InternalError(format!("Invalid local time: {}", e)))?;
+        Ok(now as i64 - local.as_secs() as i64)
+    }
+}
+
+#[async_trait]
+impl TonProvider for LiteClient {
+    async fn get_masterchain_info(&self) -> Result<BlocksMasterchainInfo, TonClientError> {
+        LiteClient::get_masterchain_info(self).await
+    }
+
+    /// Returns the state of `address` in the format of `raw.getAccountState`, with the last
+    /// transaction proved by the shard block the state is taken at.
+    async fn get_raw_account_state(
+        &self,
+        address: &TonAddress,
+    ) -> Result<RawFullAccountState, TonClientError> {
+        let state = self.query_account_state(address).await?;
+        raw_account_state(address, &state)
+    }
+
+    async fn run_get_method(
+        &self,
+        address: &TonAddress,
+        method: &TonMethodId,
+        stack: &[TvmStackEntry],
+    ) -> Result<TvmSuccess, TonContractError> {
+        let result = LiteClient::run_get_method(self, address, method, stack).await?;
+        TonContractState::raise_exit_error(address, method, result)
+    }
+
+    async fn send_raw_message_return_hash(&self, body: &[u8]) -> Result<Vec<u8>, TonClientError> {
+        let hash = BagOfCells::parse(body)?.single_root()?.cell_hash();
+        self.send_message(body).await?;
+        Ok(hash.to_vec())
+    }
+}
+
+/// Converts the response of `liteServer.getAccountState` into `RawFullAccountState`.
+fn raw_account_state(
+    address: &TonAddress,
+    state: &AccountState,
+) -> Result<RawFullAccountState, TonClientError> {
+    let account = if state.state.is_empty() {
+        None
+    } else {
+        Some(BagOfCells::parse(&state.state)?.single_root()?.clone())
+    };
+    let proof = BagOfCells::parse(&state.proof)?;
+    let proved = verify_account_state_proof(
+        &state.shardblk.root_hash.0,
+        &proof.roots,
+        address,
+        account.as_deref(),
+    )
+    .map_err(|e| TonClientError::InternalError(format!("Invalid account state proof: {}", e)))?;
+    let fields = match &account {
+        Some(account) => read_account(account)?,
+        None => AccountFields::default(),
+    };
+    let (last_transaction_id, sync_utime) = match proved {
+        Some(proved) => (
+            InternalTransactionId {
+                lt: proved.last_transaction_lt as i64,
+                hash: proved.last_transaction_hash.to_vec(),
+            },
+            proved.block.gen_utime as i64,
+        ),
+        None => (
+            InternalTransactionId {
+                lt: 0,
+                hash: vec![0; 32],
+            },
+            0,
+        ),
+    };
+    Ok(RawFullAccountState {
+        balance: fields.balance,
+        code: fields.code,
+        data: fields.data,
+        last_transaction_id,
+        block_id: block_id_from_lite(&state.shardblk),
+        frozen_hash: fields.frozen_hash,
+        sync_utime,
+    })
+}
+
+/// Fields of `RawFullAccountState` stored in `Account`, code and data serialized as BoCs.
+#[derive(Debug, Default, PartialEq)]
+struct AccountFields {
+    balance: i64,
+    code: Vec<u8>,
+    data: Vec<u8>,
+    frozen_hash: Vec<u8>,
+}
+
+/// Reads `Account` according to TL-B schema:
+///
+/// ```raw
+/// account_none$0 = Account;
+/// account$1 addr:MsgAddressInt storage_stat:StorageInfo storage:AccountStorage = Account;
+/// storage_info$_ used:StorageUsed storage_extra:StorageExtraInfo last_paid:uint32
+///   due_payment:(Maybe Grams) = StorageInfo;
+/// account_storage$_ last_trans_lt:uint64 balance:CurrencyCollection state:AccountState
+///   = AccountStorage;
+/// account_uninit$00 = AccountState;
+/// account_active$1 _:StateInit = AccountState;
+/// account_frozen$01 state_hash:bits256 = AccountState;
+/// ```
+fn read_account(account: &Cell) -> Result<AccountFields, TonCellError> {
+    let mut parser = account.parser();
+    if !parser.load_bit()? {
+        return Ok(AccountFields::default());
+    }
+    let _address = parser.load_address()?;
+    // storage_used$_ cells:(VarUInteger 7) bits:(VarUInteger 7)
+    skip_var_uint(&mut parser, 3)?;
+    skip_var_uint(&mut parser, 3)?;
+    // storage_extra_none$000 or storage_extra_info$001 dict_hash:uint256
+    if parser.load_u8(3)? == 0b001 {
+        parser.skip_bits(256)?;
+    }
+    parser.skip_bits(32)?; // last_paid
+    if parser.load_bit()? {
+        parser.load_coins()?; // due_payment
+    }
+    parser.skip_bits(64)?; // last_trans_lt
+    let balance = parser.load_coins()?;
+    let _extra_currencies = parser.load_maybe_cell_ref()?;
+    let balance = i64::try_from(balance)
+        .map_err(|e| TonCellError::CellParserError(format!("Balance doesn't fit in i64: {}", e)))?;
+    let mut fields = AccountFields {
+        balance,
+        ..Default::default()
+    };
+    if parser.load_bit()? {
+        // split_depth:(Maybe (## 5)) special:(Maybe TickTock)
+        if parser.load_bit()? {
+            parser.skip_bits(5)?;
+        }
+        if parser.load_bit()? {
+            parser.skip_bits(2)?;
+        }
+        let to_boc = |cell: Option<ArcCell>| -> Result<Vec<u8>, TonCellError> {
+            cell.map_or(Ok(vec![]), |cell| {
+                BagOfCells::from_root(cell.as_ref().clone()).serialize(false)
+            })
+        };
+        fields.code = to_boc(parser.load_maybe_cell_ref()?)?;
+        fields.data = to_boc(parser.load_maybe_cell_ref()?)?;
+    } else if parser.load_bit()? {
+        let mut state_hash = vec![0; 32];
+        parser.load_slice(&mut state_hash)?;
+        fields.frozen_hash = state_hash;
+    }
+    Ok(fields)
+}
+
+fn skip_var_uint(parser: &mut CellParser, len_bits: usize) -> Result<(), TonCellError> {
+    let len = parser.load_u8(len_bits)? as usize;
+    parser.skip_bits(len * 8)
+}
+
+/// Connection to a liteserver, see `LiteClient`.
+pub(crate) struct LiteConnection {
+    public: Vec<u8>,
+    addr: SocketAddrV4,
+    service: Option<ConnService>,
+}
+
+/// Error returned by the liteserver itself.
+#[derive(Debug, thiserror::Error)]
+#[error("Liteserver error (code: {code}, message: {message})")]
+struct LiteServerError {
+    code: i32,
+    message: String,
+}
+
+impl LiteConnection {
+    pub(crate) fn new(endpoint: LiteEndpoint) -> anyhow::Result<Self> {
+        let LiteEndpoint { ip, port, id } = endpoint;
+        let ip_addr = Ipv4Addr::from(ip as u32);
+        let public = BASE64_STANDARD.decode(id.key)?;
+        let addr = SocketAddrV4::new(ip_addr, port);
+        let conn = Self {
+            public,
+            addr,
+            service: None,
+        };
+        Ok(conn)
+    }
+
+    pub(crate) async fn get_block(
+        &mut self,
+        block_id: BlockIdExtLite,
+    ) -> anyhow::Result<BlockData> {
+        let req = WrappedRequest {
+            wait_masterchain_seqno: Some(WaitMasterchainSeqno {
+                seqno: block_id.seqno,
+                timeout_ms: REQ_TIMEOUT.as_millis() as u32,
+            }),
+            request: Request::GetBlock(GetBlock { id: block_id }),
+        };
+        match self.execute_wrapped(req).await? {
+            Response::BlockData(block) => Ok(block),
+            response => Err(response_error(response)),
+        }
+    }
+
+    pub(crate) async fn get_mc_header(&mut self, seqno: u32) -> anyhow::Result<BlockHeader> {
+        let request = Request::LookupBlock(LookupBlock {
+            mode: (),
+            id: ton_liteapi::tl::common::BlockId {
+                workchain: MASTERCHAIN_ID,
+                shard: SHARD_FULL,
+                seqno,
+            },
+            seqno: Some(()),
+            lt: None,
+            utime: None,
+            with_state_update: None,
+            with_value_flow: None,
+            with_extra: None,
+            with_shard_hashes: None,
+            with_prev_blk_signatures: None,
+        });
+        match self.execute(request).await? {
+            Response::BlockHeader(header) => Ok(header),
+            response => Err(response_error(response)),
+        }
+    }
+
+    pub(crate) async fn get_mc_info(&mut self) -> anyhow::Result<MasterchainInfo> {
+        match self.execute(Request::GetMasterchainInfo).await? {
+            Response::MasterchainInfo(info) => Ok(info),
+            response => Err(response_error(response)),
+        }
+    }
+
+    async fn execute(&mut self, request: Request) -> anyhow::Result<Response> {
+        let req = WrappedRequest {
+            wait_masterchain_seqno: None,
+            request,
+        };
+        self.execute_wrapped(req).await
+    }
+
+    async fn execute_wrapped(&mut self, req: WrappedRequest) -> anyhow::Result<Response> {
+        let ready_service = self.connect().await?.ready().await?;
+        let result = timeout(REQ_TIMEOUT, ready_service.call(req)).await;
+        if !matches!(result, Ok(Ok(_))) {
+            // the connection may be broken, establish a new one for the next request
+            self.service = None;
+        }
+        Ok(result??)
+    }
+
+    async fn connect(&mut self) -> anyhow::Result<&mut ConnService> {
+        if self.service.is_none() {
+            let adnl = timeout(
+                CONNECTION_TIMEOUT,
+                AdnlPeer::connect(&self.public, self.addr),
+            )
+            .await??;
+
+            let lite = LitePeer::new(adnl);
+            let service = ServiceBuilder::new()
+                .layer(WrapMessagesLayer)
+                .service(Client::<_, Box<dyn Error + Send + Sync + 'static>, _>::new(
+                    lite,
+                ));
+            self.service = Some(service);
+        }
+        Ok(self.service.as_mut().unwrap()) // unwrap is safe: we initialized it in branch above
+    }
+}
+
+fn response_error(response: Response) -> anyhow::Error {
+    match response {
+        Response::Error(error) => LiteServerError {
+            code: error.code,
+            message: error.message.to_string(),
+        }
+        .into(),
+        _ => LiteError::UnexpectedMessage.into(),
+    }
+}
+
+fn lite_error(method: &'static str, error: anyhow::Error) -> TonClientError {
+    match error.downcast::<LiteServerError>() {
+        Ok(LiteServerError { code, message }) => TonClientError::LiteServerError {
+            method,
+            code,
+            message,
+        },
+        Err(error) => TonClientError::InternalError(format!("{}: {}", method, error)),
+    }
+}
+
+fn account_id(address: &TonAddress) -> AccountId {
+    AccountId {
+        workchain: address.workchain,
+        id: Int256(address.hash_part),
+    }
+}
+
+fn block_id_to_lite(block_id: &BlockIdExt) -> Result<BlockIdExtLite, TonClientError> {
+    let hash = |hash: &[u8]| {
+        hash.try_into().map(Int256).map_err(|_| {
+            TonClientError::InternalError(format!(
+                "Invalid hash of block ({},{:X},{})",
+                block_id.workchain, block_id.shard as u64, block_id.seqno
+            ))
+        })
+    };
+    Ok(BlockIdExtLite {
+        workchain: block_id.workchain,
+        shard: block_id.shard as u64,
+        seqno: block_id.seqno as u32,
+        root_hash: hash(&block_id.root_hash)?,
+        file_hash: hash(&block_id.file_hash)?,
+    })
+}
+
+fn block_id_from_lite(block_id: &BlockIdExtLite) -> BlockIdExt {
+    BlockIdExt {
+        workchain: block_id.workchain,
+        shard: block_id.shard as i64,
+        seqno: block_id.seqno as i32,
+        root_hash: block_id.root_hash.0.to_vec(),
+        file_hash: block_id.file_hash.0.to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use ton_liteapi::tl::response::{Error as LiteServerErrorResponse, Response};
+    use tonlib_core::cell::{BagOfCells, CellBuilder, TonCellError};
+    use tonlib_core::constants::SHARD_FULL;
+    use tonlib_core::TonAddress;
+
+    use super::{
+        account_id, block_id_from_lite, block_id_to_lite, lite_error, read_account, response_error,
+        AccountFields,
+    };
+    use crate::client::TonClientError;
+    use crate::tl::BlockIdExt;
+
+    #[test]
+    fn test_lite_conversions() {
+        let address = TonAddress::new(-1, &[7; 32]);
+        let id = account_id(&address);
+        assert_eq!(id.workchain, -1);
+        assert_eq!(id.id.0, [7; 32]);
+
+        let block_id = BlockIdExt {
+            workchain: 0,
+            shard: SHARD_FULL as i64,
+            seqno: 42,
+            root_hash: vec![1; 32],
+            file_hash: vec![2; 32],
+        };
+        let lite_id = block_id_to_lite(&block_id).unwrap();
+        assert_eq!(lite_id.shard, SHARD_FULL);
+        assert_eq!(block_id_from_lite(&lite_id), block_id);
+        let invalid = BlockIdExt {
+            root_hash: vec![1; 31],
+            ..block_id
+        };
+        assert!(block_id_to_lite(&invalid).is_err());
+
+        let error = response_error(Response::Error(LiteServerErrorResponse {
+            code: 651,
+            message: "too big masterchain seqno".into(),
+        }));
+        assert_eq!(
+            error.to_string(),
+            "Liteserver error (code: 651, message: too big masterchain seqno)"
+        );
+        match lite_error("liteServer.getAccountState", error) {
+            TonClientError::LiteServerError {
+                method,
+                code,
+                message,
+            } => {
+                assert_eq!(method, "liteServer.getAccountState");
+                assert_eq!(code, 651);
+                assert_eq!(message, "too big masterchain seqno");
+            }
+            e => panic!("Unexpected error: {:?}", e),
+        }
+        assert!(matches!(
+            lite_error("liteServer.sendMessage", anyhow::anyhow!("closed")),
+            TonClientError::InternalError(_)
+        ));
+    }
+
+    #[test]
+    fn test_read_account() -> Result<(), TonCellError> {
+        let address = TonAddress::new(0, &[7; 32]);
+        let code = Arc::new(CellBuilder::new().store_u32(32, 0xc0de)?.build()?);
+        let data = Arc::new(CellBuilder::new().store_u32(32, 0xda7a)?.build()?);
+        let account = |state: &dyn Fn(&mut CellBuilder) -> Result<(), TonCellError>| {
+            let mut builder = CellBuilder::new();
+            builder
+                .store_bit(true)?
+                .store_address(&address)?
+                // cells and bits used, no storage extra, last_paid, no due payment
+                .store_u8(3, 1)?
+                .store_u8(8, 3)?
+                .store_u8(3, 2)?
+                .store_u32(16, 1023)?
+                .store_u8(3, 0)?
+                .store_u32(32, 1_700_000_000)?
+                .store_bit(false)?
+                // last_trans_lt, balance without extra currencies
+                .store_u64(64, 1_000_000)?
+                .store_coins(&1_500_000_000u64.into())?
+                .store_bit(false)?;
+            state(&mut builder)?;
+            builder.build()
+        };
+
+        let active = account(&|builder| {
+            builder
+                .store_bit(true)?
+                .store_bit(false)?
+                .store_bit(false)?
+                .store_maybe_cell_ref(&Some(code.clone()))?
+                .store_maybe_cell_ref(&Some(data.clone()))?
+                .store_bit(false)?;
+            Ok(())
+        })?;
+        let fields = read_account(&active)?;
+        assert_eq!(fields.balance, 1_500_000_000);
+        let root = |boc: &[u8]| -> Result<_, TonCellError> {
+            Ok(BagOfCells::parse(boc)?.single_root()?.cell_hash())
+        };
+        assert_eq!(root(&fields.code)?, code.cell_hash());
+        assert_eq!(root(&fields.data)?, data.cell_hash());
+        assert!(fields.frozen_hash.is_empty());
+
+        let frozen = account(&|builder| {
+            builder.store_u8(2, 0b01)?.store_slice(&[9; 32])?;
+            Ok(())
+        })?;
+        let fields = read_account(&frozen)?;
+        assert_eq!(fields.frozen_hash, vec![9; 32]);
+        assert!(fields.code.is_empty());
+
+        let none = CellBuilder::new().store_bit(false)?.build()?;
+        assert_eq!(read_account(&none)?, AccountFields::default());
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::stream::{self, BoxStream};
+use futures::{Stream, StreamExt};
+use tokio::time;
+
+use crate::client::{TonClientError, TonClientInterface};
+use crate::tl::BlockIdExt;
+
+/// Upper bound of the delay between polls after consecutive errors.
+pub const MAX_BLOCK_POLL_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Stream of new masterchain blocks discovered by polling `get_masterchain_info`.
+///
+/// The first item is the last masterchain block at the time of the first poll. Afterwards
+/// every block is yielded exactly once and in order: blocks created between two polls are
+/// looked up by seqno. A failed poll or lookup is yielded as an error, the stream then
+/// retries with a delay doubled after every consecutive error, up to `MAX_BLOCK_POLL_BACKOFF`.
+///
+/// Unlike notifications this doesn't require `use_callbacks_for_network`.
+/// Polling stops once the stream is dropped.
+pub struct MasterchainBlockStream {
+    inner: BoxStream<'static, Result<BlockIdExt, TonClientError>>,
+}
+
+impl MasterchainBlockStream {
+    pub fn new<C>(client: &C, poll_interval: Duration) -> MasterchainBlockStream
+    where
+        C: TonClientInterface + Clone + 'static,
+    {
+        let poller = BlockPoller {
+            client: client.clone(),
+            poll_interval,
+            next_seqno: None,
+            latest: None,
+            consecutive_errors: 0,
+        };
+        let inner = stream::unfold(poller, |mut poller| async move {
+            let item = poller.next().await;
+            Some((item, poller))
+        })
+        .boxed();
+        MasterchainBlockStream { inner }
+    }
+}
+
+impl Stream for MasterchainBlockStream {
+    type Item = Result<BlockIdExt, TonClientError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+struct BlockPoller<C: TonClientInterface> {
+    client: C,
+    poll_interval: Duration,
+    /// Seqno of the next block to yield, `None` before the first successful poll.
+    next_seqno: Option<i32>,
+    /// Last block returned by `get_masterchain_info` and not yielded yet.
+    latest: Option<BlockIdExt>,
+    consecutive_errors: u32,
+}
+
+impl<C: TonClientInterface> BlockPoller<C> {
+    async fn next(&mut self) -> Result<BlockIdExt, TonClientError> {
+        if self.consecutive_errors > 0 {
+            time::sleep(self.backoff()).await;
+        }
+        let result = self.next_block().await;
+        if result.is_ok() {
+            self.consecutive_errors = 0;
+        } else {
+            self.consecutive_errors += 1;
+        }
+        result
+    }
+
+    async fn next_block(&mut self) -> Result<BlockIdExt, TonClientError> {
+        loop {
+            if let (Some(next_seqno), Some(latest)) = (self.next_seqno, &self.latest) {
+                let block = if latest.seqno == next_seqno {
+                    self.latest.take().unwrap()
+                } else {
+                    self.client
+                        .lookup_block_by_seqno(latest.workchain, latest.shard, next_seqno)
+                        .await?
+                };
+                self.next_seqno = Some(next_seqno + 1);
+                return Ok(block);
+            }
+            let (_, info) = self.client.get_masterchain_info().await?;
+            match self.next_seqno {
+                None => {
+                    self.next_seqno = Some(info.last.seqno);
+                    self.latest = Some(info.last);
+                }
+                Some(next_seqno) if info.last.seqno >= next_seqno => {
+                    self.latest = Some(info.last);
+                }
+                Some(_) => time::sleep(self.poll_interval).await,
+            }
+        }
+    }
+
+    fn backoff(&self) -> Duration {
+        let factor = 1u32 << self.consecutive_errors.min(16);
+        self.poll_interval
+            .saturating_mul(factor)
+            .min(MAX_BLOCK_POLL_BACKOFF)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use futures::StreamExt;
+
+    use super::MasterchainBlockStream;
+    use crate::client::{
+        TonClientError, TonClientInterface, TonConnection, TonConnectionParams,
+        NOOP_CONNECTION_CALLBACK,
+    };
+    use crate::tl::{BlockIdExt, BlocksMasterchainInfo, TonFunction, TonResult};
+
+    fn master_block(seqno: i32) -> BlockIdExt {
+        BlockIdExt {
+            workchain: -1,
+            shard: i64::MIN,
+            seqno,
+            root_hash: vec![seqno as u8; 32],
+            file_hash: vec![0; 32],
+        }
+    }
+
+    /// Answers `get_masterchain_info` with scripted seqnos, `None` for an error.
+    #[derive(Clone)]
+    struct MockClient {
+        connection: TonConnection,
+        last_seqnos: Arc<Mutex<VecDeque<Option<i32>>>>,
+    }
+
+    #[async_trait]
+    impl TonClientInterface for MockClient {
+        async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+            Ok(self.connection.clone())
+        }
+
+        async fn invoke_on_connection(
+            &self,
+            function: &TonFunction,
+        ) -> Result<(TonConnection, TonResult), TonClientError> {
+            let result = match function {
+                TonFunction::BlocksGetMasterchainInfo {} => {
+                    let mut last_seqnos = self.last_seqnos.lock().unwrap();
+                    let seqno = match last_seqnos.len() {
+                        1 => last_seqnos[0],
+                        _ => last_seqnos.pop_front().unwrap(),
+                    };
+                    let seqno = seqno
+                        .ok_or_else(|| TonClientError::InternalError("Poll failed".to_string()))?;
+                    TonResult::BlocksMasterchainInfo(BlocksMasterchainInfo {
+                        last: master_block(seqno),
+                        state_root_hash: vec![],
+                        init: master_block(0),
+                    })
+                }
+                TonFunction::BlocksLookupBlock { id, .. } => {
+                    TonResult::BlockIdExt(master_block(id.seqno))
+                }
+                f => panic!("Unexpected function: {:?}", f),
+            };
+            Ok((self.connection.clone(), result))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_masterchain_block_stream_fills_gaps() {
+        let connection = TonConnection::new(
+            NOOP_CONNECTION_CALLBACK.clone(),
+            &TonConnectionParams::default(),
+        )
+        .unwrap();
+        let client = MockClient {
+            connection,
+            last_seqnos: Arc::new(Mutex::new(VecDeque::from([
+                Some(10),
+                Some(10),
+                Some(13),
+                None,
+                Some(13),
+                Some(15),
+            ]))),
+        };
+        let stream = MasterchainBlockStream::new(&client, Duration::from_millis(1));
+        let items: Vec<_> = stream.take(7).collect().await;
+        let seqnos: Vec<_> = items
+            .iter()
+            .map(|item| item.as_ref().map(|block| block.seqno).ok())
+            .collect();
+        assert_eq!(
+            seqnos,
+            vec![
+                Some(10),
+                Some(11),
+                Some(12),
+                Some(13),
+                None,
+                Some(14),
+                Some(15)
+            ]
+        );
+        assert_eq!(items[1].as_ref().unwrap(), &master_block(11));
+    }
+}
@@ -0,0 +1,228 @@
+use futures::stream::{self, StreamExt};
+use tonlib_core::cell::{BagOfCells, Cell, TonCellError};
+use tonlib_core::message::{ExternalInMessage, TonMessage, TonMessageError};
+use tonlib_core::TonHash;
+
+use crate::client::{SentMessage, TonClientError, TonClientInterface};
+
+pub const DEFAULT_MESSAGE_BATCH_CONCURRENCY: usize = 8;
+
+/// External messages sent to the network together, e.g. by payout services.
+///
+/// Messages are serialized to BoC when added, so a malformed message fails the batch before
+/// anything is sent. `send` sends up to `concurrency` messages at a time, each request goes
+/// to the connection picked by the client, so the load is spread across the pool.
+/// Every message is sent regardless of the other results, which are collected into
+/// `BatchSendReport`.
+#[derive(Debug, Clone)]
+pub struct MessageBatch {
+    messages: Vec<BatchMessage>,
+    concurrency: usize,
+}
+
+#[derive(Debug, Clone)]
+struct BatchMessage {
+    hash: TonHash,
+    boc: Vec<u8>,
+}
+
+impl MessageBatch {
+    pub fn new() -> MessageBatch {
+        MessageBatch {
+            messages: vec![],
+            concurrency: DEFAULT_MESSAGE_BATCH_CONCURRENCY,
+        }
+    }
+
+    /// Limits the number of messages sent at the same time, at least one message is sent.
+    pub fn with_concurrency(&mut self, concurrency: usize) -> &mut Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn with_message(
+        &mut self,
+        message: &ExternalInMessage,
+    ) -> Result<&mut Self, TonMessageError> {
+        Ok(self.with_cell(message.build()?)?)
+    }
+
+    /// Adds a message cell, e.g. built by a wallet.
+    pub fn with_cell(&mut self, cell: Cell) -> Result<&mut Self, TonCellError> {
+        let hash = cell.cell_hash();
+        let boc = BagOfCells::from_root(cell).serialize(true)?;
+        self.messages.push(BatchMessage { hash, boc });
+        Ok(self)
+    }
+
+    /// Adds a message serialized to BoC with a single root.
+    pub fn with_boc(&mut self, boc: &[u8]) -> Result<&mut Self, TonCellError> {
+        let hash = BagOfCells::parse(boc)?.single_root()?.cell_hash();
+        self.messages.push(BatchMessage {
+            hash,
+            boc: boc.to_vec(),
+        });
+        Ok(self)
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Sends all messages with `TonClientInterface::send_message`, reporting the result of
+    /// each one in the order they were added.
+    pub async fn send<C>(&self, client: &C) -> BatchSendReport
+    where
+        C: TonClientInterface + ?Sized,
+    {
+        let results = stream::iter(self.messages.iter().enumerate())
+            .map(|(index, message)| async move {
+                let result = client.send_message(&message.boc).await;
+                if let Err(e) = &result {
+                    log::warn!(
+                        "Failed to send message {} of the batch {}: {}",
+                        index,
+                        hex::encode(message.hash),
+                        e
+                    );
+                }
+                BatchMessageResult {
+                    index,
+                    hash: message.hash,
+                    result,
+                }
+            })
+            .buffered(self.concurrency)
+            .collect()
+            .await;
+        BatchSendReport { results }
+    }
+}
+
+impl Default for MessageBatch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Result of sending a message of `MessageBatch`.
+#[derive(Debug)]
+pub struct BatchMessageResult {
+    /// Position of the message in the batch.
+    pub index: usize,
+    /// Hash of the message cell, known even if the message wasn't sent.
+    pub hash: TonHash,
+    pub result: Result<SentMessage, TonClientError>,
+}
+
+/// Results of `MessageBatch::send`, one per message in the order they were added.
+#[derive(Debug)]
+pub struct BatchSendReport {
+    pub results: Vec<BatchMessageResult>,
+}
+
+impl BatchSendReport {
+    /// Returns `true` if every message was sent, duplicates included.
+    pub fn is_complete(&self) -> bool {
+        self.results.iter().all(|r| r.result.is_ok())
+    }
+
+    pub fn sent(&self) -> impl Iterator<Item = &BatchMessageResult> {
+        self.results.iter().filter(|r| r.result.is_ok())
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &BatchMessageResult> {
+        self.results.iter().filter(|r| r.result.is_err())
+    }
+
+    /// Returns the sent messages if all of them were sent, or the report otherwise.
+    pub fn into_result(self) -> Result<Vec<SentMessage>, BatchSendReport> {
+        if !self.is_complete() {
+            return Err(self);
+        }
+        Ok(self
+            .results
+            .into_iter()
+            .filter_map(|r| r.result.ok())
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tonlib_core::cell::{BagOfCells, CellBuilder};
+
+    use super::MessageBatch;
+    use crate::client::{MockTonClient, TonClientError, TonFixture};
+    use crate::tl::{RawExtMessageInfo, TonFunction, TonResult};
+
+    fn message_boc(value: u32) -> anyhow::Result<Vec<u8>> {
+        let cell = CellBuilder::new().store_u32(32, value)?.build()?;
+        Ok(BagOfCells::from_root(cell).serialize(true)?)
+    }
+
+    fn fixture(boc: &[u8], result: TonResult) -> TonFixture {
+        TonFixture {
+            function: TonFunction::RawSendMessageReturnHash { body: boc.to_vec() },
+            result,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_message_batch_reports_partial_failures() -> anyhow::Result<()> {
+        let bocs = [message_boc(1)?, message_boc(2)?, message_boc(3)?];
+        let mut batch = MessageBatch::new();
+        for boc in bocs.iter() {
+            batch.with_boc(boc)?;
+        }
+        batch.with_concurrency(2);
+        let sent_hash = BagOfCells::parse(&bocs[0])?.single_root()?.cell_hash();
+        let client = MockTonClient::new(vec![
+            fixture(
+                &bocs[0],
+                TonResult::RawExtMessageInfo(RawExtMessageInfo {
+                    hash: sent_hash.to_vec(),
+                }),
+            ),
+            fixture(
+                &bocs[1],
+                TonResult::Error {
+                    code: 500,
+                    message: "duplicate message".to_string(),
+                },
+            ),
+            fixture(
+                &bocs[2],
+                TonResult::Error {
+                    code: 500,
+                    message: "cannot apply external message to current state".to_string(),
+                },
+            ),
+        ]);
+
+        let report = batch.send(&client).await;
+        assert_eq!(report.results.len(), 3);
+        assert!(!report.is_complete());
+        assert_eq!(report.sent().count(), 2);
+        assert!(report.results[1].result.as_ref().unwrap().duplicate);
+        let failed: Vec<_> = report.failed().collect();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].index, 2);
+        assert!(matches!(
+            failed[0].result,
+            Err(TonClientError::ExternalMessageRejected { .. })
+        ));
+        assert!(report.into_result().is_err());
+
+        let mut batch = MessageBatch::new();
+        batch.with_boc(&bocs[0])?;
+        let sent = batch.send(&client).await.into_result().unwrap();
+        assert_eq!(sent[0].hash, sent_hash.to_vec());
+        assert!(MessageBatch::new().with_boc(&[1, 2, 3]).is_err());
+        Ok(())
+    }
+}
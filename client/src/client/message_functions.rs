@@ -0,0 +1,260 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::time::{self, Instant};
+use tonlib_core::cell::TonCellError;
+use tonlib_core::message::{ExternalInMessage, TonMessage};
+use tonlib_core::{TonAddress, TonHash};
+
+use crate::client::{TonClientError, TonClientInterface};
+use crate::tl::{InternalTransactionId, RawTransaction, NULL_TRANSACTION_ID};
+
+const MESSAGE_POLL_INTERVAL: Duration = Duration::from_millis(1000);
+const MESSAGE_POLL_TX_COUNT: usize = 16;
+/// Pages of `MESSAGE_POLL_TX_COUNT` transactions scanned by the first poll of a message.
+const MESSAGE_FIRST_POLL_PAGES: usize = 4;
+/// Pages of `MESSAGE_POLL_TX_COUNT` transactions of a destination scanned by
+/// `get_pending_out_messages` for a message.
+const PENDING_MESSAGE_SCAN_PAGES: usize = 16;
+
+/// Internal message sent by an account, for which no processing transaction was found
+/// on the destination yet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingOutMessage {
+    /// Transaction of the sender account that created the message.
+    pub source_transaction_id: InternalTransactionId,
+    pub destination: TonAddress,
+    /// Hash of the message cell.
+    pub message_hash: TonHash,
+    pub created_lt: i64,
+}
+
+/// Page of `TonMessageFunctions::get_pending_out_messages`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingOutMessages {
+    pub messages: Vec<PendingOutMessage>,
+    /// Transaction to pass as `from_tx_id` to continue with older transactions, `None` once
+    /// the first transaction of the account has been scanned.
+    pub next_tx_id: Option<InternalTransactionId>,
+}
+
+/// High-level functions for tracking messages sent to the network
+#[async_trait]
+pub trait TonMessageFunctions: TonClientInterface + Send + Sync {
+    /// Polls recent transactions of `account` until one of them has an incoming message
+    /// with the specified hash or `timeout` expires.
+    ///
+    /// External incoming messages are matched by either the hash of the message cell
+    /// or the normalized hash, see `ExternalInMessage::normalized_hash`.
+    ///
+    /// The first poll scans only the latest `MESSAGE_FIRST_POLL_PAGES` pages of transactions,
+    /// so the message is expected to be sent shortly before the call. Later polls scan
+    /// the transactions since the previous poll.
+    ///
+    /// Returns the transaction that processed the message, or `None` if no such transaction
+    /// was found within `timeout`.
+    async fn was_message_accepted(
+        &self,
+        message_hash: &TonHash,
+        account: &TonAddress,
+        timeout: Duration,
+    ) -> Result<Option<RawTransaction>, TonClientError> {
+        let deadline = Instant::now() + timeout;
+        let mut last_checked_lt: Option<i64> = None;
+        loop {
+            let state = self.get_raw_account_state(account).await?;
+            let latest_tx_id = state.last_transaction_id;
+            let (stop_lt, max_pages) = match last_checked_lt {
+                Some(lt) => (lt, usize::MAX),
+                None => (0, MESSAGE_FIRST_POLL_PAGES),
+            };
+            if latest_tx_id.lt > stop_lt {
+                let found = find_in_message(
+                    self,
+                    account,
+                    message_hash,
+                    &latest_tx_id,
+                    stop_lt,
+                    max_pages,
+                    Some(deadline),
+                )
+                .await?;
+                if found.is_some() {
+                    return Ok(found);
+                }
+                last_checked_lt = Some(latest_tx_id.lt);
+            }
+            if Instant::now() + MESSAGE_POLL_INTERVAL > deadline {
+                return Ok(None);
+            }
+            time::sleep(MESSAGE_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Same as `was_message_accepted`, but fails with `TonClientError::Timeout` if no
+    /// transaction processed the message within `timeout`.
+    ///
+    /// ```ignore
+    /// let message_hash = message.normalized_hash()?;
+    /// let boc = BagOfCells::from_root(message.build()?).serialize(true)?;
+    /// client.send_raw_message(&boc).await?;
+    /// let tx = client
+    ///     .wait_for_transaction(&message.dest, &message_hash, Duration::from_secs(60))
+    ///     .await?;
+    /// ```
+    async fn wait_for_transaction(
+        &self,
+        account: &TonAddress,
+        message_hash: &TonHash,
+        timeout: Duration,
+    ) -> Result<RawTransaction, TonClientError> {
+        self.was_message_accepted(message_hash, account, timeout)
+            .await?
+            .ok_or(TonClientError::Timeout {
+                method: "wait_for_transaction",
+                elapsed: timeout,
+            })
+    }
+
+    /// Returns internal messages sent by `tx_limit` transactions of `account` starting
+    /// from `from_tx_id`, the latest transaction if `None`, that haven't been processed by
+    /// their destinations yet. Older transactions are scanned by passing `next_tx_id` of
+    /// the returned page.
+    ///
+    /// tonlib doesn't expose the outbound message queue of an account, so this is
+    /// a best-effort approximation: for every outgoing internal message the transactions of its
+    /// destination are scanned down to the message `created_lt`, at most
+    /// `PENDING_MESSAGE_SCAN_PAGES` pages of them. A message to a busy destination processed
+    /// before those transactions is reported as pending.
+    async fn get_pending_out_messages(
+        &self,
+        account: &TonAddress,
+        from_tx_id: Option<&InternalTransactionId>,
+        tx_limit: usize,
+    ) -> Result<PendingOutMessages, TonClientError> {
+        let mut tx_id = match from_tx_id {
+            Some(tx_id) => tx_id.clone(),
+            None => {
+                self.get_raw_account_state(account)
+                    .await?
+                    .last_transaction_id
+            }
+        };
+        let mut remaining = tx_limit;
+        let mut pending = vec![];
+        while remaining > 0 && tx_id.lt != NULL_TRANSACTION_ID.lt {
+            let count = remaining.min(MESSAGE_POLL_TX_COUNT);
+            let txs = self
+                .get_raw_transactions_v2(account, &tx_id, count, false)
+                .await?;
+            if txs.transactions.is_empty() {
+                break;
+            }
+            for tx in txs.transactions.iter() {
+                if remaining == 0 {
+                    // Continue with this transaction on the next page
+                    return Ok(PendingOutMessages {
+                        messages: pending,
+                        next_tx_id: Some(tx.transaction_id.clone()),
+                    });
+                }
+                remaining -= 1;
+                let out_msg_cells = tx.out_msg_cells()?;
+                for (out_msg, cell) in tx.out_msgs.iter().zip(out_msg_cells.iter()) {
+                    // external outbound messages have no destination account
+                    if out_msg.destination.account_address.is_empty() {
+                        continue;
+                    }
+                    let destination: TonAddress =
+                        out_msg.destination.account_address.parse().map_err(|e| {
+                            TonClientError::InternalError(format!(
+                                "Invalid destination address: {}",
+                                e
+                            ))
+                        })?;
+                    let message_hash = cell.cell_hash();
+                    let dest_state = self.get_raw_account_state(&destination).await?;
+                    let delivered = dest_state.last_transaction_id.lt > out_msg.created_lt
+                        && find_in_message(
+                            self,
+                            &destination,
+                            &message_hash,
+                            &dest_state.last_transaction_id,
+                            out_msg.created_lt,
+                            PENDING_MESSAGE_SCAN_PAGES,
+                            None,
+                        )
+                        .await?
+                        .is_some();
+                    if !delivered {
+                        pending.push(PendingOutMessage {
+                            source_transaction_id: tx.transaction_id.clone(),
+                            destination,
+                            message_hash,
+                            created_lt: out_msg.created_lt,
+                        });
+                    }
+                }
+            }
+            tx_id = txs.previous_transaction_id;
+        }
+        let next_tx_id = (tx_id.lt != NULL_TRANSACTION_ID.lt).then_some(tx_id);
+        Ok(PendingOutMessages {
+            messages: pending,
+            next_tx_id,
+        })
+    }
+}
+
+impl<T> TonMessageFunctions for T where T: TonClientInterface + Send + Sync {}
+
+/// Scans transactions of `account` starting from `from_tx_id` down to `stop_lt` (exclusive),
+/// requesting at most `max_pages` pages and giving up once `deadline` passes.
+async fn find_in_message<C: TonClientInterface + ?Sized>(
+    client: &C,
+    account: &TonAddress,
+    message_hash: &TonHash,
+    from_tx_id: &InternalTransactionId,
+    stop_lt: i64,
+    max_pages: usize,
+    deadline: Option<Instant>,
+) -> Result<Option<RawTransaction>, TonClientError> {
+    let mut tx_id = from_tx_id.clone();
+    for _ in 0..max_pages {
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            return Ok(None);
+        }
+        let txs = client
+            .get_raw_transactions_v2(account, &tx_id, MESSAGE_POLL_TX_COUNT, false)
+            .await?;
+        for tx in txs.transactions.into_iter() {
+            if tx.transaction_id.lt <= stop_lt {
+                return Ok(None);
+            }
+            if has_in_message(&tx, message_hash)? {
+                return Ok(Some(tx));
+            }
+        }
+        tx_id = txs.previous_transaction_id;
+        if tx_id.lt == 0 || tx_id.lt <= stop_lt {
+            return Ok(None);
+        }
+    }
+    Ok(None)
+}
+
+/// Returns whether the incoming message of the transaction has the specified cell hash
+/// or, for an external message, normalized hash.
+fn has_in_message(tx: &RawTransaction, message_hash: &TonHash) -> Result<bool, TonCellError> {
+    let msg = match tx.in_msg_cell()? {
+        Some(msg) => msg,
+        None => return Ok(false),
+    };
+    if &msg.cell_hash() == message_hash {
+        return Ok(true);
+    }
+    let normalized_hash = ExternalInMessage::parse(&msg)
+        .and_then(|m| m.normalized_hash())
+        .ok();
+    Ok(normalized_hash.as_ref() == Some(message_hash))
+}
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use dashmap::DashMap;
+
+use crate::client::{TonClientError, TonConnectionCallback};
+use crate::tl::TonResult;
+
+/// Upper bounds of latency histogram buckets in microseconds, from 100us to 60s.
+///
+/// Durations above the last bound are counted in an additional overflow bucket.
+pub const LATENCY_BUCKET_BOUNDS_US: [u64; 19] = [
+    100, 250, 500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 500_000,
+    1_000_000, 2_500_000, 5_000_000, 10_000_000, 20_000_000, 30_000_000, 60_000_000,
+];
+
+/// Invocation statistics of a single tonlib method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MethodStats {
+    /// Number of completed invocations, including failed and timed out ones.
+    pub count: u64,
+    /// Number of invocations completed with an error.
+    pub errors: u64,
+    /// Number of invocations that timed out, also counted in `errors`.
+    pub timeouts: u64,
+    /// Sum of durations of all invocations.
+    pub total_duration: Duration,
+    /// Number of invocations per bucket of `LATENCY_BUCKET_BOUNDS_US`, the last one is
+    /// the overflow bucket.
+    pub buckets: Vec<u64>,
+}
+
+impl Default for MethodStats {
+    fn default() -> Self {
+        MethodStats {
+            count: 0,
+            errors: 0,
+            timeouts: 0,
+            total_duration: Duration::ZERO,
+            buckets: vec![0; LATENCY_BUCKET_BOUNDS_US.len() + 1],
+        }
+    }
+}
+
+impl MethodStats {
+    fn record(&mut self, duration: &Duration, is_error: bool, is_timeout: bool) {
+        self.count += 1;
+        if is_error {
+            self.errors += 1;
+        }
+        if is_timeout {
+            self.timeouts += 1;
+        }
+        self.total_duration += *duration;
+        let us = duration.as_micros();
+        let bucket = LATENCY_BUCKET_BOUNDS_US
+            .iter()
+            .position(|bound| us <= *bound as u128)
+            .unwrap_or(LATENCY_BUCKET_BOUNDS_US.len());
+        self.buckets[bucket] += 1;
+    }
+
+    /// Returns the upper bound of the bucket containing the given quantile (`0.0..=1.0`),
+    /// `None` if nothing is recorded.
+    ///
+    /// For the overflow bucket `Duration::MAX` is returned.
+    pub fn percentile(&self, quantile: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((quantile.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut cumulative = 0;
+        for (index, count) in self.buckets.iter().enumerate() {
+            cumulative += count;
+            if cumulative >= rank {
+                let bound = LATENCY_BUCKET_BOUNDS_US
+                    .get(index)
+                    .map(|us| Duration::from_micros(*us))
+                    .unwrap_or(Duration::MAX);
+                return Some(bound);
+            }
+        }
+        None
+    }
+
+    pub fn p50(&self) -> Option<Duration> {
+        self.percentile(0.5)
+    }
+
+    pub fn p95(&self) -> Option<Duration> {
+        self.percentile(0.95)
+    }
+
+    pub fn p99(&self) -> Option<Duration> {
+        self.percentile(0.99)
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(Duration::from_nanos(
+                (self.total_duration.as_nanos() / self.count as u128) as u64,
+            ))
+        }
+    }
+}
+
+/// An implementation of TonConnectionCallback that collects per-method invocation statistics.
+///
+/// Can be combined with other callbacks using `MultiConnectionCallback`.
+#[derive(Debug, Default)]
+pub struct MetricsCallback {
+    stats: DashMap<String, MethodStats>,
+}
+
+impl MetricsCallback {
+    pub fn new() -> MetricsCallback {
+        Self::default()
+    }
+
+    /// Returns a copy of statistics collected so far, keyed by method name.
+    pub fn snapshot(&self) -> HashMap<String, MethodStats> {
+        self.stats
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect()
+    }
+
+    /// Clears all statistics collected so far.
+    pub fn reset(&self) {
+        self.stats.clear()
+    }
+
+    fn record(&self, method: &str, duration: &Duration, is_error: bool, is_timeout: bool) {
+        let mut stats = match self.stats.get_mut(method) {
+            Some(stats) => stats,
+            None => self.stats.entry(method.to_string()).or_default(),
+        };
+        stats.record(duration, is_error, is_timeout);
+    }
+}
+
+impl TonConnectionCallback for MetricsCallback {
+    fn on_invoke_result(
+        &self,
+        _tag: &str,
+        _request_id: u32,
+        method: &str,
+        duration: &Duration,
+        result: &Result<TonResult, TonClientError>,
+    ) {
+        self.record(method, duration, result.is_err(), false);
+    }
+
+    fn on_invoke_timeout(&self, _tag: &str, _request_id: u32, method: &str, duration: &Duration) {
+        self.record(method, duration, true, true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{MetricsCallback, LATENCY_BUCKET_BOUNDS_US};
+    use crate::client::{TonClientError, TonConnectionCallback};
+    use crate::tl::TonResult;
+
+    #[test]
+    fn test_metrics_callback_percentiles() {
+        let callback = MetricsCallback::new();
+        for i in 1..=100u64 {
+            // 90 fast calls of up to 0.9ms, 9 calls of ~20ms and a single 3s call
+            let duration = match i {
+                1..=90 => Duration::from_micros(i * 10),
+                91..=99 => Duration::from_millis(20),
+                _ => Duration::from_secs(3),
+            };
+            let result = if i % 10 == 0 {
+                Err(TonClientError::InternalError("test".to_string()))
+            } else {
+                Ok(TonResult::Ok {})
+            };
+            callback.on_invoke_result("test", i as u32, "Sync", &duration, &result);
+        }
+        callback.on_invoke_timeout("test", 101, "GetMasterchainInfo", &Duration::from_secs(1));
+
+        let snapshot = callback.snapshot();
+        let sync = &snapshot["Sync"];
+        assert_eq!(sync.count, 100);
+        assert_eq!(sync.errors, 10);
+        assert_eq!(sync.timeouts, 0);
+        assert_eq!(sync.buckets.len(), LATENCY_BUCKET_BOUNDS_US.len() + 1);
+        assert_eq!(sync.p50(), Some(Duration::from_micros(500)));
+        assert_eq!(sync.p95(), Some(Duration::from_millis(25)));
+        assert_eq!(sync.p99(), Some(Duration::from_millis(25)));
+        assert_eq!(sync.percentile(1.0), Some(Duration::from_secs(5)));
+
+        let timed_out = &snapshot["GetMasterchainInfo"];
+        assert_eq!(timed_out.count, 1);
+        assert_eq!(timed_out.errors, 1);
+        assert_eq!(timed_out.timeouts, 1);
+        assert_eq!(timed_out.p50(), Some(Duration::from_secs(1)));
+
+        callback.reset();
+        assert!(callback.snapshot().is_empty());
+        assert_eq!(super::MethodStats::default().p50(), None);
+    }
+}
@@ -0,0 +1,195 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+use serde_json::Value;
+
+use crate::client::{TonClientError, TonConnectionCallback};
+use crate::tl::{TonFunction, TonNotification, TonResult};
+
+/// Number of finished invocations, labeled by `method` and `status` (`ok` or `error`).
+pub const METRIC_INVOKE_TOTAL: &str = "tonlib_invoke_total";
+/// Duration of invocations in seconds, labeled by `method`.
+pub const METRIC_INVOKE_DURATION_SECONDS: &str = "tonlib_invoke_duration_seconds";
+/// Number of failed invocations, labeled by `method` and `code`, the tonlib error code
+/// or the kind of the error for errors not coming from tonlib.
+pub const METRIC_INVOKE_ERRORS_TOTAL: &str = "tonlib_invoke_errors_total";
+/// Number of requests awaiting a result, labeled by connection `tag`.
+pub const METRIC_PENDING_REQUESTS: &str = "tonlib_pending_requests";
+/// Number of notifications received from tonlib, labeled by `type`.
+pub const METRIC_NOTIFICATIONS_TOTAL: &str = "tonlib_notifications_total";
+/// Number of notifications overwritten in a full notification queue.
+pub const METRIC_NOTIFICATIONS_DROPPED_TOTAL: &str = "tonlib_notifications_dropped_total";
+/// Number of notifications skipped by lagging subscribers.
+pub const METRIC_NOTIFICATIONS_LAGGED_TOTAL: &str = "tonlib_notifications_lagged_total";
+/// Number of reconnects of tonlib clients, labeled by connection `tag`.
+pub const METRIC_RECONNECTS_TOTAL: &str = "tonlib_reconnects_total";
+
+/// An implementation of TonConnectionCallback that records metrics via the `metrics` facade.
+///
+/// Metrics are exported by the recorder installed by the application, e.g. `metrics-exporter-prometheus`.
+/// Can be combined with other callbacks using `MultiConnectionCallback`.
+#[derive(Debug, Default)]
+pub struct MetricsRecorderCallback {}
+
+impl MetricsRecorderCallback {
+    pub fn new() -> MetricsRecorderCallback {
+        MetricsRecorderCallback {}
+    }
+
+    fn record_finished(&self, tag: &str, method: &str, duration: &Duration, error: Option<String>) {
+        let method = method.to_string();
+        let status = if error.is_some() { "error" } else { "ok" };
+        metrics::counter!(METRIC_INVOKE_TOTAL, "method" => method.clone(), "status" => status)
+            .increment(1);
+        metrics::histogram!(METRIC_INVOKE_DURATION_SECONDS, "method" => method.clone())
+            .record(duration.as_secs_f64());
+        if let Some(code) = error {
+            metrics::counter!(METRIC_INVOKE_ERRORS_TOTAL, "method" => method, "code" => code)
+                .increment(1);
+        }
+        metrics::gauge!(METRIC_PENDING_REQUESTS, "tag" => tag.to_string()).decrement(1.0);
+    }
+}
+
+fn error_code(error: &TonClientError) -> String {
+    let code = match error {
+        TonClientError::TonlibError { code, .. }
+        | TonClientError::ExternalMessageRejected { code, .. } => return code.to_string(),
+        TonClientError::InternalError(_) => "internal",
+        TonClientError::Timeout { .. } => "timeout",
+        TonClientError::Cancelled { .. } => "cancelled",
+        TonClientError::ConnectionClosed { .. } => "connection_closed",
+        TonClientError::Reconnecting { .. } => "reconnecting",
+        TonClientError::ConnectionReset { .. } => "connection_reset",
+        TonClientError::ArchiveUnavailable { .. } => "archive_unavailable",
+        TonClientError::HttpError { .. } => "http",
+        TonClientError::UnexpectedTonResult { .. } => "unexpected_result",
+        TonClientError::Io(_) => "io",
+        TonClientError::TlError(_) => "tl",
+        TonClientError::TonAddressParseError(_) | TonClientError::TonCellError(_) => {
+            "invalid_input"
+        }
+    };
+    code.to_string()
+}
+
+impl TonConnectionCallback for MetricsRecorderCallback {
+    fn on_invoke(&self, tag: &str, _request_id: u32, _function: &TonFunction) {
+        metrics::gauge!(METRIC_PENDING_REQUESTS, "tag" => tag.to_string()).increment(1.0);
+    }
+
+    fn on_invoke_raw(&self, tag: &str, _request_id: u32, _method: &str, _params: &Value) {
+        metrics::gauge!(METRIC_PENDING_REQUESTS, "tag" => tag.to_string()).increment(1.0);
+    }
+
+    fn on_invoke_result(
+        &self,
+        tag: &str,
+        _request_id: u32,
+        method: &str,
+        duration: &Duration,
+        result: &Result<TonResult, TonClientError>,
+    ) {
+        let error = result.as_ref().err().map(error_code);
+        self.record_finished(tag, method, duration, error);
+    }
+
+    fn on_invoke_timeout(&self, tag: &str, _request_id: u32, method: &str, duration: &Duration) {
+        self.record_finished(tag, method, duration, Some("timeout".to_string()));
+    }
+
+    fn on_notification(&self, _tag: &str, notification: &TonNotification) {
+        let notification_type = match notification {
+            TonNotification::UpdateSyncState(_) => "updateSyncState",
+        };
+        metrics::counter!(METRIC_NOTIFICATIONS_TOTAL, "type" => notification_type).increment(1);
+    }
+
+    fn on_notification_dropped(&self, tag: &str, _total: u64) {
+        metrics::counter!(METRIC_NOTIFICATIONS_DROPPED_TOTAL, "tag" => tag.to_string())
+            .increment(1);
+    }
+
+    fn on_notification_lagged(&self, tag: &str, skipped: u64) {
+        metrics::counter!(METRIC_NOTIFICATIONS_LAGGED_TOTAL, "tag" => tag.to_string())
+            .increment(skipped);
+    }
+
+    fn on_reconnect(&self, tag: &str, _consecutive_errors: usize) {
+        metrics::counter!(METRIC_RECONNECTS_TOTAL, "tag" => tag.to_string()).increment(1);
+    }
+
+    fn on_connection_stalled(&self, tag: &str, _stalled_for: &Duration) {
+        metrics::counter!(METRIC_RECONNECTS_TOTAL, "tag" => tag.to_string()).increment(1);
+    }
+}
+
+lazy_static! {
+    pub static ref METRICS_CONNECTION_CALLBACK: Arc<dyn TonConnectionCallback + Send + Sync> =
+        Arc::new(MetricsRecorderCallback::new());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    use super::{
+        MetricsRecorderCallback, METRIC_INVOKE_ERRORS_TOTAL, METRIC_INVOKE_TOTAL,
+        METRIC_PENDING_REQUESTS,
+    };
+    use crate::client::{TonClientError, TonConnectionCallback};
+    use crate::tl::{TonFunction, TonResult};
+
+    #[test]
+    fn test_metrics_recorder_callback() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let callback = MetricsRecorderCallback::new();
+        metrics::with_local_recorder(&recorder, || {
+            let duration = Duration::from_millis(10);
+            callback.on_invoke("conn", 1, &TonFunction::Sync {});
+            callback.on_invoke("conn", 2, &TonFunction::Sync {});
+            callback.on_invoke("conn", 3, &TonFunction::Sync {});
+            callback.on_invoke_result("conn", 1, "Sync", &duration, &Ok(TonResult::Ok {}));
+            let error = TonClientError::TonlibError {
+                method: "Sync",
+                code: 500,
+                message: "LITE_SERVER_NOTREADY".to_string(),
+            };
+            callback.on_invoke_result("conn", 2, "Sync", &duration, &Err(error));
+        });
+
+        let metrics = snapshotter.snapshot().into_vec();
+        let value = |name: &str, labels: &[(&str, &str)]| {
+            metrics
+                .iter()
+                .find(|(key, _, _, _)| {
+                    let key = key.key();
+                    key.name() == name
+                        && labels.iter().all(|(k, v)| {
+                            key.labels()
+                                .any(|label| label.key() == *k && label.value() == *v)
+                        })
+                })
+                .map(|(_, _, _, value)| value.clone())
+        };
+        assert_eq!(
+            value(METRIC_INVOKE_TOTAL, &[("method", "Sync"), ("status", "ok")]),
+            Some(DebugValue::Counter(1))
+        );
+        assert_eq!(
+            value(
+                METRIC_INVOKE_ERRORS_TOTAL,
+                &[("method", "Sync"), ("code", "500")]
+            ),
+            Some(DebugValue::Counter(1))
+        );
+        assert_eq!(
+            value(METRIC_PENDING_REQUESTS, &[("tag", "conn")]),
+            Some(DebugValue::Gauge(1.0.into()))
+        );
+    }
+}
@@ -0,0 +1,473 @@
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tonlib_core::TonAddress;
+
+use crate::client::{TonClientError, TonClientInterface, TonConnection, TonProvider};
+use crate::contract::{TonContractError, TonContractState};
+use crate::tl::{
+    AccountAddress, BlocksMasterchainInfo, RawFullAccountState, TonFunction, TonResult,
+    TonResultDiscriminants, TvmStackEntry as TlTvmStackEntry,
+};
+use crate::types::{TonMethodId, TvmStackEntry, TvmSuccess};
+
+/// Request sent to tonlib and the result it returned, errors are kept as `TonResult::Error`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TonFixture {
+    pub function: TonFunction,
+    pub result: TonResult,
+}
+
+/// Client serving results of recorded requests instead of sending them to liteservers,
+/// for unit tests of code using `TonClientInterface` or `TonProvider` without network access.
+///
+/// Fixtures are usually recorded from a real session with `RecordingTonClient` and loaded
+/// with `from_file`. A request is answered with the first unused fixture of an equal function,
+/// or with the last one of them once all are used, so repeated requests replay the recorded
+/// sequence of results. Requests without fixtures fail with `TonClientError::InternalError`.
+///
+/// The client has no connections, so neither tonlib nor the network is used. Requests bound
+/// to a connection, i.e. `get_connection`, `invoke_on_connection` and the functions built on
+/// them such as `smc_load`, fail with `TonClientError::InternalError`. Get-methods are
+/// replayed by `TonProvider::run_get_method`, which sends `smc.load` and `smc.runGetMethod`
+/// with `invoke`, and contract wrappers are tested with `ProviderContract`:
+///
+/// ```ignore
+/// let client = Arc::new(MockTonClient::from_file("tests/fixtures/jetton_master.json")?);
+/// let master = ProviderContract::new(client.clone(), &master_address);
+/// assert_eq!(master.get_jetton_data().await?.mintable, true);
+/// assert!(client.unused_fixtures().is_empty());
+/// ```
+pub struct MockTonClient {
+    fixtures: Vec<TonFixture>,
+    used: Mutex<Vec<bool>>,
+}
+
+impl MockTonClient {
+    pub fn new(fixtures: Vec<TonFixture>) -> MockTonClient {
+        let used = Mutex::new(vec![false; fixtures.len()]);
+        MockTonClient { fixtures, used }
+    }
+
+    /// Creates a client serving the JSON array of fixtures `json`, see `RecordingTonClient::to_json`.
+    pub fn from_json(json: &str) -> Result<MockTonClient, TonClientError> {
+        let fixtures = serde_json::from_str(json).map_err(|e| {
+            TonClientError::InternalError(format!("Failed to parse fixtures: {}", e))
+        })?;
+        Ok(Self::new(fixtures))
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<MockTonClient, TonClientError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    /// Returns fixtures which haven't been requested yet, e.g. to check that a test
+    /// made all the recorded requests.
+    pub fn unused_fixtures(&self) -> Vec<TonFixture> {
+        let used = self.used.lock().unwrap();
+        self.fixtures
+            .iter()
+            .zip(used.iter())
+            .filter(|(_, used)| !**used)
+            .map(|(fixture, _)| fixture.clone())
+            .collect()
+    }
+
+    fn replay(&self, function: &TonFunction) -> Result<TonResult, TonClientError> {
+        let mut used = self.used.lock().unwrap();
+        let matching: Vec<usize> = (0..self.fixtures.len())
+            .filter(|i| &self.fixtures[*i].function == function)
+            .collect();
+        let index = matching
+            .iter()
+            .find(|i| !used[**i])
+            .or(matching.last())
+            .copied()
+            .ok_or_else(|| {
+                let function = serde_json::to_string(function).unwrap_or_default();
+                TonClientError::InternalError(format!("No fixture for {}", function))
+            })?;
+        used[index] = true;
+        match self.fixtures[index].result.clone() {
+            TonResult::Error { code, message } => Err(TonClientError::TonlibError {
+                method: function.into(),
+                code,
+                message,
+            }),
+            result => Ok(result),
+        }
+    }
+}
+
+#[async_trait]
+impl TonClientInterface for MockTonClient {
+    async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+        Err(no_connection())
+    }
+
+    async fn invoke_on_connection(
+        &self,
+        _function: &TonFunction,
+    ) -> Result<(TonConnection, TonResult), TonClientError> {
+        Err(no_connection())
+    }
+
+    async fn invoke(&self, function: &TonFunction) -> Result<TonResult, TonClientError> {
+        self.replay(function)
+    }
+}
+
+#[async_trait]
+impl TonProvider for MockTonClient {
+    async fn get_masterchain_info(&self) -> Result<BlocksMasterchainInfo, TonClientError> {
+        match self
+            .invoke(&TonFunction::BlocksGetMasterchainInfo {})
+            .await?
+        {
+            TonResult::BlocksMasterchainInfo(info) => Ok(info),
+            r => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::BlocksMasterchainInfo,
+                r,
+            )),
+        }
+    }
+
+    async fn get_raw_account_state(
+        &self,
+        address: &TonAddress,
+    ) -> Result<RawFullAccountState, TonClientError> {
+        TonClientInterface::get_raw_account_state(self, address).await
+    }
+
+    async fn run_get_method(
+        &self,
+        address: &TonAddress,
+        method: &TonMethodId,
+        stack: &[TvmStackEntry],
+    ) -> Result<TvmSuccess, TonContractError> {
+        let load = smc_load_function(address);
+        let id = smc_id(self.invoke(&load).await?)?;
+        let run = smc_run_get_method_function(id, address, method, stack)?;
+        let result = self.invoke(&run).await?;
+        get_method_result(address, method, result)
+    }
+
+    async fn send_raw_message_return_hash(&self, body: &[u8]) -> Result<Vec<u8>, TonClientError> {
+        TonClientInterface::send_raw_message_return_hash(self, body).await
+    }
+}
+
+/// Client recording requests of `client` and their results as fixtures of `MockTonClient`.
+///
+/// Requests which fail with errors other than `TonClientError::TonlibError`, e.g. timeouts,
+/// aren't recorded. Get-methods are recorded by `TonProvider::run_get_method`.
+pub struct RecordingTonClient<C> {
+    client: C,
+    fixtures: Mutex<Vec<TonFixture>>,
+}
+
+impl<C: TonClientInterface> RecordingTonClient<C> {
+    pub fn new(client: C) -> RecordingTonClient<C> {
+        RecordingTonClient {
+            client,
+            fixtures: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn fixtures(&self) -> Vec<TonFixture> {
+        self.fixtures.lock().unwrap().clone()
+    }
+
+    pub fn to_json(&self) -> Result<String, TonClientError> {
+        serde_json::to_string_pretty(&self.fixtures()).map_err(|e| {
+            TonClientError::InternalError(format!("Failed to serialize fixtures: {}", e))
+        })
+    }
+
+    /// Saves the recorded fixtures to `path`, to be loaded with `MockTonClient::from_file`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), TonClientError> {
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+
+    fn record<T>(
+        &self,
+        function: &TonFunction,
+        result: &Result<T, TonClientError>,
+        ton_result: impl FnOnce(&T) -> &TonResult,
+    ) {
+        let result = match result {
+            Ok(r) => ton_result(r).clone(),
+            Err(TonClientError::TonlibError { code, message, .. }) => TonResult::Error {
+                code: *code,
+                message: message.clone(),
+            },
+            Err(_) => return,
+        };
+        self.fixtures.lock().unwrap().push(TonFixture {
+            function: function.clone(),
+            result,
+        });
+    }
+}
+
+#[async_trait]
+impl<C: TonClientInterface> TonClientInterface for RecordingTonClient<C> {
+    async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+        self.client.get_connection().await
+    }
+
+    async fn invoke_on_connection(
+        &self,
+        function: &TonFunction,
+    ) -> Result<(TonConnection, TonResult), TonClientError> {
+        let result = self.client.invoke_on_connection(function).await;
+        self.record(function, &result, |(_, r)| r);
+        result
+    }
+}
+
+#[async_trait]
+impl<C: TonClientInterface> TonProvider for RecordingTonClient<C> {
+    async fn get_masterchain_info(&self) -> Result<BlocksMasterchainInfo, TonClientError> {
+        let (_, info) = TonClientInterface::get_masterchain_info(self).await?;
+        Ok(info)
+    }
+
+    async fn get_raw_account_state(
+        &self,
+        address: &TonAddress,
+    ) -> Result<RawFullAccountState, TonClientError> {
+        TonClientInterface::get_raw_account_state(self, address).await
+    }
+
+    /// Runs the get-method on the connection the contract is loaded by, recording
+    /// `smc.load` and `smc.runGetMethod`, the contract is forgotten afterwards.
+    async fn run_get_method(
+        &self,
+        address: &TonAddress,
+        method: &TonMethodId,
+        stack: &[TvmStackEntry],
+    ) -> Result<TvmSuccess, TonContractError> {
+        let load = smc_load_function(address);
+        let (conn, result) = self.invoke_on_connection(&load).await?;
+        let id = smc_id(result)?;
+        let run = smc_run_get_method_function(id, address, method, stack)?;
+        let result = conn.invoke(&run).await;
+        self.record(&run, &result, |r| r);
+        if let Err(e) = conn.smc_forget(id).await {
+            log::debug!("Failed to forget contract {}: {}", id, e);
+        }
+        get_method_result(address, method, result?)
+    }
+
+    async fn send_raw_message_return_hash(&self, body: &[u8]) -> Result<Vec<u8>, TonClientError> {
+        TonClientInterface::send_raw_message_return_hash(self, body).await
+    }
+}
+
+fn no_connection() -> TonClientError {
+    TonClientError::InternalError("MockTonClient has no connections".to_string())
+}
+
+fn smc_load_function(address: &TonAddress) -> TonFunction {
+    TonFunction::SmcLoad {
+        account_address: AccountAddress {
+            account_address: address.to_hex(),
+        },
+    }
+}
+
+fn smc_id(result: TonResult) -> Result<i64, TonClientError> {
+    match result {
+        TonResult::SmcInfo(info) => Ok(info.id),
+        r => Err(TonClientError::unexpected_ton_result(
+            TonResultDiscriminants::SmcInfo,
+            r,
+        )),
+    }
+}
+
+fn smc_run_get_method_function(
+    id: i64,
+    address: &TonAddress,
+    method: &TonMethodId,
+    stack: &[TvmStackEntry],
+) -> Result<TonFunction, TonContractError> {
+    let stack = stack
+        .iter()
+        .map(TlTvmStackEntry::try_from)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|error| TonContractError::TvmStackParseError {
+            method: method.clone(),
+            address: address.clone(),
+            error,
+        })?;
+    Ok(TonFunction::SmcRunGetMethod {
+        id,
+        method: method.into(),
+        stack,
+    })
+}
+
+fn get_method_result(
+    address: &TonAddress,
+    method: &TonMethodId,
+    result: TonResult,
+) -> Result<TvmSuccess, TonContractError> {
+    let result = match result {
+        TonResult::SmcRunResult(result) => result,
+        r => {
+            return Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::SmcRunResult,
+                r,
+            )
+            .into())
+        }
+    };
+    let result =
+        TvmSuccess::try_from(&result).map_err(|error| TonContractError::TvmStackParseError {
+            method: method.clone(),
+            address: address.clone(),
+            error,
+        })?;
+    TonContractState::raise_exit_error(address, method, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use tonlib_core::TonAddress;
+
+    use super::{MockTonClient, TonFixture};
+    use crate::client::{TonClientError, TonClientInterface, TonProvider};
+    use crate::contract::{ProviderContract, TonContractError, TonWalletContract};
+    use crate::tl::{
+        AccountAddress, SmcInfo, SmcRunResult, TonFunction, TonResult, TvmNumber, TvmStack,
+        TvmStackEntry,
+    };
+    use crate::types::{TonMethodId, TvmStackEntry as StackEntry};
+
+    #[tokio::test]
+    async fn test_mock_client_replays_fixtures() -> anyhow::Result<()> {
+        let address = TonAddress::new(0, &[1; 32]);
+        let account_address = AccountAddress {
+            account_address: address.to_hex(),
+        };
+        let seqno = TonMethodId::from("seqno");
+        let run_result = |number: &str, exit_code| {
+            TonResult::SmcRunResult(SmcRunResult {
+                gas_used: 100,
+                stack: TvmStack {
+                    elements: vec![TvmStackEntry::Number {
+                        number: TvmNumber {
+                            number: number.to_string(),
+                        },
+                    }],
+                },
+                exit_code,
+            })
+        };
+        let run = TonFunction::SmcRunGetMethod {
+            id: 5,
+            method: (&seqno).into(),
+            stack: vec![],
+        };
+        let fixtures = vec![
+            TonFixture {
+                function: TonFunction::SmcLoad {
+                    account_address: account_address.clone(),
+                },
+                result: TonResult::SmcInfo(SmcInfo { id: 5 }),
+            },
+            TonFixture {
+                function: run.clone(),
+                result: run_result("7", 0),
+            },
+            TonFixture {
+                function: run,
+                result: run_result("8", 0),
+            },
+            TonFixture {
+                function: TonFunction::RawSendMessage { body: vec![1] },
+                result: TonResult::Error {
+                    code: 500,
+                    message: "duplicate message".to_string(),
+                },
+            },
+        ];
+        let client = MockTonClient::from_json(&serde_json::to_string(&fixtures)?)?;
+
+        let result = client.run_get_method(&address, &seqno, &[]).await?;
+        assert_eq!(result.stack, vec![StackEntry::Int64(7)]);
+        for _ in 0..2 {
+            let result = client.run_get_method(&address, &seqno, &[]).await?;
+            assert_eq!(result.stack, vec![StackEntry::Int64(8)]);
+        }
+        assert_eq!(client.unused_fixtures().len(), 1);
+
+        let result = client.send_raw_message(&[1]).await;
+        assert!(matches!(
+            result,
+            Err(TonClientError::ExternalMessageRejected { code: 500, .. })
+        ));
+        let result = client
+            .run_get_method(&TonAddress::new(0, &[2; 32]), &seqno, &[])
+            .await;
+        assert!(matches!(
+            result,
+            Err(TonContractError::ClientError(
+                TonClientError::InternalError(_)
+            ))
+        ));
+        assert!(client.unused_fixtures().is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mock_client_serves_contract_wrappers() -> anyhow::Result<()> {
+        let address = TonAddress::new(0, &[1; 32]);
+        let fixtures = vec![
+            TonFixture {
+                function: TonFunction::SmcLoad {
+                    account_address: AccountAddress {
+                        account_address: address.to_hex(),
+                    },
+                },
+                result: TonResult::SmcInfo(SmcInfo { id: 3 }),
+            },
+            TonFixture {
+                function: TonFunction::SmcRunGetMethod {
+                    id: 3,
+                    method: (&TonMethodId::from("seqno")).into(),
+                    stack: vec![],
+                },
+                result: TonResult::SmcRunResult(SmcRunResult {
+                    gas_used: 100,
+                    stack: TvmStack {
+                        elements: vec![TvmStackEntry::Number {
+                            number: TvmNumber {
+                                number: "42".to_string(),
+                            },
+                        }],
+                    },
+                    exit_code: 0,
+                }),
+            },
+        ];
+        let client = Arc::new(MockTonClient::new(fixtures));
+        let wallet = ProviderContract::new(client.clone(), &address);
+
+        assert_eq!(wallet.seqno().await?, 42);
+        assert!(client.unused_fixtures().is_empty());
+        assert!(matches!(
+            client.get_connection().await,
+            Err(TonClientError::InternalError(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,176 @@
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::stream::{self, BoxStream};
+use futures::{Stream, StreamExt};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::client::TonNotificationReceiver;
+use crate::tl::TonNotification;
+
+/// Stream of notifications that skips notifications missed by a lagging subscriber.
+///
+/// A `TonNotificationReceiver` that falls more than the queue length behind returns
+/// `RecvError::Lagged` and continues with the oldest notification still queued.
+/// This stream does the same transparently and counts the skipped notifications,
+/// see `dropped_count`. The stream ends once the sender is dropped.
+///
+/// A stream created with `new_filtered` yields only notifications accepted by the filter.
+/// The filter runs on the subscriber side when the stream is polled, so an expensive filter
+/// only slows down its own stream, which may then lag and drop notifications.
+///
+/// Streams of `TonConnection` also report skipped notifications to
+/// `TonConnectionCallback::on_notification_lagged`.
+pub struct TonNotificationStream {
+    inner: BoxStream<'static, Arc<TonNotification>>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl TonNotificationStream {
+    pub fn new(receiver: TonNotificationReceiver) -> TonNotificationStream {
+        Self::new_filtered(receiver, |_| true)
+    }
+
+    pub fn new_filtered<F>(receiver: TonNotificationReceiver, filter: F) -> TonNotificationStream
+    where
+        F: Fn(&TonNotification) -> bool + Send + Sync + 'static,
+    {
+        Self::new_with_lag_handler(receiver, filter, |_| {})
+    }
+
+    /// Same as `new_filtered`, but also calls `on_lag` with the number of notifications
+    /// skipped every time the subscriber lagged.
+    pub fn new_with_lag_handler<F, L>(
+        receiver: TonNotificationReceiver,
+        filter: F,
+        on_lag: L,
+    ) -> TonNotificationStream
+    where
+        F: Fn(&TonNotification) -> bool + Send + Sync + 'static,
+        L: Fn(u64) + Send + Sync + 'static,
+    {
+        let dropped = Arc::new(AtomicU64::new(0));
+        let counter = dropped.clone();
+        let filter = Arc::new(filter);
+        let on_lag = Arc::new(on_lag);
+        let inner = stream::unfold(receiver, move |mut receiver| {
+            let counter = counter.clone();
+            let filter = filter.clone();
+            let on_lag = on_lag.clone();
+            async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(notification) if filter(&notification) => {
+                            return Some((notification, receiver))
+                        }
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(n)) => {
+                            log::warn!(
+                                "Notification subscriber lagged, {} notifications dropped",
+                                n
+                            );
+                            counter.fetch_add(n, Ordering::Relaxed);
+                            on_lag(n);
+                        }
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            }
+        })
+        .boxed();
+        TonNotificationStream { inner, dropped }
+    }
+
+    /// Returns the total number of notifications skipped because the subscriber lagged.
+    pub fn dropped_count(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Stream for TonNotificationStream {
+    type Item = Arc<TonNotification>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.inner.poll_next_unpin(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use futures::StreamExt;
+    use tokio::sync::broadcast;
+
+    use super::TonNotificationStream;
+    use crate::tl::{SyncState, TonNotification, UpdateSyncState};
+
+    fn sync_done_notification() -> Arc<TonNotification> {
+        Arc::new(TonNotification::UpdateSyncState(UpdateSyncState {
+            sync_state: SyncState::Done,
+        }))
+    }
+
+    fn sync_notification(current_seqno: i32) -> Arc<TonNotification> {
+        Arc::new(TonNotification::UpdateSyncState(UpdateSyncState {
+            sync_state: SyncState::InProgress {
+                from_seqno: 0,
+                to_seqno: 10,
+                current_seqno,
+            },
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_notification_stream_skips_lagged() {
+        let (sender, receiver) = broadcast::channel(2);
+        let mut stream = TonNotificationStream::new(receiver);
+        for i in 0..5 {
+            sender.send(sync_notification(i)).unwrap();
+        }
+        // Notifications 0..3 are overwritten, the stream resumes with the oldest queued one
+        assert_eq!(stream.next().await, Some(sync_notification(3)));
+        assert_eq!(stream.dropped_count(), 3);
+        assert_eq!(stream.next().await, Some(sync_notification(4)));
+
+        sender.send(sync_notification(5)).unwrap();
+        assert_eq!(stream.next().await, Some(sync_notification(5)));
+        assert_eq!(stream.dropped_count(), 3);
+
+        drop(sender);
+        assert_eq!(stream.next().await, None);
+    }
+
+    #[tokio::test]
+    async fn test_notification_stream_filtered() {
+        let (sender, receiver) = broadcast::channel(8);
+        let mut stream =
+            TonNotificationStream::new_filtered(receiver, TonNotification::is_sync_done);
+        sender.send(sync_notification(1)).unwrap();
+        sender.send(sync_done_notification()).unwrap();
+        sender.send(sync_notification(2)).unwrap();
+        drop(sender);
+        assert_eq!(stream.next().await, Some(sync_done_notification()));
+        assert_eq!(stream.next().await, None);
+        assert_eq!(stream.dropped_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_notification_stream_reports_lag() {
+        let (sender, receiver) = broadcast::channel(2);
+        let lags = Arc::new(Mutex::new(vec![]));
+        let recorded = lags.clone();
+        let mut stream = TonNotificationStream::new_with_lag_handler(
+            receiver,
+            |_| true,
+            move |n| recorded.lock().unwrap().push(n),
+        );
+        for i in 0..4 {
+            sender.send(sync_notification(i)).unwrap();
+        }
+        assert_eq!(stream.next().await, Some(sync_notification(2)));
+        assert_eq!(*lags.lock().unwrap(), vec![2]);
+    }
+}
@@ -0,0 +1,360 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::client::{TonClientError, TonClientInterface, TonConnection};
+use crate::tl::{TonFunction, TonResult};
+
+/// Number of requests of higher priorities sent before a waiting request, after which
+/// it's sent first, see `RequestPriority`.
+pub const MAX_OVERTAKES: u32 = 64;
+
+tokio::task_local! {
+    static TASK_PRIORITY: RequestPriority;
+}
+
+/// Priority of a request waiting for a free slot of `concurrency_limit` of a connection.
+///
+/// Waiting requests of a higher priority are sent first, requests of the same priority
+/// in the order they arrive. A waiting request overtaken by `MAX_OVERTAKES` requests of
+/// higher priorities is sent next, so that bulk requests are delayed but not starved.
+/// Sent requests aren't affected, nor is `max_rps`.
+///
+/// Priorities have no effect with `concurrency_limit` of `0`, as requests never wait then.
+#[derive(
+    Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestPriority {
+    /// Latency-critical requests, e.g. sending messages or reading seqno before signing.
+    High,
+    #[default]
+    Normal,
+    /// Bulk requests, e.g. historical scans, which may wait behind other requests.
+    Low,
+}
+
+impl RequestPriority {
+    const ALL: [RequestPriority; 3] = [
+        RequestPriority::High,
+        RequestPriority::Normal,
+        RequestPriority::Low,
+    ];
+
+    fn lane(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Runs `future` with `priority` applied to every tonlib request it invokes.
+///
+/// Like any task-local value the priority is not inherited by tasks spawned from `future`.
+pub async fn with_request_priority<F: Future>(priority: RequestPriority, future: F) -> F::Output {
+    TASK_PRIORITY.scope(priority, future).await
+}
+
+/// Returns the priority set by the enclosing `with_request_priority`, `Normal` if none.
+pub fn request_priority() -> RequestPriority {
+    TASK_PRIORITY.try_with(|p| *p).unwrap_or_default()
+}
+
+/// Client sending all requests with `priority`, see `TonClient::with_priority`.
+///
+/// The priority applies to requests made through this handle, not to requests sent directly
+/// to connections it returns, e.g. get-methods of `SmcHandle`.
+#[derive(Clone)]
+pub struct PriorityClient<C> {
+    client: C,
+    priority: RequestPriority,
+}
+
+impl<C> PriorityClient<C> {
+    pub fn new(client: C, priority: RequestPriority) -> PriorityClient<C> {
+        PriorityClient { client, priority }
+    }
+
+    pub fn priority(&self) -> RequestPriority {
+        self.priority
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.client
+    }
+}
+
+#[async_trait]
+impl<C: TonClientInterface> TonClientInterface for PriorityClient<C> {
+    async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+        self.client.get_connection().await
+    }
+
+    async fn invoke_on_connection(
+        &self,
+        function: &TonFunction,
+    ) -> Result<(TonConnection, TonResult), TonClientError> {
+        with_request_priority(self.priority, self.client.invoke_on_connection(function)).await
+    }
+}
+
+/// Semaphore granting permits to waiters of a higher priority first.
+///
+/// A waiter needing more permits than available blocks all waiters after it, including
+/// those of lower priorities, so large batches aren't starved by single requests of the same
+/// or lower priorities. A waiter is served before higher priorities once overtaken by
+/// `MAX_OVERTAKES` of their waiters, so that a large batch of a low priority isn't starved
+/// by a stream of single requests of a high one.
+pub(crate) struct PrioritySemaphore {
+    state: Mutex<SemaphoreState>,
+}
+
+struct SemaphoreState {
+    available: u32,
+    lanes: [VecDeque<Waiter>; 3],
+    next_id: u64,
+}
+
+struct Waiter {
+    id: u64,
+    permits: u32,
+    /// Number of waiters of higher priorities granted permits while this one was first
+    /// of its lane.
+    overtaken: u32,
+    sender: oneshot::Sender<()>,
+}
+
+impl PrioritySemaphore {
+    pub(crate) fn new(permits: usize) -> PrioritySemaphore {
+        PrioritySemaphore {
+            state: Mutex::new(SemaphoreState {
+                available: permits as u32,
+                lanes: Default::default(),
+                next_id: 0,
+            }),
+        }
+    }
+
+    /// Waits until `permits` permits are granted, they're held until the returned permit
+    /// is dropped. `permits` must not exceed the permits of the semaphore.
+    pub(crate) async fn acquire_many(
+        &self,
+        permits: u32,
+        priority: RequestPriority,
+    ) -> PriorityPermit<'_> {
+        let (id, receiver) = {
+            let mut state = self.state.lock().unwrap();
+            let lane = priority.lane();
+            let ahead = state.lanes[..=lane].iter().any(|l| !l.is_empty());
+            if !ahead && state.starved_lane().is_none() && state.available >= permits {
+                state.available -= permits;
+                state.overtake(lane);
+                return PriorityPermit {
+                    semaphore: self,
+                    permits,
+                };
+            }
+            let id = state.next_id;
+            state.next_id += 1;
+            let (sender, receiver) = oneshot::channel();
+            state.lanes[lane].push_back(Waiter {
+                id,
+                permits,
+                overtaken: 0,
+                sender,
+            });
+            (id, receiver)
+        };
+        let mut guard = WaiterGuard {
+            semaphore: self,
+            id,
+            lane: priority.lane(),
+            permits,
+            acquired: false,
+        };
+        // The sender is dropped only after sending
+        let _ = receiver.await;
+        guard.acquired = true;
+        PriorityPermit {
+            semaphore: self,
+            permits,
+        }
+    }
+
+    fn release(&self, permits: u32) {
+        let mut state = self.state.lock().unwrap();
+        state.available += permits;
+        state.dispatch();
+    }
+}
+
+impl SemaphoreState {
+    /// Grants permits to waiters in the order of priorities while enough are available,
+    /// starting with a starved waiter.
+    fn dispatch(&mut self) {
+        loop {
+            let first = RequestPriority::ALL
+                .iter()
+                .map(|p| p.lane())
+                .find(|lane| !self.lanes[*lane].is_empty());
+            let Some(lane) = self.starved_lane().or(first) else {
+                return;
+            };
+            if self.lanes[lane][0].permits > self.available {
+                return;
+            }
+            let waiter = self.lanes[lane].pop_front().unwrap();
+            self.available -= waiter.permits;
+            if waiter.sender.send(()).is_err() {
+                self.available += waiter.permits;
+            } else {
+                self.overtake(lane);
+            }
+        }
+    }
+
+    /// Returns the lane of the first waiter overtaken `MAX_OVERTAKES` times.
+    fn starved_lane(&self) -> Option<usize> {
+        RequestPriority::ALL.iter().map(|p| p.lane()).find(
+            |lane| matches!(self.lanes[*lane].front(), Some(w) if w.overtaken >= MAX_OVERTAKES),
+        )
+    }
+
+    /// Counts granting permits in `lane` for the first waiters of lower priorities.
+    fn overtake(&mut self, lane: usize) {
+        for waiters in &mut self.lanes[lane + 1..] {
+            if let Some(waiter) = waiters.front_mut() {
+                waiter.overtaken += 1;
+            }
+        }
+    }
+}
+
+/// Removes the waiter if its future is dropped before getting permits, or returns permits
+/// granted after that.
+struct WaiterGuard<'a> {
+    semaphore: &'a PrioritySemaphore,
+    id: u64,
+    lane: usize,
+    permits: u32,
+    acquired: bool,
+}
+
+impl Drop for WaiterGuard<'_> {
+    fn drop(&mut self) {
+        if self.acquired {
+            return;
+        }
+        let mut state = self.semaphore.state.lock().unwrap();
+        let lane = &mut state.lanes[self.lane];
+        match lane.iter().position(|w| w.id == self.id) {
+            Some(index) => {
+                lane.remove(index);
+            }
+            None => state.available += self.permits,
+        }
+        // The removed waiter might have blocked others
+        state.dispatch();
+    }
+}
+
+pub(crate) struct PriorityPermit<'a> {
+    semaphore: &'a PrioritySemaphore,
+    permits: u32,
+}
+
+impl Drop for PriorityPermit<'_> {
+    fn drop(&mut self) {
+        self.semaphore.release(self.permits);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use super::{
+        request_priority, with_request_priority, PrioritySemaphore, RequestPriority, MAX_OVERTAKES,
+    };
+
+    #[tokio::test]
+    async fn test_priority_semaphore_order() {
+        let semaphore = Arc::new(PrioritySemaphore::new(1));
+        let order = Arc::new(Mutex::new(vec![]));
+        let permit = semaphore.acquire_many(1, RequestPriority::Normal).await;
+        let mut handles = vec![];
+        for priority in [
+            RequestPriority::Low,
+            RequestPriority::Normal,
+            RequestPriority::High,
+            RequestPriority::Low,
+        ] {
+            let semaphore = semaphore.clone();
+            let order = order.clone();
+            handles.push(tokio::spawn(async move {
+                let _permit = semaphore.acquire_many(1, priority).await;
+                order.lock().unwrap().push(priority);
+            }));
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        // A cancelled waiter doesn't keep the permits
+        let cancelled = tokio::time::timeout(
+            Duration::from_millis(5),
+            semaphore.acquire_many(1, RequestPriority::High),
+        )
+        .await;
+        assert!(cancelled.is_err());
+        drop(permit);
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert_eq!(
+            *order.lock().unwrap(),
+            vec![
+                RequestPriority::High,
+                RequestPriority::Normal,
+                RequestPriority::Low,
+                RequestPriority::Low
+            ]
+        );
+        let _permit = semaphore.acquire_many(1, RequestPriority::Low).await;
+    }
+
+    #[tokio::test]
+    async fn test_priority_semaphore_aging() {
+        let semaphore = Arc::new(PrioritySemaphore::new(2));
+        let permit = semaphore.acquire_many(1, RequestPriority::Normal).await;
+        let batch = {
+            let semaphore = semaphore.clone();
+            tokio::spawn(async move {
+                let _permit = semaphore.acquire_many(2, RequestPriority::Low).await;
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        for _ in 0..MAX_OVERTAKES {
+            let _permit = semaphore.acquire_many(1, RequestPriority::High).await;
+        }
+        // The batch is starved, further requests wait behind it
+        let overtaking = tokio::time::timeout(
+            Duration::from_millis(5),
+            semaphore.acquire_many(1, RequestPriority::High),
+        )
+        .await;
+        assert!(overtaking.is_err());
+        drop(permit);
+        batch.await.unwrap();
+        let _permit = semaphore.acquire_many(2, RequestPriority::High).await;
+    }
+
+    #[tokio::test]
+    async fn test_request_priority_scope() {
+        assert_eq!(request_priority(), RequestPriority::Normal);
+        with_request_priority(RequestPriority::High, async {
+            assert_eq!(request_priority(), RequestPriority::High);
+        })
+        .await;
+    }
+}
@@ -0,0 +1,310 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tonlib_core::TonAddress;
+
+use crate::client::{
+    GetMethodRequest, TonClient, TonClientError, TonClientInterface, TonConnection,
+    TonGetMethodFunctions,
+};
+use crate::contract::TonContractError;
+use crate::tl::{BlocksMasterchainInfo, RawFullAccountState};
+use crate::types::{TonMethodId, TvmStackEntry, TvmSuccess};
+
+/// Common queries served both by liteservers, via `TonClient` or `TonConnection`, and by HTTP
+/// APIs, via `HttpProvider`, with the same request and response types.
+///
+/// Use `FallbackProvider` to fail over from one provider to another. The methods share names
+/// with `TonClientInterface`, so call them as `TonProvider::get_masterchain_info(&client)`
+/// if both traits are in scope.
+#[async_trait]
+pub trait TonProvider: Send + Sync {
+    async fn get_masterchain_info(&self) -> Result<BlocksMasterchainInfo, TonClientError>;
+
+    async fn get_raw_account_state(
+        &self,
+        address: &TonAddress,
+    ) -> Result<RawFullAccountState, TonClientError>;
+
+    /// Runs get-method `method` of `address` at the latest account state.
+    ///
+    /// A non-zero exit code fails with `TonContractError::TvmRunError`.
+    async fn run_get_method(
+        &self,
+        address: &TonAddress,
+        method: &TonMethodId,
+        stack: &[TvmStackEntry],
+    ) -> Result<TvmSuccess, TonContractError>;
+
+    /// Sends the external message serialized as `body`, returns its hash.
+    async fn send_raw_message_return_hash(&self, body: &[u8]) -> Result<Vec<u8>, TonClientError>;
+}
+
+/// Shares a provider, e.g. `Arc<dyn TonProvider>` of `ProviderContract`, with users
+/// taking a `TonProvider` by value such as `SeqnoGuard`.
+#[async_trait]
+impl<P: TonProvider + ?Sized> TonProvider for Arc<P> {
+    async fn get_masterchain_info(&self) -> Result<BlocksMasterchainInfo, TonClientError> {
+        self.as_ref().get_masterchain_info().await
+    }
+
+    async fn get_raw_account_state(
+        &self,
+        address: &TonAddress,
+    ) -> Result<RawFullAccountState, TonClientError> {
+        self.as_ref().get_raw_account_state(address).await
+    }
+
+    async fn run_get_method(
+        &self,
+        address: &TonAddress,
+        method: &TonMethodId,
+        stack: &[TvmStackEntry],
+    ) -> Result<TvmSuccess, TonContractError> {
+        self.as_ref().run_get_method(address, method, stack).await
+    }
+
+    async fn send_raw_message_return_hash(&self, body: &[u8]) -> Result<Vec<u8>, TonClientError> {
+        self.as_ref().send_raw_message_return_hash(body).await
+    }
+}
+
+#[async_trait]
+impl TonProvider for TonClient {
+    async fn get_masterchain_info(&self) -> Result<BlocksMasterchainInfo, TonClientError> {
+        client_get_masterchain_info(self).await
+    }
+
+    async fn get_raw_account_state(
+        &self,
+        address: &TonAddress,
+    ) -> Result<RawFullAccountState, TonClientError> {
+        TonClientInterface::get_raw_account_state(self, address).await
+    }
+
+    async fn run_get_method(
+        &self,
+        address: &TonAddress,
+        method: &TonMethodId,
+        stack: &[TvmStackEntry],
+    ) -> Result<TvmSuccess, TonContractError> {
+        client_run_get_method(self, address, method, stack).await
+    }
+
+    async fn send_raw_message_return_hash(&self, body: &[u8]) -> Result<Vec<u8>, TonClientError> {
+        TonClientInterface::send_raw_message_return_hash(self, body).await
+    }
+}
+
+#[async_trait]
+impl TonProvider for TonConnection {
+    async fn get_masterchain_info(&self) -> Result<BlocksMasterchainInfo, TonClientError> {
+        client_get_masterchain_info(self).await
+    }
+
+    async fn get_raw_account_state(
+        &self,
+        address: &TonAddress,
+    ) -> Result<RawFullAccountState, TonClientError> {
+        TonClientInterface::get_raw_account_state(self, address).await
+    }
+
+    async fn run_get_method(
+        &self,
+        address: &TonAddress,
+        method: &TonMethodId,
+        stack: &[TvmStackEntry],
+    ) -> Result<TvmSuccess, TonContractError> {
+        client_run_get_method(self, address, method, stack).await
+    }
+
+    async fn send_raw_message_return_hash(&self, body: &[u8]) -> Result<Vec<u8>, TonClientError> {
+        TonClientInterface::send_raw_message_return_hash(self, body).await
+    }
+}
+
+async fn client_get_masterchain_info<C: TonClientInterface>(
+    client: &C,
+) -> Result<BlocksMasterchainInfo, TonClientError> {
+    let (_, info) = TonClientInterface::get_masterchain_info(client).await?;
+    Ok(info)
+}
+
+pub(crate) async fn client_run_get_method<C: TonClientInterface>(
+    client: &C,
+    address: &TonAddress,
+    method: &TonMethodId,
+    stack: &[TvmStackEntry],
+) -> Result<TvmSuccess, TonContractError> {
+    let request = GetMethodRequest::new(address, method.clone(), stack);
+    client
+        .run_get_methods_batch(&[request], 1)
+        .await
+        .into_iter()
+        .next()
+        .expect("a result for every request")
+}
+
+/// Provider trying `providers` in order, e.g. a `TonClient` with an `HttpProvider` as fallback.
+///
+/// The next provider is tried only after a retryable error, see `TonClientError::is_retryable`,
+/// other errors are returned as is. The error of the last provider is returned if all fail.
+pub struct FallbackProvider {
+    providers: Vec<Box<dyn TonProvider>>,
+}
+
+impl FallbackProvider {
+    /// # Panics
+    ///
+    /// Panics if `providers` is empty.
+    pub fn new(providers: Vec<Box<dyn TonProvider>>) -> FallbackProvider {
+        assert!(
+            !providers.is_empty(),
+            "FallbackProvider requires at least one provider"
+        );
+        FallbackProvider { providers }
+    }
+}
+
+/// Returns the result of the first provider which doesn't fail with a retryable error.
+macro_rules! fall_back {
+    ($self:ident, $provider:ident => $call:expr) => {{
+        let (last, rest) = $self.providers.split_last().expect("at least one provider");
+        for $provider in rest {
+            match $call.await {
+                Err(e) if is_retryable(&e) => {
+                    log::warn!("Provider failed, falling back to the next one: {}", e)
+                }
+                result => return result,
+            }
+        }
+        let $provider = last;
+        $call.await
+    }};
+}
+
+#[async_trait]
+impl TonProvider for FallbackProvider {
+    async fn get_masterchain_info(&self) -> Result<BlocksMasterchainInfo, TonClientError> {
+        fall_back!(self, provider => provider.get_masterchain_info())
+    }
+
+    async fn get_raw_account_state(
+        &self,
+        address: &TonAddress,
+    ) -> Result<RawFullAccountState, TonClientError> {
+        fall_back!(self, provider => provider.get_raw_account_state(address))
+    }
+
+    async fn run_get_method(
+        &self,
+        address: &TonAddress,
+        method: &TonMethodId,
+        stack: &[TvmStackEntry],
+    ) -> Result<TvmSuccess, TonContractError> {
+        fall_back!(self, provider => provider.run_get_method(address, method, stack))
+    }
+
+    async fn send_raw_message_return_hash(&self, body: &[u8]) -> Result<Vec<u8>, TonClientError> {
+        fall_back!(self, provider => provider.send_raw_message_return_hash(body))
+    }
+}
+
+trait ProviderError {
+    fn client_error(&self) -> Option<&TonClientError>;
+}
+
+impl ProviderError for TonClientError {
+    fn client_error(&self) -> Option<&TonClientError> {
+        Some(self)
+    }
+}
+
+impl ProviderError for TonContractError {
+    fn client_error(&self) -> Option<&TonClientError> {
+        match self {
+            TonContractError::ClientError(e) => Some(e),
+            TonContractError::SharedClientError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+fn is_retryable<E: ProviderError>(error: &E) -> bool {
+    error.client_error().is_some_and(|e| e.is_retryable())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use tonlib_core::TonAddress;
+
+    use super::{FallbackProvider, TonProvider};
+    use crate::client::TonClientError;
+    use crate::contract::TonContractError;
+    use crate::tl::{BlocksMasterchainInfo, RawFullAccountState};
+    use crate::types::{TonMethodId, TvmStackEntry, TvmSuccess};
+
+    struct FailingProvider {
+        error: fn() -> TonClientError,
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl TonProvider for FailingProvider {
+        async fn get_masterchain_info(&self) -> Result<BlocksMasterchainInfo, TonClientError> {
+            Err(TonClientError::InternalError("not supported".to_string()))
+        }
+
+        async fn get_raw_account_state(
+            &self,
+            _address: &TonAddress,
+        ) -> Result<RawFullAccountState, TonClientError> {
+            Err(TonClientError::InternalError("not supported".to_string()))
+        }
+
+        async fn run_get_method(
+            &self,
+            _address: &TonAddress,
+            _method: &TonMethodId,
+            _stack: &[TvmStackEntry],
+        ) -> Result<TvmSuccess, TonContractError> {
+            Err(TonClientError::InternalError("not supported".to_string()).into())
+        }
+
+        async fn send_raw_message_return_hash(
+            &self,
+            _body: &[u8],
+        ) -> Result<Vec<u8>, TonClientError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Err((self.error)())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fallback_provider() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider = |error: fn() -> TonClientError| -> Box<dyn TonProvider> {
+            Box::new(FailingProvider {
+                error,
+                calls: calls.clone(),
+            })
+        };
+        let closed = || TonClientError::ConnectionClosed { method: "test" };
+        let internal = || TonClientError::InternalError("test".to_string());
+
+        let fallback = FallbackProvider::new(vec![provider(closed), provider(internal)]);
+        let result = fallback.send_raw_message_return_hash(&[]).await;
+        assert!(matches!(result, Err(TonClientError::InternalError(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        // Non-retryable errors are returned without trying the next provider
+        let fallback = FallbackProvider::new(vec![provider(internal), provider(closed)]);
+        let result = fallback.send_raw_message_return_hash(&[]).await;
+        assert!(matches!(result, Err(TonClientError::InternalError(_))));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,137 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::client::Clock;
+
+/// Token bucket limiting the rate of requests of a connection to `max_rps`,
+/// allowing bursts of up to `burst` requests.
+///
+/// Requests exceeding the rate reserve tokens ahead, so they are delayed in the order
+/// they arrive instead of competing for tokens. Tokens of a request cancelled while waiting
+/// are returned to the bucket.
+pub(crate) struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    state: Mutex<BucketState>,
+}
+
+struct BucketState {
+    /// Negative when tokens are reserved by waiting requests.
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(max_rps: u32, burst: u32, now: Instant) -> RateLimiter {
+        let burst = burst.max(1) as f64;
+        RateLimiter {
+            rate: max_rps.max(1) as f64,
+            burst,
+            state: Mutex::new(BucketState {
+                tokens: burst,
+                updated: now,
+            }),
+        }
+    }
+
+    /// Takes `n` tokens, returns how long to wait until the requests may be sent.
+    pub(crate) fn reserve(&self, n: u32, now: Instant) -> Duration {
+        let mut state = self.state.lock().unwrap();
+        let elapsed = now.saturating_duration_since(state.updated);
+        state.tokens = (state.tokens + elapsed.as_secs_f64() * self.rate).min(self.burst);
+        state.updated = now.max(state.updated);
+        state.tokens -= n as f64;
+        if state.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-state.tokens / self.rate)
+        }
+    }
+
+    /// Returns `n` tokens reserved by requests that won't be sent.
+    fn refund(&self, n: u32) {
+        let mut state = self.state.lock().unwrap();
+        state.tokens = (state.tokens + n as f64).min(self.burst);
+    }
+
+    /// Waits with `clock` until `n` requests may be sent.
+    pub(crate) async fn acquire(&self, n: u32, clock: &dyn Clock) {
+        let wait = self.reserve(n, clock.now());
+        if !wait.is_zero() {
+            let reservation = Reservation {
+                limiter: self,
+                tokens: n,
+            };
+            clock.sleep(wait).await;
+            std::mem::forget(reservation);
+        }
+    }
+}
+
+/// Refunds the tokens of a request if it's dropped before its wait is over.
+struct Reservation<'a> {
+    limiter: &'a RateLimiter,
+    tokens: u32,
+}
+
+impl Drop for Reservation<'_> {
+    fn drop(&mut self) {
+        self.limiter.refund(self.tokens);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use super::RateLimiter;
+    use crate::client::{Clock, ManualClock};
+
+    #[test]
+    fn test_rate_limiter_reserves_tokens() {
+        let start = Instant::now();
+        let limiter = RateLimiter::new(10, 2, start);
+        // The burst passes right away, further requests queue 100ms apart
+        assert_eq!(limiter.reserve(1, start), Duration::ZERO);
+        assert_eq!(limiter.reserve(1, start), Duration::ZERO);
+        assert_eq!(limiter.reserve(1, start), Duration::from_millis(100));
+        assert_eq!(limiter.reserve(1, start), Duration::from_millis(200));
+
+        // Reserved tokens are paid back before the bucket fills up again
+        let later = start + Duration::from_millis(300);
+        assert_eq!(limiter.reserve(1, later), Duration::ZERO);
+        assert_eq!(limiter.reserve(1, later), Duration::from_millis(100));
+        let idle = start + Duration::from_secs(10);
+        assert_eq!(limiter.reserve(2, idle), Duration::ZERO);
+        assert_eq!(limiter.reserve(2, idle), Duration::from_millis(200));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_waits_with_clock() {
+        let clock = Arc::new(ManualClock::new());
+        let limiter = Arc::new(RateLimiter::new(10, 1, clock.now()));
+        limiter.acquire(1, clock.as_ref()).await;
+        let waiting = tokio::spawn({
+            let (limiter, clock) = (limiter.clone(), clock.clone());
+            async move { limiter.acquire(1, clock.as_ref()).await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!waiting.is_finished());
+        clock.advance(Duration::from_millis(100));
+        tokio::time::timeout(Duration::from_secs(1), waiting)
+            .await
+            .unwrap()
+            .unwrap();
+
+        // A cancelled request returns its token, so the next one waits as if it wasn't made
+        let cancelled = tokio::spawn({
+            let (limiter, clock) = (limiter.clone(), clock.clone());
+            async move { limiter.acquire(1, clock.as_ref()).await }
+        });
+        tokio::task::yield_now().await;
+        cancelled.abort();
+        assert!(cancelled.await.is_err());
+        assert_eq!(limiter.reserve(1, clock.now()), Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,207 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio_retry::RetryIf;
+
+use crate::client::{RetryStrategy, TonClientError, TonClientInterface, TonConnection};
+use crate::tl::{TonFunction, TonResult};
+
+type RetryPredicate = Arc<dyn Fn(&TonClientError) -> bool + Send + Sync>;
+
+/// Wrapper of any `TonClientInterface` retrying failed requests according to `RetryStrategy`,
+/// e.g. a single `TonConnection` or a `TonClient` with its own retries disabled.
+///
+/// All `TonClientInterface` methods go through `invoke_on_connection` and are retried.
+#[derive(Clone)]
+pub struct RetryingTonClient<C> {
+    client: C,
+    retry_strategy: RetryStrategy,
+    predicate: Option<RetryPredicate>,
+}
+
+impl<C: TonClientInterface> RetryingTonClient<C> {
+    /// Retries errors accepted by `RetryStrategy::is_retryable`.
+    pub fn new(client: C, retry_strategy: &RetryStrategy) -> Self {
+        RetryingTonClient {
+            client,
+            retry_strategy: retry_strategy.clone(),
+            predicate: None,
+        }
+    }
+
+    /// Retries errors accepted by `predicate` instead of `RetryStrategy::is_retryable`.
+    pub fn with_predicate<F>(client: C, retry_strategy: &RetryStrategy, predicate: F) -> Self
+    where
+        F: Fn(&TonClientError) -> bool + Send + Sync + 'static,
+    {
+        RetryingTonClient {
+            client,
+            retry_strategy: retry_strategy.clone(),
+            predicate: Some(Arc::new(predicate)),
+        }
+    }
+
+    /// Returns the wrapped client, e.g. to make a request without retries.
+    pub fn inner(&self) -> &C {
+        &self.client
+    }
+
+    fn is_retryable(&self, error: &TonClientError) -> bool {
+        match &self.predicate {
+            Some(predicate) => predicate(error),
+            None => self.retry_strategy.is_retryable(error),
+        }
+    }
+}
+
+#[async_trait]
+impl<C: TonClientInterface> TonClientInterface for RetryingTonClient<C> {
+    async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+        self.client.get_connection().await
+    }
+
+    async fn invoke_on_connection(
+        &self,
+        function: &TonFunction,
+    ) -> Result<(TonConnection, TonResult), TonClientError> {
+        RetryIf::spawn(
+            self.retry_strategy.intervals(),
+            || self.client.invoke_on_connection(function),
+            |e: &TonClientError| self.is_retryable(e),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+
+    use super::RetryingTonClient;
+    use crate::client::{
+        RetryStrategy, TonClientError, TonClientInterface, TonConnection, TonConnectionParams,
+        NOOP_CONNECTION_CALLBACK,
+    };
+    use crate::tl::{TonFunction, TonResult};
+
+    /// Fails every request with `error_code` until `failures` requests have failed.
+    struct FlakyClient {
+        connection: TonConnection,
+        failures: usize,
+        error_code: i32,
+        attempts: Arc<AtomicUsize>,
+    }
+
+    #[async_trait]
+    impl TonClientInterface for FlakyClient {
+        async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+            Ok(self.connection.clone())
+        }
+
+        async fn invoke_on_connection(
+            &self,
+            _function: &TonFunction,
+        ) -> Result<(TonConnection, TonResult), TonClientError> {
+            if self.attempts.fetch_add(1, Ordering::SeqCst) < self.failures {
+                Err(TonClientError::TonlibError {
+                    method: "test",
+                    code: self.error_code,
+                    message: "LITE_SERVER_NOTREADY".to_string(),
+                })
+            } else {
+                Ok((self.connection.clone(), TonResult::Ok {}))
+            }
+        }
+    }
+
+    fn flaky_client(failures: usize, error_code: i32) -> (FlakyClient, Arc<AtomicUsize>) {
+        let connection = TonConnection::new(
+            NOOP_CONNECTION_CALLBACK.clone(),
+            &TonConnectionParams::default(),
+        )
+        .unwrap();
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let client = FlakyClient {
+            connection,
+            failures,
+            error_code,
+            attempts: attempts.clone(),
+        };
+        (client, attempts)
+    }
+
+    fn retry_strategy(max_retries: usize) -> RetryStrategy {
+        RetryStrategy {
+            interval_ms: 1,
+            max_retries,
+            retryable_codes: vec![651],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retrying_client_retries_transient_errors() {
+        let (client, attempts) = flaky_client(2, 651);
+        let client = RetryingTonClient::new(client, &retry_strategy(3));
+        let result = client.invoke(&TonFunction::GetLogVerbosityLevel {}).await;
+        assert!(matches!(result, Ok(TonResult::Ok {})));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        let (client, attempts) = flaky_client(5, 651);
+        let client = RetryingTonClient::new(client, &retry_strategy(3));
+        let result = client.invoke(&TonFunction::GetLogVerbosityLevel {}).await;
+        assert!(matches!(
+            result,
+            Err(TonClientError::TonlibError { code: 651, .. })
+        ));
+        assert_eq!(attempts.load(Ordering::SeqCst), 4);
+
+        let (client, attempts) = flaky_client(1, 400);
+        let client = RetryingTonClient::new(client, &retry_strategy(3));
+        assert!(client
+            .invoke(&TonFunction::GetLogVerbosityLevel {})
+            .await
+            .is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_retrying_client_with_predicate() {
+        let (client, attempts) = flaky_client(1, 400);
+        let client = RetryingTonClient::with_predicate(client, &retry_strategy(3), |e| {
+            matches!(e, TonClientError::TonlibError { code: 400, .. })
+        });
+        let result = client.invoke(&TonFunction::GetLogVerbosityLevel {}).await;
+        assert!(result.is_ok());
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_retry_strategy_max_interval_and_jitter() {
+        let retry_strategy = RetryStrategy {
+            interval_ms: 10,
+            max_retries: 5,
+            exponential_backoff: true,
+            max_interval_ms: Some(50),
+            ..Default::default()
+        };
+        let intervals: Vec<_> = retry_strategy.intervals().collect();
+        assert_eq!(
+            intervals,
+            [10, 20, 40, 50, 50].map(Duration::from_millis).to_vec()
+        );
+
+        let retry_strategy = RetryStrategy {
+            jitter: true,
+            ..retry_strategy
+        };
+        assert!(retry_strategy
+            .intervals()
+            .zip(intervals)
+            .all(|(jittered, full)| jittered <= full));
+    }
+}
@@ -0,0 +1,103 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::client::correlation::{correlation_id, in_correlation_scope};
+use crate::client::TonClientError;
+use crate::tl::TonResult;
+
+/// Span of a single tonlib request, carrying `tag`, `request_id`, `method`, `correlation_id`
+/// and, once the result is received, `duration_ms` and `error` if the request failed.
+///
+/// The span is a child of the span current when the request is sent, e.g. of a function
+/// annotated with `#[tracing::instrument]`, so requests show up in traces of the caller.
+/// Connection callbacks for the request are invoked inside the span, so their log lines
+/// get attached to it when `log` records are forwarded to `tracing`.
+/// Without the `tracing` feature only the correlation id is kept.
+pub(crate) struct RequestSpan {
+    correlation_id: Option<Arc<str>>,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl RequestSpan {
+    #[allow(unused_variables)]
+    pub(crate) fn new(tag: &str, request_id: u32, method: &str) -> RequestSpan {
+        let correlation_id = correlation_id();
+        RequestSpan {
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!(
+                "ton_request",
+                tag,
+                request_id,
+                method,
+                correlation_id = correlation_id.as_deref(),
+                duration_ms = tracing::field::Empty,
+                error = tracing::field::Empty
+            ),
+            correlation_id,
+        }
+    }
+
+    pub(crate) fn in_scope<T>(&self, f: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "tracing")]
+        let _entered = self.span.enter();
+        in_correlation_scope(self.correlation_id.as_ref(), f)
+    }
+
+    #[allow(unused_variables)]
+    pub(crate) fn record_duration(&self, duration: &Duration) {
+        #[cfg(feature = "tracing")]
+        self.span
+            .record("duration_ms", duration.as_secs_f64() * 1000.0);
+    }
+
+    /// Records the duration and, if `result` is an error, the error of the request.
+    pub(crate) fn record_result(
+        &self,
+        duration: &Duration,
+        result: &Result<TonResult, TonClientError>,
+    ) {
+        self.record_duration(duration);
+        if let Err(e) = result {
+            self.record_error(e);
+        }
+    }
+
+    #[allow(unused_variables)]
+    pub(crate) fn record_error(&self, error: &TonClientError) {
+        #[cfg(feature = "tracing")]
+        self.span.record("error", tracing::field::display(error));
+    }
+}
+
+/// Runs `future` inside a span carrying `method`, for invocations through a client which
+/// may retry the request on several connections, so that the request spans of all attempts
+/// share a parent.
+#[allow(unused_variables)]
+pub(crate) fn instrument_invoke<F: Future>(
+    method: &'static str,
+    future: F,
+) -> impl Future<Output = F::Output> {
+    #[cfg(feature = "tracing")]
+    {
+        use tracing::Instrument;
+        future.instrument(tracing::debug_span!("ton_invoke", method))
+    }
+    #[cfg(not(feature = "tracing"))]
+    future
+}
+
+/// Span of a connection run loop carrying `tag`, entered while the guard is alive.
+pub(crate) struct ConnectionSpanGuard {
+    #[cfg(feature = "tracing")]
+    _entered: tracing::span::EnteredSpan,
+}
+
+#[allow(unused_variables)]
+pub(crate) fn enter_connection_span(tag: &str) -> ConnectionSpanGuard {
+    ConnectionSpanGuard {
+        #[cfg(feature = "tracing")]
+        _entered: tracing::info_span!("ton_connection", tag).entered(),
+    }
+}
@@ -0,0 +1,313 @@
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::client::TonClientError;
+
+/// Fields of key functions and their results holding secrets, e.g. of `createNewKey`,
+/// `importKey` or `exportKey`.
+const SECRET_FIELDS: [&str; 5] = [
+    "local_password",
+    "mnemonic_password",
+    "random_extra_seed",
+    "secret",
+    "word_list",
+];
+/// Value replacing secrets in captured payloads.
+pub const REDACTED_SECRET: &str = "<redacted>";
+
+/// Capture of the raw TL traffic of a connection, see `TonConnectionParams::traffic_capture`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TrafficCapture {
+    /// Keeps the latest `capacity` records in memory, see `TonConnection::captured_traffic`.
+    RingBuffer { capacity: usize },
+    /// Appends records to the NDJSON file at `path`, one JSON object per line.
+    File { path: String },
+}
+
+impl TrafficCapture {
+    pub fn recorder(&self) -> Result<Arc<dyn TrafficRecorder>, TonClientError> {
+        Ok(match self {
+            TrafficCapture::RingBuffer { capacity } => {
+                Arc::new(RingBufferTrafficRecorder::new(*capacity))
+            }
+            TrafficCapture::File { path } => Arc::new(NdjsonTrafficRecorder::new(path)?),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TlTrafficDirection {
+    /// Function sent to tonlib.
+    Outgoing,
+    /// Result or notification received from tonlib.
+    Incoming,
+}
+
+/// Function sent to tonlib or result received from it, as JSON passed through tonlib.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TlTrafficRecord {
+    /// Tag of the connection.
+    pub tag: String,
+    pub direction: TlTrafficDirection,
+    /// Id of the request, `None` for notifications.
+    pub request_id: Option<u32>,
+    /// Method of the request, `None` for results of requests no longer awaited and notifications.
+    pub method: Option<String>,
+    /// Unix time of sending or receiving, in milliseconds.
+    pub unix_time_ms: u64,
+    /// Time since the request was sent, set for results of awaited requests.
+    #[serde(default)]
+    pub duration: Option<Duration>,
+    /// The function or result, `Null` if the result couldn't be received. Secrets of key
+    /// functions and results are replaced by `REDACTED_SECRET`.
+    pub payload: Value,
+    /// Error of receiving the result.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl TlTrafficRecord {
+    pub(crate) fn new(
+        tag: &str,
+        direction: TlTrafficDirection,
+        request_id: Option<u32>,
+        method: Option<&str>,
+        mut payload: Value,
+    ) -> TlTrafficRecord {
+        redact_secrets(&mut payload);
+        let unix_time_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or_default();
+        TlTrafficRecord {
+            tag: tag.to_string(),
+            direction,
+            request_id,
+            method: method.map(str::to_string),
+            unix_time_ms,
+            duration: None,
+            payload,
+            error: None,
+        }
+    }
+}
+
+/// Destination of the TL traffic captured by `TonConnection`.
+///
+/// `record` is called by the connection loop and by `invoke` callers, so it must not block
+/// for long. Secrets of key functions, e.g. mnemonics, local passwords and exported keys,
+/// are redacted before recording.
+pub trait TrafficRecorder: Send + Sync {
+    fn record(&self, record: &TlTrafficRecord);
+
+    /// Returns up to `count` latest records, oldest first. Recorders that don't keep records
+    /// return none.
+    fn latest(&self, _count: usize) -> Vec<TlTrafficRecord> {
+        vec![]
+    }
+}
+
+/// Keeps the latest `capacity` records, e.g. to log them once a request fails.
+pub struct RingBufferTrafficRecorder {
+    capacity: usize,
+    records: Mutex<VecDeque<TlTrafficRecord>>,
+}
+
+impl RingBufferTrafficRecorder {
+    pub fn new(capacity: usize) -> RingBufferTrafficRecorder {
+        RingBufferTrafficRecorder {
+            capacity,
+            records: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+}
+
+impl TrafficRecorder for RingBufferTrafficRecorder {
+    fn record(&self, record: &TlTrafficRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut records = self.records.lock().unwrap();
+        if records.len() == self.capacity {
+            records.pop_front();
+        }
+        records.push_back(record.clone());
+    }
+
+    fn latest(&self, count: usize) -> Vec<TlTrafficRecord> {
+        let records = self.records.lock().unwrap();
+        let skip = records.len().saturating_sub(count);
+        records.iter().skip(skip).cloned().collect()
+    }
+}
+
+/// Appends records to a file as NDJSON.
+///
+/// Every line is appended with a single write, so recorders of several connections may share
+/// a file, e.g. all connections of a `TonClient`, records are told apart by `tag`.
+pub struct NdjsonTrafficRecorder {
+    file: Mutex<File>,
+}
+
+impl NdjsonTrafficRecorder {
+    pub fn new(path: &str) -> Result<NdjsonTrafficRecorder, TonClientError> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(NdjsonTrafficRecorder {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl TrafficRecorder for NdjsonTrafficRecorder {
+    fn record(&self, record: &TlTrafficRecord) {
+        let mut line = match serde_json::to_vec(record) {
+            Ok(line) => line,
+            Err(e) => {
+                log::warn!("[{}] Failed to serialize traffic record: {}", record.tag, e);
+                return;
+            }
+        };
+        line.push(b'\n');
+        if let Err(e) = self.file.lock().unwrap().write_all(&line) {
+            log::warn!("[{}] Failed to write traffic record: {}", record.tag, e);
+        }
+    }
+}
+
+/// Replaces values of `SECRET_FIELDS` and the data of unencrypted keys in `value` by `REDACTED_SECRET`.
+fn redact_secrets(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            let unencrypted_key = object
+                .get("@type")
+                .is_some_and(|t| t == "exportedUnencryptedKey");
+            for (name, field) in object.iter_mut() {
+                if SECRET_FIELDS.contains(&name.as_str()) || (unencrypted_key && name == "data") {
+                    *field = Value::from(REDACTED_SECRET);
+                } else {
+                    redact_secrets(field);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use super::{TlTrafficDirection, TlTrafficRecord, TrafficCapture, REDACTED_SECRET};
+    use crate::tl::{InputKey, Key, TonFunction};
+
+    fn record(request_id: u32) -> TlTrafficRecord {
+        TlTrafficRecord::new(
+            "ton-conn-0",
+            TlTrafficDirection::Outgoing,
+            Some(request_id),
+            Some("GetLogVerbosityLevel"),
+            json!({"@type": "getLogVerbosityLevel"}),
+        )
+    }
+
+    #[test]
+    fn test_ring_buffer_keeps_latest_records() -> anyhow::Result<()> {
+        let recorder = TrafficCapture::RingBuffer { capacity: 3 }.recorder()?;
+        for request_id in 0..5 {
+            recorder.record(&record(request_id));
+        }
+        let ids = |records: Vec<TlTrafficRecord>| {
+            records
+                .iter()
+                .map(|r| r.request_id.unwrap())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(recorder.latest(10)), vec![2, 3, 4]);
+        assert_eq!(ids(recorder.latest(2)), vec![3, 4]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ndjson_recorder_appends_lines() -> anyhow::Result<()> {
+        let path = std::env::temp_dir().join(format!("tl-traffic-{}.ndjson", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let recorder = TrafficCapture::File {
+            path: path.to_string_lossy().to_string(),
+        }
+        .recorder()?;
+        let mut incoming = record(1);
+        incoming.direction = TlTrafficDirection::Incoming;
+        incoming.duration = Some(Duration::from_millis(15));
+        recorder.record(&record(1));
+        recorder.record(&incoming);
+        assert!(recorder.latest(10).is_empty());
+
+        let content = fs::read_to_string(&path)?;
+        let lines: Vec<TlTrafficRecord> = content
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?;
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].direction, TlTrafficDirection::Outgoing);
+        assert_eq!(lines[0].payload, json!({"@type": "getLogVerbosityLevel"}));
+        assert_eq!(lines[1].direction, TlTrafficDirection::Incoming);
+        assert_eq!(lines[1].duration, Some(Duration::from_millis(15)));
+        assert_eq!(lines[1].request_id, Some(1));
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_records_redact_secrets() -> anyhow::Result<()> {
+        let function = TonFunction::ExportUnencryptedKey {
+            input_key: InputKey::Regular {
+                key: Key {
+                    public_key: "public".to_string(),
+                    secret: vec![1; 32],
+                },
+                local_password: vec![2; 8],
+            },
+        };
+        let outgoing = TlTrafficRecord::new(
+            "ton-conn-0",
+            TlTrafficDirection::Outgoing,
+            Some(1),
+            Some("ExportUnencryptedKey"),
+            serde_json::to_value(&function)?,
+        );
+        let input_key = &outgoing.payload["input_key"];
+        assert_eq!(input_key["key"]["public_key"], "public");
+        assert_eq!(input_key["key"]["secret"], REDACTED_SECRET);
+        assert_eq!(input_key["local_password"], REDACTED_SECRET);
+
+        let incoming = TlTrafficRecord::new(
+            "ton-conn-0",
+            TlTrafficDirection::Incoming,
+            Some(1),
+            Some("ExportUnencryptedKey"),
+            json!({"@type": "exportedUnencryptedKey", "data": "c2VjcmV0"}),
+        );
+        assert_eq!(incoming.payload["data"], REDACTED_SECRET);
+        let incoming = TlTrafficRecord::new(
+            "ton-conn-0",
+            TlTrafficDirection::Incoming,
+            Some(2),
+            Some("ExportKey"),
+            json!({"@type": "exportedKey", "word_list": ["abandon", "ability"]}),
+        );
+        assert_eq!(incoming.payload["word_list"], REDACTED_SECRET);
+        Ok(())
+    }
+}
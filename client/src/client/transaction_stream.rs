@@ -0,0 +1,298 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use futures::stream::{self, BoxStream};
+use futures::StreamExt;
+use tokio::time;
+use tonlib_core::TonAddress;
+
+use crate::client::{TonClientError, TonClientInterface};
+use crate::tl::{InternalTransactionId, RawTransaction, NULL_TRANSACTION_ID};
+
+/// Number of transactions requested with one `get_raw_transactions_v2` call.
+pub const DEFAULT_TRANSACTION_PAGE_SIZE: usize = 16;
+
+/// Pages backwards through the transaction history of an account.
+///
+/// The result of `next` call is the previous transaction of the account, starting with
+/// the transaction the stream was created with, or `None` once the first transaction of
+/// the account or `stop_at_lt` is reached. Transactions whose lt is not lower than the lt of
+/// the last returned one are skipped, so a page overlapping with the previous one
+/// doesn't produce duplicates.
+pub struct TransactionStream<C: TonClientInterface + Clone> {
+    client: C,
+    address: TonAddress,
+    cursor: InternalTransactionId,
+    page_size: usize,
+    stop_at_lt: Option<i64>,
+    last_lt: Option<i64>,
+    pending: VecDeque<RawTransaction>,
+}
+
+impl<C: TonClientInterface + Clone> TransactionStream<C> {
+    /// Creates a stream starting with the transaction `from`, usually the
+    /// `last_transaction_id` of the account state.
+    pub fn new(client: &C, address: &TonAddress, from: &InternalTransactionId) -> Self {
+        TransactionStream {
+            client: client.clone(),
+            address: address.clone(),
+            cursor: from.clone(),
+            page_size: DEFAULT_TRANSACTION_PAGE_SIZE,
+            stop_at_lt: None,
+            last_lt: None,
+            pending: Default::default(),
+        }
+    }
+
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// Ends the stream before the first transaction with lt lower or equal to `lt`,
+    /// e.g. the last transaction processed in a previous run.
+    pub fn with_stop_at_lt(mut self, lt: i64) -> Self {
+        self.stop_at_lt = Some(lt);
+        self
+    }
+
+    /// Retrieves the previous transaction, `None` once the history is exhausted.
+    pub async fn next(&mut self) -> Result<Option<RawTransaction>, TonClientError> {
+        loop {
+            if let Some(tx) = self.pending.pop_front() {
+                let lt = tx.transaction_id.lt;
+                if matches!(self.stop_at_lt, Some(stop) if lt <= stop) {
+                    self.pending.clear();
+                    self.cursor = NULL_TRANSACTION_ID.clone();
+                    return Ok(None);
+                }
+                if matches!(self.last_lt, Some(last) if lt >= last) {
+                    continue;
+                }
+                self.last_lt = Some(lt);
+                return Ok(Some(tx));
+            }
+            if self.cursor.lt == 0 {
+                return Ok(None);
+            }
+            let txs = self
+                .client
+                .get_raw_transactions_v2(&self.address, &self.cursor, self.page_size, false)
+                .await?;
+            if txs.transactions.is_empty() {
+                self.cursor = NULL_TRANSACTION_ID.clone();
+                return Ok(None);
+            }
+            self.pending.extend(txs.transactions);
+            self.cursor = txs.previous_transaction_id;
+        }
+    }
+
+    /// Converts into a stream of transactions ending like `next`.
+    ///
+    /// The stream ends after the first error.
+    pub fn into_stream(self) -> BoxStream<'static, Result<RawTransaction, TonClientError>>
+    where
+        C: 'static,
+    {
+        stream::unfold(Some(self), |state| async move {
+            let mut tx_stream = state?;
+            match tx_stream.next().await {
+                Ok(Some(tx)) => Some((Ok(tx), Some(tx_stream))),
+                Ok(None) => None,
+                Err(e) => Some((Err(e), None)),
+            }
+        })
+        .boxed()
+    }
+}
+
+/// Follows new transactions of an account, like `tail -f`.
+///
+/// The account state is polled every `poll_interval`. Once its last transaction changes,
+/// all transactions since the previously known one are loaded with `TransactionStream`
+/// and returned by `next` in chronological order.
+pub struct TransactionTail<C: TonClientInterface + Clone> {
+    client: C,
+    address: TonAddress,
+    poll_interval: Duration,
+    last_lt: Option<i64>,
+    pending: VecDeque<RawTransaction>,
+}
+
+impl<C: TonClientInterface + Clone> TransactionTail<C> {
+    /// Creates a tail returning transactions with lt above `after_lt`, or, if `after_lt`
+    /// is `None`, transactions made after the first poll.
+    pub fn new(
+        client: &C,
+        address: &TonAddress,
+        after_lt: Option<i64>,
+        poll_interval: Duration,
+    ) -> Self {
+        TransactionTail {
+            client: client.clone(),
+            address: address.clone(),
+            poll_interval,
+            last_lt: after_lt,
+            pending: Default::default(),
+        }
+    }
+
+    /// Retrieves the next new transaction.
+    ///
+    /// If there is no new transaction yet, the returned future resolves when one is made.
+    pub async fn next(&mut self) -> Result<RawTransaction, TonClientError> {
+        loop {
+            if let Some(tx) = self.pending.pop_front() {
+                self.last_lt = Some(tx.transaction_id.lt);
+                return Ok(tx);
+            }
+            self.poll().await?;
+            if self.pending.is_empty() {
+                time::sleep(self.poll_interval).await;
+            }
+        }
+    }
+
+    async fn poll(&mut self) -> Result<(), TonClientError> {
+        let state = self.client.get_raw_account_state(&self.address).await?;
+        let last_tx_id = state.last_transaction_id;
+        let last_lt = match self.last_lt {
+            Some(lt) => lt,
+            None => {
+                self.last_lt = Some(last_tx_id.lt);
+                return Ok(());
+            }
+        };
+        if last_tx_id.lt <= last_lt {
+            return Ok(());
+        }
+        let mut tx_stream = TransactionStream::new(&self.client, &self.address, &last_tx_id)
+            .with_stop_at_lt(last_lt);
+        let mut new_txs = Vec::new();
+        while let Some(tx) = tx_stream.next().await? {
+            new_txs.push(tx);
+        }
+        self.pending.extend(new_txs.into_iter().rev());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use tonlib_core::TonAddress;
+
+    use super::TransactionStream;
+    use crate::client::{
+        TonClientError, TonClientInterface, TonConnection, TonConnectionParams,
+        NOOP_CONNECTION_CALLBACK,
+    };
+    use crate::tl::{
+        AccountAddress, InternalTransactionId, RawTransaction, RawTransactions, TonFunction,
+        TonResult, NULL_TRANSACTION_ID,
+    };
+
+    fn tx_id(lt: i64) -> InternalTransactionId {
+        InternalTransactionId {
+            lt,
+            hash: vec![lt as u8; 32],
+        }
+    }
+
+    fn tx(lt: i64) -> RawTransaction {
+        RawTransaction {
+            address: AccountAddress {
+                account_address: TonAddress::null().to_hex(),
+            },
+            utime: lt,
+            data: vec![],
+            transaction_id: tx_id(lt),
+            fee: 0,
+            storage_fee: 0,
+            other_fee: 0,
+            in_msg: None,
+            out_msgs: vec![],
+        }
+    }
+
+    /// Serves `raw.getTransactionsV2` from a history of transactions with given lts.
+    #[derive(Clone)]
+    struct MockClient {
+        connection: TonConnection,
+        lts: Vec<i64>,
+    }
+
+    #[async_trait]
+    impl TonClientInterface for MockClient {
+        async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+            Ok(self.connection.clone())
+        }
+
+        async fn invoke_on_connection(
+            &self,
+            function: &TonFunction,
+        ) -> Result<(TonConnection, TonResult), TonClientError> {
+            let (from, count) = match function {
+                TonFunction::RawGetTransactionsV2 {
+                    from_transaction_id,
+                    count,
+                    ..
+                } => (from_transaction_id.lt, *count as usize),
+                f => panic!("Unexpected function: {:?}", f),
+            };
+            let mut lts = self.lts.iter().filter(|lt| **lt <= from);
+            let transactions: Vec<_> = lts.by_ref().take(count).map(|lt| tx(*lt)).collect();
+            let previous_transaction_id = match lts.next() {
+                Some(lt) => tx_id(*lt),
+                None => NULL_TRANSACTION_ID.clone(),
+            };
+            let result = TonResult::RawTransactions(RawTransactions {
+                transactions,
+                previous_transaction_id,
+            });
+            Ok((self.connection.clone(), result))
+        }
+    }
+
+    fn mock_client() -> MockClient {
+        let connection = TonConnection::new(
+            NOOP_CONNECTION_CALLBACK.clone(),
+            &TonConnectionParams::default(),
+        )
+        .unwrap();
+        MockClient {
+            connection,
+            lts: vec![90, 70, 50, 40, 30, 20, 10],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transaction_stream_pages_backwards() {
+        let client = mock_client();
+        let stream = TransactionStream::new(&client, &TonAddress::null(), &tx_id(70))
+            .with_page_size(2)
+            .into_stream();
+        let lts: Vec<_> = stream
+            .map(|tx| tx.unwrap().transaction_id.lt)
+            .collect()
+            .await;
+        assert_eq!(lts, vec![70, 50, 40, 30, 20, 10]);
+    }
+
+    #[tokio::test]
+    async fn test_transaction_stream_stops_at_lt() -> Result<(), TonClientError> {
+        let client = mock_client();
+        let mut stream = TransactionStream::new(&client, &TonAddress::null(), &tx_id(90))
+            .with_page_size(3)
+            .with_stop_at_lt(40);
+        let mut lts = Vec::new();
+        while let Some(tx) = stream.next().await? {
+            lts.push(tx.transaction_id.lt);
+        }
+        assert_eq!(lts, vec![90, 70, 50]);
+        assert!(stream.next().await?.is_none());
+        Ok(())
+    }
+}
@@ -1,20 +1,35 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use lazy_static::lazy_static;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, mpsc};
 use tonlib_core::TonAddress;
 
 use super::{
-    BlocksShortTxId, TonClientError, DEFAULT_CONNECTION_CONCURRENCY_LIMIT,
-    DEFAULT_NOTIFICATION_QUEUE_LENGTH, DEFAULT_UPDATE_INIT_BLOCK,
+    BlocksShortTxId, TonClientError, TonConnectionParamsBuilder, TrafficCapture,
+    DEFAULT_CONNECTION_CONCURRENCY_LIMIT, DEFAULT_NOTIFICATION_QUEUE_LENGTH,
+    DEFAULT_RECONNECT_ERROR_THRESHOLD, DEFAULT_REQUEST_REAP_INTERVAL, DEFAULT_UPDATE_INIT_BLOCK,
 };
 use crate::config::MAINNET_CONFIG;
 use crate::tl::{InternalTransactionId, TonNotification};
 
 pub type TonNotificationReceiver = broadcast::Receiver<Arc<TonNotification>>;
 
-#[derive(Debug, Clone, Eq, Hash, PartialEq)]
+/// Receiver of `TonConnection::subscribe_reliable`, which never loses notifications.
+pub type TonReliableNotificationReceiver = mpsc::Receiver<Arc<TonNotification>>;
+
+/// External message sent by `TonClientInterface::send_message`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SentMessage {
+    /// Hash of the message cell.
+    pub hash: Vec<u8>,
+    /// Whether the message had already been sent, e.g. through another lite server.
+    pub duplicate: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Eq, Hash, PartialEq)]
 pub struct TxId {
     pub address: TonAddress,
     pub internal_transaction_id: InternalTransactionId,
@@ -44,6 +59,17 @@ pub struct TonConnectionParams {
     pub config: String,
     #[serde(default)]
     pub blockchain_name: Option<String>,
+    /// Indices of liteservers of `config` the connection is restricted to,
+    /// `None` to let tonlib choose among all of them.
+    #[serde(default)]
+    pub liteserver_indices: Option<Vec<usize>>,
+    /// Number of attempts of `TonConnection::connect` to initialize a connection that passes
+    /// `TonConnection::health_check`, `None` to return the initialized connection unchecked.
+    ///
+    /// Every attempt is restricted to a single liteserver, going through `liteserver_indices`,
+    /// or all liteservers of `config`, in order.
+    #[serde(default)]
+    pub connect_probe_attempts: Option<usize>,
     #[serde(default)]
     pub use_callbacks_for_network: bool,
     #[serde(default)]
@@ -52,10 +78,84 @@ pub struct TonConnectionParams {
     pub keystore_dir: Option<String>,
     #[serde(default = "default_notification_queue_length")]
     pub notification_queue_length: usize,
+    /// Number of queued notifications at which
+    /// `TonConnectionCallback::on_notification_queue_high_watermark` gets called.
+    /// `None` disables the check.
+    #[serde(default)]
+    pub notification_queue_high_watermark: Option<usize>,
+    /// Number of requests in flight at which
+    /// `TonConnectionCallback::on_request_queue_high_watermark` gets called.
+    /// `None` disables the check.
+    #[serde(default)]
+    pub request_queue_high_watermark: Option<usize>,
+    /// Maximum number of requests in flight, further requests wait for a free slot in the
+    /// order of their `RequestPriority`. `0` for no limit, which makes priorities ineffective.
     #[serde(default = "default_connection_concurrency_limit")]
     pub concurrency_limit: usize,
+    /// Maximum number of requests sent per second, further requests are delayed.
+    /// `None` for no limit.
+    #[serde(default)]
+    pub max_rps: Option<u32>,
+    /// Number of requests that may be sent at once before `max_rps` applies,
+    /// `None` to allow bursts of `max_rps` requests.
+    #[serde(default)]
+    pub rate_limit_burst: Option<u32>,
     #[serde(default = "default_update_init_block")]
     pub update_init_block: bool,
+    /// Maximum time to wait for a response from tonlib, `None` to wait indefinitely.
+    #[serde(default)]
+    pub request_timeout: Option<Duration>,
+    /// Duration above which a completed request is reported with
+    /// `TonConnectionCallback::on_slow_invoke`, `None` disables the check.
+    #[serde(default)]
+    pub slow_request_threshold: Option<Duration>,
+    /// Age after which a request still awaiting its result is removed and completed with
+    /// `TonClientError::Timeout`, `None` to keep requests indefinitely.
+    ///
+    /// This also covers requests whose callers are gone, e.g. cancelled futures.
+    #[serde(default)]
+    pub max_request_age: Option<Duration>,
+    /// How often requests are checked against `max_request_age`.
+    #[serde(default = "default_request_reap_interval")]
+    pub request_reap_interval: Duration,
+    /// Replace the tonlib client and re-run `init` once `reconnect_error_threshold` receive
+    /// calls in a row fail. Requests in flight are completed with `TonClientError::Reconnecting`.
+    #[serde(default)]
+    pub reconnect: bool,
+    #[serde(default = "default_reconnect_error_threshold")]
+    pub reconnect_error_threshold: usize,
+    /// Replace the tonlib client and re-run `init` once nothing was received from tonlib for
+    /// this long while requests were in flight, `None` disables the check. Requests in flight
+    /// are completed with `TonClientError::ConnectionReset`.
+    #[serde(default)]
+    pub stall_timeout: Option<Duration>,
+    /// Run the connection loop with `tokio::task::spawn_blocking` instead of a dedicated thread.
+    ///
+    /// The loop occupies a thread of the blocking pool for the whole lifetime of the connection,
+    /// so the runtime's `max_blocking_threads` must exceed the number of connections, leaving
+    /// room for other blocking tasks.
+    #[serde(default)]
+    pub run_loop_on_blocking_pool: bool,
+    /// Contracts queried on a new pool connection before it starts serving requests.
+    #[serde(default)]
+    pub warmup: ConnectionWarmup,
+    /// Log verbosity of the tonlib C library applied when the connection is created,
+    /// `None` keeps the current level.
+    ///
+    /// The level is global for all tonlib clients of the process, see
+    /// `TonClient::set_log_verbosity_level` for the meaning of the values.
+    #[serde(default)]
+    pub log_verbosity_level: Option<u32>,
+    /// Records every function sent to tonlib and every result received from it,
+    /// `None` disables the capture. See `TonConnection::captured_traffic`.
+    #[serde(default)]
+    pub traffic_capture: Option<TrafficCapture>,
+}
+
+impl TonConnectionParams {
+    pub fn builder() -> TonConnectionParamsBuilder {
+        TonConnectionParamsBuilder::default()
+    }
 }
 
 impl Default for TonConnectionParams {
@@ -63,12 +163,29 @@ impl Default for TonConnectionParams {
         TonConnectionParams {
             config: MAINNET_CONFIG.to_string(),
             blockchain_name: None,
+            liteserver_indices: None,
+            connect_probe_attempts: None,
             use_callbacks_for_network: false,
             ignore_cache: false,
             keystore_dir: None,
             notification_queue_length: DEFAULT_NOTIFICATION_QUEUE_LENGTH,
+            notification_queue_high_watermark: None,
+            request_queue_high_watermark: None,
             concurrency_limit: DEFAULT_CONNECTION_CONCURRENCY_LIMIT,
+            max_rps: None,
+            rate_limit_burst: None,
             update_init_block: DEFAULT_UPDATE_INIT_BLOCK,
+            request_timeout: None,
+            slow_request_threshold: None,
+            max_request_age: None,
+            request_reap_interval: DEFAULT_REQUEST_REAP_INTERVAL,
+            reconnect: false,
+            reconnect_error_threshold: DEFAULT_RECONNECT_ERROR_THRESHOLD,
+            stall_timeout: None,
+            run_loop_on_blocking_pool: false,
+            warmup: ConnectionWarmup::default(),
+            log_verbosity_level: None,
+            traffic_capture: None,
         }
     }
 }
@@ -84,6 +201,56 @@ fn default_update_init_block() -> bool {
     DEFAULT_UPDATE_INIT_BLOCK
 }
 
+fn default_request_reap_interval() -> Duration {
+    DEFAULT_REQUEST_REAP_INTERVAL
+}
+
+fn default_reconnect_error_threshold() -> usize {
+    DEFAULT_RECONNECT_ERROR_THRESHOLD
+}
+
+/// Contracts to pre-load on a connection, so that tonlib caches of a fresh connection
+/// are warm before it receives traffic.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct ConnectionWarmup {
+    /// Accounts whose states get fetched.
+    #[serde(default)]
+    pub accounts: Vec<TonAddress>,
+    /// Get-methods to run with empty stack, e.g. `get_jetton_data` of jetton masters.
+    #[serde(default)]
+    pub get_methods: Vec<(TonAddress, String)>,
+}
+
+impl ConnectionWarmup {
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty() && self.get_methods.is_empty()
+    }
+}
+
+/// Request counters of a connection returned by `TonConnection::stats`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionStats {
+    pub tag: String,
+    /// Number of requests awaiting a result from tonlib.
+    pub in_flight: usize,
+    /// Number of requests sent since the connection was created.
+    pub total_invocations: u32,
+    /// Number of notifications overwritten in a full queue before a subscriber received them,
+    /// see `TonConnectionCallback::on_notification_dropped`.
+    pub dropped_notifications: u64,
+}
+
+/// Result of `TonConnection::health_check`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HealthStatus {
+    /// Whether tonlib isn't catching up with the masterchain, according to the last
+    /// sync state it reported.
+    pub synced: bool,
+    pub masterchain_seqno: i32,
+    /// Round-trip time of the masterchain info request.
+    pub latency: Duration,
+}
+
 lazy_static! {
     pub static ref DEFAULT_CONNECTION_PARAMS: TonConnectionParams = TonConnectionParams::default();
 }
@@ -92,17 +259,84 @@ lazy_static! {
 pub struct RetryStrategy {
     pub interval_ms: u64,
     pub max_retries: usize,
+    /// Tonlib error codes considered transient in addition to errors of transient
+    /// `TonlibErrorKind`s, e.g. `NotReady`, other errors are returned immediately.
+    #[serde(default = "default_retryable_codes")]
+    pub retryable_codes: Vec<i32>,
+    /// Doubles the interval after every retry if set.
+    #[serde(default)]
+    pub exponential_backoff: bool,
+    /// Upper bound of the interval growing with `exponential_backoff`, `None` for no bound.
+    #[serde(default)]
+    pub max_interval_ms: Option<u64>,
+    /// Waits a random duration between zero and the interval instead of the full interval,
+    /// so that clients failing at the same time don't retry at the same time.
+    #[serde(default)]
+    pub jitter: bool,
+    /// Also retries requests failed with `TonClientError::Timeout`.
+    #[serde(default)]
+    pub retry_timeouts: bool,
+}
+
+impl RetryStrategy {
+    /// Returns the delays before every retry.
+    pub fn intervals(&self) -> impl Iterator<Item = Duration> {
+        let interval_ms = self.interval_ms;
+        let exponential_backoff = self.exponential_backoff;
+        let max_interval_ms = self.max_interval_ms.unwrap_or(u64::MAX);
+        let jitter = self.jitter;
+        (0..self.max_retries).map(move |i| {
+            let factor = if exponential_backoff {
+                1u64 << i.min(MAX_BACKOFF_SHIFT)
+            } else {
+                1
+            };
+            let ms = interval_ms.saturating_mul(factor).min(max_interval_ms);
+            let ms = if jitter {
+                rand::thread_rng().gen_range(0..=ms)
+            } else {
+                ms
+            };
+            Duration::from_millis(ms)
+        })
+    }
+
+    pub fn is_retryable(&self, error: &TonClientError) -> bool {
+        match error {
+            TonClientError::TonlibError { code, .. } => {
+                self.retryable_codes.contains(code)
+                    || error
+                        .tonlib_error_kind()
+                        .is_some_and(|kind| kind.is_transient())
+            }
+            TonClientError::Timeout { .. } => self.retry_timeouts,
+            // Retried on another pool connection, the closed one is replaced on next use
+            TonClientError::ConnectionClosed { .. } => true,
+            _ => false,
+        }
+    }
 }
 
+const MAX_BACKOFF_SHIFT: usize = 20;
+
 impl Default for RetryStrategy {
     fn default() -> Self {
         RetryStrategy {
             interval_ms: 5,
             max_retries: 10,
+            retryable_codes: default_retryable_codes(),
+            exponential_backoff: false,
+            max_interval_ms: None,
+            jitter: false,
+            retry_timeouts: false,
         }
     }
 }
 
+fn default_retryable_codes() -> Vec<i32> {
+    vec![500]
+}
+
 lazy_static! {
     pub static ref DEFAULT_RETRY_STRATEGY: RetryStrategy = RetryStrategy::default();
 }
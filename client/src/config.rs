@@ -1,8 +1,215 @@
+use std::fs;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use futures::future::join_all;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use sha2::{Digest, Sha256};
+use tokio::net::TcpStream;
+use tokio::sync::watch;
+use tokio::time::{self, Instant};
+
+use crate::client::TonClientError;
+
+mod network;
+
+pub use network::*;
 
 pub const MAINNET_CONFIG: &str = include_str!("../resources/config/global.config.json");
 pub const TESTNET_CONFIG: &str = include_str!("../resources/config/testnet-global.config.json");
+pub const MAINNET_CONFIG_URL: &str = "https://ton.org/global.config.json";
+pub const TESTNET_CONFIG_URL: &str = "https://ton.org/testnet-global.config.json";
+
+/// Downloads network config from `url` and checks that it's a valid global config.
+///
+/// The returned string can be used as `TonConnectionParams::config`.
+pub async fn fetch_config(url: &str) -> Result<String, TonClientError> {
+    let fetch = async { reqwest::get(url).await?.error_for_status()?.text().await };
+    let config = fetch.await.map_err(|e| {
+        let msg = format!("Fail to fetch config from {}: {}", url, e);
+        TonClientError::InternalError(msg)
+    })?;
+    validate_config(&config).map_err(|e| {
+        let msg = format!("Invalid config fetched from {}: {}", url, e);
+        TonClientError::InternalError(msg)
+    })?;
+    Ok(config)
+}
+
+pub async fn fetch_mainnet_config() -> Result<String, TonClientError> {
+    fetch_config(MAINNET_CONFIG_URL).await
+}
+
+pub async fn fetch_testnet_config() -> Result<String, TonClientError> {
+    fetch_config(TESTNET_CONFIG_URL).await
+}
+
+/// Same as `fetch_config`, but stores the downloaded config in `cache_dir` and returns
+/// the stored copy while it's younger than `ttl`.
+///
+/// Cache files are named after the SHA-256 of `url`. A cached copy that can't be read
+/// or is invalid is downloaded again.
+pub async fn fetch_config_cached(
+    url: &str,
+    cache_dir: &Path,
+    ttl: Duration,
+) -> Result<String, TonClientError> {
+    let cache_path = cache_dir.join(format!("{}.json", hex::encode(Sha256::digest(url))));
+    let is_fresh = fs::metadata(&cache_path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .is_some_and(|age| age < ttl);
+    if is_fresh {
+        match fs::read_to_string(&cache_path) {
+            Ok(config) if validate_config(&config).is_ok() => return Ok(config),
+            _ => log::warn!("Ignoring invalid cached config {:?}", cache_path),
+        }
+    }
+    let config = fetch_config(url).await?;
+    fs::create_dir_all(cache_dir)?;
+    fs::write(&cache_path, &config)?;
+    Ok(config)
+}
+
+/// Keeps the config downloaded from `url` up to date, caching it in `cache_dir` as
+/// `fetch_config_cached` does.
+///
+/// The returned receiver holds the current config, which is re-downloaded every `ttl`
+/// in a background task. A failed refresh keeps the current config and is retried after
+/// another `ttl`. The task stops once all receivers are dropped.
+///
+/// Configs are passed to `TonConnection::connect` on connection, so a refreshed config
+/// affects connections created after the refresh.
+pub async fn watch_config(
+    url: &str,
+    cache_dir: &Path,
+    ttl: Duration,
+) -> Result<watch::Receiver<String>, TonClientError> {
+    let config = fetch_config_cached(url, cache_dir, ttl).await?;
+    let (sender, receiver) = watch::channel(config);
+    let url = url.to_string();
+    let cache_dir = PathBuf::from(cache_dir);
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = time::sleep(ttl) => {},
+                _ = sender.closed() => return,
+            }
+            match fetch_config_cached(&url, &cache_dir, ttl).await {
+                Ok(config) => {
+                    sender.send_if_modified(|current| {
+                        let modified = *current != config;
+                        *current = config;
+                        modified
+                    });
+                }
+                Err(e) => log::warn!("Failed to refresh config from {}: {}", url, e),
+            }
+        }
+    });
+    Ok(receiver)
+}
+
+/// Measures TCP connect time of every liteserver of `config`, in the order of the config.
+///
+/// `None` stands for a liteserver that couldn't be reached within `timeout`.
+pub async fn measure_liteserver_latencies(
+    config: &str,
+    timeout: Duration,
+) -> Result<Vec<Option<Duration>>, TonClientError> {
+    let config: TonConfig = serde_json::from_str(config)
+        .map_err(|e| TonClientError::InternalError(format!("Invalid config: {}", e)))?;
+    let measurements = config.liteservers.iter().map(|ls| async move {
+        let start = Instant::now();
+        match time::timeout(timeout, TcpStream::connect(ls.socket_addr())).await {
+            Ok(Ok(_)) => Some(start.elapsed()),
+            _ => None,
+        }
+    });
+    Ok(join_all(measurements).await)
+}
+
+/// Returns `config` with unreachable liteservers removed and the rest ordered by latency,
+/// at most `max_count` of them, see `measure_liteserver_latencies`.
+///
+/// Tonlib tries liteservers in the config order, so the fastest ones are tried first.
+pub async fn prioritize_liteservers(
+    config: &str,
+    timeout: Duration,
+    max_count: usize,
+) -> Result<String, TonClientError> {
+    let latencies = measure_liteserver_latencies(config, timeout).await?;
+    let indices = rank_by_latency(&latencies, max_count);
+    if indices.is_empty() {
+        return Err(TonClientError::InternalError(format!(
+            "None of {} liteservers is reachable",
+            latencies.len()
+        )));
+    }
+    select_liteservers(config, &indices)
+}
+
+fn rank_by_latency(latencies: &[Option<Duration>], max_count: usize) -> Vec<usize> {
+    let mut reachable: Vec<_> = latencies
+        .iter()
+        .enumerate()
+        .filter_map(|(i, latency)| latency.map(|l| (i, l)))
+        .collect();
+    reachable.sort_by_key(|(_, latency)| *latency);
+    reachable
+        .into_iter()
+        .take(max_count)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Returns `config` with the `liteservers` array restricted to the entries at `indices`,
+/// in the given order.
+///
+/// All other parts of the config are preserved as is.
+pub fn select_liteservers(config: &str, indices: &[usize]) -> Result<String, TonClientError> {
+    let invalid_config =
+        |e: String| TonClientError::InternalError(format!("Invalid config: {}", e));
+    let mut value: Value =
+        serde_json::from_str(config).map_err(|e| invalid_config(e.to_string()))?;
+    let liteservers = value
+        .get_mut("liteservers")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| invalid_config("no liteservers array".to_string()))?;
+    if indices.is_empty() {
+        return Err(TonClientError::InternalError(
+            "No liteservers selected".to_string(),
+        ));
+    }
+    let selected = indices
+        .iter()
+        .map(|i| {
+            liteservers.get(*i).cloned().ok_or_else(|| {
+                TonClientError::InternalError(format!(
+                    "Liteserver index {} out of range, config has {} liteservers",
+                    i,
+                    liteservers.len()
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    *liteservers = selected;
+    serde_json::to_string(&value).map_err(|e| invalid_config(e.to_string()))
+}
+
+/// Returns the number of liteservers in `config`.
+pub fn liteserver_count(config: &str) -> Result<usize, TonClientError> {
+    let config: TonConfig = serde_json::from_str(config)
+        .map_err(|e| TonClientError::InternalError(format!("Invalid config: {}", e)))?;
+    Ok(config.liteservers.len())
+}
+
+fn validate_config(config: &str) -> Result<(), serde_json::Error> {
+    serde_json::from_str::<TonConfig>(config).map(|_| ())
+}
 
 #[derive(Serialize, Deserialize)]
 pub(crate) struct TonConfig {
@@ -42,6 +249,13 @@ pub(crate) struct LiteEndpoint {
     pub id: LiteID,
 }
 
+impl LiteEndpoint {
+    /// Returns the address of the liteserver, the config stores IPv4 address as signed integer.
+    pub fn socket_addr(&self) -> SocketAddr {
+        SocketAddr::from((Ipv4Addr::from(self.ip as u32), self.port))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct LiteID {
     #[serde(rename = "@type")]
@@ -57,3 +271,119 @@ pub(crate) struct Validator {
     pub init_block: Value,
     pub hardforks: Value,
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+    use std::thread::JoinHandle;
+    use std::time::Duration;
+
+    use serde_json::Value;
+
+    use super::{
+        fetch_config, fetch_config_cached, liteserver_count, rank_by_latency, select_liteservers,
+        watch_config, MAINNET_CONFIG, TESTNET_CONFIG,
+    };
+
+    /// Serves `bodies` to consecutive HTTP requests, one connection per body.
+    fn serve(bodies: Vec<&'static str>) -> (String, JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!(
+            "http://{}/global.config.json",
+            listener.local_addr().unwrap()
+        );
+        let handle = thread::spawn(move || {
+            for body in bodies {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0u8; 4096];
+                let _ = stream.read(&mut request).unwrap();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        (url, handle)
+    }
+
+    #[tokio::test]
+    async fn test_fetch_config() {
+        let (url, handle) = serve(vec![MAINNET_CONFIG, "{\"liteservers\": []}"]);
+        assert_eq!(fetch_config(&url).await.unwrap(), MAINNET_CONFIG);
+        assert!(fetch_config(&url).await.is_err());
+        handle.join().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_fetch_config_cached() {
+        let cache_dir =
+            std::env::temp_dir().join(format!("tonlib-config-{}", rand::random::<u64>()));
+        let ttl = Duration::from_secs(60);
+        // The server answers only once, so the second call must be served from cache
+        let (url, handle) = serve(vec![MAINNET_CONFIG]);
+        let config = fetch_config_cached(&url, &cache_dir, ttl).await.unwrap();
+        handle.join().unwrap();
+        assert_eq!(config, MAINNET_CONFIG);
+        let cached = fetch_config_cached(&url, &cache_dir, ttl).await.unwrap();
+        assert_eq!(cached, MAINNET_CONFIG);
+        // Expired copy is downloaded again, failing since the server is gone
+        assert!(fetch_config_cached(&url, &cache_dir, Duration::ZERO)
+            .await
+            .is_err());
+        std::fs::remove_dir_all(&cache_dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_watch_config() {
+        let cache_dir =
+            std::env::temp_dir().join(format!("tonlib-config-{}", rand::random::<u64>()));
+        let ttl = Duration::from_millis(200);
+        let (url, handle) = serve(vec![MAINNET_CONFIG, TESTNET_CONFIG]);
+        let mut receiver = watch_config(&url, &cache_dir, ttl).await.unwrap();
+        assert_eq!(*receiver.borrow_and_update(), MAINNET_CONFIG);
+        tokio::time::timeout(Duration::from_secs(5), receiver.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(*receiver.borrow(), TESTNET_CONFIG);
+        handle.join().unwrap();
+        std::fs::remove_dir_all(&cache_dir).unwrap();
+    }
+
+    #[test]
+    fn test_rank_by_latency() {
+        let ms = |ms| Some(Duration::from_millis(ms));
+        let latencies = vec![ms(30), None, ms(10), ms(20), None];
+        assert_eq!(rank_by_latency(&latencies, 10), vec![2, 3, 0]);
+        assert_eq!(rank_by_latency(&latencies, 2), vec![2, 3]);
+        assert!(rank_by_latency(&[None, None], 2).is_empty());
+    }
+
+    #[test]
+    fn test_select_liteservers() {
+        let original: Value = serde_json::from_str(MAINNET_CONFIG).unwrap();
+        let liteservers = original["liteservers"].as_array().unwrap();
+        assert!(liteservers.len() > 3);
+
+        let config = select_liteservers(MAINNET_CONFIG, &[3, 1]).unwrap();
+        let selected: Value = serde_json::from_str(&config).unwrap();
+        assert_eq!(
+            selected["liteservers"].as_array().unwrap(),
+            &vec![liteservers[3].clone(), liteservers[1].clone()]
+        );
+        assert_eq!(selected["validator"], original["validator"]);
+        assert_eq!(selected["dht"], original["dht"]);
+
+        assert!(select_liteservers(MAINNET_CONFIG, &[]).is_err());
+        assert!(select_liteservers(MAINNET_CONFIG, &[liteservers.len()]).is_err());
+        assert!(select_liteservers("{}", &[0]).is_err());
+
+        assert_eq!(liteserver_count(MAINNET_CONFIG).unwrap(), liteservers.len());
+        assert_eq!(liteserver_count(&config).unwrap(), 2);
+        assert!(liteserver_count("{}").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use tonlib_core::{TonAddress, TonAddressFlags};
+
+use super::{fetch_config, MAINNET_CONFIG, MAINNET_CONFIG_URL, TESTNET_CONFIG, TESTNET_CONFIG_URL};
+use crate::client::TonClientError;
+
+/// Address of the config contract, config param 0 of mainnet and testnet.
+pub const CONFIG_CONTRACT_ADDRESS: TonAddress = TonAddress {
+    workchain: -1,
+    hash_part: [0x55; 32],
+};
+
+/// Address of the elector contract, config param 1 of mainnet and testnet.
+pub const ELECTOR_CONTRACT_ADDRESS: TonAddress = TonAddress {
+    workchain: -1,
+    hash_part: [0x33; 32],
+};
+
+/// Zero address of the basechain conventionally used to burn coins and tokens.
+pub const BURN_ADDRESS: TonAddress = TonAddress::NULL;
+
+/// TON network a client connects to.
+///
+/// Bundles the settings that have to be consistent with each other: the embedded and
+/// downloadable configs, the testnet flag of user-friendly addresses and the addresses
+/// of system contracts.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Testnet,
+    /// Network with the given global config, e.g. a local one.
+    Custom {
+        config: String,
+    },
+}
+
+impl Network {
+    /// Returns the config embedded into the crate, or the config of a custom network.
+    ///
+    /// Embedded configs can get stale, see `fetch_config` for an up-to-date one.
+    pub fn config(&self) -> &str {
+        match self {
+            Network::Mainnet => MAINNET_CONFIG,
+            Network::Testnet => TESTNET_CONFIG,
+            Network::Custom { config } => config,
+        }
+    }
+
+    /// Returns the URL of the official config, `None` for a custom network.
+    pub fn config_url(&self) -> Option<&'static str> {
+        match self {
+            Network::Mainnet => Some(MAINNET_CONFIG_URL),
+            Network::Testnet => Some(TESTNET_CONFIG_URL),
+            Network::Custom { .. } => None,
+        }
+    }
+
+    /// Downloads the official config, a custom network returns its own config.
+    pub async fn fetch_config(&self) -> Result<String, TonClientError> {
+        match self.config_url() {
+            Some(url) => fetch_config(url).await,
+            None => Ok(self.config().to_string()),
+        }
+    }
+
+    pub fn is_testnet(&self) -> bool {
+        matches!(self, Network::Testnet)
+    }
+
+    /// Returns flags of user-friendly addresses of the network.
+    pub fn address_flags(&self, bounceable: bool) -> TonAddressFlags {
+        TonAddressFlags::new(bounceable, self.is_testnet())
+    }
+
+    /// Formats `address` in user-friendly form with the testnet flag set for testnet.
+    pub fn format_address(&self, address: &TonAddress, bounceable: bool) -> String {
+        address
+            .formatter()
+            .with_flags(self.address_flags(bounceable))
+            .format()
+    }
+
+    /// Returns the address of the config contract.
+    ///
+    /// Custom networks are assumed to keep the standard address, the actual one is
+    /// config param 0.
+    pub fn config_contract_address(&self) -> TonAddress {
+        CONFIG_CONTRACT_ADDRESS
+    }
+
+    /// Returns the address of the elector contract.
+    ///
+    /// Custom networks are assumed to keep the standard address, the actual one is
+    /// config param 1.
+    pub fn elector_contract_address(&self) -> TonAddress {
+        ELECTOR_CONTRACT_ADDRESS
+    }
+
+    pub fn burn_address(&self) -> TonAddress {
+        BURN_ADDRESS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use tonlib_core::TonAddress;
+
+    use super::{Network, CONFIG_CONTRACT_ADDRESS, ELECTOR_CONTRACT_ADDRESS};
+    use crate::config::{MAINNET_CONFIG, TESTNET_CONFIG, TESTNET_CONFIG_URL};
+
+    #[test]
+    fn test_network() {
+        assert_eq!(Network::default(), Network::Mainnet);
+        assert_eq!(Network::Mainnet.config(), MAINNET_CONFIG);
+        assert_eq!(Network::Testnet.config(), TESTNET_CONFIG);
+        assert_eq!(Network::Testnet.config_url(), Some(TESTNET_CONFIG_URL));
+        let custom = Network::Custom {
+            config: "{}".to_string(),
+        };
+        assert_eq!(custom.config(), "{}");
+        assert_eq!(custom.config_url(), None);
+
+        let address =
+            TonAddress::from_str("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR").unwrap();
+        assert_eq!(
+            Network::Mainnet.format_address(&address, true),
+            "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR"
+        );
+        assert_eq!(
+            Network::Testnet.format_address(&address, true),
+            address.to_base64_url_flags(false, true)
+        );
+        assert!(!custom.address_flags(false).testnet);
+
+        assert_eq!(
+            CONFIG_CONTRACT_ADDRESS.to_hex(),
+            "-1:5555555555555555555555555555555555555555555555555555555555555555"
+        );
+        assert_eq!(
+            ELECTOR_CONTRACT_ADDRESS.to_hex(),
+            "-1:3333333333333333333333333333333333333333333333333333333333333333"
+        );
+        assert_eq!(Network::Testnet.burn_address(), TonAddress::NULL);
+    }
+}
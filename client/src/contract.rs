@@ -1,26 +1,41 @@
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
+pub use dns::*;
+pub use elector::*;
 pub use error::*;
 pub use factory::*;
 pub use interface::*;
+pub use interface_detection::*;
 pub use jetton::*;
 pub use latest_transactions_cache::*;
+pub use multisig::*;
 pub use nft::*;
+pub use provider_contract::*;
 pub use state::*;
+use tonlib_core::cell::BagOfCells;
 use tonlib_core::TonAddress;
 pub use wallet::*;
 
-use crate::client::TonClientInterface;
+use crate::client::{
+    AccountInfo, TonClient, TonClientInterface, TransactionStream, TransactionTail,
+};
 use crate::tl::{InternalTransactionId, RawFullAccountState};
 use crate::types::{TonMethodId, TvmStackEntry, TvmSuccess};
 
+mod dns;
+mod elector;
 mod error;
 mod factory;
 mod interface;
+mod interface_detection;
 mod jetton;
 mod latest_transactions_cache;
+mod multisig;
 mod nft;
+mod provider_contract;
 mod state;
 mod wallet;
 
@@ -46,6 +61,57 @@ impl TonContract {
             .await
     }
 
+    /// Returns the latest state of the contract with parsed code and data,
+    /// see `AccountInfo::state`.
+    pub async fn get_account_state_full(&self) -> Result<AccountInfo, TonContractError> {
+        let state = self.get_account_state().await?;
+        Ok(AccountInfo::from_raw(&self.address, &state)?)
+    }
+
+    /// Same as `get_account_state_full`, but returns the state right after the transaction
+    /// with `transaction_id`, which may require an archive node for old transactions.
+    pub async fn load_by_transaction(
+        &self,
+        transaction_id: &InternalTransactionId,
+    ) -> Result<AccountInfo, TonContractError> {
+        let state = self
+            .get_account_state_by_transaction(transaction_id)
+            .await?;
+        Ok(AccountInfo::from_raw(&self.address, &state)?)
+    }
+
+    /// Detects standard interfaces of the contract, see `detect_interfaces_with`.
+    pub async fn detect_interfaces(&self) -> Result<HashSet<ContractInterface>, TonContractError> {
+        self.detect_interfaces_with(&KNOWN_CODE_HASHES).await
+    }
+
+    /// Detects standard interfaces of the contract by its code hash, falls back to probing
+    /// get-methods of the interfaces if the code is not in `registry`.
+    ///
+    /// Returns an empty set for an account without code. Fails if a probe fails for another
+    /// reason than the absence of its interface, e.g. a timeout.
+    pub async fn detect_interfaces_with(
+        &self,
+        registry: &CodeHashRegistry,
+    ) -> Result<HashSet<ContractInterface>, TonContractError> {
+        let state = self.get_state().await?;
+        let code = &state.get_account_state().code;
+        if code.is_empty() {
+            return Ok(HashSet::new());
+        }
+        let code_hash = BagOfCells::parse(code)
+            .and_then(|boc| Ok(boc.single_root()?.cell_hash()))
+            .map_err(|error| TonContractError::CellError {
+                method: "detect_interfaces".to_string(),
+                address: self.address.clone(),
+                error,
+            })?;
+        match registry.lookup(&code_hash) {
+            Some(interfaces) => Ok(interfaces.clone()),
+            None => probe_interfaces(&state).await,
+        }
+    }
+
     pub async fn get_state(&self) -> Result<TonContractState, TonContractError> {
         let r = self
             .factory
@@ -54,6 +120,25 @@ impl TonContract {
         Ok(r)
     }
 
+    /// Returns a stream paging backwards through the transactions of the contract,
+    /// starting with its last transaction.
+    pub async fn transaction_stream(
+        &self,
+    ) -> Result<TransactionStream<TonClient>, TonContractError> {
+        let state = self.get_account_state().await?;
+        Ok(TransactionStream::new(
+            self.factory.client(),
+            &self.address,
+            &state.last_transaction_id,
+        ))
+    }
+
+    /// Returns a tail following transactions of the contract made after the first poll,
+    /// see `TransactionTail`.
+    pub fn transaction_tail(&self, poll_interval: Duration) -> TransactionTail<TonClient> {
+        TransactionTail::new(self.factory.client(), &self.address, None, poll_interval)
+    }
+
     pub async fn get_state_by_transaction(
         &self,
         transaction_id: &InternalTransactionId,
@@ -66,12 +151,14 @@ impl TonContract {
     }
 }
 
-#[async_trait]
-impl TonContractInterface for TonContract {
+impl TonFactoryContract for TonContract {
     fn factory(&self) -> &TonContractFactory {
         &self.factory
     }
+}
 
+#[async_trait]
+impl TonContractInterface for TonContract {
     fn address(&self) -> &TonAddress {
         &self.address
     }
@@ -0,0 +1,185 @@
+use async_trait::async_trait;
+use num_bigint::{BigInt, Sign};
+use strum::IntoStaticStr;
+use tonlib_core::cell::{ArcCell, CellBuilder};
+use tonlib_core::dns::{encode_domain, DnsCategory, DnsRecord};
+use tonlib_core::{TonAddress, TonHash};
+
+use crate::contract::{
+    MapCellError, MapStackError, TonContractError, TonContractFactory, TonContractInterface,
+};
+use crate::types::TvmStackEntry;
+
+/// Root DNS resolver of the mainnet, as set in config param 4.
+pub const MAINNET_DNS_ROOT: &str = "Ef_lZ1T4NCb2mwkme9h2rJfESCE0W34ma9lWp7-_uY3zXDvq";
+
+/// Maximal number of resolvers visited while resolving a single domain.
+const MAX_RESOLVE_STEPS: usize = 16;
+
+#[derive(IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+enum DnsResolverMethods {
+    Dnsresolve,
+}
+
+/// Result of a single `dnsresolve` call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DnsResolveResult {
+    /// Number of bytes of the requested subdomain resolved by the contract.
+    pub resolved_bytes: usize,
+    /// Record of the requested category, or `dns_next_resolver` record if only a prefix
+    /// of the subdomain is resolved, `None` if there's no record.
+    pub record: Option<ArcCell>,
+}
+
+/// `dnsresolve` get-method of TON DNS resolvers (root, collections and items) according to TEP-81.
+#[async_trait]
+pub trait DnsResolverContract: TonContractInterface {
+    /// Resolves `subdomain` encoded by [`encode_domain`] relative to this resolver.
+    async fn dnsresolve(
+        &self,
+        subdomain: &[u8],
+        category: &DnsCategory,
+    ) -> Result<DnsResolveResult, TonContractError> {
+        let method: &'static str = DnsResolverMethods::Dnsresolve.into();
+        let address = self.address().clone();
+        let subdomain_cell = CellBuilder::new()
+            .store_slice(subdomain)
+            .and_then(|builder| builder.build())
+            .map_cell_error(method, &address)?;
+        let input_stack = vec![
+            TvmStackEntry::slice(subdomain_cell).map_stack_error(method, &address)?,
+            TvmStackEntry::Int257(BigInt::from_bytes_be(Sign::Plus, &category.hash())),
+        ];
+        let stack = self.run_get_method(method, &input_stack).await?.stack;
+        if stack.len() != 2 {
+            return Err(TonContractError::InvalidMethodResultStackSize {
+                method: method.to_string(),
+                address,
+                actual: stack.len(),
+                expected: 2,
+            });
+        }
+        let resolved_bits = stack[0].get_i64().map_stack_error(method, &address)?;
+        if resolved_bits < 0
+            || resolved_bits % 8 != 0
+            || resolved_bits as usize > subdomain.len() * 8
+        {
+            return Err(TonContractError::InternalError(format!(
+                "Invalid number of resolved bits {} of {} returned by {}",
+                resolved_bits,
+                subdomain.len() * 8,
+                address
+            )));
+        }
+        let record = match &stack[1] {
+            TvmStackEntry::Null => None,
+            entry => Some(entry.get_cell().map_stack_error(method, &address)?),
+        };
+        Ok(DnsResolveResult {
+            resolved_bytes: resolved_bits as usize / 8,
+            record,
+        })
+    }
+}
+
+impl<T> DnsResolverContract for T where T: TonContractInterface {}
+
+/// Resolves TON DNS domains, e.g. `alice.ton`, following the chain of resolvers
+/// starting with the root one.
+#[derive(Clone)]
+pub struct DnsResolver {
+    factory: TonContractFactory,
+    root: TonAddress,
+}
+
+impl DnsResolver {
+    pub fn new(factory: &TonContractFactory, root: &TonAddress) -> DnsResolver {
+        DnsResolver {
+            factory: factory.clone(),
+            root: root.clone(),
+        }
+    }
+
+    /// Creates a resolver starting with [`MAINNET_DNS_ROOT`].
+    pub fn mainnet(factory: &TonContractFactory) -> DnsResolver {
+        let root = MAINNET_DNS_ROOT
+            .parse()
+            .expect("MAINNET_DNS_ROOT is a valid address");
+        DnsResolver::new(factory, &root)
+    }
+
+    /// Resolves the record of `category` of `domain`, `None` if the domain or the record
+    /// doesn't exist.
+    pub async fn resolve(
+        &self,
+        domain: &str,
+        category: &DnsCategory,
+    ) -> Result<Option<DnsRecord>, TonContractError> {
+        let method: &'static str = DnsResolverMethods::Dnsresolve.into();
+        let encoded =
+            encode_domain(domain).map_err(|e| TonContractError::IllegalArgument(e.to_string()))?;
+        let mut resolver = self.root.clone();
+        let mut subdomain = encoded.as_slice();
+        for _ in 0..MAX_RESOLVE_STEPS {
+            let result = self
+                .factory
+                .get_contract(&resolver)
+                .dnsresolve(subdomain, category)
+                .await?;
+            let Some(cell) = result.record else {
+                return Ok(None);
+            };
+            if result.resolved_bytes == 0 {
+                return Ok(None);
+            }
+            let record = DnsRecord::parse(&cell).map_cell_error(method, &resolver)?;
+            if result.resolved_bytes == subdomain.len() {
+                return Ok(Some(record));
+            }
+            match record {
+                DnsRecord::NextResolver(next) => {
+                    subdomain = &subdomain[result.resolved_bytes..];
+                    resolver = next;
+                }
+                _ => {
+                    return Err(TonContractError::InternalError(format!(
+                        "Expected next resolver record from {} for {}, got {:?}",
+                        resolver, domain, record
+                    )))
+                }
+            }
+        }
+        Err(TonContractError::InternalError(format!(
+            "Resolution of {} exceeded {} resolvers",
+            domain, MAX_RESOLVE_STEPS
+        )))
+    }
+
+    /// Resolves the wallet address of `domain`.
+    pub async fn resolve_wallet(
+        &self,
+        domain: &str,
+    ) -> Result<Option<TonAddress>, TonContractError> {
+        match self.resolve(domain, &DnsCategory::Wallet).await? {
+            Some(DnsRecord::SmcAddress(address)) => Ok(Some(address)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Resolves the ADNL address of the TON Site of `domain`.
+    pub async fn resolve_site(&self, domain: &str) -> Result<Option<TonHash>, TonContractError> {
+        match self.resolve(domain, &DnsCategory::Site).await? {
+            Some(DnsRecord::AdnlAddress(adnl)) => Ok(Some(adnl)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Resolves the TON Storage bag id of `domain`.
+    pub async fn resolve_storage(&self, domain: &str) -> Result<Option<TonHash>, TonContractError> {
+        match self.resolve(domain, &DnsCategory::Storage).await? {
+            Some(DnsRecord::StorageAddress(bag_id)) => Ok(Some(bag_id)),
+            _ => Ok(None),
+        }
+    }
+}
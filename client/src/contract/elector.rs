@@ -0,0 +1,389 @@
+use std::collections::{BTreeMap, HashMap};
+
+use async_trait::async_trait;
+use num_bigint::{BigInt, BigUint, Sign};
+use strum::IntoStaticStr;
+use tonlib_core::cell::dict::predefined_readers::{
+    key_reader_256bit, key_reader_u32, val_reader_coins,
+};
+use tonlib_core::cell::{BagOfCells, CellParser, TonCellError};
+use tonlib_core::constants::MASTERCHAIN_ID;
+use tonlib_core::tlb::TlbType;
+use tonlib_core::{TonAddress, TonHash};
+
+use crate::contract::{MapCellError, MapStackError, TonContractError, TonContractInterface};
+use crate::types::TvmStackEntry;
+
+/// Data of the elector contract, decoded from its persistent data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElectorData {
+    /// Elections accepting stakes, if any.
+    pub current_election: Option<Election>,
+    /// Stakes and bonuses to be recovered, by the addresses of their owners in the masterchain.
+    pub credits: HashMap<TonHash, BigUint>,
+    /// Elections whose stakes are frozen, by election id.
+    pub past_elections: BTreeMap<u32, PastElection>,
+    /// Balance of the elector not belonging to any participant.
+    pub grams: BigUint,
+    /// Id of the elections of the current validator set.
+    pub active_id: u32,
+    /// Hash of the current validator set.
+    pub active_hash: TonHash,
+}
+
+impl ElectorData {
+    /// Returns the amount `address` can recover with `ElectorRecoverStakeMessage`.
+    pub fn credit(&self, address: &TonAddress) -> BigUint {
+        if address.workchain != MASTERCHAIN_ID {
+            return BigUint::default();
+        }
+        self.credits
+            .get(&address.hash_part)
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// Elections accepting stakes of validator candidates.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Election {
+    /// Unix time the elected validators start validating at, also the id of the elections.
+    pub elect_at: u32,
+    /// Unix time the elections end at.
+    pub elect_close: u32,
+    pub min_stake: BigUint,
+    pub total_stake: BigUint,
+    /// Participants by their validator public keys.
+    pub participants: HashMap<TonHash, ElectionParticipant>,
+    pub failed: bool,
+    pub finished: bool,
+}
+
+impl Election {
+    /// Returns participants sorted by stake, the largest first.
+    pub fn participants_by_stake(&self) -> Vec<(&TonHash, &ElectionParticipant)> {
+        let mut participants: Vec<_> = self.participants.iter().collect();
+        participants.sort_by(|(_, a), (_, b)| b.stake.cmp(&a.stake));
+        participants
+    }
+}
+
+/// Stake of a validator candidate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElectionParticipant {
+    pub stake: BigUint,
+    /// Unix time the stake was made at.
+    pub time: u32,
+    /// Maximum ratio of the effective stake to the minimal one, multiplied by 65536.
+    pub max_factor: u32,
+    /// Masterchain address the stake was made from and is returned to.
+    pub src_addr: TonAddress,
+    pub adnl_addr: TonHash,
+}
+
+/// Elections whose stakes are frozen until `unfreeze_at`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PastElection {
+    pub unfreeze_at: u32,
+    /// Time in seconds the stakes are held for after the validation period.
+    pub stake_held: u32,
+    pub vset_hash: TonHash,
+    /// Frozen stakes by validator public keys.
+    pub frozen: HashMap<TonHash, FrozenStake>,
+    pub total_stake: BigUint,
+    /// Fees collected for the validation, distributed proportionally to the stakes.
+    pub bonuses: BigUint,
+}
+
+impl PastElection {
+    /// Returns the bonus the elector adds to `stake` on unfreezing, i.e.
+    /// `bonuses * stake / total_stake`, rounded down. Banned validators get nothing.
+    pub fn reward(&self, stake: &FrozenStake) -> BigUint {
+        if stake.banned || self.total_stake == BigUint::default() {
+            return BigUint::default();
+        }
+        &self.bonuses * &stake.stake / &self.total_stake
+    }
+
+    /// Returns the bonuses of all validators, summed up by the addresses they are credited to.
+    pub fn rewards(&self) -> HashMap<TonAddress, BigUint> {
+        let mut rewards: HashMap<TonAddress, BigUint> = HashMap::new();
+        for stake in self.frozen.values() {
+            *rewards.entry(stake.address.clone()).or_default() += self.reward(stake);
+        }
+        rewards
+    }
+}
+
+/// Stake of an elected validator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrozenStake {
+    /// Masterchain address the stake is credited to when unfrozen.
+    pub address: TonAddress,
+    /// Weight of the validator in the validator set.
+    pub weight: u64,
+    pub stake: BigUint,
+    /// Whether the stake is punished for misbehaviour.
+    pub banned: bool,
+}
+
+#[derive(IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+enum ElectorMethods {
+    ActiveElectionId,
+    ParticipatesIn,
+    ComputeReturnedStake,
+}
+
+/// Get-methods and data of the elector contract, the address of which is config param 1.
+#[async_trait]
+pub trait ElectorContract: TonContractInterface {
+    /// Returns the id of the elections accepting stakes, `0` if there are none.
+    async fn get_active_election_id(&self) -> Result<u32, TonContractError> {
+        let method: &'static str = ElectorMethods::ActiveElectionId.into();
+        let stack = self.run_get_method(method, Vec::new()).await?.stack;
+        let id = read_single_number(method, self.address(), &stack)?;
+        Ok(id.to_u32_digits().first().copied().unwrap_or_default())
+    }
+
+    /// Returns the stake of the validator with `validator_pubkey` in the current elections.
+    async fn get_participant_stake(
+        &self,
+        validator_pubkey: &TonHash,
+    ) -> Result<BigUint, TonContractError> {
+        let method: &'static str = ElectorMethods::ParticipatesIn.into();
+        let input_stack = vec![hash_entry(validator_pubkey)];
+        let stack = self.run_get_method(method, &input_stack).await?.stack;
+        read_single_number(method, self.address(), &stack)
+    }
+
+    /// Returns the amount the masterchain `address` can recover, see `ElectorData::credit`.
+    async fn get_returned_stake(&self, address: &TonAddress) -> Result<BigUint, TonContractError> {
+        let method: &'static str = ElectorMethods::ComputeReturnedStake.into();
+        let input_stack = vec![hash_entry(&address.hash_part)];
+        let stack = self.run_get_method(method, &input_stack).await?.stack;
+        read_single_number(method, self.address(), &stack)
+    }
+
+    /// Decodes current and past elections and credits from the contract data.
+    ///
+    /// The data layout is `elect:(Maybe ^Elect) credits:(HashmapE 256 Grams)
+    /// past_elections:(HashmapE 32 PastElection) grams:Grams active_id:uint32
+    /// active_hash:bits256`, as in `elector-code.fc`.
+    async fn get_elector_data(&self) -> Result<ElectorData, TonContractError> {
+        const METHOD: &str = "get_elector_data";
+        let state = self.get_account_state().await?;
+        let boc = BagOfCells::parse(&state.data).map_cell_error(METHOD, self.address())?;
+        let data = boc.single_root().map_cell_error(METHOD, self.address())?;
+        let mut parser = data.parser();
+        let data = read_elector_data(&mut parser).map_cell_error(METHOD, self.address())?;
+        Ok(data)
+    }
+}
+
+impl<T> ElectorContract for T where T: TonContractInterface {}
+
+fn hash_entry(hash: &TonHash) -> TvmStackEntry {
+    TvmStackEntry::number(BigInt::from_bytes_be(Sign::Plus, hash))
+}
+
+fn read_single_number(
+    method: &'static str,
+    address: &TonAddress,
+    stack: &[TvmStackEntry],
+) -> Result<BigUint, TonContractError> {
+    if stack.len() != 1 {
+        return Err(TonContractError::InvalidMethodResultStackSize {
+            method: method.to_string(),
+            address: address.clone(),
+            actual: stack.len(),
+            expected: 1,
+        });
+    }
+    stack[0].get_biguint().map_stack_error(method, address)
+}
+
+fn read_elector_data(parser: &mut CellParser) -> Result<ElectorData, TonCellError> {
+    let current_election = match parser.load_maybe_cell_ref()? {
+        Some(cell) => Some(cell.parse_fully(read_election)?),
+        None => None,
+    };
+    let credits = parser.load_maybe_dict(256, key_reader_256bit, val_reader_coins)?;
+    let past_elections = parser
+        .load_maybe_dict(32, key_reader_u32, read_past_election)?
+        .into_iter()
+        .collect();
+    let grams = parser.load_coins()?;
+    let active_id = parser.load_u32(32)?;
+    let active_hash = TonHash::read(parser)?;
+    Ok(ElectorData {
+        current_election,
+        credits,
+        past_elections,
+        grams,
+        active_id,
+        active_hash,
+    })
+}
+
+/// Reads `elect_at:uint32 elect_close:uint32 min_stake:Grams total_stake:Grams
+/// members:(HashmapE 256 Participant) failed:Bool finished:Bool`.
+fn read_election(parser: &mut CellParser) -> Result<Election, TonCellError> {
+    Ok(Election {
+        elect_at: parser.load_u32(32)?,
+        elect_close: parser.load_u32(32)?,
+        min_stake: parser.load_coins()?,
+        total_stake: parser.load_coins()?,
+        participants: parser.load_maybe_dict(256, key_reader_256bit, read_participant)?,
+        failed: parser.load_bit()?,
+        finished: parser.load_bit()?,
+    })
+}
+
+/// Reads `stake:Grams time:uint32 max_factor:uint32 src_addr:bits256 adnl_addr:bits256`.
+fn read_participant(parser: &mut CellParser) -> Result<ElectionParticipant, TonCellError> {
+    Ok(ElectionParticipant {
+        stake: parser.load_coins()?,
+        time: parser.load_u32(32)?,
+        max_factor: parser.load_u32(32)?,
+        src_addr: TonAddress::new(MASTERCHAIN_ID, &TonHash::read(parser)?),
+        adnl_addr: TonHash::read(parser)?,
+    })
+}
+
+/// Reads `unfreeze_at:uint32 stake_held:uint32 vset_hash:bits256
+/// frozen_dict:(HashmapE 256 FrozenStake) total_stake:Grams bonuses:Grams
+/// complaints:(HashmapE 256 Complaint)`, skipping the complaints.
+fn read_past_election(parser: &mut CellParser) -> Result<PastElection, TonCellError> {
+    let past_election = PastElection {
+        unfreeze_at: parser.load_u32(32)?,
+        stake_held: parser.load_u32(32)?,
+        vset_hash: TonHash::read(parser)?,
+        frozen: parser.load_maybe_dict(256, key_reader_256bit, read_frozen_stake)?,
+        total_stake: parser.load_coins()?,
+        bonuses: parser.load_coins()?,
+    };
+    let _complaints = parser.load_maybe_cell_ref()?;
+    Ok(past_election)
+}
+
+/// Reads `addr:bits256 weight:uint64 stake:Grams banned:Bool`.
+fn read_frozen_stake(parser: &mut CellParser) -> Result<FrozenStake, TonCellError> {
+    Ok(FrozenStake {
+        address: TonAddress::new(MASTERCHAIN_ID, &TonHash::read(parser)?),
+        weight: parser.load_u64(64)?,
+        stake: parser.load_coins()?,
+        banned: parser.load_bit()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use num_bigint::BigUint;
+    use tonlib_core::cell::dict::predefined_writers::val_writer_coins;
+    use tonlib_core::cell::{CellBuilder, TonCellError};
+    use tonlib_core::TonAddress;
+
+    use super::read_elector_data;
+
+    fn key(byte: u8) -> BigUint {
+        BigUint::from_bytes_be(&[byte; 32])
+    }
+
+    #[test]
+    fn test_elector_data_decoding() -> Result<(), TonCellError> {
+        let participants = HashMap::from([(key(1), 300u32), (key(2), 500u32)]);
+        let mut election = CellBuilder::new();
+        election
+            .store_u32(32, 1_700_000_000)?
+            .store_u32(32, 1_699_990_000)?
+            .store_coins(&BigUint::from(100u32))?
+            .store_coins(&BigUint::from(800u32))?
+            .store_maybe_dict(
+                256,
+                |builder, stake: u32| {
+                    builder
+                        .store_coins(&BigUint::from(stake))?
+                        .store_u32(32, 1_699_900_000)?
+                        .store_u32(32, 3 << 16)?
+                        .store_slice(&[stake as u8; 32])?
+                        .store_slice(&[9; 32])?;
+                    Ok(())
+                },
+                participants,
+            )?
+            .store_bit(false)?
+            .store_bit(false)?;
+
+        let frozen = HashMap::from([(key(3), (4u8, 300u32, false)), (key(5), (6, 100, true))]);
+        let past_elections = HashMap::from([(1_690_000_000u32, frozen)]);
+        let credits = HashMap::from([(key(4), BigUint::from(700u32))]);
+        let data = CellBuilder::new()
+            .store_maybe_cell_ref(&Some(election.build()?.into()))?
+            .store_maybe_dict(256, val_writer_coins, credits)?
+            .store_maybe_dict(
+                32,
+                |builder, frozen: HashMap<BigUint, (u8, u32, bool)>| {
+                    builder
+                        .store_u32(32, 1_690_100_000)?
+                        .store_u32(32, 32768)?
+                        .store_slice(&[7; 32])?
+                        .store_maybe_dict(
+                            256,
+                            |builder, (address, stake, banned): (u8, u32, bool)| {
+                                builder
+                                    .store_slice(&[address; 32])?
+                                    .store_u64(64, 1 << 59)?
+                                    .store_coins(&BigUint::from(stake))?
+                                    .store_bit(banned)?;
+                                Ok(())
+                            },
+                            frozen,
+                        )?
+                        .store_coins(&BigUint::from(400u32))?
+                        .store_coins(&BigUint::from(10u32))?
+                        .store_bit(false)?;
+                    Ok(())
+                },
+                past_elections,
+            )?
+            .store_coins(&BigUint::from(5u32))?
+            .store_u32(32, 1_690_000_000)?
+            .store_slice(&[8; 32])?
+            .build()?;
+
+        let data = read_elector_data(&mut data.parser())?;
+        let election = data.current_election.as_ref().unwrap();
+        assert_eq!(election.elect_at, 1_700_000_000);
+        assert_eq!(election.total_stake, BigUint::from(800u32));
+        let stakes: Vec<_> = election
+            .participants_by_stake()
+            .iter()
+            .map(|(_, participant)| participant.stake.clone())
+            .collect();
+        assert_eq!(stakes, vec![BigUint::from(500u32), BigUint::from(300u32)]);
+        let participant = &election.participants[&[2; 32]];
+        assert_eq!(participant.src_addr, TonAddress::new(-1, &[244; 32]));
+        assert_eq!(participant.max_factor, 3 << 16);
+
+        let stake_owner = TonAddress::new(-1, &[4; 32]);
+        assert_eq!(data.credit(&stake_owner), BigUint::from(700u32));
+        assert_eq!(
+            data.credit(&TonAddress::new(0, &[4; 32])),
+            BigUint::default()
+        );
+        assert_eq!(data.active_id, 1_690_000_000);
+        assert_eq!(data.active_hash, [8; 32]);
+
+        let past = &data.past_elections[&1_690_000_000];
+        assert_eq!(past.unfreeze_at, 1_690_100_000);
+        assert_eq!(past.frozen.len(), 2);
+        assert!(past.frozen[&[5; 32]].banned);
+        let rewards = past.rewards();
+        assert_eq!(rewards[&stake_owner], BigUint::from(7u32));
+        assert_eq!(rewards[&TonAddress::new(-1, &[6; 32])], BigUint::default());
+        Ok(())
+    }
+}
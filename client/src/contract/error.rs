@@ -1,5 +1,5 @@
-#[cfg(feature = "state_cache")]
 use std::sync::Arc;
+use std::time::Duration;
 
 use thiserror::Error;
 use tonlib_core::cell::TonCellError;
@@ -21,6 +21,11 @@ pub enum TonContractError {
     #[error("TonClientError ({0})")]
     ClientError(#[from] TonClientError),
 
+    /// Client error failing several requests at once, e.g. `smc.load` of a contract whose
+    /// get-methods are run by `run_get_methods_batch`.
+    #[error("TonClientError ({0})")]
+    SharedClientError(Arc<TonClientError>),
+
     #[error("Method emulation error (Method: {method}, address: {address}, error {error}")]
     MethodEmulationError {
         method: String,
@@ -70,6 +75,13 @@ pub enum TonContractError {
         missing_library: String,
     },
 
+    #[error("Seqno not advanced (Address: {address}, seqno: {seqno}, timeout: {timeout:?})")]
+    SeqnoTimeout {
+        address: TonAddress,
+        seqno: u32,
+        timeout: Duration,
+    },
+
     #[error(
         "Tvm stack parse  error (Method: {method}, address: {address}, stack error: {error:?})"
     )]
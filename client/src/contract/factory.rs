@@ -11,7 +11,7 @@ use tokio::sync::OnceCell;
 use tonlib_core::TonAddress;
 
 use crate::client::{TonClient, TonClientError, TonClientInterface};
-use crate::contract::{LoadedSmcState, TonContract, TonContractError, TonContractState};
+use crate::contract::{SmcHandle, TonContract, TonContractError, TonContractState};
 use crate::tl::{ConfigInfo, InternalTransactionId, RawFullAccountState};
 
 mod builder;
@@ -155,7 +155,7 @@ impl TonContractFactory {
         &self,
         address: &TonAddress,
         transaction_id: &InternalTransactionId,
-    ) -> Result<Arc<LoadedSmcState>, TonContractError> {
+    ) -> Result<Arc<SmcHandle>, TonContractError> {
         if let Some(cache) = self.inner.cache.as_ref() {
             cache
                 .get_smc_state_by_transaction(address, transaction_id)
@@ -174,7 +174,7 @@ impl TonContractFactory {
         &self,
         address: &TonAddress,
         transaction_id: &InternalTransactionId,
-    ) -> Result<Arc<LoadedSmcState>, TonContractError> {
+    ) -> Result<Arc<SmcHandle>, TonContractError> {
         Ok(Arc::new(
             self.client()
                 .smc_load_by_transaction(address, transaction_id)
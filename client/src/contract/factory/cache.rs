@@ -11,11 +11,12 @@ use tonlib_core::TonAddress;
 use crate::client::{
     BlockStream, BlockStreamItem, TonBlockFunctions, TonClient, TonClientError, TonClientInterface,
 };
-use crate::contract::{LoadedSmcState, TonContractError};
+use crate::contract::{SmcHandle, TonContractError};
 use crate::tl::{InternalTransactionId, RawFullAccountState};
 
 type TxIdCache = Cache<TonAddress, Arc<InternalTransactionId>>;
 type AccountStateCache = Cache<TonAddress, Arc<RawFullAccountState>>;
+type SmcStateCache = Cache<(TonAddress, InternalTransactionId), Arc<SmcHandle>>;
 
 const DELAY_ON_TON_FAILURE: u64 = 100;
 
@@ -45,6 +46,10 @@ impl ContractFactoryCache {
                 .max_capacity(txid_cache_capacity)
                 .time_to_live(txid_state_cache_time_to_live)
                 .build(),
+            smc_state_cache: Cache::builder()
+                .max_capacity(account_state_cache_capacity)
+                .time_to_live(account_state_cache_time_to_live)
+                .build(),
             presync_blocks,
             account_state_cache_counters: ContractFactoryCacheCounters::default(),
 
@@ -60,18 +65,37 @@ impl ContractFactoryCache {
         Ok(cache)
     }
 
+    /// Returns the contract loaded at the state after `transaction_id`, loading it only once
+    /// while it's cached. Evicted contracts are forgotten once they're not in use.
+    ///
+    /// Contracts lost by a reconnect of their connection are loaded again.
     pub async fn get_smc_state_by_transaction(
         &self,
         address: &TonAddress,
         transaction_id: &InternalTransactionId,
-    ) -> Result<Arc<LoadedSmcState>, TonContractError> {
-        let loaded_state = self
-            .inner
-            .client
-            .smc_load_by_transaction(address, transaction_id)
-            .await?;
-
-        Ok(Arc::new(loaded_state))
+    ) -> Result<Arc<SmcHandle>, TonContractError> {
+        let key = (address.clone(), transaction_id.clone());
+        let load = || async {
+            let loaded_state = self
+                .inner
+                .client
+                .smc_load_by_transaction(address, transaction_id)
+                .await?;
+            Ok::<_, TonContractError>(Arc::new(loaded_state))
+        };
+        let cache = &self.inner.smc_state_cache;
+        let handle = cache
+            .try_get_with(key.clone(), load())
+            .await
+            .map_err(TonContractError::CacheError)?;
+        if handle.is_valid() {
+            return Ok(handle);
+        }
+        cache.invalidate(&key).await;
+        cache
+            .try_get_with(key, load())
+            .await
+            .map_err(TonContractError::CacheError)
     }
 
     pub async fn get_account_state(
@@ -228,6 +252,7 @@ struct Inner {
     client: TonClient,
     tx_id_cache: TxIdCache,
     account_state_cache: AccountStateCache,
+    smc_state_cache: SmcStateCache,
     presync_blocks: i32,
     tx_id_cache_counters: ContractFactoryCacheCounters,
     account_state_cache_counters: ContractFactoryCacheCounters,
@@ -1,13 +1,16 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use base64::engine::general_purpose::STANDARD;
-use base64::Engine;
-use tonlib_core::TonAddress;
+use tonlib_core::cell::{ArcCell, BagOfCells};
+use tonlib_core::{TonAddress, TonHash};
 
+use super::library_provider::{code_roots, resolve_libraries};
 use crate::client::{TonClient, TonClientInterface};
 use crate::contract::TonContractError;
-use crate::tl::{SmcLibraryQueryExt, TonLibraryId};
+use crate::tl::TonLibraryId;
+
+/// Maximum number of libraries a lite server returns for a single `liteServer.getLibraries`.
+const MAX_LIBRARIES_PER_QUERY: usize = 16;
 
 pub struct ContractLibraryDict {
     pub dict_boc: Vec<u8>,
@@ -16,13 +19,22 @@ pub struct ContractLibraryDict {
 
 #[async_trait]
 pub trait LibraryLoader: Send + Sync {
+    /// Loads the root cells of the libraries with `hashes`, omitting the ones not found.
+    async fn load_libraries(&self, hashes: &[TonHash]) -> Result<Vec<ArcCell>, TonContractError>;
+
+    /// Loads the dictionary of all libraries used by contract `code`, like
+    /// `LibraryProvider::get_libraries_by_contract_code` but without caching them.
     async fn load_contract_libraries(
         &self,
         address: &TonAddress,
         code: &[u8],
-    ) -> Result<Arc<ContractLibraryDict>, TonContractError>;
+    ) -> Result<Arc<ContractLibraryDict>, TonContractError> {
+        let roots = code_roots(address, code)?;
+        resolve_libraries(self, None, address, &roots).await
+    }
 }
 
+/// Loads libraries with `smc.getLibraries`, i.e. `liteServer.getLibraries` of a lite server.
 pub struct DefaultLibraryLoader {
     client: TonClient,
 }
@@ -37,33 +49,33 @@ impl DefaultLibraryLoader {
 
 #[async_trait]
 impl LibraryLoader for DefaultLibraryLoader {
-    async fn load_contract_libraries(
-        &self,
-        address: &TonAddress,
-        code: &[u8],
-    ) -> Result<Arc<ContractLibraryDict>, TonContractError> {
-        const DEFAULT_MAX_LIBS: i32 = 255;
-        let library_query = SmcLibraryQueryExt::ScanBoc {
-            boc: code.to_vec(),
-            max_libs: DEFAULT_MAX_LIBS,
-        };
-        let library_result = self.client.smc_get_libraries_ext(&[library_query]).await?;
-        if !library_result.libs_not_found.is_empty() {
-            let missing_libs = library_result
-                .libs_not_found
+    async fn load_libraries(&self, hashes: &[TonHash]) -> Result<Vec<ArcCell>, TonContractError> {
+        let mut libraries = Vec::with_capacity(hashes.len());
+        for chunk in hashes.chunks(MAX_LIBRARIES_PER_QUERY) {
+            let library_list: Vec<TonLibraryId> = chunk
                 .iter()
-                .map(|l| STANDARD.encode(&l.id))
+                .map(|hash| TonLibraryId { id: hash.to_vec() })
                 .collect();
-            return Err(TonContractError::LibraryNotFound {
-                address: address.clone(),
-                missing_library: missing_libs,
-            });
+            let library_result = self.client.smc_get_libraries(&library_list).await?;
+            for entry in library_result.result {
+                let root =
+                    match BagOfCells::parse(&entry.data).and_then(|b| b.single_root().cloned()) {
+                        Ok(root) => root,
+                        Err(e) => {
+                            log::warn!(
+                                "Failed to parse library {}: {}",
+                                hex::encode(&entry.hash),
+                                e
+                            );
+                            continue;
+                        }
+                    };
+                // Libraries not matching the requested hash are reported as not found
+                if root.repr_hash().as_slice() == entry.hash.as_slice() {
+                    libraries.push(root);
+                }
+            }
         }
-
-        let dict_boc = library_result.dict_boc;
-        let keys = library_result.libs_ok;
-
-        let contract_libraies = ContractLibraryDict { dict_boc, keys };
-        Ok(Arc::new(contract_libraies))
+        Ok(libraries)
     }
 }
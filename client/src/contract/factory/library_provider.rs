@@ -1,19 +1,41 @@
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-use tonlib_core::TonAddress;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use moka::future::Cache;
+use num_bigint::BigUint;
+use tonlib_core::cell::dict::predefined_writers::val_writer_ref_cell;
+use tonlib_core::cell::{ArcCell, BagOfCells, CellBuilder, CellType, TonCellError};
+use tonlib_core::{TonAddress, TonHash};
 
 use super::{ContractLibraryDict, LibraryLoader};
 use crate::contract::TonContractError;
-use crate::tl::RawFullAccountState;
+use crate::tl::{RawFullAccountState, TonLibraryId};
 
+pub const DEFAULT_LIBRARY_CACHE_CAPACITY: u64 = 1000;
+
+/// Provides libraries used by contract code to the emulator.
+///
+/// Library cells referred by the code are found locally, only libraries missing in the cache
+/// are loaded by the `LibraryLoader`. Libraries are identified by their hash, so they're cached
+/// without expiration.
 #[derive(Clone)]
 pub struct LibraryProvider {
     loader: Arc<dyn LibraryLoader>,
+    cache: Cache<TonHash, ArcCell>,
 }
 
 impl LibraryProvider {
     pub fn new(loader: Arc<dyn LibraryLoader>) -> LibraryProvider {
-        LibraryProvider { loader }
+        Self::with_cache_capacity(loader, DEFAULT_LIBRARY_CACHE_CAPACITY)
+    }
+
+    pub fn with_cache_capacity(loader: Arc<dyn LibraryLoader>, capacity: u64) -> LibraryProvider {
+        LibraryProvider {
+            loader,
+            cache: Cache::new(capacity),
+        }
     }
 
     pub async fn get_contract_libraries(
@@ -30,7 +52,231 @@ impl LibraryProvider {
         address: &TonAddress,
         code: &[u8],
     ) -> Result<Arc<ContractLibraryDict>, TonContractError> {
-        // TODO cache
-        self.loader.load_contract_libraries(address, code).await
+        let roots = code_roots(address, code)?;
+        self.get_libraries(address, &roots).await
+    }
+
+    /// Returns the dictionary of all libraries referred by `cells`, including the libraries
+    /// referred by these libraries.
+    ///
+    /// The dictionary is empty if there are no libraries, `dict_boc` is empty then.
+    pub async fn get_libraries(
+        &self,
+        address: &TonAddress,
+        cells: &[ArcCell],
+    ) -> Result<Arc<ContractLibraryDict>, TonContractError> {
+        resolve_libraries(self.loader.as_ref(), Some(&self.cache), address, cells).await
+    }
+}
+
+/// Returns the root of contract `code`, none for empty code of an account without code.
+pub(super) fn code_roots(
+    address: &TonAddress,
+    code: &[u8],
+) -> Result<Vec<ArcCell>, TonContractError> {
+    if code.is_empty() {
+        return Ok(vec![]);
+    }
+    let root = BagOfCells::parse(code)
+        .and_then(|boc| boc.single_root().cloned())
+        .map_err(|e| library_cell_error(address, e))?;
+    Ok(vec![root])
+}
+
+/// Loads libraries referred by `cells` and by the loaded libraries with `loader`, except
+/// the ones found in `cache`, see `LibraryProvider::get_libraries`.
+pub(super) async fn resolve_libraries<L: LibraryLoader + ?Sized>(
+    loader: &L,
+    cache: Option<&Cache<TonHash, ArcCell>>,
+    address: &TonAddress,
+    cells: &[ArcCell],
+) -> Result<Arc<ContractLibraryDict>, TonContractError> {
+    let mut libraries: HashMap<TonHash, ArcCell> = HashMap::new();
+    let mut pending = collect_library_hashes(cells);
+    while !pending.is_empty() {
+        let mut found = vec![];
+        let mut missing = vec![];
+        for hash in pending {
+            if libraries.contains_key(&hash) {
+                continue;
+            }
+            let cached = match cache {
+                Some(cache) => cache.get(&hash).await,
+                None => None,
+            };
+            match cached {
+                Some(library) => found.push(library),
+                None => missing.push(hash),
+            }
+        }
+        if !missing.is_empty() {
+            let loaded = loader.load_libraries(&missing).await?;
+            for library in loaded {
+                if let Some(cache) = cache {
+                    cache.insert(*library.repr_hash(), library.clone()).await;
+                }
+                found.push(library);
+            }
+            let not_found: Vec<String> = missing
+                .iter()
+                .filter(|hash| !found.iter().any(|l| l.repr_hash() == *hash))
+                .map(|hash| STANDARD.encode(hash))
+                .collect();
+            if !not_found.is_empty() {
+                return Err(TonContractError::LibraryNotFound {
+                    address: address.clone(),
+                    missing_library: not_found.join(","),
+                });
+            }
+        }
+        pending = collect_library_hashes(&found);
+        libraries.extend(found.into_iter().map(|l| (*l.repr_hash(), l)));
+    }
+    build_library_dict(libraries).map_err(|e| library_cell_error(address, e))
+}
+
+/// Returns the hashes of library cells in the trees `cells`, visiting shared subtrees once.
+fn collect_library_hashes(cells: &[ArcCell]) -> Vec<TonHash> {
+    let mut visited = HashSet::new();
+    let mut hashes = vec![];
+    let mut stack: Vec<&ArcCell> = cells.iter().collect();
+    while let Some(cell) = stack.pop() {
+        if !visited.insert(*cell.repr_hash()) {
+            continue;
+        }
+        if cell.cell_type() == CellType::Library {
+            if let Ok(hash) = cell.library_hash() {
+                hashes.push(hash);
+            }
+        }
+        stack.extend(cell.references());
+    }
+    hashes
+}
+
+/// Builds the root of `Hashmap 256 ^Cell` of libraries keyed by hash, as expected by
+/// the emulator.
+fn build_library_dict(
+    libraries: HashMap<TonHash, ArcCell>,
+) -> Result<Arc<ContractLibraryDict>, TonCellError> {
+    let keys = libraries
+        .keys()
+        .map(|hash| TonLibraryId { id: hash.to_vec() })
+        .collect();
+    let dict_boc = if libraries.is_empty() {
+        vec![]
+    } else {
+        let data: HashMap<BigUint, ArcCell> = libraries
+            .into_iter()
+            .map(|(hash, library)| (BigUint::from_bytes_be(&hash), library))
+            .collect();
+        let dict = CellBuilder::new()
+            .store_dict(256, val_writer_ref_cell, data)?
+            .build()?;
+        BagOfCells::from_root(dict).serialize(false)?
+    };
+    Ok(Arc::new(ContractLibraryDict { dict_boc, keys }))
+}
+
+fn library_cell_error(address: &TonAddress, error: TonCellError) -> TonContractError {
+    TonContractError::CellError {
+        method: "get_libraries".to_string(),
+        address: address.clone(),
+        error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use tonlib_core::cell::dict::predefined_readers::{key_reader_256bit, val_reader_ref_cell};
+    use tonlib_core::cell::{ArcCell, BagOfCells, Cell, CellBuilder};
+    use tonlib_core::{TonAddress, TonHash};
+
+    use super::{LibraryLoader, LibraryProvider};
+    use crate::contract::TonContractError;
+
+    struct TestLoader {
+        libraries: Vec<ArcCell>,
+        loaded: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LibraryLoader for TestLoader {
+        async fn load_libraries(
+            &self,
+            hashes: &[TonHash],
+        ) -> Result<Vec<ArcCell>, TonContractError> {
+            self.loaded.fetch_add(hashes.len(), Ordering::SeqCst);
+            Ok(self
+                .libraries
+                .iter()
+                .filter(|l| hashes.contains(l.repr_hash()))
+                .cloned()
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_library_provider_collects_and_caches() -> anyhow::Result<()> {
+        let inner = Arc::new(CellBuilder::new().store_u32(32, 1)?.build()?);
+        let inner_ref = Arc::new(Cell::new_library(inner.repr_hash())?);
+        // A library referring to another library
+        let outer = Arc::new(
+            CellBuilder::new()
+                .store_u32(32, 2)?
+                .store_reference(&inner_ref)?
+                .build()?,
+        );
+        let code = CellBuilder::new()
+            .store_u32(32, 3)?
+            .store_child(Cell::new_library(outer.repr_hash())?)?
+            .build()?;
+        let code = BagOfCells::from_root(code).serialize(false)?;
+
+        let loader = Arc::new(TestLoader {
+            libraries: vec![inner.clone(), outer.clone()],
+            loaded: AtomicUsize::new(0),
+        });
+        let provider = LibraryProvider::new(loader.clone());
+        let address = TonAddress::NULL;
+        let libs = provider
+            .get_libraries_by_contract_code(&address, &code)
+            .await?;
+        assert_eq!(libs.keys.len(), 2);
+        let dict = BagOfCells::parse(&libs.dict_boc)?.single_root()?.clone();
+        let parsed = dict
+            .parser()
+            .load_dict(256, key_reader_256bit, val_reader_ref_cell)?;
+        assert_eq!(parsed.get(inner.repr_hash()), Some(&inner));
+        assert_eq!(parsed.get(outer.repr_hash()), Some(&outer));
+
+        // Cached libraries aren't loaded again
+        provider
+            .get_libraries_by_contract_code(&address, &code)
+            .await?;
+        assert_eq!(loader.loaded.load(Ordering::SeqCst), 2);
+        // but they are by the loader itself
+        let uncached = loader.load_contract_libraries(&address, &code).await?;
+        assert_eq!(uncached.dict_boc, libs.dict_boc);
+        assert_eq!(loader.loaded.load(Ordering::SeqCst), 4);
+
+        let unknown = CellBuilder::new()
+            .store_child(Cell::new_library(&[7; 32])?)?
+            .build()?;
+        let result = provider
+            .get_libraries_by_contract_code(
+                &address,
+                &BagOfCells::from_root(unknown).serialize(false)?,
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(TonContractError::LibraryNotFound { .. })
+        ));
+        Ok(())
     }
 }
@@ -1,23 +1,91 @@
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use tonlib_core::TonAddress;
 
 use super::TonContractError;
-use crate::client::TonConnection;
+use crate::client::{TonClientError, TonClientInterface, TonConnection};
 use crate::contract::TonContractFactory;
 use crate::tl::{InternalTransactionId, RawFullAccountState};
 use crate::types::{TonMethodId, TvmStackEntry, TvmSuccess};
 
-pub struct LoadedSmcState {
+/// Contract loaded into tonlib by `smc.load`, released with `smc.forget` when dropped.
+///
+/// Forgetting requires a request to tonlib, so on drop it's done by a task spawned
+/// on the current tokio runtime. Use `forget` to release the contract right away.
+///
+/// The contract is lost when its connection reconnects, see `is_valid`.
+pub struct SmcHandle {
     pub conn: TonConnection,
     pub id: i64,
+    /// Generation of `conn` the contract was loaded in, see `TonConnection::generation`.
+    generation: u64,
+    forgotten: AtomicBool,
 }
 
+#[deprecated(note = "renamed to `SmcHandle`")]
+pub type LoadedSmcState = SmcHandle;
+
+impl SmcHandle {
+    pub(crate) fn new(conn: TonConnection, id: i64) -> SmcHandle {
+        let generation = conn.generation();
+        SmcHandle {
+            conn,
+            id,
+            generation,
+            forgotten: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns `false` once the connection the contract was loaded by has reconnected or
+    /// closed, `id` might refer to another contract loaded after reconnecting then.
+    pub fn is_valid(&self) -> bool {
+        !self.conn.is_closed() && self.conn.generation() == self.generation
+    }
+
+    /// Releases the contract on the connection it was loaded by.
+    ///
+    /// `id` is invalid afterwards, repeated calls do nothing. Contracts lost by reconnecting
+    /// aren't forgotten, since their ids might be in use again.
+    pub async fn forget(&self) -> Result<(), TonClientError> {
+        if self.forgotten.swap(true, Ordering::AcqRel) || !self.is_valid() {
+            return Ok(());
+        }
+        self.conn.smc_forget(self.id).await
+    }
+}
+
+impl Drop for SmcHandle {
+    fn drop(&mut self) {
+        if *self.forgotten.get_mut() || !self.is_valid() {
+            return;
+        }
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => {
+                let conn = self.conn.clone();
+                let id = self.id;
+                runtime.spawn(async move {
+                    if let Err(e) = conn.smc_forget(id).await {
+                        log::debug!("Failed to forget contract {}: {}", id, e);
+                    }
+                });
+            }
+            Err(_) => log::warn!(
+                "Contract {} is dropped outside of tokio runtime and can't be forgotten",
+                self.id
+            ),
+        }
+    }
+}
+
+/// Account state and get-methods of a contract, which the contract wrappers, e.g.
+/// `JettonMasterContract`, are built on.
+///
+/// Besides `TonContract` of a `TonContractFactory`, it's implemented by `ProviderContract`
+/// serving the contract with a `TonProvider`, e.g. `MockTonClient` in unit tests.
 #[async_trait]
 pub trait TonContractInterface {
-    fn factory(&self) -> &TonContractFactory;
-
     fn address(&self) -> &TonAddress;
 
     async fn get_account_state(&self) -> Result<Arc<RawFullAccountState>, TonContractError>;
@@ -35,3 +103,9 @@ pub trait TonContractInterface {
         M: Into<TonMethodId> + Send + Copy,
         S: AsRef<[TvmStackEntry]> + Send;
 }
+
+/// Contract created by a `TonContractFactory`, whose wrappers follow references to other
+/// contracts with the factory, e.g. `NftItemContract` loading metadata of the collection.
+pub trait TonFactoryContract: TonContractInterface {
+    fn factory(&self) -> &TonContractFactory;
+}
@@ -0,0 +1,175 @@
+use std::collections::{HashMap, HashSet};
+
+use lazy_static::lazy_static;
+use tonlib_core::wallet::WalletVersion;
+use tonlib_core::TonHash;
+
+use crate::contract::{
+    DnsItemContract, JettonMasterContract, JettonWalletContract, NftCollectionContract,
+    NftItemContract, TonContractError, TonContractState,
+};
+
+/// Standard interface implemented by a contract, see `TonContract::detect_interfaces`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ContractInterface {
+    Wallet(WalletVersion),
+    JettonMaster,
+    JettonWallet,
+    NftItem,
+    NftCollection,
+    DnsItem,
+}
+
+lazy_static! {
+    pub static ref KNOWN_CODE_HASHES: CodeHashRegistry = CodeHashRegistry::default();
+}
+
+/// Registry of code hashes of contracts with known interfaces.
+///
+/// The default registry contains all wallet versions of `WalletVersion`. Jettons and NFTs
+/// are deployed with many code variants, so they are detected by probing get-methods unless
+/// their code hashes are registered.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeHashRegistry {
+    interfaces: HashMap<TonHash, HashSet<ContractInterface>>,
+}
+
+impl CodeHashRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> CodeHashRegistry {
+        CodeHashRegistry {
+            interfaces: HashMap::new(),
+        }
+    }
+
+    pub fn register(&mut self, code_hash: &TonHash, interface: ContractInterface) -> &mut Self {
+        self.interfaces
+            .entry(*code_hash)
+            .or_default()
+            .insert(interface);
+        self
+    }
+
+    /// Returns interfaces registered for `code_hash`, `None` for an unknown code.
+    pub fn lookup(&self, code_hash: &TonHash) -> Option<&HashSet<ContractInterface>> {
+        self.interfaces.get(code_hash)
+    }
+}
+
+impl Default for CodeHashRegistry {
+    fn default() -> Self {
+        let mut registry = CodeHashRegistry::new();
+        for version in WalletVersion::ALL {
+            if let Ok(code) = version.code() {
+                let code_hash = code.cell_hash();
+                registry.register(&code_hash, ContractInterface::Wallet(version));
+            }
+        }
+        registry
+    }
+}
+
+/// Probes characteristic get-methods of the standard interfaces on `state`.
+///
+/// An interface is absent if its get-method fails in TVM or returns an unexpected stack,
+/// other errors, e.g. of the connection, are returned.
+pub(crate) async fn probe_interfaces(
+    state: &TonContractState,
+) -> Result<HashSet<ContractInterface>, TonContractError> {
+    let (jetton_master, jetton_wallet, nft_item, nft_collection, dns_item) = futures::join!(
+        state.get_jetton_data(),
+        state.get_wallet_data(),
+        state.get_nft_data(),
+        state.get_collection_data(),
+        state.get_full_domain(),
+    );
+    let probes = [
+        (
+            is_implemented(jetton_master)?,
+            ContractInterface::JettonMaster,
+        ),
+        (
+            is_implemented(jetton_wallet)?,
+            ContractInterface::JettonWallet,
+        ),
+        (is_implemented(nft_item)?, ContractInterface::NftItem),
+        (
+            is_implemented(nft_collection)?,
+            ContractInterface::NftCollection,
+        ),
+        (is_implemented(dns_item)?, ContractInterface::DnsItem),
+    ];
+    Ok(probes
+        .into_iter()
+        .filter_map(|(detected, interface)| detected.then_some(interface))
+        .collect())
+}
+
+/// Returns whether a probed get-method succeeded, `false` if it doesn't exist or returned
+/// something else than the interface defines.
+fn is_implemented<T>(result: Result<T, TonContractError>) -> Result<bool, TonContractError> {
+    match result {
+        Ok(_) => Ok(true),
+        Err(
+            TonContractError::TvmRunError { .. }
+            | TonContractError::TvmStackParseError { .. }
+            | TonContractError::InvalidMethodResultStackSize { .. }
+            | TonContractError::MethodResultStackError { .. }
+            | TonContractError::CellError { .. },
+        ) => Ok(false),
+        Err(TonContractError::ClientError(e)) if e.exit_code().is_some() => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use tonlib_core::wallet::WalletVersion;
+
+    use tonlib_core::TonAddress;
+
+    use super::{is_implemented, CodeHashRegistry, ContractInterface, KNOWN_CODE_HASHES};
+    use crate::client::TonClientError;
+    use crate::contract::TonContractError;
+
+    #[test]
+    fn test_code_hash_registry() {
+        let code_hash = WalletVersion::V4R2.code().unwrap().cell_hash();
+        assert_eq!(
+            KNOWN_CODE_HASHES.lookup(&code_hash),
+            Some(&HashSet::from([ContractInterface::Wallet(
+                WalletVersion::V4R2
+            )]))
+        );
+        assert_eq!(KNOWN_CODE_HASHES.lookup(&[1; 32]), None);
+
+        let mut registry = CodeHashRegistry::new();
+        registry
+            .register(&[1; 32], ContractInterface::NftItem)
+            .register(&[1; 32], ContractInterface::DnsItem);
+        assert_eq!(registry.lookup(&[1; 32]).map(HashSet::len), Some(2));
+        assert_eq!(registry.lookup(&code_hash), None);
+    }
+
+    #[test]
+    fn test_probe_errors() {
+        let absent = TonContractError::TvmRunError {
+            method: "get_jetton_data".into(),
+            address: TonAddress::NULL,
+            vm_log: None,
+            exit_code: 11,
+            stack: vec![],
+            missing_library: None,
+            gas_used: 0,
+        };
+        assert!(!is_implemented::<()>(Err(absent)).unwrap());
+        assert!(is_implemented(Ok(())).unwrap());
+        let timeout = TonContractError::ClientError(TonClientError::Timeout {
+            method: "smc.runGetMethod",
+            elapsed: std::time::Duration::from_secs(10),
+        });
+        assert!(is_implemented::<()>(Err(timeout)).is_err());
+    }
+}
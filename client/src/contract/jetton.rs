@@ -1,5 +1,7 @@
 mod master_contract;
+mod supply_cache;
 mod wallet_contract;
 
 pub use master_contract::*;
+pub use supply_cache::*;
 pub use wallet_contract::*;
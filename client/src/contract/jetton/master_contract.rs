@@ -1,7 +1,11 @@
 use async_trait::async_trait;
+use futures::future::try_join_all;
 use num_bigint::BigUint;
+use num_traits::Zero;
 use strum::IntoStaticStr;
-use tonlib_core::cell::{ArcCell, BagOfCells, CellBuilder, CellSlice, TonCellError};
+use tonlib_core::cell::{
+    ArcCell, BagOfCells, Cell, CellBuilder, CellSlice, StateInit, TonCellError,
+};
 use tonlib_core::TonAddress;
 
 use crate::contract::{MapCellError, MapStackError, TonContractError, TonContractInterface};
@@ -17,6 +21,101 @@ pub struct JettonData {
     pub wallet_code: ArcCell,
 }
 
+impl JettonData {
+    /// Decodes the result stack of `get_jetton_data` of the jetton master at `address`:
+    /// total supply, mintable flag, admin address, content and jetton wallet code.
+    pub fn from_stack(
+        address: &TonAddress,
+        stack: &[TvmStackEntry],
+    ) -> Result<JettonData, TonContractError> {
+        const JETTON_DATA_STACK_ELEMENTS: usize = 5;
+        let method: &'static str = JettonMasterMethods::GetJettonData.into();
+        if stack.len() != JETTON_DATA_STACK_ELEMENTS {
+            return Err(TonContractError::InvalidMethodResultStackSize {
+                method: method.to_string(),
+                address: address.clone(),
+                actual: stack.len(),
+                expected: JETTON_DATA_STACK_ELEMENTS,
+            });
+        }
+        let total_supply = stack[0].get_biguint().map_stack_error(method, address)?;
+        let mintable = stack[1].get_bool().map_stack_error(method, address)?;
+        let admin_address = stack[2].get_address().map_stack_error(method, address)?;
+        let cell = stack[3].get_cell().map_stack_error(method, address)?;
+        let content = read_jetton_metadata_content(cell).map_cell_error(method, address)?;
+        let wallet_code = stack[4].get_cell().map_stack_error(method, address)?;
+        Ok(JettonData {
+            total_supply,
+            mintable,
+            admin_address,
+            content,
+            wallet_code,
+        })
+    }
+
+    /// Computes the address of the jetton wallet of `owner_address` off-chain, see
+    /// [`predict_jetton_wallet_address_with_layout`].
+    pub fn predict_wallet_address(
+        &self,
+        layout: JettonWalletLayout,
+        master_address: &TonAddress,
+        owner_address: &TonAddress,
+    ) -> Result<TonAddress, TonCellError> {
+        predict_jetton_wallet_address_with_layout(
+            layout,
+            &self.wallet_code,
+            master_address,
+            owner_address,
+        )
+    }
+}
+
+/// Layout of the initial data of jetton wallets of known jetton implementations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JettonWalletLayout {
+    /// TEP-74 reference implementation: zero balance, the owner address, the master address
+    /// and a reference to the wallet code.
+    Standard,
+    /// Jettons with governance, e.g. USDT: zero status (4 bits), zero balance, the owner address
+    /// and the master address.
+    Governance,
+}
+
+impl JettonWalletLayout {
+    pub const ALL: [JettonWalletLayout; 2] =
+        [JettonWalletLayout::Standard, JettonWalletLayout::Governance];
+
+    /// Builds the initial data of the jetton wallet of `owner_address`.
+    pub fn wallet_data(
+        &self,
+        wallet_code: &ArcCell,
+        master_address: &TonAddress,
+        owner_address: &TonAddress,
+    ) -> Result<Cell, TonCellError> {
+        let mut builder = CellBuilder::new();
+        if *self == JettonWalletLayout::Governance {
+            builder.store_u8(4, 0)?;
+        }
+        builder
+            .store_coins(&BigUint::zero())?
+            .store_address(owner_address)?
+            .store_address(master_address)?;
+        if *self == JettonWalletLayout::Standard {
+            builder.store_reference(wallet_code)?;
+        }
+        builder.build()
+    }
+}
+
+/// How `JettonMasterContract::get_wallet_addresses` obtained jetton wallet addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JettonWalletAddressSource {
+    /// Computed off-chain with [`predict_jetton_wallet_address_with_layout`].
+    Predicted,
+    /// Returned by the `get_wallet_address` get-method.
+    GetMethod,
+}
+
 #[derive(IntoStaticStr)]
 #[strum(serialize_all = "snake_case")]
 enum JettonMasterMethods {
@@ -27,37 +126,13 @@ enum JettonMasterMethods {
 #[async_trait]
 pub trait JettonMasterContract: TonContractInterface {
     async fn get_jetton_data(&self) -> Result<JettonData, TonContractError> {
-        const JETTON_DATA_STACK_ELEMENTS: usize = 5;
-        let method = JettonMasterMethods::GetJettonData.into();
-        let address = self.address().clone();
-
+        let method: &'static str = JettonMasterMethods::GetJettonData.into();
         let res = self.run_get_method(method, Vec::new()).await?;
-
-        let stack = res.stack;
-        if stack.len() == JETTON_DATA_STACK_ELEMENTS {
-            let total_supply = stack[0].get_biguint().map_stack_error(method, &address)?;
-            let mintable = stack[1].get_bool().map_stack_error(method, &address)?;
-            let admin_address = stack[2].get_address().map_stack_error(method, &address)?;
-            let cell = stack[3].get_cell().map_stack_error(method, &address)?;
-            let content = read_jetton_metadata_content(cell).map_cell_error(method, &address)?;
-            let wallet_code = stack[4].get_cell().map_stack_error(method, &address)?;
-            Ok(JettonData {
-                total_supply,
-                mintable,
-                admin_address,
-                content,
-                wallet_code,
-            })
-        } else {
-            Err(TonContractError::InvalidMethodResultStackSize {
-                method: method.to_string(),
-                address: self.address().clone(),
-                actual: stack.len(),
-                expected: JETTON_DATA_STACK_ELEMENTS,
-            })
-        }
+        JettonData::from_stack(self.address(), &res.stack)
     }
 
+    /// For standard jettons the address can be computed offline with
+    /// [`predict_jetton_wallet_address`].
     async fn get_wallet_address(
         &self,
         owner_address: &TonAddress,
@@ -84,10 +159,93 @@ pub trait JettonMasterContract: TonContractInterface {
             })
         }
     }
+
+    /// Returns jetton wallet addresses of all `owners`, in the same order.
+    ///
+    /// The address of the first owner is both queried with `get_wallet_address` and predicted
+    /// with every `JettonWalletLayout`. If one of them matches, the addresses of the remaining
+    /// owners are predicted off-chain with that layout, otherwise `get_wallet_address` is called
+    /// for every owner. The returned `JettonWalletAddressSource` tells which of the two happened.
+    async fn get_wallet_addresses(
+        &self,
+        owners: &[TonAddress],
+    ) -> Result<(Vec<TonAddress>, JettonWalletAddressSource), TonContractError> {
+        let method: &'static str = JettonMasterMethods::GetWalletAddress.into();
+        let first_owner = match owners.first() {
+            Some(owner) => owner,
+            None => return Ok((Vec::new(), JettonWalletAddressSource::Predicted)),
+        };
+        let jetton_data = self.get_jetton_data().await?;
+        let expected = self.get_wallet_address(first_owner).await?;
+        let mut matching_layout = None;
+        for layout in JettonWalletLayout::ALL {
+            let predicted = jetton_data
+                .predict_wallet_address(layout, self.address(), first_owner)
+                .map_cell_error(method, first_owner)?;
+            if predicted == expected {
+                matching_layout = Some(layout);
+                break;
+            }
+        }
+        if let Some(layout) = matching_layout {
+            let mut addresses = Vec::with_capacity(owners.len());
+            for owner in owners {
+                let address = jetton_data
+                    .predict_wallet_address(layout, self.address(), owner)
+                    .map_cell_error(method, owner)?;
+                addresses.push(address);
+            }
+            Ok((addresses, JettonWalletAddressSource::Predicted))
+        } else {
+            log::debug!(
+                "Jetton wallet address of {} can't be predicted, falling back to {}",
+                self.address(),
+                method
+            );
+            let mut addresses = vec![expected];
+            addresses.extend(
+                try_join_all(owners[1..].iter().map(|o| self.get_wallet_address(o))).await?,
+            );
+            Ok((addresses, JettonWalletAddressSource::GetMethod))
+        }
+    }
 }
 
 impl<T> JettonMasterContract for T where T: TonContractInterface {}
 
+/// Computes the address of a jetton wallet of a standard jetton without calling
+/// `get_wallet_address`.
+///
+/// The standard jetton master (TEP-74 reference implementation) deploys wallets with
+/// `wallet_code` (returned by `get_jetton_data`) and initial data consisting of zero balance,
+/// the owner address, the master address and a reference to `wallet_code`.
+/// Jettons deriving wallet state init differently must use the on-chain method.
+pub fn predict_jetton_wallet_address(
+    wallet_code: &ArcCell,
+    master_address: &TonAddress,
+    owner_address: &TonAddress,
+) -> Result<TonAddress, TonCellError> {
+    predict_jetton_wallet_address_with_layout(
+        JettonWalletLayout::Standard,
+        wallet_code,
+        master_address,
+        owner_address,
+    )
+}
+
+/// Computes the address of a jetton wallet deployed with `wallet_code` and the initial data
+/// of `layout` without calling `get_wallet_address`.
+pub fn predict_jetton_wallet_address_with_layout(
+    layout: JettonWalletLayout,
+    wallet_code: &ArcCell,
+    master_address: &TonAddress,
+    owner_address: &TonAddress,
+) -> Result<TonAddress, TonCellError> {
+    let data = layout.wallet_data(wallet_code, master_address, owner_address)?;
+    let hash = StateInit::create_account_id(wallet_code, &ArcCell::new(data))?;
+    Ok(TonAddress::new(master_address.workchain, &hash))
+}
+
 fn read_jetton_metadata_content(cell: ArcCell) -> Result<MetaDataContent, TonCellError> {
     let mut parser = cell.parser();
     let content_representation = parser.load_byte()?;
@@ -0,0 +1,165 @@
+use dashmap::DashMap;
+use moka::future::Cache;
+use num_bigint::BigUint;
+use num_traits::Zero;
+use tonlib_core::TonAddress;
+
+use crate::contract::{JettonMasterContract, TonContractError, TonContractFactory};
+use crate::meta::{JettonMetaData, JettonMetaLoader, LoadMeta, MetaDataContent};
+
+/// Number of decimals assumed when jetton metadata doesn't specify it.
+pub const DEFAULT_JETTON_DECIMALS: u8 = 9;
+
+const DEFAULT_SUPPLY_CACHE_CAPACITY: u64 = 10_000;
+
+/// Cache of jetton total supply and decimals.
+///
+/// Total supply is cached per master contract and masterchain block of its account state,
+/// so a new value is requested only when a new state of the master contract is observed.
+/// Decimals are considered immutable and are cached until `invalidate_decimals`, e.g. after
+/// an update of the metadata. The two are loaded and invalidated independently, so getting
+/// supply never depends on loading metadata.
+pub struct JettonSupplyCache {
+    contract_factory: TonContractFactory,
+    meta_loader: JettonMetaLoader,
+    /// Supply of a master contract with the masterchain seqno of the state it was read from.
+    supply_cache: Cache<TonAddress, (i32, BigUint)>,
+    decimals_cache: DashMap<TonAddress, u8>,
+}
+
+impl JettonSupplyCache {
+    /// Creates a cache of supply values of up to `DEFAULT_SUPPLY_CACHE_CAPACITY` jettons.
+    ///
+    /// Decimals are kept in a separate unbounded map, one byte per master contract, so that
+    /// evicting supply values of busy jettons never causes metadata to be loaded again.
+    pub fn new(
+        contract_factory: &TonContractFactory,
+        meta_loader: JettonMetaLoader,
+    ) -> JettonSupplyCache {
+        Self::with_capacity(contract_factory, meta_loader, DEFAULT_SUPPLY_CACHE_CAPACITY)
+    }
+
+    /// Same as `new`, limiting the number of master contracts with cached supply to `capacity`.
+    pub fn with_capacity(
+        contract_factory: &TonContractFactory,
+        meta_loader: JettonMetaLoader,
+        capacity: u64,
+    ) -> JettonSupplyCache {
+        JettonSupplyCache {
+            contract_factory: contract_factory.clone(),
+            meta_loader,
+            supply_cache: Cache::builder().max_capacity(capacity).build(),
+            decimals_cache: DashMap::new(),
+        }
+    }
+
+    /// Returns total supply of the jetton in elementary units.
+    pub async fn get_jetton_supply(
+        &self,
+        master: &TonAddress,
+    ) -> Result<BigUint, TonContractError> {
+        let state = self
+            .contract_factory
+            .get_latest_contract_state(master)
+            .await?;
+        let seqno = state.get_account_state().block_id.seqno;
+        if let Some((cached_seqno, supply)) = self.supply_cache.get(master).await {
+            if cached_seqno == seqno {
+                return Ok(supply);
+            }
+        }
+
+        let jetton_data = state.get_jetton_data().await?;
+        self.supply_cache
+            .insert(master.clone(), (seqno, jetton_data.total_supply.clone()))
+            .await;
+        Ok(jetton_data.total_supply)
+    }
+
+    /// Returns decimals of the jetton, `DEFAULT_JETTON_DECIMALS` if not specified in metadata.
+    pub async fn get_jetton_decimals(&self, master: &TonAddress) -> Result<u8, TonContractError> {
+        if let Some(decimals) = self.decimals_cache.get(master) {
+            return Ok(*decimals);
+        }
+        let jetton_data = self
+            .contract_factory
+            .get_contract(master)
+            .get_jetton_data()
+            .await?;
+        let decimals = self.load_decimals(&jetton_data.content).await?;
+        self.decimals_cache.insert(master.clone(), decimals);
+        Ok(decimals)
+    }
+
+    /// Removes cached supply of the jetton, e.g. to re-read a state known to be stale.
+    pub async fn invalidate_supply(&self, master: &TonAddress) {
+        self.supply_cache.invalidate(master).await;
+    }
+
+    /// Removes cached decimals of the jetton, e.g. after an update of its metadata.
+    pub fn invalidate_decimals(&self, master: &TonAddress) {
+        self.decimals_cache.remove(master);
+    }
+
+    /// Returns total supply of the jetton in user representation, e.g. `"1234.5"`.
+    pub async fn get_jetton_supply_formatted(
+        &self,
+        master: &TonAddress,
+    ) -> Result<String, TonContractError> {
+        let supply = self.get_jetton_supply(master).await?;
+        let decimals = self.get_jetton_decimals(master).await?;
+        Ok(format_jetton_amount(&supply, decimals))
+    }
+
+    async fn load_decimals(&self, content: &MetaDataContent) -> Result<u8, TonContractError> {
+        let decimals = match content {
+            MetaDataContent::Internal { dict } => JettonMetaData::from(dict).decimals,
+            content => {
+                self.meta_loader
+                    .load(content)
+                    .await
+                    .map_err(|e| TonContractError::InternalError(e.to_string()))?
+                    .decimals
+            }
+        };
+        Ok(decimals.unwrap_or(DEFAULT_JETTON_DECIMALS))
+    }
+}
+
+/// Formats an amount in elementary units as a decimal number with `decimals` fractional digits.
+///
+/// Trailing zeros of the fractional part are omitted.
+pub fn format_jetton_amount(amount: &BigUint, decimals: u8) -> String {
+    let digits = amount.to_string();
+    let decimals = decimals as usize;
+    if decimals == 0 || amount.is_zero() {
+        return digits;
+    }
+    let padded = format!("{:0>width$}", digits, width = decimals + 1);
+    let (integer, fraction) = padded.split_at(padded.len() - decimals);
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        integer.to_string()
+    } else {
+        format!("{}.{}", integer, fraction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use super::format_jetton_amount;
+
+    #[test]
+    fn test_format_jetton_amount() {
+        assert_eq!(format_jetton_amount(&BigUint::from(0u32), 9), "0");
+        assert_eq!(format_jetton_amount(&BigUint::from(1u32), 9), "0.000000001");
+        assert_eq!(
+            format_jetton_amount(&BigUint::from(1_234_500_000_000u64), 9),
+            "1234.5"
+        );
+        assert_eq!(format_jetton_amount(&BigUint::from(1_000_000u32), 6), "1");
+        assert_eq!(format_jetton_amount(&BigUint::from(42u32), 0), "42");
+    }
+}
@@ -5,6 +5,7 @@ use tonlib_core::cell::ArcCell;
 use tonlib_core::TonAddress;
 
 use crate::contract::{MapStackError, TonContractError, TonContractInterface};
+use crate::types::TvmStackEntry;
 
 #[derive(Debug, Clone)]
 pub struct WalletData {
@@ -14,6 +15,38 @@ pub struct WalletData {
     pub wallet_code: ArcCell,
 }
 
+impl WalletData {
+    /// Decodes the result stack of `get_wallet_data` of the jetton wallet at `address`.
+    ///
+    /// Some jetton wallet implementations return additional entries after the standard ones,
+    /// so only the first four (balance, owner, jetton master, wallet code) are decoded.
+    pub fn from_stack(
+        address: &TonAddress,
+        stack: &[TvmStackEntry],
+    ) -> Result<WalletData, TonContractError> {
+        const WALLET_DATA_STACK_ELEMENTS: usize = 4;
+        let method: &'static str = JettonWalletMethods::GetWalletData.into();
+        if stack.len() < WALLET_DATA_STACK_ELEMENTS {
+            return Err(TonContractError::InvalidMethodResultStackSize {
+                method: method.to_string(),
+                address: address.clone(),
+                actual: stack.len(),
+                expected: WALLET_DATA_STACK_ELEMENTS,
+            });
+        }
+        let balance = stack[0].get_biguint().map_stack_error(method, address)?;
+        let owner_address = stack[1].get_address().map_stack_error(method, address)?;
+        let master_address = stack[2].get_address().map_stack_error(method, address)?;
+        let wallet_code = stack[3].get_cell().map_stack_error(method, address)?;
+        Ok(WalletData {
+            balance,
+            owner_address,
+            master_address,
+            wallet_code,
+        })
+    }
+}
+
 #[derive(IntoStaticStr)]
 #[strum(serialize_all = "snake_case")]
 enum JettonWalletMethods {
@@ -23,32 +56,9 @@ enum JettonWalletMethods {
 #[async_trait]
 pub trait JettonWalletContract: TonContractInterface {
     async fn get_wallet_data(&self) -> Result<WalletData, TonContractError> {
-        const WALLET_DATA_STACK_ELEMENTS: usize = 4;
-        let method = JettonWalletMethods::GetWalletData.into();
-        let address = self.address().clone();
-
+        let method: &'static str = JettonWalletMethods::GetWalletData.into();
         let res = self.run_get_method(method, Vec::new()).await?;
-
-        let stack = res.stack;
-        if stack.len() == WALLET_DATA_STACK_ELEMENTS {
-            let balance = stack[0].get_biguint().map_stack_error(method, &address)?;
-            let owner_address = stack[1].get_address().map_stack_error(method, &address)?;
-            let master_address = stack[2].get_address().map_stack_error(method, &address)?;
-            let wallet_code = stack[3].get_cell().map_stack_error(method, &address)?;
-            Ok(WalletData {
-                balance,
-                owner_address,
-                master_address,
-                wallet_code,
-            })
-        } else {
-            Err(TonContractError::InvalidMethodResultStackSize {
-                method: method.to_string(),
-                address: self.address().clone(),
-                actual: stack.len(),
-                expected: WALLET_DATA_STACK_ELEMENTS,
-            })
-        }
+        WalletData::from_stack(self.address(), &res.stack)
     }
 }
 
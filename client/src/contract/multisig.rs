@@ -0,0 +1,5 @@
+mod multisig_contract;
+mod order_contract;
+
+pub use multisig_contract::*;
+pub use order_contract::*;
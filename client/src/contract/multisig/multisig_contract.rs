@@ -0,0 +1,277 @@
+use async_trait::async_trait;
+use num_bigint::{BigInt, BigUint};
+use num_traits::Zero;
+use strum::IntoStaticStr;
+use tonlib_core::cell::{ArcCell, CellBuilder, StateInit, TonCellError};
+use tonlib_core::message::MultisigNewOrderMessage;
+use tonlib_core::TonAddress;
+
+use super::order_contract::read_address_list;
+use crate::contract::{
+    MapStackError, MultisigOrderContract, MultisigOrderData, TonContractError, TonFactoryContract,
+};
+use crate::types::TvmStackEntry;
+
+/// Data returned by `get_multisig_data` of a multisig v2 contract.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultisigData {
+    /// seqno of the next order, -1 if the multisig accepts orders with arbitrary seqno.
+    pub next_order_seqno: BigInt,
+    /// number of approvals required to execute an order.
+    pub threshold: u8,
+    /// addresses allowed to create and approve orders, ordered by signer index.
+    pub signers: Vec<TonAddress>,
+    /// addresses allowed to create orders only, ordered by proposer index.
+    pub proposers: Vec<TonAddress>,
+}
+
+impl MultisigData {
+    pub fn signer_index(&self, address: &TonAddress) -> Option<u8> {
+        self.signers
+            .iter()
+            .position(|s| s == address)
+            .map(|i| i as u8)
+    }
+
+    pub fn proposer_index(&self, address: &TonAddress) -> Option<u8> {
+        self.proposers
+            .iter()
+            .position(|p| p == address)
+            .map(|i| i as u8)
+    }
+
+    /// Creates a proposal of a new order with `next_order_seqno` sent by `sender`, a signer
+    /// or a proposer of the multisig. Actions are to be added with `with_action`.
+    ///
+    /// Fails if `sender` is neither a signer nor a proposer, or if the multisig accepts
+    /// arbitrary order seqno, in which case the message is to be created with an explicit seqno.
+    pub fn new_order(
+        &self,
+        sender: &TonAddress,
+        expiration_date: u64,
+    ) -> Result<MultisigNewOrderMessage, TonContractError> {
+        let order_seqno = self.next_order_seqno.to_biguint().ok_or_else(|| {
+            TonContractError::IllegalArgument(
+                "Multisig accepts arbitrary order seqno, it must be set explicitly".to_string(),
+            )
+        })?;
+        if let Some(index) = self.signer_index(sender) {
+            return Ok(MultisigNewOrderMessage::new(
+                &order_seqno,
+                index,
+                expiration_date,
+            ));
+        }
+        match self.proposer_index(sender) {
+            Some(index) => {
+                let mut message = MultisigNewOrderMessage::new(&order_seqno, 0, expiration_date);
+                message.with_proposer(index);
+                Ok(message)
+            }
+            None => Err(TonContractError::IllegalArgument(format!(
+                "{} is neither a signer nor a proposer of the multisig",
+                sender
+            ))),
+        }
+    }
+}
+
+/// Computes the address of the order with `order_seqno` of a multisig v2 without calling
+/// `get_order_address`.
+///
+/// The multisig deploys orders with `order_code` embedded into its code and initial data
+/// consisting of the multisig address and the order seqno as `uint256`.
+pub fn predict_multisig_order_address(
+    order_code: &ArcCell,
+    multisig_address: &TonAddress,
+    order_seqno: &BigUint,
+) -> Result<TonAddress, TonCellError> {
+    let data = CellBuilder::new()
+        .store_address(multisig_address)?
+        .store_uint(256, order_seqno)?
+        .build()?;
+    StateInit::new(order_code, &ArcCell::new(data)).address(multisig_address.workchain)
+}
+
+#[derive(IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+enum MultisigMethods {
+    GetMultisigData,
+    GetOrderAddress,
+}
+
+#[async_trait]
+pub trait MultisigContract: TonFactoryContract {
+    /// Returns threshold, signers and proposers of the multisig.
+    async fn get_multisig_data(&self) -> Result<MultisigData, TonContractError> {
+        const MULTISIG_STACK_ELEMENTS: usize = 4;
+        let method: &str = MultisigMethods::GetMultisigData.into();
+        let address = self.address().clone();
+
+        let stack = self.run_get_method(method, Vec::new()).await?.stack;
+        if stack.len() != MULTISIG_STACK_ELEMENTS {
+            return Err(TonContractError::InvalidMethodResultStackSize {
+                method: method.to_string(),
+                address,
+                actual: stack.len(),
+                expected: MULTISIG_STACK_ELEMENTS,
+            });
+        }
+        let next_order_seqno = stack[0].get_bigint().map_stack_error(method, &address)?;
+        let threshold = stack[1].get_i64().map_stack_error(method, &address)? as u8;
+        let signers = read_address_list(&stack[2]).map_stack_error(method, &address)?;
+        let proposers = read_address_list(&stack[3]).map_stack_error(method, &address)?;
+
+        Ok(MultisigData {
+            next_order_seqno,
+            threshold,
+            signers,
+            proposers,
+        })
+    }
+
+    /// Returns address of the order contract with `order_seqno`.
+    ///
+    /// Can be computed without a request with [`predict_multisig_order_address`].
+    async fn get_order_address(
+        &self,
+        order_seqno: &BigUint,
+    ) -> Result<TonAddress, TonContractError> {
+        let method: &str = MultisigMethods::GetOrderAddress.into();
+        let input_stack = vec![TvmStackEntry::from(order_seqno.clone())];
+        let stack = self.run_get_method(method, &input_stack).await?.stack;
+
+        if stack.len() == 1 {
+            stack[0]
+                .get_address()
+                .map_stack_error(method, self.address())
+        } else {
+            Err(TonContractError::InvalidMethodResultStackSize {
+                method: method.to_string(),
+                address: self.address().clone(),
+                actual: stack.len(),
+                expected: 1,
+            })
+        }
+    }
+
+    /// Returns orders awaiting approvals at unix time `now` among the last `limit` orders,
+    /// newest first.
+    ///
+    /// Orders are enumerated by seqno, so this doesn't work for multisigs accepting
+    /// arbitrary order seqno. Order contracts which were never deployed are skipped.
+    async fn get_pending_orders(
+        &self,
+        now: u64,
+        limit: usize,
+    ) -> Result<Vec<(TonAddress, MultisigOrderData)>, TonContractError> {
+        let data = self.get_multisig_data().await?;
+        let next_order_seqno = data.next_order_seqno.to_biguint().ok_or_else(|| {
+            TonContractError::IllegalArgument(format!(
+                "Multisig {} accepts arbitrary order seqno, pending orders can't be enumerated",
+                self.address()
+            ))
+        })?;
+        let mut seqno = next_order_seqno;
+        let mut orders = Vec::new();
+        for _ in 0..limit {
+            if seqno.is_zero() {
+                break;
+            }
+            seqno -= 1u32;
+            let order_address = self.get_order_address(&seqno).await?;
+            let state = self
+                .factory()
+                .get_latest_account_state(&order_address)
+                .await?;
+            if state.code.is_empty() {
+                continue;
+            }
+            let order = self.factory().get_contract(&order_address);
+            let order_data = order.get_order_data().await?;
+            if order_data.is_pending(now) {
+                orders.push((order_address, order_data));
+            }
+        }
+        Ok(orders)
+    }
+}
+
+impl<T> MultisigContract for T where T: TonFactoryContract {}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use num_bigint::{BigInt, BigUint};
+    use tonlib_core::cell::{CellBuilder, TonCellError};
+    use tonlib_core::TonAddress;
+
+    use super::{predict_multisig_order_address, MultisigData};
+    use crate::contract::MultisigOrderData;
+
+    fn address(n: u8) -> TonAddress {
+        TonAddress::new(0, &[n; 32])
+    }
+
+    fn multisig_data(next_order_seqno: i32) -> MultisigData {
+        MultisigData {
+            next_order_seqno: BigInt::from(next_order_seqno),
+            threshold: 2,
+            signers: vec![address(1), address(2), address(3)],
+            proposers: vec![address(4)],
+        }
+    }
+
+    #[test]
+    fn test_predict_multisig_order_address() -> Result<(), TonCellError> {
+        let order_code = Arc::new(CellBuilder::new().store_string("order")?.build()?);
+        let multisig =
+            TonAddress::from_str("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR").unwrap();
+        let first = predict_multisig_order_address(&order_code, &multisig, &BigUint::from(0u32))?;
+        let second = predict_multisig_order_address(&order_code, &multisig, &BigUint::from(1u32))?;
+        assert_eq!(first.workchain, multisig.workchain);
+        assert_ne!(first, second);
+        assert_eq!(
+            first,
+            predict_multisig_order_address(&order_code, &multisig, &BigUint::from(0u32))?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_multisig_new_order() {
+        let data = multisig_data(5);
+        let by_signer = data.new_order(&address(2), 1_000).unwrap();
+        assert_eq!(by_signer.order_seqno, BigUint::from(5u32));
+        assert!(by_signer.signer);
+        assert_eq!(by_signer.index, 1);
+        let by_proposer = data.new_order(&address(4), 1_000).unwrap();
+        assert!(!by_proposer.signer);
+        assert_eq!(by_proposer.index, 0);
+        assert!(data.new_order(&address(5), 1_000).is_err());
+        assert!(multisig_data(-1).new_order(&address(1), 1_000).is_err());
+    }
+
+    #[test]
+    fn test_multisig_order_approve() -> Result<(), TonCellError> {
+        let data = MultisigOrderData {
+            multisig_address: address(0),
+            order_seqno: BigUint::from(5u32),
+            threshold: 2,
+            sent_for_execution: false,
+            signers: vec![address(1), address(2), address(3)],
+            approvals_mask: BigUint::from(0b010u32),
+            approvals_num: 1,
+            expiration_date: 1_000,
+            order: Arc::new(CellBuilder::new().build()?),
+        };
+        assert_eq!(data.approve(&address(3)).unwrap().signer_index, 2);
+        // Already approved
+        assert!(data.approve(&address(2)).is_err());
+        // Not a signer
+        assert!(data.approve(&address(4)).is_err());
+        Ok(())
+    }
+}
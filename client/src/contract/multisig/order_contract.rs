@@ -0,0 +1,133 @@
+use async_trait::async_trait;
+use num_bigint::BigUint;
+use strum::IntoStaticStr;
+use tonlib_core::cell::dict::predefined_readers::{key_reader_u8, val_reader_address};
+use tonlib_core::cell::ArcCell;
+use tonlib_core::message::{MultisigAction, MultisigApproveMessage, TonMessageError};
+use tonlib_core::TonAddress;
+
+use crate::contract::{MapStackError, TonContractError, TonContractInterface};
+use crate::types::{StackParseError, TvmStackEntry};
+
+/// Data returned by `get_order_data` of a multisig v2 order contract.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultisigOrderData {
+    /// address of the multisig the order belongs to.
+    pub multisig_address: TonAddress,
+    pub order_seqno: BigUint,
+    /// number of approvals required to execute the order.
+    pub threshold: u8,
+    /// whether the order has collected enough approvals and was sent to the multisig.
+    pub sent_for_execution: bool,
+    /// signers of the multisig at the moment of the order creation.
+    pub signers: Vec<TonAddress>,
+    /// bit `i` is set if signer `i` approved the order.
+    pub approvals_mask: BigUint,
+    pub approvals_num: u8,
+    /// unix time after which the order can't be approved anymore.
+    pub expiration_date: u64,
+    /// `Order` cell, see `MultisigAction::parse_order`.
+    pub order: ArcCell,
+}
+
+impl MultisigOrderData {
+    /// Returns whether signer with `signer_index` approved the order.
+    pub fn is_approved_by(&self, signer_index: u8) -> bool {
+        self.approvals_mask.bit(signer_index as u64)
+    }
+
+    /// Returns whether the order still awaits approvals at unix time `now`.
+    pub fn is_pending(&self, now: u64) -> bool {
+        !self.sent_for_execution && self.expiration_date > now
+    }
+
+    /// Decodes the actions of the order.
+    pub fn actions(&self) -> Result<Vec<MultisigAction>, TonMessageError> {
+        MultisigAction::parse_order(&self.order)
+    }
+
+    /// Creates an approval of the order by `signer`, to be sent to the order contract.
+    ///
+    /// Fails if `signer` isn't a signer of the order or has already approved it.
+    pub fn approve(&self, signer: &TonAddress) -> Result<MultisigApproveMessage, TonContractError> {
+        let index = self
+            .signers
+            .iter()
+            .position(|s| s == signer)
+            .ok_or_else(|| {
+                TonContractError::IllegalArgument(format!(
+                    "{} is not a signer of the order",
+                    signer
+                ))
+            })? as u8;
+        if self.is_approved_by(index) {
+            return Err(TonContractError::IllegalArgument(format!(
+                "Order is already approved by {}",
+                signer
+            )));
+        }
+        Ok(MultisigApproveMessage::new(index))
+    }
+}
+
+#[derive(IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+enum MultisigOrderMethods {
+    GetOrderData,
+}
+
+#[async_trait]
+pub trait MultisigOrderContract: TonContractInterface {
+    /// Returns data of the multisig order.
+    async fn get_order_data(&self) -> Result<MultisigOrderData, TonContractError> {
+        const MULTISIG_ORDER_STACK_ELEMENTS: usize = 9;
+        let method: &str = MultisigOrderMethods::GetOrderData.into();
+        let address = self.address().clone();
+
+        let stack = self.run_get_method(method, Vec::new()).await?.stack;
+        if stack.len() != MULTISIG_ORDER_STACK_ELEMENTS {
+            return Err(TonContractError::InvalidMethodResultStackSize {
+                method: method.to_string(),
+                address,
+                actual: stack.len(),
+                expected: MULTISIG_ORDER_STACK_ELEMENTS,
+            });
+        }
+        let multisig_address = stack[0].get_address().map_stack_error(method, &address)?;
+        let order_seqno = stack[1].get_biguint().map_stack_error(method, &address)?;
+        let threshold = stack[2].get_i64().map_stack_error(method, &address)? as u8;
+        let sent_for_execution = stack[3].get_bool().map_stack_error(method, &address)?;
+        let signers = read_address_list(&stack[4]).map_stack_error(method, &address)?;
+        let approvals_mask = stack[5].get_biguint().map_stack_error(method, &address)?;
+        let approvals_num = stack[6].get_i64().map_stack_error(method, &address)? as u8;
+        let expiration_date = stack[7].get_i64().map_stack_error(method, &address)? as u64;
+        let order = stack[8].get_cell().map_stack_error(method, &address)?;
+
+        Ok(MultisigOrderData {
+            multisig_address,
+            order_seqno,
+            threshold,
+            sent_for_execution,
+            signers,
+            approvals_mask,
+            approvals_num,
+            expiration_date,
+            order,
+        })
+    }
+}
+
+impl<T> MultisigOrderContract for T where T: TonContractInterface {}
+
+/// Reads `Hashmap 8 MsgAddressInt` (or null for an empty `HashmapE`) ordered by keys.
+pub(super) fn read_address_list(entry: &TvmStackEntry) -> Result<Vec<TonAddress>, StackParseError> {
+    if let TvmStackEntry::Null = entry {
+        return Ok(Vec::new());
+    }
+    let mut entries: Vec<_> = entry
+        .get_dict(8, key_reader_u8, val_reader_address)?
+        .into_iter()
+        .collect();
+    entries.sort_by_key(|(index, _)| *index);
+    Ok(entries.into_iter().map(|(_, address)| address).collect())
+}
@@ -1,5 +1,7 @@
 mod collection_contract;
+mod dns_item_contract;
 mod item_contract;
 
 pub use collection_contract::*;
+pub use dns_item_contract::*;
 pub use item_contract::*;
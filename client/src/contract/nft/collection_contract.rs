@@ -2,15 +2,15 @@ use async_trait::async_trait;
 use num_bigint::BigUint;
 use num_traits::Zero;
 use strum::IntoStaticStr;
-use tonlib_core::cell::{ArcCell, BagOfCells};
+use tonlib_core::cell::{ArcCell, BagOfCells, CellBuilder, StateInit, TonCellError};
 use tonlib_core::TonAddress;
 
 use crate::contract::factory::TonContractFactory;
 use crate::contract::{
-    MapCellError, MapStackError, NftItemContract, TonContractError, TonContractInterface,
+    MapCellError, MapStackError, NftItemContract, TonContractError, TonFactoryContract,
 };
 use crate::meta::MetaDataContent;
-use crate::types::TvmStackEntry;
+use crate::types::{StackParseError, TvmStackEntry};
 
 /// Data returned by get_collection_data according to TEP-62
 #[derive(Debug, Clone)]
@@ -26,15 +26,64 @@ pub struct NftCollectionData {
     pub owner_address: TonAddress,
 }
 
+/// Royalty parameters returned by royalty_params according to TEP-66
+#[derive(Debug, Clone, PartialEq)]
+pub struct NftRoyaltyParams {
+    /// Royalty share is `numerator / denominator` of the sale price.
+    pub numerator: u16,
+    pub denominator: u16,
+    /// Address to send royalty to.
+    pub destination: TonAddress,
+}
+
+impl NftRoyaltyParams {
+    /// Decodes the result stack of `royalty_params` of the NFT collection at `address`.
+    pub fn from_stack(
+        address: &TonAddress,
+        stack: &[TvmStackEntry],
+    ) -> Result<NftRoyaltyParams, TonContractError> {
+        const ROYALTY_PARAMS_STACK_ELEMENTS: usize = 3;
+        let method: &'static str = NftCollectionMethods::RoyaltyParams.into();
+        if stack.len() != ROYALTY_PARAMS_STACK_ELEMENTS {
+            return Err(TonContractError::InvalidMethodResultStackSize {
+                method: method.to_string(),
+                address: address.clone(),
+                actual: stack.len(),
+                expected: ROYALTY_PARAMS_STACK_ELEMENTS,
+            });
+        }
+        let get_u16 = |entry: &TvmStackEntry| {
+            let value = entry.get_i64()?;
+            u16::try_from(value).map_err(|_| {
+                StackParseError::InvalidEntryValue(format!("expected uint16, found {}", value))
+            })
+        };
+        Ok(NftRoyaltyParams {
+            numerator: get_u16(&stack[0]).map_stack_error(method, address)?,
+            denominator: get_u16(&stack[1]).map_stack_error(method, address)?,
+            destination: stack[2].get_address().map_stack_error(method, address)?,
+        })
+    }
+
+    /// Computes the royalty for `sale_price`, zero if the denominator is zero.
+    pub fn royalty(&self, sale_price: &BigUint) -> BigUint {
+        if self.denominator == 0 {
+            return BigUint::zero();
+        }
+        sale_price * self.numerator / self.denominator
+    }
+}
+
 #[derive(IntoStaticStr)]
 #[strum(serialize_all = "snake_case")]
 enum NftCollectionMethods {
     GetCollectionData,
     GetNftAddressByIndex,
+    RoyaltyParams,
 }
 
 #[async_trait]
-pub trait NftCollectionContract: TonContractInterface {
+pub trait NftCollectionContract: TonFactoryContract {
     /// Returns nft collection data.
     async fn get_collection_data(&self) -> Result<NftCollectionData, TonContractError> {
         const NFT_COLLECTION_STACK_ELEMENTS: usize = 3;
@@ -66,6 +115,9 @@ pub trait NftCollectionContract: TonContractInterface {
 
     /// Gets the serial number of the NFT item of this collection and
     /// returns the address (TonAddress) of this NFT item smart contract.
+    ///
+    /// For standard collections the address can be computed offline with
+    /// [`predict_nft_item_address`].
     async fn get_nft_address_by_index(&self, index: i64) -> Result<TonAddress, TonContractError> {
         let method = NftCollectionMethods::GetNftAddressByIndex.into();
         let input_stack = vec![TvmStackEntry::Int64(index)];
@@ -84,9 +136,36 @@ pub trait NftCollectionContract: TonContractInterface {
             })
         }
     }
+
+    /// Returns royalty parameters of the collection, if it implements TEP-66.
+    async fn royalty_params(&self) -> Result<NftRoyaltyParams, TonContractError> {
+        let method: &'static str = NftCollectionMethods::RoyaltyParams.into();
+        let stack = self.run_get_method(method, Vec::new()).await?.stack;
+        NftRoyaltyParams::from_stack(self.address(), &stack)
+    }
+}
+
+impl<T> NftCollectionContract for T where T: TonFactoryContract {}
+
+/// Computes the address of an NFT item of a standard collection without calling
+/// `get_nft_address_by_index`.
+///
+/// The standard collection deploys items with `nft_item_code` (stored in the collection data)
+/// and initial data consisting of `index:uint64` followed by the collection address.
+/// Collections deriving item state init differently must use the on-chain method.
+pub fn predict_nft_item_address(
+    nft_item_code: &ArcCell,
+    collection_address: &TonAddress,
+    index: u64,
+) -> Result<TonAddress, TonCellError> {
+    let data = CellBuilder::new()
+        .store_u64(64, index)?
+        .store_address(collection_address)?
+        .build()?;
+    let hash = StateInit::create_account_id(nft_item_code, &ArcCell::new(data))?;
+    Ok(TonAddress::new(collection_address.workchain, &hash))
 }
 
-impl<T> NftCollectionContract for T where T: TonContractInterface {}
 async fn read_collection_metadata_content(
     factory: &TonContractFactory,
     collection_address: &TonAddress,
@@ -0,0 +1,195 @@
+use async_trait::async_trait;
+use num_bigint::BigUint;
+use strum::IntoStaticStr;
+use tonlib_core::TonAddress;
+
+use crate::contract::{MapStackError, TonContractError, TonContractInterface};
+use crate::types::TvmStackEntry;
+
+/// Auction state returned by `get_telemint_auction_state`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TelemintAuctionState {
+    /// Address of the current highest bidder, `None` if there are no bids yet.
+    pub bidder_address: Option<TonAddress>,
+    /// Current highest bid in nanotons.
+    pub bid: BigUint,
+    /// Unix time of the current highest bid.
+    pub bid_ts: u64,
+    /// Minimal amount of the next bid in nanotons.
+    pub min_bid: BigUint,
+    /// Unix time when the auction ends.
+    pub end_time: u64,
+}
+
+/// Auction configuration returned by `get_telemint_auction_config`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TelemintAuctionConfig {
+    /// Address receiving the auction proceeds.
+    pub beneficiary_address: TonAddress,
+    /// Minimal amount of the first bid in nanotons.
+    pub initial_min_bid: BigUint,
+    /// Bid that finishes the auction immediately, zero if not set.
+    pub max_bid: BigUint,
+    /// Minimal bid increment in percents.
+    pub min_bid_step: u64,
+    /// Auction is extended by this number of seconds if a bid arrives near its end.
+    pub min_extend_time: u64,
+    /// Auction duration in seconds.
+    pub duration: u64,
+}
+
+#[derive(IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+#[allow(clippy::enum_variant_names)]
+enum DnsItemContractMethods {
+    GetFullDomain,
+    GetTelemintTokenName,
+    GetTelemintAuctionState,
+    GetTelemintAuctionConfig,
+}
+
+/// Get-methods of TON DNS and Telemint (anonymous numbers, usernames) items.
+#[async_trait]
+pub trait DnsItemContract: TonContractInterface {
+    /// Returns the full domain of the item in human-readable form, e.g. `alice.ton`.
+    ///
+    /// The contract returns the domain as `\0`-separated labels in reverse order,
+    /// this method converts it to the dotted notation.
+    async fn get_full_domain(&self) -> Result<String, TonContractError> {
+        let method: &'static str = DnsItemContractMethods::GetFullDomain.into();
+        let stack = self.run_get_method(method, Vec::new()).await?.stack;
+        if stack.len() == 1 {
+            let raw = stack[0]
+                .get_string()
+                .map_stack_error(method, self.address())?;
+            Ok(full_domain_to_dotted(raw.as_str()))
+        } else {
+            Err(TonContractError::InvalidMethodResultStackSize {
+                method: method.to_string(),
+                address: self.address().clone(),
+                actual: stack.len(),
+                expected: 1,
+            })
+        }
+    }
+
+    /// Returns the token name of the Telemint item (e.g. the username or the number).
+    async fn get_telemint_token_name(&self) -> Result<String, TonContractError> {
+        let method: &'static str = DnsItemContractMethods::GetTelemintTokenName.into();
+        let stack = self.run_get_method(method, Vec::new()).await?.stack;
+        if stack.len() == 1 {
+            stack[0]
+                .get_string()
+                .map_stack_error(method, self.address())
+        } else {
+            Err(TonContractError::InvalidMethodResultStackSize {
+                method: method.to_string(),
+                address: self.address().clone(),
+                actual: stack.len(),
+                expected: 1,
+            })
+        }
+    }
+
+    /// Returns the state of the running auction, `None` if there is no auction.
+    async fn get_telemint_auction_state(
+        &self,
+    ) -> Result<Option<TelemintAuctionState>, TonContractError> {
+        const AUCTION_STATE_STACK_ELEMENTS: usize = 5;
+        let method: &'static str = DnsItemContractMethods::GetTelemintAuctionState.into();
+        let address = self.address().clone();
+        let stack = self.run_get_method(method, Vec::new()).await?.stack;
+        if stack.len() != AUCTION_STATE_STACK_ELEMENTS {
+            return Err(TonContractError::InvalidMethodResultStackSize {
+                method: method.to_string(),
+                address,
+                actual: stack.len(),
+                expected: AUCTION_STATE_STACK_ELEMENTS,
+            });
+        }
+        let end_time = stack[4].get_i64().map_stack_error(method, &address)? as u64;
+        // No auction: (null, 0, 0, 0, 0)
+        if stack[0] == TvmStackEntry::Null && end_time == 0 {
+            return Ok(None);
+        }
+        let bidder_address = match &stack[0] {
+            TvmStackEntry::Null => None,
+            e => {
+                let bidder = e.get_address().map_stack_error(method, &address)?;
+                // addr_none is stored until the first bid arrives
+                if bidder == TonAddress::NULL {
+                    None
+                } else {
+                    Some(bidder)
+                }
+            }
+        };
+        let bid = stack[1].get_biguint().map_stack_error(method, &address)?;
+        let bid_ts = stack[2].get_i64().map_stack_error(method, &address)? as u64;
+        let min_bid = stack[3].get_biguint().map_stack_error(method, &address)?;
+        Ok(Some(TelemintAuctionState {
+            bidder_address,
+            bid,
+            bid_ts,
+            min_bid,
+            end_time,
+        }))
+    }
+
+    /// Returns the configuration of the auction, `None` if there is no auction.
+    async fn get_telemint_auction_config(
+        &self,
+    ) -> Result<Option<TelemintAuctionConfig>, TonContractError> {
+        const AUCTION_CONFIG_STACK_ELEMENTS: usize = 6;
+        let method: &'static str = DnsItemContractMethods::GetTelemintAuctionConfig.into();
+        let address = self.address().clone();
+        let stack = self.run_get_method(method, Vec::new()).await?.stack;
+        if stack.len() != AUCTION_CONFIG_STACK_ELEMENTS {
+            return Err(TonContractError::InvalidMethodResultStackSize {
+                method: method.to_string(),
+                address,
+                actual: stack.len(),
+                expected: AUCTION_CONFIG_STACK_ELEMENTS,
+            });
+        }
+        if stack[0] == TvmStackEntry::Null {
+            return Ok(None);
+        }
+        let beneficiary_address = stack[0].get_address().map_stack_error(method, &address)?;
+        let initial_min_bid = stack[1].get_biguint().map_stack_error(method, &address)?;
+        let max_bid = stack[2].get_biguint().map_stack_error(method, &address)?;
+        let min_bid_step = stack[3].get_i64().map_stack_error(method, &address)? as u64;
+        let min_extend_time = stack[4].get_i64().map_stack_error(method, &address)? as u64;
+        let duration = stack[5].get_i64().map_stack_error(method, &address)? as u64;
+        Ok(Some(TelemintAuctionConfig {
+            beneficiary_address,
+            initial_min_bid,
+            max_bid,
+            min_bid_step,
+            min_extend_time,
+            duration,
+        }))
+    }
+}
+
+impl<T> DnsItemContract for T where T: TonContractInterface {}
+
+fn full_domain_to_dotted(raw: &str) -> String {
+    raw.split('\0')
+        .filter(|label| !label.is_empty())
+        .rev()
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::full_domain_to_dotted;
+
+    #[test]
+    fn test_full_domain_to_dotted() {
+        assert_eq!(full_domain_to_dotted("ton\0alice\0"), "alice.ton");
+        assert_eq!(full_domain_to_dotted("me\0t\0username\0"), "username.t.me");
+        assert_eq!(full_domain_to_dotted(""), "");
+    }
+}
@@ -5,9 +5,7 @@ use strum::IntoStaticStr;
 use tonlib_core::cell::{ArcCell, BagOfCells};
 use tonlib_core::TonAddress;
 
-use crate::contract::{
-    factory, MapCellError, MapStackError, TonContractError, TonContractInterface,
-};
+use crate::contract::{factory, MapCellError, MapStackError, TonContractError, TonFactoryContract};
 use crate::meta::MetaDataContent;
 use crate::types::TvmStackEntry;
 
@@ -29,6 +27,44 @@ pub struct NftItemData {
     pub individual_content: MetaDataContent,
 }
 
+/// Result of get_nft_data with the individual content cell not yet resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawNftItemData {
+    pub init: bool,
+    pub index: BigUint,
+    pub collection_address: TonAddress,
+    pub owner_address: TonAddress,
+    /// Individual content as returned by the item, in semi-chain layout it has to be
+    /// completed with `get_nft_content` of the collection.
+    pub individual_content: ArcCell,
+}
+
+impl RawNftItemData {
+    /// Decodes the result stack of `get_nft_data` of the NFT item at `address`.
+    pub fn from_stack(
+        address: &TonAddress,
+        stack: &[TvmStackEntry],
+    ) -> Result<RawNftItemData, TonContractError> {
+        const NFT_DATA_STACK_ELEMENTS: usize = 5;
+        let method: &'static str = NftItemContractMethods::GetNftData.into();
+        if stack.len() != NFT_DATA_STACK_ELEMENTS {
+            return Err(TonContractError::InvalidMethodResultStackSize {
+                method: method.to_string(),
+                address: address.clone(),
+                actual: stack.len(),
+                expected: NFT_DATA_STACK_ELEMENTS,
+            });
+        }
+        Ok(RawNftItemData {
+            init: stack[0].get_bool().map_stack_error(method, address)?,
+            index: stack[1].get_biguint().map_stack_error(method, address)?,
+            collection_address: stack[2].get_address().map_stack_error(method, address)?,
+            owner_address: stack[3].get_address().map_stack_error(method, address)?,
+            individual_content: stack[4].get_cell().map_stack_error(method, address)?,
+        })
+    }
+}
+
 #[derive(IntoStaticStr)]
 #[strum(serialize_all = "snake_case")]
 enum NftItemContractMethods {
@@ -37,44 +73,26 @@ enum NftItemContractMethods {
 }
 
 #[async_trait]
-pub trait NftItemContract: TonContractInterface {
+pub trait NftItemContract: TonFactoryContract {
     async fn get_nft_data(&self) -> Result<NftItemData, TonContractError> {
-        let method = NftItemContractMethods::GetNftData.into();
-        const NFT_DATA_STACK_ELEMENTS: usize = 5;
-        let address = self.address().clone();
-
+        let method: &'static str = NftItemContractMethods::GetNftData.into();
         let stack = self.run_get_method(method, Vec::new()).await?.stack;
-        if stack.len() == NFT_DATA_STACK_ELEMENTS {
-            let init = stack[0].get_bool().map_stack_error(method, &address)?;
-            let index = stack[1].get_biguint().map_stack_error(method, &address)?;
-            let collection_address = stack[2].get_address().map_stack_error(method, &address)?;
-            let owner_address = stack[3].get_address().map_stack_error(method, &address)?;
-            let cell = stack[4].get_cell().map_stack_error(method, &address)?;
-
-            let individual_content = read_item_metadata_content(
-                self.factory(),
-                &index.clone(),
-                &collection_address.clone(),
-                &address,
-                cell,
-            )
-            .await?;
-
-            Ok(NftItemData {
-                init,
-                index,
-                collection_address,
-                owner_address,
-                individual_content,
-            })
-        } else {
-            Err(TonContractError::InvalidMethodResultStackSize {
-                method: method.to_string(),
-                address: self.address().clone(),
-                actual: stack.len(),
-                expected: NFT_DATA_STACK_ELEMENTS,
-            })
-        }
+        let raw = RawNftItemData::from_stack(self.address(), &stack)?;
+        let individual_content = read_item_metadata_content(
+            self.factory(),
+            &raw.index,
+            &raw.collection_address,
+            self.address(),
+            raw.individual_content,
+        )
+        .await?;
+        Ok(NftItemData {
+            init: raw.init,
+            index: raw.index,
+            collection_address: raw.collection_address,
+            owner_address: raw.owner_address,
+            individual_content,
+        })
     }
 
     /// Gets the serial number of the NFT item of this collection and
@@ -117,7 +135,7 @@ pub trait NftItemContract: TonContractInterface {
     }
 }
 
-impl<T> NftItemContract for T where T: TonContractInterface {}
+impl<T> NftItemContract for T where T: TonFactoryContract {}
 
 async fn read_item_metadata_content(
     factory: &TonContractFactory,
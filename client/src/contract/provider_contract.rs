@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tonlib_core::TonAddress;
+
+use crate::client::{TonClientError, TonProvider};
+use crate::contract::{TonContractError, TonContractInterface};
+use crate::tl::{InternalTransactionId, RawFullAccountState};
+use crate::types::{TonMethodId, TvmStackEntry, TvmSuccess};
+
+/// Contract whose account state and get-methods are served by a `TonProvider`, so that
+/// the contract wrappers work with any provider, e.g. `HttpProvider` or, in unit tests,
+/// `MockTonClient`.
+///
+/// ```ignore
+/// let contract = ProviderContract::new(Arc::new(mock_client), &master_address);
+/// let data = contract.get_jetton_data().await?;
+/// ```
+///
+/// `TonProvider` has no states by transaction, so `get_account_state_by_transaction` fails.
+#[derive(Clone)]
+pub struct ProviderContract {
+    provider: Arc<dyn TonProvider>,
+    address: TonAddress,
+}
+
+impl ProviderContract {
+    pub fn new(provider: Arc<dyn TonProvider>, address: &TonAddress) -> ProviderContract {
+        ProviderContract {
+            provider,
+            address: address.clone(),
+        }
+    }
+
+    pub fn provider(&self) -> &Arc<dyn TonProvider> {
+        &self.provider
+    }
+}
+
+#[async_trait]
+impl TonContractInterface for ProviderContract {
+    fn address(&self) -> &TonAddress {
+        &self.address
+    }
+
+    async fn get_account_state(&self) -> Result<Arc<RawFullAccountState>, TonContractError> {
+        Ok(Arc::new(
+            self.provider.get_raw_account_state(&self.address).await?,
+        ))
+    }
+
+    async fn get_account_state_by_transaction(
+        &self,
+        _tx_id: &InternalTransactionId,
+    ) -> Result<RawFullAccountState, TonContractError> {
+        Err(TonClientError::InternalError(
+            "Account states by transaction aren't served by TonProvider".to_string(),
+        )
+        .into())
+    }
+
+    async fn run_get_method<M, S>(
+        &self,
+        method: M,
+        stack: S,
+    ) -> Result<TvmSuccess, TonContractError>
+    where
+        M: Into<TonMethodId> + Send + Copy,
+        S: AsRef<[TvmStackEntry]> + Send,
+    {
+        self.provider
+            .run_get_method(&self.address, &method.into(), stack.as_ref())
+            .await
+    }
+}
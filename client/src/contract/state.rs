@@ -5,8 +5,10 @@ use tonlib_core::cell::Cell;
 use tonlib_core::TonAddress;
 
 use crate::client::{TonClientError, TonClientInterface};
-use crate::contract::{TonContractError, TonContractFactory, TonContractInterface};
-use crate::emulator::{TvmEmulator, TvmEmulatorC7Builder};
+use crate::contract::{
+    TonContractError, TonContractFactory, TonContractInterface, TonFactoryContract,
+};
+use crate::emulator::{TvmEmulator, TvmEmulatorC7Builder, TvmEmulatorError};
 use crate::tl::{InternalTransactionId, RawFullAccountState};
 use crate::types::{TonMethodId, TvmMsgSuccess, TvmStackEntry, TvmSuccess};
 
@@ -111,7 +113,9 @@ impl TonContractState {
                 let data = state.data.as_slice();
                 let mut emulator = TvmEmulator::new(code, data)?;
                 emulator.set_c7(&c7)?;
-                emulator.set_libraries(libs.dict_boc.as_slice())?;
+                if !libs.dict_boc.is_empty() {
+                    emulator.set_libraries(libs.dict_boc.as_slice())?;
+                }
                 let run_result = emulator.run_get_method(static_method_id, static_stack);
                 run_result
             })
@@ -131,19 +135,45 @@ impl TonContractState {
         message: Cell,
         amount: u64,
     ) -> Result<TvmMsgSuccess, TonContractError> {
+        self.emulate_message(move |emulator| emulator.send_internal_message(message, amount))
+            .await
+    }
+
+    /// Emulates processing of an external message, e.g. a signed wallet message, by the
+    /// contract in this state, to predict its outcome and gas usage before sending it.
+    pub async fn emulate_external_message(
+        &self,
+        message: Cell,
+    ) -> Result<TvmMsgSuccess, TonContractError> {
+        self.emulate_message(move |emulator| emulator.send_external_message(message))
+            .await
+    }
+
+    async fn emulate_message<F>(&self, send: F) -> Result<TvmMsgSuccess, TonContractError>
+    where
+        F: FnOnce(&mut TvmEmulator) -> Result<TvmMsgSuccess, TvmEmulatorError> + Send + 'static,
+    {
         let state = self.account_state.clone();
         let c7 = TvmEmulatorC7Builder::new(
             &self.address,
             self.factory.get_config_cell_serial().await?,
-            0,
+            state.balance.max(0) as u64,
         )
         .build();
+        let libs = self
+            .factory
+            .library_provider()
+            .get_contract_libraries(&self.address, &self.account_state)
+            .await?;
         let run_result = tokio::task::spawn_blocking(move || {
             let code = state.code.as_slice();
             let data = state.data.as_slice();
             let mut emulator = TvmEmulator::new(code, data)?;
             emulator.set_c7(&c7)?;
-            emulator.send_internal_message(message, amount)
+            if !libs.dict_boc.is_empty() {
+                emulator.set_libraries(libs.dict_boc.as_slice())?;
+            }
+            send(&mut emulator)
         })
         .await
         .map_err(|e| TonContractError::InternalError(e.to_string()))?
@@ -167,7 +197,7 @@ impl TonContractState {
         let transaction_id = &self.account_state.last_transaction_id;
 
         let maybe_state = self
-            .factory()
+            .factory
             .get_smc_state_by_transaction(address, transaction_id)
             .await;
         // this fallback is not necessary
@@ -195,28 +225,17 @@ impl TonContractState {
             .smc_run_get_method(state.id, &method.into(), &stack_tl)
             .await?;
 
-        let stack = run_result
-            .stack
-            .elements
-            .iter()
-            .map(|e| e.try_into())
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| TonContractError::TvmStackParseError {
+        let result = TvmSuccess::try_from(&run_result).map_err(|e| {
+            TonContractError::TvmStackParseError {
                 method: method.into(),
                 address: self.address().clone(),
                 error: e,
-            })?;
-        let result = TvmSuccess {
-            vm_log: None,
-            vm_exit_code: run_result.exit_code,
-            stack,
-            missing_library: None,
-            gas_used: run_result.gas_used as i32,
-        };
+            }
+        })?;
         Self::raise_exit_error(self.address(), &method.into(), result)
     }
 
-    fn raise_exit_error(
+    pub(crate) fn raise_exit_error(
         address: &TonAddress,
         method: &TonMethodId,
         run_result: TvmSuccess,
@@ -237,12 +256,14 @@ impl TonContractState {
     }
 }
 
-#[async_trait]
-impl TonContractInterface for TonContractState {
+impl TonFactoryContract for TonContractState {
     fn factory(&self) -> &TonContractFactory {
         &self.factory
     }
+}
 
+#[async_trait]
+impl TonContractInterface for TonContractState {
     fn address(&self) -> &TonAddress {
         &self.address
     }
@@ -1,3 +1,7 @@
+mod lockup_contract;
+mod seqno_guard;
 mod wallet_contract;
 
+pub use lockup_contract::*;
+pub use seqno_guard::*;
 pub use wallet_contract::*;
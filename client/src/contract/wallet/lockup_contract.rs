@@ -0,0 +1,220 @@
+use async_trait::async_trait;
+use num_bigint::BigUint;
+use strum::IntoStaticStr;
+use tonlib_core::cell::dict::predefined_readers::{key_reader_u32, val_reader_coins};
+use tonlib_core::cell::{BagOfCells, CellParser, TonCellError};
+
+use crate::contract::{MapCellError, MapStackError, TonContractError, TonContractInterface};
+use crate::types::{StackParseError, TvmStackEntry};
+
+/// Balances returned by `get_balances` / `get_balances_at` of a lockup wallet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockupBalances {
+    /// Total balance of the wallet in nanotons.
+    pub balance: BigUint,
+    /// Part of the balance that may only be sent to allowed destinations.
+    pub restricted: BigUint,
+    /// Part of the balance that can't be spent yet.
+    pub locked: BigUint,
+}
+
+impl LockupBalances {
+    /// Returns the part of the balance that can be spent without restrictions.
+    pub fn liquid(&self) -> BigUint {
+        let unavailable = &self.restricted + &self.locked;
+        if self.balance > unavailable {
+            &self.balance - unavailable
+        } else {
+            BigUint::default()
+        }
+    }
+}
+
+/// Lock schedule of a lockup wallet, entries are `(unlock_time, amount)` sorted by unlock time.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LockupSchedule {
+    pub restricted: Vec<(u32, BigUint)>,
+    pub locked: Vec<(u32, BigUint)>,
+}
+
+impl LockupSchedule {
+    /// Returns the restricted amount not yet released at `time`.
+    pub fn restricted_at(&self, time: u32) -> BigUint {
+        sum_after(&self.restricted, time)
+    }
+
+    /// Returns the locked amount not yet released at `time`.
+    pub fn locked_at(&self, time: u32) -> BigUint {
+        sum_after(&self.locked, time)
+    }
+}
+
+fn sum_after(entries: &[(u32, BigUint)], time: u32) -> BigUint {
+    entries
+        .iter()
+        .filter(|(unlock_time, _)| *unlock_time > time)
+        .map(|(_, amount)| amount)
+        .sum()
+}
+
+const LOCKUP_BALANCES_STACK_ELEMENTS: usize = 3;
+
+#[derive(IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+enum LockupWalletMethods {
+    GetBalances,
+    GetBalancesAt,
+}
+
+/// Get-methods of the lockup wallet (`lockup-wallet-v1`) used for vested allocations.
+#[async_trait]
+pub trait LockupWalletContract: TonContractInterface {
+    /// Returns balances at the current time of the network.
+    async fn get_balances(&self) -> Result<LockupBalances, TonContractError> {
+        let method: &str = LockupWalletMethods::GetBalances.into();
+        let stack = self.run_get_method(method, Vec::new()).await?.stack;
+        if stack.len() != LOCKUP_BALANCES_STACK_ELEMENTS {
+            return Err(TonContractError::InvalidMethodResultStackSize {
+                method: method.to_string(),
+                address: self.address().clone(),
+                actual: stack.len(),
+                expected: LOCKUP_BALANCES_STACK_ELEMENTS,
+            });
+        }
+        read_balances(&stack).map_stack_error(method, self.address())
+    }
+
+    /// Returns balances at unix time `time`.
+    async fn get_balances_at(&self, time: u32) -> Result<LockupBalances, TonContractError> {
+        let method: &str = LockupWalletMethods::GetBalancesAt.into();
+        let input_stack = vec![TvmStackEntry::Int64(time as i64)];
+        let stack = self.run_get_method(method, &input_stack).await?.stack;
+        if stack.len() != LOCKUP_BALANCES_STACK_ELEMENTS {
+            return Err(TonContractError::InvalidMethodResultStackSize {
+                method: method.to_string(),
+                address: self.address().clone(),
+                actual: stack.len(),
+                expected: LOCKUP_BALANCES_STACK_ELEMENTS,
+            });
+        }
+        read_balances(&stack).map_stack_error(method, self.address())
+    }
+
+    /// Returns the balance that can be spent without restrictions at unix time `time`.
+    async fn get_liquid_balance_at(&self, time: u32) -> Result<BigUint, TonContractError> {
+        Ok(self.get_balances_at(time).await?.liquid())
+    }
+
+    /// Decodes the lock schedule from the contract data.
+    ///
+    /// The data layout, as stored by `store_data` of `lockup-wallet.fc`, is
+    /// `seqno:uint32 subwallet_id:uint32 public_key:uint256 config_public_key:uint256
+    /// allowed_destinations:(Maybe ^Cell) total_locked_value:Grams
+    /// locked:(HashmapE 32 Grams) total_restricted_value:Grams restricted:(HashmapE 32 Grams)`,
+    /// the schedules are keyed by unlock time.
+    async fn get_lock_schedule(&self) -> Result<LockupSchedule, TonContractError> {
+        const METHOD: &str = "get_lock_schedule";
+        let state = self.get_account_state().await?;
+        let boc = BagOfCells::parse(&state.data).map_cell_error(METHOD, self.address())?;
+        let data = boc.single_root().map_cell_error(METHOD, self.address())?;
+        let mut parser = data.parser();
+        let schedule = read_schedule(&mut parser).map_cell_error(METHOD, self.address())?;
+        Ok(schedule)
+    }
+}
+
+impl<T> LockupWalletContract for T where T: TonContractInterface {}
+
+fn read_balances(stack: &[TvmStackEntry]) -> Result<LockupBalances, StackParseError> {
+    Ok(LockupBalances {
+        balance: stack[0].get_biguint()?,
+        restricted: stack[1].get_biguint()?,
+        locked: stack[2].get_biguint()?,
+    })
+}
+
+fn read_schedule(parser: &mut CellParser) -> Result<LockupSchedule, TonCellError> {
+    let _seqno = parser.load_u32(32)?;
+    let _subwallet_id = parser.load_u32(32)?;
+    parser.skip_bits(256)?; // public_key
+    parser.skip_bits(256)?; // config_public_key
+    let _allowed_destinations = parser.load_maybe_cell_ref()?;
+    let _total_locked_value = parser.load_coins()?;
+    let locked = read_unlock_dict(parser)?;
+    let _total_restricted_value = parser.load_coins()?;
+    let restricted = read_unlock_dict(parser)?;
+    Ok(LockupSchedule { restricted, locked })
+}
+
+fn read_unlock_dict(parser: &mut CellParser) -> Result<Vec<(u32, BigUint)>, TonCellError> {
+    let mut entries: Vec<_> = parser
+        .load_maybe_dict(32, key_reader_u32, val_reader_coins)?
+        .into_iter()
+        .collect();
+    entries.sort_by_key(|(unlock_time, _)| *unlock_time);
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use num_bigint::BigUint;
+    use tonlib_core::cell::dict::predefined_writers::val_writer_coins;
+    use tonlib_core::cell::{CellBuilder, TonCellError};
+
+    use super::{read_schedule, LockupBalances};
+
+    #[test]
+    fn test_lockup_schedule_decoding() -> Result<(), TonCellError> {
+        let locked = HashMap::from([
+            (2_000u32, BigUint::from(300u32)),
+            (1_000u32, BigUint::from(200u32)),
+        ]);
+        let restricted = HashMap::from([(1_500u32, BigUint::from(100u32))]);
+        let dict = |entries| -> Result<_, TonCellError> {
+            let mut builder = CellBuilder::new();
+            builder.store_dict(32, val_writer_coins, entries)?;
+            builder.build()
+        };
+        // Allowed destinations are a prefix dictionary of addresses, only skipped here
+        let allowed_destinations = CellBuilder::new().store_u32(32, 0xdead)?.build()?;
+        // Stored in the order of `store_data` of lockup-wallet.fc
+        let data = CellBuilder::new()
+            .store_u32(32, 5)?
+            .store_u32(32, 698983191)?
+            .store_uint(256, &BigUint::from(1u32))?
+            .store_uint(256, &BigUint::from(2u32))?
+            .store_bit(true)?
+            .store_child(allowed_destinations)?
+            .store_coins(&BigUint::from(500u32))?
+            .store_bit(true)?
+            .store_child(dict(locked)?)?
+            .store_coins(&BigUint::from(100u32))?
+            .store_bit(true)?
+            .store_child(dict(restricted)?)?
+            .build()?;
+
+        let schedule = read_schedule(&mut data.parser())?;
+        assert_eq!(schedule.restricted, vec![(1_500, BigUint::from(100u32))]);
+        assert_eq!(schedule.restricted_at(1_500), BigUint::from(0u32));
+        assert_eq!(
+            schedule.locked,
+            vec![
+                (1_000, BigUint::from(200u32)),
+                (2_000, BigUint::from(300u32))
+            ]
+        );
+        assert_eq!(schedule.locked_at(999), BigUint::from(500u32));
+        assert_eq!(schedule.locked_at(1_000), BigUint::from(300u32));
+        assert_eq!(schedule.locked_at(2_000), BigUint::from(0u32));
+
+        let balances = LockupBalances {
+            balance: BigUint::from(1_000u32),
+            restricted: BigUint::from(100u32),
+            locked: schedule.locked_at(999),
+        };
+        assert_eq!(balances.liquid(), BigUint::from(400u32));
+        Ok(())
+    }
+}
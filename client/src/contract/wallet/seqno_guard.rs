@@ -0,0 +1,301 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use dashmap::DashMap;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use tonlib_core::TonAddress;
+
+use crate::client::TonProvider;
+use crate::contract::{MapStackError, TonContractError};
+use crate::types::TonMethodId;
+
+pub const DEFAULT_SEQNO_TIMEOUT: Duration = Duration::from_secs(60);
+pub const DEFAULT_SEQNO_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Serializes sends from the same wallet, so that concurrent tasks never sign messages
+/// with the same seqno.
+///
+/// `send` holds a lock of the wallet address while it reads the current seqno, builds and
+/// sends the message and waits until the seqno of the wallet advances. The seqno is read again
+/// only after a new masterchain block, every `poll_interval` at most. Sends of other
+/// processes using the same wallet aren't serialized.
+#[derive(Clone)]
+pub struct SeqnoGuard<P> {
+    provider: P,
+    locks: Arc<DashMap<TonAddress, Arc<Mutex<()>>>>,
+    timeout: Duration,
+    poll_interval: Duration,
+}
+
+impl<P: TonProvider> SeqnoGuard<P> {
+    pub fn new(provider: P) -> SeqnoGuard<P> {
+        SeqnoGuard {
+            provider,
+            locks: Arc::new(DashMap::new()),
+            timeout: DEFAULT_SEQNO_TIMEOUT,
+            poll_interval: DEFAULT_SEQNO_POLL_INTERVAL,
+        }
+    }
+
+    /// Limits the time of waiting for the seqno to advance after sending. To know for sure
+    /// that a timed out message will never be executed, use a timeout exceeding the time
+    /// until `valid_until` of the message.
+    pub fn with_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_poll_interval(&mut self, poll_interval: Duration) -> &mut Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    /// Returns the seqno of the wallet at the latest block, 0 if it's not deployed yet.
+    pub async fn seqno(&self, address: &TonAddress) -> Result<u32, TonContractError> {
+        let state = self.provider.get_raw_account_state(address).await?;
+        if state.code.is_empty() {
+            return Ok(0);
+        }
+        let method: &'static str = "seqno";
+        let result = self
+            .provider
+            .run_get_method(address, &TonMethodId::from(method), &[])
+            .await?;
+        if result.stack.len() != 1 {
+            return Err(TonContractError::InvalidMethodResultStackSize {
+                method: method.to_string(),
+                address: address.clone(),
+                actual: result.stack.len(),
+                expected: 1,
+            });
+        }
+        let seqno = result.stack[0].get_i64().map_stack_error(method, address)?;
+        Ok(seqno as u32)
+    }
+
+    /// Calls `send` with the current seqno of the wallet at `address` and waits until the seqno
+    /// advances, returning the result of `send`.
+    ///
+    /// Other sends from the wallet wait until this one completes. If the seqno doesn't advance
+    /// within the timeout, fails with `TonContractError::SeqnoTimeout` and lets the next send
+    /// read the seqno again. Errors of `send` are returned without waiting.
+    pub async fn send<F, Fut, T>(
+        &self,
+        address: &TonAddress,
+        send: F,
+    ) -> Result<T, TonContractError>
+    where
+        F: FnOnce(u32) -> Fut,
+        Fut: Future<Output = Result<T, TonContractError>>,
+    {
+        let lock = AddressLock {
+            locks: &self.locks,
+            address,
+            lock: self.locks.entry(address.clone()).or_default().clone(),
+        };
+        let _guard = lock.lock.lock().await;
+        self.send_locked(address, send).await
+    }
+
+    async fn send_locked<F, Fut, T>(
+        &self,
+        address: &TonAddress,
+        send: F,
+    ) -> Result<T, TonContractError>
+    where
+        F: FnOnce(u32) -> Fut,
+        Fut: Future<Output = Result<T, TonContractError>>,
+    {
+        let seqno = self.seqno(address).await?;
+        let mut mc_seqno = self.provider.get_masterchain_info().await?.last.seqno;
+        let result = send(seqno).await?;
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            tokio::time::sleep_until((Instant::now() + self.poll_interval).min(deadline)).await;
+            // The message is sent, so errors are only logged until the deadline
+            match self.advanced(address, seqno, mc_seqno).await {
+                Ok((last, true)) => {
+                    log::trace!("Seqno {} of {} advanced in block {}", seqno, address, last);
+                    return Ok(result);
+                }
+                Ok((last, false)) => mc_seqno = last,
+                Err(e) => log::warn!("Failed to check seqno {} of {}: {}", seqno, address, e),
+            }
+            if Instant::now() >= deadline {
+                return Err(TonContractError::SeqnoTimeout {
+                    address: address.clone(),
+                    seqno,
+                    timeout: self.timeout,
+                });
+            }
+        }
+    }
+
+    /// Returns the last masterchain seqno and whether the seqno of the wallet exceeds `seqno`,
+    /// which is only checked if the masterchain advanced past `mc_seqno`.
+    async fn advanced(
+        &self,
+        address: &TonAddress,
+        seqno: u32,
+        mc_seqno: i32,
+    ) -> Result<(i32, bool), TonContractError> {
+        let last = self.provider.get_masterchain_info().await?.last.seqno;
+        if last == mc_seqno {
+            return Ok((last, false));
+        }
+        Ok((last, self.seqno(address).await? > seqno))
+    }
+}
+
+/// Lock of an address used by a send, removed from `locks` once no other send uses it,
+/// also if the send is cancelled.
+struct AddressLock<'a> {
+    locks: &'a DashMap<TonAddress, Arc<Mutex<()>>>,
+    address: &'a TonAddress,
+    lock: Arc<Mutex<()>>,
+}
+
+impl Drop for AddressLock<'_> {
+    fn drop(&mut self) {
+        // Referenced only by the map and this send
+        self.locks
+            .remove_if(self.address, |_, lock| Arc::strong_count(lock) == 2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tonlib_core::TonAddress;
+
+    use super::SeqnoGuard;
+    use crate::client::{MockTonClient, TonFixture, TonProvider};
+    use crate::contract::{ProviderContract, TonContractError, TonWalletContract};
+    use crate::tl::{
+        AccountAddress, BlockIdExt, BlocksMasterchainInfo, InternalTransactionId,
+        RawExtMessageInfo, RawFullAccountState, SmcInfo, SmcRunResult, TonFunction, TonResult,
+        TvmNumber, TvmStack, TvmStackEntry,
+    };
+    use crate::types::TonMethodId;
+
+    fn block_id(seqno: i32) -> BlockIdExt {
+        BlockIdExt {
+            workchain: -1,
+            shard: i64::MIN,
+            seqno,
+            root_hash: vec![0; 32],
+            file_hash: vec![0; 32],
+        }
+    }
+
+    /// Fixtures of a wallet at `address` whose seqno reads `seqnos` and of masterchain blocks
+    /// `mc_seqnos`, the last ones are repeated once used up.
+    fn wallet_fixtures(address: &TonAddress, seqnos: &[u32], mc_seqnos: &[i32]) -> Vec<TonFixture> {
+        let account_address = AccountAddress {
+            account_address: address.to_hex(),
+        };
+        let mut fixtures = vec![
+            TonFixture {
+                function: TonFunction::RawGetAccountState {
+                    account_address: account_address.clone(),
+                },
+                result: TonResult::RawFullAccountState(RawFullAccountState {
+                    balance: 1_000_000_000,
+                    code: vec![1],
+                    data: vec![1],
+                    last_transaction_id: InternalTransactionId {
+                        lt: 0,
+                        hash: vec![0; 32],
+                    },
+                    block_id: block_id(0),
+                    frozen_hash: vec![],
+                    sync_utime: 0,
+                }),
+            },
+            TonFixture {
+                function: TonFunction::SmcLoad { account_address },
+                result: TonResult::SmcInfo(SmcInfo { id: 1 }),
+            },
+        ];
+        fixtures.extend(seqnos.iter().map(|seqno| TonFixture {
+            function: TonFunction::SmcRunGetMethod {
+                id: 1,
+                method: (&TonMethodId::from("seqno")).into(),
+                stack: vec![],
+            },
+            result: TonResult::SmcRunResult(SmcRunResult {
+                gas_used: 100,
+                stack: TvmStack {
+                    elements: vec![TvmStackEntry::Number {
+                        number: TvmNumber {
+                            number: seqno.to_string(),
+                        },
+                    }],
+                },
+                exit_code: 0,
+            }),
+        }));
+        fixtures.extend(mc_seqnos.iter().map(|mc_seqno| TonFixture {
+            function: TonFunction::BlocksGetMasterchainInfo {},
+            result: TonResult::BlocksMasterchainInfo(BlocksMasterchainInfo {
+                last: block_id(*mc_seqno),
+                state_root_hash: vec![0; 32],
+                init: block_id(0),
+            }),
+        }));
+        fixtures.extend((0..2u8).map(|seqno| TonFixture {
+            function: TonFunction::RawSendMessageReturnHash { body: vec![seqno] },
+            result: TonResult::RawExtMessageInfo(RawExtMessageInfo { hash: vec![0; 32] }),
+        }));
+        fixtures
+    }
+
+    #[tokio::test]
+    async fn test_seqno_guard_serializes_sends() {
+        let address = TonAddress::new(0, &[1; 32]);
+        // Each send reads the seqno before sending and once the masterchain advances
+        let fixtures = wallet_fixtures(&address, &[0, 1, 1, 2, 2], &[1, 2, 2, 3]);
+        let client = Arc::new(MockTonClient::new(fixtures));
+        let mut guard = SeqnoGuard::new(client.clone());
+        guard
+            .with_poll_interval(Duration::from_millis(1))
+            .with_timeout(Duration::from_millis(50));
+        let send = |seqno: u32| {
+            let client = client.clone();
+            async move {
+                client.send_raw_message_return_hash(&[seqno as u8]).await?;
+                Ok::<_, TonContractError>(seqno)
+            }
+        };
+        let (first, second) = tokio::join!(guard.send(&address, send), guard.send(&address, send));
+        let mut seqnos = vec![first.unwrap(), second.unwrap()];
+        seqnos.sort();
+        assert_eq!(seqnos, vec![0, 1]);
+        let wallet = ProviderContract::new(client.clone(), &address);
+        assert_eq!(wallet.seqno().await.unwrap(), 2);
+        assert!(client.unused_fixtures().is_empty());
+
+        // A message which is never executed times out
+        let result = guard.send(&address, |seqno| async move { Ok(seqno) }).await;
+        assert!(matches!(
+            result,
+            Err(TonContractError::SeqnoTimeout { seqno: 2, .. })
+        ));
+        assert!(guard.locks.is_empty());
+
+        // A cancelled send releases the lock of the address
+        let pending = guard.send(&address, |_| std::future::pending::<Result<(), _>>());
+        assert!(tokio::time::timeout(Duration::from_millis(10), pending)
+            .await
+            .is_err());
+        assert!(guard.locks.is_empty());
+    }
+}
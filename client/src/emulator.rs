@@ -83,6 +83,33 @@ impl TvmEmulator {
         Ok(ton_contract_emulator)
     }
 
+    /// Creates an emulator of a contract with `code` and `data`, e.g. taken from a fetched
+    /// account state.
+    pub fn from_cells(code: &Cell, data: &Cell) -> Result<TvmEmulator, TvmEmulatorError> {
+        let code = BagOfCells::from_root(code.clone()).serialize(false)?;
+        let data = BagOfCells::from_root(data.clone()).serialize(false)?;
+        Self::new(&code, &data)
+    }
+
+    /// Runs a get-method of a contract with `code` and `data` locally, without creating
+    /// the emulator explicitly.
+    ///
+    /// `c7` is required by get-methods depending on the address of the contract,
+    /// the blockchain config or the current time.
+    pub fn execute_get_method(
+        code: &Cell,
+        data: &Cell,
+        c7: Option<&TvmEmulatorC7>,
+        method: &TonMethodId,
+        stack: &[TvmStackEntry],
+    ) -> Result<TvmSuccess, TvmEmulatorError> {
+        let mut emulator = Self::from_cells(code, data)?;
+        if let Some(c7) = c7 {
+            emulator.set_c7(c7)?;
+        }
+        emulator.run_get_method(method, stack)
+    }
+
     pub fn set_c7(&mut self, c7: &TvmEmulatorC7) -> Result<&mut Self, TvmEmulatorError> {
         let addr_str = c7.address.to_hex();
         let hex_str = hex::encode(c7.seed);
@@ -1,3 +1,4 @@
+pub mod address_book;
 pub mod client;
 pub mod config;
 pub mod contract;
@@ -1,21 +1,29 @@
 pub use error::*;
 pub use ipfs_loader::*;
 pub use loader::*;
+pub use resolver::*;
 use serde_json::Value;
 use tonlib_core::cell::{ArcCell, BagOfCells, TonCellError};
 use tonlib_core::TonHash;
 mod error;
 mod ipfs_loader;
 mod loader;
+mod resolver;
 
 use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use lazy_static::lazy_static;
 use serde::de::DeserializeOwned;
 use sha2::{Digest, Sha256};
+use tokio_retry::RetryIf;
 use tonlib_core::cell::dict::SnakeFormatDict;
 use tonlib_core::types::ZERO_HASH;
+
+use crate::client::RetryStrategy;
+
 struct MetaDataField {
     pub(crate) key: TonHash,
 }
@@ -99,12 +107,19 @@ impl MetaDataContent {
     }
 }
 
+/// Default limit of the time of loading off-chain metadata, per attempt.
+pub const DEFAULT_META_LOADER_TIMEOUT: Duration = Duration::from_secs(30);
+/// Default limit of the size of off-chain metadata.
+pub const DEFAULT_META_LOADER_MAX_SIZE: usize = 4 * 1024 * 1024;
+
 pub struct MetaLoader<MetaData>
 where
     MetaData: DeserializeOwned,
 {
-    http_client: reqwest::Client,
-    ipfs_loader: IpfsLoader,
+    resolvers: Vec<Arc<dyn ContentResolver>>,
+    timeout: Option<Duration>,
+    max_size: usize,
+    retry_strategy: Option<RetryStrategy>,
     meta_data_marker: std::marker::PhantomData<MetaData>,
 }
 pub type JettonMetaLoader = MetaLoader<JettonMetaData>;
@@ -119,47 +134,100 @@ where
         ipfs_loader_config: &IpfsLoaderConfig,
     ) -> Result<MetaLoader<MetaData>, MetaLoaderError> {
         let http_client = reqwest::Client::builder().build()?;
-        let ipfs_loader = IpfsLoader::new(ipfs_loader_config)?; // Replace with actual initialization
+        let ipfs_loader = IpfsLoader::new(ipfs_loader_config)?;
         Ok(MetaLoader {
-            http_client,
-            ipfs_loader,
+            resolvers: vec![
+                Arc::new(HttpResolver::new(http_client)),
+                Arc::new(ipfs_loader),
+            ],
+            timeout: Some(DEFAULT_META_LOADER_TIMEOUT),
+            max_size: DEFAULT_META_LOADER_MAX_SIZE,
+            retry_strategy: None,
             meta_data_marker: std::marker::PhantomData,
         })
     }
 
     #[allow(clippy::should_implement_trait)]
     pub fn default() -> Result<MetaLoader<MetaData>, MetaLoaderError> {
-        let http_client = reqwest::Client::builder().build()?;
-        let ipfs_loader = IpfsLoader::new(&IpfsLoaderConfig::default())?; // Replace with actual initialization
-        Ok(MetaLoader {
-            http_client,
-            ipfs_loader,
-            meta_data_marker: std::marker::PhantomData,
-        })
+        Self::new(&IpfsLoaderConfig::default())
+    }
+
+    /// Limits the time of every attempt to load metadata, `None` for no limit.
+    pub fn with_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_max_size(&mut self, max_size: usize) -> &mut Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Retries loading failed with an error accepted by `MetaLoaderError::is_retryable`
+    /// with the intervals of `retry_strategy`. Its `retryable_codes` aren't used.
+    pub fn with_retry_strategy(&mut self, retry_strategy: &RetryStrategy) -> &mut Self {
+        self.retry_strategy = Some(retry_strategy.clone());
+        self
+    }
+
+    /// Adds a resolver tried before the ones added earlier and the built-in resolvers
+    /// of `http(s)://` and `ipfs://` URIs.
+    pub fn with_resolver(&mut self, resolver: Arc<dyn ContentResolver>) -> &mut Self {
+        self.resolvers.insert(0, resolver);
+        self
     }
 
     pub async fn load_meta_from_uri(&self, uri: &str) -> Result<MetaData, MetaLoaderError> {
         log::trace!("Downloading metadata from {}", uri);
-        let meta_str: String = if uri.starts_with("ipfs://") {
-            let path: String = uri.chars().skip(7).collect();
-            self.ipfs_loader.load_utf8_lossy(path.as_str()).await?
-        } else {
-            let resp = self.http_client.get(uri).send().await?;
-            if resp.status().is_success() {
-                resp.text().await?
-            } else {
-                return Err(MetaLoaderError::LoadMetaDataFailed {
-                    uri: uri.to_string(),
-                    status: resp.status(),
-                });
-            }
-        };
-
-        // Deserialize using the original meta_str
+        let content = self.load_content(uri).await?;
+        let meta_str = String::from_utf8_lossy(&content);
         let meta: MetaData = serde_json::from_str(&meta_str)?;
-
         Ok(meta)
     }
+
+    /// Loads the raw content of `uri` by the first resolver supporting it.
+    pub async fn load_content(&self, uri: &str) -> Result<Vec<u8>, MetaLoaderError> {
+        let resolver = self
+            .resolvers
+            .iter()
+            .find(|r| r.supports(uri))
+            .ok_or_else(|| MetaLoaderError::UnsupportedUri(uri.to_string()))?;
+        let load = || self.load_with_timeout(resolver.as_ref(), uri);
+        match &self.retry_strategy {
+            Some(retry_strategy) => {
+                RetryIf::spawn(retry_strategy.intervals(), load, |e: &MetaLoaderError| {
+                    e.is_retryable()
+                })
+                .await
+            }
+            None => load().await,
+        }
+    }
+
+    async fn load_with_timeout(
+        &self,
+        resolver: &dyn ContentResolver,
+        uri: &str,
+    ) -> Result<Vec<u8>, MetaLoaderError> {
+        let load = resolver.load(uri, self.max_size);
+        let content = match self.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, load).await.map_err(|_| {
+                MetaLoaderError::Timeout {
+                    uri: uri.to_string(),
+                    timeout,
+                }
+            })??,
+            None => load.await?,
+        };
+        // Custom resolvers might not check the size
+        if content.len() > self.max_size {
+            return Err(MetaLoaderError::ContentTooLarge {
+                uri: uri.to_string(),
+                max_size: self.max_size,
+            });
+        }
+        Ok(content)
+    }
 }
 
 #[async_trait]
@@ -169,3 +237,122 @@ where
 {
     async fn load(&self, content: &MetaDataContent) -> Result<T, MetaLoaderError>;
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use reqwest::StatusCode;
+
+    use super::{
+        ContentResolver, JettonMetaData, JettonMetaLoader, LoadMeta, MetaDataContent,
+        MetaLoaderError, NftItemMetaData, META_DECIMALS, META_NAME, META_URI,
+    };
+    use crate::client::RetryStrategy;
+
+    /// Serves `test://` URIs, failing the first `failures` attempts with a server error.
+    struct TestResolver {
+        failures: usize,
+        attempts: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ContentResolver for TestResolver {
+        fn supports(&self, uri: &str) -> bool {
+            uri.starts_with("test://")
+        }
+
+        async fn load(&self, uri: &str, _max_size: usize) -> Result<Vec<u8>, MetaLoaderError> {
+            if self.attempts.fetch_add(1, Ordering::SeqCst) < self.failures {
+                return Err(MetaLoaderError::LoadMetaDataFailed {
+                    uri: uri.to_string(),
+                    status: StatusCode::SERVICE_UNAVAILABLE,
+                });
+            }
+            if uri == "test://slow" {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+            }
+            Ok(br#"{"name":"Off-chain","symbol":"OFF","decimals":"6"}"#.to_vec())
+        }
+    }
+
+    fn meta_loader(failures: usize) -> (JettonMetaLoader, Arc<TestResolver>) {
+        let resolver = Arc::new(TestResolver {
+            failures,
+            attempts: AtomicUsize::new(0),
+        });
+        let mut loader = JettonMetaLoader::default().unwrap();
+        loader.with_resolver(resolver.clone());
+        (loader, resolver)
+    }
+
+    #[tokio::test]
+    async fn test_meta_loader_limits() {
+        let (mut loader, resolver) = meta_loader(2);
+        let result = loader.load_content("test://meta").await;
+        assert!(matches!(result, Err(e) if e.is_retryable()));
+        loader.with_retry_strategy(&RetryStrategy {
+            interval_ms: 1,
+            max_retries: 3,
+            ..Default::default()
+        });
+        let meta = loader.load_meta_from_uri("test://meta").await.unwrap();
+        assert_eq!(meta.symbol.as_deref(), Some("OFF"));
+        assert_eq!(resolver.attempts.load(Ordering::SeqCst), 3);
+
+        loader.with_max_size(16);
+        let result = loader.load_content("test://meta").await;
+        assert!(matches!(
+            result,
+            Err(MetaLoaderError::ContentTooLarge { max_size: 16, .. })
+        ));
+
+        loader.with_timeout(Some(Duration::from_millis(10)));
+        let result = loader.load_content("test://slow").await;
+        assert!(matches!(result, Err(MetaLoaderError::Timeout { .. })));
+
+        let result = loader.load_content("ftp://meta").await;
+        assert!(matches!(result, Err(MetaLoaderError::UnsupportedUri(_))));
+    }
+
+    #[tokio::test]
+    async fn test_semi_chain_meta_prefers_on_chain_values() {
+        let (loader, _) = meta_loader(0);
+        let dict = HashMap::from([
+            (META_URI.key, b"test://meta".to_vec()),
+            (META_NAME.key, b"On-chain".to_vec()),
+        ]);
+        let meta = loader
+            .load(&MetaDataContent::Internal { dict })
+            .await
+            .unwrap();
+        let expected = JettonMetaData {
+            name: Some("On-chain".to_string()),
+            uri: Some("test://meta".to_string()),
+            symbol: Some("OFF".to_string()),
+            description: None,
+            image: None,
+            image_data: None,
+            decimals: Some(6),
+        };
+        assert_eq!(meta, expected);
+    }
+
+    #[test]
+    fn test_on_chain_meta_fields() {
+        let dict = HashMap::from([
+            (META_URI.key, b"https://example.com/item.json".to_vec()),
+            (META_DECIMALS.key, b"nine".to_vec()),
+        ]);
+        assert_eq!(JettonMetaData::from(&dict).decimals, None);
+        let nft = NftItemMetaData::from(&dict);
+        assert_eq!(
+            nft.content_url.as_deref(),
+            Some("https://example.com/item.json")
+        );
+    }
+}
@@ -1,7 +1,9 @@
+use std::time::Duration;
+
 use reqwest::StatusCode;
 use thiserror::Error;
 
-use crate::meta::{IpfsLoaderError, MetaDataContent};
+use crate::meta::{is_retryable_status, IpfsLoaderError, MetaDataContent};
 
 #[derive(Debug, Error)]
 pub enum MetaLoaderError {
@@ -11,6 +13,15 @@ pub enum MetaLoaderError {
     #[error("Failed to load jetton metadata (URI: {uri}, response status code: {status})")]
     LoadMetaDataFailed { uri: String, status: StatusCode },
 
+    #[error("Metadata too large (URI: {uri}, max size: {max_size} bytes)")]
+    ContentTooLarge { uri: String, max_size: usize },
+
+    #[error("Timeout loading metadata (URI: {uri}, timeout: {timeout:?})")]
+    Timeout { uri: String, timeout: Duration },
+
+    #[error("No content resolver supports URI {0}")]
+    UnsupportedUri(String),
+
     #[error("IpfsLoaderError ({0})")]
     IpfsLoaderError(#[from] IpfsLoaderError),
 
@@ -23,3 +34,16 @@ pub enum MetaLoaderError {
     #[error("Internal error ({0})")]
     InternalError(String),
 }
+
+impl MetaLoaderError {
+    /// Returns whether loading may succeed if repeated, i.e. after a timeout, a transport error
+    /// or a server error.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            MetaLoaderError::LoadMetaDataFailed { status, .. } => is_retryable_status(*status),
+            MetaLoaderError::Timeout { .. } | MetaLoaderError::TransportError(_) => true,
+            MetaLoaderError::IpfsLoaderError(e) => e.is_retryable(),
+            _ => false,
+        }
+    }
+}
@@ -3,6 +3,8 @@ use std::fmt::Debug;
 pub use error::*;
 use serde::{Deserialize, Serialize};
 
+use crate::meta::read_limited;
+
 mod error;
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
@@ -17,6 +19,9 @@ pub enum IpfsConnectionType {
 pub struct IpfsLoaderConfig {
     connection_type: IpfsConnectionType,
     base_url: String,
+    /// Gateways or nodes tried in order if `base_url` fails with a transport or server error.
+    #[serde(default)]
+    fallback_urls: Vec<String>,
 }
 
 impl IpfsLoaderConfig {
@@ -24,6 +29,7 @@ impl IpfsLoaderConfig {
         IpfsLoaderConfig {
             connection_type: IpfsConnectionType::HttpGateway,
             base_url: url.to_string(),
+            fallback_urls: vec![],
         }
     }
 
@@ -31,8 +37,15 @@ impl IpfsLoaderConfig {
         IpfsLoaderConfig {
             connection_type: IpfsConnectionType::IpfsNode,
             base_url: url.to_string(),
+            fallback_urls: vec![],
         }
     }
+
+    /// Adds a gateway or node of the same connection type tried if the previous ones fail.
+    pub fn with_fallback_url(&mut self, url: &str) -> &mut Self {
+        self.fallback_urls.push(url.to_string());
+        self
+    }
 }
 
 impl Default for IpfsLoaderConfig {
@@ -40,6 +53,7 @@ impl Default for IpfsLoaderConfig {
         Self {
             connection_type: IpfsConnectionType::HttpGateway,
             base_url: "https://cloudflare-ipfs.com/ipfs/".to_string(),
+            fallback_urls: vec!["https://ipfs.io/ipfs/".to_string()],
         }
     }
 }
@@ -47,7 +61,7 @@ impl Default for IpfsLoaderConfig {
 #[derive(Clone)]
 pub struct IpfsLoader {
     connection_type: IpfsConnectionType,
-    base_url: String,
+    base_urls: Vec<String>,
     client: reqwest::Client,
 }
 
@@ -55,7 +69,10 @@ impl IpfsLoader {
     pub fn new(config: &IpfsLoaderConfig) -> Result<Self, IpfsLoaderError> {
         Ok(Self {
             connection_type: config.connection_type.clone(),
-            base_url: config.base_url.clone(),
+            base_urls: std::iter::once(&config.base_url)
+                .chain(&config.fallback_urls)
+                .map(|url| url.trim_end_matches('/').to_string())
+                .collect(),
             client: reqwest::Client::builder().build()?,
         })
     }
@@ -66,20 +83,59 @@ impl IpfsLoader {
     }
 
     pub async fn load(&self, path: &str) -> Result<Vec<u8>, IpfsLoaderError> {
+        self.load_limited(path, usize::MAX).await
+    }
+
+    /// Loads the object at `path`, failing with `IpfsLoaderError::ObjectTooLarge` as soon as
+    /// more than `max_size` bytes are received.
+    ///
+    /// Fallback URLs are tried in order while loading fails with a retryable error.
+    pub async fn load_limited(
+        &self,
+        path: &str,
+        max_size: usize,
+    ) -> Result<Vec<u8>, IpfsLoaderError> {
+        let (last, rest) = self.base_urls.split_last().expect("at least one base url");
+        for base_url in rest {
+            match self.load_from(base_url, path, max_size).await {
+                Err(e) if e.is_retryable() => {
+                    log::warn!(
+                        "Failed to load {} from {}, trying next url: {}",
+                        path,
+                        base_url,
+                        e
+                    )
+                }
+                result => return result,
+            }
+        }
+        self.load_from(last, path, max_size).await
+    }
+
+    async fn load_from(
+        &self,
+        base_url: &str,
+        path: &str,
+        max_size: usize,
+    ) -> Result<Vec<u8>, IpfsLoaderError> {
         let response = match self.connection_type {
             IpfsConnectionType::HttpGateway => {
-                let full_url = format!("{}/{}", self.base_url, path);
+                let full_url = format!("{}/{}", base_url, path);
                 self.client.get(full_url).send().await?
             }
             IpfsConnectionType::IpfsNode => {
-                let full_url = format!("{}/api/v0/cat?arg={}", self.base_url, path);
+                let full_url = format!("{}/api/v0/cat?arg={}", base_url, path);
                 self.client.post(full_url).send().await?
             }
         };
         let status = response.status();
         if status.is_success() {
-            let bytes = response.bytes().await?.to_vec();
-            Ok(bytes)
+            read_limited(response, max_size)
+                .await?
+                .ok_or_else(|| IpfsLoaderError::ObjectTooLarge {
+                    path: path.to_string(),
+                    max_size,
+                })
         } else {
             const MAX_MESSAGE_SIZE: usize = 200;
             let body = String::from_utf8_lossy(&response.bytes().await?).to_string();
@@ -120,6 +176,12 @@ mod tests {
         let config: IpfsLoaderConfig = serde_json::from_str(CONFIG_JSON)?;
         assert_eq!(config.connection_type, IpfsConnectionType::HttpGateway);
         assert_eq!(config.base_url, "http://example.com/");
+        assert!(config.fallback_urls.is_empty());
+
+        let mut config = IpfsLoaderConfig::ipfs_node("http://localhost:5001");
+        config.with_fallback_url("http://localhost:5002");
+        let json = serde_json::to_string(&config)?;
+        assert_eq!(serde_json::from_str::<IpfsLoaderConfig>(&json)?, config);
         Ok(())
     }
 }
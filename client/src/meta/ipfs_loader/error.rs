@@ -1,5 +1,6 @@
 use reqwest::StatusCode;
 use thiserror::Error;
+
 #[derive(Debug, Error)]
 pub enum IpfsLoaderError {
     #[error("Failed to load IPFS object (path: {path}, status: {status}, message: {message})")]
@@ -9,6 +10,25 @@ pub enum IpfsLoaderError {
         message: String,
     },
 
+    #[error("IPFS object too large (path: {path}, max size: {max_size} bytes)")]
+    ObjectTooLarge { path: String, max_size: usize },
+
     #[error("Transport error: {0}")]
     TransportError(#[from] reqwest::Error),
 }
+
+impl IpfsLoaderError {
+    /// Returns whether loading may succeed if repeated, e.g. from another gateway.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            IpfsLoaderError::IpfsLoadObjectFailed { status, .. } => is_retryable_status(*status),
+            IpfsLoaderError::ObjectTooLarge { .. } => false,
+            IpfsLoaderError::TransportError(_) => true,
+        }
+    }
+}
+
+/// Returns whether a request failed with `status` may succeed if repeated.
+pub(crate) fn is_retryable_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
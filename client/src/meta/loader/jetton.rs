@@ -40,20 +40,7 @@ impl LoadMeta<JettonMetaData> for MetaLoader<JettonMetaData> {
                     let result = self.load_meta_from_uri(uri.as_str()).await;
 
                     match result {
-                        Ok(external_meta) => Ok(JettonMetaData {
-                            name: META_NAME.use_string_or(external_meta.name, dict),
-                            uri: META_URI.use_string_or(external_meta.uri, dict),
-                            symbol: META_SYMBOL.use_string_or(external_meta.symbol, dict),
-                            description: META_DESCRIPTION
-                                .use_string_or(external_meta.description, dict),
-                            image: META_IMAGE.use_string_or(external_meta.image, dict),
-                            image_data: external_meta
-                                .image_data
-                                .or(dict.get(&META_IMAGE_DATA.key).cloned()),
-                            decimals: META_DECIMALS
-                                .use_string_or(None, dict)
-                                .map(|v| v.parse::<u8>().unwrap()),
-                        }),
+                        Ok(external_meta) => Ok(JettonMetaData::from(dict).merge(external_meta)),
                         Err(_) => Ok(dict.into()),
                     }
                 } else {
@@ -66,6 +53,22 @@ impl LoadMeta<JettonMetaData> for MetaLoader<JettonMetaData> {
     }
 }
 
+impl JettonMetaData {
+    /// Merges semi-chain metadata, on-chain values take precedence over off-chain ones
+    /// as defined by TEP-64.
+    pub fn merge(self, off_chain: JettonMetaData) -> JettonMetaData {
+        JettonMetaData {
+            name: self.name.or(off_chain.name),
+            uri: self.uri.or(off_chain.uri),
+            symbol: self.symbol.or(off_chain.symbol),
+            description: self.description.or(off_chain.description),
+            image: self.image.or(off_chain.image),
+            image_data: self.image_data.or(off_chain.image_data),
+            decimals: self.decimals.or(off_chain.decimals),
+        }
+    }
+}
+
 impl From<&SnakeFormatDict> for JettonMetaData {
     fn from(dict: &SnakeFormatDict) -> Self {
         JettonMetaData {
@@ -77,7 +80,7 @@ impl From<&SnakeFormatDict> for JettonMetaData {
             image_data: dict.get(&META_IMAGE_DATA.key).cloned(),
             decimals: META_DECIMALS
                 .use_string_or(None, dict)
-                .map(|v| v.parse::<u8>().unwrap()),
+                .and_then(|v| v.parse::<u8>().ok()),
         }
     }
 }
@@ -30,27 +30,38 @@ impl LoadMeta<NftCollectionMetaData> for MetaLoader<NftCollectionMetaData> {
                 if dict.contains_key(&META_URI.key) {
                     let uri = String::from_utf8_lossy(dict.get(&META_URI.key).unwrap()).to_string();
                     let external_meta = self.load_meta_from_uri(uri.as_str()).await?;
-                    Ok(NftCollectionMetaData {
-                        image: META_IMAGE.use_string_or(external_meta.image, dict),
-                        name: META_NAME.use_string_or(external_meta.name, dict),
-                        description: META_DESCRIPTION
-                            .use_string_or(external_meta.description, dict),
-                        social_links: META_SOCIAL_LINKS
-                            .use_value_or(external_meta.social_links, dict),
-                        marketplace: META_MARKETPLACE
-                            .use_string_or(external_meta.marketplace, dict),
-                    })
+                    Ok(NftCollectionMetaData::from(dict).merge(external_meta))
                 } else {
-                    Ok(NftCollectionMetaData {
-                        image: META_IMAGE.use_string_or(None, dict),
-                        name: META_NAME.use_string_or(None, dict),
-                        description: META_DESCRIPTION.use_string_or(None, dict),
-                        social_links: META_SOCIAL_LINKS.use_value_or(None, dict),
-                        marketplace: META_MARKETPLACE.use_string_or(None, dict),
-                    })
+                    Ok(dict.into())
                 }
             }
             content => Err(MetaLoaderError::ContentLayoutUnsupported(content.clone())),
         }
     }
 }
+
+impl NftCollectionMetaData {
+    /// Merges semi-chain metadata, on-chain values take precedence over off-chain ones
+    /// as defined by TEP-64.
+    pub fn merge(self, off_chain: NftCollectionMetaData) -> NftCollectionMetaData {
+        NftCollectionMetaData {
+            image: self.image.or(off_chain.image),
+            name: self.name.or(off_chain.name),
+            description: self.description.or(off_chain.description),
+            social_links: self.social_links.or(off_chain.social_links),
+            marketplace: self.marketplace.or(off_chain.marketplace),
+        }
+    }
+}
+
+impl From<&SnakeFormatDict> for NftCollectionMetaData {
+    fn from(dict: &SnakeFormatDict) -> Self {
+        NftCollectionMetaData {
+            image: META_IMAGE.use_string_or(None, dict),
+            name: META_NAME.use_string_or(None, dict),
+            description: META_DESCRIPTION.use_string_or(None, dict),
+            social_links: META_SOCIAL_LINKS.use_value_or(None, dict),
+            marketplace: META_MARKETPLACE.use_string_or(None, dict),
+        }
+    }
+}
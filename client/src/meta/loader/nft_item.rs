@@ -27,25 +27,38 @@ impl LoadMeta<NftItemMetaData> for MetaLoader<NftItemMetaData> {
                 if dict.contains_key(&META_URI.key) {
                     let uri = String::from_utf8_lossy(dict.get(&META_URI.key).unwrap()).to_string();
                     let external_meta = self.load_meta_from_uri(uri.as_str()).await?;
-                    Ok(NftItemMetaData {
-                        name: META_NAME.use_string_or(external_meta.name, dict),
-                        content_url: META_URI.use_string_or(external_meta.content_url, dict),
-                        description: META_DESCRIPTION
-                            .use_string_or(external_meta.description, dict),
-                        image: META_IMAGE.use_string_or(external_meta.image, dict),
-                        attributes: META_ATTRIBUTES.use_value_or(external_meta.attributes, dict),
-                    })
+                    Ok(NftItemMetaData::from(dict).merge(external_meta))
                 } else {
-                    Ok(NftItemMetaData {
-                        name: META_NAME.use_string_or(None, dict),
-                        content_url: META_URI.use_string_or(None, dict),
-                        description: META_DESCRIPTION.use_string_or(None, dict),
-                        image: META_IMAGE.use_string_or(None, dict),
-                        attributes: META_ATTRIBUTES.use_value_or(None, dict),
-                    })
+                    Ok(dict.into())
                 }
             }
             content => Err(MetaLoaderError::ContentLayoutUnsupported(content.clone())),
         }
     }
 }
+
+impl NftItemMetaData {
+    /// Merges semi-chain metadata, on-chain values take precedence over off-chain ones
+    /// as defined by TEP-64.
+    pub fn merge(self, off_chain: NftItemMetaData) -> NftItemMetaData {
+        NftItemMetaData {
+            name: self.name.or(off_chain.name),
+            description: self.description.or(off_chain.description),
+            image: self.image.or(off_chain.image),
+            content_url: self.content_url.or(off_chain.content_url),
+            attributes: self.attributes.or(off_chain.attributes),
+        }
+    }
+}
+
+impl From<&SnakeFormatDict> for NftItemMetaData {
+    fn from(dict: &SnakeFormatDict) -> Self {
+        NftItemMetaData {
+            name: META_NAME.use_string_or(None, dict),
+            description: META_DESCRIPTION.use_string_or(None, dict),
+            image: META_IMAGE.use_string_or(None, dict),
+            content_url: META_URI.use_string_or(None, dict),
+            attributes: META_ATTRIBUTES.use_value_or(None, dict),
+        }
+    }
+}
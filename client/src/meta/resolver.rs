@@ -0,0 +1,90 @@
+use async_trait::async_trait;
+
+use crate::meta::{IpfsLoader, IpfsLoaderError, MetaLoaderError};
+
+/// Transport loading off-chain metadata of `MetaLoader`, e.g. from a private storage.
+#[async_trait]
+pub trait ContentResolver: Send + Sync {
+    /// Returns whether the resolver loads `uri`, usually decided by its scheme.
+    fn supports(&self, uri: &str) -> bool;
+
+    /// Loads the content of `uri`, failing with `MetaLoaderError::ContentTooLarge` if it exceeds
+    /// `max_size` bytes.
+    async fn load(&self, uri: &str, max_size: usize) -> Result<Vec<u8>, MetaLoaderError>;
+}
+
+/// Loads `http://` and `https://` URIs.
+#[derive(Clone)]
+pub struct HttpResolver {
+    client: reqwest::Client,
+}
+
+impl HttpResolver {
+    pub fn new(client: reqwest::Client) -> HttpResolver {
+        HttpResolver { client }
+    }
+}
+
+#[async_trait]
+impl ContentResolver for HttpResolver {
+    fn supports(&self, uri: &str) -> bool {
+        uri.starts_with("http://") || uri.starts_with("https://")
+    }
+
+    async fn load(&self, uri: &str, max_size: usize) -> Result<Vec<u8>, MetaLoaderError> {
+        let resp = self.client.get(uri).send().await?;
+        if !resp.status().is_success() {
+            return Err(MetaLoaderError::LoadMetaDataFailed {
+                uri: uri.to_string(),
+                status: resp.status(),
+            });
+        }
+        read_limited(resp, max_size)
+            .await?
+            .ok_or_else(|| MetaLoaderError::ContentTooLarge {
+                uri: uri.to_string(),
+                max_size,
+            })
+    }
+}
+
+/// Loads `ipfs://<cid>/<path>` URIs, also accepting the `ipfs://ipfs/<cid>` form.
+#[async_trait]
+impl ContentResolver for IpfsLoader {
+    fn supports(&self, uri: &str) -> bool {
+        uri.starts_with("ipfs://")
+    }
+
+    async fn load(&self, uri: &str, max_size: usize) -> Result<Vec<u8>, MetaLoaderError> {
+        let path = uri.trim_start_matches("ipfs://");
+        let path = path.strip_prefix("ipfs/").unwrap_or(path);
+        match self.load_limited(path, max_size).await {
+            Err(IpfsLoaderError::ObjectTooLarge { .. }) => Err(MetaLoaderError::ContentTooLarge {
+                uri: uri.to_string(),
+                max_size,
+            }),
+            result => Ok(result?),
+        }
+    }
+}
+
+/// Reads the body of `response`, `None` if it exceeds `max_size` bytes.
+pub(crate) async fn read_limited(
+    mut response: reqwest::Response,
+    max_size: usize,
+) -> Result<Option<Vec<u8>>, reqwest::Error> {
+    if response
+        .content_length()
+        .is_some_and(|len| len > max_size as u64)
+    {
+        return Ok(None);
+    }
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+        if body.len() + chunk.len() > max_size {
+            return Ok(None);
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(Some(body))
+}
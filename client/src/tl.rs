@@ -18,9 +18,10 @@ pub use stack::*;
 use tonlib_sys::*;
 pub use types::*;
 
+pub(crate) use self::serial::result_from_value;
 use self::serial::*;
 
-base64_serde_type!(Base64Standard, STANDARD);
+base64_serde_type!(pub(crate) Base64Standard, STANDARD);
 
 // Wrapper around ton client with support for TL data types
 
@@ -78,22 +79,50 @@ impl TlTonClient {
         Ok(())
     }
 
+    /// Sends a function not covered by `TonFunction`, see `TonConnection::invoke_raw_json`.
+    pub fn send_raw(
+        &self,
+        method: &str,
+        params: &serde_json::Value,
+        extra: &str,
+    ) -> Result<(), TlError> {
+        let f_str = serialize_raw_function_extra(method, params, extra)?;
+        log::trace!(
+            "[{}] send: {}",
+            self.tag,
+            f_str.to_str().unwrap_or("<Error decoding string as UTF-8>")
+        );
+        unsafe { tonlib_client_json_send(self.ptr, f_str.as_ptr()) };
+        Ok(())
+    }
+
     pub fn receive(&self, timeout: f64) -> Option<(Result<TonResult, TlError>, Option<String>)> {
+        let c_str = self.receive_c_str(timeout)?;
+        Some(unsafe { deserialize_result_extra(c_str) })
+    }
+
+    /// Receives a message from tonlib as JSON, leaving its conversion to `TonResult`
+    /// to the caller.
+    pub(crate) fn receive_value(
+        &self,
+        timeout: f64,
+    ) -> Option<(Result<serde_json::Value, TlError>, Option<String>)> {
+        let c_str = self.receive_c_str(timeout)?;
+        Some(unsafe { deserialize_value_extra(c_str) })
+    }
+
+    fn receive_c_str(&self, timeout: f64) -> Option<*const c_char> {
         let c_str = unsafe { tonlib_client_json_receive(self.ptr, timeout) };
         if c_str.is_null() {
-            None
+            return None;
+        }
+        let c_str_slice = unsafe { CStr::from_ptr(c_str) };
+        if let Ok(c_str_str) = c_str_slice.to_str() {
+            log::trace!("[{}] receive: {}", self.tag, c_str_str);
         } else {
-            let c_str_slice = unsafe { CStr::from_ptr(c_str) };
-            if let Ok(c_str_str) = c_str_slice.to_str() {
-                log::trace!("[{}] receive: {}", self.tag, c_str_str);
-            } else {
-                log::trace!("[{}] receive: <Error decoding string as UTF-8>", self.tag);
-            }
-            let c_str_bytes = c_str_slice.to_bytes();
-            let (result, extra) =
-                unsafe { deserialize_result_extra(c_str_bytes.as_ptr() as *const c_char) };
-            Some((result, extra))
+            log::trace!("[{}] receive: <Error decoding string as UTF-8>", self.tag);
         }
+        Some(c_str)
     }
 
     pub fn set_log_verbosity_level(verbosity_level: u32) {
@@ -5,8 +5,8 @@ use strum::IntoStaticStr;
 
 use crate::tl::stack::TvmStackEntry;
 use crate::tl::types::{
-    AccountAddress, BlockId, BlockIdExt, BlocksAccountTransactionId, InternalTransactionId,
-    Options, SmcLibraryQueryExt, SmcMethodId,
+    AccountAddress, BlockId, BlockIdExt, BlocksAccountTransactionId, ExportedKey, InputKey,
+    InternalTransactionId, Key, Options, SmcLibraryQueryExt, SmcMethodId,
 };
 use crate::tl::Base64Standard;
 
@@ -57,6 +57,43 @@ pub enum TonFunction {
         options: Options,
     },
 
+    // tonlib_api.tl, line 239
+    Close {},
+
+    // tonlib_api.tl, line 244
+    CreateNewKey {
+        #[serde(with = "Base64Standard")]
+        local_password: Vec<u8>,
+        #[serde(with = "Base64Standard")]
+        mnemonic_password: Vec<u8>,
+        #[serde(with = "Base64Standard")]
+        random_extra_seed: Vec<u8>,
+    },
+
+    // tonlib_api.tl, line 245
+    DeleteKey {
+        key: Key,
+    },
+
+    // tonlib_api.tl, line 247
+    ExportKey {
+        input_key: InputKey,
+    },
+
+    // tonlib_api.tl, line 250
+    ExportUnencryptedKey {
+        input_key: InputKey,
+    },
+
+    // tonlib_api.tl, line 251
+    ImportKey {
+        #[serde(with = "Base64Standard")]
+        local_password: Vec<u8>,
+        #[serde(with = "Base64Standard")]
+        mnemonic_password: Vec<u8>,
+        exported_key: ExportedKey,
+    },
+
     // tonlib_api.tl, line 261
     #[serde(rename = "raw.sendMessageReturnHash")]
     RawSendMessageReturnHash {
@@ -104,6 +141,18 @@ pub enum TonFunction {
         body: Vec<u8>,
     },
 
+    // tonlib_api.tl, line 273
+    #[serde(rename = "raw.createQuery")]
+    RawCreateQuery {
+        destination: AccountAddress,
+        #[serde(with = "Base64Standard")]
+        init_code: Vec<u8>,
+        #[serde(with = "Base64Standard")]
+        init_data: Vec<u8>,
+        #[serde(with = "Base64Standard")]
+        body: Vec<u8>,
+    },
+
     // tonlib_api.tl, line 288
     #[serde(rename = "getAccountState")]
     GetAccountState {
@@ -123,6 +172,25 @@ pub enum TonFunction {
         mode: u32,
     },
 
+    // tonlib_api.tl, line 300
+    #[serde(rename = "query.send")]
+    QuerySend {
+        id: i64,
+    },
+
+    // tonlib_api.tl, line 301
+    #[serde(rename = "query.forget")]
+    QueryForget {
+        id: i64,
+    },
+
+    // tonlib_api.tl, line 302
+    #[serde(rename = "query.estimateFees")]
+    QueryEstimateFees {
+        id: i64,
+        ignore_chksig: bool,
+    },
+
     // tonlib_api.tl, line 306
     #[serde(rename = "smc.load")]
     SmcLoad {
@@ -223,6 +291,36 @@ pub enum TonFunction {
         id: BlockIdExt,
     },
 
+    // tonlib_api.tl, line 332
+    #[serde(rename = "blocks.getMasterchainBlockSignatures")]
+    BlocksGetMasterchainBlockSignatures {
+        seqno: i32,
+    },
+
+    // tonlib_api.tl, line 333
+    #[serde(rename = "blocks.getShardBlockProof")]
+    BlocksGetShardBlockProof {
+        id: BlockIdExt,
+        mode: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        from: Option<BlockIdExt>,
+    },
+
+    // Added to tonlib_api.tl in TON v2024.04, along with liteServer.getOutMsgQueueSizes
+    #[serde(rename = "blocks.getOutMsgQueueSizes")]
+    BlocksGetOutMsgQueueSizes {
+        mode: u32,
+        wc: i32,
+        shard: i64,
+    },
+
+    // tonlib_api.tl, line 338
+    #[serde(rename = "withBlock")]
+    WithBlock {
+        id: BlockIdExt,
+        function: Box<TonFunction>,
+    },
+
     // tonlib_ai.tl, line 342
     #[serde(rename = "liteServer.getInfo")]
     LiteServerGetInfo {},
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 
 use crate::tl::result::TonResult;
-use crate::tl::types::UpdateSyncState;
+use crate::tl::types::{SyncState, UpdateSyncState};
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum TonNotification {
@@ -18,4 +18,26 @@ impl TonNotification {
             _ => None,
         }
     }
+
+    /// Filter accepting `UpdateSyncState` notifications.
+    pub fn is_sync_state(&self) -> bool {
+        matches!(self, TonNotification::UpdateSyncState(_))
+    }
+
+    /// Returns the sync state of an `UpdateSyncState` notification.
+    pub fn sync_state(&self) -> Option<&SyncState> {
+        match self {
+            TonNotification::UpdateSyncState(update) => Some(&update.sync_state),
+        }
+    }
+
+    /// Filter accepting notifications of finished synchronization.
+    pub fn is_sync_done(&self) -> bool {
+        matches!(
+            self,
+            TonNotification::UpdateSyncState(UpdateSyncState {
+                sync_state: SyncState::Done
+            })
+        )
+    }
 }
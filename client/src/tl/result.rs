@@ -6,10 +6,11 @@ use strum::{Display, EnumDiscriminants, IntoStaticStr};
 use crate::client::TonClientError;
 use crate::tl::stack::TvmCell;
 use crate::tl::types::{
-    BlockIdExt, BlocksHeader, BlocksMasterchainInfo, BlocksShards, BlocksTransactions,
-    BlocksTransactionsExt, ConfigInfo, FullAccountState, LiteServerInfo, LogVerbosityLevel,
-    OptionsInfo, RawExtMessageInfo, RawFullAccountState, RawTransactions, SmcInfo,
-    SmcLibraryResult, SmcLibraryResultExt, SmcRunResult, UpdateSyncState,
+    BlockIdExt, BlocksBlockSignatures, BlocksHeader, BlocksMasterchainInfo, BlocksOutMsgQueueSizes,
+    BlocksShardBlockProof, BlocksShards, BlocksTransactions, BlocksTransactionsExt, ConfigInfo,
+    ExportedKey, ExportedUnencryptedKey, FullAccountState, Key, LiteServerInfo, LogVerbosityLevel,
+    OptionsInfo, QueryFees, QueryInfo, RawExtMessageInfo, RawFullAccountState, RawTransactions,
+    SmcInfo, SmcLibraryResult, SmcLibraryResultExt, SmcRunResult, UpdateSyncState,
 };
 
 #[derive(
@@ -28,6 +29,12 @@ pub enum TonResult {
     // tonlib_api.tl, line 30
     #[serde(rename = "options.info")]
     OptionsInfo(OptionsInfo),
+    // tonlib_api.tl, line 32
+    Key(Key),
+    // tonlib_api.tl, line 35
+    ExportedKey(ExportedKey),
+    // tonlib_api.tl, line 38
+    ExportedUnencryptedKey(ExportedUnencryptedKey),
     // tonlib_api.tl, line 51
     #[serde(rename = "ton.blockIdExt")]
     BlockIdExt(BlockIdExt),
@@ -43,6 +50,12 @@ pub enum TonResult {
     // tonlib_api.tl, line 90
     #[serde(rename = "fullAccountState")]
     FullAccountState(FullAccountState),
+    // tonlib_api.tl, line 162
+    #[serde(rename = "query.fees")]
+    QueryFees(QueryFees),
+    // tonlib_api.tl, line 164
+    #[serde(rename = "query.info")]
+    QueryInfo(QueryInfo),
     // tonlib_api.tl, line 167
     #[serde(rename = "tvm.cell")]
     TvmCell(TvmCell),
@@ -82,9 +95,21 @@ pub enum TonResult {
     // tonlib_api.tl, line 225
     #[serde(rename = "blocks.header")]
     BlocksHeader(BlocksHeader),
+    // tonlib_api.tl, line 229
+    #[serde(rename = "blocks.blockSignatures")]
+    BlocksBlockSignatures(BlocksBlockSignatures),
+    // tonlib_api.tl, line 232
+    #[serde(rename = "blocks.shardBlockProof")]
+    BlocksShardBlockProof(BlocksShardBlockProof),
+    // Added to tonlib_api.tl in TON v2024.04
+    #[serde(rename = "blocks.outMsgQueueSizes")]
+    BlocksOutMsgQueueSizes(BlocksOutMsgQueueSizes),
     // tonlib_api.tl, line 243
     #[serde(rename = "configInfo")]
     ConfigInfo(ConfigInfo),
+    /// Result of `TonConnection::invoke_raw_json`, the JSON object received from tonlib.
+    #[serde(skip)]
+    Raw(String),
 }
 
 impl TonResult {
@@ -114,6 +139,16 @@ impl fmt::Display for TonResult {
                 options_info.config_info.default_wallet_id
             ),
 
+            TonResult::Key(key) => write!(f, "TonResult::Key: {}", key.public_key),
+
+            TonResult::ExportedKey(exported_key) => write!(
+                f,
+                "TonResult::ExportedKey: {} words",
+                exported_key.word_list.len()
+            ),
+
+            TonResult::ExportedUnencryptedKey(_) => write!(f, "TonResult::ExportedUnencryptedKey"),
+
             TonResult::BlockIdExt(block_id_ext) => write!(
                 f,
                 "TonResult::BlockIdExt: {}:{}, seqno{}",
@@ -143,6 +178,16 @@ impl fmt::Display for TonResult {
                 full_account_state.address.account_address
             ),
 
+            TonResult::QueryFees(query_fees) => write!(
+                f,
+                "TonResult::QueryFees: source fees: {}",
+                query_fees.source_fees.total()
+            ),
+
+            TonResult::QueryInfo(query_info) => {
+                write!(f, "TonResult::QueryInfo: id: {}", query_info.id)
+            }
+
             TonResult::SmcInfo(_) => write!(f, "TonResult::SmcInfo"),
 
             TonResult::SmcRunResult(smc_run_result) => {
@@ -204,9 +249,30 @@ impl fmt::Display for TonResult {
                 blocks_header.id.workchain, blocks_header.id.shard, blocks_header.id.seqno
             ),
 
+            TonResult::BlocksBlockSignatures(signatures) => write!(
+                f,
+                "TonResult::BlocksBlockSignatures: seqno {}, {} signatures",
+                signatures.id.seqno,
+                signatures.signatures.len()
+            ),
+
+            TonResult::BlocksShardBlockProof(proof) => write!(
+                f,
+                "TonResult::BlocksShardBlockProof: {}:{}, seqno {}",
+                proof.from.workchain, proof.from.shard, proof.from.seqno
+            ),
+
+            TonResult::BlocksOutMsgQueueSizes(sizes) => write!(
+                f,
+                "TonResult::BlocksOutMsgQueueSizes: {} shards",
+                sizes.shards.len()
+            ),
+
             TonResult::ConfigInfo(_) => write!(f, "TonResult::ConfigInfo"),
 
             TonResult::TvmCell(_) => write!(f, "TonResult::TvmCell"),
+
+            TonResult::Raw(json) => write!(f, "TonResult::Raw: {}", json),
         }
     }
 }
@@ -27,6 +27,29 @@ pub(crate) fn serialize_function_extra(
     Ok(cstr)
 }
 
+/// Serializes a function not covered by `TonFunction`, whose `@type` is `method` and whose
+/// fields are `params`, a JSON object or `null` for no fields.
+pub(crate) fn serialize_raw_function_extra(
+    method: &str,
+    params: &Value,
+    extra: &str,
+) -> Result<CString, TlError> {
+    let mut obj = match params {
+        Value::Object(obj) => obj.clone(),
+        Value::Null => serde_json::Map::new(),
+        _ => {
+            return Err(TlError::SerdeJsonError(serde::ser::Error::custom(
+                "params must be a JSON object",
+            )))
+        }
+    };
+    obj.insert(String::from("@type"), Value::from(method));
+    obj.insert(String::from("@extra"), Value::from(extra));
+    let str = serde_json::to_string(&obj)?;
+    let cstr = CString::new(str)?;
+    Ok(cstr)
+}
+
 pub(crate) unsafe fn deserialize_result(c_str: *const c_char) -> Result<TonResult, TlError> {
     let cstr = CStr::from_ptr(c_str);
     // TODO: Optimize to avoid copying
@@ -38,6 +61,14 @@ pub(crate) unsafe fn deserialize_result(c_str: *const c_char) -> Result<TonResul
 pub(crate) unsafe fn deserialize_result_extra(
     c_str: *const c_char,
 ) -> (Result<TonResult, TlError>, Option<String>) {
+    let (value, extra) = deserialize_value_extra(c_str);
+    (value.and_then(|v| result_from_value(v, false)), extra)
+}
+
+/// Parses a message received from tonlib as JSON, returning it along with its `@extra`.
+pub(crate) unsafe fn deserialize_value_extra(
+    c_str: *const c_char,
+) -> (Result<Value, TlError>, Option<String>) {
     let cstr = CStr::from_ptr(c_str);
     // TODO: Optimize to avoid copying
     let str_result = cstr.to_str();
@@ -55,9 +86,17 @@ pub(crate) unsafe fn deserialize_result_extra(
         .and_then(|m| m.get("@extra"))
         .and_then(|v| v.as_str())
         .map(|s| s.to_string());
-    let result: Result<TonResult, TlError> =
-        serde_json::from_value(value).map_err(TlError::SerdeJsonError);
-    (result, extra)
+    (Ok(value), extra)
+}
+
+/// Converts a message received from tonlib to `TonResult`, keeping it as `TonResult::Raw`
+/// if `raw` unless it's an error.
+pub(crate) fn result_from_value(value: Value, raw: bool) -> Result<TonResult, TlError> {
+    let is_error = value.get("@type").and_then(|t| t.as_str()) == Some("error");
+    if raw && !is_error {
+        return Ok(TonResult::Raw(value.to_string()));
+    }
+    serde_json::from_value(value).map_err(TlError::SerdeJsonError)
 }
 
 #[cfg(test)]
@@ -65,9 +104,19 @@ mod tests {
 
     use std::ffi::CString;
 
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+    use tonlib_core::cell::{BagOfCells, CellBuilder};
+
     use crate::tl::function::TonFunction;
     use crate::tl::result::TonResult;
-    use crate::tl::serial::{deserialize_result_extra, serialize_function_extra};
+    use crate::tl::serial::{
+        deserialize_result_extra, result_from_value, serialize_function, serialize_function_extra,
+        serialize_raw_function_extra,
+    };
+    use crate::tl::types::{
+        AccountAddress, BlockId, BlockIdExt, ExportedKey, ExportedUnencryptedKey, InputKey, Key,
+    };
 
     #[test]
     fn it_serializes_function_extra() {
@@ -80,6 +129,37 @@ mod tests {
             cstr.to_str().unwrap())
     }
 
+    #[test]
+    fn it_serializes_raw_send_message() {
+        let cell = CellBuilder::new()
+            .store_u32(32, 0xdeadbeef)
+            .unwrap()
+            .build()
+            .unwrap();
+        let body = BagOfCells::from_root(cell).serialize(true).unwrap();
+        let expected_body = STANDARD.encode(&body);
+
+        let func = TonFunction::RawSendMessage { body: body.clone() };
+        let cstr = serialize_function(&func).unwrap();
+        assert_eq!(
+            format!(
+                "{{\"@type\":\"raw.sendMessage\",\"body\":\"{}\"}}",
+                expected_body
+            ),
+            cstr.to_str().unwrap()
+        );
+
+        let func = TonFunction::RawSendMessageReturnHash { body };
+        let cstr = serialize_function(&func).unwrap();
+        assert_eq!(
+            format!(
+                "{{\"@type\":\"raw.sendMessageReturnHash\",\"body\":\"{}\"}}",
+                expected_body
+            ),
+            cstr.to_str().unwrap()
+        );
+    }
+
     #[test]
     fn it_deserializes_result_extra() {
         let cstr = CString::new("{\"@extra\":\"some_extra\",\"@type\":\"logVerbosityLevel\",\"verbosity_level\":100500}").unwrap();
@@ -94,6 +174,244 @@ mod tests {
         }
     }
 
+    #[test]
+    fn it_serializes_block_functions() {
+        let func = TonFunction::BlocksGetMasterchainInfo {};
+        let cstr = serialize_function(&func).unwrap();
+        assert_eq!(
+            "{\"@type\":\"blocks.getMasterchainInfo\"}",
+            cstr.to_str().unwrap()
+        );
+
+        let func = TonFunction::BlocksLookupBlock {
+            mode: 1,
+            id: BlockId {
+                workchain: -1,
+                shard: i64::MIN,
+                seqno: 42,
+            },
+            lt: 0,
+            utime: 0,
+        };
+        let cstr = serialize_function(&func).unwrap();
+        assert_eq!(
+            "{\"@type\":\"blocks.lookupBlock\",\"mode\":1,\"id\":{\"workchain\":-1,\"shard\":-9223372036854775808,\"seqno\":42},\"lt\":0,\"utime\":0}",
+            cstr.to_str().unwrap()
+        );
+
+        // Optional `from` is omitted unless set by mode
+        let func = TonFunction::BlocksGetShardBlockProof {
+            id: BlockIdExt {
+                workchain: 0,
+                shard: i64::MIN,
+                seqno: 7,
+                root_hash: vec![0; 32],
+                file_hash: vec![0; 32],
+            },
+            mode: 0,
+            from: None,
+        };
+        let cstr = serialize_function(&func).unwrap();
+        let json: serde_json::Value = serde_json::from_str(cstr.to_str().unwrap()).unwrap();
+        assert_eq!(json["@type"], "blocks.getShardBlockProof");
+        assert_eq!(json["mode"], 0);
+        assert!(json.get("from").is_none());
+    }
+
+    #[test]
+    fn it_deserializes_block_results() {
+        let cstr = CString::new(
+            r#"{"@type":"blocks.masterchainInfo",
+            "last":{"@type":"ton.blockIdExt","workchain":-1,"shard":"-9223372036854775808",
+            "seqno":42,"root_hash":"AQID","file_hash":"BAUG"},
+            "state_root_hash":"BwgJ",
+            "init":{"@type":"ton.blockIdExt","workchain":-1,"shard":"-9223372036854775808",
+            "seqno":0,"root_hash":"AQID","file_hash":"BAUG"},"@extra":"1"}"#,
+        )
+        .unwrap();
+        let (result, extra) = unsafe { deserialize_result_extra(cstr.as_ptr()) };
+        assert_eq!(extra, Some(String::from("1")));
+        match result.unwrap() {
+            TonResult::BlocksMasterchainInfo(info) => {
+                assert_eq!(info.last.seqno, 42);
+                assert_eq!(info.last.shard, i64::MIN);
+                assert_eq!(info.last.root_hash, vec![1, 2, 3]);
+                assert_eq!(info.state_root_hash, vec![7, 8, 9]);
+                assert_eq!(info.init.seqno, 0);
+            }
+            _ => panic!("Unexpected result"),
+        }
+
+        let cstr = CString::new(
+            r#"{"@type":"ton.blockIdExt","workchain":0,"shard":"-9223372036854775808",
+            "seqno":7,"root_hash":"AQID","file_hash":"BAUG","@extra":"2"}"#,
+        )
+        .unwrap();
+        let (result, _) = unsafe { deserialize_result_extra(cstr.as_ptr()) };
+        match result.unwrap() {
+            TonResult::BlockIdExt(block_id) => {
+                assert_eq!(block_id.workchain, 0);
+                assert_eq!(block_id.seqno, 7);
+                assert_eq!(block_id.to_block_id().shard, i64::MIN);
+                assert_eq!(block_id.file_hash, vec![4, 5, 6]);
+            }
+            _ => panic!("Unexpected result"),
+        }
+
+        let cstr = CString::new(
+            r#"{"@type":"blocks.outMsgQueueSizes","shards":[{"@type":"blocks.outMsgQueueSize",
+            "id":{"@type":"ton.blockIdExt","workchain":0,"shard":"-9223372036854775808",
+            "seqno":7,"root_hash":"AQID","file_hash":"BAUG"},"size":12}],
+            "ext_msg_queue_size_limit":8000,"@extra":"3"}"#,
+        )
+        .unwrap();
+        let (result, _) = unsafe { deserialize_result_extra(cstr.as_ptr()) };
+        match result.unwrap() {
+            TonResult::BlocksOutMsgQueueSizes(sizes) => {
+                assert_eq!(sizes.shards.len(), 1);
+                assert_eq!(sizes.shards[0].id.seqno, 7);
+                assert_eq!(sizes.shards[0].size, 12);
+                assert_eq!(sizes.ext_msg_queue_size_limit, 8000);
+            }
+            _ => panic!("Unexpected result"),
+        }
+    }
+
+    #[test]
+    fn it_serializes_raw_functions() {
+        let params = serde_json::json!({"mode": 0, "wc": 0, "shard": 0, "@extra": "overridden"});
+        let cstr =
+            serialize_raw_function_extra("blocks.getOutMsgQueueSizes", &params, "7").unwrap();
+        let json: serde_json::Value = serde_json::from_str(cstr.to_str().unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "@type": "blocks.getOutMsgQueueSizes",
+                "@extra": "7",
+                "mode": 0,
+                "wc": 0,
+                "shard": 0
+            })
+        );
+
+        let cstr = serialize_raw_function_extra(
+            "blocks.getMasterchainInfo",
+            &serde_json::Value::Null,
+            "8",
+        )
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_str(cstr.to_str().unwrap()).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"@type": "blocks.getMasterchainInfo", "@extra": "8"})
+        );
+        assert!(serialize_raw_function_extra(
+            "blocks.getMasterchainInfo",
+            &serde_json::json!([1]),
+            "9"
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn it_keeps_raw_results() {
+        let value = serde_json::json!({"@type": "blocks.unknownResult", "size": 1});
+        match result_from_value(value.clone(), true).unwrap() {
+            TonResult::Raw(json) => {
+                assert_eq!(
+                    serde_json::from_str::<serde_json::Value>(&json).unwrap(),
+                    value
+                )
+            }
+            r => panic!("Unexpected result: {:?}", r),
+        }
+        assert!(result_from_value(value, false).is_err());
+
+        // Errors are parsed, so they're reported like errors of any request
+        let error = serde_json::json!({"@type": "error", "code": 400, "message": "bad"});
+        assert!(matches!(
+            result_from_value(error, true).unwrap(),
+            TonResult::Error { code: 400, .. }
+        ));
+    }
+
+    #[test]
+    fn it_serializes_query_functions() {
+        let func = TonFunction::QueryEstimateFees {
+            id: 3,
+            ignore_chksig: true,
+        };
+        let cstr = serialize_function(&func).unwrap();
+        assert_eq!(
+            "{\"@type\":\"query.estimateFees\",\"id\":3,\"ignore_chksig\":true}",
+            cstr.to_str().unwrap()
+        );
+
+        let func = TonFunction::QuerySend { id: 3 };
+        let cstr = serialize_function(&func).unwrap();
+        assert_eq!(
+            "{\"@type\":\"query.send\",\"id\":3}",
+            cstr.to_str().unwrap()
+        );
+
+        let func = TonFunction::WithBlock {
+            id: BlockIdExt {
+                workchain: -1,
+                shard: i64::MIN,
+                seqno: 5,
+                root_hash: vec![0; 32],
+                file_hash: vec![0; 32],
+            },
+            function: Box::new(TonFunction::SmcForget { id: 3 }),
+        };
+        let cstr = serialize_function(&func).unwrap();
+        let json: serde_json::Value = serde_json::from_str(cstr.to_str().unwrap()).unwrap();
+        assert_eq!(json["@type"], "withBlock");
+        assert_eq!(json["id"]["seqno"], 5);
+        assert_eq!(
+            json["function"],
+            serde_json::json!({"@type": "smc.forget", "id": 3})
+        );
+
+        let func = TonFunction::RawCreateQuery {
+            destination: AccountAddress {
+                account_address: "0:1234".to_string(),
+            },
+            init_code: vec![],
+            init_data: vec![],
+            body: vec![1, 2, 3],
+        };
+        let cstr = serialize_function(&func).unwrap();
+        assert_eq!(
+            "{\"@type\":\"raw.createQuery\",\"destination\":{\"account_address\":\"0:1234\"},\"init_code\":\"\",\"init_data\":\"\",\"body\":\"AQID\"}",
+            cstr.to_str().unwrap()
+        );
+    }
+
+    #[test]
+    fn it_deserializes_query_fees() {
+        let cstr = CString::new(
+            r#"{"@type":"query.fees",
+            "source_fees":{"@type":"fees","in_fwd_fee":394000,"storage_fee":12,"gas_fee":3308000,"fwd_fee":0},
+            "destination_fees":[{"@type":"fees","in_fwd_fee":0,"storage_fee":"7","gas_fee":0,"fwd_fee":0}],
+            "@extra":"3"}"#,
+        )
+        .unwrap();
+        let (result, _) = unsafe { deserialize_result_extra(cstr.as_ptr()) };
+        match result.unwrap() {
+            TonResult::QueryFees(fees) => {
+                assert_eq!(fees.source_fees.in_fwd_fee, 394000);
+                assert_eq!(fees.source_fees.storage_fee, 12);
+                assert_eq!(fees.source_fees.gas_fee, 3308000);
+                assert_eq!(fees.source_fees.fwd_fee, 0);
+                assert_eq!(fees.source_fees.total(), 3702012);
+                assert_eq!(fees.destination_fees.len(), 1);
+                assert_eq!(fees.destination_fees[0].storage_fee, 7);
+            }
+            _ => panic!("Unexpected result"),
+        }
+    }
+
     #[test]
     fn it_deserializes_options_info() {
         let cstr = CString::new(r#"{"@type":"options.info","config_info":
@@ -102,4 +420,66 @@ mod tests {
         let (_, extra) = unsafe { deserialize_result_extra(cstr.as_ptr()) };
         assert_eq!(extra, Some(String::from("0")));
     }
+
+    #[test]
+    fn it_serializes_key_functions() {
+        let func = TonFunction::ImportKey {
+            local_password: vec![1, 2, 3],
+            mnemonic_password: vec![],
+            exported_key: ExportedKey {
+                word_list: vec!["dose".to_string(), "ice".to_string()],
+            },
+        };
+        let cstr = serialize_function(&func).unwrap();
+        assert_eq!(
+            r#"{"@type":"importKey","local_password":"AQID","mnemonic_password":"","exported_key":{"word_list":["dose","ice"]}}"#,
+            cstr.to_str().unwrap()
+        );
+
+        let key = Key {
+            public_key: "PubAStGIXBJ_6GOrsAdS-oROZDm7BPJk1w3nzqWAsyY3q6IK".to_string(),
+            secret: vec![4, 5, 6],
+        };
+        let func = TonFunction::ExportKey {
+            input_key: InputKey::Regular {
+                key: key.clone(),
+                local_password: vec![1, 2, 3],
+            },
+        };
+        let cstr = serialize_function(&func).unwrap();
+        assert_eq!(
+            r#"{"@type":"exportKey","input_key":{"@type":"inputKeyRegular","key":{"public_key":"PubAStGIXBJ_6GOrsAdS-oROZDm7BPJk1w3nzqWAsyY3q6IK","secret":"BAUG"},"local_password":"AQID"}}"#,
+            cstr.to_str().unwrap()
+        );
+
+        let cstr = CString::new(
+            r#"{"@type":"key","public_key":"PubAStGIXBJ_6GOrsAdS-oROZDm7BPJk1w3nzqWAsyY3q6IK",
+            "secret":"BAUG","@extra":"3"}"#,
+        )
+        .unwrap();
+        let (result, _) = unsafe { deserialize_result_extra(cstr.as_ptr()) };
+        assert_eq!(result.unwrap(), TonResult::Key(key.clone()));
+
+        let func = TonFunction::ExportUnencryptedKey {
+            input_key: InputKey::Regular {
+                key,
+                local_password: vec![1, 2, 3],
+            },
+        };
+        let cstr = serialize_function(&func).unwrap();
+        assert_eq!(
+            r#"{"@type":"exportUnencryptedKey","input_key":{"@type":"inputKeyRegular","key":{"public_key":"PubAStGIXBJ_6GOrsAdS-oROZDm7BPJk1w3nzqWAsyY3q6IK","secret":"BAUG"},"local_password":"AQID"}}"#,
+            cstr.to_str().unwrap()
+        );
+
+        let cstr = CString::new(r#"{"@type":"exportedUnencryptedKey","data":"BwgJ","@extra":"4"}"#)
+            .unwrap();
+        let (result, _) = unsafe { deserialize_result_extra(cstr.as_ptr()) };
+        assert_eq!(
+            result.unwrap(),
+            TonResult::ExportedUnencryptedKey(ExportedUnencryptedKey {
+                data: vec![7, 8, 9]
+            })
+        );
+    }
 }
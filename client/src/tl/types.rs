@@ -4,11 +4,16 @@ use std::fmt::{Debug, Display, Formatter};
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use serde_aux::prelude::*;
+use tonlib_core::cell::dict::predefined_readers::{key_reader_u16, val_reader_ref_cell};
+use tonlib_core::cell::{ArcCell, BagOfCells, TonCellError};
+use tonlib_core::message::{BouncedMessage, TonMessageError};
+use tonlib_core::transaction::Transaction;
 use tonlib_core::{TonHash, TonTxId};
 
 use super::TonLibraryId;
 use crate::tl::stack::{TvmCell, TvmStack};
 use crate::tl::Base64Standard;
+use crate::types::method_name_to_id;
 
 // tonlib_api.tl, line 23
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
@@ -50,6 +55,48 @@ pub struct OptionsInfo {
     pub config_info: OptionsConfigInfo,
 }
 
+// tonlib_api.tl, line 32
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Key {
+    pub public_key: String,
+    #[serde(with = "Base64Standard")]
+    pub secret: Vec<u8>,
+}
+
+// tonlib_api.tl, line 33
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(tag = "@type")]
+pub enum InputKey {
+    #[serde(rename = "inputKeyRegular")]
+    Regular {
+        key: Key,
+        #[serde(with = "Base64Standard")]
+        local_password: Vec<u8>,
+    },
+    #[serde(rename = "inputKeyFake")]
+    Fake,
+}
+
+// tonlib_api.tl, line 35
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ExportedKey {
+    pub word_list: Vec<String>,
+}
+
+// tonlib_api.tl, line 38
+#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+pub struct ExportedUnencryptedKey {
+    #[serde(with = "Base64Standard")]
+    pub data: Vec<u8>,
+}
+
+impl Debug for ExportedUnencryptedKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExportedUnencryptedKey")
+            .finish_non_exhaustive()
+    }
+}
+
 // tonlib_api.tl, line 44
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct AccountAddress {
@@ -190,6 +237,49 @@ pub struct RawTransaction {
     pub out_msgs: Vec<RawMessage>,
 }
 
+impl RawTransaction {
+    /// Decodes the transaction `data`, including the description of its phases.
+    pub fn transaction(&self) -> Result<Transaction, TonCellError> {
+        Transaction::from_boc(&self.data)
+    }
+
+    /// Returns the incoming message cell parsed from the transaction `data`, if any.
+    pub fn in_msg_cell(&self) -> Result<Option<ArcCell>, TonCellError> {
+        let boc = BagOfCells::parse(self.data.as_slice())?;
+        let tx_cell = boc.single_root()?;
+        let mut parser = tx_cell.reference(0)?.parser();
+        parser.load_maybe_cell_ref()
+    }
+
+    /// Returns the outgoing message cells parsed from the transaction `data`,
+    /// in the same order as `out_msgs`.
+    pub fn out_msg_cells(&self) -> Result<Vec<ArcCell>, TonCellError> {
+        let boc = BagOfCells::parse(self.data.as_slice())?;
+        let tx_cell = boc.single_root()?;
+        let mut parser = tx_cell.reference(0)?.parser();
+        let _in_msg = parser.load_maybe_cell_ref()?;
+        let out_msgs = match parser.load_maybe_cell_ref()? {
+            Some(dict_cell) => {
+                dict_cell
+                    .parser()
+                    .load_dict(15, key_reader_u16, val_reader_ref_cell)?
+            }
+            None => return Ok(vec![]),
+        };
+        let mut out_msgs: Vec<_> = out_msgs.into_iter().collect();
+        out_msgs.sort_by_key(|(idx, _)| *idx);
+        Ok(out_msgs.into_iter().map(|(_, msg)| msg).collect())
+    }
+
+    /// Returns the decoded bounced message if the incoming message of the transaction is a bounce.
+    pub fn bounced_in_msg(&self) -> Result<Option<BouncedMessage>, TonMessageError> {
+        match self.in_msg_cell()? {
+            Some(msg) => BouncedMessage::from_message(&msg),
+            None => Ok(None),
+        }
+    }
+}
+
 // tonlib_api.tl, line 56
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct RawTransactions {
@@ -396,6 +486,48 @@ pub enum MsgData {
     },
 }
 
+// tonlib_api.tl, line 161
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Fees {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub in_fwd_fee: i64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub storage_fee: i64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub gas_fee: i64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub fwd_fee: i64,
+}
+
+impl Fees {
+    /// Returns the sum of all fee components.
+    pub fn total(&self) -> i64 {
+        self.in_fwd_fee + self.storage_fee + self.gas_fee + self.fwd_fee
+    }
+}
+
+// tonlib_api.tl, line 162
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryFees {
+    pub source_fees: Fees,
+    pub destination_fees: Vec<Fees>,
+}
+
+// tonlib_api.tl, line 164
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryInfo {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub id: i64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub valid_until: i64,
+    #[serde(with = "Base64Standard")]
+    pub body_hash: Vec<u8>,
+    #[serde(with = "Base64Standard")]
+    pub body: Vec<u8>,
+    #[serde(with = "Base64Standard")]
+    pub init_state: Vec<u8>,
+}
+
 // tonlib_api.tl, line 179
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SmcInfo {
@@ -413,6 +545,32 @@ pub enum SmcMethodId {
     Name { name: Cow<'static, str> },
 }
 
+impl SmcMethodId {
+    /// Creates numeric method id from the method name.
+    pub fn from_name(name: &str) -> SmcMethodId {
+        SmcMethodId::Number {
+            number: method_name_to_id(name),
+        }
+    }
+
+    /// Returns numeric method id for either form.
+    pub fn id(&self) -> i32 {
+        match self {
+            SmcMethodId::Number { number } => *number,
+            SmcMethodId::Name { name } => method_name_to_id(name),
+        }
+    }
+}
+
+impl Display for SmcMethodId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SmcMethodId::Number { number } => write!(f, "#{:08x}", number),
+            SmcMethodId::Name { name } => write!(f, "'{}'", name),
+        }
+    }
+}
+
 // tonlib_api.tl, line 184
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SmcRunResult {
@@ -475,11 +633,11 @@ pub struct LogVerbosityLevel {
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct LiteServerInfo {
     #[serde(deserialize_with = "deserialize_number_from_string")]
-    now: i64,
+    pub now: i64,
     #[serde(deserialize_with = "deserialize_number_from_string")]
-    version: i32,
+    pub version: i32,
     #[serde(deserialize_with = "deserialize_number_from_string")]
-    capabilities: i64,
+    pub capabilities: i64,
 }
 
 // tonlib_api.tl, line 219
@@ -571,6 +729,67 @@ pub struct BlocksHeader {
     pub prev_blocks: Option<Vec<BlockIdExt>>,
 }
 
+// tonlib_api.tl, line 228
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlocksSignature {
+    #[serde(with = "Base64Standard")]
+    pub node_id_short: Vec<u8>,
+    #[serde(with = "Base64Standard")]
+    pub signature: Vec<u8>,
+}
+
+// tonlib_api.tl, line 229
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlocksBlockSignatures {
+    pub id: BlockIdExt,
+    pub signatures: Vec<BlocksSignature>,
+}
+
+// tonlib_api.tl, line 230
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlocksShardBlockLink {
+    pub id: BlockIdExt,
+    #[serde(with = "Base64Standard")]
+    pub proof: Vec<u8>,
+}
+
+// tonlib_api.tl, line 231
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlocksBlockLinkBack {
+    pub to_key_block: bool,
+    pub from: BlockIdExt,
+    pub to: BlockIdExt,
+    #[serde(with = "Base64Standard")]
+    pub dest_proof: Vec<u8>,
+    #[serde(with = "Base64Standard")]
+    pub proof: Vec<u8>,
+    #[serde(with = "Base64Standard")]
+    pub state_proof: Vec<u8>,
+}
+
+// tonlib_api.tl, line 232
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlocksShardBlockProof {
+    pub from: BlockIdExt,
+    pub mc_id: BlockIdExt,
+    pub links: Vec<BlocksShardBlockLink>,
+    pub mc_proof: Vec<BlocksBlockLinkBack>,
+}
+
+// Added to tonlib_api.tl in TON v2024.04
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlocksOutMsgQueueSize {
+    pub id: BlockIdExt,
+    pub size: i32,
+}
+
+// Added to tonlib_api.tl in TON v2024.04
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlocksOutMsgQueueSizes {
+    pub shards: Vec<BlocksOutMsgQueueSize>,
+    pub ext_msg_queue_size_limit: i32,
+}
+
 // tonlib_api.tl, line 234
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ConfigInfo {
@@ -4,5 +4,9 @@ mod tvm_success;
 pub use tvm_success::*;
 mod tvm_stack_entry;
 pub use tvm_stack_entry::*;
+mod tvm_stack_parser;
+pub use tvm_stack_parser::*;
+mod vm_stack;
+pub use vm_stack::*;
 mod error;
 pub use error::*;
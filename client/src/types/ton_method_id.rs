@@ -53,12 +53,26 @@ impl From<&TonMethodId> for SmcMethodId {
 impl TonMethodId {
     pub fn to_id(&self) -> i32 {
         match self {
-            TonMethodId::Name(name) => CRC_16_XMODEM.checksum(name.as_bytes()) as i32 | 0x10000,
+            TonMethodId::Name(name) => method_name_to_id(name),
             TonMethodId::Number(id) => *id,
         }
     }
 }
 
+impl From<&SmcMethodId> for TonMethodId {
+    fn from(value: &SmcMethodId) -> Self {
+        match value {
+            SmcMethodId::Number { number } => TonMethodId::Number(*number),
+            SmcMethodId::Name { name } => TonMethodId::Name(name.clone()),
+        }
+    }
+}
+
+/// Computes numeric id of a get-method from its name.
+pub(crate) fn method_name_to_id(name: &str) -> i32 {
+    CRC_16_XMODEM.checksum(name.as_bytes()) as i32 | 0x10000
+}
+
 impl Display for TonMethodId {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -85,6 +99,7 @@ fn calc_opcode(command: &str) -> i32 {
 
 #[cfg(test)]
 mod tests {
+    use crate::tl::SmcMethodId;
     use crate::types::TonMethodId;
 
     #[test]
@@ -95,6 +110,21 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_smc_method_id() -> anyhow::Result<()> {
+        let by_name = SmcMethodId::Name {
+            name: "seqno".into(),
+        };
+        let by_number = SmcMethodId::from_name("seqno");
+        assert_eq!(by_number, SmcMethodId::Number { number: 85143 });
+        assert_eq!(by_name.id(), by_number.id());
+        assert_eq!(format!("{}", by_name), "'seqno'");
+        assert_eq!(format!("{}", by_number), "#00014c97");
+        assert_eq!(TonMethodId::from(&by_name).to_id(), 85143);
+        assert_eq!(TonMethodId::from(&by_number), TonMethodId::Number(85143));
+        Ok(())
+    }
+
     #[test]
     fn test_opcode() -> anyhow::Result<()> {
         let p = "transfer query_id:uint64 amount:VarUInteger 16 destination:MsgAddress \
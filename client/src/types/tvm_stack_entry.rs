@@ -1,14 +1,16 @@
 use std::collections::HashMap;
 use std::hash::Hash;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use num_bigint::{BigInt, BigUint};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use strum::Display;
 use tonlib_core::cell::dict::{KeyReader, ValReader};
-use tonlib_core::cell::{ArcCell, BagOfCells, Cell, CellBuilder, CellSlice};
+use tonlib_core::cell::{ArcCell, BagOfCells, Cell, CellBuilder, CellParser, CellSlice};
 use tonlib_core::TonAddress;
 
-use crate::tl::{TvmCell, TvmNumber, TvmSlice, TvmStackEntry as TlTvmStackEntry};
+use crate::tl::{Base64Standard, TvmCell, TvmNumber, TvmSlice, TvmStackEntry as TlTvmStackEntry};
 use crate::types::StackParseError;
 
 #[derive(Debug, Display, Clone, PartialEq)]
@@ -23,6 +25,30 @@ pub enum TvmStackEntry {
 }
 
 impl TvmStackEntry {
+    /// Creates a number entry, `Int64` if the value fits, `Int257` otherwise.
+    pub fn number<N: Into<BigInt>>(value: N) -> TvmStackEntry {
+        let value = value.into();
+        match i64::try_from(&value) {
+            Ok(n) => TvmStackEntry::Int64(n),
+            Err(_) => TvmStackEntry::Int257(value),
+        }
+    }
+
+    /// Creates a cell entry from a serialized bag of cells with a single root.
+    pub fn cell(boc: &[u8]) -> Result<TvmStackEntry, StackParseError> {
+        let boc = BagOfCells::parse(boc)?;
+        Ok(TvmStackEntry::Cell(boc.single_root()?.clone()))
+    }
+
+    /// Creates a slice entry covering the whole cell.
+    pub fn slice(cell: Cell) -> Result<TvmStackEntry, StackParseError> {
+        Ok(TvmStackEntry::Slice(CellSlice::full_cell(cell)?))
+    }
+
+    pub fn address(address: &TonAddress) -> Result<TvmStackEntry, StackParseError> {
+        address.try_into()
+    }
+
     pub fn get_bool(&self) -> Result<bool, StackParseError> {
         match self {
             TvmStackEntry::Int64(number) => match number {
@@ -95,6 +121,17 @@ impl TvmStackEntry {
         }
     }
 
+    /// Returns a parser positioned at the start of the slice, respecting its bit and reference offsets.
+    pub fn as_slice(&self) -> Result<CellParser<'_>, StackParseError> {
+        match self {
+            TvmStackEntry::Slice(slice) => Ok(slice.parser()?),
+            t => Err(StackParseError::InvalidEntryType {
+                expected: "Slice".to_string(),
+                found: t.clone(),
+            }),
+        }
+    }
+
     pub fn get_address(&self) -> Result<TonAddress, StackParseError> {
         match self {
             TvmStackEntry::Cell(cell) => cell
@@ -176,6 +213,24 @@ impl From<BigUint> for TvmStackEntry {
     }
 }
 
+impl From<i32> for TvmStackEntry {
+    fn from(value: i32) -> Self {
+        TvmStackEntry::Int64(value as i64)
+    }
+}
+
+impl From<u64> for TvmStackEntry {
+    fn from(value: u64) -> Self {
+        TvmStackEntry::number(value)
+    }
+}
+
+impl From<ArcCell> for TvmStackEntry {
+    fn from(value: ArcCell) -> Self {
+        TvmStackEntry::Cell(value)
+    }
+}
+
 impl From<Cell> for TvmStackEntry {
     fn from(value: Cell) -> Self {
         TvmStackEntry::Cell(Arc::new(value))
@@ -191,6 +246,14 @@ impl TryFrom<&TonAddress> for TvmStackEntry {
     }
 }
 
+impl TryFrom<TonAddress> for TvmStackEntry {
+    type Error = StackParseError;
+
+    fn try_from(value: TonAddress) -> Result<Self, Self::Error> {
+        TvmStackEntry::try_from(&value)
+    }
+}
+
 impl TryFrom<&String> for TvmStackEntry {
     type Error = StackParseError;
 
@@ -253,7 +316,7 @@ impl TryFrom<&TlTvmStackEntry> for TvmStackEntry {
                     start_bit: 0,
                     start_ref: 0,
                     end_bit: cell.bit_len(),
-                    end_ref: 0,
+                    end_ref: cell.references().len(),
                 };
                 TvmStackEntry::Slice(cell_slice)
             }
@@ -279,3 +342,214 @@ impl TryFrom<&TlTvmStackEntry> for TvmStackEntry {
         Ok(entry)
     }
 }
+
+/// Serialized form of `TvmStackEntry`, cells are stored as base64 encoded bags of cells
+/// and big numbers as decimal strings.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SerialTvmStackEntry {
+    Null,
+    Nan,
+    Int64 {
+        value: i64,
+    },
+    Int257 {
+        value: String,
+    },
+    Cell {
+        #[serde(with = "Base64Standard")]
+        boc: Vec<u8>,
+    },
+    Slice {
+        #[serde(with = "Base64Standard")]
+        boc: Vec<u8>,
+        start_bit: usize,
+        end_bit: usize,
+        start_ref: usize,
+        end_ref: usize,
+    },
+    Unsupported,
+}
+
+impl TryFrom<&TvmStackEntry> for SerialTvmStackEntry {
+    type Error = StackParseError;
+
+    fn try_from(value: &TvmStackEntry) -> Result<Self, Self::Error> {
+        let entry = match value {
+            TvmStackEntry::Null => SerialTvmStackEntry::Null,
+            TvmStackEntry::Nan => SerialTvmStackEntry::Nan,
+            TvmStackEntry::Int64(value) => SerialTvmStackEntry::Int64 { value: *value },
+            TvmStackEntry::Int257(value) => SerialTvmStackEntry::Int257 {
+                value: value.to_string(),
+            },
+            TvmStackEntry::Cell(cell) => SerialTvmStackEntry::Cell {
+                boc: BagOfCells::from_root(cell.as_ref().clone()).serialize(false)?,
+            },
+            TvmStackEntry::Slice(slice) => SerialTvmStackEntry::Slice {
+                boc: BagOfCells::from_root(slice.cell.as_ref().clone()).serialize(false)?,
+                start_bit: slice.start_bit,
+                end_bit: slice.end_bit,
+                start_ref: slice.start_ref,
+                end_ref: slice.end_ref,
+            },
+            TvmStackEntry::Unsupported => SerialTvmStackEntry::Unsupported,
+        };
+        Ok(entry)
+    }
+}
+
+impl TryFrom<SerialTvmStackEntry> for TvmStackEntry {
+    type Error = StackParseError;
+
+    fn try_from(value: SerialTvmStackEntry) -> Result<Self, Self::Error> {
+        let entry = match value {
+            SerialTvmStackEntry::Null => TvmStackEntry::Null,
+            SerialTvmStackEntry::Nan => TvmStackEntry::Nan,
+            SerialTvmStackEntry::Int64 { value } => TvmStackEntry::Int64(value),
+            SerialTvmStackEntry::Int257 { value } => TvmStackEntry::Int257(
+                BigInt::from_str(&value)
+                    .map_err(|_| StackParseError::InvalidEntryValue(value.clone()))?,
+            ),
+            SerialTvmStackEntry::Cell { boc } => TvmStackEntry::cell(&boc)?,
+            SerialTvmStackEntry::Slice {
+                boc,
+                start_bit,
+                end_bit,
+                start_ref,
+                end_ref,
+            } => {
+                let boc = BagOfCells::parse(&boc)?;
+                let cell = boc.single_root()?;
+                TvmStackEntry::Slice(CellSlice::new(
+                    cell, start_bit, end_bit, start_ref, end_ref,
+                )?)
+            }
+            SerialTvmStackEntry::Unsupported => TvmStackEntry::Unsupported,
+        };
+        Ok(entry)
+    }
+}
+
+impl Serialize for TvmStackEntry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerialTvmStackEntry::try_from(self)
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TvmStackEntry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let entry = SerialTvmStackEntry::deserialize(deserializer)?;
+        TvmStackEntry::try_from(entry).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use num_bigint::{BigInt, BigUint};
+    use tonlib_core::cell::{BagOfCells, CellBuilder, CellSlice};
+    use tonlib_core::TonAddress;
+
+    use super::TvmStackEntry;
+    use crate::tl::{SmcRunResult, TvmStack, TvmStackEntry as TlTvmStackEntry};
+    use crate::types::{StackParseError, TvmSuccess};
+
+    fn round_trip(entry: &TvmStackEntry) -> Result<TvmStackEntry, StackParseError> {
+        let tl_entry = TlTvmStackEntry::try_from(entry)?;
+        TvmStackEntry::try_from(&tl_entry)
+    }
+
+    #[test]
+    fn test_stack_entry_number_round_trip() -> Result<(), StackParseError> {
+        assert_eq!(TvmStackEntry::number(-5), TvmStackEntry::Int64(-5));
+        assert_eq!(round_trip(&TvmStackEntry::from(-5))?.get_i64()?, -5);
+        assert_eq!(
+            round_trip(&TvmStackEntry::from(i64::MAX))?.get_i64()?,
+            i64::MAX
+        );
+
+        let big = BigUint::from(u64::MAX) * 1000u32;
+        let entry = TvmStackEntry::number(big.clone());
+        assert_eq!(entry, TvmStackEntry::Int257(BigInt::from(big.clone())));
+        assert_eq!(round_trip(&entry)?.get_biguint()?, big);
+        assert_eq!(
+            TvmStackEntry::from(u64::MAX).get_biguint()?,
+            BigUint::from(u64::MAX)
+        );
+        assert!(round_trip(&TvmStackEntry::from(-1))?.get_biguint().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stack_entry_address_round_trip() -> Result<(), StackParseError> {
+        let address =
+            TonAddress::from_str("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR").unwrap();
+        let entry = TvmStackEntry::address(&address)?;
+        assert_eq!(round_trip(&entry)?.get_address()?, address);
+        assert_eq!(TvmStackEntry::try_from(address.clone())?, entry);
+        assert!(TvmStackEntry::from(1).get_address().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stack_entry_cell_from_boc() -> Result<(), StackParseError> {
+        let cell = CellBuilder::new().store_u32(32, 0xdeadbeef)?.build()?;
+        let boc = BagOfCells::from_root(cell.clone()).serialize(false)?;
+        let entry = TvmStackEntry::cell(&boc)?;
+        assert_eq!(entry.get_cell()?.as_ref(), &cell);
+        assert_eq!(round_trip(&entry)?, entry);
+        assert!(TvmStackEntry::cell(&[1, 2, 3]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_tvm_success_serde_round_trip() -> Result<(), StackParseError> {
+        let inner = CellBuilder::new().store_u8(8, 7)?.build()?;
+        let cell = CellBuilder::new()
+            .store_u32(32, 0xdeadbeef)?
+            .store_reference(&Arc::new(inner))?
+            .build()?;
+        let cell = Arc::new(cell);
+        let success = TvmSuccess {
+            vm_log: None,
+            vm_exit_code: 0,
+            stack: vec![
+                TvmStackEntry::Null,
+                TvmStackEntry::Nan,
+                TvmStackEntry::from(-5),
+                TvmStackEntry::number(BigUint::from(u64::MAX) * 1000u32),
+                TvmStackEntry::Cell(cell.clone()),
+                TvmStackEntry::Slice(CellSlice::new(&cell, 8, 24, 0, 1)?),
+                TvmStackEntry::Unsupported,
+            ],
+            missing_library: None,
+            gas_used: 1000,
+        };
+        let json = serde_json::to_string(&success).unwrap();
+        let parsed: TvmSuccess = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, success);
+
+        let entry: Result<TvmStackEntry, _> =
+            serde_json::from_str(r#"{"type":"int257","value":"12x"}"#);
+        assert!(entry.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_smc_run_result_serde_round_trip() {
+        let result = SmcRunResult {
+            gas_used: 100,
+            stack: TvmStack {
+                elements: vec![TlTvmStackEntry::try_from(&TvmStackEntry::from(42)).unwrap()],
+            },
+            exit_code: 0,
+        };
+        let json = serde_json::to_string(&result).unwrap();
+        let parsed: SmcRunResult = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, result);
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use num_bigint::{BigInt, BigUint};
+use tonlib_core::cell::dict::{KeyReader, ValReader, ValWriter};
+use tonlib_core::cell::{ArcCell, Cell, CellBuilder, CellSlice};
+use tonlib_core::TonAddress;
+
+use crate::types::{StackParseError, TvmStackEntry};
+
+/// Max bit length of a number stored in a slice, the size of TVM integers.
+const MAX_SLICE_NUMBER_BITS: usize = 257;
+
+/// Reads typed values from a stack returned by a get-method, in the order they are returned.
+///
+/// ```ignore
+/// let mut parser = TvmStackParser::new(&result.stack);
+/// let balance: BigUint = parser.pop_int()?;
+/// let owner = parser.pop_address()?;
+/// ```
+pub struct TvmStackParser<'a> {
+    stack: &'a [TvmStackEntry],
+    position: usize,
+}
+
+impl<'a> TvmStackParser<'a> {
+    pub fn new(stack: &'a [TvmStackEntry]) -> TvmStackParser<'a> {
+        TvmStackParser { stack, position: 0 }
+    }
+
+    /// Returns the number of entries not read yet.
+    pub fn remaining(&self) -> usize {
+        self.stack.len() - self.position
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    /// Returns the next entry, `StackParseError::InvalidStackSize` if all entries are read.
+    pub fn pop(&mut self) -> Result<&'a TvmStackEntry, StackParseError> {
+        let entry = self
+            .stack
+            .get(self.position)
+            .ok_or(StackParseError::InvalidStackSize(self.stack.len()))?;
+        self.position += 1;
+        Ok(entry)
+    }
+
+    pub fn skip(&mut self, count: usize) -> Result<&mut Self, StackParseError> {
+        for _ in 0..count {
+            self.pop()?;
+        }
+        Ok(self)
+    }
+
+    pub fn pop_bool(&mut self) -> Result<bool, StackParseError> {
+        self.pop()?.get_bool()
+    }
+
+    /// Reads a number converted to `T`, e.g. `u32`, `i64` or `BigUint`.
+    ///
+    /// Besides number entries accepts slices holding an unsigned number in all their bits,
+    /// which some contracts return instead of numbers.
+    pub fn pop_int<T: TryFrom<BigInt>>(&mut self) -> Result<T, StackParseError> {
+        let number = match self.pop()? {
+            TvmStackEntry::Slice(slice) => parse_slice_number(slice)?,
+            entry => entry.get_bigint()?,
+        };
+        T::try_from(number.clone()).map_err(|_| {
+            StackParseError::InvalidEntryValue(format!("number out of range: {}", number))
+        })
+    }
+
+    pub fn pop_address(&mut self) -> Result<TonAddress, StackParseError> {
+        self.pop()?.get_address()
+    }
+
+    pub fn pop_cell(&mut self) -> Result<ArcCell, StackParseError> {
+        self.pop()?.get_cell()
+    }
+
+    /// Same as `pop_cell`, but returns `None` for a `Null` entry.
+    pub fn pop_maybe_cell(&mut self) -> Result<Option<ArcCell>, StackParseError> {
+        match self.pop()? {
+            TvmStackEntry::Null => Ok(None),
+            entry => entry.get_cell().map(Some),
+        }
+    }
+
+    pub fn pop_slice(&mut self) -> Result<&'a CellSlice, StackParseError> {
+        match self.pop()? {
+            TvmStackEntry::Slice(slice) => Ok(slice),
+            t => Err(StackParseError::InvalidEntryType {
+                expected: "Slice".to_string(),
+                found: t.clone(),
+            }),
+        }
+    }
+
+    pub fn pop_string(&mut self) -> Result<String, StackParseError> {
+        self.pop()?.get_string()
+    }
+
+    /// Reads a dictionary, a `Null` entry is read as an empty one.
+    pub fn pop_dict<K, V>(
+        &mut self,
+        key_len: usize,
+        key_reader: KeyReader<K>,
+        val_reader: ValReader<V>,
+    ) -> Result<HashMap<K, V>, StackParseError>
+    where
+        K: Hash + Eq + Clone,
+    {
+        match self.pop()? {
+            TvmStackEntry::Null => Ok(HashMap::new()),
+            entry => entry.get_dict(key_len, key_reader, val_reader),
+        }
+    }
+}
+
+fn parse_slice_number(slice: &CellSlice) -> Result<BigInt, StackParseError> {
+    let mut parser = slice.parser()?;
+    let bit_len = parser.remaining_bits();
+    if bit_len == 0 || bit_len > MAX_SLICE_NUMBER_BITS {
+        return Err(StackParseError::InvalidEntryValue(format!(
+            "expected number, found slice of {} bits",
+            bit_len
+        )));
+    }
+    Ok(parser.load_uint(bit_len)?.into())
+}
+
+/// Builds a stack of get-method arguments, symmetric to `TvmStackParser`.
+///
+/// ```ignore
+/// let stack = TvmStackBuilder::new()
+///     .push_address(&owner)?
+///     .push_int(42)
+///     .build();
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct TvmStackBuilder {
+    stack: Vec<TvmStackEntry>,
+}
+
+impl TvmStackBuilder {
+    pub fn new() -> TvmStackBuilder {
+        TvmStackBuilder::default()
+    }
+
+    pub fn push(&mut self, entry: TvmStackEntry) -> &mut Self {
+        self.stack.push(entry);
+        self
+    }
+
+    pub fn push_bool(&mut self, value: bool) -> &mut Self {
+        self.push(value.into())
+    }
+
+    pub fn push_int<N: Into<BigInt>>(&mut self, value: N) -> &mut Self {
+        self.push(TvmStackEntry::number(value))
+    }
+
+    pub fn push_address(&mut self, address: &TonAddress) -> Result<&mut Self, StackParseError> {
+        Ok(self.push(TvmStackEntry::address(address)?))
+    }
+
+    pub fn push_cell(&mut self, cell: ArcCell) -> &mut Self {
+        self.push(TvmStackEntry::Cell(cell))
+    }
+
+    pub fn push_slice(&mut self, cell: Cell) -> Result<&mut Self, StackParseError> {
+        Ok(self.push(TvmStackEntry::slice(cell)?))
+    }
+
+    pub fn push_string(&mut self, value: &str) -> Result<&mut Self, StackParseError> {
+        Ok(self.push(TvmStackEntry::try_from(&value.to_string())?))
+    }
+
+    /// Pushes a dictionary as a cell, an empty one is pushed as `Null`.
+    pub fn push_dict<K, V>(
+        &mut self,
+        key_len: usize,
+        val_writer: ValWriter<V>,
+        data: HashMap<K, V>,
+    ) -> Result<&mut Self, StackParseError>
+    where
+        BigUint: From<K>,
+    {
+        if data.is_empty() {
+            return Ok(self.push(TvmStackEntry::Null));
+        }
+        let cell = CellBuilder::new()
+            .store_dict(key_len, val_writer, data)?
+            .build()?;
+        Ok(self.push(cell.into()))
+    }
+
+    pub fn build(&self) -> Vec<TvmStackEntry> {
+        self.stack.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::str::FromStr;
+
+    use num_bigint::{BigInt, BigUint};
+    use tonlib_core::cell::dict::predefined_readers::{key_reader_u8, val_reader_uint};
+    use tonlib_core::cell::dict::predefined_writers::val_writer_unsigned_min_size;
+    use tonlib_core::cell::{CellBuilder, CellSlice};
+    use tonlib_core::TonAddress;
+
+    use super::{TvmStackBuilder, TvmStackParser};
+    use crate::tl::TvmStackEntry as TlTvmStackEntry;
+    use crate::types::{StackParseError, TvmStackEntry};
+
+    #[test]
+    fn test_stack_builder_parser_round_trip() -> Result<(), StackParseError> {
+        let address =
+            TonAddress::from_str("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR").unwrap();
+        let cell = CellBuilder::new().store_u32(32, 0xdeadbeef)?.build()?;
+        let dict = HashMap::from([(1u8, BigUint::from(100u32)), (2u8, BigUint::from(200u32))]);
+        let stack = TvmStackBuilder::new()
+            .push_int(7)
+            .push_int(BigUint::from(u64::MAX) * 1000u32)
+            .push_bool(true)
+            .push_address(&address)?
+            .push_cell(cell.clone().into())
+            .push_string("jetton")?
+            .push_dict(8, val_writer_unsigned_min_size, dict.clone())?
+            .push_dict(
+                8,
+                val_writer_unsigned_min_size,
+                HashMap::<u8, BigUint>::new(),
+            )?
+            .build();
+        // Entries go through the TL representation as in a get-method call
+        let stack = stack
+            .iter()
+            .map(|e| TvmStackEntry::try_from(&TlTvmStackEntry::try_from(e)?))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut parser = TvmStackParser::new(&stack);
+        assert_eq!(parser.pop_int::<u32>()?, 7);
+        assert_eq!(parser.remaining(), 7);
+        assert_eq!(
+            parser.pop_int::<BigUint>()?,
+            BigUint::from(u64::MAX) * 1000u32
+        );
+        assert!(parser.pop_bool()?);
+        assert_eq!(parser.pop_address()?, address);
+        assert_eq!(parser.pop_cell()?.as_ref(), &cell);
+        assert_eq!(parser.pop_string()?, "jetton");
+        assert_eq!(parser.pop_dict(8, key_reader_u8, val_reader_uint)?, dict);
+        assert!(parser
+            .pop_dict(8, key_reader_u8, val_reader_uint)?
+            .is_empty());
+        assert!(parser.is_empty());
+        assert!(matches!(
+            parser.pop(),
+            Err(StackParseError::InvalidStackSize(8))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stack_parser_numbers() -> Result<(), StackParseError> {
+        let slice_cell = CellBuilder::new().store_u32(16, 0x1234)?.build()?;
+        let stack = vec![
+            TvmStackEntry::Int257(BigInt::from(-1)),
+            TvmStackEntry::Slice(CellSlice::full_cell(slice_cell)?),
+            TvmStackEntry::Int64(300),
+            TvmStackEntry::Null,
+        ];
+        let mut parser = TvmStackParser::new(&stack);
+        assert_eq!(parser.pop_int::<i8>()?, -1);
+        assert_eq!(parser.pop_int::<u64>()?, 0x1234);
+        assert!(parser.pop_int::<u8>().is_err());
+        assert!(parser.pop_maybe_cell()?.is_none());
+        Ok(())
+    }
+}
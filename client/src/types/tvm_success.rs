@@ -1,8 +1,12 @@
+use num_bigint::{BigInt, BigUint};
+use serde::{Deserialize, Serialize};
 use tonlib_core::cell::ArcCell;
+use tonlib_core::TonAddress;
 
-use crate::types::TvmStackEntry;
+use crate::tl::SmcRunResult;
+use crate::types::{StackParseError, TvmStackEntry, TvmStackParser};
 
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct TvmSuccess {
     pub vm_log: Option<String>,
     pub vm_exit_code: i32,
@@ -21,6 +25,63 @@ impl TvmSuccess {
     pub fn exit_error(&self) -> bool {
         !self.exit_success()
     }
+
+    /// Returns the stack entry at `index`, `StackParseError::InvalidStackSize` if the stack is
+    /// shorter.
+    pub fn entry(&self, index: usize) -> Result<&TvmStackEntry, StackParseError> {
+        self.stack
+            .get(index)
+            .ok_or(StackParseError::InvalidStackSize(self.stack.len()))
+    }
+
+    pub fn get_bool(&self, index: usize) -> Result<bool, StackParseError> {
+        self.entry(index)?.get_bool()
+    }
+
+    pub fn get_i64(&self, index: usize) -> Result<i64, StackParseError> {
+        self.entry(index)?.get_i64()
+    }
+
+    pub fn get_bigint(&self, index: usize) -> Result<BigInt, StackParseError> {
+        self.entry(index)?.get_bigint()
+    }
+
+    pub fn get_biguint(&self, index: usize) -> Result<BigUint, StackParseError> {
+        self.entry(index)?.get_biguint()
+    }
+
+    pub fn get_address(&self, index: usize) -> Result<TonAddress, StackParseError> {
+        self.entry(index)?.get_address()
+    }
+
+    pub fn get_cell(&self, index: usize) -> Result<ArcCell, StackParseError> {
+        self.entry(index)?.get_cell()
+    }
+
+    /// Returns a parser reading the stack from the first entry.
+    pub fn parser(&self) -> TvmStackParser<'_> {
+        TvmStackParser::new(&self.stack)
+    }
+}
+
+impl TryFrom<&SmcRunResult> for TvmSuccess {
+    type Error = StackParseError;
+
+    fn try_from(value: &SmcRunResult) -> Result<Self, Self::Error> {
+        let stack = value
+            .stack
+            .elements
+            .iter()
+            .map(TvmStackEntry::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(TvmSuccess {
+            vm_log: None,
+            vm_exit_code: value.exit_code,
+            stack,
+            missing_library: None,
+            gas_used: value.gas_used as i32,
+        })
+    }
 }
 
 #[derive(Debug)]
@@ -0,0 +1,197 @@
+use num_bigint::{BigInt, Sign};
+use tonlib_core::cell::{ArcCell, Cell, CellBuilder, CellParser, CellSlice};
+
+use crate::types::{StackParseError, TvmStackEntry};
+
+const VM_STK_NULL: u8 = 0x00;
+const VM_STK_TINYINT: u8 = 0x01;
+/// Prefix of `vm_stk_int#0201_` and `vm_stk_nan#02ff`, followed by 7 bits of a suffix.
+const VM_STK_INT: u8 = 0x02;
+const VM_STK_INT_SUFFIX: u8 = 0x00;
+const VM_STK_NAN_SUFFIX: u8 = 0x7f;
+const VM_STK_CELL: u8 = 0x03;
+const VM_STK_SLICE: u8 = 0x04;
+const INT257_BITS: usize = 257;
+/// Maximum number of entries allocated ahead of parsing, the depth of a stack isn't trusted.
+const MAX_PREALLOCATED_DEPTH: usize = 256;
+
+/// Serializes `stack`, topmost entry last, according to TL-B schema:
+///
+/// ```raw
+/// vm_stack#_ depth:(## 24) stack:(VmStackList depth) = VmStack;
+/// vm_stk_cons#_ {n:#} rest:^(VmStackList n) tos:VmStackValue = VmStackList (n + 1);
+/// vm_stk_nil#_ = VmStackList 0;
+/// ```
+///
+/// Only null, NaN, numbers, cells and slices are supported, as for get-method arguments.
+pub fn build_vm_stack(stack: &[TvmStackEntry]) -> Result<Cell, StackParseError> {
+    let mut builder = CellBuilder::new();
+    builder.store_u32(24, stack.len() as u32)?;
+    if let Some((top, rest)) = stack.split_last() {
+        let mut list = Cell::default();
+        for entry in rest {
+            let mut list_builder = CellBuilder::new();
+            list_builder.store_child(list)?;
+            store_vm_stack_value(&mut list_builder, entry)?;
+            list = list_builder.build()?;
+        }
+        builder.store_child(list)?;
+        store_vm_stack_value(&mut builder, top)?;
+    }
+    Ok(builder.build()?)
+}
+
+/// Parses a stack serialized as in `build_vm_stack`, e.g. the result of a get-method,
+/// topmost entry last.
+///
+/// Values of unsupported types, e.g. tuples and continuations, are returned
+/// as `TvmStackEntry::Unsupported`.
+pub fn parse_vm_stack(cell: &Cell) -> Result<Vec<TvmStackEntry>, StackParseError> {
+    let mut parser = cell.parser();
+    let depth = parser.load_u32(24)? as usize;
+    let mut stack = Vec::with_capacity(depth.min(MAX_PREALLOCATED_DEPTH));
+    let mut list: Option<ArcCell> = None;
+    for _ in 0..depth {
+        let (rest, value) = match &list {
+            None => (parser.next_reference()?, load_vm_stack_value(&mut parser)?),
+            Some(cell) => {
+                let mut list_parser = cell.parser();
+                let rest = list_parser.next_reference()?;
+                (rest, load_vm_stack_value(&mut list_parser)?)
+            }
+        };
+        stack.push(value);
+        list = Some(rest);
+    }
+    stack.reverse();
+    Ok(stack)
+}
+
+fn store_vm_stack_value(
+    builder: &mut CellBuilder,
+    entry: &TvmStackEntry,
+) -> Result<(), StackParseError> {
+    match entry {
+        TvmStackEntry::Null => {
+            builder.store_u8(8, VM_STK_NULL)?;
+        }
+        TvmStackEntry::Nan => {
+            builder
+                .store_u8(8, VM_STK_INT)?
+                .store_u8(7, VM_STK_NAN_SUFFIX)?
+                .store_bit(true)?;
+        }
+        TvmStackEntry::Int64(value) => {
+            builder.store_u8(8, VM_STK_TINYINT)?.store_i64(64, *value)?;
+        }
+        TvmStackEntry::Int257(value) => {
+            builder
+                .store_u8(8, VM_STK_INT)?
+                .store_u8(7, VM_STK_INT_SUFFIX)?;
+            store_int257(builder, value)?;
+        }
+        TvmStackEntry::Cell(cell) => {
+            builder.store_u8(8, VM_STK_CELL)?.store_reference(cell)?;
+        }
+        TvmStackEntry::Slice(slice) => {
+            builder
+                .store_u8(8, VM_STK_SLICE)?
+                .store_reference(&slice.cell)?
+                .store_u32(10, slice.start_bit as u32)?
+                .store_u32(10, slice.end_bit as u32)?
+                .store_u8(3, slice.start_ref as u8)?
+                .store_u8(3, slice.end_ref as u8)?;
+        }
+        TvmStackEntry::Unsupported => {
+            return Err(StackParseError::InvalidEntryValue(
+                "unsupported stack entry can't be serialized".to_string(),
+            ))
+        }
+    }
+    Ok(())
+}
+
+fn load_vm_stack_value(parser: &mut CellParser) -> Result<TvmStackEntry, StackParseError> {
+    let entry = match parser.load_u8(8)? {
+        VM_STK_NULL => TvmStackEntry::Null,
+        VM_STK_TINYINT => TvmStackEntry::Int64(parser.load_i64(64)?),
+        VM_STK_INT => match parser.load_u8(7)? {
+            VM_STK_INT_SUFFIX => TvmStackEntry::Int257(load_int257(parser)?),
+            VM_STK_NAN_SUFFIX if parser.load_bit()? => TvmStackEntry::Nan,
+            _ => TvmStackEntry::Unsupported,
+        },
+        VM_STK_CELL => TvmStackEntry::Cell(parser.next_reference()?),
+        VM_STK_SLICE => {
+            let cell = parser.next_reference()?;
+            let start_bit = parser.load_u32(10)? as usize;
+            let end_bit = parser.load_u32(10)? as usize;
+            let start_ref = parser.load_u8(3)? as usize;
+            let end_ref = parser.load_u8(3)? as usize;
+            TvmStackEntry::Slice(CellSlice::new(
+                &cell, start_bit, end_bit, start_ref, end_ref,
+            )?)
+        }
+        _ => TvmStackEntry::Unsupported,
+    };
+    Ok(entry)
+}
+
+/// Stores `value` as a 257-bit two's complement number, the sign bit first.
+fn store_int257(builder: &mut CellBuilder, value: &BigInt) -> Result<(), StackParseError> {
+    let sign_bit = BigInt::from(1u8) << (INT257_BITS - 1);
+    if value >= &sign_bit || value < &-&sign_bit {
+        return Err(StackParseError::InvalidEntryValue(format!(
+            "{} doesn't fit in {} bits",
+            value, INT257_BITS
+        )));
+    }
+    let negative = value.sign() == Sign::Minus;
+    let value = if negative {
+        value + &sign_bit
+    } else {
+        value.clone()
+    };
+    builder
+        .store_bit(negative)?
+        .store_uint(INT257_BITS - 1, value.magnitude())?;
+    Ok(())
+}
+
+fn load_int257(parser: &mut CellParser) -> Result<BigInt, StackParseError> {
+    let negative = parser.load_bit()?;
+    let value = BigInt::from(parser.load_uint(INT257_BITS - 1)?);
+    if negative {
+        Ok(value - (BigInt::from(1u8) << (INT257_BITS - 1)))
+    } else {
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use num_bigint::BigInt;
+    use tonlib_core::cell::{CellBuilder, CellSlice};
+
+    use super::{build_vm_stack, parse_vm_stack};
+    use crate::types::TvmStackEntry;
+
+    #[test]
+    fn test_vm_stack_round_trip() -> anyhow::Result<()> {
+        let cell = Arc::new(CellBuilder::new().store_u32(32, 7)?.build()?);
+        let stack = vec![
+            TvmStackEntry::Null,
+            TvmStackEntry::Int64(-5),
+            TvmStackEntry::Int257(BigInt::from(u64::MAX) * 3),
+            TvmStackEntry::Int257(-(BigInt::from(1u8) << 256usize)),
+            TvmStackEntry::Nan,
+            TvmStackEntry::Cell(cell.clone()),
+            TvmStackEntry::Slice(CellSlice::new(&cell, 8, 24, 0, 0)?),
+        ];
+        let serialized = build_vm_stack(&stack)?;
+        assert_eq!(parse_vm_stack(&serialized)?, stack);
+        assert_eq!(parse_vm_stack(&build_vm_stack(&[])?)?, vec![]);
+        Ok(())
+    }
+}
@@ -0,0 +1,24 @@
+use std::time::Duration;
+
+use tokio_test::assert_ok;
+use tonlib_client::client::AccountStateStream;
+use tonlib_core::TonAddress;
+
+mod common;
+
+#[tokio::test]
+pub async fn account_state_stream_works() {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let address: TonAddress =
+        assert_ok!("EQCD39VS5jcptHL8vMjEXrzGaRcCVYto7HUn4bpAOg8xqB2N".parse());
+    let mut stream = AccountStateStream::new(
+        &client,
+        std::slice::from_ref(&address),
+        Duration::from_secs(1),
+    );
+    let change = assert_ok!(stream.next().await);
+    log::info!("{:?}", change);
+    assert_eq!(change.address, address);
+    assert!(change.previous.is_none());
+}
@@ -1,3 +1,4 @@
+use futures::StreamExt;
 use tokio_test::assert_ok;
 use tonlib_client::client::{
     BlockStream, TonBlockFunctions, TonClientInterface, TonConnection, TonConnectionParams,
@@ -30,6 +31,31 @@ pub async fn block_listener_works() {
     }
 }
 
+#[tokio::test]
+pub async fn block_stream_with_transactions_resumes_from_checkpoint() {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let (_, mc_info) = assert_ok!(client.get_masterchain_info().await);
+    let seqno = mc_info.last.seqno - 10;
+    let mut block_stream = BlockStream::new(&client, seqno);
+    let first = assert_ok!(block_stream.next_with_transactions().await);
+    assert_eq!(first.block.master_shard.seqno, seqno);
+    assert_eq!(first.transactions[0].0, first.block.master_shard);
+    assert_eq!(first.transactions.len(), first.block.shards.len() + 1);
+    let checkpoint = block_stream.next_seqno();
+    assert_eq!(checkpoint, seqno + 1);
+
+    let mut resumed = BlockStream::new(&client, checkpoint).into_stream();
+    let second = assert_ok!(resumed.next().await.unwrap());
+    assert_eq!(second.block.master_shard.seqno, checkpoint);
+    let first_shards: Vec<_> = first.block.shards.iter().map(|s| s.to_block_id()).collect();
+    assert!(second
+        .block
+        .shards
+        .iter()
+        .all(|s| !first_shards.contains(&s.to_block_id())));
+}
+
 #[tokio::test]
 pub async fn block_listener_get_block_header() {
     common::init_logging();
@@ -0,0 +1,89 @@
+#[cfg(feature = "blocking")]
+use tokio_test::assert_ok;
+#[cfg(feature = "blocking")]
+use tonlib_client::client::blocking::BlockingTonConnection;
+#[cfg(feature = "blocking")]
+use tonlib_client::client::{
+    TonClientError, TonConnectionParams, DEFAULT_CONNECTION_PARAMS, LOGGING_CONNECTION_CALLBACK,
+};
+#[cfg(feature = "blocking")]
+use tonlib_client::tl::{KeyStoreType, TonFunction, TonResult};
+#[cfg(feature = "blocking")]
+use tonlib_client::types::TonMethodId;
+#[cfg(feature = "blocking")]
+use tonlib_core::TonAddress;
+
+mod common;
+
+#[test]
+#[cfg(feature = "blocking")]
+fn test_blocking_connection_init_and_get_method() {
+    common::init_logging();
+    let conn = assert_ok!(BlockingTonConnection::new(
+        LOGGING_CONNECTION_CALLBACK.clone(),
+        &DEFAULT_CONNECTION_PARAMS,
+    ));
+    assert_ok!(conn.init(
+        &common::MAINNET_CONFIG,
+        None,
+        false,
+        false,
+        KeyStoreType::InMemory
+    ));
+    let result = assert_ok!(conn.invoke(&TonFunction::GetLogVerbosityLevel {}));
+    assert!(matches!(result, TonResult::LogVerbosityLevel(_)));
+
+    let address = assert_ok!(TonAddress::from_base64_url(
+        "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR"
+    ));
+    let state = assert_ok!(conn.smc_load(&address));
+    let result =
+        assert_ok!(conn.smc_run_get_method(state.id, &TonMethodId::from("get_jetton_data"), &[]));
+    assert_eq!(result.exit_code, 0);
+    assert_ok!(conn.smc_forget(state.id));
+}
+
+#[test]
+#[cfg(feature = "blocking")]
+fn test_blocking_connection_connect() {
+    common::init_logging();
+    let params = TonConnectionParams {
+        config: common::MAINNET_CONFIG.to_string(),
+        ..Default::default()
+    };
+    let conn = assert_ok!(BlockingTonConnection::connect(
+        &params,
+        LOGGING_CONNECTION_CALLBACK.clone()
+    ));
+    assert_ok!(conn.invoke(&TonFunction::GetLogVerbosityLevel {}));
+}
+
+#[tokio::test]
+#[cfg(feature = "blocking")]
+async fn test_blocking_connection_in_async_context() {
+    let result = BlockingTonConnection::connect(
+        &DEFAULT_CONNECTION_PARAMS,
+        LOGGING_CONNECTION_CALLBACK.clone(),
+    );
+    assert!(matches!(result, Err(TonClientError::InternalError(_))));
+}
+
+#[test]
+#[cfg(feature = "blocking")]
+fn test_blocking_client() {
+    use tonlib_client::client::blocking::TonClient;
+    use tonlib_client::client::TonClientBuilder;
+
+    common::init_logging();
+    let client = assert_ok!(TonClient::new(
+        TonClientBuilder::new().with_config(&common::MAINNET_CONFIG)
+    ));
+    let info = assert_ok!(client.get_masterchain_info());
+    assert!(info.last.seqno > 0);
+
+    let address = assert_ok!(TonAddress::from_base64_url(
+        "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR"
+    ));
+    let result = assert_ok!(client.run_get_method(&address, "get_jetton_data", &[]));
+    assert_eq!(result.vm_exit_code, 0);
+}
@@ -7,13 +7,14 @@ use std::time::Duration;
 use base64::engine::general_purpose::STANDARD;
 use base64::Engine;
 use futures::future::join_all;
+use num_bigint::BigUint;
 use tokio::time::timeout;
 use tokio::{self};
 use tokio_test::assert_ok;
 use tonlib_client::client::{
-    TonBlockFunctions, TonClient, TonClientBuilder, TonClientInterface, TxId,
+    AccountStatus, TonBlockFunctions, TonClient, TonClientBuilder, TonClientInterface, TxId,
 };
-use tonlib_client::config::{MAINNET_CONFIG, TESTNET_CONFIG};
+use tonlib_client::config::{MAINNET_CONFIG, MAINNET_CONFIG_URL, TESTNET_CONFIG};
 use tonlib_client::contract::{TonContractFactory, TonContractInterface};
 use tonlib_client::tl::{
     BlockId, BlockIdExt, BlocksShards, BlocksTransactions, BlocksTransactionsExt,
@@ -22,7 +23,14 @@ use tonlib_client::tl::{
 };
 use tonlib_core::cell::dict::predefined_readers::{key_reader_256bit, val_reader_cell};
 use tonlib_core::cell::BagOfCells;
+use tonlib_core::config::{
+    ConfigParam, CONFIG_PARAM_CURRENT_VALIDATORS, CONFIG_PARAM_ELECTOR_ADDRESS,
+    CONFIG_PARAM_MSG_FORWARD_PRICES,
+};
+use tonlib_core::message::{CommonMsgInfo, TonMessage, TransferMessage};
+use tonlib_core::mnemonic::Mnemonic;
 use tonlib_core::types::ZERO_HASH;
+use tonlib_core::wallet::{TonWallet, WalletVersion};
 use tonlib_core::{TonAddress, TonTxId};
 
 mod common;
@@ -35,6 +43,33 @@ async fn test_client_get_masterchain_info() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_client_reconfigure() -> anyhow::Result<()> {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let (_, before) = client.get_masterchain_info().await?;
+    client.reconfigure(MAINNET_CONFIG).await?;
+    let (_, after) = client.get_masterchain_info().await?;
+    assert!(after.last.seqno >= before.last.seqno);
+    client.reconfigure_from_url(MAINNET_CONFIG_URL).await?;
+    let (_, _) = client.get_masterchain_info().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_get_masterchain_time() -> anyhow::Result<()> {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let now = client.get_masterchain_time().await?;
+    log::info!("Masterchain time: {}", now);
+    assert!(now > 1_700_000_000);
+    let expire_at = client
+        .get_message_expire_at(Duration::from_secs(60))
+        .await?;
+    assert!(expire_at >= now + 60);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_client_get_account_state_of_inactive() -> anyhow::Result<()> {
     common::init_logging();
@@ -189,6 +224,38 @@ async fn client_smc_get_code_works() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn client_get_account_info_works() -> anyhow::Result<()> {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let user_friendly = "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR";
+    let info = assert_ok!(client.get_account_info(user_friendly).await);
+    assert_eq!(info.status, AccountStatus::Active);
+    assert!(info.code.is_some());
+    assert!(info.data.is_some());
+    let raw = info.address.to_hex();
+    let info_by_raw = assert_ok!(client.get_account_info(&raw).await);
+    assert_eq!(info_by_raw.address, info.address);
+    Ok(())
+}
+
+#[tokio::test]
+async fn client_smc_forget_works() -> anyhow::Result<()> {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let address = &assert_ok!(TonAddress::from_base64_url(
+        "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR"
+    ));
+    let loaded_state = assert_ok!(client.smc_load(address).await);
+    assert_ok!(loaded_state.forget().await);
+    assert!(loaded_state
+        .conn
+        .smc_get_code(loaded_state.id)
+        .await
+        .is_err());
+    Ok(())
+}
+
 #[tokio::test]
 async fn client_smc_get_data_works() -> anyhow::Result<()> {
     common::init_logging();
@@ -232,6 +299,20 @@ async fn client_get_block_header_works() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_client_lookup_block_by_seqno() -> anyhow::Result<()> {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let (_, info) = assert_ok!(client.get_masterchain_info().await);
+    let block_id_ext = assert_ok!(
+        client
+            .lookup_block_by_seqno(-1, i64::MIN, info.last.seqno)
+            .await
+    );
+    assert_eq!(block_id_ext, info.last);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_client_blocks_get_transactions() -> anyhow::Result<()> {
     common::init_logging();
@@ -342,6 +423,40 @@ async fn test_get_config_param() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_get_parsed_config_param() -> anyhow::Result<()> {
+    common::init_logging();
+    let client = &common::new_mainnet_client().await;
+    let param = assert_ok!(
+        client
+            .get_parsed_config_param(CONFIG_PARAM_CURRENT_VALIDATORS)
+            .await
+    );
+    let ConfigParam::CurrentValidators(validators) = param else {
+        panic!("Expected validator set, got {:?}", param);
+    };
+    assert_eq!(validators.validators.len(), validators.total as usize);
+    assert!(validators.main <= validators.total);
+
+    let param = assert_ok!(
+        client
+            .get_parsed_config_param(CONFIG_PARAM_MSG_FORWARD_PRICES)
+            .await
+    );
+    let ConfigParam::MsgForwardPrices(prices) = param else {
+        panic!("Expected forward prices, got {:?}", param);
+    };
+    assert!(prices.compute_fwd_fee(0, 0) >= prices.lump_price);
+
+    let param = assert_ok!(
+        client
+            .get_parsed_config_param(CONFIG_PARAM_ELECTOR_ADDRESS)
+            .await
+    );
+    assert!(matches!(param, ConfigParam::ElectorAddress(_)));
+    Ok(())
+}
+
 #[tokio::test]
 pub async fn test_get_block_header() -> anyhow::Result<()> {
     common::init_logging();
@@ -677,3 +792,27 @@ async fn archive_node_client_test() -> anyhow::Result<()> {
     log::info!("master_info: {:?}", master_info);
     Ok(())
 }
+
+#[tokio::test]
+async fn test_estimate_fees_of_wallet_message() -> anyhow::Result<()> {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let mnemonic = Mnemonic::from_str(
+        "fancy carpet hello mandate penalty trial consider property top vicious exit rebuild \
+        tragic profit urban major total month holiday sudden rib gather media vicious",
+        &None,
+    )?;
+    let key_pair = mnemonic.to_key_pair()?;
+    let wallet = TonWallet::derive_default(WalletVersion::V4R2, &key_pair)?;
+    let transfer = TransferMessage::new(CommonMsgInfo::new_default_internal(
+        &wallet.address,
+        &BigUint::from(1_000_000u32),
+    ))
+    .build()?;
+    let message = wallet.create_external_message(u32::MAX, 0, [Arc::new(transfer)], true)?;
+    let conn = assert_ok!(client.get_connection().await);
+    let fees = assert_ok!(conn.estimate_fees(&message, true).await);
+    log::info!("{:?}", fees);
+    assert!(fees.source_fees.total() > 0);
+    Ok(())
+}
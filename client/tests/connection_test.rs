@@ -3,16 +3,20 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use tokio_test::assert_ok;
+use tonlib_client::client::keys::TonKeyStore;
 use tonlib_client::client::{
-    MultiConnectionCallback, TonClientError, TonClientInterface, TonConnection,
-    TonConnectionCallback, DEFAULT_CONNECTION_PARAMS, LOGGING_CONNECTION_CALLBACK,
-    NOOP_CONNECTION_CALLBACK,
+    MultiConnectionCallback, RunLoopHandle, TonClientError, TonClientInterface, TonConnection,
+    TonConnectionCallback, TonConnectionParams, DEFAULT_CONNECTION_PARAMS, HEALTH_CHECK_TIMEOUT,
+    LOGGING_CONNECTION_CALLBACK, NOOP_CONNECTION_CALLBACK,
 };
 use tonlib_client::config::MAINNET_CONFIG;
 use tonlib_client::tl::{
-    KeyStoreType, SyncState, TonFunction, TonNotification, TonResult, UpdateSyncState,
+    AccountAddress, KeyStoreType, SyncState, TonFunction, TonNotification, TonResult,
+    UpdateSyncState,
 };
-use tonlib_core::TonAddress;
+use tonlib_core::mnemonic::Mnemonic;
+use tonlib_core::wallet::Signer;
+use tonlib_core::{TonAddress, TonHash};
 
 mod common;
 
@@ -136,3 +140,314 @@ async fn test_connection_sync() {
     assert_ok!(r);
     assert!(flag.load(Ordering::Acquire));
 }
+
+#[tokio::test]
+async fn test_connection_request_timeout() {
+    common::init_logging();
+    let mut params = DEFAULT_CONNECTION_PARAMS.clone();
+    params.request_timeout = Some(Duration::from_millis(1));
+    let conn = assert_ok!(TonConnection::new(
+        LOGGING_CONNECTION_CALLBACK.clone(),
+        &params
+    ));
+    // Init may or may not fit into the timeout, its late result must be discarded gracefully
+    let _ = conn
+        .init(MAINNET_CONFIG, None, false, false, KeyStoreType::InMemory)
+        .await;
+    let r = conn.sync().await.map(|(_, block_id)| block_id);
+    log::info!("{:?}", r);
+    assert!(matches!(r, Err(TonClientError::Timeout { .. })));
+    tokio::time::sleep(Duration::from_secs(2)).await;
+}
+
+#[tokio::test]
+async fn test_connection_notification_queue_length() {
+    common::init_logging();
+    let mut params = DEFAULT_CONNECTION_PARAMS.clone();
+    params.notification_queue_length = 4;
+    let conn =
+        assert_ok!(TonConnection::connect(&params, LOGGING_CONNECTION_CALLBACK.clone()).await);
+    assert_eq!(conn.notification_queue_capacity(), 4);
+    let _receiver = conn.subscribe();
+    assert_ok!(conn.sync().await.map(|(_, block_id)| block_id));
+    assert!(conn.notification_queue_len() <= 4);
+}
+
+#[tokio::test]
+async fn test_connection_loop_exits_after_drop() {
+    common::init_logging();
+    let (conn, join_handle) = assert_ok!(
+        TonConnection::connect_joinable(
+            &DEFAULT_CONNECTION_PARAMS,
+            LOGGING_CONNECTION_CALLBACK.clone()
+        )
+        .await
+    );
+    drop(conn);
+    // The loop notices the drop after the current receive(1.0) call returns
+    for _ in 0..30 {
+        if join_handle.is_finished() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(join_handle.is_finished());
+    assert_ok!(join_handle.join());
+}
+
+#[derive(Default)]
+struct EventRecordingCallback {
+    events: std::sync::Mutex<Vec<(&'static str, u32)>>,
+}
+
+#[allow(unused_variables)]
+impl TonConnectionCallback for EventRecordingCallback {
+    fn on_invoke(&self, tag: &str, request_id: u32, function: &TonFunction) {
+        self.events.lock().unwrap().push(("invoke", request_id));
+    }
+
+    fn on_invoke_result(
+        &self,
+        tag: &str,
+        request_id: u32,
+        method: &str,
+        duration: &Duration,
+        result: &Result<TonResult, TonClientError>,
+    ) {
+        self.events.lock().unwrap().push(("result", request_id));
+    }
+
+    fn on_slow_invoke(&self, tag: &str, request_id: u32, method: &str, duration: &Duration) {
+        self.events.lock().unwrap().push(("slow", request_id));
+    }
+}
+
+#[tokio::test]
+async fn test_connection_invoke_batch() {
+    common::init_logging();
+    let callback = Arc::new(EventRecordingCallback::default());
+    let conn =
+        assert_ok!(TonConnection::connect(&DEFAULT_CONNECTION_PARAMS, callback.clone()).await);
+    callback.events.lock().unwrap().clear();
+
+    let addresses = [
+        "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR",
+        "EQCDM_QGggZ3qMa_f3lRPk4_qLDnLTqdi6OkMAV2NB9r5TG3",
+    ];
+    let mut functions = vec![TonFunction::GetLogVerbosityLevel {}];
+    for address in addresses {
+        let address = assert_ok!(TonAddress::from_base64_url(address));
+        functions.push(TonFunction::RawGetAccountState {
+            account_address: AccountAddress {
+                account_address: address.to_hex(),
+            },
+        });
+    }
+    functions.push(TonFunction::GetLogVerbosityLevel {});
+
+    let results = conn.invoke_batch(&functions).await;
+    assert_eq!(results.len(), functions.len());
+    assert!(matches!(results[0], Ok(TonResult::LogVerbosityLevel(_))));
+    assert!(matches!(results[1], Ok(TonResult::RawFullAccountState(_))));
+    assert!(matches!(results[2], Ok(TonResult::RawFullAccountState(_))));
+    assert!(matches!(results[3], Ok(TonResult::LogVerbosityLevel(_))));
+
+    // All functions are sent before any result is received
+    let events = callback.events.lock().unwrap();
+    let first_result = events.iter().position(|(e, _)| *e == "result").unwrap();
+    assert_eq!(first_result, functions.len());
+}
+
+#[tokio::test]
+async fn test_connection_loop_on_blocking_pool() {
+    common::init_logging();
+    let mut params = DEFAULT_CONNECTION_PARAMS.clone();
+    params.run_loop_on_blocking_pool = true;
+    let (conn, join_handle) = assert_ok!(
+        TonConnection::connect_joinable(&params, LOGGING_CONNECTION_CALLBACK.clone()).await
+    );
+    assert!(matches!(join_handle, RunLoopHandle::Task(_)));
+    assert_ok!(conn.get_masterchain_info().await.map(|(_, info)| info));
+    drop(conn);
+    for _ in 0..30 {
+        if join_handle.is_finished() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert!(join_handle.is_finished());
+}
+
+#[test]
+fn test_connection_loop_on_blocking_pool_requires_runtime() {
+    let mut params = DEFAULT_CONNECTION_PARAMS.clone();
+    params.run_loop_on_blocking_pool = true;
+    let result = TonConnection::new(NOOP_CONNECTION_CALLBACK.clone(), &params);
+    assert!(matches!(result, Err(TonClientError::InternalError(_))));
+}
+
+#[tokio::test]
+async fn test_connection_invoke_cancellable() {
+    common::init_logging();
+    let conn = assert_ok!(
+        TonConnection::connect(
+            &DEFAULT_CONNECTION_PARAMS,
+            LOGGING_CONNECTION_CALLBACK.clone()
+        )
+        .await
+    );
+    let result = conn
+        .invoke_cancellable(&TonFunction::Sync {}, std::future::ready(()))
+        .await;
+    assert!(matches!(result, Err(TonClientError::Cancelled { .. })));
+    assert_eq!(conn.in_flight(), 0);
+
+    // Not cancelled, completes normally
+    let result = conn
+        .invoke_cancellable(&TonFunction::Sync {}, std::future::pending())
+        .await;
+    assert!(matches!(result, Ok(TonResult::BlockIdExt(_))));
+    assert_eq!(conn.in_flight(), 0);
+}
+
+#[tokio::test]
+async fn test_connection_slow_invoke() {
+    common::init_logging();
+    for (threshold, expect_slow) in [(Duration::ZERO, true), (Duration::from_secs(3600), false)] {
+        let callback = Arc::new(EventRecordingCallback::default());
+        let params = TonConnectionParams {
+            slow_request_threshold: Some(threshold),
+            ..DEFAULT_CONNECTION_PARAMS.clone()
+        };
+        let conn = assert_ok!(TonConnection::connect(&params, callback.clone()).await);
+        callback.events.lock().unwrap().clear();
+
+        assert_ok!(conn.get_masterchain_info().await);
+        let events = callback.events.lock().unwrap();
+        let results = events.iter().filter(|(e, _)| *e == "result").count();
+        let slow = events.iter().filter(|(e, _)| *e == "slow").count();
+        assert_eq!(results, 1);
+        assert_eq!(slow, if expect_slow { 1 } else { 0 });
+    }
+}
+
+#[tokio::test]
+async fn test_connection_health_check() {
+    common::init_logging();
+    let conn = assert_ok!(
+        TonConnection::connect(
+            &DEFAULT_CONNECTION_PARAMS,
+            LOGGING_CONNECTION_CALLBACK.clone()
+        )
+        .await
+    );
+    conn.sync().await.unwrap();
+    let status = assert_ok!(conn.health_check().await);
+    log::info!("{:?}", status);
+    assert!(status.synced);
+    assert!(status.masterchain_seqno > 0);
+    assert!(status.latency < HEALTH_CHECK_TIMEOUT);
+}
+
+#[tokio::test]
+async fn test_connection_import_key_from_mnemonic() {
+    common::init_logging();
+    let words: Vec<String> = "dose ice enrich trigger test dove century still betray gas diet dune use other base gym mad law immense village world example praise game"
+        .split(' ')
+        .map(String::from)
+        .collect();
+    let keystore_dir = std::env::temp_dir().join("tonlib-rs-import-key-test");
+    std::fs::create_dir_all(&keystore_dir).unwrap();
+    for keystore_dir in [None, Some(keystore_dir.to_string_lossy().to_string())] {
+        let params = TonConnectionParams {
+            keystore_dir,
+            ..DEFAULT_CONNECTION_PARAMS.clone()
+        };
+        let conn =
+            assert_ok!(TonConnection::connect(&params, LOGGING_CONNECTION_CALLBACK.clone()).await);
+        let key = assert_ok!(
+            conn.import_key_from_mnemonic(&words, b"local password")
+                .await
+        );
+        assert_eq!(
+            key.public_key,
+            "PubAStGIXBJ_6GOrsAdS-oROZDm7BPJk1w3nzqWAsyY3q6IK"
+        );
+        let exported = assert_ok!(conn.export_key(&key, b"local password").await);
+        assert_eq!(exported, words);
+        assert!(conn.export_key(&key, b"wrong password").await.is_err());
+        assert_ok!(conn.delete_key(&key).await);
+    }
+}
+
+#[tokio::test]
+async fn test_keystore_sign_with_key() {
+    common::init_logging();
+    let words = "dose ice enrich trigger test dove century still betray gas diet dune use other base gym mad law immense village world example praise game";
+    let conn = assert_ok!(
+        TonConnection::connect(
+            &DEFAULT_CONNECTION_PARAMS,
+            LOGGING_CONNECTION_CALLBACK.clone()
+        )
+        .await
+    );
+    let keystore = TonKeyStore::new(conn, b"local password");
+    let word_list: Vec<String> = words.split(' ').map(String::from).collect();
+    let key = assert_ok!(keystore.import_key(&word_list, &[]).await);
+    assert_eq!(assert_ok!(keystore.export_key(&key).await), word_list);
+
+    let hash: TonHash = [7u8; 32];
+    let key_pair = assert_ok!(Mnemonic::from_str(words, &None).and_then(|m| m.to_key_pair()));
+    let expected = assert_ok!(key_pair.sign(&hash).await);
+    assert_eq!(
+        assert_ok!(keystore.signer(&key).sign(&hash).await),
+        expected
+    );
+
+    let wrong_password = TonKeyStore::new(keystore.connection().clone(), b"wrong password");
+    assert!(wrong_password
+        .sign_with_key(&key, &[1, 2, 3])
+        .await
+        .is_err());
+    assert_ok!(keystore.delete_key(&key).await);
+}
+
+#[derive(Default)]
+struct LoopStartCountingCallback {
+    starts: AtomicU32,
+}
+
+impl TonConnectionCallback for LoopStartCountingCallback {
+    fn on_connection_loop_start(&self, _tag: &str) {
+        self.starts.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[tokio::test]
+async fn test_connection_probe_skips_unreachable_liteserver() {
+    common::init_logging();
+    let mut config: serde_json::Value = serde_json::from_str(MAINNET_CONFIG).unwrap();
+    let liteservers = config["liteservers"].as_array_mut().unwrap();
+    // Nothing listens on 127.0.0.1:1
+    let mut unreachable = liteservers[0].clone();
+    unreachable["ip"] = serde_json::Value::from(2130706433);
+    unreachable["port"] = serde_json::Value::from(1);
+    liteservers.insert(0, unreachable);
+    let params = TonConnectionParams {
+        config: config.to_string(),
+        liteserver_indices: Some(vec![0, 1]),
+        connect_probe_attempts: Some(3),
+        ..DEFAULT_CONNECTION_PARAMS.clone()
+    };
+    let callback = Arc::new(LoopStartCountingCallback::default());
+    let conn = assert_ok!(TonConnection::connect(&params, callback.clone()).await);
+    assert_eq!(callback.starts.load(Ordering::SeqCst), 2);
+    assert_ok!(conn.get_masterchain_info().await);
+
+    let params = TonConnectionParams {
+        liteserver_indices: Some(vec![0]),
+        connect_probe_attempts: Some(2),
+        ..params
+    };
+    assert!(TonConnection::connect(&params, callback).await.is_err());
+}
@@ -0,0 +1,36 @@
+use tonlib_client::contract::{DnsResolver, TonContractFactory};
+use tonlib_core::dns::DnsCategory;
+use tonlib_core::TonAddress;
+
+mod common;
+
+#[tokio::test]
+async fn test_resolve_wallet() -> anyhow::Result<()> {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let factory = TonContractFactory::builder(&client).build().await?;
+    let resolver = DnsResolver::mainnet(&factory);
+
+    let wallet = resolver.resolve_wallet("foundation.ton").await?;
+    assert!(wallet.is_some());
+    assert_ne!(wallet, Some(TonAddress::NULL));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_resolve_missing_domain() -> anyhow::Result<()> {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let factory = TonContractFactory::builder(&client).build().await?;
+    let resolver = DnsResolver::mainnet(&factory);
+
+    let record = resolver
+        .resolve(
+            "this-domain-should-never-be-registered-42.ton",
+            &DnsCategory::Wallet,
+        )
+        .await?;
+    assert_eq!(record, None);
+    assert!(resolver.resolve("", &DnsCategory::Wallet).await.is_err());
+    Ok(())
+}
@@ -1,7 +1,13 @@
+use num_bigint::BigUint;
 use sha2::{Digest, Sha256};
 use tokio_test::assert_ok;
-use tonlib_client::contract::{JettonMasterContract, TonContractFactory};
+use tonlib_client::contract::{
+    predict_jetton_wallet_address, JettonData, JettonMasterContract, JettonWalletAddressSource,
+    JettonWalletLayout, TonContractError, TonContractFactory, WalletData,
+};
 use tonlib_client::meta::{JettonMetaLoader, LoadMeta, MetaDataContent};
+use tonlib_client::types::TvmStackEntry;
+use tonlib_core::cell::CellBuilder;
 use tonlib_core::{TonAddress, TonHash};
 
 mod common;
@@ -131,6 +137,56 @@ async fn test_get_wallet_address() {
     );
 }
 
+#[tokio::test]
+async fn test_predict_jetton_wallet_address() -> anyhow::Result<()> {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let factory = TonContractFactory::builder(&client).build().await?;
+    let master_address: TonAddress = "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR".parse()?;
+    let contract = factory.get_contract(&master_address);
+    let owner_address: TonAddress = "EQB2BtXDXaQuIcMYW7JEWhHmwHfPPwa-eoCdefiAxOhU3pQg".parse()?;
+    let expected: TonAddress = "EQCGY3OVLtD9KRcOsP2ldQDtuY0FMzV7wPoxjrFbayBXc23c".parse()?;
+
+    let wallet_code = contract.get_jetton_data().await?.wallet_code;
+    let predicted = predict_jetton_wallet_address(&wallet_code, &master_address, &owner_address)?;
+    assert_eq!(predicted, expected);
+
+    let other_owner: TonAddress = "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR".parse()?;
+    let owners = [owner_address, other_owner.clone()];
+    let (addresses, source) = contract.get_wallet_addresses(&owners).await?;
+    assert_eq!(source, JettonWalletAddressSource::Predicted);
+    assert_eq!(addresses[0], expected);
+    assert_eq!(
+        addresses[1],
+        contract.get_wallet_address(&other_owner).await?
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_predict_governance_jetton_wallet_address() -> anyhow::Result<()> {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let factory = TonContractFactory::builder(&client).build().await?;
+    let master_address: TonAddress = "EQCxE6mUtQJKFnGfaROTKOt1lZbDiiX1kCixRv7Nw2Id_sDs".parse()?; // USDT
+    let contract = factory.get_contract(&master_address);
+    let owner_address: TonAddress = "EQB2BtXDXaQuIcMYW7JEWhHmwHfPPwa-eoCdefiAxOhU3pQg".parse()?;
+
+    let jetton_data = contract.get_jetton_data().await?;
+    let predicted = jetton_data.predict_wallet_address(
+        JettonWalletLayout::Governance,
+        &master_address,
+        &owner_address,
+    )?;
+    assert_eq!(
+        predicted,
+        contract.get_wallet_address(&owner_address).await?
+    );
+    let (_, source) = contract.get_wallet_addresses(&[owner_address]).await?;
+    assert_eq!(source, JettonWalletAddressSource::Predicted);
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_get_jetton_data_invalid_utf8_sequence() {
     common::init_logging();
@@ -185,3 +241,82 @@ async fn test_jetton_image_data() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_jetton_data_from_stack() -> anyhow::Result<()> {
+    // Synthetic stack in the layout of get_jetton_data, with addresses of the Moon jetton
+    // master and a placeholder wallet code instead of a captured response
+    let master: TonAddress = "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR".parse()?;
+    let admin: TonAddress = "EQB3ncyBUTjZUA5EnFKR5_EnOMI9V1tTEAAPaiU71gc4TiUt".parse()?;
+    let content = CellBuilder::new()
+        .store_byte(1)?
+        .store_string("https://tarantini.dev/ston/moon.json")?
+        .build()?;
+    let wallet_code = CellBuilder::new().store_u32(32, 0xdeadbeef)?.build()?;
+    let stack = vec![
+        TvmStackEntry::number(BigUint::from(1_000_000_000_000_000_000u64)),
+        TvmStackEntry::from(-1),
+        TvmStackEntry::address(&admin)?,
+        TvmStackEntry::Cell(content.to_arc()),
+        TvmStackEntry::Cell(wallet_code.clone().to_arc()),
+    ];
+
+    let data = JettonData::from_stack(&master, &stack)?;
+    assert_eq!(
+        data.total_supply,
+        BigUint::from(1_000_000_000_000_000_000u64)
+    );
+    assert!(data.mintable);
+    assert_eq!(data.admin_address, admin);
+    assert_eq!(
+        data.content,
+        MetaDataContent::External {
+            uri: "https://tarantini.dev/ston/moon.json".to_string()
+        }
+    );
+    assert_eq!(data.wallet_code.as_ref(), &wallet_code);
+
+    match JettonData::from_stack(&master, &stack[..4]) {
+        Err(TonContractError::InvalidMethodResultStackSize {
+            actual, expected, ..
+        }) => assert_eq!((actual, expected), (4, 5)),
+        r => panic!("Unexpected result: {:?}", r),
+    }
+    let mut swapped = stack.clone();
+    swapped.swap(1, 2);
+    assert!(matches!(
+        JettonData::from_stack(&master, &swapped),
+        Err(TonContractError::MethodResultStackError { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn test_wallet_data_from_stack() -> anyhow::Result<()> {
+    // Synthetic stack in the layout of get_wallet_data with a placeholder wallet code
+    let wallet: TonAddress = "EQAW42HutyDem98Be1f27PoXobghh81umTQ-cGgaKVmRLS7-".parse()?;
+    let owner: TonAddress = "EQB3ncyBUTjZUA5EnFKR5_EnOMI9V1tTEAAPaiU71gc4TiUt".parse()?;
+    let master: TonAddress = "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR".parse()?;
+    let wallet_code = CellBuilder::new().store_u32(32, 0xdeadbeef)?.build()?;
+    let mut stack = vec![
+        TvmStackEntry::from(123_456_789),
+        TvmStackEntry::address(&owner)?,
+        TvmStackEntry::address(&master)?,
+        TvmStackEntry::Cell(wallet_code.clone().to_arc()),
+    ];
+
+    let data = WalletData::from_stack(&wallet, &stack)?;
+    assert_eq!(data.balance, BigUint::from(123_456_789u32));
+    assert_eq!(data.owner_address, owner);
+    assert_eq!(data.master_address, master);
+    assert_eq!(data.wallet_code.as_ref(), &wallet_code);
+
+    // trailing non-standard entries are ignored
+    stack.push(TvmStackEntry::from(0));
+    assert_eq!(
+        WalletData::from_stack(&wallet, &stack)?.balance,
+        data.balance
+    );
+    assert!(WalletData::from_stack(&wallet, &stack[..3]).is_err());
+    Ok(())
+}
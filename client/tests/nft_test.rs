@@ -1,8 +1,14 @@
+use num_bigint::BigUint;
 use sha2::{Digest, Sha256};
 use tokio_test::assert_ok;
-use tonlib_client::contract::{NftCollectionContract, NftItemContract, TonContractFactory};
+use tonlib_client::contract::{
+    predict_nft_item_address, NftCollectionContract, NftItemContract, NftRoyaltyParams,
+    RawNftItemData, TonContractError, TonContractFactory,
+};
 use tonlib_client::meta::{LoadMeta, MetaDataContent, NftColletionMetaLoader, NftItemMetaLoader};
-use tonlib_core::TonHash;
+use tonlib_client::types::TvmStackEntry;
+use tonlib_core::cell::{BagOfCells, CellBuilder};
+use tonlib_core::{TonAddress, TonHash};
 
 mod common;
 
@@ -40,6 +46,111 @@ async fn test_get_nft_address_by_index() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_get_nft_royalty_params() -> anyhow::Result<()> {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let factory = TonContractFactory::builder(&client).build().await?;
+    let contract =
+        factory.get_contract(&"EQB2iHQ9lmJ9zvYPauxN9hVOfHL3c_fuN5AyRq5Pm84UH6jC".parse()?);
+    let params = contract.royalty_params().await?;
+    assert!(params.numerator <= params.denominator);
+    Ok(())
+}
+
+#[test]
+fn test_nft_royalty_params_from_stack() -> anyhow::Result<()> {
+    let collection: TonAddress = "EQB2iHQ9lmJ9zvYPauxN9hVOfHL3c_fuN5AyRq5Pm84UH6jC".parse()?;
+    let destination: TonAddress = "EQB3ncyBUTjZUA5EnFKR5_EnOMI9V1tTEAAPaiU71gc4TiUt".parse()?;
+    let stack = vec![
+        TvmStackEntry::from(5),
+        TvmStackEntry::from(100),
+        TvmStackEntry::address(&destination)?,
+    ];
+
+    let params = NftRoyaltyParams::from_stack(&collection, &stack)?;
+    assert_eq!(
+        params,
+        NftRoyaltyParams {
+            numerator: 5,
+            denominator: 100,
+            destination,
+        }
+    );
+    assert_eq!(
+        params.royalty(&BigUint::from(1_000_000_000u64)),
+        BigUint::from(50_000_000u64)
+    );
+
+    let mut broken = stack.clone();
+    broken[0] = TvmStackEntry::from(-1);
+    assert!(matches!(
+        NftRoyaltyParams::from_stack(&collection, &broken),
+        Err(TonContractError::MethodResultStackError { .. })
+    ));
+    assert!(NftRoyaltyParams::from_stack(&collection, &stack[1..]).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_predict_nft_item_address() -> anyhow::Result<()> {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let factory = TonContractFactory::builder(&client).build().await?;
+    let collection_address: TonAddress =
+        "EQB2iHQ9lmJ9zvYPauxN9hVOfHL3c_fuN5AyRq5Pm84UH6jC".parse()?;
+    let contract = factory.get_contract(&collection_address);
+    let state = factory
+        .get_latest_account_state(&collection_address)
+        .await?;
+    let data = BagOfCells::parse(state.data.as_slice())?;
+    // standard collection data: owner, next_item_index, ^content, ^nft_item_code, ^royalty_params
+    let nft_item_code = data.single_root()?.reference(1)?.clone();
+
+    let expected = contract.get_nft_address_by_index(2).await?;
+    let predicted = predict_nft_item_address(&nft_item_code, &collection_address, 2)?;
+    assert_eq!(predicted, expected);
+    Ok(())
+}
+
+#[test]
+fn test_raw_nft_item_data_from_stack() -> anyhow::Result<()> {
+    // Synthetic stack in the layout of get_nft_data of item #2 of the collection, content is
+    // relative to the collection base uri
+    let item: TonAddress = "EQBKwtMZSZurMxGp7FLZ_lM9t54_ECEsS46NLR3qfIwwTnKW".parse()?;
+    let collection: TonAddress = "EQB2iHQ9lmJ9zvYPauxN9hVOfHL3c_fuN5AyRq5Pm84UH6jC".parse()?;
+    let owner: TonAddress = "EQB3ncyBUTjZUA5EnFKR5_EnOMI9V1tTEAAPaiU71gc4TiUt".parse()?;
+    let content = CellBuilder::new().store_string("2.json")?.build()?;
+    let stack = vec![
+        TvmStackEntry::from(-1),
+        TvmStackEntry::from(2),
+        TvmStackEntry::address(&collection)?,
+        TvmStackEntry::address(&owner)?,
+        TvmStackEntry::Cell(content.clone().to_arc()),
+    ];
+
+    let data = RawNftItemData::from_stack(&item, &stack)?;
+    assert!(data.init);
+    assert_eq!(data.index, BigUint::from(2u32));
+    assert_eq!(data.collection_address, collection);
+    assert_eq!(data.owner_address, owner);
+    assert_eq!(data.individual_content.as_ref(), &content);
+
+    match RawNftItemData::from_stack(&item, &stack[1..]) {
+        Err(TonContractError::InvalidMethodResultStackSize {
+            actual, expected, ..
+        }) => assert_eq!((actual, expected), (4, 5)),
+        r => panic!("Unexpected result: {:?}", r),
+    }
+    let mut broken = stack.clone();
+    broken[3] = TvmStackEntry::Null;
+    assert!(matches!(
+        RawNftItemData::from_stack(&item, &broken),
+        Err(TonContractError::MethodResultStackError { .. })
+    ));
+    Ok(())
+}
+
 // ---------------------nft get item metadata tests
 
 #[tokio::test]
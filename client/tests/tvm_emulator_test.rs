@@ -197,6 +197,24 @@ mod contract_emulator_tests {
         assert_eq!(result, expected);
     }
 
+    #[tokio::test]
+    async fn test_emulator_execute_get_method_on_cells() {
+        common::init_logging();
+        let code = assert_ok!(BagOfCells::parse(&TEST_CONTRACT_CODE));
+        let data = assert_ok!(BagOfCells::parse(&TEST_CONTRACT_DATA));
+        let stack = vec![3i64.into(), 5i64.into()];
+        let emulator_result = assert_ok!(TvmEmulator::execute_get_method(
+            assert_ok!(code.single_root()),
+            assert_ok!(data.single_root()),
+            None,
+            &"get_val".into(),
+            stack.as_slice()
+        ));
+        assert_eq!(emulator_result.vm_exit_code, 0);
+        let result = assert_ok!(emulator_result.stack[0].get_bigint());
+        assert_eq!(result, BigInt::from(15));
+    }
+
     #[tokio::test]
     async fn test_emulator_get_jetton_data() {
         common::init_logging();
@@ -0,0 +1,1323 @@
+//! Typed blocks, e.g. decoded from the `data` of `liteServer.getBlock`.
+//!
+//! See https://github.com/ton-blockchain/ton/blob/master/crypto/block/block.tlb for the schemas.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use num_bigint::BigUint;
+
+use crate::cell::dict::predefined_readers::{
+    key_reader_256bit, key_reader_u16, key_reader_u32, key_reader_u64, val_reader_ref_cell,
+};
+use crate::cell::dict::{KeyReader, ValReader};
+use crate::cell::{ArcCell, BagOfCells, Cell, CellParser, TonCellError};
+use crate::config::ConfigParam;
+use crate::constants::SHARD_FULL;
+use crate::tlb::{check_tag, TlbType};
+use crate::transaction::{load_maybe, sorted_values, CurrencyCollection, HashUpdate, Transaction};
+use crate::TonHash;
+
+/// Block of a masterchain or a shardchain, according to TL-B schema:
+///
+/// ```raw
+/// block#11ef55aa global_id:int32
+///   info:^BlockInfo value_flow:^ValueFlow
+///   state_update:^(MERKLE_UPDATE ShardState)
+///   extra:^BlockExtra = Block;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub global_id: i32,
+    pub info: BlockInfo,
+    /// Cell of `ValueFlow`.
+    pub value_flow: ArcCell,
+    /// Merkle update of the shard state, see `proof::MerkleUpdate`.
+    pub state_update: ArcCell,
+    pub extra: BlockExtra,
+}
+
+impl Block {
+    const TAG: u64 = 0x11ef55aa;
+
+    pub fn parse(cell: &Cell) -> Result<Block, TonCellError> {
+        cell.parse_fully(Self::read)
+    }
+
+    /// Parses the block from the single root of a serialized bag of cells.
+    pub fn from_boc(boc: &[u8]) -> Result<Block, TonCellError> {
+        Self::parse(BagOfCells::parse(boc)?.single_root()?)
+    }
+
+    pub fn read(parser: &mut CellParser) -> Result<Block, TonCellError> {
+        check_tag(parser, 32, Self::TAG, "Block")?;
+        Ok(Block {
+            global_id: parser.load_i32(32)?,
+            info: parser.next_reference()?.parse_fully(BlockInfo::read)?,
+            value_flow: parser.next_reference()?,
+            state_update: parser.next_reference()?,
+            extra: parser.next_reference()?.parse_fully(BlockExtra::read)?,
+        })
+    }
+
+    /// Parses all transactions of the block, ordered by their logical time.
+    pub fn transactions(&self) -> Result<Vec<Transaction>, TonCellError> {
+        let mut transactions = self
+            .extra
+            .account_blocks
+            .values()
+            .flat_map(|account_block| &account_block.transactions)
+            .map(|cell| Transaction::parse(cell))
+            .collect::<Result<Vec<_>, _>>()?;
+        transactions.sort_by_key(|transaction| transaction.lt);
+        Ok(transactions)
+    }
+}
+
+/// ```raw
+/// block_info#9bc7a987 version:uint32
+///   not_master:(## 1) after_merge:(## 1) before_split:(## 1) after_split:(## 1)
+///   want_split:Bool want_merge:Bool key_block:Bool vert_seqno_incr:(## 1)
+///   flags:(## 8) { flags <= 1 }
+///   seq_no:# vert_seq_no:# { vert_seq_no >= vert_seqno_incr }
+///   { prev_seq_no:# } { ~prev_seq_no + 1 = seq_no }
+///   shard:ShardIdent gen_utime:uint32
+///   start_lt:uint64 end_lt:uint64
+///   gen_validator_list_hash_short:uint32
+///   gen_catchain_seqno:uint32
+///   min_ref_mc_seqno:uint32
+///   prev_key_block_seqno:uint32
+///   gen_software:flags . 0?GlobalVersion
+///   master_ref:not_master?^BlkMasterInfo
+///   prev_ref:^(BlkPrevInfo after_merge)
+///   prev_vert_ref:vert_seqno_incr?^(BlkPrevInfo 0)
+///   = BlockInfo;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockInfo {
+    pub version: u32,
+    pub not_master: bool,
+    pub after_merge: bool,
+    pub before_split: bool,
+    pub after_split: bool,
+    pub want_split: bool,
+    pub want_merge: bool,
+    pub key_block: bool,
+    pub vert_seqno_incr: bool,
+    pub flags: u8,
+    pub seqno: u32,
+    pub vert_seqno: u32,
+    pub shard: ShardIdent,
+    /// Unix time the block was generated at.
+    pub gen_utime: u32,
+    pub start_lt: u64,
+    pub end_lt: u64,
+    pub gen_validator_list_hash_short: u32,
+    pub gen_catchain_seqno: u32,
+    pub min_ref_mc_seqno: u32,
+    pub prev_key_block_seqno: u32,
+    /// Version and capabilities of the validator software, if reported.
+    pub gen_software: Option<GlobalVersion>,
+    /// Last masterchain block known to the shardchain block, `None` for masterchain blocks.
+    pub master_ref: Option<ExtBlkRef>,
+    pub prev_ref: BlkPrevInfo,
+    pub prev_vert_ref: Option<ExtBlkRef>,
+}
+
+impl BlockInfo {
+    const TAG: u64 = 0x9bc7a987;
+
+    pub fn read(parser: &mut CellParser) -> Result<BlockInfo, TonCellError> {
+        check_tag(parser, 32, Self::TAG, "BlockInfo")?;
+        let version = parser.load_u32(32)?;
+        let not_master = parser.load_bit()?;
+        let after_merge = parser.load_bit()?;
+        let before_split = parser.load_bit()?;
+        let after_split = parser.load_bit()?;
+        let want_split = parser.load_bit()?;
+        let want_merge = parser.load_bit()?;
+        let key_block = parser.load_bit()?;
+        let vert_seqno_incr = parser.load_bit()?;
+        let flags = parser.load_u8(8)?;
+        let seqno = parser.load_u32(32)?;
+        let vert_seqno = parser.load_u32(32)?;
+        let shard = ShardIdent::read(parser)?;
+        let gen_utime = parser.load_u32(32)?;
+        let start_lt = parser.load_u64(64)?;
+        let end_lt = parser.load_u64(64)?;
+        let gen_validator_list_hash_short = parser.load_u32(32)?;
+        let gen_catchain_seqno = parser.load_u32(32)?;
+        let min_ref_mc_seqno = parser.load_u32(32)?;
+        let prev_key_block_seqno = parser.load_u32(32)?;
+        let gen_software = match flags & 1 {
+            0 => None,
+            _ => Some(GlobalVersion::read(parser)?),
+        };
+        let master_ref = match not_master {
+            false => None,
+            true => Some(parser.next_reference()?.parse_fully(ExtBlkRef::read)?),
+        };
+        let prev_ref = parser
+            .next_reference()?
+            .parse_fully(|parser| BlkPrevInfo::read(parser, after_merge))?;
+        let prev_vert_ref = match vert_seqno_incr {
+            false => None,
+            true => Some(parser.next_reference()?.parse_fully(ExtBlkRef::read)?),
+        };
+        Ok(BlockInfo {
+            version,
+            not_master,
+            after_merge,
+            before_split,
+            after_split,
+            want_split,
+            want_merge,
+            key_block,
+            vert_seqno_incr,
+            flags,
+            seqno,
+            vert_seqno,
+            shard,
+            gen_utime,
+            start_lt,
+            end_lt,
+            gen_validator_list_hash_short,
+            gen_catchain_seqno,
+            min_ref_mc_seqno,
+            prev_key_block_seqno,
+            gen_software,
+            master_ref,
+            prev_ref,
+            prev_vert_ref,
+        })
+    }
+}
+
+/// ```raw
+/// shard_ident$00 shard_pfx_bits:(#<= 60) workchain_id:int32 shard_prefix:uint64 = ShardIdent;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ShardIdent {
+    pub workchain: i32,
+    /// Shard id in the format of `BlockIdExt`, i.e. the shard prefix followed by a 1 bit.
+    pub shard: i64,
+}
+
+impl ShardIdent {
+    pub fn read(parser: &mut CellParser) -> Result<ShardIdent, TonCellError> {
+        check_tag(parser, 2, 0b00, "ShardIdent")?;
+        let prefix_bits = parser.load_u8(6)?;
+        if prefix_bits > 60 {
+            return Err(TonCellError::CellParserError(format!(
+                "Invalid shard prefix length: {prefix_bits}"
+            )));
+        }
+        let workchain = parser.load_i32(32)?;
+        let prefix = parser.load_u64(64)?;
+        let shard = prefix | (1 << (63 - prefix_bits));
+        Ok(ShardIdent {
+            workchain,
+            shard: shard as i64,
+        })
+    }
+}
+
+/// ```raw
+/// capabilities#c4 version:uint32 capabilities:uint64 = GlobalVersion;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GlobalVersion {
+    pub version: u32,
+    pub capabilities: u64,
+}
+
+impl GlobalVersion {
+    const TAG: u64 = 0xc4;
+
+    pub fn read(parser: &mut CellParser) -> Result<GlobalVersion, TonCellError> {
+        check_tag(parser, 8, Self::TAG, "GlobalVersion")?;
+        Ok(GlobalVersion {
+            version: parser.load_u32(32)?,
+            capabilities: parser.load_u64(64)?,
+        })
+    }
+}
+
+/// Reference to a block of the same workchain, or to a masterchain block in `master_ref`:
+///
+/// ```raw
+/// ext_blk_ref$_ end_lt:uint64 seq_no:uint32 root_hash:bits256 file_hash:bits256 = ExtBlkRef;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtBlkRef {
+    pub end_lt: u64,
+    pub seqno: u32,
+    pub root_hash: TonHash,
+    pub file_hash: TonHash,
+}
+
+impl ExtBlkRef {
+    pub fn read(parser: &mut CellParser) -> Result<ExtBlkRef, TonCellError> {
+        Ok(ExtBlkRef {
+            end_lt: parser.load_u64(64)?,
+            seqno: parser.load_u32(32)?,
+            root_hash: TonHash::read(parser)?,
+            file_hash: TonHash::read(parser)?,
+        })
+    }
+}
+
+/// ```raw
+/// prev_blk_info$_ prev:ExtBlkRef = BlkPrevInfo 0;
+/// prev_blks_info$_ prev1:^ExtBlkRef prev2:^ExtBlkRef = BlkPrevInfo 1;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlkPrevInfo {
+    Single(ExtBlkRef),
+    /// Both previous blocks of a block after the merge of two shards.
+    AfterMerge {
+        prev1: ExtBlkRef,
+        prev2: ExtBlkRef,
+    },
+}
+
+impl BlkPrevInfo {
+    pub fn read(parser: &mut CellParser, after_merge: bool) -> Result<BlkPrevInfo, TonCellError> {
+        if !after_merge {
+            return Ok(BlkPrevInfo::Single(ExtBlkRef::read(parser)?));
+        }
+        Ok(BlkPrevInfo::AfterMerge {
+            prev1: parser.next_reference()?.parse_fully(ExtBlkRef::read)?,
+            prev2: parser.next_reference()?.parse_fully(ExtBlkRef::read)?,
+        })
+    }
+}
+
+/// ```raw
+/// block_extra in_msg_descr:^InMsgDescr
+///   out_msg_descr:^OutMsgDescr
+///   account_blocks:^ShardAccountBlocks
+///   rand_seed:bits256
+///   created_by:bits256
+///   custom:(Maybe ^McBlockExtra) = BlockExtra;
+/// _ (HashmapAugE 256 InMsg ImportFees) = InMsgDescr;
+/// _ (HashmapAugE 256 OutMsg CurrencyCollection) = OutMsgDescr;
+/// _ (HashmapAugE 256 AccountBlock CurrencyCollection) = ShardAccountBlocks;
+/// ```
+///
+/// The extra values of the augmented dictionaries, i.e. the fees, are skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockExtra {
+    /// Inbound messages by the hashes of the messages.
+    pub in_msg_descr: HashMap<TonHash, InMsg>,
+    /// Outbound messages by the hashes of the messages.
+    pub out_msg_descr: HashMap<TonHash, OutMsg>,
+    /// Transactions by the addresses of accounts within the workchain.
+    pub account_blocks: HashMap<TonHash, AccountBlock>,
+    pub rand_seed: TonHash,
+    /// Public key of the validator which created the block.
+    pub created_by: TonHash,
+    /// Masterchain-specific data, present only in masterchain blocks.
+    pub custom: Option<McBlockExtra>,
+}
+
+impl BlockExtra {
+    const TAG: u64 = 0x4a33f6fd;
+
+    pub fn read(parser: &mut CellParser) -> Result<BlockExtra, TonCellError> {
+        check_tag(parser, 32, Self::TAG, "BlockExtra")?;
+        let in_msg_descr = parser.next_reference()?.parse_fully(|parser| {
+            load_aug_dict(
+                parser,
+                256,
+                key_reader_256bit,
+                read_in_msg_leaf,
+                skip_import_fees,
+            )
+        })?;
+        let out_msg_descr = parser.next_reference()?.parse_fully(|parser| {
+            load_aug_dict(
+                parser,
+                256,
+                key_reader_256bit,
+                read_out_msg_leaf,
+                skip_currency_collection,
+            )
+        })?;
+        let account_blocks = parser.next_reference()?.parse_fully(|parser| {
+            load_aug_dict(
+                parser,
+                256,
+                key_reader_256bit,
+                read_account_block_leaf,
+                skip_currency_collection,
+            )
+        })?;
+        let rand_seed = TonHash::read(parser)?;
+        let created_by = TonHash::read(parser)?;
+        let custom = match parser.load_maybe_cell_ref()? {
+            Some(cell) => Some(cell.parse_fully(McBlockExtra::read)?),
+            None => None,
+        };
+        Ok(BlockExtra {
+            in_msg_descr,
+            out_msg_descr,
+            account_blocks,
+            rand_seed,
+            created_by,
+            custom,
+        })
+    }
+}
+
+/// ```raw
+/// masterchain_block_extra#cca5
+///   key_block:(## 1)
+///   shard_hashes:ShardHashes
+///   shard_fees:ShardFees
+///   ^[ prev_blk_signatures:(HashmapE 16 CryptoSignaturePair)
+///      recover_create_msg:(Maybe ^InMsg)
+///      mint_msg:(Maybe ^InMsg) ]
+///   config:key_block?ConfigParams
+/// = McBlockExtra;
+/// _ (HashmapE 32 ^(BinTree ShardDescr)) = ShardHashes;
+/// _ (HashmapAugE 96 ShardFeeCreated ShardFeeCreated) = ShardFees;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct McBlockExtra {
+    pub key_block: bool,
+    /// Latest blocks of the shards of all workchains, ordered by workchain and shard prefix.
+    pub shard_hashes: Vec<(ShardIdent, ShardDescr)>,
+    /// Signatures of the validators of the previous masterchain block.
+    pub prev_blk_signatures: Vec<CryptoSignaturePair>,
+    pub recover_create_msg: Option<InMsg>,
+    pub mint_msg: Option<InMsg>,
+    /// Blockchain config, present only in key blocks.
+    pub config: Option<ConfigParams>,
+}
+
+impl McBlockExtra {
+    const TAG: u64 = 0xcca5;
+
+    pub fn read(parser: &mut CellParser) -> Result<McBlockExtra, TonCellError> {
+        check_tag(parser, 16, Self::TAG, "McBlockExtra")?;
+        let key_block = parser.load_bit()?;
+        let shard_trees = parser.load_maybe_dict(32, key_reader_u32, val_reader_ref_cell)?;
+        let mut shard_trees: Vec<_> = shard_trees
+            .into_iter()
+            .map(|(workchain, tree)| (workchain as i32, tree))
+            .collect();
+        shard_trees.sort_by_key(|(workchain, _)| *workchain);
+        let mut shard_hashes = vec![];
+        for (workchain, tree) in shard_trees {
+            load_shard_tree(&tree, workchain, SHARD_FULL, &mut shard_hashes)?;
+        }
+        let _shard_fees = parser.load_maybe_cell_ref()?;
+        // ShardFeeCreated of all shards, fees:CurrencyCollection create:CurrencyCollection
+        skip_currency_collection(parser)?;
+        skip_currency_collection(parser)?;
+        let (prev_blk_signatures, recover_create_msg, mint_msg) =
+            parser.next_reference()?.parse_fully(|parser| {
+                let signatures =
+                    parser.load_maybe_dict(16, key_reader_u16, CryptoSignaturePair::read)?;
+                let recover_create_msg = load_in_msg_ref(parser)?;
+                let mint_msg = load_in_msg_ref(parser)?;
+                Ok((sorted_values(signatures), recover_create_msg, mint_msg))
+            })?;
+        let config = match key_block {
+            false => None,
+            true => Some(ConfigParams::read(parser)?),
+        };
+        Ok(McBlockExtra {
+            key_block,
+            shard_hashes,
+            prev_blk_signatures,
+            recover_create_msg,
+            mint_msg,
+            config,
+        })
+    }
+}
+
+/// Reads `BinTree ShardDescr` of the shard `shard`, appending its leaves left to right:
+///
+/// ```raw
+/// bt_leaf$0 {X:Type} leaf:X = BinTree X;
+/// bt_fork$1 {X:Type} left:^(BinTree X) right:^(BinTree X) = BinTree X;
+/// ```
+fn load_shard_tree(
+    cell: &Cell,
+    workchain: i32,
+    shard: u64,
+    dst: &mut Vec<(ShardIdent, ShardDescr)>,
+) -> Result<(), TonCellError> {
+    let mut parser = cell.parser();
+    if !parser.load_bit()? {
+        let descr = ShardDescr::read(&mut parser)?;
+        let shard = shard as i64;
+        dst.push((ShardIdent { workchain, shard }, descr));
+        return Ok(());
+    }
+    // Children of a shard have its prefix followed by 0 and 1 bits
+    let delta = (shard & shard.wrapping_neg()) >> 1;
+    if delta == 0 {
+        return Err(TonCellError::CellParserError(
+            "Shard tree is too deep".to_string(),
+        ));
+    }
+    let left = parser.next_reference()?;
+    let right = parser.next_reference()?;
+    load_shard_tree(&left, workchain, shard - delta, dst)?;
+    load_shard_tree(&right, workchain, shard + delta, dst)
+}
+
+/// Description of the latest block of a shard, as known to the masterchain:
+///
+/// ```raw
+/// shard_descr#b seq_no:uint32 reg_mc_seqno:uint32
+///   start_lt:uint64 end_lt:uint64
+///   root_hash:bits256 file_hash:bits256
+///   before_split:Bool before_merge:Bool
+///   want_split:Bool want_merge:Bool
+///   nx_cc_updated:Bool flags:(## 3) { flags = 0 }
+///   next_catchain_seqno:uint32 next_validator_shard:uint64
+///   min_ref_mc_seqno:uint32 gen_utime:uint32
+///   split_merge_at:FutureSplitMerge
+///   fees_collected:CurrencyCollection
+///   funds_created:CurrencyCollection = ShardDescr;
+/// shard_descr_new#a ... split_merge_at:FutureSplitMerge
+///   ^[ fees_collected:CurrencyCollection
+///      funds_created:CurrencyCollection ] = ShardDescr;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShardDescr {
+    pub seqno: u32,
+    /// Seqno of the masterchain block the shard block is registered in.
+    pub reg_mc_seqno: u32,
+    pub start_lt: u64,
+    pub end_lt: u64,
+    pub root_hash: TonHash,
+    pub file_hash: TonHash,
+    pub before_split: bool,
+    pub before_merge: bool,
+    pub want_split: bool,
+    pub want_merge: bool,
+    pub nx_cc_updated: bool,
+    pub next_catchain_seqno: u32,
+    /// Shard whose validators create the next block, in the format of `BlockIdExt`.
+    pub next_validator_shard: i64,
+    pub min_ref_mc_seqno: u32,
+    pub gen_utime: u32,
+    pub split_merge_at: FutureSplitMerge,
+    pub fees_collected: CurrencyCollection,
+    pub funds_created: CurrencyCollection,
+}
+
+impl ShardDescr {
+    const TAG: u8 = 0xb;
+    const TAG_NEW: u8 = 0xa;
+
+    pub fn read(parser: &mut CellParser) -> Result<ShardDescr, TonCellError> {
+        let tag = parser.load_u8(4)?;
+        if tag != Self::TAG && tag != Self::TAG_NEW {
+            return Err(TonCellError::CellParserError(format!(
+                "Invalid tag of ShardDescr: {tag:#x}"
+            )));
+        }
+        let seqno = parser.load_u32(32)?;
+        let reg_mc_seqno = parser.load_u32(32)?;
+        let start_lt = parser.load_u64(64)?;
+        let end_lt = parser.load_u64(64)?;
+        let root_hash = TonHash::read(parser)?;
+        let file_hash = TonHash::read(parser)?;
+        let before_split = parser.load_bit()?;
+        let before_merge = parser.load_bit()?;
+        let want_split = parser.load_bit()?;
+        let want_merge = parser.load_bit()?;
+        let nx_cc_updated = parser.load_bit()?;
+        let _flags = parser.load_u8(3)?;
+        let next_catchain_seqno = parser.load_u32(32)?;
+        let next_validator_shard = parser.load_i64(64)?;
+        let min_ref_mc_seqno = parser.load_u32(32)?;
+        let gen_utime = parser.load_u32(32)?;
+        let split_merge_at = FutureSplitMerge::read(parser)?;
+        let read_fees = |parser: &mut CellParser| {
+            let fees_collected = CurrencyCollection::read(parser)?;
+            let funds_created = CurrencyCollection::read(parser)?;
+            Ok((fees_collected, funds_created))
+        };
+        let (fees_collected, funds_created) = match tag {
+            Self::TAG => read_fees(parser)?,
+            _ => parser.next_reference()?.parse_fully(read_fees)?,
+        };
+        Ok(ShardDescr {
+            seqno,
+            reg_mc_seqno,
+            start_lt,
+            end_lt,
+            root_hash,
+            file_hash,
+            before_split,
+            before_merge,
+            want_split,
+            want_merge,
+            nx_cc_updated,
+            next_catchain_seqno,
+            next_validator_shard,
+            min_ref_mc_seqno,
+            gen_utime,
+            split_merge_at,
+            fees_collected,
+            funds_created,
+        })
+    }
+}
+
+/// ```raw
+/// fsm_none$0 = FutureSplitMerge;
+/// fsm_split$10 split_utime:uint32 interval:uint32 = FutureSplitMerge;
+/// fsm_merge$11 merge_utime:uint32 interval:uint32 = FutureSplitMerge;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FutureSplitMerge {
+    None,
+    Split { split_utime: u32, interval: u32 },
+    Merge { merge_utime: u32, interval: u32 },
+}
+
+impl FutureSplitMerge {
+    pub fn read(parser: &mut CellParser) -> Result<FutureSplitMerge, TonCellError> {
+        if !parser.load_bit()? {
+            return Ok(FutureSplitMerge::None);
+        }
+        let split_merge = if !parser.load_bit()? {
+            FutureSplitMerge::Split {
+                split_utime: parser.load_u32(32)?,
+                interval: parser.load_u32(32)?,
+            }
+        } else {
+            FutureSplitMerge::Merge {
+                merge_utime: parser.load_u32(32)?,
+                interval: parser.load_u32(32)?,
+            }
+        };
+        Ok(split_merge)
+    }
+}
+
+/// ```raw
+/// sig_pair$_ node_id_short:bits256 sign:CryptoSignature = CryptoSignaturePair;
+/// ed25519_signature#5 R:bits256 s:bits256 = CryptoSignatureSimple;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CryptoSignaturePair {
+    /// Short id of the validator, i.e. the hash of its public key.
+    pub node_id_short: TonHash,
+    /// Ed25519 signature of the block.
+    pub signature: [u8; 64],
+}
+
+impl CryptoSignaturePair {
+    const SIGNATURE_TAG: u64 = 0x5;
+
+    pub fn read(parser: &mut CellParser) -> Result<CryptoSignaturePair, TonCellError> {
+        let node_id_short = TonHash::read(parser)?;
+        check_tag(parser, 4, Self::SIGNATURE_TAG, "CryptoSignatureSimple")?;
+        let mut signature = [0u8; 64];
+        parser.load_slice(&mut signature)?;
+        Ok(CryptoSignaturePair {
+            node_id_short,
+            signature,
+        })
+    }
+}
+
+/// ```raw
+/// _ config_addr:bits256 config:^(Hashmap 32 ^Cell) = ConfigParams;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigParams {
+    /// Address of the config contract within the masterchain.
+    pub config_addr: TonHash,
+    /// Cells of config params by their indexes.
+    pub config: HashMap<u32, ArcCell>,
+}
+
+impl ConfigParams {
+    pub fn read(parser: &mut CellParser) -> Result<ConfigParams, TonCellError> {
+        let config_addr = TonHash::read(parser)?;
+        let config =
+            parser
+                .next_reference()?
+                .parser()
+                .load_dict(32, key_reader_u32, val_reader_ref_cell)?;
+        Ok(ConfigParams {
+            config_addr,
+            config,
+        })
+    }
+
+    /// Decodes config param `index`, `None` if it isn't set, see `ConfigParam::parse`.
+    pub fn param(&self, index: u32) -> Result<Option<ConfigParam>, TonCellError> {
+        self.config
+            .get(&index)
+            .map(|cell| ConfigParam::parse(index, cell))
+            .transpose()
+    }
+}
+
+/// Inbound message of a block, according to TL-B schema:
+///
+/// ```raw
+/// msg_import_ext$000 msg:^(Message Any) transaction:^Transaction = InMsg;
+/// msg_import_ihr$010 msg:^(Message Any) transaction:^Transaction
+///   ihr_fee:Grams proof_created:^Cell = InMsg;
+/// msg_import_imm$011 in_msg:^MsgEnvelope transaction:^Transaction fwd_fee:Grams = InMsg;
+/// msg_import_fin$100 in_msg:^MsgEnvelope transaction:^Transaction fwd_fee:Grams = InMsg;
+/// msg_import_tr$101 in_msg:^MsgEnvelope out_msg:^MsgEnvelope transit_fee:Grams = InMsg;
+/// msg_discard_fin$110 in_msg:^MsgEnvelope transaction_id:uint64 fwd_fee:Grams = InMsg;
+/// msg_discard_tr$111 in_msg:^MsgEnvelope transaction_id:uint64 fwd_fee:Grams
+///   proof_delivered:^Cell = InMsg;
+/// msg_import_deferred_fin$00100 in_msg:^MsgEnvelope transaction:^Transaction
+///   fwd_fee:Grams = InMsg;
+/// msg_import_deferred_tr$00101 in_msg:^MsgEnvelope out_msg:^MsgEnvelope = InMsg;
+/// ```
+///
+/// Messages and transactions are kept as cells, see `Transaction::parse`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InMsg {
+    External {
+        msg: ArcCell,
+        transaction: ArcCell,
+    },
+    Ihr {
+        msg: ArcCell,
+        transaction: ArcCell,
+        ihr_fee: BigUint,
+        proof_created: ArcCell,
+    },
+    Immediate {
+        in_msg: ArcCell,
+        transaction: ArcCell,
+        fwd_fee: BigUint,
+    },
+    Final {
+        in_msg: ArcCell,
+        transaction: ArcCell,
+        fwd_fee: BigUint,
+    },
+    Transit {
+        in_msg: ArcCell,
+        out_msg: ArcCell,
+        transit_fee: BigUint,
+    },
+    DiscardedFinal {
+        in_msg: ArcCell,
+        transaction_id: u64,
+        fwd_fee: BigUint,
+    },
+    DiscardedTransit {
+        in_msg: ArcCell,
+        transaction_id: u64,
+        fwd_fee: BigUint,
+        proof_delivered: ArcCell,
+    },
+    DeferredFinal {
+        in_msg: ArcCell,
+        transaction: ArcCell,
+        fwd_fee: BigUint,
+    },
+    DeferredTransit {
+        in_msg: ArcCell,
+        out_msg: ArcCell,
+    },
+}
+
+impl InMsg {
+    pub fn read(parser: &mut CellParser) -> Result<InMsg, TonCellError> {
+        let in_msg = match parser.load_u8(3)? {
+            0b000 => InMsg::External {
+                msg: parser.next_reference()?,
+                transaction: parser.next_reference()?,
+            },
+            0b001 => match parser.load_u8(2)? {
+                0b00 => InMsg::DeferredFinal {
+                    in_msg: parser.next_reference()?,
+                    transaction: parser.next_reference()?,
+                    fwd_fee: parser.load_coins()?,
+                },
+                0b01 => InMsg::DeferredTransit {
+                    in_msg: parser.next_reference()?,
+                    out_msg: parser.next_reference()?,
+                },
+                tag => {
+                    return Err(TonCellError::CellParserError(format!(
+                        "Invalid tag of InMsg: 0b001{tag:02b}"
+                    )))
+                }
+            },
+            0b010 => InMsg::Ihr {
+                msg: parser.next_reference()?,
+                transaction: parser.next_reference()?,
+                ihr_fee: parser.load_coins()?,
+                proof_created: parser.next_reference()?,
+            },
+            0b011 => InMsg::Immediate {
+                in_msg: parser.next_reference()?,
+                transaction: parser.next_reference()?,
+                fwd_fee: parser.load_coins()?,
+            },
+            0b100 => InMsg::Final {
+                in_msg: parser.next_reference()?,
+                transaction: parser.next_reference()?,
+                fwd_fee: parser.load_coins()?,
+            },
+            0b101 => InMsg::Transit {
+                in_msg: parser.next_reference()?,
+                out_msg: parser.next_reference()?,
+                transit_fee: parser.load_coins()?,
+            },
+            0b110 => InMsg::DiscardedFinal {
+                in_msg: parser.next_reference()?,
+                transaction_id: parser.load_u64(64)?,
+                fwd_fee: parser.load_coins()?,
+            },
+            _ => InMsg::DiscardedTransit {
+                in_msg: parser.next_reference()?,
+                transaction_id: parser.load_u64(64)?,
+                fwd_fee: parser.load_coins()?,
+                proof_delivered: parser.next_reference()?,
+            },
+        };
+        Ok(in_msg)
+    }
+
+    /// Returns the cell of the transaction processing the message in this block, if any.
+    pub fn transaction(&self) -> Option<&ArcCell> {
+        match self {
+            InMsg::External { transaction, .. }
+            | InMsg::Ihr { transaction, .. }
+            | InMsg::Immediate { transaction, .. }
+            | InMsg::Final { transaction, .. }
+            | InMsg::DeferredFinal { transaction, .. } => Some(transaction),
+            _ => None,
+        }
+    }
+}
+
+/// Outbound message of a block, according to TL-B schema:
+///
+/// ```raw
+/// msg_export_ext$000 msg:^(Message Any) transaction:^Transaction = OutMsg;
+/// msg_export_imm$010 out_msg:^MsgEnvelope transaction:^Transaction reimport:^InMsg = OutMsg;
+/// msg_export_new$001 out_msg:^MsgEnvelope transaction:^Transaction = OutMsg;
+/// msg_export_tr$011 out_msg:^MsgEnvelope imported:^InMsg = OutMsg;
+/// msg_export_deq$1100 out_msg:^MsgEnvelope import_block_lt:uint63 = OutMsg;
+/// msg_export_deq_short$1101 msg_env_hash:bits256 next_workchain:int32
+///   next_addr_pfx:uint64 import_block_lt:uint64 = OutMsg;
+/// msg_export_tr_req$111 out_msg:^MsgEnvelope imported:^InMsg = OutMsg;
+/// msg_export_deq_imm$100 out_msg:^MsgEnvelope reimport:^InMsg = OutMsg;
+/// msg_export_new_defer$10100 out_msg:^MsgEnvelope transaction:^Transaction = OutMsg;
+/// msg_export_deferred_tr$10101 out_msg:^MsgEnvelope imported:^InMsg = OutMsg;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum OutMsg {
+    External {
+        msg: ArcCell,
+        transaction: ArcCell,
+    },
+    New {
+        out_msg: ArcCell,
+        transaction: ArcCell,
+    },
+    Immediate {
+        out_msg: ArcCell,
+        transaction: ArcCell,
+        /// Cell of the `InMsg` importing the message into the same block.
+        reimport: ArcCell,
+    },
+    Transit {
+        out_msg: ArcCell,
+        /// Cell of the `InMsg` importing the message.
+        imported: ArcCell,
+    },
+    Dequeue {
+        out_msg: ArcCell,
+        import_block_lt: u64,
+    },
+    DequeueShort {
+        msg_env_hash: TonHash,
+        next_workchain: i32,
+        next_addr_pfx: u64,
+        import_block_lt: u64,
+    },
+    TransitRequired {
+        out_msg: ArcCell,
+        imported: ArcCell,
+    },
+    DequeueImmediate {
+        out_msg: ArcCell,
+        reimport: ArcCell,
+    },
+    NewDeferred {
+        out_msg: ArcCell,
+        transaction: ArcCell,
+    },
+    DeferredTransit {
+        out_msg: ArcCell,
+        imported: ArcCell,
+    },
+}
+
+impl OutMsg {
+    pub fn read(parser: &mut CellParser) -> Result<OutMsg, TonCellError> {
+        let out_msg = match parser.load_u8(3)? {
+            0b000 => OutMsg::External {
+                msg: parser.next_reference()?,
+                transaction: parser.next_reference()?,
+            },
+            0b001 => OutMsg::New {
+                out_msg: parser.next_reference()?,
+                transaction: parser.next_reference()?,
+            },
+            0b010 => OutMsg::Immediate {
+                out_msg: parser.next_reference()?,
+                transaction: parser.next_reference()?,
+                reimport: parser.next_reference()?,
+            },
+            0b011 => OutMsg::Transit {
+                out_msg: parser.next_reference()?,
+                imported: parser.next_reference()?,
+            },
+            0b100 => OutMsg::DequeueImmediate {
+                out_msg: parser.next_reference()?,
+                reimport: parser.next_reference()?,
+            },
+            0b101 => match parser.load_u8(2)? {
+                0b00 => OutMsg::NewDeferred {
+                    out_msg: parser.next_reference()?,
+                    transaction: parser.next_reference()?,
+                },
+                0b01 => OutMsg::DeferredTransit {
+                    out_msg: parser.next_reference()?,
+                    imported: parser.next_reference()?,
+                },
+                tag => {
+                    return Err(TonCellError::CellParserError(format!(
+                        "Invalid tag of OutMsg: 0b101{tag:02b}"
+                    )))
+                }
+            },
+            0b110 if !parser.load_bit()? => OutMsg::Dequeue {
+                out_msg: parser.next_reference()?,
+                import_block_lt: parser.load_u64(63)?,
+            },
+            0b110 => OutMsg::DequeueShort {
+                msg_env_hash: TonHash::read(parser)?,
+                next_workchain: parser.load_i32(32)?,
+                next_addr_pfx: parser.load_u64(64)?,
+                import_block_lt: parser.load_u64(64)?,
+            },
+            _ => OutMsg::TransitRequired {
+                out_msg: parser.next_reference()?,
+                imported: parser.next_reference()?,
+            },
+        };
+        Ok(out_msg)
+    }
+
+    /// Returns the cell of the transaction creating the message in this block, if any.
+    pub fn transaction(&self) -> Option<&ArcCell> {
+        match self {
+            OutMsg::External { transaction, .. }
+            | OutMsg::New { transaction, .. }
+            | OutMsg::Immediate { transaction, .. }
+            | OutMsg::NewDeferred { transaction, .. } => Some(transaction),
+            _ => None,
+        }
+    }
+}
+
+/// Transactions of an account in a block:
+///
+/// ```raw
+/// acc_trans#5 account_addr:bits256
+///   transactions:(HashmapAug 64 ^Transaction CurrencyCollection)
+///   state_update:^(HASH_UPDATE Account) = AccountBlock;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountBlock {
+    pub account_addr: TonHash,
+    /// Cells of the transactions, ordered by their logical time, see `Transaction::parse`.
+    pub transactions: Vec<ArcCell>,
+    pub state_update: HashUpdate,
+}
+
+impl AccountBlock {
+    const TAG: u64 = 0x5;
+
+    pub fn read(parser: &mut CellParser) -> Result<AccountBlock, TonCellError> {
+        check_tag(parser, 4, Self::TAG, "AccountBlock")?;
+        let account_addr = TonHash::read(parser)?;
+        let transactions = parser.load_dict(64, key_reader_u64, read_transaction_leaf)?;
+        // The root of the inlined dictionary carries the extra value only if it is a fork
+        if parser.remaining_bits() > 0 {
+            skip_currency_collection(parser)?;
+        }
+        let state_update = parser.next_reference()?.parse_fully(HashUpdate::read)?;
+        Ok(AccountBlock {
+            account_addr,
+            transactions: sorted_values(transactions),
+            state_update,
+        })
+    }
+}
+
+/// Loads `HashmapAugE n X Y`, i.e. `Maybe ^(HashmapAug n X Y)` followed by the extra value
+/// of the whole dictionary, skipped with `skip_extra`.
+///
+/// `read_leaf` reads both the extra value and the value of a leaf, the extra values of forks
+/// are ignored.
+fn load_aug_dict<K: Eq + Hash, V>(
+    parser: &mut CellParser,
+    key_len: usize,
+    key_reader: KeyReader<K>,
+    read_leaf: ValReader<V>,
+    skip_extra: fn(&mut CellParser) -> Result<(), TonCellError>,
+) -> Result<HashMap<K, V>, TonCellError> {
+    let dict = parser.load_maybe_dict(key_len, key_reader, read_leaf)?;
+    skip_extra(parser)?;
+    Ok(dict)
+}
+
+fn read_in_msg_leaf(parser: &mut CellParser) -> Result<InMsg, TonCellError> {
+    skip_import_fees(parser)?;
+    InMsg::read(parser)
+}
+
+fn read_out_msg_leaf(parser: &mut CellParser) -> Result<OutMsg, TonCellError> {
+    skip_currency_collection(parser)?;
+    OutMsg::read(parser)
+}
+
+fn read_account_block_leaf(parser: &mut CellParser) -> Result<AccountBlock, TonCellError> {
+    skip_currency_collection(parser)?;
+    AccountBlock::read(parser)
+}
+
+fn read_transaction_leaf(parser: &mut CellParser) -> Result<ArcCell, TonCellError> {
+    skip_currency_collection(parser)?;
+    parser.next_reference()
+}
+
+fn load_in_msg_ref(parser: &mut CellParser) -> Result<Option<InMsg>, TonCellError> {
+    load_maybe(parser, |parser| {
+        parser.next_reference()?.parse_fully(InMsg::read)
+    })
+}
+
+/// Skips `import_fees$_ fees_collected:Grams value_imported:CurrencyCollection = ImportFees;`.
+fn skip_import_fees(parser: &mut CellParser) -> Result<(), TonCellError> {
+    parser.load_coins()?;
+    skip_currency_collection(parser)
+}
+
+fn skip_currency_collection(parser: &mut CellParser) -> Result<(), TonCellError> {
+    CurrencyCollection::read(parser)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use num_bigint::BigUint;
+
+    use super::{BlkPrevInfo, Block, FutureSplitMerge, InMsg, ShardIdent};
+    use crate::cell::dict::predefined_writers::val_writer_ref_cell;
+    use crate::cell::{ArcCell, Cell, CellBuilder, TonCellError};
+    use crate::config::ConfigParam;
+    use crate::transaction::tests::transaction;
+    use crate::TonAddress;
+
+    fn store_empty_currencies(builder: &mut CellBuilder) -> Result<&mut CellBuilder, TonCellError> {
+        builder.store_coins(&BigUint::from(0u32))?.store_bit(false)
+    }
+
+    fn shard_descr(seqno: u32, split_merge_at: bool) -> Result<Cell, TonCellError> {
+        let mut builder = CellBuilder::new();
+        builder
+            .store_bit(false)?
+            .store_u8(4, 0xb)?
+            .store_u32(32, seqno)?
+            .store_u32(32, 7)?
+            .store_u64(64, 2_000_000)?
+            .store_u64(64, 2_000_005)?
+            .store_slice(&[8; 32])?
+            .store_slice(&[9; 32])?
+            // before_split before_merge want_split want_merge nx_cc_updated flags:(## 3)
+            .store_u8(8, 0b0010_0000)?
+            .store_u32(32, 3)?
+            .store_u64(64, 0x6000_0000_0000_0000)?
+            .store_u32(32, 6)?
+            .store_u32(32, 1_700_000_000)?;
+        if split_merge_at {
+            builder
+                .store_u8(2, 0b10)?
+                .store_u32(32, 1_700_000_100)?
+                .store_u32(32, 60)?;
+        } else {
+            builder.store_bit(false)?;
+        }
+        store_empty_currencies(&mut builder)?;
+        store_empty_currencies(&mut builder)?;
+        builder.build()
+    }
+
+    fn masterchain_extra() -> Result<Cell, TonCellError> {
+        let shard_tree = CellBuilder::new()
+            .store_bit(true)?
+            .store_child(shard_descr(100, false)?)?
+            .store_child(shard_descr(101, true)?)?
+            .build()?;
+        let shard_hashes = HashMap::from([(0u32, Arc::new(shard_tree))]);
+        let signatures = HashMap::from([(0u16, ())]);
+        let signatures = CellBuilder::new()
+            .store_maybe_dict(
+                16,
+                |builder, ()| {
+                    builder
+                        .store_slice(&[4; 32])?
+                        .store_u8(4, 0x5)?
+                        .store_slice(&[5; 64])?;
+                    Ok(())
+                },
+                signatures,
+            )?
+            .store_bit(false)?
+            .store_bit(false)?
+            .build()?;
+        let elector = Arc::new(CellBuilder::new().store_slice(&[7; 32])?.build()?);
+        let config = CellBuilder::new()
+            .store_dict(32, val_writer_ref_cell, HashMap::from([(1u32, elector)]))?
+            .build()?;
+        let mut builder = CellBuilder::new();
+        builder
+            .store_u32(16, 0xcca5)?
+            .store_bit(true)?
+            .store_maybe_dict(32, val_writer_ref_cell, shard_hashes)?
+            .store_bit(false)?;
+        store_empty_currencies(&mut builder)?;
+        store_empty_currencies(&mut builder)?;
+        builder
+            .store_child(signatures)?
+            .store_slice(&[6; 32])?
+            .store_child(config)?
+            .build()
+    }
+
+    fn account_block(transactions: Vec<(u64, ArcCell)>) -> Result<Cell, TonCellError> {
+        let count = transactions.len();
+        let state_update = CellBuilder::new()
+            .store_u8(8, 0x72)?
+            .store_slice(&[1; 32])?
+            .store_slice(&[2; 32])?
+            .build()?;
+        let mut builder = CellBuilder::new();
+        builder
+            .store_u8(4, 0x5)?
+            .store_slice(&[3; 32])?
+            .store_dict(
+                64,
+                |builder, transaction: ArcCell| {
+                    store_empty_currencies(builder)?.store_reference(&transaction)?;
+                    Ok(())
+                },
+                transactions.into_iter().collect(),
+            )?;
+        if count > 1 {
+            store_empty_currencies(&mut builder)?;
+        }
+        builder.store_child(state_update)?.build()
+    }
+
+    // A block assembled from synthetic cells following the TL-B scheme: no real block BoC is
+    // bundled with the tests, the layouts of the parsed structures aren't checked against one.
+    #[test]
+    fn test_parse_block() -> Result<(), TonCellError> {
+        let storage_transaction = |lt| {
+            let description = CellBuilder::new()
+                .store_u8(4, 0b0001)?
+                .store_coins(&BigUint::from(1u32))?
+                .store_bit(false)?
+                .store_bit(false)?
+                .build()?;
+            transaction(lt, description).map(Arc::new)
+        };
+        let tx1 = storage_transaction(1_000_002)?;
+        let tx2 = storage_transaction(1_000_003)?;
+        let message = Arc::new(CellBuilder::new().store_u8(8, 1)?.build()?);
+
+        let in_msgs = HashMap::from([(BigUint::from(1u32), (message.clone(), tx1.clone()))]);
+        let mut in_msg_descr = CellBuilder::new();
+        in_msg_descr.store_maybe_dict(
+            256,
+            |builder, (message, transaction): (ArcCell, ArcCell)| {
+                builder.store_coins(&BigUint::from(0u32))?;
+                store_empty_currencies(builder)?
+                    .store_u8(3, 0b000)?
+                    .store_reference(&message)?
+                    .store_reference(&transaction)?;
+                Ok(())
+            },
+            in_msgs,
+        )?;
+        in_msg_descr.store_coins(&BigUint::from(0u32))?;
+        store_empty_currencies(&mut in_msg_descr)?;
+        let mut out_msg_descr = CellBuilder::new();
+        out_msg_descr.store_bit(false)?;
+        store_empty_currencies(&mut out_msg_descr)?;
+        let account_blocks = HashMap::from([(
+            BigUint::from_bytes_be(&[3; 32]),
+            Arc::new(account_block(vec![
+                (1_000_003, tx2),
+                (1_000_002, tx1.clone()),
+            ])?),
+        )]);
+        let mut shard_account_blocks = CellBuilder::new();
+        shard_account_blocks.store_maybe_dict(
+            256,
+            |builder, account_block: ArcCell| {
+                store_empty_currencies(builder)?.store_cell(&account_block)?;
+                Ok(())
+            },
+            account_blocks,
+        )?;
+        store_empty_currencies(&mut shard_account_blocks)?;
+        let extra = CellBuilder::new()
+            .store_u32(32, 0x4a33f6fd)?
+            .store_child(in_msg_descr.build()?)?
+            .store_child(out_msg_descr.build()?)?
+            .store_child(shard_account_blocks.build()?)?
+            .store_slice(&[10; 32])?
+            .store_slice(&[11; 32])?
+            .store_maybe_cell_ref(&Some(Arc::new(masterchain_extra()?)))?
+            .build()?;
+
+        let prev = CellBuilder::new()
+            .store_u64(64, 1_000_000)?
+            .store_u32(32, 6)?
+            .store_slice(&[12; 32])?
+            .store_slice(&[13; 32])?
+            .build()?;
+        let info = CellBuilder::new()
+            .store_u32(32, 0x9bc7a987)?
+            .store_u32(32, 0)?
+            // not_master ... vert_seqno_incr, flags
+            .store_u8(8, 0b0000_0010)?
+            .store_u8(8, 1)?
+            .store_u32(32, 7)?
+            .store_u32(32, 0)?
+            .store_u8(8, 0)?
+            .store_i32(32, -1)?
+            .store_u64(64, 0)?
+            .store_u32(32, 1_700_000_000)?
+            .store_u64(64, 1_000_001)?
+            .store_u64(64, 1_000_005)?
+            .store_u32(32, 0x1234)?
+            .store_u32(32, 2)?
+            .store_u32(32, 6)?
+            .store_u32(32, 5)?
+            // gen_software
+            .store_u8(8, 0xc4)?
+            .store_u32(32, 9)?
+            .store_u64(64, 0x2e)?
+            .store_child(prev)?
+            .build()?;
+        let empty = Arc::new(Cell::default());
+        let block = CellBuilder::new()
+            .store_u32(32, 0x11ef55aa)?
+            .store_i32(32, -239)?
+            .store_child(info)?
+            .store_reference(&empty)?
+            .store_reference(&empty)?
+            .store_child(extra)?
+            .build()?;
+
+        let block = Block::parse(&block)?;
+        assert_eq!(block.global_id, -239);
+        let info = &block.info;
+        assert!(info.key_block);
+        assert_eq!(info.seqno, 7);
+        assert_eq!(info.gen_utime, 1_700_000_000);
+        assert_eq!(info.gen_validator_list_hash_short, 0x1234);
+        assert_eq!(info.prev_key_block_seqno, 5);
+        assert_eq!(info.gen_software.map(|v| v.version), Some(9));
+        assert_eq!(info.master_ref, None);
+        assert_eq!(
+            info.shard,
+            ShardIdent {
+                workchain: -1,
+                shard: i64::MIN
+            }
+        );
+        match &info.prev_ref {
+            BlkPrevInfo::Single(prev) => assert_eq!(prev.seqno, 6),
+            prev => panic!("Unexpected prev_ref: {:?}", prev),
+        }
+
+        let extra = &block.extra;
+        let mut key = [0; 32];
+        key[31] = 1;
+        match &extra.in_msg_descr[&key] {
+            InMsg::External { msg, transaction } => {
+                assert_eq!(msg, &message);
+                assert_eq!(transaction, &tx1);
+            }
+            in_msg => panic!("Unexpected in_msg: {:?}", in_msg),
+        }
+        assert!(extra.out_msg_descr.is_empty());
+        assert_eq!(
+            extra.account_blocks[&[3; 32]].state_update.new_hash,
+            [2; 32]
+        );
+        assert_eq!(extra.created_by, [11; 32]);
+        let lts: Vec<_> = block.transactions()?.iter().map(|tx| tx.lt).collect();
+        assert_eq!(lts, vec![1_000_002, 1_000_003]);
+
+        let custom = extra.custom.as_ref().unwrap();
+        let shards: Vec<_> = custom
+            .shard_hashes
+            .iter()
+            .map(|(shard, descr)| (shard.workchain, shard.shard as u64, descr.seqno))
+            .collect();
+        assert_eq!(
+            shards,
+            vec![
+                (0, 0x4000_0000_0000_0000, 100),
+                (0, 0xc000_0000_0000_0000, 101)
+            ]
+        );
+        let (_, right) = &custom.shard_hashes[1];
+        assert!(right.want_split);
+        assert_eq!(right.next_validator_shard, 0x6000_0000_0000_0000);
+        assert_eq!(
+            right.split_merge_at,
+            FutureSplitMerge::Split {
+                split_utime: 1_700_000_100,
+                interval: 60
+            }
+        );
+        assert_eq!(custom.prev_blk_signatures.len(), 1);
+        assert_eq!(custom.prev_blk_signatures[0].signature, [5; 64]);
+        let config = custom.config.as_ref().unwrap();
+        assert_eq!(config.config_addr, [6; 32]);
+        assert_eq!(
+            config.param(1)?,
+            Some(ConfigParam::ElectorAddress(TonAddress::new(-1, &[7; 32])))
+        );
+        assert_eq!(config.param(2)?, None);
+        Ok(())
+    }
+}
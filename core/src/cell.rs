@@ -4,11 +4,13 @@ use std::ops::Deref;
 use std::sync::Arc;
 use std::{fmt, io};
 
+pub use arena::*;
 pub use bag_of_cells::*;
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use base64::Engine;
 use bitstream_io::{BigEndian, BitWrite, BitWriter};
 pub use builder::*;
+pub use cell_type::CellType;
 pub use error::*;
 use hmac::digest::Digest;
 use lazy_static::lazy_static;
@@ -19,21 +21,24 @@ pub use slice::*;
 pub use state_init::*;
 pub use util::*;
 
-use crate::cell::cell_type::CellType;
 use crate::cell::level_mask::LevelMask;
 use crate::types::DEFAULT_CELL_HASH;
 use crate::TonHash;
 
+mod arena;
 mod bag_of_cells;
 mod builder;
 
 mod cell_type;
 pub mod dict;
 mod error;
+mod exotic;
 mod level_mask;
 mod parser;
 mod raw;
 mod raw_boc_from_boc;
+#[cfg(feature = "serde")]
+mod serde_impl;
 mod slice;
 mod state_init;
 mod util;
@@ -47,7 +52,11 @@ lazy_static! {
     pub static ref EMPTY_ARC_CELL: ArcCell = Arc::new(Cell::default());
 }
 
-#[derive(PartialEq, Eq, Clone, Hash)]
+/// Cell of TVM, immutable once created.
+///
+/// Equality and `Hash` use the representation hash instead of comparing the whole tree,
+/// it's computed once when the cell is created along with the hashes and depths of all levels.
+#[derive(Clone)]
 pub struct Cell {
     data: Vec<u8>,
     bit_len: usize,
@@ -132,7 +141,7 @@ impl Cell {
         self.references.as_slice()
     }
 
-    pub(crate) fn get_level_mask(&self) -> u32 {
+    pub fn get_level_mask(&self) -> u32 {
         self.level_mask.mask()
     }
 
@@ -148,6 +157,11 @@ impl Cell {
         self.get_hash(MAX_LEVEL)
     }
 
+    /// Returns the representation hash without copying it.
+    pub fn repr_hash(&self) -> &TonHash {
+        &self.hashes[MAX_LEVEL as usize]
+    }
+
     pub fn get_hash(&self, level: u8) -> TonHash {
         self.hashes[level.min(3) as usize]
     }
@@ -239,6 +253,27 @@ impl Cell {
     }
 }
 
+impl PartialEq for Cell {
+    fn eq(&self, other: &Self) -> bool {
+        self.repr_hash() == other.repr_hash()
+    }
+}
+
+impl Eq for Cell {}
+
+impl Hash for Cell {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        state.write(self.repr_hash())
+    }
+}
+
+// Cells are shared between threads, e.g. by workers of an indexer
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Cell>();
+    assert_send_sync::<ArcCell>();
+};
+
 impl Debug for Cell {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let t = match self.cell_type {
@@ -517,11 +552,12 @@ fn write_ref_hashes(
 
 #[cfg(test)]
 mod test {
+    use std::collections::HashSet;
     use std::sync::Arc;
 
     use super::cell_type::CellType;
     use super::{get_bits_descriptor, get_refs_descriptor, Cell};
-    use crate::cell::CellBuilder;
+    use crate::cell::{ArcCell, CellBuilder, TonCellError, EMPTY_CELL};
 
     #[test]
     fn default_cell() {
@@ -561,4 +597,32 @@ mod test {
         let r3 = get_bits_descriptor(1024).is_err();
         assert!(r3)
     }
+
+    #[test]
+    fn test_cell_equality_by_hash() -> Result<(), TonCellError> {
+        let cell = |value: u32, child: Option<ArcCell>| -> Result<ArcCell, TonCellError> {
+            let mut builder = CellBuilder::new();
+            builder.store_u32(32, value)?;
+            if let Some(child) = child {
+                builder.store_reference(&child)?;
+            }
+            Ok(builder.build()?.to_arc())
+        };
+        let mut first = cell(0, None)?;
+        let mut second = cell(0, None)?;
+        for i in 1..1000 {
+            first = cell(i, Some(first))?;
+            second = cell(i, Some(second))?;
+        }
+        assert!(!Arc::ptr_eq(&first, &second));
+        assert_eq!(first, second);
+        assert_eq!(*first.repr_hash(), second.cell_hash());
+        assert_eq!(first.cell_depth(), 999);
+        assert_ne!(first, cell(999, Some(cell(0, None)?))?);
+
+        let set = HashSet::from([first, second, Arc::new(Cell::default())]);
+        assert_eq!(set.len(), 2);
+        assert_eq!(Cell::new(vec![], 0, vec![], false)?, *EMPTY_CELL);
+        Ok(())
+    }
 }
@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::cell::level_mask::LevelMask;
+use crate::cell::raw::BocHeader;
+use crate::cell::{ArcCell, Cell, CellParser, MapTonCellError, TonCellError};
+
+/// Position of a cell in the serialized BoC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ArenaCellInfo {
+    data_start: u32,
+    bit_len: u16,
+    is_exotic: bool,
+    ref_count: u8,
+    refs: [u32; 4],
+}
+
+/// Bag of cells parsed without copying the data of cells.
+///
+/// Cells are stored as offsets into the serialized BoC in a single allocation, and
+/// [`ArenaCell::parser`] reads bits directly from the serialized buffer. This makes it
+/// suitable for scanning large BoCs, e.g. blocks or states of big contracts, while
+/// [`ArenaCell::to_cell`] allows to build regular cells of the parts which have to be
+/// kept or hashed.
+#[derive(Debug, Clone)]
+pub struct BocArena<'a> {
+    serial: &'a [u8],
+    cells: Vec<ArenaCellInfo>,
+    roots: Vec<usize>,
+}
+
+impl<'a> BocArena<'a> {
+    pub fn parse(serial: &'a [u8]) -> Result<BocArena<'a>, TonCellError> {
+        let header = BocHeader::parse(serial)?;
+        let size = header.size as usize;
+        let mut cells = Vec::with_capacity(header.cell_count.min(serial.len() / 2));
+        let region = &serial[..header.cells_end];
+        let mut pos = header.cells_start;
+        for index in 0..header.cell_count {
+            let descriptors = region
+                .get(pos..pos + 2)
+                .ok_or_else(|| truncated_error(index))?;
+            let (d1, d2) = (descriptors[0], descriptors[1]);
+            pos += 2;
+
+            let ref_count = d1 & 0b111;
+            let is_exotic = (d1 & 0b1000) != 0;
+            let has_hashes = (d1 & 0b10000) != 0;
+            if ref_count > 4 {
+                return Err(TonCellError::boc_deserialization_error(format!(
+                    "Invalid number of references {} of cell {}",
+                    ref_count, index
+                )));
+            }
+            if has_hashes {
+                let hash_count = LevelMask::new((d1 >> 5) as u32).hash_count();
+                pos += hash_count * (32 + 2);
+            }
+
+            let data_size = ((d2 >> 1) + (d2 & 1)) as usize;
+            let full_bytes = (d2 & 1) == 0;
+            let data_start = pos;
+            pos += data_size;
+            let data = region
+                .get(data_start..pos)
+                .ok_or_else(|| truncated_error(index))?;
+            let bit_len = match data.last() {
+                Some(&last) if !full_bytes => {
+                    if last == 0 {
+                        return Err(TonCellError::boc_deserialization_error(
+                            "Last byte of binary must not be zero if full_byte flag is not set",
+                        ));
+                    }
+                    data_size * 8 - last.trailing_zeros() as usize - 1
+                }
+                _ => data_size * 8,
+            };
+
+            let mut refs = [0u32; 4];
+            for r in refs.iter_mut().take(ref_count as usize) {
+                let bytes = region
+                    .get(pos..pos + size)
+                    .ok_or_else(|| truncated_error(index))?;
+                let ref_index = bytes
+                    .iter()
+                    .fold(0usize, |acc, byte| (acc << 8) | *byte as usize);
+                if ref_index <= index || ref_index >= header.cell_count {
+                    return Err(TonCellError::boc_deserialization_error(format!(
+                        "Invalid reference {} of cell {}",
+                        ref_index, index
+                    )));
+                }
+                *r = ref_index as u32;
+                pos += size;
+            }
+            cells.push(ArenaCellInfo {
+                data_start: data_start as u32,
+                bit_len: bit_len as u16,
+                is_exotic,
+                ref_count,
+                refs,
+            });
+        }
+        Ok(BocArena {
+            serial,
+            cells,
+            roots: header.roots,
+        })
+    }
+
+    pub fn cell_count(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn num_roots(&self) -> usize {
+        self.roots.len()
+    }
+
+    pub fn root(&self, idx: usize) -> Result<ArenaCell<'_, 'a>, TonCellError> {
+        let index = self.roots.get(idx).ok_or_else(|| {
+            TonCellError::boc_deserialization_error(format!(
+                "Invalid root index: {}, BoC contains {} roots",
+                idx,
+                self.roots.len()
+            ))
+        })?;
+        Ok(ArenaCell {
+            arena: self,
+            index: *index,
+        })
+    }
+
+    pub fn single_root(&self) -> Result<ArenaCell<'_, 'a>, TonCellError> {
+        if self.roots.len() != 1 {
+            return Err(TonCellError::CellParserError(format!(
+                "Single root expected, got {}",
+                self.roots.len()
+            )));
+        }
+        self.root(0)
+    }
+
+    /// Returns the cell by its index in the BoC.
+    pub fn cell(&self, index: usize) -> Result<ArenaCell<'_, 'a>, TonCellError> {
+        if index >= self.cells.len() {
+            return Err(TonCellError::InvalidIndex {
+                idx: index,
+                ref_count: self.cells.len(),
+            });
+        }
+        Ok(ArenaCell { arena: self, index })
+    }
+
+    /// Builds regular cells of the cell with `index` and all cells it references,
+    /// reusing already built cells of `cache`.
+    fn build_cell(
+        &self,
+        index: usize,
+        cache: &mut HashMap<usize, ArcCell>,
+    ) -> Result<ArcCell, TonCellError> {
+        if let Some(cell) = cache.get(&index) {
+            return Ok(cell.clone());
+        }
+        let info = &self.cells[index];
+        let mut references = Vec::with_capacity(info.ref_count as usize);
+        for &r in &info.refs[..info.ref_count as usize] {
+            references.push(self.build_cell(r as usize, cache)?);
+        }
+        let bit_len = info.bit_len as usize;
+        let mut data = self.data_of(info).to_vec();
+        // clear the completion tag
+        if !bit_len.is_multiple_of(8) {
+            if let Some(last) = data.last_mut() {
+                *last &= 0xff << (8 - bit_len % 8);
+            }
+        }
+        let cell = Arc::new(
+            Cell::new(data, bit_len, references, info.is_exotic).map_boc_deserialization_error()?,
+        );
+        cache.insert(index, cell.clone());
+        Ok(cell)
+    }
+
+    fn data_of(&self, info: &ArenaCellInfo) -> &'a [u8] {
+        let start = info.data_start as usize;
+        let end = start + (info.bit_len as usize).div_ceil(8);
+        &self.serial[start..end]
+    }
+}
+
+fn truncated_error(index: usize) -> TonCellError {
+    TonCellError::boc_deserialization_error(format!("Cell {} is truncated", index))
+}
+
+/// Cell of [`BocArena`], which borrows its data from the serialized BoC.
+#[derive(Debug, Clone, Copy)]
+pub struct ArenaCell<'r, 'a> {
+    arena: &'r BocArena<'a>,
+    index: usize,
+}
+
+impl<'r, 'a> ArenaCell<'r, 'a> {
+    /// Index of the cell in the BoC.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn bit_len(&self) -> usize {
+        self.info().bit_len as usize
+    }
+
+    /// Data of the cell, the last byte of which may contain the completion tag
+    /// if `bit_len` isn't a multiple of 8.
+    pub fn data(&self) -> &'a [u8] {
+        self.arena.data_of(self.info())
+    }
+
+    pub fn is_exotic(&self) -> bool {
+        self.info().is_exotic
+    }
+
+    pub fn reference_count(&self) -> usize {
+        self.info().ref_count as usize
+    }
+
+    pub fn reference(&self, idx: usize) -> Result<ArenaCell<'r, 'a>, TonCellError> {
+        let info = self.info();
+        if idx >= info.ref_count as usize {
+            return Err(TonCellError::InvalidIndex {
+                idx,
+                ref_count: info.ref_count as usize,
+            });
+        }
+        Ok(ArenaCell {
+            arena: self.arena,
+            index: info.refs[idx] as usize,
+        })
+    }
+
+    pub fn references(&self) -> impl Iterator<Item = ArenaCell<'r, 'a>> + '_ {
+        let info = self.info();
+        info.refs[..info.ref_count as usize]
+            .iter()
+            .map(|r| ArenaCell {
+                arena: self.arena,
+                index: *r as usize,
+            })
+    }
+
+    /// Returns a parser reading the data of the cell from the serialized BoC.
+    ///
+    /// The parser has no references, use [`ArenaCell::reference`] to walk the tree.
+    pub fn parser(&self) -> CellParser<'a> {
+        CellParser::new(self.bit_len(), self.data(), &[])
+    }
+
+    /// Builds a regular cell of this cell and all cells it references, e.g. to compute its hash.
+    pub fn to_cell(&self) -> Result<ArcCell, TonCellError> {
+        self.arena.build_cell(self.index, &mut HashMap::new())
+    }
+
+    fn info(&self) -> &'r ArenaCellInfo {
+        &self.arena.cells[self.index]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::BocArena;
+    use crate::cell::{BagOfCells, CellBuilder, TonCellError};
+
+    #[test]
+    fn test_arena_matches_bag_of_cells() -> Result<(), TonCellError> {
+        let raw = include_str!("../../resources/wallet/wallet_v4r2.code");
+        let boc = BagOfCells::parse_base64(raw)?;
+        let serial = boc.serialize(true)?;
+        let arena = BocArena::parse(&serial)?;
+        assert_eq!(arena.num_roots(), 1);
+
+        let mut cells = vec![(arena.single_root()?, boc.single_root()?.clone())];
+        while let Some((arena_cell, cell)) = cells.pop() {
+            assert_eq!(arena_cell.bit_len(), cell.bit_len());
+            assert_eq!(arena_cell.is_exotic(), cell.is_exotic());
+            assert_eq!(arena_cell.reference_count(), cell.references().len());
+            let mut parser = arena_cell.parser();
+            let mut expected = cell.parser();
+            assert_eq!(
+                parser.load_bits(cell.bit_len())?,
+                expected.load_bits(cell.bit_len())?
+            );
+            parser.ensure_empty()?;
+            for (idx, reference) in cell.references().iter().enumerate() {
+                cells.push((arena_cell.reference(idx)?, reference.clone()));
+            }
+        }
+        assert_eq!(
+            arena.single_root()?.to_cell()?.cell_hash(),
+            boc.single_root()?.cell_hash()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_arena_shares_cells() -> Result<(), TonCellError> {
+        let leaf = Arc::new(CellBuilder::new().store_u32(31, 0x1234)?.build()?);
+        let root = CellBuilder::new()
+            .store_u8(3, 5)?
+            .store_reference(&leaf)?
+            .store_reference(&leaf)?
+            .build()?;
+        let serial = BagOfCells::from_root(root.clone()).serialize(false)?;
+        let arena = BocArena::parse(&serial)?;
+        assert_eq!(arena.cell_count(), 2);
+
+        let arena_root = arena.single_root()?;
+        assert_eq!(arena_root.parser().load_u8(3)?, 5);
+        let refs: Vec<_> = arena_root.references().map(|r| r.index()).collect();
+        assert_eq!(refs, vec![1, 1]);
+        assert_eq!(arena_root.reference(1)?.parser().load_u32(31)?, 0x1234);
+        assert!(arena_root.reference(2).is_err());
+        assert_eq!(arena_root.to_cell()?.as_ref(), &root);
+
+        assert!(BocArena::parse(&serial[..serial.len() - 1]).is_err());
+        Ok(())
+    }
+}
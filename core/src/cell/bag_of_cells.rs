@@ -5,6 +5,96 @@ use base64::engine::general_purpose::STANDARD;
 use crate::cell::raw_boc_from_boc::convert_to_raw_boc;
 use crate::cell::*;
 
+/// Optional parts of serialized BoC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct BocSerializeOptions {
+    /// Stores the index of cell offsets, which allows to load cells lazily.
+    pub has_index: bool,
+    /// Appends CRC32-C checksum of the serialized BoC.
+    pub has_crc32c: bool,
+}
+
+impl BocSerializeOptions {
+    pub fn new() -> BocSerializeOptions {
+        BocSerializeOptions::default()
+    }
+
+    pub fn with_index(&mut self, has_index: bool) -> &mut Self {
+        self.has_index = has_index;
+        self
+    }
+
+    pub fn with_crc32c(&mut self, has_crc32c: bool) -> &mut Self {
+        self.has_crc32c = has_crc32c;
+        self
+    }
+}
+
+pub const DEFAULT_BOC_MAX_CELLS: usize = 1 << 20;
+pub const DEFAULT_BOC_MAX_DEPTH: u16 = 1024;
+pub const DEFAULT_BOC_MAX_TOTAL_BITS: usize = 1 << 26;
+
+/// Limits of BoCs parsed by [`BagOfCells::parse_with_limits`], e.g. BoCs received from users.
+///
+/// The cell count is checked against the header before any cell is read, total bits while
+/// reading cells and depth while building them, so parsing stops as soon as a limit is exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BocLimits {
+    pub max_cells: usize,
+    /// Maximum depth of a cell, i.e. the number of references on the longest path to a leaf.
+    pub max_depth: u16,
+    /// Maximum number of data bits of all cells.
+    pub max_total_bits: usize,
+}
+
+impl BocLimits {
+    /// No limits, as applied by [`BagOfCells::parse`].
+    pub fn unlimited() -> BocLimits {
+        BocLimits {
+            max_cells: usize::MAX,
+            max_depth: u16::MAX,
+            max_total_bits: usize::MAX,
+        }
+    }
+
+    pub fn with_max_cells(&mut self, max_cells: usize) -> &mut Self {
+        self.max_cells = max_cells;
+        self
+    }
+
+    pub fn with_max_depth(&mut self, max_depth: u16) -> &mut Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    pub fn with_max_total_bits(&mut self, max_total_bits: usize) -> &mut Self {
+        self.max_total_bits = max_total_bits;
+        self
+    }
+
+    pub(crate) fn check(&self, limit: BocLimit, actual: usize) -> Result<(), TonCellError> {
+        let max = match limit {
+            BocLimit::CellCount => self.max_cells,
+            BocLimit::Depth => self.max_depth as usize,
+            BocLimit::TotalBits => self.max_total_bits,
+        };
+        if actual > max {
+            return Err(TonCellError::BocLimitExceeded { limit, actual, max });
+        }
+        Ok(())
+    }
+}
+
+impl Default for BocLimits {
+    fn default() -> Self {
+        BocLimits {
+            max_cells: DEFAULT_BOC_MAX_CELLS,
+            max_depth: DEFAULT_BOC_MAX_DEPTH,
+            max_total_bits: DEFAULT_BOC_MAX_TOTAL_BITS,
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, Debug, Clone, Hash)]
 pub struct BagOfCells {
     pub roots: Vec<ArcCell>,
@@ -54,7 +144,18 @@ impl BagOfCells {
     }
 
     pub fn parse(serial: &[u8]) -> Result<BagOfCells, TonCellError> {
-        let raw = RawBagOfCells::parse(serial)?;
+        Self::parse_with_limits(serial, &BocLimits::unlimited())
+    }
+
+    /// Parses an untrusted BoC, failing with `TonCellError::BocLimitExceeded` if it exceeds
+    /// `limits`.
+    ///
+    /// Cells may only refer to cells after them, which rules out reference cycles.
+    pub fn parse_with_limits(
+        serial: &[u8],
+        limits: &BocLimits,
+    ) -> Result<BagOfCells, TonCellError> {
+        let raw = RawBagOfCells::parse_with_limits(serial, limits)?;
         let num_cells = raw.cells.len();
         let mut cells: Vec<ArcCell> = Vec::with_capacity(num_cells);
 
@@ -66,6 +167,12 @@ impl BagOfCells {
                         "References to previous cells are not supported",
                     ));
                 }
+                if *ref_index >= num_cells {
+                    return Err(TonCellError::boc_deserialization_error(format!(
+                        "Invalid reference {} of {} cells",
+                        ref_index, num_cells
+                    )));
+                }
                 references.push(cells[num_cells - 1 - ref_index].clone());
             }
 
@@ -76,6 +183,7 @@ impl BagOfCells {
                 raw_cell.is_exotic,
             )
             .map_boc_deserialization_error()?;
+            limits.check(BocLimit::Depth, cell.cell_depth() as usize)?;
             cells.push(cell.to_arc());
         }
 
@@ -100,9 +208,22 @@ impl BagOfCells {
         Self::parse(&bin)
     }
 
+    /// Serializes the BoC without the index.
+    ///
+    /// Identical cells (by representation hash) are stored only once and cells are
+    /// ordered the same way as by the reference implementation, so the result matches
+    /// BoCs serialized by other TON tools byte for byte.
     pub fn serialize(&self, has_crc32: bool) -> Result<Vec<u8>, TonCellError> {
+        self.serialize_with_options(BocSerializeOptions::new().with_crc32c(has_crc32))
+    }
+
+    /// Serializes the BoC with optional parts set by `options`, see [`BagOfCells::serialize`].
+    pub fn serialize_with_options(
+        &self,
+        options: &BocSerializeOptions,
+    ) -> Result<Vec<u8>, TonCellError> {
         let raw = convert_to_raw_boc(self)?;
-        raw.serialize(has_crc32)
+        raw.serialize(options)
     }
 }
 
@@ -111,9 +232,15 @@ mod tests {
     use std::sync::Arc;
     use std::time::Instant;
 
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+
     use crate::cell::raw_boc_from_boc::convert_to_raw_boc;
-    use crate::cell::{BagOfCells, CellBuilder, TonCellError};
+    use crate::cell::{
+        BagOfCells, BocLimit, BocLimits, BocSerializeOptions, CellBuilder, TonCellError,
+    };
     use crate::message::ZERO_COINS;
+    use crate::TonAddress;
 
     #[test]
     fn cell_hash_works() -> Result<(), TonCellError> {
@@ -304,6 +431,128 @@ mod tests {
         // initially it works for 10.39seceonds
     }
 
+    #[test]
+    fn it_round_trips_cell_with_address() -> Result<(), TonCellError> {
+        let address =
+            TonAddress::from_base64_url("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR")
+                .unwrap();
+        let child = CellBuilder::new().store_u32(32, 0xdeadbeef)?.build()?;
+        let root = CellBuilder::new()
+            .store_u8(8, 7)?
+            .store_address(&address)?
+            .store_child(child)?
+            .build()?;
+        let boc = BagOfCells::from_root(root.clone());
+        for has_crc32 in [false, true] {
+            let serial = boc.serialize(has_crc32)?;
+            let parsed = BagOfCells::parse(&serial)?;
+            let parsed_root = parsed.single_root()?;
+            assert_eq!(parsed_root.as_ref(), &root);
+            assert_eq!(parsed.serialize(has_crc32)?, serial);
+
+            let mut parser = parsed_root.parser();
+            assert_eq!(parser.load_u8(8)?, 7);
+            assert_eq!(parser.load_address()?, address);
+            assert_eq!(parser.remaining_bits(), 0);
+            let child = parser.next_reference()?;
+            assert_eq!(child.bit_len(), 32);
+            assert_eq!(child.data(), &[0xde, 0xad, 0xbe, 0xef]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_serializes_like_reference_implementation() -> Result<(), TonCellError> {
+        // Bundled wallet codes with more than one cell, except highload_v2.code and
+        // wallet_v5r1.code: the tools that serialized them ordered their cells depth-first
+        let codes = [
+            include_str!("../../resources/wallet/highload_v1r1.code"),
+            include_str!("../../resources/wallet/highload_v1r2.code"),
+            include_str!("../../resources/wallet/highload_v2r1.code"),
+            include_str!("../../resources/wallet/highload_v2r2.code"),
+            include_str!("../../resources/wallet/wallet_v3r2.code"),
+            include_str!("../../resources/wallet/wallet_v4r1.code"),
+            include_str!("../../resources/wallet/wallet_v4r2.code"),
+        ];
+        for code in codes {
+            let serial = STANDARD.decode(code.trim()).unwrap();
+            let has_crc32 = serial[4] & 0x40 != 0;
+            let boc = BagOfCells::parse(&serial)?;
+            assert_eq!(boc.serialize(has_crc32)?, serial);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn it_round_trips_with_options() -> Result<(), TonCellError> {
+        let child = Arc::new(CellBuilder::new().store_u32(32, 0xdeadbeef)?.build()?);
+        let root1 = CellBuilder::new()
+            .store_u8(8, 1)?
+            .store_reference(&child)?
+            .build()?;
+        let root2 = CellBuilder::new()
+            .store_u8(8, 2)?
+            .store_reference(&child)?
+            .build()?;
+        let boc = BagOfCells::new(&[Arc::new(root1), Arc::new(root2)]);
+        for has_index in [false, true] {
+            for has_crc32c in [false, true] {
+                let options = *BocSerializeOptions::new()
+                    .with_index(has_index)
+                    .with_crc32c(has_crc32c);
+                let serial = boc.serialize_with_options(&options)?;
+                assert_eq!(serial[4] >> 6, (has_index as u8) << 1 | has_crc32c as u8);
+                let parsed = BagOfCells::parse(&serial)?;
+                assert_eq!(parsed, boc);
+                assert_eq!(parsed.serialize_with_options(&options)?, serial);
+            }
+        }
+
+        let mut serial = boc.serialize(true)?;
+        let last = serial.len() - 1;
+        serial[last] ^= 1;
+        assert!(BagOfCells::parse(&serial).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn it_parses_indexed_boc() -> Result<(), TonCellError> {
+        let root = CellBuilder::new()
+            .store_u8(8, 1)?
+            .store_child(CellBuilder::new().store_u8(8, 2)?.build()?)?
+            .build()?;
+        let boc = BagOfCells::from_root(root);
+        let serial = boc.serialize_with_options(BocSerializeOptions::new().with_index(true))?;
+        // serialized_boc_idx#68ff65f3 has the same layout without flags and root list
+        let size = serial[4] & 0b111;
+        let mut indexed = vec![0x68, 0xff, 0x65, 0xf3, size];
+        indexed.extend_from_slice(&serial[5..6 + 3 * size as usize + serial[5] as usize]);
+        indexed.extend_from_slice(&serial[6 + 4 * size as usize + serial[5] as usize..]);
+        assert_eq!(BagOfCells::parse(&indexed)?, boc);
+        Ok(())
+    }
+
+    #[test]
+    fn it_rejects_invalid_index_size() {
+        // has_idx, size 4, off_bytes 0xff, 0xffffffff cells, a single root
+        let mut serial = vec![
+            0xb5, 0xee, 0x9c, 0x72, 0x84, 0xff, 0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x01,
+            0x00, 0x00, 0x00, 0x00,
+        ];
+        serial.extend_from_slice(&[0; 0xff + 4]);
+        assert!(matches!(
+            BagOfCells::parse(&serial),
+            Err(TonCellError::BagOfCellsDeserializationError(_))
+        ));
+
+        // Index of 0xffffffff cells of 8 bytes each
+        serial[5] = 8;
+        assert!(matches!(
+            BagOfCells::parse(&serial),
+            Err(TonCellError::BagOfCellsDeserializationError(_))
+        ));
+    }
+
     #[test]
     fn it_constructs_raw() -> Result<(), TonCellError> {
         let leaf = CellBuilder::new().store_byte(10)?.build()?;
@@ -319,4 +568,101 @@ mod tests {
         let _raw = convert_to_raw_boc(&boc)?;
         Ok(())
     }
+
+    #[test]
+    fn it_deduplicates_shared_cells() -> Result<(), TonCellError> {
+        let shared_leaf = Arc::new(CellBuilder::new().store_u32(32, 0xdeadbeef)?.build()?);
+        let shared_inter = Arc::new(
+            CellBuilder::new()
+                .store_byte(20)?
+                .store_reference(&shared_leaf)?
+                .store_reference(&shared_leaf)?
+                .build()?,
+        );
+        let shared_root = CellBuilder::new()
+            .store_byte(30)?
+            .store_reference(&shared_inter)?
+            .store_reference(&shared_inter)?
+            .store_reference(&shared_leaf)?
+            .build()?;
+        let shared_boc = BagOfCells::from_root(shared_root);
+
+        let raw = convert_to_raw_boc(&shared_boc)?;
+        assert_eq!(raw.cells.len(), 3);
+        for (index, cell) in raw.cells.iter().enumerate() {
+            assert!(cell.references.iter().all(|r| *r > index));
+        }
+
+        let distinct_root = CellBuilder::new()
+            .store_byte(30)?
+            .store_child(
+                CellBuilder::new()
+                    .store_byte(20)?
+                    .store_child(CellBuilder::new().store_u32(32, 1)?.build()?)?
+                    .store_child(CellBuilder::new().store_u32(32, 2)?.build()?)?
+                    .build()?,
+            )?
+            .store_child(
+                CellBuilder::new()
+                    .store_byte(21)?
+                    .store_child(CellBuilder::new().store_u32(32, 3)?.build()?)?
+                    .store_child(CellBuilder::new().store_u32(32, 4)?.build()?)?
+                    .build()?,
+            )?
+            .store_child(CellBuilder::new().store_u32(32, 5)?.build()?)?
+            .build()?;
+        let distinct_boc = BagOfCells::from_root(distinct_root);
+
+        let shared_serial = shared_boc.serialize(false)?;
+        let distinct_serial = distinct_boc.serialize(false)?;
+        assert!(shared_serial.len() < distinct_serial.len());
+
+        let parsed = BagOfCells::parse(&shared_serial)?;
+        assert_eq!(parsed, shared_boc);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_with_limits() -> Result<(), TonCellError> {
+        let mut cell = CellBuilder::new().store_u32(32, 0)?.build()?;
+        for i in 1..10 {
+            cell = CellBuilder::new()
+                .store_u32(32, i)?
+                .store_child(cell)?
+                .build()?;
+        }
+        let serial = BagOfCells::from_root(cell).serialize(true)?;
+        let limit_exceeded =
+            |limits: &BocLimits| match BagOfCells::parse_with_limits(&serial, limits) {
+                Err(TonCellError::BocLimitExceeded { limit, actual, max }) => {
+                    Some((limit, actual, max))
+                }
+                _ => None,
+            };
+        assert_eq!(
+            limit_exceeded(BocLimits::default().with_max_cells(5)),
+            Some((BocLimit::CellCount, 10, 5))
+        );
+        assert_eq!(
+            limit_exceeded(BocLimits::default().with_max_depth(5)),
+            Some((BocLimit::Depth, 6, 5))
+        );
+        assert_eq!(
+            limit_exceeded(BocLimits::default().with_max_total_bits(100)),
+            Some((BocLimit::TotalBits, 128, 100))
+        );
+        let parsed = BagOfCells::parse_with_limits(&serial, &BocLimits::default())?;
+        assert_eq!(parsed.single_root()?.cell_depth(), 9);
+
+        // A single cell referring to a missing cell
+        let serial = [
+            0xb5, 0xee, 0x9c, 0x72, 0x01, 0x01, 0x01, 0x01, 0x00, 0x03, 0x00, 0x01, 0x00, 0x05,
+        ];
+        let result = BagOfCells::parse_with_limits(&serial, &BocLimits::default());
+        assert!(matches!(
+            result,
+            Err(TonCellError::BagOfCellsDeserializationError(_))
+        ));
+        Ok(())
+    }
 }
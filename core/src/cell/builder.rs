@@ -6,6 +6,7 @@ use bitstream_io::{BigEndian, BitWrite, BitWriter};
 use num_bigint::{BigInt, BigUint, Sign};
 use num_traits::{One, Zero};
 
+use crate::cell::dict::predefined_writers::val_writer_ref_cell;
 use crate::cell::dict::{DictBuilder, ValWriter};
 use crate::cell::error::{MapTonCellError, TonCellError};
 use crate::cell::{ArcCell, Cell, CellParser};
@@ -14,6 +15,8 @@ use crate::TonAddress;
 pub(crate) const MAX_CELL_BITS: usize = 1023;
 pub(crate) const MAX_CELL_REFERENCES: usize = 4;
 pub(crate) const MAX_LEVEL_MASK: u32 = 3;
+/// Number of whole bytes fitting in a cell.
+const SNAKE_CELL_BYTES: usize = MAX_CELL_BITS / 8;
 
 pub struct CellBuilder {
     bit_writer: BitWriter<Vec<u8>, BigEndian>,
@@ -249,6 +252,44 @@ impl CellBuilder {
         Ok(self)
     }
 
+    /// Stores `data` in snake format: as many bytes as fit in this cell, the rest in the
+    /// chain of the first references of child cells, see TEP-64.
+    ///
+    /// Prefixes like `0x00` of on-chain content must be stored before calling this method.
+    pub fn store_snake_data(&mut self, data: &[u8]) -> Result<&mut Self, TonCellError> {
+        let head_len = (self.remaining_bits() / 8).min(data.len());
+        let (head, rest) = data.split_at(head_len);
+        self.store_slice(head)?;
+        let mut tail: Option<Cell> = None;
+        let chunks: Vec<_> = rest.chunks(SNAKE_CELL_BYTES).collect();
+        for chunk in chunks.into_iter().rev() {
+            let mut builder = CellBuilder::new();
+            builder.store_slice(chunk)?;
+            if let Some(next) = tail {
+                builder.store_child(next)?;
+            }
+            tail = Some(builder.build()?);
+        }
+        if let Some(next) = tail {
+            self.store_child(next)?;
+        }
+        Ok(self)
+    }
+
+    /// Stores `data` in chunked format of TEP-64, i.e.
+    /// `chunked_data#_ data:(HashmapE 32 ^(SnakeData ~0)) = ChunkedData;`
+    pub fn store_chunked_data(&mut self, data: &[u8]) -> Result<&mut Self, TonCellError> {
+        let chunks = data
+            .chunks(SNAKE_CELL_BYTES)
+            .enumerate()
+            .map(|(idx, chunk)| {
+                let cell = CellBuilder::new().store_slice(chunk)?.build()?;
+                Ok((idx as u32, Arc::new(cell)))
+            })
+            .collect::<Result<HashMap<_, _>, TonCellError>>()?;
+        self.store_maybe_dict(32, val_writer_ref_cell, chunks)
+    }
+
     pub fn store_cell_data(&mut self, cell: &Cell) -> Result<&mut Self, TonCellError> {
         let mut parser = cell.parser();
         self.store_remaining_bits(&mut parser)?;
@@ -305,6 +346,8 @@ impl CellBuilder {
         Ok(self)
     }
 
+    /// Stores `data` as `Hashmap n X`, i.e. the root of a non-empty dictionary inlined
+    /// into the cell.
     pub fn store_dict<K, V>(
         &mut self,
         key_len_bits: usize,
@@ -319,6 +362,26 @@ impl CellBuilder {
         self.store_cell(&dict_cell)
     }
 
+    /// Stores `data` as `HashmapE n X`, i.e. `Maybe ^(Hashmap n X)`, the way most contracts
+    /// store dictionaries which may be empty.
+    pub fn store_maybe_dict<K, V>(
+        &mut self,
+        key_len_bits: usize,
+        value_writer: ValWriter<V>,
+        data: HashMap<K, V>,
+    ) -> Result<&mut Self, TonCellError>
+    where
+        BigUint: From<K>,
+    {
+        if data.is_empty() {
+            return self.store_bit(false);
+        }
+        let dict_builder = DictBuilder::new(key_len_bits, value_writer, data)?;
+        let dict_cell = dict_builder.build()?;
+        self.store_bit(true)?;
+        self.store_child(dict_cell)
+    }
+
     pub fn remaining_bits(&self) -> usize {
         MAX_CELL_BITS - self.bits_to_write
     }
@@ -399,7 +462,8 @@ mod tests {
     use num_traits::Zero;
 
     use crate::cell::builder::extend_and_invert_bits;
-    use crate::cell::dict::predefined_readers::{key_reader_u8, val_reader_uint};
+    use crate::cell::dict::predefined_readers::{key_reader_u32, key_reader_u8, val_reader_uint};
+    use crate::cell::dict::predefined_writers::val_writer_unsigned_min_size;
     use crate::cell::{CellBuilder, TonCellError};
     use crate::types::TonAddress;
 
@@ -686,4 +750,67 @@ mod tests {
         assert_eq!(data, parsed);
         Ok(())
     }
+
+    #[test]
+    fn test_store_snake_data() -> Result<(), TonCellError> {
+        let data: Vec<u8> = (0..400).map(|i| (i % 251) as u8).collect();
+        for len in [0, 10, 126, 127, 128, 400] {
+            let cell = CellBuilder::new()
+                .store_u8(8, 0)?
+                .store_snake_data(&data[..len])?
+                .build()?;
+            let mut parser = cell.parser();
+            assert_eq!(parser.load_u8(8)?, 0);
+            assert_eq!(parser.load_snake_data()?, &data[..len]);
+        }
+
+        let uri = "https://example.com/metadata/".repeat(10);
+        let cell = CellBuilder::new()
+            .store_u8(8, 0)?
+            .store_snake_data(uri.as_bytes())?
+            .build()?;
+        assert_eq!(cell.bit_len(), 1016);
+        assert_eq!(cell.reference(0)?.bit_len(), 1016);
+        assert_eq!(cell.load_snake_formatted_string()?, uri);
+        Ok(())
+    }
+
+    #[test]
+    fn test_store_chunked_data() -> Result<(), TonCellError> {
+        let data: Vec<u8> = (0..1000).map(|i| (i % 253) as u8).collect();
+        for len in [0, 1, 127, 128, 1000] {
+            let cell = CellBuilder::new()
+                .store_u8(8, 1)?
+                .store_chunked_data(&data[..len])?
+                .build()?;
+            let mut parser = cell.parser();
+            assert_eq!(parser.load_u8(8)?, 1);
+            assert_eq!(parser.load_chunked_data()?, &data[..len]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_store_maybe_dict() -> Result<(), TonCellError> {
+        let data = HashMap::from([(1u32, BigUint::from(2u8)), (300, BigUint::from(4u8))]);
+        let cell = CellBuilder::new()
+            .store_maybe_dict(32, val_writer_unsigned_min_size, data.clone())?
+            .store_maybe_dict(
+                32,
+                val_writer_unsigned_min_size,
+                HashMap::<u32, BigUint>::new(),
+            )?
+            .store_u8(8, 0xff)?
+            .build()?;
+        assert_eq!(cell.bit_len(), 1 + 1 + 8);
+        assert_eq!(cell.references().len(), 1);
+
+        let mut parser = cell.parser();
+        let parsed = parser.load_maybe_dict(32, key_reader_u32, val_reader_uint)?;
+        assert_eq!(data, parsed);
+        let parsed = parser.load_maybe_dict(32, key_reader_u32, val_reader_uint)?;
+        assert!(parsed.is_empty());
+        assert_eq!(parser.load_u8(8)?, 0xff);
+        Ok(())
+    }
 }
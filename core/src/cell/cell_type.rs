@@ -9,8 +9,9 @@ use crate::cell::{ArcCell, Cell, MapTonCellError, TonCellError, DEPTH_BYTES, MAX
 use crate::types::{TON_HASH_BYTES, ZERO_HASH};
 use crate::TonHash;
 
+/// Type of a cell, see https://docs.ton.org/develop/data-formats/exotic-cells
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
-pub(crate) enum CellType {
+pub enum CellType {
     #[default]
     Ordinary,
     PrunedBranch,
@@ -26,6 +27,11 @@ struct Pruned {
 }
 
 impl CellType {
+    pub const PRUNED_BRANCH_TAG: u8 = 1;
+    pub const LIBRARY_TAG: u8 = 2;
+    pub const MERKLE_PROOF_TAG: u8 = 3;
+    pub const MERKLE_UPDATE_TAG: u8 = 4;
+
     pub(crate) fn determine_exotic_cell_type(data: &[u8]) -> Result<Self, TonCellError> {
         let Some(type_byte) = data.first() else {
             return Err(TonCellError::InvalidExoticCellData(
@@ -33,11 +39,11 @@ impl CellType {
             ));
         };
 
-        let cell_type = match type_byte {
-            1 => CellType::PrunedBranch,
-            2 => CellType::Library,
-            3 => CellType::MerkleProof,
-            4 => CellType::MerkleUpdate,
+        let cell_type = match *type_byte {
+            CellType::PRUNED_BRANCH_TAG => CellType::PrunedBranch,
+            CellType::LIBRARY_TAG => CellType::Library,
+            CellType::MERKLE_PROOF_TAG => CellType::MerkleProof,
+            CellType::MERKLE_UPDATE_TAG => CellType::MerkleUpdate,
             cell_type => {
                 return Err(TonCellError::InvalidExoticCellData(format!(
                     "Invalid first byte in exotic cell data: {}",
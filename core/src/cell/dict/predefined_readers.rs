@@ -4,7 +4,7 @@ use num_traits::ToPrimitive;
 use crate::cell::TonCellError::{InternalError, InvalidInput};
 use crate::cell::{ArcCell, Cell, CellParser, TonCellError};
 use crate::types::TON_HASH_BYTES;
-use crate::TonHash;
+use crate::{TonAddress, TonHash};
 
 pub fn key_reader_u8(raw_key: &BigUint) -> Result<u8, TonCellError> {
     validate_bit_len(raw_key, 8)?;
@@ -77,6 +77,14 @@ pub fn val_reader_int(parser: &mut CellParser) -> Result<BigInt, TonCellError> {
     Ok(result)
 }
 
+pub fn val_reader_coins(parser: &mut CellParser) -> Result<BigUint, TonCellError> {
+    parser.load_coins()
+}
+
+pub fn val_reader_address(parser: &mut CellParser) -> Result<TonAddress, TonCellError> {
+    parser.load_address()
+}
+
 fn validate_bit_len(val: &BigUint, max_bits: usize) -> Result<(), TonCellError> {
     if val.bits() > max_bits as u64 {
         let msg = format!(
@@ -4,6 +4,7 @@ use std::sync::Arc;
 use num_bigint::{BigInt, BigUint};
 
 use crate::cell::{Cell, CellBuilder, TonCellError};
+use crate::TonAddress;
 
 #[allow(dead_code)]
 pub fn val_writer_ref_cell(builder: &mut CellBuilder, val: Arc<Cell>) -> Result<(), TonCellError> {
@@ -11,6 +12,16 @@ pub fn val_writer_ref_cell(builder: &mut CellBuilder, val: Arc<Cell>) -> Result<
     Ok(())
 }
 
+pub fn val_writer_coins(builder: &mut CellBuilder, val: BigUint) -> Result<(), TonCellError> {
+    builder.store_coins(&val)?;
+    Ok(())
+}
+
+pub fn val_writer_address(builder: &mut CellBuilder, val: TonAddress) -> Result<(), TonCellError> {
+    builder.store_address(&val)?;
+    Ok(())
+}
+
 pub fn val_writer_unsigned_min_size<V>(
     builder: &mut CellBuilder,
     val: V,
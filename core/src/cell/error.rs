@@ -1,3 +1,5 @@
+use std::fmt;
+
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -5,6 +7,13 @@ pub enum TonCellError {
     #[error("Bag of cells deserialization error ({0})")]
     BagOfCellsDeserializationError(String),
 
+    #[error("Bag of cells limit exceeded (Limit: {limit}, actual: {actual}, max: {max})")]
+    BocLimitExceeded {
+        limit: BocLimit,
+        actual: usize,
+        max: usize,
+    },
+
     #[error("Bag of cells serialization error ({0})")]
     BagOfCellsSerializationError(String),
 
@@ -44,6 +53,25 @@ pub enum TonCellError {
     },
 }
 
+/// Limit of `BocLimits` exceeded by a parsed BoC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BocLimit {
+    CellCount,
+    Depth,
+    TotalBits,
+}
+
+impl fmt::Display for BocLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            BocLimit::CellCount => "cell count",
+            BocLimit::Depth => "depth",
+            BocLimit::TotalBits => "total bits",
+        };
+        f.write_str(name)
+    }
+}
+
 pub trait MapTonCellError<R, E>
 where
     E: std::error::Error,
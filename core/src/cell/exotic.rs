@@ -0,0 +1,180 @@
+use std::sync::Arc;
+
+use crate::cell::cell_type::CellType;
+use crate::cell::level_mask::LevelMask;
+use crate::cell::{ArcCell, Cell, TonCellError, MAX_LEVEL};
+use crate::types::TON_HASH_BYTES;
+use crate::TonHash;
+
+/// Construction and inspection of exotic cells.
+///
+/// See https://docs.ton.org/develop/data-formats/exotic-cells for details.
+impl Cell {
+    /// Creates a pruned branch replacing `cell` in a Merkle proof or update
+    /// at `merkle_depth`, i.e. the number of Merkle proof and update cells above it.
+    pub fn new_pruned_branch(cell: &Cell, merkle_depth: u8) -> Result<Cell, TonCellError> {
+        let level_mask = cell.level_mask;
+        if merkle_depth == 0 || merkle_depth > MAX_LEVEL || merkle_depth <= level_mask.level() {
+            return Err(TonCellError::InvalidInput(format!(
+                "Can't prune cell of level {} at Merkle depth {merkle_depth}",
+                level_mask.level()
+            )));
+        }
+        let new_level_mask = level_mask.apply_or(LevelMask::new(1 << (merkle_depth - 1)));
+        let levels: Vec<u8> = (0..=level_mask.level())
+            .filter(|level| level_mask.is_significant(*level))
+            .collect();
+
+        let mut data = vec![CellType::PRUNED_BRANCH_TAG, new_level_mask.mask() as u8];
+        for level in &levels {
+            data.extend(cell.get_hash(*level));
+        }
+        for level in &levels {
+            data.extend(cell.get_depth(*level).to_be_bytes());
+        }
+        let bit_len = data.len() * 8;
+        Cell::new(data, bit_len, vec![], true)
+    }
+
+    /// Creates a library cell, referring to the library cell with `hash`.
+    pub fn new_library(hash: &TonHash) -> Result<Cell, TonCellError> {
+        let mut data = vec![CellType::LIBRARY_TAG];
+        data.extend(hash);
+        Cell::new(data, (1 + TON_HASH_BYTES) * 8, vec![], true)
+    }
+
+    /// Creates a Merkle proof of the tree `root`, whose subtrees not needed for the proof
+    /// are replaced with pruned branches.
+    pub fn new_merkle_proof(root: &ArcCell) -> Result<Cell, TonCellError> {
+        let mut data = vec![CellType::MERKLE_PROOF_TAG];
+        data.extend(root.get_hash(0));
+        data.extend(root.get_depth(0).to_be_bytes());
+        let bit_len = data.len() * 8;
+        Cell::new(data, bit_len, vec![root.clone()], true)
+    }
+
+    /// Creates a Merkle update, proving the transition of the tree `old` to the tree `new`.
+    pub fn new_merkle_update(old: &ArcCell, new: &ArcCell) -> Result<Cell, TonCellError> {
+        let mut data = vec![CellType::MERKLE_UPDATE_TAG];
+        data.extend(old.get_hash(0));
+        data.extend(new.get_hash(0));
+        data.extend(old.get_depth(0).to_be_bytes());
+        data.extend(new.get_depth(0).to_be_bytes());
+        let bit_len = data.len() * 8;
+        Cell::new(data, bit_len, vec![old.clone(), new.clone()], true)
+    }
+
+    pub fn cell_type(&self) -> CellType {
+        self.cell_type
+    }
+
+    /// Level of the cell, i.e. the number of Merkle proof and update cells above
+    /// the pruned branches of it.
+    pub fn level(&self) -> u8 {
+        self.level_mask.level()
+    }
+
+    /// Hash of the library cell referred by this library cell.
+    pub fn library_hash(&self) -> Result<TonHash, TonCellError> {
+        if self.cell_type != CellType::Library {
+            return Err(TonCellError::InvalidExoticCellType(
+                self.data.first().copied(),
+            ));
+        }
+        let mut hash = [0; TON_HASH_BYTES];
+        hash.copy_from_slice(&self.data[1..1 + TON_HASH_BYTES]);
+        Ok(hash)
+    }
+
+    /// Creates a copy of the cell with references at `indexes` replaced with pruned branches
+    /// at `merkle_depth`.
+    pub fn with_pruned_references(
+        &self,
+        indexes: &[usize],
+        merkle_depth: u8,
+    ) -> Result<Cell, TonCellError> {
+        let references = self
+            .references
+            .iter()
+            .enumerate()
+            .map(|(idx, reference)| {
+                if indexes.contains(&idx) {
+                    Cell::new_pruned_branch(reference, merkle_depth).map(Arc::new)
+                } else {
+                    Ok(reference.clone())
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Cell::new(
+            self.data.clone(),
+            self.bit_len,
+            references,
+            self.is_exotic(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::CellType;
+    use crate::cell::{BagOfCells, Cell, CellBuilder, TonCellError};
+
+    #[test]
+    fn exotic_cells_round_trip() -> Result<(), TonCellError> {
+        let leaf = CellBuilder::new().store_u64(64, 42)?.build()?;
+        let left = Arc::new(
+            CellBuilder::new()
+                .store_u8(8, 1)?
+                .store_child(leaf.clone())?
+                .build()?,
+        );
+        let right = Arc::new(CellBuilder::new().store_u32(32, 7)?.build()?);
+        let tree = CellBuilder::new()
+            .store_reference(&left)?
+            .store_reference(&right)?
+            .build()?;
+
+        let pruned = tree.with_pruned_references(&[0], 1)?;
+        assert_eq!(pruned.reference(0)?.cell_type(), CellType::PrunedBranch);
+        assert_eq!(pruned.level(), 1);
+        assert_eq!(pruned.get_hash(0), tree.cell_hash());
+        assert_ne!(pruned.cell_hash(), tree.cell_hash());
+        assert!(Cell::new_pruned_branch(&pruned, 1).is_err());
+        let nested = Cell::new_pruned_branch(&pruned, 2)?;
+        assert_eq!(nested.get_level_mask(), 0b11);
+        assert_eq!(nested.get_hash(0), tree.cell_hash());
+        assert_eq!(nested.get_hash(1), pruned.get_hash(1));
+
+        let proof = Arc::new(Cell::new_merkle_proof(&Arc::new(pruned))?);
+        assert_eq!(proof.cell_type(), CellType::MerkleProof);
+        assert_eq!(proof.level(), 0);
+        assert_eq!(proof.reference(0)?.get_hash(0), tree.cell_hash());
+
+        let new_tree = CellBuilder::new()
+            .store_reference(&left)?
+            .store_child(CellBuilder::new().store_u32(32, 8)?.build()?)?
+            .build()?;
+        let update = Cell::new_merkle_update(
+            &Arc::new(tree.with_pruned_references(&[0], 1)?),
+            &Arc::new(new_tree.with_pruned_references(&[0], 1)?),
+        )?;
+        assert_eq!(update.cell_type(), CellType::MerkleUpdate);
+
+        let library = Cell::new_library(&leaf.cell_hash())?;
+        assert_eq!(library.cell_type(), CellType::Library);
+        assert_eq!(library.library_hash()?, leaf.cell_hash());
+        assert!(leaf.library_hash().is_err());
+
+        let boc = BagOfCells::new(&[proof.clone(), Arc::new(update), Arc::new(library)])
+            .serialize(true)?;
+        let parsed = BagOfCells::parse(&boc)?;
+        assert_eq!(parsed.roots[0], proof);
+        assert_eq!(parsed.roots[0].cell_hash(), proof.cell_hash());
+        assert_eq!(parsed.roots[1].cell_type(), CellType::MerkleUpdate);
+        assert_eq!(parsed.roots[2].library_hash()?, leaf.cell_hash());
+        assert_eq!(parsed.serialize(true)?, boc);
+        Ok(())
+    }
+}
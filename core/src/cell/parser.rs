@@ -9,7 +9,9 @@ use num_traits::identities::Zero;
 
 use super::dict::{DictParser, KeyReader, SnakeFormatDict, ValReader};
 use super::{ArcCell, Cell, CellBuilder};
-use crate::cell::dict::predefined_readers::{key_reader_256bit, val_reader_snake_formatted_string};
+use crate::cell::dict::predefined_readers::{
+    key_reader_256bit, key_reader_u32, val_reader_ref_cell, val_reader_snake_formatted_string,
+};
 use crate::cell::util::*;
 use crate::cell::{MapTonCellError, TonCellError};
 use crate::TonAddress;
@@ -174,26 +176,64 @@ impl<'a> CellParser<'a> {
         cell
     }
 
+    /// Loads `MsgAddress`, returning [`TonAddress::NULL`] for `addr_none`.
+    ///
+    /// For anycast addresses the first `depth` bits of the hash are replaced with
+    /// `rewrite_pfx`, i.e. the address of the account the message is delivered to.
+    /// `addr_var` is supported only with 256-bit addresses, `addr_extern` is rejected.
     pub fn load_address(&mut self) -> Result<TonAddress, TonCellError> {
         self.ensure_enough_bits(2)?;
         let tp = self.bit_reader.read::<u8>(2).map_cell_parser_error()?;
         match tp {
             0 => Ok(TonAddress::null()),
             2 => {
-                self.ensure_enough_bits(1 + 8 + 32 * 8)?;
-                let _res1 = self.bit_reader.read::<u8>(1).map_cell_parser_error()?;
-                let wc = self.bit_reader.read::<u8>(8).map_cell_parser_error()?;
+                let anycast = self.load_anycast()?;
+                let wc = self.load_u8(8)? as i8;
                 let mut hash_part = [0_u8; 32];
-                self.bit_reader
-                    .read_bytes(&mut hash_part)
-                    .map_cell_parser_error()?;
-                let addr = TonAddress::new(wc as i32, &hash_part);
+                self.load_slice(&mut hash_part)?;
+                let mut addr = TonAddress::new(wc as i32, &hash_part);
+                if let Some((depth, rewrite_pfx)) = anycast {
+                    addr.rewrite_prefix(depth, rewrite_pfx);
+                }
+                Ok(addr)
+            }
+            3 => {
+                let anycast = self.load_anycast()?;
+                let addr_len = self.load_u16(9)?;
+                if addr_len != 256 {
+                    return Err(TonCellError::CellParserError(format!(
+                        "Unsupported length of addr_var: {addr_len}"
+                    )));
+                }
+                let wc = self.load_u32(32)? as i32;
+                let mut hash_part = [0_u8; 32];
+                self.load_slice(&mut hash_part)?;
+                let mut addr = TonAddress::new(wc, &hash_part);
+                if let Some((depth, rewrite_pfx)) = anycast {
+                    addr.rewrite_prefix(depth, rewrite_pfx);
+                }
                 Ok(addr)
             }
             _ => Err(TonCellError::InvalidAddressType(tp)),
         }
     }
 
+    /// Loads `Maybe Anycast`, i.e. `anycast_info$_ depth:(#<= 30) { depth >= 1 }
+    /// rewrite_pfx:(bits depth) = Anycast;`
+    fn load_anycast(&mut self) -> Result<Option<(u8, u32)>, TonCellError> {
+        if !self.load_bit()? {
+            return Ok(None);
+        }
+        let depth = self.load_u8(5)?;
+        if !(1..=30).contains(&depth) {
+            return Err(TonCellError::CellParserError(format!(
+                "Invalid anycast depth: {depth}"
+            )));
+        }
+        let rewrite_pfx = self.load_u32(depth as usize)?;
+        Ok(Some((depth, rewrite_pfx)))
+    }
+
     pub fn load_unary_length(&mut self) -> Result<usize, TonCellError> {
         let mut res = 0;
         while self.load_bit()? {
@@ -202,6 +242,7 @@ impl<'a> CellParser<'a> {
         Ok(res)
     }
 
+    /// Loads `Hashmap n X`, i.e. the root of a non-empty dictionary inlined into the cell.
     pub fn load_dict<K: Eq + Hash, V>(
         &mut self,
         key_len: usize,
@@ -212,6 +253,73 @@ impl<'a> CellParser<'a> {
         dict_parser.parse(self)
     }
 
+    /// Loads `HashmapE n X`, i.e. `Maybe ^(Hashmap n X)`, returning an empty map for
+    /// an empty dictionary.
+    pub fn load_maybe_dict<K: Eq + Hash, V>(
+        &mut self,
+        key_len: usize,
+        key_reader: KeyReader<K>,
+        val_reader: ValReader<V>,
+    ) -> Result<HashMap<K, V>, TonCellError> {
+        match self.load_maybe_cell_ref()? {
+            Some(root) => root.parser().load_dict(key_len, key_reader, val_reader),
+            None => Ok(HashMap::new()),
+        }
+    }
+
+    /// Loads data in snake format: the remaining bytes of the cell followed by the data
+    /// of the chain of the first references of child cells, see TEP-64.
+    ///
+    /// Prefixes like `0x00` of on-chain content must be loaded before calling this method.
+    pub fn load_snake_data(&mut self) -> Result<Vec<u8>, TonCellError> {
+        let mut data = self.load_remaining_bytes()?;
+        let mut next = if self.next_ref < self.references.len() {
+            Some(self.next_reference()?)
+        } else {
+            None
+        };
+        while let Some(cell) = next {
+            let mut parser = cell.parser();
+            data.extend(parser.load_remaining_bytes()?);
+            next = match cell.references().len() {
+                0 => None,
+                1 => Some(parser.next_reference()?),
+                n => {
+                    return Err(TonCellError::boc_deserialization_error(format!(
+                        "Invalid snake format string: found cell with {} references",
+                        n
+                    )))
+                }
+            };
+        }
+        Ok(data)
+    }
+
+    /// Loads data in chunked format of TEP-64, i.e.
+    /// `chunked_data#_ data:(HashmapE 32 ^(SnakeData ~0)) = ChunkedData;`
+    pub fn load_chunked_data(&mut self) -> Result<Vec<u8>, TonCellError> {
+        let mut chunks: Vec<_> = self
+            .load_maybe_dict(32, key_reader_u32, val_reader_ref_cell)?
+            .into_iter()
+            .collect();
+        chunks.sort_by_key(|(idx, _)| *idx);
+        let mut data = Vec::new();
+        for (_, chunk) in chunks {
+            data.extend(chunk.parser().load_remaining_bytes()?);
+        }
+        Ok(data)
+    }
+
+    fn load_remaining_bytes(&mut self) -> Result<Vec<u8>, TonCellError> {
+        let remaining_bits = self.remaining_bits();
+        if !remaining_bits.is_multiple_of(8) {
+            return Err(TonCellError::CellParserError(format!(
+                "Snake data must consist of whole bytes, got {remaining_bits} bits"
+            )));
+        }
+        self.load_bytes(remaining_bits / 8)
+    }
+
     ///Snake format when we store part of the data in a cell and the rest of the data in the first child cell (and so recursively).
     ///
     ///Must be prefixed with 0x00 byte.
@@ -310,7 +418,7 @@ mod tests {
 
     use num_bigint::{BigInt, BigUint};
 
-    use crate::cell::{Cell, CellBuilder, EitherCellLayout};
+    use crate::cell::{Cell, CellBuilder, EitherCellLayout, TonCellError};
     use crate::TonAddress;
 
     #[test]
@@ -551,6 +659,40 @@ mod tests {
         assert!(parser.load_address().is_err());
     }
 
+    #[test]
+    fn test_load_address_workchain_and_anycast() -> Result<(), TonCellError> {
+        let addr = TonAddress::new(-1, &[0x55; 32]);
+        let cell = CellBuilder::new().store_address(&addr)?.build()?;
+        assert_eq!(cell.parser().load_address()?, addr);
+
+        // addr_std with anycast of depth 4 rewriting 0x5 prefix to 0xa
+        let cell = CellBuilder::new()
+            .store_u8(2, 0b10)?
+            .store_bit(true)?
+            .store_u8(5, 4)?
+            .store_u8(4, 0xa)?
+            .store_u8(8, 0)?
+            .store_slice(&[0x55; 32])?
+            .build()?;
+        let mut expected = [0x55; 32];
+        expected[0] = 0xa5;
+        assert_eq!(cell.parser().load_address()?, TonAddress::new(0, &expected));
+
+        // addr_var with 256-bit address
+        let cell = CellBuilder::new()
+            .store_u8(2, 0b11)?
+            .store_bit(false)?
+            .store_u32(9, 256)?
+            .store_i32(32, 7)?
+            .store_slice(&[0x55; 32])?
+            .build()?;
+        assert_eq!(
+            cell.parser().load_address()?,
+            TonAddress::new(7, &[0x55; 32])
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_ensure_empty() {
         let cell = Cell::new([0b10101010].to_vec(), 7, vec![], false).unwrap();
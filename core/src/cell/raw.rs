@@ -5,7 +5,7 @@ use crc::Crc;
 use lazy_static::lazy_static;
 
 use crate::cell::level_mask::LevelMask;
-use crate::cell::{MapTonCellError, TonCellError};
+use crate::cell::{BocLimit, BocLimits, BocSerializeOptions, MapTonCellError, TonCellError};
 
 lazy_static! {
     pub static ref CRC_32_ISCSI: Crc<u32> = Crc::<u32>::new(&crc::CRC_32_ISCSI);
@@ -51,19 +51,33 @@ pub(crate) struct RawBagOfCells {
 }
 
 const GENERIC_BOC_MAGIC: u32 = 0xb5ee9c72;
-const _INDEXED_BOC_MAGIC: u32 = 0x68ff65f3;
-const _INDEXED_CRC32_MAGIC: u32 = 0xacc3a728;
+const INDEXED_BOC_MAGIC: u32 = 0x68ff65f3;
+const INDEXED_CRC32_MAGIC: u32 = 0xacc3a728;
 
-impl RawBagOfCells {
-    pub(crate) fn parse(serial: &[u8]) -> Result<RawBagOfCells, TonCellError> {
+/// Header of serialized BoC with the positions of cell data in the serialized buffer.
+#[derive(PartialEq, Eq, Debug, Clone, Hash)]
+pub(crate) struct BocHeader {
+    /// Size of cell references in bytes.
+    pub(crate) size: u8,
+    pub(crate) cell_count: usize,
+    pub(crate) roots: Vec<usize>,
+    /// Position of the first cell.
+    pub(crate) cells_start: usize,
+    /// Position after the last cell.
+    pub(crate) cells_end: usize,
+}
+
+impl BocHeader {
+    /// Parses the header and verifies CRC32-C of the BoC if present.
+    pub(crate) fn parse(serial: &[u8]) -> Result<BocHeader, TonCellError> {
         let cursor = Cursor::new(serial);
 
         let mut reader: ByteReader<Cursor<&[u8]>, BigEndian> =
             ByteReader::endian(cursor, BigEndian);
-        // serialized_boc#b5ee9c72
         let magic = reader.read::<u32>().map_boc_deserialization_error()?;
 
-        let (has_idx, has_crc32c, _has_cache_bits, size) = match magic {
+        let (has_root_list, has_idx, has_crc32c, _has_cache_bits, size) = match magic {
+            // serialized_boc#b5ee9c72
             GENERIC_BOC_MAGIC => {
                 // has_idx:(## 1) has_crc32c:(## 1) has_cache_bits:(## 1) flags:(## 2) { flags = 0 }
                 let header = reader.read::<u8>().map_boc_deserialization_error()?;
@@ -73,65 +87,152 @@ impl RawBagOfCells {
                 // size:(## 3) { size <= 4 }
                 let size = header & 0b0000_0111;
 
-                (has_idx, has_crc32c, has_cache_bits, size)
+                (true, has_idx, has_crc32c, has_cache_bits, size)
+            }
+            // serialized_boc_idx#68ff65f3 and serialized_boc_idx_crc32c#acc3a728
+            INDEXED_BOC_MAGIC | INDEXED_CRC32_MAGIC => {
+                // size:(## 8) { size <= 4 }
+                let size = reader.read::<u8>().map_boc_deserialization_error()?;
+                (false, true, magic == INDEXED_CRC32_MAGIC, false, size)
             }
             magic => {
                 return Err(TonCellError::boc_deserialization_error(format!(
-                    "Unsupported cell magic number: {:#}",
+                    "Unsupported cell magic number: {:#x}",
                     magic
                 )));
             }
         };
+        if size == 0 || size > 4 {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "Invalid size of cell references: {}",
+                size
+            )));
+        }
         //   off_bytes:(## 8) { off_bytes <= 8 }
         let off_bytes = reader.read::<u8>().map_boc_deserialization_error()?;
+        if off_bytes == 0 || off_bytes > 8 {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "Invalid size of cell offsets: {}",
+                off_bytes
+            )));
+        }
         //cells:(##(size * 8))
         let cells = read_var_size(&mut reader, size)?;
         //   roots:(##(size * 8)) { roots >= 1 }
         let roots = read_var_size(&mut reader, size)?;
         //   absent:(##(size * 8)) { roots + absent <= cells }
-        let _absent = read_var_size(&mut reader, size)?;
+        let absent = read_var_size(&mut reader, size)?;
+        if roots == 0 || roots + absent > cells {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "Invalid number of roots {} or absent cells {} of {} cells",
+                roots, absent, cells
+            )));
+        }
         //   tot_cells_size:(##(off_bytes * 8))
-        let _tot_cells_size = read_var_size(&mut reader, off_bytes)?;
-        //   root_list:(roots * ##(size * 8))
+        let tot_cells_size = read_var_size(&mut reader, off_bytes)?;
+        //   root_list:(roots * ##(size * 8)), the only root is the first cell of indexed BoCs
         let mut root_list = vec![];
-        for _ in 0..roots {
-            root_list.push(read_var_size(&mut reader, size)?)
+        if has_root_list {
+            for _ in 0..roots {
+                root_list.push(read_var_size(&mut reader, size)?)
+            }
+        } else if roots == 1 {
+            root_list.push(0);
+        } else {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "Indexed BoC must have a single root, got {}",
+                roots
+            )));
         }
         //   index:has_idx?(cells * ##(off_bytes * 8))
-        let mut index = vec![];
         if has_idx {
-            for _ in 0..cells {
-                index.push(read_var_size(&mut reader, off_bytes)?)
-            }
+            let index_len = u32::try_from(cells)
+                .ok()
+                .and_then(|cells| cells.checked_mul(off_bytes as u32))
+                .ok_or_else(|| {
+                    TonCellError::boc_deserialization_error(format!(
+                        "Index of {} cells is too big",
+                        cells
+                    ))
+                })?;
+            reader.skip(index_len).map_boc_deserialization_error()?;
         }
         //   cell_data:(tot_cells_size * [ uint8 ])
-        let mut cell_vec = Vec::with_capacity(cells);
+        let cells_start = reader.reader().position() as usize;
+        let cells_end = cells_start.saturating_add(tot_cells_size);
+        //   crc32c:has_crc32c?uint32
+        let expected_len = cells_end.saturating_add(if has_crc32c { 4 } else { 0 });
+        if serial.len() < expected_len {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "BoC is truncated: expected {} bytes, got {}",
+                expected_len,
+                serial.len()
+            )));
+        }
+        if has_crc32c {
+            let mut crc_bytes = [0; 4];
+            crc_bytes.copy_from_slice(&serial[cells_end..cells_end + 4]);
+            let crc32c = u32::from_le_bytes(crc_bytes);
+            let expected = CRC_32_ISCSI.checksum(&serial[..cells_end]);
+            if crc32c != expected {
+                return Err(TonCellError::boc_deserialization_error(format!(
+                    "CRC32C mismatch: expected {:#010x}, got {:#010x}",
+                    expected, crc32c
+                )));
+            }
+        }
+        if let Some(root) = root_list.iter().find(|root| **root >= cells) {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "Invalid root index {} of {} cells",
+                root, cells
+            )));
+        }
 
-        for _ in 0..cells {
-            let cell = read_cell(&mut reader, size)?;
+        Ok(BocHeader {
+            size,
+            cell_count: cells,
+            roots: root_list,
+            cells_start,
+            cells_end,
+        })
+    }
+}
+
+impl RawBagOfCells {
+    /// Parses the cells, checking the cell count and total bits of `limits`.
+    pub(crate) fn parse_with_limits(
+        serial: &[u8],
+        limits: &BocLimits,
+    ) -> Result<RawBagOfCells, TonCellError> {
+        let header = BocHeader::parse(serial)?;
+        limits.check(BocLimit::CellCount, header.cell_count)?;
+        let cells_data = &serial[header.cells_start..header.cells_end];
+        let mut reader: ByteReader<Cursor<&[u8]>, BigEndian> =
+            ByteReader::endian(Cursor::new(cells_data), BigEndian);
+
+        let mut cell_vec = Vec::with_capacity(header.cell_count.min(cells_data.len() / 2));
+        let mut total_bits = 0usize;
+        for _ in 0..header.cell_count {
+            let cell = read_cell(&mut reader, header.size)?;
+            total_bits += cell.bit_len;
+            limits.check(BocLimit::TotalBits, total_bits)?;
             cell_vec.push(cell);
         }
-        //   crc32c:has_crc32c?uint32
-        let _crc32c = if has_crc32c {
-            reader.read::<u32>().map_boc_deserialization_error()?
-        } else {
-            0
-        };
-        // TODO: Check crc32
 
         Ok(RawBagOfCells {
             cells: cell_vec,
-            roots: root_list,
+            roots: header.roots,
         })
     }
 
-    pub(crate) fn serialize(&self, has_crc32: bool) -> Result<Vec<u8>, TonCellError> {
+    pub(crate) fn serialize(&self, options: &BocSerializeOptions) -> Result<Vec<u8>, TonCellError> {
         //Based on https://github.com/toncenter/tonweb/blob/c2d5d0fc23d2aec55a0412940ce6e580344a288c/src/boc/Cell.js#L198
 
         let root_count = self.roots.len();
         let num_ref_bits = 32 - (self.cells.len() as u32).leading_zeros();
         let num_ref_bytes = (num_ref_bits + 7) / 8;
-        let has_idx = false;
+        let has_idx = options.has_index;
+        let has_crc32 = options.has_crc32c;
 
         let mut full_size = 0u32;
 
@@ -147,7 +248,7 @@ impl RawBagOfCells {
             1 + // offset_bytes
             3 * num_ref_bytes + // cells_num, roots, complete
             num_offset_bytes + // full_size
-            num_ref_bytes + // root_idx
+            root_count as u32 * num_ref_bytes + // root_list
             (if has_idx { self.cells.len() as u32 * num_offset_bytes } else { 0 }) +
             full_size +
             (if has_crc32 { 4 } else { 0 });
@@ -191,6 +292,17 @@ impl RawBagOfCells {
                 .map_boc_serialization_error()?;
         }
 
+        if has_idx {
+            // offsets of the ends of the cells
+            let mut offset = 0;
+            for cell in &self.cells {
+                offset += raw_cell_size(cell, num_ref_bytes);
+                writer
+                    .write(8 * num_offset_bytes, offset)
+                    .map_boc_serialization_error()?;
+            }
+        }
+
         for cell in &self.cells {
             write_raw_cell(&mut writer, cell, num_ref_bytes)?;
         }
@@ -336,6 +448,6 @@ mod tests {
             cells: vec![raw_cell],
             roots: vec![0],
         };
-        assert!(raw_bag.serialize(false).is_ok());
+        assert!(raw_bag.serialize(&BocSerializeOptions::default()).is_ok());
     }
 }
@@ -1,34 +1,73 @@
-use std::cell::RefCell;
 use std::collections::HashMap;
-use std::sync::Arc;
 
-use crate::cell::{ArcCell, BagOfCells, Cell, RawBagOfCells, RawCell, TonCellError};
+use crate::cell::{ArcCell, BagOfCells, RawBagOfCells, RawCell, TonCellError};
 use crate::TonHash;
 
+/// Maximal weight of a cell the hashes of which are not stored, as used by the reference
+/// implementation (`vm::BagOfCells::max_cell_whs`) for ordering cells.
+const MAX_CELL_WHS: u32 = 64;
+
+/// Index meanings of `CellInfo::new_idx` before cells are allocated.
+const NOT_VISITED: isize = -1;
+const PREVISITED: isize = -2;
+const VISITED: isize = -3;
+
 #[derive(Debug, Clone)]
-struct IndexedCell {
-    index: usize,
+struct CellInfo {
     cell: ArcCell,
+    refs: Vec<usize>,
+    weight: u32,
+    new_idx: isize,
 }
 
-pub(crate) fn convert_to_raw_boc(boc: &BagOfCells) -> Result<RawBagOfCells, TonCellError> {
-    let cells_by_hash = build_and_verify_index(&boc.roots);
-
-    // Sort indexed cells by their index value.
-    let mut index_slice: Vec<_> = cells_by_hash.values().collect();
-    index_slice.sort_unstable_by(|a, b| a.borrow().index.cmp(&b.borrow().index));
+impl CellInfo {
+    fn is_special(&self) -> bool {
+        self.weight == 0
+    }
+}
 
-    // Remove gaps in indices.
-    index_slice
+/// Orders cells of `boc` the same way as `vm::BagOfCells` of the reference implementation:
+/// cells are imported depth-first, weighted by the size of their subtrees and then
+/// reordered so that roots come first and every cell precedes the cells it references.
+pub(crate) fn convert_to_raw_boc(boc: &BagOfCells) -> Result<RawBagOfCells, TonCellError> {
+    let mut ordering = CellOrdering::default();
+    let roots = boc
+        .roots
         .iter()
-        .enumerate()
-        .for_each(|(real_index, indexed_cell)| indexed_cell.borrow_mut().index = real_index);
+        .map(|root| ordering.import_cell(root))
+        .collect::<Vec<_>>();
+    ordering.update_weights();
+    for &root in &roots {
+        ordering.revisit(root, 0);
+        ordering.revisit(root, 1);
+    }
+    for &root in &roots {
+        ordering.revisit(root, 2);
+    }
 
-    let cells_iter = index_slice
-        .into_iter()
-        .map(|indexed_cell| indexed_cell.borrow().cell.clone());
-    let raw_cells = raw_cells_from_cells(cells_iter, &cells_by_hash)?;
-    let root_indices = root_indices(&boc.roots, &cells_by_hash)?;
+    // Cells are allocated children first, so the resulting order is reversed.
+    let cell_count = ordering.allocated.len();
+    let raw_index = |idx: usize| cell_count - 1 - ordering.cells[idx].new_idx as usize;
+    let mut raw_cells = Vec::with_capacity(cell_count);
+    for &idx in ordering.allocated.iter().rev() {
+        let info = &ordering.cells[idx];
+        let references = info.refs.iter().map(|&r| raw_index(r)).collect();
+        raw_cells.push(RawCell::new(
+            info.cell.data.clone(),
+            info.cell.bit_len,
+            references,
+            info.cell.get_level_mask(),
+            info.cell.is_exotic(),
+        ));
+    }
+    if raw_cells.len() != ordering.cells.len() {
+        return Err(TonCellError::BagOfCellsSerializationError(format!(
+            "Allocated {} of {} cells",
+            raw_cells.len(),
+            ordering.cells.len()
+        )));
+    }
+    let root_indices = roots.into_iter().map(raw_index).collect();
 
     Ok(RawBagOfCells {
         cells: raw_cells,
@@ -36,118 +75,119 @@ pub(crate) fn convert_to_raw_boc(boc: &BagOfCells) -> Result<RawBagOfCells, TonC
     })
 }
 
-fn build_and_verify_index(roots: &[ArcCell]) -> HashMap<TonHash, RefCell<IndexedCell>> {
-    let mut current_cells: Vec<_> = roots.iter().map(Arc::clone).collect();
-    let mut new_hash_index = 0;
-    let mut cells_by_hash = HashMap::new();
-
-    // Process cells to build the initial index.
-    while !current_cells.is_empty() {
-        let mut next_cells = Vec::with_capacity(current_cells.len() * 4);
-        for cell in current_cells.iter() {
-            let hash = cell.cell_hash();
-
-            if cells_by_hash.contains_key(&hash) {
-                continue; // Skip if already indexed.
-            }
-
-            cells_by_hash.insert(
-                hash,
-                RefCell::new(IndexedCell {
-                    cell: Arc::clone(cell),
-                    index: new_hash_index,
-                }),
-            );
+#[derive(Default)]
+struct CellOrdering {
+    cells: Vec<CellInfo>,
+    cells_by_hash: HashMap<TonHash, usize>,
+    /// Indices of `cells` in the order of allocation.
+    allocated: Vec<usize>,
+}
 
-            new_hash_index += 1;
-            next_cells.extend(cell.references.clone()); // Add referenced cells for the next iteration.
+impl CellOrdering {
+    /// Imports the cell after its references, deduplicating cells by representation hash.
+    fn import_cell(&mut self, cell: &ArcCell) -> usize {
+        let hash = cell.cell_hash();
+        if let Some(&idx) = self.cells_by_hash.get(&hash) {
+            return idx;
         }
-
-        current_cells = next_cells;
+        let mut refs = Vec::with_capacity(cell.references.len());
+        let mut weight = 1;
+        for reference in &cell.references {
+            let ref_idx = self.import_cell(reference);
+            weight += self.cells[ref_idx].weight;
+            refs.push(ref_idx);
+        }
+        let idx = self.cells.len();
+        self.cells.push(CellInfo {
+            cell: cell.clone(),
+            refs,
+            weight: weight.min(0xff),
+            new_idx: NOT_VISITED,
+        });
+        self.cells_by_hash.insert(hash, idx);
+        idx
     }
 
-    // Ensure indices are in the correct order based on cell references.
-    let mut verify_order = true;
-    while verify_order {
-        verify_order = false;
-
-        for index_cell in cells_by_hash.values() {
-            for reference in index_cell.borrow().cell.references.iter() {
-                let ref_hash = reference.cell_hash();
-                if let Some(id_ref) = cells_by_hash.get(&ref_hash) {
-                    if id_ref.borrow().index < index_cell.borrow().index {
-                        id_ref.borrow_mut().index = new_hash_index;
-                        new_hash_index += 1;
-                        verify_order = true; // Reverify if an index was updated.
+    /// Limits weights of cells by `MAX_CELL_WHS`, cells exceeding their limit become special,
+    /// i.e. weight 0.
+    fn update_weights(&mut self) {
+        for idx in (0..self.cells.len()).rev() {
+            let refs = self.cells[idx].refs.clone();
+            let ref_count = refs.len() as u32;
+            let mut remaining = ref_count;
+            let mut sum = MAX_CELL_WHS - 1;
+            let mut light = 0u32;
+            for (j, &r) in refs.iter().enumerate() {
+                let limit = (MAX_CELL_WHS - 1 + j as u32) / ref_count;
+                if self.cells[r].weight <= limit {
+                    sum -= self.cells[r].weight;
+                    remaining -= 1;
+                    light |= 1 << j;
+                }
+            }
+            // `remaining` counts the heavy references, so it isn't zero when one is found
+            for (j, &r) in refs.iter().enumerate() {
+                if light & (1 << j) == 0 {
+                    let limit = sum / remaining;
+                    sum += 1;
+                    if self.cells[r].weight > limit {
+                        self.cells[r].weight = limit;
                     }
                 }
             }
         }
+        for idx in 0..self.cells.len() {
+            let sum = 1 + self.cells[idx]
+                .refs
+                .iter()
+                .map(|&r| self.cells[r].weight)
+                .sum::<u32>();
+            let info = &mut self.cells[idx];
+            info.weight = if sum <= info.weight { sum } else { 0 };
+        }
     }
 
-    cells_by_hash
-}
-
-fn root_indices(
-    roots: &[ArcCell],
-    cells_dict: &HashMap<TonHash, RefCell<IndexedCell>>,
-) -> Result<Vec<usize>, TonCellError> {
-    roots
-        .iter()
-        .map(|root_cell| root_cell.cell_hash())
-        .map(|root_cell_hash| {
-            cells_dict
-                .get(&root_cell_hash)
-                .map(|index_record| index_record.borrow().index)
-                .ok_or_else(|| {
-                    TonCellError::BagOfCellsSerializationError(format!(
-                        "Couldn't find cell with hash {root_cell_hash:?} while searching for roots"
-                    ))
-                })
-        })
-        .collect()
-}
-
-fn raw_cells_from_cells(
-    cells: impl Iterator<Item = ArcCell>,
-    cells_by_hash: &HashMap<TonHash, RefCell<IndexedCell>>,
-) -> Result<Vec<RawCell>, TonCellError> {
-    cells
-        .map(|cell| raw_cell_from_cell(&cell, cells_by_hash))
-        .collect()
-}
-
-fn raw_cell_from_cell(
-    cell: &Cell,
-    cells_by_hash: &HashMap<TonHash, RefCell<IndexedCell>>,
-) -> Result<RawCell, TonCellError> {
-    raw_cell_reference_indices(cell, cells_by_hash).map(|reference_indices| {
-        RawCell::new(
-            cell.data.clone(),
-            cell.bit_len,
-            reference_indices,
-            cell.get_level_mask(),
-            cell.is_exotic(),
-        )
-    })
-}
-
-fn raw_cell_reference_indices(
-    cell: &Cell,
-    cells_by_hash: &HashMap<TonHash, RefCell<IndexedCell>>,
-) -> Result<Vec<usize>, TonCellError> {
-    cell.references
-        .iter()
-        .map(|cell| {
-            cells_by_hash
-                .get(&cell.cell_hash())
-                .ok_or_else(|| {
-                    TonCellError::BagOfCellsSerializationError(format!(
-                        "Couldn't find cell with hash {:?} while searching for references",
-                        cell.cell_hash()
-                    ))
-                })
-                .map(|cell| cell.borrow().index)
-        })
-        .collect()
+    /// Previsits (`force = 0`), visits (`force = 1`) or allocates (`force = 2`) the cell.
+    fn revisit(&mut self, idx: usize, force: u8) -> isize {
+        let new_idx = self.cells[idx].new_idx;
+        if new_idx >= 0 {
+            return new_idx;
+        }
+        let refs = self.cells[idx].refs.clone();
+        match force {
+            0 => {
+                if new_idx != NOT_VISITED {
+                    return new_idx;
+                }
+                for &r in refs.iter().rev() {
+                    let child_force = self.cells[r].is_special() as u8;
+                    self.revisit(r, child_force);
+                }
+                self.cells[idx].new_idx = PREVISITED;
+                PREVISITED
+            }
+            1 => {
+                if new_idx == VISITED {
+                    return new_idx;
+                }
+                if self.cells[idx].is_special() {
+                    self.revisit(idx, 0);
+                }
+                for &r in refs.iter().rev() {
+                    self.revisit(r, 1);
+                }
+                for &r in refs.iter().rev() {
+                    self.revisit(r, 2);
+                }
+                self.cells[idx].new_idx = VISITED;
+                VISITED
+            }
+            _ => {
+                let allocated = self.allocated.len() as isize;
+                self.cells[idx].new_idx = allocated;
+                self.allocated.push(idx);
+                allocated
+            }
+        }
+    }
 }
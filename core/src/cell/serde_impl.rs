@@ -0,0 +1,67 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::cell::{BagOfCells, Cell};
+
+/// Cells are serialized as base64 encoded bags of cells without crc32,
+/// the way `tonlibjson` and most HTTP APIs represent them.
+impl Serialize for Cell {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Serialize::serialize(&BagOfCells::from_root(self.clone()), serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cell {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let boc = BagOfCells::deserialize(deserializer)?;
+        let root = boc.single_root().map_err(D::Error::custom)?;
+        Ok(root.as_ref().clone())
+    }
+}
+
+impl Serialize for BagOfCells {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let boc = BagOfCells::serialize(self, false).map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&STANDARD.encode(boc))
+    }
+}
+
+impl<'de> Deserialize<'de> for BagOfCells {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let base64 = String::deserialize(deserializer)?;
+        BagOfCells::parse_base64(&base64).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+
+    use crate::cell::{BagOfCells, Cell, CellBuilder, TonCellError};
+
+    #[test]
+    fn test_cell_serde_round_trip() -> Result<(), TonCellError> {
+        let child = CellBuilder::new().store_u32(32, 0xdeadbeef)?.build()?;
+        let cell = CellBuilder::new()
+            .store_u8(8, 42)?
+            .store_reference(&Arc::new(child))?
+            .build()?;
+
+        let json = serde_json::to_string(&cell).unwrap();
+        let boc = BagOfCells::from_root(cell.clone()).serialize(false)?;
+        assert_eq!(json, format!("\"{}\"", STANDARD.encode(boc)));
+        assert_eq!(serde_json::from_str::<Cell>(&json).unwrap(), cell);
+
+        let boc = BagOfCells::new(&[Arc::new(cell.clone()), Arc::new(cell)]);
+        let json = serde_json::to_string(&boc).unwrap();
+        assert_eq!(serde_json::from_str::<BagOfCells>(&json).unwrap(), boc);
+        assert!(serde_json::from_str::<Cell>(&json).is_err());
+        assert!(serde_json::from_str::<Cell>("\"not a boc\"").is_err());
+        Ok(())
+    }
+}
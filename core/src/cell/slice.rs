@@ -4,7 +4,7 @@ use std::sync::Arc;
 use bitstream_io::{BigEndian, BitRead, BitReader};
 
 use crate::cell::util::BitReadExt;
-use crate::cell::{ArcCell, Cell, MapTonCellError, TonCellError};
+use crate::cell::{ArcCell, Cell, CellParser, MapTonCellError, TonCellError};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct CellSlice {
@@ -84,6 +84,19 @@ impl CellSlice {
             })
     }
 
+    /// Creates a parser positioned at the start of the slice.
+    ///
+    /// The parser sees only the bits and references within the slice bounds.
+    pub fn parser(&self) -> Result<CellParser<'_>, TonCellError> {
+        let mut parser = CellParser::new(
+            self.end_bit,
+            &self.cell.data,
+            &self.cell.references[self.start_ref..self.end_ref],
+        );
+        parser.skip_bits(self.start_bit)?;
+        Ok(parser)
+    }
+
     /// Converts the slice to full `Cell` dropping references to original cell.
     pub fn into_cell(&self) -> Result<Cell, TonCellError> {
         let bit_len = self.end_bit - self.start_bit;
@@ -105,3 +118,36 @@ impl CellSlice {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::CellSlice;
+    use crate::cell::{CellBuilder, TonCellError};
+
+    #[test]
+    fn test_parser_starts_at_slice_offset() -> Result<(), TonCellError> {
+        let ref0 = Arc::new(CellBuilder::new().store_u8(8, 10)?.build()?);
+        let ref1 = Arc::new(CellBuilder::new().store_u8(8, 11)?.build()?);
+        let ref2 = Arc::new(CellBuilder::new().store_u8(8, 12)?.build()?);
+        let cell = Arc::new(
+            CellBuilder::new()
+                .store_u8(4, 0xf)?
+                .store_u32(32, 0xdeadbeef)?
+                .store_u8(4, 0xa)?
+                .store_references(&[ref0, ref1.clone(), ref2.clone()])?
+                .build()?,
+        );
+
+        let slice = CellSlice::new(&cell, 4, 36, 1, 3)?;
+        let mut parser = slice.parser()?;
+        assert_eq!(parser.remaining_bits(), 32);
+        assert_eq!(parser.load_u32(32)?, 0xdeadbeef);
+        assert_eq!(parser.remaining_bits(), 0);
+        assert_eq!(parser.next_reference()?, ref1);
+        assert_eq!(parser.next_reference()?, ref2);
+        assert!(parser.next_reference().is_err());
+        Ok(())
+    }
+}
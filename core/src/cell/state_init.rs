@@ -1,6 +1,7 @@
 use super::ArcCell;
-use crate::cell::{Cell, CellBuilder, TonCellError};
-use crate::TonHash;
+use crate::cell::{Cell, CellBuilder, CellParser, TonCellError};
+use crate::tlb::TlbType;
+use crate::{TonAddress, TonHash};
 
 pub struct StateInitBuilder {
     code: Option<ArcCell>,
@@ -9,9 +10,30 @@ pub struct StateInitBuilder {
     tick_tock: bool,
     library: bool,
 }
+
+/// Initial state of a contract according to TL-B schema:
+///
+/// ```raw
+/// _ split_depth:(Maybe (## 5)) special:(Maybe TickTock)
+///   code:(Maybe ^Cell) data:(Maybe ^Cell)
+///   library:(HashmapE 256 SimpleLib) = StateInit;
+/// ```
+///
+/// The library dictionary is kept as its root cell.
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct StateInit {
+    pub split_depth: Option<u8>,
+    pub special: Option<TickTock>,
     pub code: Option<ArcCell>,
     pub data: Option<ArcCell>,
+    pub library: Option<ArcCell>,
+}
+
+/// `tick_tock$_ tick:Bool tock:Bool = TickTock;`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TickTock {
+    pub tick: bool,
+    pub tock: bool,
 }
 
 impl StateInitBuilder {
@@ -59,17 +81,89 @@ impl StateInitBuilder {
 }
 
 impl StateInit {
+    pub fn new(code: &ArcCell, data: &ArcCell) -> StateInit {
+        StateInit {
+            code: Some(code.clone()),
+            data: Some(data.clone()),
+            ..Default::default()
+        }
+    }
+
     pub fn create_account_id(code: &ArcCell, data: &ArcCell) -> Result<TonHash, TonCellError> {
         Ok(StateInitBuilder::new(code, data).build()?.cell_hash())
     }
+
+    /// Returns the address of the contract with this initial state in `workchain`.
+    pub fn address(&self, workchain: i32) -> Result<TonAddress, TonCellError> {
+        derive_address(workchain, self)
+    }
+}
+
+/// Computes the address of a contract deployed with `state_init` to `workchain`,
+/// i.e. the hash of the state init cell.
+pub fn derive_address(workchain: i32, state_init: &StateInit) -> Result<TonAddress, TonCellError> {
+    Ok(TonAddress::new(
+        workchain,
+        &state_init.to_cell()?.cell_hash(),
+    ))
+}
+
+impl TlbType for StateInit {
+    fn read(parser: &mut CellParser) -> Result<Self, TonCellError> {
+        let split_depth = if parser.load_bit()? {
+            Some(parser.load_u8(5)?)
+        } else {
+            None
+        };
+        let special = if parser.load_bit()? {
+            Some(TickTock {
+                tick: parser.load_bit()?,
+                tock: parser.load_bit()?,
+            })
+        } else {
+            None
+        };
+        Ok(StateInit {
+            split_depth,
+            special,
+            code: parser.load_maybe_cell_ref()?,
+            data: parser.load_maybe_cell_ref()?,
+            library: parser.load_maybe_cell_ref()?,
+        })
+    }
+
+    fn write(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        builder.store_bit(self.split_depth.is_some())?;
+        if let Some(split_depth) = self.split_depth {
+            builder.store_u8(5, split_depth)?;
+        }
+        builder.store_bit(self.special.is_some())?;
+        if let Some(special) = self.special {
+            builder.store_bit(special.tick)?.store_bit(special.tock)?;
+        }
+        builder
+            .store_maybe_cell_ref(&self.code)?
+            .store_maybe_cell_ref(&self.data)?
+            .store_maybe_cell_ref(&self.library)?;
+        Ok(())
+    }
+}
+
+impl TryFrom<&Cell> for StateInit {
+    type Error = TonCellError;
+
+    fn try_from(cell: &Cell) -> Result<Self, Self::Error> {
+        StateInit::read(&mut cell.parser())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
 
-    use super::StateInitBuilder;
+    use super::{derive_address, StateInit, StateInitBuilder, TickTock};
     use crate::cell::{CellBuilder, TonCellError};
+    use crate::tlb::TlbType;
 
     #[test]
     fn test_state_init() -> Result<(), TonCellError> {
@@ -123,4 +217,54 @@ mod tests {
         assert_eq!(state_init.data[0], 0b00111000);
         Ok(())
     }
+
+    #[test]
+    fn test_state_init_parse() -> Result<(), TonCellError> {
+        let code = Arc::new(CellBuilder::new().store_string("code")?.build()?);
+        let data = Arc::new(CellBuilder::new().store_string("data")?.build()?);
+        let cell = StateInitBuilder::new(&code, &data).build()?;
+        let state_init = StateInit::try_from(&cell)?;
+        assert_eq!(state_init.code, Some(code));
+        assert_eq!(state_init.data, Some(data));
+        Ok(())
+    }
+
+    #[test]
+    fn test_state_init_tlb_round_trip() -> Result<(), TonCellError> {
+        let code = Arc::new(CellBuilder::new().store_string("code")?.build()?);
+        let data = Arc::new(CellBuilder::new().store_string("data")?.build()?);
+        let state_init = StateInit::new(&code, &data);
+        let cell = state_init.to_cell()?;
+        assert_eq!(cell, StateInitBuilder::new(&code, &data).build()?);
+        assert_eq!(StateInit::from_cell(&cell)?, state_init);
+
+        let state_init = StateInit {
+            split_depth: Some(3),
+            special: Some(TickTock {
+                tick: true,
+                tock: false,
+            }),
+            library: Some(data.clone()),
+            ..state_init
+        };
+        let cell = state_init.to_cell()?;
+        assert_eq!(cell.data()[0], 0b10001111);
+        assert_eq!(StateInit::from_cell(&cell)?, state_init);
+        Ok(())
+    }
+
+    #[test]
+    fn test_derive_address() -> Result<(), TonCellError> {
+        let code = Arc::new(CellBuilder::new().store_string("code")?.build()?);
+        let data = Arc::new(CellBuilder::new().store_string("data")?.build()?);
+        let state_init = StateInit::new(&code, &data);
+        let address = derive_address(-1, &state_init)?;
+        assert_eq!(address.workchain, -1);
+        assert_eq!(
+            address.hash_part,
+            StateInit::create_account_id(&code, &data)?
+        );
+        assert_eq!(state_init.address(-1)?, address);
+        Ok(())
+    }
 }
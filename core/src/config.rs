@@ -0,0 +1,463 @@
+//! Typed blockchain configuration parameters, as stored in the config of masterchain blocks.
+//!
+//! See https://docs.ton.org/develop/howto/blockchain-configs for the list of parameters.
+
+use crate::cell::dict::predefined_readers::{key_reader_u16, key_reader_u32};
+use crate::cell::{Cell, CellParser, TonCellError};
+use crate::tlb::{check_tag, Tlb, TlbType};
+use crate::{TonAddress, TonHash};
+
+pub const CONFIG_PARAM_CONFIG_ADDRESS: u32 = 0;
+pub const CONFIG_PARAM_ELECTOR_ADDRESS: u32 = 1;
+pub const CONFIG_PARAM_STORAGE_PRICES: u32 = 18;
+pub const CONFIG_PARAM_MASTERCHAIN_GAS_PRICES: u32 = 20;
+pub const CONFIG_PARAM_GAS_PRICES: u32 = 21;
+pub const CONFIG_PARAM_MASTERCHAIN_MSG_FORWARD_PRICES: u32 = 24;
+pub const CONFIG_PARAM_MSG_FORWARD_PRICES: u32 = 25;
+pub const CONFIG_PARAM_CURRENT_VALIDATORS: u32 = 34;
+
+/// Config parameter decoded according to its index.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigParam {
+    /// `_ config_addr:bits256 = ConfigParam 0;`
+    ConfigAddress(TonAddress),
+    /// `_ elector_addr:bits256 = ConfigParam 1;`
+    ElectorAddress(TonAddress),
+    /// `_ (Hashmap 32 StoragePrices) = ConfigParam 18;`, ordered by `utime_since`.
+    StoragePrices(Vec<StoragePrices>),
+    /// `config_mc_gas_prices#_ GasLimitsPrices = ConfigParam 20;`
+    MasterchainGasPrices(GasLimitsPrices),
+    /// `config_gas_prices#_ GasLimitsPrices = ConfigParam 21;`
+    GasPrices(GasLimitsPrices),
+    /// `config_mc_fwd_prices#_ MsgForwardPrices = ConfigParam 24;`
+    MasterchainMsgForwardPrices(MsgForwardPrices),
+    /// `config_fwd_prices#_ MsgForwardPrices = ConfigParam 25;`
+    MsgForwardPrices(MsgForwardPrices),
+    /// `_ cur_validators:ValidatorSet = ConfigParam 34;`
+    CurrentValidators(ValidatorSet),
+}
+
+impl ConfigParam {
+    /// Decodes the value `cell` of config param `index`, failing for unsupported params.
+    pub fn parse(index: u32, cell: &Cell) -> Result<ConfigParam, TonCellError> {
+        let param = match index {
+            CONFIG_PARAM_CONFIG_ADDRESS => ConfigParam::ConfigAddress(parse_address(cell)?),
+            CONFIG_PARAM_ELECTOR_ADDRESS => ConfigParam::ElectorAddress(parse_address(cell)?),
+            CONFIG_PARAM_STORAGE_PRICES => {
+                let mut prices: Vec<_> = cell
+                    .parser()
+                    .load_dict(32, key_reader_u32, StoragePrices::read)?
+                    .into_values()
+                    .collect();
+                prices.sort_by_key(|price| price.utime_since);
+                ConfigParam::StoragePrices(prices)
+            }
+            CONFIG_PARAM_MASTERCHAIN_GAS_PRICES => {
+                ConfigParam::MasterchainGasPrices(cell.parse_fully(GasLimitsPrices::read)?)
+            }
+            CONFIG_PARAM_GAS_PRICES => {
+                ConfigParam::GasPrices(cell.parse_fully(GasLimitsPrices::read)?)
+            }
+            CONFIG_PARAM_MASTERCHAIN_MSG_FORWARD_PRICES => {
+                ConfigParam::MasterchainMsgForwardPrices(MsgForwardPrices::from_cell(cell)?)
+            }
+            CONFIG_PARAM_MSG_FORWARD_PRICES => {
+                ConfigParam::MsgForwardPrices(MsgForwardPrices::from_cell(cell)?)
+            }
+            CONFIG_PARAM_CURRENT_VALIDATORS => {
+                ConfigParam::CurrentValidators(cell.parse_fully(ValidatorSet::read)?)
+            }
+            _ => {
+                return Err(TonCellError::InvalidInput(format!(
+                    "Unsupported config param {index}"
+                )))
+            }
+        };
+        Ok(param)
+    }
+}
+
+/// Address of a masterchain contract stored as `bits256`, e.g. in config params 0 and 1.
+fn parse_address(cell: &Cell) -> Result<TonAddress, TonCellError> {
+    let hash = cell.parse_fully(TonHash::read)?;
+    Ok(TonAddress::new(-1, &hash))
+}
+
+/// Storage prices per bit and per cell in nanotons per 2^16 seconds.
+///
+/// ```raw
+/// storage_prices#cc utime_since:uint32 bit_price_ps:uint64 cell_price_ps:uint64
+///   mc_bit_price_ps:uint64 mc_cell_price_ps:uint64 = StoragePrices;
+/// ```
+#[derive(Debug, Clone, PartialEq, Tlb)]
+#[tlb(tag = "#cc")]
+pub struct StoragePrices {
+    /// Unix time the prices are effective since.
+    pub utime_since: u32,
+    pub bit_price_ps: u64,
+    pub cell_price_ps: u64,
+    pub mc_bit_price_ps: u64,
+    pub mc_cell_price_ps: u64,
+}
+
+/// Gas prices and limits, gas prices are in nanotons per 2^16 gas units.
+///
+/// ```raw
+/// gas_prices#dd gas_price:uint64 gas_limit:uint64 gas_credit:uint64
+///   block_gas_limit:uint64 freeze_due_limit:uint64 delete_due_limit:uint64
+///   = GasLimitsPrices;
+/// gas_prices_ext#de gas_price:uint64 gas_limit:uint64 special_gas_limit:uint64 gas_credit:uint64
+///   block_gas_limit:uint64 freeze_due_limit:uint64 delete_due_limit:uint64
+///   = GasLimitsPrices;
+/// gas_flat_pfx#d1 flat_gas_limit:uint64 flat_gas_price:uint64 other:GasLimitsPrices
+///   = GasLimitsPrices;
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct GasLimitsPrices {
+    /// Gas consumed for `flat_gas_price`, zero if not set.
+    pub flat_gas_limit: u64,
+    pub flat_gas_price: u64,
+    pub gas_price: u64,
+    pub gas_limit: u64,
+    /// Gas limit of special (e.g. elector and config) contracts, `gas_limit` if not set.
+    pub special_gas_limit: u64,
+    pub gas_credit: u64,
+    pub block_gas_limit: u64,
+    pub freeze_due_limit: u64,
+    pub delete_due_limit: u64,
+}
+
+impl GasLimitsPrices {
+    const GAS_PRICES_TAG: u8 = 0xdd;
+    const GAS_PRICES_EXT_TAG: u8 = 0xde;
+    const GAS_FLAT_PFX_TAG: u8 = 0xd1;
+
+    pub fn read(parser: &mut CellParser) -> Result<GasLimitsPrices, TonCellError> {
+        let tag = parser.load_u8(8)?;
+        let prices = match tag {
+            Self::GAS_FLAT_PFX_TAG => {
+                let flat_gas_limit = parser.load_u64(64)?;
+                let flat_gas_price = parser.load_u64(64)?;
+                GasLimitsPrices {
+                    flat_gas_limit,
+                    flat_gas_price,
+                    ..Self::read(parser)?
+                }
+            }
+            Self::GAS_PRICES_TAG | Self::GAS_PRICES_EXT_TAG => {
+                let gas_price = parser.load_u64(64)?;
+                let gas_limit = parser.load_u64(64)?;
+                let special_gas_limit = if tag == Self::GAS_PRICES_EXT_TAG {
+                    parser.load_u64(64)?
+                } else {
+                    gas_limit
+                };
+                GasLimitsPrices {
+                    flat_gas_limit: 0,
+                    flat_gas_price: 0,
+                    gas_price,
+                    gas_limit,
+                    special_gas_limit,
+                    gas_credit: parser.load_u64(64)?,
+                    block_gas_limit: parser.load_u64(64)?,
+                    freeze_due_limit: parser.load_u64(64)?,
+                    delete_due_limit: parser.load_u64(64)?,
+                }
+            }
+            _ => {
+                return Err(TonCellError::CellParserError(format!(
+                    "Invalid tag of GasLimitsPrices: {tag:#x}"
+                )))
+            }
+        };
+        Ok(prices)
+    }
+
+    /// Computes the fee in nanotons for `gas_used` gas units.
+    pub fn compute_gas_fee(&self, gas_used: u64) -> u64 {
+        if gas_used <= self.flat_gas_limit {
+            return self.flat_gas_price;
+        }
+        let gas = (gas_used - self.flat_gas_limit) as u128;
+        let fee = (self.gas_price as u128 * gas).div_ceil(1 << 16);
+        self.flat_gas_price
+            .saturating_add(fee.try_into().unwrap_or(u64::MAX))
+    }
+}
+
+/// Prices of forwarding messages, bit and cell prices are in nanotons per 2^16 bits and cells.
+///
+/// ```raw
+/// msg_forward_prices#ea lump_price:uint64 bit_price:uint64 cell_price:uint64
+///   ihr_price_factor:uint32 first_frac:uint16 next_frac:uint16 = MsgForwardPrices;
+/// ```
+#[derive(Debug, Clone, PartialEq, Tlb)]
+#[tlb(tag = "#ea")]
+pub struct MsgForwardPrices {
+    pub lump_price: u64,
+    pub bit_price: u64,
+    pub cell_price: u64,
+    pub ihr_price_factor: u32,
+    /// Part of the forward fee taken by the current validators, in 1/2^16 units.
+    pub first_frac: u16,
+    pub next_frac: u16,
+}
+
+impl MsgForwardPrices {
+    /// Computes the forward fee in nanotons for a message of `cells` cells with `bits` bits,
+    /// not counting the root cell.
+    pub fn compute_fwd_fee(&self, cells: u64, bits: u64) -> u64 {
+        let fee = (self.bit_price as u128 * bits as u128 + self.cell_price as u128 * cells as u128)
+            .div_ceil(1 << 16);
+        self.lump_price
+            .saturating_add(fee.try_into().unwrap_or(u64::MAX))
+    }
+}
+
+/// Validator of a validator set.
+///
+/// ```raw
+/// validator#53 public_key:SigPubKey weight:uint64 = ValidatorDescr;
+/// validator_addr#73 public_key:SigPubKey weight:uint64 adnl_addr:bits256 = ValidatorDescr;
+/// ed25519_pubkey#8e81278a pubkey:bits256 = SigPubKey;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidatorDescr {
+    pub public_key: TonHash,
+    pub weight: u64,
+    pub adnl_addr: Option<TonHash>,
+}
+
+impl ValidatorDescr {
+    const VALIDATOR_TAG: u8 = 0x53;
+    const VALIDATOR_ADDR_TAG: u8 = 0x73;
+    const ED25519_PUBKEY_TAG: u64 = 0x8e81278a;
+
+    pub fn read(parser: &mut CellParser) -> Result<ValidatorDescr, TonCellError> {
+        let tag = parser.load_u8(8)?;
+        if tag != Self::VALIDATOR_TAG && tag != Self::VALIDATOR_ADDR_TAG {
+            return Err(TonCellError::CellParserError(format!(
+                "Invalid tag of ValidatorDescr: {tag:#x}"
+            )));
+        }
+        check_tag(parser, 32, Self::ED25519_PUBKEY_TAG, "SigPubKey")?;
+        let public_key = TonHash::read(parser)?;
+        let weight = parser.load_u64(64)?;
+        let adnl_addr = if tag == Self::VALIDATOR_ADDR_TAG {
+            Some(TonHash::read(parser)?)
+        } else {
+            None
+        };
+        Ok(ValidatorDescr {
+            public_key,
+            weight,
+            adnl_addr,
+        })
+    }
+}
+
+/// Validator set, e.g. the current one in config param 34.
+///
+/// ```raw
+/// validators#11 utime_since:uint32 utime_until:uint32 total:(## 16) main:(## 16)
+///   list:(Hashmap 16 ValidatorDescr) = ValidatorSet;
+/// validators_ext#12 utime_since:uint32 utime_until:uint32 total:(## 16) main:(## 16)
+///   total_weight:uint64 list:(HashmapE 16 ValidatorDescr) = ValidatorSet;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidatorSet {
+    pub utime_since: u32,
+    pub utime_until: u32,
+    pub total: u16,
+    /// Number of masterchain validators, the first ones of `validators`.
+    pub main: u16,
+    pub total_weight: u64,
+    /// Validators ordered by their index.
+    pub validators: Vec<ValidatorDescr>,
+}
+
+impl ValidatorSet {
+    const VALIDATORS_TAG: u8 = 0x11;
+    const VALIDATORS_EXT_TAG: u8 = 0x12;
+
+    pub fn read(parser: &mut CellParser) -> Result<ValidatorSet, TonCellError> {
+        let tag = parser.load_u8(8)?;
+        if tag != Self::VALIDATORS_TAG && tag != Self::VALIDATORS_EXT_TAG {
+            return Err(TonCellError::CellParserError(format!(
+                "Invalid tag of ValidatorSet: {tag:#x}"
+            )));
+        }
+        let utime_since = parser.load_u32(32)?;
+        let utime_until = parser.load_u32(32)?;
+        let total = parser.load_u16(16)?;
+        let main = parser.load_u16(16)?;
+        let (total_weight, list) = if tag == Self::VALIDATORS_EXT_TAG {
+            let total_weight = parser.load_u64(64)?;
+            let list = parser.load_maybe_dict(16, key_reader_u16, ValidatorDescr::read)?;
+            (Some(total_weight), list)
+        } else {
+            let list = parser.load_dict(16, key_reader_u16, ValidatorDescr::read)?;
+            (None, list)
+        };
+        let mut list: Vec<_> = list.into_iter().collect();
+        list.sort_by_key(|(index, _)| *index);
+        let validators: Vec<_> = list.into_iter().map(|(_, validator)| validator).collect();
+        let total_weight =
+            total_weight.unwrap_or_else(|| validators.iter().map(|v| v.weight).sum());
+        Ok(ValidatorSet {
+            utime_since,
+            utime_until,
+            total,
+            main,
+            total_weight,
+            validators,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{
+        ConfigParam, GasLimitsPrices, MsgForwardPrices, StoragePrices, ValidatorDescr,
+        CONFIG_PARAM_CURRENT_VALIDATORS, CONFIG_PARAM_ELECTOR_ADDRESS, CONFIG_PARAM_GAS_PRICES,
+        CONFIG_PARAM_MSG_FORWARD_PRICES, CONFIG_PARAM_STORAGE_PRICES,
+    };
+    use crate::cell::{Cell, CellBuilder, TonCellError};
+    use crate::tlb::TlbType;
+    use crate::TonAddress;
+
+    #[test]
+    fn test_parse_config_params() -> Result<(), TonCellError> {
+        let elector = CellBuilder::new().store_slice(&[0x33; 32])?.build()?;
+        assert_eq!(
+            ConfigParam::parse(CONFIG_PARAM_ELECTOR_ADDRESS, &elector)?,
+            ConfigParam::ElectorAddress(TonAddress::new(-1, &[0x33; 32]))
+        );
+
+        let storage_prices = StoragePrices {
+            utime_since: 0,
+            bit_price_ps: 1,
+            cell_price_ps: 500,
+            mc_bit_price_ps: 1000,
+            mc_cell_price_ps: 500000,
+        };
+        let dict = HashMap::from([(0u32, storage_prices.clone())]);
+        let cell = CellBuilder::new()
+            .store_dict(32, |builder, val: StoragePrices| val.write(builder), dict)?
+            .build()?;
+        assert_eq!(
+            ConfigParam::parse(CONFIG_PARAM_STORAGE_PRICES, &cell)?,
+            ConfigParam::StoragePrices(vec![storage_prices])
+        );
+
+        // basechain gas prices of the mainnet
+        let gas = CellBuilder::new()
+            .store_u8(8, 0xd1)?
+            .store_u64(64, 100)?
+            .store_u64(64, 40000)?
+            .store_u8(8, 0xde)?
+            .store_u64(64, 26214400)?
+            .store_u64(64, 1000000)?
+            .store_u64(64, 1000000)?
+            .store_u64(64, 10000)?
+            .store_u64(64, 10000000)?
+            .store_u64(64, 100000000)?
+            .store_u64(64, 100000000)?
+            .build()?;
+        let ConfigParam::GasPrices(gas) = ConfigParam::parse(CONFIG_PARAM_GAS_PRICES, &gas)? else {
+            panic!("Expected gas prices");
+        };
+        assert_eq!(
+            gas,
+            GasLimitsPrices {
+                flat_gas_limit: 100,
+                flat_gas_price: 40000,
+                gas_price: 26214400,
+                gas_limit: 1000000,
+                special_gas_limit: 1000000,
+                gas_credit: 10000,
+                block_gas_limit: 10000000,
+                freeze_due_limit: 100000000,
+                delete_due_limit: 100000000,
+            }
+        );
+        assert_eq!(gas.compute_gas_fee(50), 40000);
+        assert_eq!(gas.compute_gas_fee(3000), 40000 + 2900 * 400);
+
+        let fwd = MsgForwardPrices {
+            lump_price: 400000,
+            bit_price: 26214400,
+            cell_price: 2621440000,
+            ihr_price_factor: 98304,
+            first_frac: 21845,
+            next_frac: 21845,
+        };
+        assert_eq!(
+            ConfigParam::parse(CONFIG_PARAM_MSG_FORWARD_PRICES, &fwd.to_cell()?)?,
+            ConfigParam::MsgForwardPrices(fwd.clone())
+        );
+        assert_eq!(
+            fwd.compute_fwd_fee(2, 1000),
+            400000 + 1000 * 400 + 2 * 40000
+        );
+
+        assert!(ConfigParam::parse(2, &elector).is_err());
+        Ok(())
+    }
+
+    fn val_writer_cell(builder: &mut CellBuilder, val: Cell) -> Result<(), TonCellError> {
+        builder.store_cell(&val)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_validator_set() -> Result<(), TonCellError> {
+        let validator = |idx: u8, with_adnl: bool| -> Result<Cell, TonCellError> {
+            let mut builder = CellBuilder::new();
+            builder
+                .store_u8(8, if with_adnl { 0x73 } else { 0x53 })?
+                .store_u32(32, 0x8e81278a)?
+                .store_slice(&[idx; 32])?
+                .store_u64(64, 10 + idx as u64)?;
+            if with_adnl {
+                builder.store_slice(&[idx + 100; 32])?;
+            }
+            builder.build()
+        };
+        let dict = HashMap::from([(0u16, validator(0, true)?), (1u16, validator(1, false)?)]);
+        let cell = CellBuilder::new()
+            .store_u8(8, 0x12)?
+            .store_u32(32, 1_700_000_000)?
+            .store_u32(32, 1_700_065_536)?
+            .store_u32(16, 2)?
+            .store_u32(16, 1)?
+            .store_u64(64, 21)?
+            .store_maybe_dict(16, val_writer_cell, dict)?
+            .build()?;
+
+        let ConfigParam::CurrentValidators(set) =
+            ConfigParam::parse(CONFIG_PARAM_CURRENT_VALIDATORS, &cell)?
+        else {
+            panic!("Expected validator set");
+        };
+        assert_eq!((set.total, set.main, set.total_weight), (2, 1, 21));
+        assert_eq!(
+            set.validators,
+            vec![
+                ValidatorDescr {
+                    public_key: [0; 32],
+                    weight: 10,
+                    adnl_addr: Some([100; 32]),
+                },
+                ValidatorDescr {
+                    public_key: [1; 32],
+                    weight: 11,
+                    adnl_addr: None,
+                },
+            ]
+        );
+        Ok(())
+    }
+}
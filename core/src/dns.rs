@@ -0,0 +1,195 @@
+//! TON DNS (TEP-81) domain encoding, record categories and records.
+//!
+//! See https://github.com/ton-blockchain/TEPs/blob/master/text/0081-dns-standard.md
+
+use sha2::{Digest, Sha256};
+
+use crate::cell::{Cell, CellBuilder, TonCellError};
+use crate::{TonAddress, TonHash};
+
+/// dns_smc_address#9fd3 smc_addr:MsgAddressInt flags:(## 8) { flags <= 1 }
+///   cap_list:flags . 0?SmcCapList = DNSRecord;
+pub const DNS_SMC_ADDRESS_TAG: u16 = 0x9fd3;
+/// dns_next_resolver#ba93 resolver:MsgAddressInt = DNSRecord;
+pub const DNS_NEXT_RESOLVER_TAG: u16 = 0xba93;
+/// dns_adnl_address#ad01 adnl_addr:bits256 flags:(## 8) { flags <= 1 }
+///   proto_list:flags . 0?ProtoList = DNSRecord;
+pub const DNS_ADNL_ADDRESS_TAG: u16 = 0xad01;
+/// dns_storage_address#7473 bag_id:bits256 = DNSRecord;
+pub const DNS_STORAGE_ADDRESS_TAG: u16 = 0x7473;
+
+/// Maximal length of an encoded domain, so that it fits into a single cell.
+pub const DNS_MAX_DOMAIN_BYTES: usize = 127;
+
+/// Category of a DNS record, identified by the sha256 hash of its name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DnsCategory {
+    /// Address of the wallet of the domain owner, `dns_smc_address`.
+    Wallet,
+    /// ADNL address of a TON Site, `dns_adnl_address`.
+    Site,
+    /// Bag id of TON Storage, `dns_storage_address`.
+    Storage,
+    /// Resolver of the subdomains, `dns_next_resolver`.
+    NextResolver,
+    /// Any other category by its name.
+    Custom(String),
+}
+
+impl DnsCategory {
+    pub fn name(&self) -> &str {
+        match self {
+            DnsCategory::Wallet => "wallet",
+            DnsCategory::Site => "site",
+            DnsCategory::Storage => "storage",
+            DnsCategory::NextResolver => "dns_next_resolver",
+            DnsCategory::Custom(name) => name.as_str(),
+        }
+    }
+
+    /// Key of the category in the record dictionary of a DNS item, i.e. `sha256(name)`.
+    pub fn hash(&self) -> TonHash {
+        Sha256::digest(self.name().as_bytes()).into()
+    }
+}
+
+/// Encodes `domain`, e.g. `alice.ton`, into the form expected by `dnsresolve`:
+/// labels in reverse order, each terminated with `\0`, i.e. `ton\0alice\0`.
+pub fn encode_domain(domain: &str) -> Result<Vec<u8>, TonCellError> {
+    let domain = domain.strip_suffix('.').unwrap_or(domain);
+    if domain.is_empty() {
+        return Err(TonCellError::InvalidInput("Empty domain".to_string()));
+    }
+    let mut encoded = Vec::with_capacity(domain.len() + 1);
+    for label in domain.split('.').rev() {
+        if label.is_empty() {
+            return Err(TonCellError::InvalidInput(format!(
+                "Empty label in domain {domain}"
+            )));
+        }
+        if let Some(c) = label.bytes().find(|c| !(0x21..=0x7e).contains(c)) {
+            return Err(TonCellError::InvalidInput(format!(
+                "Invalid character {c:#x} in domain {domain}"
+            )));
+        }
+        encoded.extend(label.to_ascii_lowercase().bytes());
+        encoded.push(0);
+    }
+    if encoded.len() > DNS_MAX_DOMAIN_BYTES {
+        return Err(TonCellError::InvalidInput(format!(
+            "Domain {domain} is longer than {DNS_MAX_DOMAIN_BYTES} bytes"
+        )));
+    }
+    Ok(encoded)
+}
+
+/// DNS record according to TL-B schema of TEP-81. Capability and protocol lists of
+/// smart contract and ADNL addresses are not retained.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DnsRecord {
+    SmcAddress(TonAddress),
+    NextResolver(TonAddress),
+    AdnlAddress(TonHash),
+    StorageAddress(TonHash),
+}
+
+impl DnsRecord {
+    pub fn parse(cell: &Cell) -> Result<DnsRecord, TonCellError> {
+        let mut parser = cell.parser();
+        let tag = parser.load_u16(16)?;
+        let record = match tag {
+            DNS_SMC_ADDRESS_TAG => DnsRecord::SmcAddress(parser.load_address()?),
+            DNS_NEXT_RESOLVER_TAG => DnsRecord::NextResolver(parser.load_address()?),
+            DNS_ADNL_ADDRESS_TAG => {
+                let mut hash = [0; 32];
+                parser.load_slice(&mut hash)?;
+                DnsRecord::AdnlAddress(hash)
+            }
+            DNS_STORAGE_ADDRESS_TAG => {
+                let mut hash = [0; 32];
+                parser.load_slice(&mut hash)?;
+                DnsRecord::StorageAddress(hash)
+            }
+            _ => {
+                return Err(TonCellError::CellParserError(format!(
+                    "Unknown DNS record tag {tag:#06x}"
+                )))
+            }
+        };
+        Ok(record)
+    }
+
+    pub fn build(&self) -> Result<Cell, TonCellError> {
+        let mut builder = CellBuilder::new();
+        match self {
+            DnsRecord::SmcAddress(address) => {
+                builder.store_u32(16, DNS_SMC_ADDRESS_TAG as u32)?;
+                builder.store_address(address)?;
+                builder.store_u8(8, 0)?;
+            }
+            DnsRecord::NextResolver(address) => {
+                builder.store_u32(16, DNS_NEXT_RESOLVER_TAG as u32)?;
+                builder.store_address(address)?;
+            }
+            DnsRecord::AdnlAddress(hash) => {
+                builder.store_u32(16, DNS_ADNL_ADDRESS_TAG as u32)?;
+                builder.store_slice(hash)?;
+                builder.store_u8(8, 0)?;
+            }
+            DnsRecord::StorageAddress(hash) => {
+                builder.store_u32(16, DNS_STORAGE_ADDRESS_TAG as u32)?;
+                builder.store_slice(hash)?;
+            }
+        }
+        builder.build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_domain, DnsCategory, DnsRecord};
+    use crate::cell::{CellBuilder, TonCellError};
+    use crate::TonAddress;
+
+    #[test]
+    fn test_encode_domain() -> Result<(), TonCellError> {
+        assert_eq!(encode_domain("alice.ton")?, b"ton\0alice\0");
+        assert_eq!(encode_domain("Wallet.Alice.ton.")?, b"ton\0alice\0wallet\0");
+        assert!(encode_domain("").is_err());
+        assert!(encode_domain("alice..ton").is_err());
+        assert!(encode_domain("al ice.ton").is_err());
+        assert!(encode_domain(&format!("{}.ton", "a".repeat(123))).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_dns_category_hash() {
+        assert_eq!(
+            hex::encode(DnsCategory::Wallet.hash()),
+            "e8d44050873dba865aa7c170ab4cce64d90839a34dcfd6cf71d14e0205443b1b"
+        );
+        assert_eq!(
+            DnsCategory::Custom("site".to_string()).hash(),
+            DnsCategory::Site.hash()
+        );
+    }
+
+    #[test]
+    fn test_dns_record_round_trip() -> Result<(), TonCellError> {
+        let address =
+            TonAddress::from_base64_url("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR")
+                .unwrap();
+        let records = [
+            DnsRecord::SmcAddress(address.clone()),
+            DnsRecord::NextResolver(address),
+            DnsRecord::AdnlAddress([1; 32]),
+            DnsRecord::StorageAddress([2; 32]),
+        ];
+        for record in records {
+            assert_eq!(DnsRecord::parse(&record.build()?)?, record);
+        }
+        let unknown = CellBuilder::new().store_u32(16, 0x1234)?.build()?;
+        assert!(DnsRecord::parse(&unknown).is_err());
+        Ok(())
+    }
+}
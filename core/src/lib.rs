@@ -1,10 +1,20 @@
+// Allows the derive macros to refer to `tonlib_core` inside the crate itself.
+extern crate self as tonlib_core;
+
+pub mod block;
 pub mod cell;
+pub mod config;
 pub mod constants;
+pub mod dns;
 pub mod message;
 pub mod mnemonic;
+pub mod proof;
+pub mod tlb;
+pub mod transaction;
 pub mod types;
 pub mod wallet;
 
 pub use crate::types::{
-    TonAddress, TonAddressParseError, TonHash, TonTxId, TransactionIdParseError,
+    ParsedTonAddress, TonAddress, TonAddressFlags, TonAddressFormat, TonAddressParseError, TonHash,
+    TonTxId, TransactionIdParseError,
 };
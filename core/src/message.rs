@@ -9,14 +9,28 @@ pub use common_msg_info::*;
 
 use crate::cell::{ArcCell, Cell};
 
+mod bounce;
+mod comment;
 mod common;
+mod deploy;
+mod elector;
+mod external_in;
 mod jetton;
+mod multisig;
 mod nft;
+mod ops;
 mod sbt;
 mod transfer;
+pub use bounce::*;
+pub use comment::*;
 pub use common::*;
+pub use deploy::*;
+pub use elector::*;
+pub use external_in::*;
 pub use jetton::*;
+pub use multisig::*;
 pub use nft::*;
+pub use ops::*;
 pub use sbt::*;
 pub use transfer::*;
 
@@ -55,6 +69,20 @@ pub trait HasOpcode: TonMessage {
     fn query_id(&self) -> u64;
 }
 
+/// Same as `HasOpcode::verify_opcode` for payloads without query id, e.g. comments.
+pub(crate) fn verify_opcode(opcode: u32, expected_opcode: u32) -> Result<(), TonMessageError> {
+    if opcode != expected_opcode {
+        let invalid = InvalidMessage {
+            opcode: Some(opcode),
+            query_id: None,
+            message: format!("Unexpected opcode.  {0:08x} expected", expected_opcode),
+        };
+        Err(TonMessageError::InvalidMessage(invalid))
+    } else {
+        Ok(())
+    }
+}
+
 impl TonMessage for Cell {
     fn build(&self) -> Result<Cell, TonMessageError> {
         Ok(self.clone())
@@ -0,0 +1,177 @@
+use crate::cell::{Cell, CellBuilder, CellParser};
+use crate::message::{InvalidMessage, TonMessage, TonMessageError};
+
+/// Prefix of the body of a bounced message
+pub const BOUNCED_MESSAGE_PREFIX: u32 = 0xffffffff;
+
+/// Body of a bounced message according to TL-B schema:
+///
+/// ```raw
+/// bounced#ffffffff original_body:bits = InternalMsgBody;
+/// ```
+///
+/// `original_body` holds the first bits (up to 256) of the body of the original message,
+/// which typically are the op code and the query id.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BouncedMessage {
+    /// op code of the original message, if the original body was long enough.
+    pub original_opcode: Option<u32>,
+    /// query id of the original message, if the original body was long enough.
+    pub original_query_id: Option<u64>,
+    /// truncated body of the original message.
+    pub original_body: Cell,
+}
+
+impl BouncedMessage {
+    /// Parses a full message cell (`Message Any`).
+    ///
+    /// Returns `None` if the message is not an internal message with the `bounced` flag set.
+    pub fn from_message(message: &Cell) -> Result<Option<Self>, TonMessageError> {
+        let mut parser = message.parser();
+        let is_external = parser.load_bit()?;
+        if is_external {
+            return Ok(None);
+        }
+        let _ihr_disabled = parser.load_bit()?;
+        let _bounce = parser.load_bit()?;
+        let bounced = parser.load_bit()?;
+        if !bounced {
+            return Ok(None);
+        }
+        let _src = parser.load_address()?;
+        let _dest = parser.load_address()?;
+        let _value = parser.load_coins()?;
+        let _extra_currencies = parser.load_maybe_cell_ref()?;
+        let _ihr_fee = parser.load_coins()?;
+        let _fwd_fee = parser.load_coins()?;
+        let _created_lt = parser.load_u64(64)?;
+        let _created_at = parser.load_u32(32)?;
+        let has_state_init = parser.load_bit()?;
+        if has_state_init {
+            let state_init_in_ref = parser.load_bit()?;
+            if !state_init_in_ref {
+                return Err(TonMessageError::InvalidMessage(InvalidMessage {
+                    opcode: None,
+                    query_id: None,
+                    message: "Inline state init in bounced message is not supported".to_string(),
+                }));
+            }
+            parser.next_reference()?;
+        }
+        let body = load_body(&mut parser)?;
+        Ok(Some(Self::parse(&body)?))
+    }
+}
+
+fn load_body(parser: &mut CellParser) -> Result<Cell, TonMessageError> {
+    let body_in_ref = parser.load_bit()?;
+    let body = if body_in_ref {
+        parser.next_reference()?.as_ref().clone()
+    } else {
+        parser.load_remaining()?
+    };
+    Ok(body)
+}
+
+impl TonMessage for BouncedMessage {
+    fn build(&self) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        builder.store_u32(32, BOUNCED_MESSAGE_PREFIX)?;
+        builder.store_cell_data(&self.original_body)?;
+
+        Ok(builder.build()?)
+    }
+
+    fn parse(cell: &Cell) -> Result<Self, TonMessageError> {
+        let mut parser = cell.parser();
+
+        let prefix = parser.load_u32(32)?;
+        if prefix != BOUNCED_MESSAGE_PREFIX {
+            return Err(TonMessageError::InvalidMessage(InvalidMessage {
+                opcode: Some(prefix),
+                query_id: None,
+                message: format!(
+                    "Unexpected prefix.  {0:08x} expected",
+                    BOUNCED_MESSAGE_PREFIX
+                ),
+            }));
+        }
+        let original_body = parser.load_remaining()?;
+
+        let mut original_parser = original_body.parser();
+        let original_opcode = if original_parser.remaining_bits() >= 32 {
+            Some(original_parser.load_u32(32)?)
+        } else {
+            None
+        };
+        let original_query_id =
+            if original_opcode.is_some() && original_parser.remaining_bits() >= 64 {
+                Some(original_parser.load_u64(64)?)
+            } else {
+                None
+            };
+
+        Ok(BouncedMessage {
+            original_opcode,
+            original_query_id,
+            original_body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use num_bigint::BigUint;
+
+    use super::BouncedMessage;
+    use crate::cell::CellBuilder;
+    use crate::message::{
+        CommonMsgInfo, HasOpcode, JettonTransferMessage, TonMessage, TonMessageError,
+        TransferMessage,
+    };
+    use crate::TonAddress;
+
+    #[test]
+    fn test_bounced_message_from_message() -> Result<(), TonMessageError> {
+        let dest = TonAddress::NULL;
+        let jetton_transfer = JettonTransferMessage::new(&dest, &BigUint::from(100u32))
+            .with_query_id(42)
+            .build()?;
+
+        let mut bounced_body = CellBuilder::new();
+        bounced_body.store_u32(32, 0xffffffff)?;
+        bounced_body.store_cell_data(&jetton_transfer)?;
+        let bounced_body = bounced_body.build()?;
+
+        let info = CommonMsgInfo::new_default_internal(&dest, &BigUint::from(1u32));
+        let message = TransferMessage::new(info)
+            .with_data(Arc::new(bounced_body.clone()))
+            .build()?;
+        let parsed = BouncedMessage::parse(&bounced_body)?;
+        assert_eq!(
+            parsed.original_opcode,
+            Some(JettonTransferMessage::opcode())
+        );
+        assert_eq!(parsed.original_query_id, Some(42));
+        assert_eq!(parsed.build()?, bounced_body);
+
+        let bounced = BouncedMessage::from_message(&message)?.unwrap();
+        assert_eq!(bounced, parsed);
+
+        let not_bounced = CommonMsgInfo::new_default_internal(&dest, &BigUint::from(1u32));
+        let not_bounced = match not_bounced {
+            CommonMsgInfo::InternalMessage(mut m) => {
+                m.bounced = false;
+                CommonMsgInfo::InternalMessage(m)
+            }
+            other => other,
+        };
+        let message = TransferMessage::new(not_bounced)
+            .with_data(Arc::new(jetton_transfer))
+            .build()?;
+        assert_eq!(BouncedMessage::from_message(&message)?, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,328 @@
+use aes::cipher::generic_array::GenericArray;
+use aes::cipher::{BlockDecrypt, BlockEncrypt, KeyInit};
+use aes::Aes256;
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::{Digest, Sha512};
+
+use crate::cell::{Cell, CellBuilder};
+use crate::message::{verify_opcode, InvalidMessage, TonMessage, TonMessageError};
+use crate::mnemonic::KeyPair;
+use crate::TonAddress;
+
+/// text_comment#00000000 text:SnakeData = InternalMsgBody;
+pub const TEXT_COMMENT: u32 = 0x00000000;
+
+/// encrypted_comment#2167da4b data:SnakeData = InternalMsgBody;
+pub const ENCRYPTED_COMMENT: u32 = 0x2167da4b;
+
+const AES_BLOCK_SIZE: usize = 16;
+const MIN_PADDING: usize = 16;
+const MSG_KEY_LEN: usize = 16;
+const PUBLIC_KEY_LEN: usize = 32;
+
+/// Builders of message bodies with common payloads.
+pub struct MessagePayload;
+
+impl MessagePayload {
+    /// Builds the body of a text comment, see `TextComment`.
+    pub fn comment(text: &str) -> Result<Cell, TonMessageError> {
+        TextComment::new(text).build()
+    }
+
+    /// Builds the body of a comment encrypted for the owner of `receiver_public_key`,
+    /// see `EncryptedComment::encrypt`.
+    pub fn encrypted_comment(
+        text: &str,
+        sender_address: &TonAddress,
+        sender_key_pair: &KeyPair,
+        receiver_public_key: &[u8],
+    ) -> Result<Cell, TonMessageError> {
+        EncryptedComment::encrypt(text, sender_address, sender_key_pair, receiver_public_key)?
+            .build()
+    }
+
+    /// Returns the text of a text comment body, `None` for bodies of other messages.
+    pub fn parse_comment(body: &Cell) -> Result<Option<String>, TonMessageError> {
+        if body.bit_len() < 32 || body.parser().load_u32(32)? != TEXT_COMMENT {
+            return Ok(None);
+        }
+        Ok(Some(TextComment::parse(body)?.text))
+    }
+}
+
+/// Creates a body of a text comment according to TL-B schema:
+///
+/// ```raw
+/// text_comment#00000000 text:SnakeData = InternalMsgBody;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextComment {
+    pub text: String,
+}
+
+impl TextComment {
+    pub fn new(text: &str) -> Self {
+        TextComment {
+            text: text.to_string(),
+        }
+    }
+}
+
+impl TonMessage for TextComment {
+    fn build(&self) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        builder.store_u32(32, TEXT_COMMENT)?;
+        builder.store_snake_data(self.text.as_bytes())?;
+        Ok(builder.build()?)
+    }
+
+    fn parse(cell: &Cell) -> Result<Self, TonMessageError> {
+        let mut parser = cell.parser();
+        let opcode = parser.load_u32(32)?;
+        verify_opcode(opcode, TEXT_COMMENT)?;
+        let text = String::from_utf8(parser.load_snake_data()?)
+            .map_err(|e| invalid_comment(opcode, format!("Invalid text comment: {}", e)))?;
+        Ok(TextComment { text })
+    }
+}
+
+/// Creates a body of an encrypted comment according to TL-B schema:
+///
+/// ```raw
+/// encrypted_comment#2167da4b data:SnakeData = InternalMsgBody;
+/// ```
+///
+/// The comment is encrypted the same way as by wallet apps: with AES-256-CBC keyed by
+/// the X25519 shared secret of the sender and receiver keys, salted with the sender address.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EncryptedComment {
+    /// XOR of the public keys of the sender and receiver (32 bytes), the message key (16 bytes)
+    /// and the encrypted padded comment.
+    pub data: Vec<u8>,
+}
+
+impl EncryptedComment {
+    /// Encrypts `text` sent by the owner of `sender_key_pair` from `sender_address`.
+    pub fn encrypt(
+        text: &str,
+        sender_address: &TonAddress,
+        sender_key_pair: &KeyPair,
+        receiver_public_key: &[u8],
+    ) -> Result<Self, TonMessageError> {
+        let shared_secret = shared_secret(sender_key_pair, receiver_public_key)?;
+        let prefix_len =
+            ((MIN_PADDING + AES_BLOCK_SIZE - 1 + text.len()) & !(AES_BLOCK_SIZE - 1)) - text.len();
+        let mut plain = vec![0; prefix_len];
+        rand::thread_rng().fill_bytes(&mut plain);
+        plain[0] = prefix_len as u8;
+        plain.extend_from_slice(text.as_bytes());
+
+        let msg_key = hmac_sha512(sender_address.to_base64_url().as_bytes(), &plain)?;
+        let msg_key = &msg_key[..MSG_KEY_LEN];
+        let (key, iv) = aes_key_iv(&shared_secret, msg_key)?;
+        aes_cbc_encrypt(&mut plain, &key, iv);
+
+        let mut data = xor_keys(&sender_key_pair.public_key, receiver_public_key)?;
+        data.extend_from_slice(msg_key);
+        data.extend(plain);
+        Ok(EncryptedComment { data })
+    }
+
+    /// Decrypts the comment with `key_pair` of either the receiver or the sender,
+    /// `sender_address` is the address the comment was sent from.
+    pub fn decrypt(
+        &self,
+        key_pair: &KeyPair,
+        sender_address: &TonAddress,
+    ) -> Result<String, TonMessageError> {
+        let encrypted_len = self.data.len().saturating_sub(PUBLIC_KEY_LEN + MSG_KEY_LEN);
+        if encrypted_len == 0 || !encrypted_len.is_multiple_of(AES_BLOCK_SIZE) {
+            return Err(invalid_comment(
+                ENCRYPTED_COMMENT,
+                format!("Invalid encrypted comment length: {}", self.data.len()),
+            ));
+        }
+        let (keys, rest) = self.data.split_at(PUBLIC_KEY_LEN);
+        let (msg_key, encrypted) = rest.split_at(MSG_KEY_LEN);
+        let other_public_key = xor_keys(keys, &key_pair.public_key)?;
+        let shared_secret = shared_secret(key_pair, &other_public_key)?;
+        let (key, iv) = aes_key_iv(&shared_secret, msg_key)?;
+        let mut plain = encrypted.to_vec();
+        aes_cbc_decrypt(&mut plain, &key, iv);
+
+        let expected_msg_key = hmac_sha512(sender_address.to_base64_url().as_bytes(), &plain)?;
+        if expected_msg_key[..MSG_KEY_LEN] != *msg_key {
+            return Err(TonMessageError::NaclCryptographicError(
+                "Failed to decrypt comment: message key mismatch".to_string(),
+            ));
+        }
+        let prefix_len = plain[0] as usize;
+        if !(MIN_PADDING..=plain.len()).contains(&prefix_len) {
+            return Err(invalid_comment(
+                ENCRYPTED_COMMENT,
+                format!("Invalid encrypted comment prefix length: {}", prefix_len),
+            ));
+        }
+        String::from_utf8(plain.split_off(prefix_len)).map_err(|e| {
+            invalid_comment(
+                ENCRYPTED_COMMENT,
+                format!("Invalid encrypted comment: {}", e),
+            )
+        })
+    }
+}
+
+impl TonMessage for EncryptedComment {
+    fn build(&self) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        builder.store_u32(32, ENCRYPTED_COMMENT)?;
+        builder.store_snake_data(&self.data)?;
+        Ok(builder.build()?)
+    }
+
+    fn parse(cell: &Cell) -> Result<Self, TonMessageError> {
+        let mut parser = cell.parser();
+        let opcode = parser.load_u32(32)?;
+        verify_opcode(opcode, ENCRYPTED_COMMENT)?;
+        let data = parser.load_snake_data()?;
+        Ok(EncryptedComment { data })
+    }
+}
+
+fn invalid_comment(opcode: u32, message: String) -> TonMessageError {
+    TonMessageError::InvalidMessage(InvalidMessage {
+        opcode: Some(opcode),
+        query_id: None,
+        message,
+    })
+}
+
+/// X25519 shared secret of the ed25519 keys, converted the same way as by libsodium.
+fn shared_secret(key_pair: &KeyPair, other_public_key: &[u8]) -> Result<[u8; 32], TonMessageError> {
+    let crypto_error = |message: &str| TonMessageError::NaclCryptographicError(message.to_string());
+    let seed = key_pair
+        .secret_key
+        .get(..32)
+        .ok_or_else(|| crypto_error("Invalid secret key length"))?;
+    let mut scalar = [0; 32];
+    scalar.copy_from_slice(&Sha512::digest(seed)[..32]);
+    let other_public_key = CompressedEdwardsY::from_slice(other_public_key)
+        .map_err(|_| crypto_error("Invalid public key length"))?
+        .decompress()
+        .ok_or_else(|| crypto_error("Invalid public key"))?;
+    Ok(other_public_key
+        .to_montgomery()
+        .mul_clamped(scalar)
+        .to_bytes())
+}
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> Result<Vec<u8>, TonMessageError> {
+    let mut mac = <Hmac<Sha512> as Mac>::new_from_slice(key)
+        .map_err(|e| TonMessageError::NaclCryptographicError(e.to_string()))?;
+    mac.update(data);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+/// AES key and IV of the message with `msg_key`.
+fn aes_key_iv(
+    shared_secret: &[u8],
+    msg_key: &[u8],
+) -> Result<(Vec<u8>, [u8; 16]), TonMessageError> {
+    let hash = hmac_sha512(shared_secret, msg_key)?;
+    let mut iv = [0; 16];
+    iv.copy_from_slice(&hash[32..48]);
+    Ok((hash[..32].to_vec(), iv))
+}
+
+fn xor_keys(a: &[u8], b: &[u8]) -> Result<Vec<u8>, TonMessageError> {
+    if a.len() != PUBLIC_KEY_LEN || b.len() != PUBLIC_KEY_LEN {
+        return Err(TonMessageError::NaclCryptographicError(
+            "Invalid public key length".to_string(),
+        ));
+    }
+    Ok(a.iter().zip(b).map(|(a, b)| a ^ b).collect())
+}
+
+/// Encrypts `data` of whole blocks in place.
+fn aes_cbc_encrypt(data: &mut [u8], key: &[u8], iv: [u8; 16]) {
+    let cipher = Aes256::new(GenericArray::from_slice(key));
+    let mut prev = iv;
+    for chunk in data.chunks_exact_mut(AES_BLOCK_SIZE) {
+        chunk.iter_mut().zip(prev).for_each(|(b, p)| *b ^= p);
+        cipher.encrypt_block(GenericArray::from_mut_slice(chunk));
+        prev.copy_from_slice(chunk);
+    }
+}
+
+/// Decrypts `data` of whole blocks in place.
+fn aes_cbc_decrypt(data: &mut [u8], key: &[u8], iv: [u8; 16]) {
+    let cipher = Aes256::new(GenericArray::from_slice(key));
+    let mut prev = iv;
+    for chunk in data.chunks_exact_mut(AES_BLOCK_SIZE) {
+        let mut encrypted = [0; 16];
+        encrypted.copy_from_slice(chunk);
+        cipher.decrypt_block(GenericArray::from_mut_slice(chunk));
+        chunk.iter_mut().zip(prev).for_each(|(b, p)| *b ^= p);
+        prev = encrypted;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EncryptedComment, MessagePayload, TextComment};
+    use crate::cell::CellBuilder;
+    use crate::message::{decode_message_body, MessageBody, TonMessage, TonMessageError};
+    use crate::mnemonic::KeyPair;
+    use crate::TonAddress;
+
+    #[test]
+    fn test_text_comment() -> Result<(), TonMessageError> {
+        let text = "a comment long enough to span more than a single cell ".repeat(3);
+        let body = MessagePayload::comment(&text)?;
+        assert_eq!(MessagePayload::parse_comment(&body)?, Some(text.clone()));
+        assert_eq!(TextComment::parse(&body)?, TextComment::new(&text));
+        assert_eq!(decode_message_body(&body)?, MessageBody::TextComment(text));
+
+        let other = CellBuilder::new().store_u32(32, 1)?.build()?;
+        assert_eq!(MessagePayload::parse_comment(&other)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_comment() -> Result<(), TonMessageError> {
+        let sender = KeyPair::from_seed(&[1; 32]);
+        let receiver = KeyPair::from_seed(&[2; 32]);
+        let stranger = KeyPair::from_seed(&[3; 32]);
+        let sender_address = TonAddress::new(0, &[4; 32]);
+
+        for text in [
+            "",
+            "hello",
+            "a comment long enough to need a reference cell",
+        ] {
+            let body = MessagePayload::encrypted_comment(
+                text,
+                &sender_address,
+                &sender,
+                &receiver.public_key,
+            )?;
+            let comment = match decode_message_body(&body)? {
+                MessageBody::EncryptedComment(comment) => comment,
+                body => panic!("Unexpected body: {:?}", body),
+            };
+            assert_eq!(comment.decrypt(&receiver, &sender_address)?, text);
+            // The sender can read its own comments too
+            assert_eq!(comment.decrypt(&sender, &sender_address)?, text);
+            assert!(comment.decrypt(&stranger, &sender_address).is_err());
+            assert!(comment
+                .decrypt(&receiver, &TonAddress::new(0, &[5; 32]))
+                .is_err());
+        }
+
+        let truncated = EncryptedComment { data: vec![0; 50] };
+        assert!(truncated.decrypt(&receiver, &sender_address).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use num_bigint::BigUint;
+
+use super::{CommonMsgInfo, ExternalInMessage, TonMessageError, TransferMessage};
+use crate::cell::{ArcCell, StateInit};
+use crate::tlb::TlbType;
+use crate::TonAddress;
+
+/// Builds the first message to a contract, carrying its state init so the contract is deployed
+/// to the address derived from it, see `derive_address`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeployMessage {
+    pub workchain: i32,
+    pub state_init: StateInit,
+    pub body: Option<ArcCell>,
+}
+
+impl DeployMessage {
+    pub fn new(workchain: i32, state_init: &StateInit) -> Self {
+        DeployMessage {
+            workchain,
+            state_init: state_init.clone(),
+            body: None,
+        }
+    }
+
+    pub fn with_body(&mut self, body: &ArcCell) -> &mut Self {
+        self.body = Some(body.clone());
+        self
+    }
+
+    /// Returns the address the contract is deployed to.
+    pub fn address(&self) -> Result<TonAddress, TonMessageError> {
+        Ok(self.state_init.address(self.workchain)?)
+    }
+
+    /// Builds an external message deploying a contract that pays for its own deployment,
+    /// e.g. a wallet, with an empty body if none is set.
+    pub fn external_message(&self) -> Result<ExternalInMessage, TonMessageError> {
+        let body = self.body.clone().unwrap_or_default();
+        let mut message = ExternalInMessage::new(&self.address()?, &body);
+        message.with_state_init(&Arc::new(self.state_init.to_cell()?));
+        Ok(message)
+    }
+
+    /// Builds a non-bounceable internal message deploying the contract with `value` attached.
+    ///
+    /// The message is non-bounceable, so the value stays on the address if the deployment fails.
+    pub fn internal_message(&self, value: &BigUint) -> Result<TransferMessage, TonMessageError> {
+        let mut common_msg_info = CommonMsgInfo::new_default_internal(&self.address()?, value);
+        if let CommonMsgInfo::InternalMessage(m) = &mut common_msg_info {
+            m.bounce = false;
+            m.bounced = false;
+        }
+        let mut message = TransferMessage::new(common_msg_info);
+        message.with_state_init(self.state_init.to_cell()?);
+        if let Some(body) = &self.body {
+            message.with_data(body.clone());
+        }
+        Ok(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use num_bigint::BigUint;
+
+    use super::DeployMessage;
+    use crate::cell::{CellBuilder, StateInit};
+    use crate::message::{CommonMsgInfo, ExternalInMessage, TonMessage, TonMessageError};
+    use crate::tlb::TlbType;
+
+    #[test]
+    fn test_deploy_message() -> Result<(), TonMessageError> {
+        let code = Arc::new(CellBuilder::new().store_string("code")?.build()?);
+        let data = Arc::new(CellBuilder::new().store_string("data")?.build()?);
+        let body = Arc::new(CellBuilder::new().store_u32(32, 1)?.build()?);
+        let state_init = StateInit::new(&code, &data);
+        let mut deploy = DeployMessage::new(0, &state_init);
+        deploy.with_body(&body);
+        let address = state_init.address(0)?;
+        assert_eq!(deploy.address()?, address);
+
+        let external = deploy.external_message()?;
+        assert_eq!(external.dest, address);
+        assert_eq!(external.body, body);
+        let parsed = ExternalInMessage::parse(&external.build()?)?;
+        let parsed_state_init = StateInit::from_cell(parsed.state_init.as_ref().unwrap())?;
+        assert_eq!(parsed_state_init, state_init);
+
+        let internal = deploy.internal_message(&BigUint::from(100u32))?;
+        match &internal.common_msg_info {
+            CommonMsgInfo::InternalMessage(m) => {
+                assert_eq!(m.dest, address);
+                assert!(!m.bounce);
+            }
+            _ => panic!("internal message expected"),
+        }
+        assert_eq!(internal.state_init, Some(Arc::new(state_init.to_cell()?)));
+        assert_eq!(internal.data, Some(body));
+        Ok(())
+    }
+}
@@ -0,0 +1,23 @@
+// Constants from the elector smart contract
+// https://github.com/ton-blockchain/ton/blob/master/crypto/smartcont/elector-code.fc
+
+/// recover_stake#47657424
+///   query_id:uint64
+/// = InternalMsgBody;
+pub const ELECTOR_RECOVER_STAKE: u32 = 0x47657424;
+
+/// recover_stake_ok#f96f7324
+///   query_id:uint64
+/// = InternalMsgBody;
+pub const ELECTOR_RECOVER_STAKE_OK: u32 = 0xf96f7324;
+
+/// recover_stake_error#fffffffe
+///   query_id:uint64
+/// = InternalMsgBody;
+pub const ELECTOR_RECOVER_STAKE_ERROR: u32 = 0xfffffffe;
+
+mod recover_stake;
+mod recover_stake_response;
+
+pub use recover_stake::*;
+pub use recover_stake_response::*;
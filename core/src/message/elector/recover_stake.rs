@@ -0,0 +1,104 @@
+use super::ELECTOR_RECOVER_STAKE;
+use crate::cell::{Cell, CellBuilder};
+use crate::message::{HasOpcode, TonMessage, TonMessageError};
+
+/// Creates a body for recovery of the stake credited to the sender by the elector,
+/// i.e. a returned stake together with its bonuses, according to TL-B schema:
+///
+/// ```raw
+/// recover_stake#47657424
+///   query_id:uint64
+/// = InternalMsgBody;
+/// ```
+///
+/// The elector answers with `ElectorRecoverStakeResponse`. It must be sent from the
+/// masterchain address the stake was made from, with at least 1 TON attached.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ElectorRecoverStakeMessage {
+    /// arbitrary request number.
+    pub query_id: u64,
+}
+
+#[allow(clippy::new_without_default)]
+impl ElectorRecoverStakeMessage {
+    pub fn new() -> Self {
+        ElectorRecoverStakeMessage { query_id: 0 }
+    }
+}
+
+impl TonMessage for ElectorRecoverStakeMessage {
+    fn build(&self) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        builder.store_u32(32, Self::opcode())?;
+        builder.store_u64(64, self.query_id)?;
+
+        Ok(builder.build()?)
+    }
+
+    fn parse(cell: &Cell) -> Result<Self, TonMessageError> {
+        let mut parser = cell.parser();
+
+        let opcode: u32 = parser.load_u32(32)?;
+        let query_id = parser.load_u64(64)?;
+        parser.ensure_empty()?;
+
+        let result = ElectorRecoverStakeMessage { query_id };
+        result.verify_opcode(opcode)?;
+        Ok(result)
+    }
+}
+
+impl HasOpcode for ElectorRecoverStakeMessage {
+    fn set_query_id(&mut self, query_id: u64) {
+        self.query_id = query_id;
+    }
+
+    fn query_id(&self) -> u64 {
+        self.query_id
+    }
+
+    fn opcode() -> u32 {
+        ELECTOR_RECOVER_STAKE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cell::CellBuilder;
+    use crate::message::{
+        ElectorRecoverStakeMessage, ElectorRecoverStakeResponse, HasOpcode, TonMessage,
+        TonMessageError,
+    };
+
+    #[test]
+    fn test_elector_recover_stake_round_trip() -> Result<(), TonMessageError> {
+        let request = ElectorRecoverStakeMessage::new().with_query_id(42).clone();
+        let request_cell = request.build()?;
+        assert_eq!(
+            request_cell.data(),
+            &[0x47, 0x65, 0x74, 0x24, 0, 0, 0, 0, 0, 0, 0, 42]
+        );
+        assert_eq!(ElectorRecoverStakeMessage::parse(&request_cell)?, request);
+
+        let ok = CellBuilder::new()
+            .store_u32(32, 0xf96f7324)?
+            .store_u64(64, 42)?
+            .build()?;
+        assert_eq!(
+            ElectorRecoverStakeResponse::parse(&ok)?,
+            ElectorRecoverStakeResponse::Ok { query_id: 42 }
+        );
+        let error = CellBuilder::new()
+            .store_u32(32, 0xfffffffe)?
+            .store_u64(64, 42)?
+            .build()?;
+        let response = ElectorRecoverStakeResponse::parse(&error)?;
+        assert_eq!(
+            response,
+            ElectorRecoverStakeResponse::Error { query_id: 42 }
+        );
+        assert_eq!(response.build()?, error);
+        assert!(ElectorRecoverStakeResponse::parse(&request_cell).is_err());
+        Ok(())
+    }
+}
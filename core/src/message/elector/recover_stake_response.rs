@@ -0,0 +1,66 @@
+use super::{ELECTOR_RECOVER_STAKE_ERROR, ELECTOR_RECOVER_STAKE_OK};
+use crate::cell::{Cell, CellBuilder};
+use crate::message::{InvalidMessage, TonMessage, TonMessageError};
+
+/// Response of the elector to `ElectorRecoverStakeMessage`, according to TL-B schema:
+///
+/// ```raw
+/// recover_stake_ok#f96f7324
+///   query_id:uint64
+/// = InternalMsgBody;
+/// recover_stake_error#fffffffe
+///   query_id:uint64
+/// = InternalMsgBody;
+/// ```
+///
+/// The recovered stake is the value of `Ok` message, `Error` is returned as a bounce
+/// if there's nothing to recover.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ElectorRecoverStakeResponse {
+    Ok { query_id: u64 },
+    Error { query_id: u64 },
+}
+
+impl ElectorRecoverStakeResponse {
+    pub fn query_id(&self) -> u64 {
+        match self {
+            ElectorRecoverStakeResponse::Ok { query_id }
+            | ElectorRecoverStakeResponse::Error { query_id } => *query_id,
+        }
+    }
+}
+
+impl TonMessage for ElectorRecoverStakeResponse {
+    fn build(&self) -> Result<Cell, TonMessageError> {
+        let opcode = match self {
+            ElectorRecoverStakeResponse::Ok { .. } => ELECTOR_RECOVER_STAKE_OK,
+            ElectorRecoverStakeResponse::Error { .. } => ELECTOR_RECOVER_STAKE_ERROR,
+        };
+        let mut builder = CellBuilder::new();
+        builder.store_u32(32, opcode)?;
+        builder.store_u64(64, self.query_id())?;
+
+        Ok(builder.build()?)
+    }
+
+    fn parse(cell: &Cell) -> Result<Self, TonMessageError> {
+        let mut parser = cell.parser();
+
+        let opcode: u32 = parser.load_u32(32)?;
+        let query_id = parser.load_u64(64)?;
+        parser.ensure_empty()?;
+
+        match opcode {
+            ELECTOR_RECOVER_STAKE_OK => Ok(ElectorRecoverStakeResponse::Ok { query_id }),
+            ELECTOR_RECOVER_STAKE_ERROR => Ok(ElectorRecoverStakeResponse::Error { query_id }),
+            _ => Err(TonMessageError::InvalidMessage(InvalidMessage {
+                opcode: Some(opcode),
+                query_id: Some(query_id),
+                message: format!(
+                    "Unexpected opcode.  {:08x} or {:08x} expected",
+                    ELECTOR_RECOVER_STAKE_OK, ELECTOR_RECOVER_STAKE_ERROR
+                ),
+            })),
+        }
+    }
+}
@@ -12,6 +12,9 @@ pub enum TonMessageError {
     #[error("NaCl cryptographic error ({0})")]
     NaclCryptographicError(String),
 
+    #[error("Signer error ({0})")]
+    SignerError(String),
+
     #[error("TonCellError ({0})")]
     TonCellError(#[from] TonCellError),
 
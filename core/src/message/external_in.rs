@@ -0,0 +1,280 @@
+use std::sync::Arc;
+
+use num_bigint::BigUint;
+
+use crate::cell::{ArcCell, Cell, CellBuilder, CellParser, TonCellError};
+use crate::message::{TonMessage, TonMessageError};
+use crate::{TonAddress, TonHash};
+
+/// External address according to TL-B schema:
+///
+/// ```raw
+/// addr_extern$01 len:(## 9) external_address:(bits len) = MsgAddressExt;
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExternalAddress {
+    /// length of the address in bits.
+    pub bit_len: usize,
+    /// address bits, padded with zeros to the whole number of bytes.
+    pub data: Vec<u8>,
+}
+
+/// External incoming message (e.g. a message submitted to a wallet) according to TL-B schema:
+///
+/// ```raw
+/// ext_in_msg_info$10 src:MsgAddressExt dest:MsgAddressInt import_fee:Grams = CommonMsgInfo;
+/// message$_ {X:Type} info:CommonMsgInfo init:(Maybe (Either StateInit ^StateInit))
+///                    body:(Either X ^X) = Message X;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExternalInMessage {
+    /// external source address, `None` for `addr_none`.
+    pub src: Option<ExternalAddress>,
+    /// address of smart contract destination of message.
+    pub dest: TonAddress,
+    /// fee for executing and delivering of message.
+    pub import_fee: BigUint,
+    /// optional state init to deploy the destination contract.
+    pub state_init: Option<ArcCell>,
+    /// message body.
+    pub body: ArcCell,
+}
+
+impl ExternalInMessage {
+    pub fn new(dest: &TonAddress, body: &ArcCell) -> Self {
+        ExternalInMessage {
+            src: None,
+            dest: dest.clone(),
+            import_fee: BigUint::default(),
+            state_init: None,
+            body: body.clone(),
+        }
+    }
+
+    pub fn with_state_init(&mut self, state_init: &ArcCell) -> &mut Self {
+        self.state_init = Some(state_init.clone());
+        self
+    }
+
+    /// Returns the message with `src` set to `addr_none`, zero `import_fee` and no state init,
+    /// keeping only the fields the destination contract relies on.
+    pub fn normalized(&self) -> ExternalInMessage {
+        ExternalInMessage::new(&self.dest, &self.body)
+    }
+
+    /// Returns the hash of the normalized message, see `normalized`.
+    ///
+    /// Unlike the hash of the message cell, the normalized hash doesn't depend on the way
+    /// the message was serialized and on the fields a validator or a lite server may alter,
+    /// so it can be used to find the transaction that processed the message.
+    pub fn normalized_hash(&self) -> Result<TonHash, TonMessageError> {
+        Ok(self.normalized().build()?.cell_hash())
+    }
+}
+
+impl TonMessage for ExternalInMessage {
+    fn build(&self) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        builder.store_u8(2, 2)?; // ext_in_msg_info$10
+        match &self.src {
+            Some(src) => {
+                builder.store_u8(2, 1)?; // addr_extern$01
+                builder.store_u32(9, src.bit_len as u32)?;
+                builder.store_bits(src.bit_len, &src.data)?;
+            }
+            None => {
+                builder.store_u8(2, 0)?; // addr_none$00
+            }
+        }
+        builder.store_address(&self.dest)?;
+        builder.store_coins(&self.import_fee)?;
+        if let Some(state_init) = &self.state_init {
+            builder.store_bit(true)?; // state init present
+            builder.store_bit(true)?; // state init in ref
+            builder.store_reference(state_init)?;
+        } else {
+            builder.store_bit(false)?; // state init absent
+        }
+        builder.store_bit(true)?; // body in ref
+        builder.store_reference(&self.body)?;
+
+        Ok(builder.build()?)
+    }
+
+    fn parse(cell: &Cell) -> Result<Self, TonMessageError> {
+        let mut parser = cell.parser();
+
+        let tag = parser.load_u8(2)?;
+        if tag != 2 {
+            return Err(TonCellError::CellParserError(format!(
+                "Unexpected message info tag {:02b}, ext_in_msg_info$10 expected",
+                tag
+            ))
+            .into());
+        }
+        let src = load_external_address(&mut parser)?;
+        let dest = parser.load_address()?;
+        let import_fee = parser.load_coins()?;
+        let state_init = if parser.load_bit()? {
+            if parser.load_bit()? {
+                Some(parser.next_reference()?)
+            } else {
+                Some(Arc::new(load_inline_state_init(&mut parser)?))
+            }
+        } else {
+            None
+        };
+        let body = if parser.load_bit()? {
+            parser.next_reference()?
+        } else {
+            Arc::new(parser.load_remaining()?)
+        };
+
+        Ok(ExternalInMessage {
+            src,
+            dest,
+            import_fee,
+            state_init,
+            body,
+        })
+    }
+}
+
+fn load_external_address(parser: &mut CellParser) -> Result<Option<ExternalAddress>, TonCellError> {
+    let tp = parser.load_u8(2)?;
+    match tp {
+        0 => Ok(None),
+        1 => {
+            let bit_len = parser.load_u32(9)? as usize;
+            let data = parser.load_bits(bit_len)?;
+            Ok(Some(ExternalAddress { bit_len, data }))
+        }
+        _ => Err(TonCellError::InvalidAddressType(tp)),
+    }
+}
+
+/// Reads the fields of `StateInit` stored inline and builds a standalone cell out of them.
+fn load_inline_state_init(parser: &mut CellParser) -> Result<Cell, TonCellError> {
+    let mut builder = CellBuilder::new();
+    let split_depth = parser.load_bit()?;
+    builder.store_bit(split_depth)?;
+    if split_depth {
+        builder.store_u8(5, parser.load_u8(5)?)?;
+    }
+    let special = parser.load_bit()?;
+    builder.store_bit(special)?;
+    if special {
+        builder.store_u8(2, parser.load_u8(2)?)?;
+    }
+    let code = parser.load_maybe_cell_ref()?;
+    let data = parser.load_maybe_cell_ref()?;
+    let library = parser.load_maybe_cell_ref()?;
+    builder
+        .store_maybe_cell_ref(&code)?
+        .store_maybe_cell_ref(&data)?
+        .store_maybe_cell_ref(&library)?;
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{ExternalAddress, ExternalInMessage};
+    use crate::cell::{CellBuilder, StateInitBuilder};
+    use crate::message::{TonMessage, TonMessageError};
+    use crate::TonAddress;
+
+    #[test]
+    fn test_external_in_message_round_trip() -> Result<(), TonMessageError> {
+        let dest: TonAddress = "EQCDM_QGggZ3qMa_f3lRPk4_qLDnLTqdi6OkMAV2NB9r5TG3"
+            .parse()
+            .unwrap();
+        let body = Arc::new(CellBuilder::new().store_u32(32, 0xdeadbeef)?.build()?);
+        let code = Arc::new(CellBuilder::new().store_u8(8, 1)?.build()?);
+        let data = Arc::new(CellBuilder::new().store_u8(8, 2)?.build()?);
+        let state_init = Arc::new(StateInitBuilder::new(&code, &data).build()?);
+
+        let mut message = ExternalInMessage::new(&dest, &body);
+        message.with_state_init(&state_init);
+        let parsed = ExternalInMessage::parse(&message.build()?)?;
+        assert_eq!(parsed, message);
+
+        message.src = Some(ExternalAddress {
+            bit_len: 12,
+            data: vec![0xab, 0xc0],
+        });
+        message.state_init = None;
+        let parsed = ExternalInMessage::parse(&message.build()?)?;
+        assert_eq!(parsed, message);
+        Ok(())
+    }
+
+    #[test]
+    fn test_external_in_message_inline_parts() -> Result<(), TonMessageError> {
+        let dest: TonAddress = "EQCDM_QGggZ3qMa_f3lRPk4_qLDnLTqdi6OkMAV2NB9r5TG3"
+            .parse()
+            .unwrap();
+        let code = Arc::new(CellBuilder::new().store_u8(8, 1)?.build()?);
+        let data = Arc::new(CellBuilder::new().store_u8(8, 2)?.build()?);
+        let state_init = StateInitBuilder::new(&code, &data).build()?;
+
+        let cell = CellBuilder::new()
+            .store_u8(2, 2)?
+            .store_u8(2, 0)?
+            .store_address(&dest)?
+            .store_coins(&0u32.into())?
+            .store_bit(true)?
+            .store_bit(false)?
+            .store_cell(&state_init)?
+            .store_bit(false)?
+            .store_u32(32, 0xdeadbeef)?
+            .build()?;
+        let parsed = ExternalInMessage::parse(&cell)?;
+        assert_eq!(parsed.src, None);
+        assert_eq!(parsed.dest, dest);
+        assert_eq!(parsed.state_init.as_deref(), Some(&state_init));
+        assert_eq!(parsed.body.parser().load_u32(32)?, 0xdeadbeef);
+        Ok(())
+    }
+
+    #[test]
+    fn test_external_in_message_normalized_hash() -> Result<(), TonMessageError> {
+        let dest: TonAddress = "EQCDM_QGggZ3qMa_f3lRPk4_qLDnLTqdi6OkMAV2NB9r5TG3"
+            .parse()
+            .unwrap();
+        let body = Arc::new(CellBuilder::new().store_u32(32, 0xdeadbeef)?.build()?);
+        let code = Arc::new(CellBuilder::new().store_u8(8, 1)?.build()?);
+        let data = Arc::new(CellBuilder::new().store_u8(8, 2)?.build()?);
+        let state_init = Arc::new(StateInitBuilder::new(&code, &data).build()?);
+
+        let message = ExternalInMessage::new(&dest, &body);
+        let normalized_hash = message.normalized_hash()?;
+        assert_eq!(normalized_hash, message.build()?.cell_hash());
+
+        let mut altered = message.clone();
+        altered.with_state_init(&state_init);
+        altered.import_fee = 100u32.into();
+        altered.src = Some(ExternalAddress {
+            bit_len: 12,
+            data: vec![0xab, 0xc0],
+        });
+        assert_ne!(altered.build()?.cell_hash(), normalized_hash);
+        assert_eq!(altered.normalized_hash()?, normalized_hash);
+
+        // Inline body is moved to a reference
+        let inline = CellBuilder::new()
+            .store_u8(2, 2)?
+            .store_u8(2, 0)?
+            .store_address(&dest)?
+            .store_coins(&0u32.into())?
+            .store_bit(false)?
+            .store_bit(false)?
+            .store_u32(32, 0xdeadbeef)?
+            .build()?;
+        let parsed = ExternalInMessage::parse(&inline)?;
+        assert_ne!(inline.cell_hash(), normalized_hash);
+        assert_eq!(parsed.normalized_hash()?, normalized_hash);
+        Ok(())
+    }
+}
@@ -6,6 +6,7 @@
 // crc32('excesses query_id:uint64 = InternalMsgBody') = 0x553276db | 0x80000000 = 0xd53276db
 // crc32('burn query_id:uint64 amount:VarUInteger 16 response_destination:MsgAddress custom_payload:Maybe ^Cell = InternalMsgBody') = 0x595f07bc & 0x7fffffff = 0x595f07bc
 // crc32('internal_transfer query_id:uint64 amount:VarUInteger 16 from:MsgAddress response_address:MsgAddress forward_ton_amount:VarUInteger 16 forward_payload:Either Cell ^Cell = InternalMsgBody') = 0x978d4519 & 0x7fffffff = 0x178d4519
+// mint#642b7d07 query_id:uint64 to_address:MsgAddress ton_amount:VarUInteger 16 master_msg:^InternalMsgBody = InternalMsgBody (reference jetton minter, not part of TEP-74)
 // crc32('burn_notification query_id:uint64 amount:VarUInteger 16 sender:MsgAddress response_destination:MsgAddress = InternalMsgBody') = 0x7bdd97de & 0x7fffffff = 0x7bdd97de
 
 pub const JETTON_TRANSFER: u32 = 0x0f8a7ea5;
@@ -13,11 +14,20 @@ pub const JETTON_TRANSFER_NOTIFICATION: u32 = 0x7362d09c;
 pub const JETTON_INTERNAL_TRANSFER: u32 = 0x178d4519;
 pub const JETTON_BURN: u32 = 0x595f07bc;
 pub const JETTON_BURN_NOTIFICATION: u32 = 0x7bdd97de;
+pub const JETTON_MINT: u32 = 0x642b7d07;
 
 mod burn;
+mod burn_notification;
+mod internal_transfer;
+mod message;
+mod mint;
 mod transfer;
 mod transfer_notification;
 
 pub use burn::*;
+pub use burn_notification::*;
+pub use internal_transfer::*;
+pub use message::*;
+pub use mint::*;
 pub use transfer::*;
 pub use transfer_notification::*;
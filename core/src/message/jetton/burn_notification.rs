@@ -0,0 +1,121 @@
+use num_bigint::BigUint;
+
+use super::JETTON_BURN_NOTIFICATION;
+use crate::cell::{Cell, CellBuilder};
+use crate::message::{HasOpcode, TonMessage, TonMessageError};
+use crate::TonAddress;
+
+/// Creates a body for jetton burn notification (sent by a jetton wallet to the jetton master)
+/// according to TL-B schema:
+///
+/// ```raw
+/// burn_notification#7bdd97de query_id:uint64 amount:(VarUInteger 16)
+///                            sender:MsgAddress response_destination:MsgAddress
+///                            = InternalMsgBody;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct JettonBurnNotificationMessage {
+    /// should be equal with request's query_id.
+    pub query_id: u64,
+    /// amount of burned jettons.
+    pub amount: BigUint,
+    /// address of the owner of burned jettons.
+    pub sender: TonAddress,
+    /// address where to send a response with confirmation of a successful burn and the rest of the incoming message coins.
+    pub response_destination: TonAddress,
+}
+
+impl JettonBurnNotificationMessage {
+    pub fn new(sender: &TonAddress, amount: &BigUint) -> Self {
+        JettonBurnNotificationMessage {
+            query_id: 0,
+            amount: amount.clone(),
+            sender: sender.clone(),
+            response_destination: TonAddress::null(),
+        }
+    }
+
+    pub fn with_response_destination(&mut self, response_destination: &TonAddress) -> &mut Self {
+        self.response_destination = response_destination.clone();
+        self
+    }
+}
+
+impl TonMessage for JettonBurnNotificationMessage {
+    fn build(&self) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        builder.store_u32(32, Self::opcode())?;
+        builder.store_u64(64, self.query_id)?;
+        builder.store_coins(&self.amount)?;
+        builder.store_address(&self.sender)?;
+        builder.store_address(&self.response_destination)?;
+
+        Ok(builder.build()?)
+    }
+
+    fn parse(cell: &Cell) -> Result<Self, TonMessageError> {
+        let mut parser = cell.parser();
+
+        let opcode: u32 = parser.load_u32(32)?;
+        let query_id = parser.load_u64(64)?;
+
+        let amount = parser.load_coins()?;
+        let sender = parser.load_address()?;
+        let response_destination = parser.load_address()?;
+        parser.ensure_empty()?;
+
+        let result = JettonBurnNotificationMessage {
+            query_id,
+            amount,
+            sender,
+            response_destination,
+        };
+        result.verify_opcode(opcode)?;
+        Ok(result)
+    }
+}
+
+impl HasOpcode for JettonBurnNotificationMessage {
+    fn set_query_id(&mut self, query_id: u64) {
+        self.query_id = query_id;
+    }
+
+    fn query_id(&self) -> u64 {
+        self.query_id
+    }
+
+    fn opcode() -> u32 {
+        JETTON_BURN_NOTIFICATION
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use num_bigint::BigUint;
+
+    use crate::message::{
+        HasOpcode, JettonBurnMessage, JettonBurnNotificationMessage, TonMessage, TonMessageError,
+    };
+    use crate::TonAddress;
+
+    #[test]
+    fn test_jetton_burn_notification_round_trip() -> Result<(), TonMessageError> {
+        let sender =
+            TonAddress::from_str("EQB3ncyBUTjZUA5EnFKR5_EnOMI9V1tTEAAPaiU71gc4TiUt").unwrap();
+        let response_destination =
+            TonAddress::from_str("EQAd8QRKoA5sKcug9bwK6vMdmhSAoAxr8vvABvC1TCeTude5").unwrap();
+        let msg = JettonBurnNotificationMessage::new(&sender, &BigUint::from(528161u64))
+            .with_response_destination(&response_destination)
+            .with_query_id(667217747695)
+            .clone();
+
+        let cell = msg.build()?;
+        assert_eq!(JettonBurnNotificationMessage::parse(&cell)?, msg);
+
+        let burn = JettonBurnMessage::new(&BigUint::from(528161u64)).build()?;
+        assert!(JettonBurnNotificationMessage::parse(&burn).is_err());
+        Ok(())
+    }
+}
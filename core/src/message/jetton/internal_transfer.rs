@@ -0,0 +1,157 @@
+use num_bigint::BigUint;
+
+use super::JETTON_INTERNAL_TRANSFER;
+use crate::cell::{ArcCell, Cell, CellBuilder, EitherCellLayout, EMPTY_ARC_CELL};
+use crate::message::{HasOpcode, TonMessage, TonMessageError, WithForwardPayload, ZERO_COINS};
+use crate::TonAddress;
+
+/// Creates a body for jetton internal transfer (sent between jetton wallets
+/// or from jetton master to a jetton wallet on mint) according to TL-B schema:
+///
+/// ```raw
+/// internal_transfer#178d4519 query_id:uint64 amount:(VarUInteger 16) from:MsgAddress
+///                            response_address:MsgAddress
+///                            forward_ton_amount:(VarUInteger 16)
+///                            forward_payload:(Either Cell ^Cell)
+///                            = InternalMsgBody;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct JettonInternalTransferMessage {
+    /// arbitrary request number.
+    pub query_id: u64,
+    /// amount of transferred jettons in elementary units.
+    pub amount: BigUint,
+    /// address of the previous owner of the jettons, jetton master address on mint.
+    pub from: TonAddress,
+    /// address where to send a response with confirmation of a successful transfer and the rest of the incoming message Toncoins.
+    pub response_address: TonAddress,
+    ///  the amount of nanotons to be sent to the new owner.
+    pub forward_ton_amount: BigUint,
+    ///  optional custom data that should be sent to the new owner.
+    pub forward_payload: ArcCell,
+
+    pub forward_payload_layout: EitherCellLayout,
+}
+
+impl JettonInternalTransferMessage {
+    pub fn new(amount: &BigUint) -> Self {
+        JettonInternalTransferMessage {
+            query_id: 0,
+            amount: amount.clone(),
+            from: TonAddress::null(),
+            response_address: TonAddress::null(),
+            forward_ton_amount: ZERO_COINS.clone(),
+            forward_payload: EMPTY_ARC_CELL.clone(),
+            forward_payload_layout: EitherCellLayout::Native,
+        }
+    }
+
+    pub fn with_from(&mut self, from: &TonAddress) -> &mut Self {
+        self.from = from.clone();
+        self
+    }
+
+    pub fn with_response_address(&mut self, response_address: &TonAddress) -> &mut Self {
+        self.response_address = response_address.clone();
+        self
+    }
+
+    pub fn set_either_cell_layout(&mut self, layout: EitherCellLayout) -> &mut Self {
+        self.forward_payload_layout = layout;
+        self
+    }
+}
+
+impl WithForwardPayload for JettonInternalTransferMessage {
+    fn set_forward_payload(&mut self, forward_payload: ArcCell, forward_ton_amount: BigUint) {
+        self.forward_payload = forward_payload;
+        self.forward_ton_amount = forward_ton_amount;
+    }
+}
+
+impl TonMessage for JettonInternalTransferMessage {
+    fn build(&self) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        builder.store_u32(32, Self::opcode())?;
+        builder.store_u64(64, self.query_id)?;
+        builder.store_coins(&self.amount)?;
+        builder.store_address(&self.from)?;
+        builder.store_address(&self.response_address)?;
+        builder.store_coins(&self.forward_ton_amount)?;
+        builder
+            .store_either_cell_or_cell_ref(&self.forward_payload, self.forward_payload_layout)?;
+        Ok(builder.build()?)
+    }
+
+    fn parse(cell: &Cell) -> Result<Self, TonMessageError> {
+        let mut parser = cell.parser();
+
+        let opcode: u32 = parser.load_u32(32)?;
+        let query_id = parser.load_u64(64)?;
+
+        let amount = parser.load_coins()?;
+        let from = parser.load_address()?;
+        let response_address = parser.load_address()?;
+        let forward_ton_amount = parser.load_coins()?;
+        let forward_payload = parser.load_either_cell_or_cell_ref()?;
+        parser.ensure_empty()?;
+
+        let result = JettonInternalTransferMessage {
+            query_id,
+            amount,
+            from,
+            response_address,
+            forward_ton_amount,
+            forward_payload,
+            forward_payload_layout: EitherCellLayout::Native,
+        };
+        result.verify_opcode(opcode)?;
+
+        Ok(result)
+    }
+}
+
+impl HasOpcode for JettonInternalTransferMessage {
+    fn set_query_id(&mut self, query_id: u64) {
+        self.query_id = query_id;
+    }
+
+    fn query_id(&self) -> u64 {
+        self.query_id
+    }
+
+    fn opcode() -> u32 {
+        JETTON_INTERNAL_TRANSFER
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use num_bigint::BigUint;
+
+    use crate::cell::{CellBuilder, EitherCellLayout, TonCellError};
+    use crate::message::{JettonInternalTransferMessage, TonMessage, WithForwardPayload};
+
+    #[test]
+    fn test_jetton_internal_transfer_payload_in_ref() -> Result<(), TonCellError> {
+        let payload = Arc::new(
+            CellBuilder::new()
+                .store_u32(32, 0)?
+                .store_string("jetton payment")?
+                .build()?,
+        );
+        let msg = JettonInternalTransferMessage::new(&BigUint::from(5u32))
+            .with_forward_payload(BigUint::from(1u32), payload.clone())
+            .set_either_cell_layout(EitherCellLayout::ToRef)
+            .clone();
+        let cell = msg.build().unwrap();
+        assert_eq!(cell.references()[0], payload);
+
+        let parsed = JettonInternalTransferMessage::parse(&cell).unwrap();
+        assert_eq!(parsed.forward_payload, payload);
+        assert_eq!(parsed.forward_ton_amount, BigUint::from(1u32));
+        Ok(())
+    }
+}
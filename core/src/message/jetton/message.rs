@@ -0,0 +1,176 @@
+use super::{
+    JettonBurnMessage, JettonBurnNotificationMessage, JettonInternalTransferMessage,
+    JettonMintMessage, JettonTransferMessage, JettonTransferNotificationMessage, JETTON_BURN,
+    JETTON_BURN_NOTIFICATION, JETTON_INTERNAL_TRANSFER, JETTON_MINT, JETTON_TRANSFER,
+    JETTON_TRANSFER_NOTIFICATION,
+};
+use crate::cell::Cell;
+use crate::message::{
+    HasOpcode, InvalidMessage, NftExcessesMessage, TonMessage, TonMessageError, EXCESSES,
+};
+
+/// Any message body of the jetton standard, dispatched by its opcode,
+/// e.g. for decoding jetton activity of a transaction.
+#[derive(Clone, Debug, PartialEq)]
+pub enum JettonMessage {
+    Transfer(JettonTransferMessage),
+    TransferNotification(JettonTransferNotificationMessage),
+    InternalTransfer(JettonInternalTransferMessage),
+    Burn(JettonBurnMessage),
+    BurnNotification(JettonBurnNotificationMessage),
+    Mint(JettonMintMessage),
+    Excesses(NftExcessesMessage),
+}
+
+impl JettonMessage {
+    pub fn opcode(&self) -> u32 {
+        match self {
+            JettonMessage::Transfer(_) => JettonTransferMessage::opcode(),
+            JettonMessage::TransferNotification(_) => JettonTransferNotificationMessage::opcode(),
+            JettonMessage::InternalTransfer(_) => JettonInternalTransferMessage::opcode(),
+            JettonMessage::Burn(_) => JettonBurnMessage::opcode(),
+            JettonMessage::BurnNotification(_) => JettonBurnNotificationMessage::opcode(),
+            JettonMessage::Mint(_) => JettonMintMessage::opcode(),
+            JettonMessage::Excesses(_) => NftExcessesMessage::opcode(),
+        }
+    }
+
+    pub fn query_id(&self) -> u64 {
+        match self {
+            JettonMessage::Transfer(msg) => msg.query_id(),
+            JettonMessage::TransferNotification(msg) => msg.query_id(),
+            JettonMessage::InternalTransfer(msg) => msg.query_id(),
+            JettonMessage::Burn(msg) => msg.query_id(),
+            JettonMessage::BurnNotification(msg) => msg.query_id(),
+            JettonMessage::Mint(msg) => msg.query_id(),
+            JettonMessage::Excesses(msg) => msg.query_id(),
+        }
+    }
+}
+
+impl TonMessage for JettonMessage {
+    fn build(&self) -> Result<Cell, TonMessageError> {
+        match self {
+            JettonMessage::Transfer(msg) => msg.build(),
+            JettonMessage::TransferNotification(msg) => msg.build(),
+            JettonMessage::InternalTransfer(msg) => msg.build(),
+            JettonMessage::Burn(msg) => msg.build(),
+            JettonMessage::BurnNotification(msg) => msg.build(),
+            JettonMessage::Mint(msg) => msg.build(),
+            JettonMessage::Excesses(msg) => msg.build(),
+        }
+    }
+
+    fn parse(cell: &Cell) -> Result<Self, TonMessageError> {
+        let opcode: u32 = cell.parser().load_u32(32)?;
+        let result = match opcode {
+            JETTON_TRANSFER => JettonMessage::Transfer(JettonTransferMessage::parse(cell)?),
+            JETTON_TRANSFER_NOTIFICATION => {
+                JettonMessage::TransferNotification(JettonTransferNotificationMessage::parse(cell)?)
+            }
+            JETTON_INTERNAL_TRANSFER => {
+                JettonMessage::InternalTransfer(JettonInternalTransferMessage::parse(cell)?)
+            }
+            JETTON_BURN => JettonMessage::Burn(JettonBurnMessage::parse(cell)?),
+            JETTON_BURN_NOTIFICATION => {
+                JettonMessage::BurnNotification(JettonBurnNotificationMessage::parse(cell)?)
+            }
+            JETTON_MINT => JettonMessage::Mint(JettonMintMessage::parse(cell)?),
+            EXCESSES => JettonMessage::Excesses(NftExcessesMessage::parse(cell)?),
+            _ => {
+                return Err(TonMessageError::InvalidMessage(InvalidMessage {
+                    opcode: Some(opcode),
+                    query_id: None,
+                    message: "Unknown jetton message opcode".to_string(),
+                }))
+            }
+        };
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use num_bigint::BigUint;
+
+    use crate::cell::CellBuilder;
+    use crate::message::{
+        HasOpcode, JettonBurnMessage, JettonBurnNotificationMessage, JettonInternalTransferMessage,
+        JettonMessage, JettonTransferMessage, JettonTransferNotificationMessage,
+        NftExcessesMessage, TonMessage, TonMessageError, WithForwardPayload, JETTON_TRANSFER,
+    };
+    use crate::TonAddress;
+
+    #[test]
+    fn test_jetton_message_round_trip() -> Result<(), TonMessageError> {
+        let owner =
+            TonAddress::from_str("EQB3ncyBUTjZUA5EnFKR5_EnOMI9V1tTEAAPaiU71gc4TiUt").unwrap();
+        let wallet =
+            TonAddress::from_str("EQAd8QRKoA5sKcug9bwK6vMdmhSAoAxr8vvABvC1TCeTude5").unwrap();
+        let amount = BigUint::from(1_000_000u64);
+        let comment = Arc::new(
+            CellBuilder::new()
+                .store_u32(32, 0)?
+                .store_string("jetton payment")?
+                .build()?,
+        );
+        let custom_payload = Arc::new(CellBuilder::new().store_u8(8, 42)?.build()?);
+
+        let messages = [
+            JettonMessage::Transfer(
+                JettonTransferMessage::new(&owner, &amount)
+                    .with_query_id(1)
+                    .with_response_destination(&wallet)
+                    .with_custom_payload(custom_payload.clone())
+                    .with_forward_payload(BigUint::from(1u32), comment.clone())
+                    .clone(),
+            ),
+            JettonMessage::TransferNotification(
+                JettonTransferNotificationMessage::new(&owner, &amount)
+                    .with_query_id(2)
+                    .with_forward_payload(comment.clone())
+                    .clone(),
+            ),
+            JettonMessage::InternalTransfer(
+                JettonInternalTransferMessage::new(&amount)
+                    .with_query_id(3)
+                    .with_from(&owner)
+                    .with_response_address(&wallet)
+                    .with_forward_payload(BigUint::from(1u32), comment.clone())
+                    .clone(),
+            ),
+            JettonMessage::Burn(
+                JettonBurnMessage::new(&amount)
+                    .with_query_id(4)
+                    .with_response_destination(&owner)
+                    .with_custom_payload(custom_payload)
+                    .clone(),
+            ),
+            JettonMessage::BurnNotification(
+                JettonBurnNotificationMessage::new(&owner, &amount)
+                    .with_query_id(5)
+                    .clone(),
+            ),
+            JettonMessage::Excesses(NftExcessesMessage::new().with_query_id(6).clone()),
+        ];
+        for (idx, msg) in messages.iter().enumerate() {
+            let parsed = JettonMessage::parse(&msg.build()?)?;
+            assert_eq!(&parsed, msg);
+            assert_eq!(parsed.query_id(), idx as u64 + 1);
+        }
+        assert_eq!(messages[0].opcode(), JETTON_TRANSFER);
+
+        let unknown = CellBuilder::new()
+            .store_u32(32, 0x12345678)?
+            .store_u64(64, 0)?
+            .build()?;
+        assert!(matches!(
+            JettonMessage::parse(&unknown),
+            Err(TonMessageError::InvalidMessage(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+use num_bigint::BigUint;
+
+use super::{JettonInternalTransferMessage, JETTON_MINT};
+use crate::cell::{Cell, CellBuilder};
+use crate::message::{HasOpcode, TonMessage, TonMessageError};
+use crate::TonAddress;
+
+/// Creates a body for jetton mint, sent by the admin to the jetton master, according to TL-B schema:
+///
+/// ```raw
+/// mint#642b7d07 query_id:uint64 to_address:MsgAddress ton_amount:(VarUInteger 16)
+///               master_msg:^InternalMsgBody
+///               = InternalMsgBody;
+/// ```
+///
+/// `master_msg` is the `internal_transfer` the master sends to the jetton wallet of `to_address`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JettonMintMessage {
+    /// arbitrary request number.
+    pub query_id: u64,
+    /// owner of the minted jettons.
+    pub to_address: TonAddress,
+    /// amount of nanotons attached to the message sent to the jetton wallet.
+    pub ton_amount: BigUint,
+    /// internal transfer forwarded to the jetton wallet.
+    pub master_msg: JettonInternalTransferMessage,
+}
+
+impl JettonMintMessage {
+    /// Creates a mint of `jetton_amount` jettons to `to_address`.
+    pub fn new(to_address: &TonAddress, ton_amount: &BigUint, jetton_amount: &BigUint) -> Self {
+        JettonMintMessage {
+            query_id: 0,
+            to_address: to_address.clone(),
+            ton_amount: ton_amount.clone(),
+            master_msg: JettonInternalTransferMessage::new(jetton_amount),
+        }
+    }
+
+    pub fn with_master_msg(&mut self, master_msg: &JettonInternalTransferMessage) -> &mut Self {
+        self.master_msg = master_msg.clone();
+        self
+    }
+}
+
+impl TonMessage for JettonMintMessage {
+    fn build(&self) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        builder.store_u32(32, Self::opcode())?;
+        builder.store_u64(64, self.query_id)?;
+        builder.store_address(&self.to_address)?;
+        builder.store_coins(&self.ton_amount)?;
+        builder.store_child(self.master_msg.build()?)?;
+        Ok(builder.build()?)
+    }
+
+    fn parse(cell: &Cell) -> Result<Self, TonMessageError> {
+        let mut parser = cell.parser();
+
+        let opcode: u32 = parser.load_u32(32)?;
+        let query_id = parser.load_u64(64)?;
+
+        let to_address = parser.load_address()?;
+        let ton_amount = parser.load_coins()?;
+        let master_msg = parser.next_reference()?;
+        parser.ensure_empty()?;
+        let master_msg = JettonInternalTransferMessage::parse(&master_msg)?;
+
+        let result = JettonMintMessage {
+            query_id,
+            to_address,
+            ton_amount,
+            master_msg,
+        };
+        result.verify_opcode(opcode)?;
+
+        Ok(result)
+    }
+}
+
+impl HasOpcode for JettonMintMessage {
+    fn set_query_id(&mut self, query_id: u64) {
+        self.query_id = query_id;
+    }
+
+    fn query_id(&self) -> u64 {
+        self.query_id
+    }
+
+    fn opcode() -> u32 {
+        JETTON_MINT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use num_bigint::BigUint;
+
+    use crate::cell::CellBuilder;
+    use crate::message::{
+        HasOpcode, JettonInternalTransferMessage, JettonMintMessage, TonMessage, TonMessageError,
+        WithForwardPayload, JETTON_INTERNAL_TRANSFER,
+    };
+    use crate::TonAddress;
+
+    #[test]
+    fn test_jetton_mint_round_trip() -> Result<(), TonMessageError> {
+        let master =
+            TonAddress::from_str("EQBYE3OMjPlkHPsc-Dxs9zXk66yXXvKr9vgbMIoOPi-XUa-f").unwrap();
+        let owner =
+            TonAddress::from_str("EQB3ncyBUTjZUA5EnFKR5_EnOMI9V1tTEAAPaiU71gc4TiUt").unwrap();
+        let forward_payload = Arc::new(CellBuilder::new().store_byte(123)?.build()?);
+
+        let mut master_msg = JettonInternalTransferMessage::new(&BigUint::from(1_000_000u32));
+        master_msg
+            .with_query_id(7)
+            .with_from(&master)
+            .with_response_address(&owner)
+            .with_forward_payload(BigUint::from(1u32), forward_payload);
+        let mut mint = JettonMintMessage::new(
+            &owner,
+            &BigUint::from(50_000_000u32),
+            &BigUint::from(1_000_000u32),
+        );
+        mint.with_query_id(7).with_master_msg(&master_msg);
+
+        let cell = mint.build()?;
+        assert_eq!(cell.references().len(), 1);
+        let nested = cell.reference(0)?.parser().load_u32(32)?;
+        assert_eq!(nested, JETTON_INTERNAL_TRANSFER);
+
+        let parsed = JettonMintMessage::parse(&cell)?;
+        assert_eq!(parsed, mint);
+        assert_eq!(
+            JettonInternalTransferMessage::parse(&master_msg.build()?)?,
+            master_msg
+        );
+        assert!(JettonMintMessage::parse(&master_msg.build()?).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+// Constants from multisig v2 contract
+// https://github.com/ton-blockchain/multisig-contract-v2
+
+/// new_order#f718510f
+///   query_id:uint64
+///   order_seqno:uint256
+///   signer:(## 1)
+///   index:uint8
+///   expiration_date:uint48
+///   order:^Order
+/// = InternalMsgBody;
+pub const MULTISIG_NEW_ORDER: u32 = 0xf718510f;
+
+/// approve#a762230f
+///   query_id:uint64
+///   signer_index:uint8
+/// = InternalMsgBody;
+pub const MULTISIG_APPROVE: u32 = 0xa762230f;
+
+/// send_message#f1381e5b
+///   mode:uint8
+///   message:^Cell
+/// = Action;
+pub const MULTISIG_SEND_MESSAGE_ACTION: u32 = 0xf1381e5b;
+
+/// update_multisig_params#1d0cfbd3
+///   threshold:uint8
+///   signers:^(Hashmap 8 MsgAddressInt)
+///   proposers:(HashmapE 8 MsgAddressInt)
+/// = Action;
+pub const MULTISIG_UPDATE_PARAMS_ACTION: u32 = 0x1d0cfbd3;
+
+mod action;
+mod approve;
+mod new_order;
+
+pub use action::*;
+pub use approve::*;
+pub use new_order::*;
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::{MULTISIG_SEND_MESSAGE_ACTION, MULTISIG_UPDATE_PARAMS_ACTION};
+use crate::cell::dict::predefined_readers::{
+    key_reader_u8, val_reader_address, val_reader_ref_cell,
+};
+use crate::cell::dict::predefined_writers::{val_writer_address, val_writer_ref_cell};
+use crate::cell::{ArcCell, Cell, CellBuilder, TonCellError};
+use crate::message::{InvalidMessage, TonMessage, TonMessageError};
+use crate::TonAddress;
+
+/// Maximal number of actions in a single multisig order.
+pub const MULTISIG_MAX_ACTIONS: usize = 255;
+
+/// Action of a multisig order according to TL-B schema:
+///
+/// ```raw
+/// send_message#f1381e5b mode:uint8 message:^Cell = Action;
+/// update_multisig_params#1d0cfbd3 threshold:uint8 signers:^(Hashmap 8 MsgAddressInt)
+///                                 proposers:(HashmapE 8 MsgAddressInt) = Action;
+/// _ (Hashmap 8 ^Action) = Order;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum MultisigAction {
+    /// Sends `message` (a full `MessageRelaxed` cell) from the multisig with send `mode`.
+    SendMessage { mode: u8, message: ArcCell },
+    /// Replaces threshold, signers and proposers of the multisig.
+    UpdateParams {
+        threshold: u8,
+        signers: Vec<TonAddress>,
+        proposers: Vec<TonAddress>,
+    },
+}
+
+impl MultisigAction {
+    /// Builds an `Order` cell out of `actions`, keyed by their position.
+    pub fn build_order(actions: &[MultisigAction]) -> Result<Cell, TonMessageError> {
+        if actions.is_empty() || actions.len() > MULTISIG_MAX_ACTIONS {
+            return Err(TonMessageError::InvalidMessage(InvalidMessage {
+                opcode: None,
+                query_id: None,
+                message: format!(
+                    "Order must contain from 1 to {} actions, got {}",
+                    MULTISIG_MAX_ACTIONS,
+                    actions.len()
+                ),
+            }));
+        }
+        let mut dict = HashMap::new();
+        for (index, action) in actions.iter().enumerate() {
+            dict.insert(index as u8, Arc::new(action.build()?));
+        }
+        let mut builder = CellBuilder::new();
+        builder.store_dict(8, val_writer_ref_cell, dict)?;
+        Ok(builder.build()?)
+    }
+
+    /// Parses an `Order` cell into the list of actions, ordered by their keys.
+    pub fn parse_order(order: &Cell) -> Result<Vec<MultisigAction>, TonMessageError> {
+        let mut entries: Vec<_> = order
+            .parser()
+            .load_dict(8, key_reader_u8, val_reader_ref_cell)?
+            .into_iter()
+            .collect();
+        entries.sort_by_key(|(index, _)| *index);
+        entries
+            .iter()
+            .map(|(_, action)| MultisigAction::parse(action))
+            .collect()
+    }
+}
+
+impl TonMessage for MultisigAction {
+    fn build(&self) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        match self {
+            MultisigAction::SendMessage { mode, message } => {
+                builder.store_u32(32, MULTISIG_SEND_MESSAGE_ACTION)?;
+                builder.store_u8(8, *mode)?;
+                builder.store_reference(message)?;
+            }
+            MultisigAction::UpdateParams {
+                threshold,
+                signers,
+                proposers,
+            } => {
+                builder.store_u32(32, MULTISIG_UPDATE_PARAMS_ACTION)?;
+                builder.store_u8(8, *threshold)?;
+                builder.store_child(build_address_dict(signers)?)?;
+                if proposers.is_empty() {
+                    builder.store_bit(false)?;
+                } else {
+                    builder.store_bit(true)?;
+                    builder.store_child(build_address_dict(proposers)?)?;
+                }
+            }
+        }
+        Ok(builder.build()?)
+    }
+
+    fn parse(cell: &Cell) -> Result<Self, TonMessageError> {
+        let mut parser = cell.parser();
+
+        let opcode: u32 = parser.load_u32(32)?;
+        let action = match opcode {
+            MULTISIG_SEND_MESSAGE_ACTION => {
+                let mode = parser.load_u8(8)?;
+                let message = parser.next_reference()?;
+                MultisigAction::SendMessage { mode, message }
+            }
+            MULTISIG_UPDATE_PARAMS_ACTION => {
+                let threshold = parser.load_u8(8)?;
+                let signers = parser.next_reference()?;
+                let signers = parse_address_dict(&signers)?;
+                let proposers = match parser.load_maybe_cell_ref()? {
+                    Some(proposers) => parse_address_dict(&proposers)?,
+                    None => Vec::new(),
+                };
+                MultisigAction::UpdateParams {
+                    threshold,
+                    signers,
+                    proposers,
+                }
+            }
+            opcode => {
+                return Err(TonMessageError::InvalidMessage(InvalidMessage {
+                    opcode: Some(opcode),
+                    query_id: None,
+                    message: "Unknown multisig action".to_string(),
+                }))
+            }
+        };
+        parser.ensure_empty()?;
+        Ok(action)
+    }
+}
+
+/// Builds a `Hashmap 8 MsgAddressInt` cell keyed by the position of the address.
+fn build_address_dict(addresses: &[TonAddress]) -> Result<Cell, TonCellError> {
+    let dict: HashMap<u8, TonAddress> = addresses
+        .iter()
+        .enumerate()
+        .map(|(index, address)| (index as u8, address.clone()))
+        .collect();
+    let mut builder = CellBuilder::new();
+    builder.store_dict(8, val_writer_address, dict)?;
+    builder.build()
+}
+
+/// Parses a `Hashmap 8 MsgAddressInt` cell into addresses ordered by their keys.
+fn parse_address_dict(dict: &Cell) -> Result<Vec<TonAddress>, TonCellError> {
+    let mut entries: Vec<_> = dict
+        .parser()
+        .load_dict(8, key_reader_u8, val_reader_address)?
+        .into_iter()
+        .collect();
+    entries.sort_by_key(|(index, _)| *index);
+    Ok(entries.into_iter().map(|(_, address)| address).collect())
+}
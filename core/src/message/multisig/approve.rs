@@ -0,0 +1,70 @@
+use super::MULTISIG_APPROVE;
+use crate::cell::{Cell, CellBuilder};
+use crate::message::{HasOpcode, TonMessage, TonMessageError};
+
+/// Creates a body for approval of a multisig order, sent by a signer to the order contract,
+/// according to TL-B schema:
+///
+/// ```raw
+/// approve#a762230f
+///   query_id:uint64
+///   signer_index:uint8
+/// = InternalMsgBody;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultisigApproveMessage {
+    /// arbitrary request number.
+    pub query_id: u64,
+    /// index of the approving signer in the signer list of the multisig.
+    pub signer_index: u8,
+}
+
+impl MultisigApproveMessage {
+    pub fn new(signer_index: u8) -> Self {
+        MultisigApproveMessage {
+            query_id: 0,
+            signer_index,
+        }
+    }
+}
+
+impl TonMessage for MultisigApproveMessage {
+    fn build(&self) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        builder.store_u32(32, Self::opcode())?;
+        builder.store_u64(64, self.query_id)?;
+        builder.store_u8(8, self.signer_index)?;
+
+        Ok(builder.build()?)
+    }
+
+    fn parse(cell: &Cell) -> Result<Self, TonMessageError> {
+        let mut parser = cell.parser();
+
+        let opcode: u32 = parser.load_u32(32)?;
+        let query_id = parser.load_u64(64)?;
+        let signer_index = parser.load_u8(8)?;
+        parser.ensure_empty()?;
+
+        let result = MultisigApproveMessage {
+            query_id,
+            signer_index,
+        };
+        result.verify_opcode(opcode)?;
+        Ok(result)
+    }
+}
+
+impl HasOpcode for MultisigApproveMessage {
+    fn set_query_id(&mut self, query_id: u64) {
+        self.query_id = query_id;
+    }
+
+    fn query_id(&self) -> u64 {
+        self.query_id
+    }
+
+    fn opcode() -> u32 {
+        MULTISIG_APPROVE
+    }
+}
@@ -0,0 +1,161 @@
+use num_bigint::BigUint;
+
+use super::{MultisigAction, MULTISIG_NEW_ORDER};
+use crate::cell::{Cell, CellBuilder};
+use crate::message::{HasOpcode, TonMessage, TonMessageError};
+
+/// Creates a body for a new multisig order, sent by a signer or a proposer to the multisig,
+/// according to TL-B schema:
+///
+/// ```raw
+/// new_order#f718510f
+///   query_id:uint64
+///   order_seqno:uint256
+///   signer:(## 1)
+///   index:uint8
+///   expiration_date:uint48
+///   order:^Order
+/// = InternalMsgBody;
+/// ```
+///
+/// An order created by a signer is approved by that signer on creation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultisigNewOrderMessage {
+    /// arbitrary request number.
+    pub query_id: u64,
+    /// seqno of the order, `next_order_seqno` of the multisig unless arbitrary seqno is allowed.
+    pub order_seqno: BigUint,
+    /// whether the sender is a signer (`true`) or a proposer (`false`).
+    pub signer: bool,
+    /// index of the sender in the signer or proposer list.
+    pub index: u8,
+    /// unix time after which the order can't be approved anymore.
+    pub expiration_date: u64,
+    /// actions executed once the order is approved.
+    pub actions: Vec<MultisigAction>,
+}
+
+impl MultisigNewOrderMessage {
+    pub fn new(order_seqno: &BigUint, signer_index: u8, expiration_date: u64) -> Self {
+        MultisigNewOrderMessage {
+            query_id: 0,
+            order_seqno: order_seqno.clone(),
+            signer: true,
+            index: signer_index,
+            expiration_date,
+            actions: Vec::new(),
+        }
+    }
+
+    /// Marks the sender as the proposer with index `proposer_index`.
+    pub fn with_proposer(&mut self, proposer_index: u8) -> &mut Self {
+        self.signer = false;
+        self.index = proposer_index;
+        self
+    }
+
+    pub fn with_action(&mut self, action: MultisigAction) -> &mut Self {
+        self.actions.push(action);
+        self
+    }
+}
+
+impl TonMessage for MultisigNewOrderMessage {
+    fn build(&self) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        builder.store_u32(32, Self::opcode())?;
+        builder.store_u64(64, self.query_id)?;
+        builder.store_uint(256, &self.order_seqno)?;
+        builder.store_bit(self.signer)?;
+        builder.store_u8(8, self.index)?;
+        builder.store_u64(48, self.expiration_date)?;
+        builder.store_child(MultisigAction::build_order(&self.actions)?)?;
+
+        Ok(builder.build()?)
+    }
+
+    fn parse(cell: &Cell) -> Result<Self, TonMessageError> {
+        let mut parser = cell.parser();
+
+        let opcode: u32 = parser.load_u32(32)?;
+        let query_id = parser.load_u64(64)?;
+        let order_seqno = parser.load_uint(256)?;
+        let signer = parser.load_bit()?;
+        let index = parser.load_u8(8)?;
+        let expiration_date = parser.load_u64(48)?;
+        let order = parser.next_reference()?;
+        parser.ensure_empty()?;
+
+        let result = MultisigNewOrderMessage {
+            query_id,
+            order_seqno,
+            signer,
+            index,
+            expiration_date,
+            actions: MultisigAction::parse_order(&order)?,
+        };
+        result.verify_opcode(opcode)?;
+        Ok(result)
+    }
+}
+
+impl HasOpcode for MultisigNewOrderMessage {
+    fn set_query_id(&mut self, query_id: u64) {
+        self.query_id = query_id;
+    }
+
+    fn query_id(&self) -> u64 {
+        self.query_id
+    }
+
+    fn opcode() -> u32 {
+        MULTISIG_NEW_ORDER
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use num_bigint::BigUint;
+
+    use crate::cell::CellBuilder;
+    use crate::message::{
+        HasOpcode, MultisigAction, MultisigApproveMessage, MultisigNewOrderMessage, TonMessage,
+        TonMessageError,
+    };
+    use crate::TonAddress;
+
+    #[test]
+    fn test_multisig_new_order_round_trip() -> Result<(), TonMessageError> {
+        let signer_1 =
+            TonAddress::from_str("EQBYE3OMjPlkHPsc-Dxs9zXk66yXXvKr9vgbMIoOPi-XUa-f").unwrap();
+        let signer_2 =
+            TonAddress::from_str("EQB3ncyBUTjZUA5EnFKR5_EnOMI9V1tTEAAPaiU71gc4TiUt").unwrap();
+        let message = Arc::new(CellBuilder::new().store_u32(32, 0xdeadbeef)?.build()?);
+
+        let mut new_order = MultisigNewOrderMessage::new(&BigUint::from(3u32), 1, 1_700_000_000);
+        new_order
+            .with_query_id(42)
+            .with_action(MultisigAction::SendMessage { mode: 3, message })
+            .with_action(MultisigAction::UpdateParams {
+                threshold: 2,
+                signers: vec![signer_1.clone(), signer_2],
+                proposers: vec![signer_1],
+            });
+        let parsed = MultisigNewOrderMessage::parse(&new_order.build()?)?;
+        assert_eq!(parsed, new_order);
+
+        new_order.with_proposer(0).actions.truncate(1);
+        let parsed = MultisigNewOrderMessage::parse(&new_order.build()?)?;
+        assert_eq!(parsed, new_order);
+
+        new_order.actions.clear();
+        assert!(new_order.build().is_err());
+
+        let approve = MultisigApproveMessage::new(1).with_query_id(7).clone();
+        assert_eq!(MultisigApproveMessage::parse(&approve.build()?)?, approve);
+        Ok(())
+    }
+}
@@ -30,12 +30,32 @@ pub const NFT_GET_STATIC_DATA: u32 = 0x2fcb26a2;
 /// = InternalMsgBody
 pub const NFT_REPORT_STATIC_DATA: u32 = 0x8b771735;
 
+/// Constants from nft royalty standard
+/// https://github.com/ton-blockchain/TEPs/blob/master/text/0066-nft-royalty-standard.md
+///
+/// get_royalty_params#693d3950
+///   query_id:uint64
+/// = InternalMsgBody;
+pub const NFT_GET_ROYALTY_PARAMS: u32 = 0x693d3950;
+
+/// report_royalty_params#a8cb00ad
+///   query_id:uint64
+///   numerator:uint16
+///   denominator:uint16
+///   destination:MsgAddress
+/// = InternalMsgBody;
+pub const NFT_REPORT_ROYALTY_PARAMS: u32 = 0xa8cb00ad;
+
+mod get_royalty_params;
 mod get_static_data;
 mod ownership_assigned;
+mod report_royalty_params;
 mod report_static_data;
 mod transfer;
 
+pub use get_royalty_params::*;
 pub use get_static_data::*;
 pub use ownership_assigned::*;
+pub use report_royalty_params::*;
 pub use report_static_data::*;
 pub use transfer::*;
@@ -0,0 +1,59 @@
+use super::NFT_GET_ROYALTY_PARAMS;
+use crate::cell::{Cell, CellBuilder};
+use crate::message::{HasOpcode, TonMessage, TonMessageError};
+
+/// Creates a body for nft collection get_royalty_params according to TL-B schema:
+///
+/// ```raw
+/// get_royalty_params#693d3950
+///   query_id:uint64
+/// = InternalMsgBody;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct NftGetRoyaltyParamsMessage {
+    /// arbitrary request number.
+    pub query_id: u64,
+}
+
+#[allow(clippy::new_without_default)]
+impl NftGetRoyaltyParamsMessage {
+    pub fn new() -> Self {
+        NftGetRoyaltyParamsMessage { query_id: 0 }
+    }
+}
+
+impl TonMessage for NftGetRoyaltyParamsMessage {
+    fn build(&self) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        builder.store_u32(32, Self::opcode())?;
+        builder.store_u64(64, self.query_id)?;
+
+        Ok(builder.build()?)
+    }
+
+    fn parse(cell: &Cell) -> Result<Self, TonMessageError> {
+        let mut parser = cell.parser();
+
+        let opcode: u32 = parser.load_u32(32)?;
+        let query_id = parser.load_u64(64)?;
+        parser.ensure_empty()?;
+
+        let result = NftGetRoyaltyParamsMessage { query_id };
+        result.verify_opcode(opcode)?;
+        Ok(result)
+    }
+}
+
+impl HasOpcode for NftGetRoyaltyParamsMessage {
+    fn set_query_id(&mut self, query_id: u64) {
+        self.query_id = query_id;
+    }
+
+    fn query_id(&self) -> u64 {
+        self.query_id
+    }
+
+    fn opcode() -> u32 {
+        NFT_GET_ROYALTY_PARAMS
+    }
+}
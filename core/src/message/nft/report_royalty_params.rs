@@ -0,0 +1,114 @@
+use super::NFT_REPORT_ROYALTY_PARAMS;
+use crate::cell::{Cell, CellBuilder};
+use crate::message::{HasOpcode, TonMessage, TonMessageError};
+use crate::TonAddress;
+
+/// Creates a body for nft collection report_royalty_params according to TL-B schema:
+///
+/// ```raw
+/// report_royalty_params#a8cb00ad
+///   query_id:uint64
+///   numerator:uint16
+///   denominator:uint16
+///   destination:MsgAddress
+/// = InternalMsgBody;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct NftReportRoyaltyParamsMessage {
+    /// should be equal with request's query_id.
+    pub query_id: u64,
+    /// royalty share is `numerator / denominator` of the sale price.
+    pub numerator: u16,
+    pub denominator: u16,
+    /// address to send royalty to.
+    pub destination: TonAddress,
+}
+
+impl NftReportRoyaltyParamsMessage {
+    pub fn new(numerator: u16, denominator: u16, destination: &TonAddress) -> Self {
+        NftReportRoyaltyParamsMessage {
+            query_id: 0,
+            numerator,
+            denominator,
+            destination: destination.clone(),
+        }
+    }
+}
+
+impl TonMessage for NftReportRoyaltyParamsMessage {
+    fn build(&self) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        builder.store_u32(32, Self::opcode())?;
+        builder.store_u64(64, self.query_id)?;
+
+        builder.store_u32(16, self.numerator as u32)?;
+        builder.store_u32(16, self.denominator as u32)?;
+        builder.store_address(&self.destination)?;
+
+        Ok(builder.build()?)
+    }
+
+    fn parse(cell: &Cell) -> Result<Self, TonMessageError> {
+        let mut parser = cell.parser();
+
+        let opcode = parser.load_u32(32)?;
+        let query_id = parser.load_u64(64)?;
+
+        let numerator = parser.load_u16(16)?;
+        let denominator = parser.load_u16(16)?;
+        let destination = parser.load_address()?;
+        parser.ensure_empty()?;
+
+        let result = NftReportRoyaltyParamsMessage {
+            query_id,
+            numerator,
+            denominator,
+            destination,
+        };
+        result.verify_opcode(opcode)?;
+        Ok(result)
+    }
+}
+
+impl HasOpcode for NftReportRoyaltyParamsMessage {
+    fn set_query_id(&mut self, query_id: u64) {
+        self.query_id = query_id;
+    }
+
+    fn query_id(&self) -> u64 {
+        self.query_id
+    }
+
+    fn opcode() -> u32 {
+        NFT_REPORT_ROYALTY_PARAMS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::message::{
+        HasOpcode, NftGetRoyaltyParamsMessage, NftReportRoyaltyParamsMessage, TonMessage,
+        TonMessageError,
+    };
+    use crate::TonAddress;
+
+    #[test]
+    fn test_nft_royalty_params_round_trip() -> Result<(), TonMessageError> {
+        let request = NftGetRoyaltyParamsMessage::new().with_query_id(42).clone();
+        let request_cell = request.build()?;
+        assert_eq!(request_cell.bit_len(), 96);
+        assert_eq!(NftGetRoyaltyParamsMessage::parse(&request_cell)?, request);
+
+        let destination =
+            TonAddress::from_str("EQB3ncyBUTjZUA5EnFKR5_EnOMI9V1tTEAAPaiU71gc4TiUt").unwrap();
+        let report = NftReportRoyaltyParamsMessage::new(5, 100, &destination)
+            .with_query_id(request.query_id)
+            .clone();
+        let report_cell = report.build()?;
+        assert_eq!(NftReportRoyaltyParamsMessage::parse(&report_cell)?, report);
+        assert!(NftReportRoyaltyParamsMessage::parse(&request_cell).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,307 @@
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+
+use crate::cell::Cell;
+use crate::message::{
+    BouncedMessage, EncryptedComment, HasOpcode, JettonBurnMessage, JettonBurnNotificationMessage,
+    JettonInternalTransferMessage, JettonMessage, JettonMintMessage, JettonTransferMessage,
+    JettonTransferNotificationMessage, NftExcessesMessage, NftGetRoyaltyParamsMessage,
+    NftGetStaticDataMessage, NftOwnershipAssignedMessage, NftReportRoyaltyParamsMessage,
+    NftReportStaticDataMessage, NftTransferMessage, TextComment, TonMessage, TonMessageError,
+    BOUNCED_MESSAGE_PREFIX, ENCRYPTED_COMMENT, TEXT_COMMENT,
+};
+
+#[cfg(feature = "defi")]
+mod dedust;
+#[cfg(feature = "defi")]
+mod stonfi;
+#[cfg(feature = "defi")]
+pub use dedust::*;
+#[cfg(feature = "defi")]
+pub use stonfi::*;
+
+/// Message body decoded by `OpRegistry`.
+///
+/// Non-exhaustive, since the variants of the DeFi protocols exist only with the `defi`
+/// feature.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum MessageBody {
+    /// Body without data and references, e.g. of a plain transfer of TON.
+    Empty,
+    TextComment(String),
+    /// Encrypted comment, see `EncryptedComment::decrypt`.
+    EncryptedComment(EncryptedComment),
+    Jetton(JettonMessage),
+    NftTransfer(NftTransferMessage),
+    NftOwnershipAssigned(NftOwnershipAssignedMessage),
+    NftGetStaticData(NftGetStaticDataMessage),
+    NftReportStaticData(NftReportStaticDataMessage),
+    NftGetRoyaltyParams(NftGetRoyaltyParamsMessage),
+    NftReportRoyaltyParams(NftReportRoyaltyParamsMessage),
+    Bounced(BouncedMessage),
+    #[cfg(feature = "defi")]
+    StonfiSwap(StonfiSwapPayload),
+    #[cfg(feature = "defi")]
+    StonfiProvideLp(StonfiProvideLpPayload),
+    #[cfg(feature = "defi")]
+    DedustSwap(DedustSwapMessage),
+    #[cfg(feature = "defi")]
+    DedustJettonSwap(DedustJettonSwapPayload),
+    /// Body decoded by a decoder registered with `OpRegistry::register`.
+    Custom(CustomMessageBody),
+    /// Body with an op code without registered decoder, `opcode` is `None` for bodies
+    /// shorter than 32 bits.
+    Unknown {
+        opcode: Option<u32>,
+        body: Cell,
+    },
+}
+
+/// Value returned by a custom decoder, see `OpRegistry::register`.
+#[derive(Clone)]
+pub struct CustomMessageBody {
+    pub opcode: u32,
+    pub value: Arc<dyn Any + Send + Sync>,
+}
+
+impl CustomMessageBody {
+    pub fn new<T: Any + Send + Sync>(opcode: u32, value: T) -> Self {
+        CustomMessageBody {
+            opcode,
+            value: Arc::new(value),
+        }
+    }
+
+    pub fn downcast_ref<T: Any>(&self) -> Option<&T> {
+        self.value.downcast_ref()
+    }
+}
+
+impl fmt::Debug for CustomMessageBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomMessageBody")
+            .field("opcode", &format_args!("{:08x}", self.opcode))
+            .finish_non_exhaustive()
+    }
+}
+
+/// Custom bodies are equal only if they share the decoded value.
+impl PartialEq for CustomMessageBody {
+    fn eq(&self, other: &Self) -> bool {
+        self.opcode == other.opcode && Arc::ptr_eq(&self.value, &other.value)
+    }
+}
+
+/// Decodes a message body starting with the op code it's registered for.
+pub type OpDecoder = fn(&Cell) -> Result<MessageBody, TonMessageError>;
+
+/// Registry of message body decoders by op code.
+///
+/// The default registry knows comments, bounced messages, jetton and NFT ops and, with
+/// the `defi` feature, ops of STON.fi and DeDust. Decoders of other ops are added with
+/// `register`, e.g. returning `MessageBody::Custom`.
+#[derive(Clone)]
+pub struct OpRegistry {
+    decoders: HashMap<u32, OpDecoder>,
+}
+
+impl OpRegistry {
+    /// Creates a registry without any decoders.
+    pub fn empty() -> Self {
+        OpRegistry {
+            decoders: HashMap::new(),
+        }
+    }
+
+    /// Registers `decoder` for `opcode`, replacing the previous decoder if any.
+    pub fn register(&mut self, opcode: u32, decoder: OpDecoder) -> &mut Self {
+        self.decoders.insert(opcode, decoder);
+        self
+    }
+
+    pub fn is_registered(&self, opcode: u32) -> bool {
+        self.decoders.contains_key(&opcode)
+    }
+
+    /// Decodes `body` with the decoder of its op code.
+    ///
+    /// Bodies with an unknown op code are returned as `MessageBody::Unknown`, an error means
+    /// the body has a known op code but doesn't match its schema.
+    pub fn decode(&self, body: &Cell) -> Result<MessageBody, TonMessageError> {
+        if body.bit_len() == 0 && body.references().is_empty() {
+            return Ok(MessageBody::Empty);
+        }
+        if body.bit_len() < 32 {
+            return Ok(MessageBody::Unknown {
+                opcode: None,
+                body: body.clone(),
+            });
+        }
+        let opcode = body.parser().load_u32(32)?;
+        match self.decoders.get(&opcode) {
+            Some(decoder) => decoder(body),
+            None => Ok(MessageBody::Unknown {
+                opcode: Some(opcode),
+                body: body.clone(),
+            }),
+        }
+    }
+}
+
+impl Default for OpRegistry {
+    fn default() -> Self {
+        let mut registry = OpRegistry::empty();
+        registry
+            .register(TEXT_COMMENT, |c| {
+                Ok(MessageBody::TextComment(TextComment::parse(c)?.text))
+            })
+            .register(ENCRYPTED_COMMENT, |c| {
+                Ok(MessageBody::EncryptedComment(EncryptedComment::parse(c)?))
+            })
+            .register(BOUNCED_MESSAGE_PREFIX, |c| {
+                Ok(MessageBody::Bounced(BouncedMessage::parse(c)?))
+            });
+        for opcode in [
+            JettonTransferMessage::opcode(),
+            JettonTransferNotificationMessage::opcode(),
+            JettonInternalTransferMessage::opcode(),
+            JettonBurnMessage::opcode(),
+            JettonBurnNotificationMessage::opcode(),
+            JettonMintMessage::opcode(),
+            NftExcessesMessage::opcode(),
+        ] {
+            registry.register(opcode, |c| {
+                Ok(MessageBody::Jetton(JettonMessage::parse(c)?))
+            });
+        }
+        registry
+            .register(NftTransferMessage::opcode(), |c| {
+                Ok(MessageBody::NftTransfer(NftTransferMessage::parse(c)?))
+            })
+            .register(NftOwnershipAssignedMessage::opcode(), |c| {
+                Ok(MessageBody::NftOwnershipAssigned(
+                    NftOwnershipAssignedMessage::parse(c)?,
+                ))
+            })
+            .register(NftGetStaticDataMessage::opcode(), |c| {
+                Ok(MessageBody::NftGetStaticData(
+                    NftGetStaticDataMessage::parse(c)?,
+                ))
+            })
+            .register(NftReportStaticDataMessage::opcode(), |c| {
+                Ok(MessageBody::NftReportStaticData(
+                    NftReportStaticDataMessage::parse(c)?,
+                ))
+            })
+            .register(NftGetRoyaltyParamsMessage::opcode(), |c| {
+                Ok(MessageBody::NftGetRoyaltyParams(
+                    NftGetRoyaltyParamsMessage::parse(c)?,
+                ))
+            })
+            .register(NftReportRoyaltyParamsMessage::opcode(), |c| {
+                Ok(MessageBody::NftReportRoyaltyParams(
+                    NftReportRoyaltyParamsMessage::parse(c)?,
+                ))
+            });
+        #[cfg(feature = "defi")]
+        registry
+            .register(STONFI_SWAP, |c| {
+                Ok(MessageBody::StonfiSwap(StonfiSwapPayload::parse(c)?))
+            })
+            .register(STONFI_PROVIDE_LP, |c| {
+                Ok(MessageBody::StonfiProvideLp(StonfiProvideLpPayload::parse(
+                    c,
+                )?))
+            })
+            .register(DEDUST_SWAP, |c| {
+                Ok(MessageBody::DedustSwap(DedustSwapMessage::parse(c)?))
+            })
+            .register(DEDUST_JETTON_SWAP, |c| {
+                Ok(MessageBody::DedustJettonSwap(
+                    DedustJettonSwapPayload::parse(c)?,
+                ))
+            });
+        registry
+    }
+}
+
+lazy_static! {
+    static ref DEFAULT_OP_REGISTRY: OpRegistry = OpRegistry::default();
+}
+
+/// Decodes a message body, e.g. of an incoming message or a jetton forward payload,
+/// with the default `OpRegistry`.
+pub fn decode_message_body(body: &Cell) -> Result<MessageBody, TonMessageError> {
+    DEFAULT_OP_REGISTRY.decode(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use super::{decode_message_body, CustomMessageBody, MessageBody, OpRegistry};
+    use crate::cell::{Cell, CellBuilder};
+    use crate::message::{
+        HasOpcode, JettonBurnMessage, JettonMessage, NftGetStaticDataMessage, TonMessage,
+        TonMessageError,
+    };
+
+    #[test]
+    fn test_decode_message_body() -> Result<(), TonMessageError> {
+        assert_eq!(decode_message_body(&Cell::default())?, MessageBody::Empty);
+
+        let comment = "a comment long enough to span more than a single cell ".repeat(3);
+        let body = CellBuilder::new()
+            .store_u32(32, 0)?
+            .store_snake_data(comment.as_bytes())?
+            .build()?;
+        assert_eq!(
+            decode_message_body(&body)?,
+            MessageBody::TextComment(comment)
+        );
+
+        let burn = JettonBurnMessage::new(&BigUint::from(1u32))
+            .with_query_id(7)
+            .clone();
+        assert_eq!(
+            decode_message_body(&burn.build()?)?,
+            MessageBody::Jetton(JettonMessage::Burn(burn))
+        );
+        let get_static_data = NftGetStaticDataMessage::new().with_query_id(8).clone();
+        assert_eq!(
+            decode_message_body(&get_static_data.build()?)?,
+            MessageBody::NftGetStaticData(get_static_data)
+        );
+
+        let unknown = CellBuilder::new().store_u32(32, 0x12345678)?.build()?;
+        assert_eq!(
+            decode_message_body(&unknown)?,
+            MessageBody::Unknown {
+                opcode: Some(0x12345678),
+                body: unknown.clone()
+            }
+        );
+        let short = CellBuilder::new().store_u8(8, 1)?.build()?;
+        assert!(matches!(
+            decode_message_body(&short)?,
+            MessageBody::Unknown { opcode: None, .. }
+        ));
+
+        let mut registry = OpRegistry::default();
+        registry.register(0x12345678, |_| {
+            Ok(MessageBody::Custom(CustomMessageBody::new(
+                0x12345678, 42u8,
+            )))
+        });
+        match registry.decode(&unknown)? {
+            MessageBody::Custom(body) => assert_eq!(body.downcast_ref::<u8>(), Some(&42)),
+            body => panic!("Unexpected body: {:?}", body),
+        }
+        Ok(())
+    }
+}
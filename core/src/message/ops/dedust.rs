@@ -0,0 +1,251 @@
+use std::sync::Arc;
+
+use num_bigint::BigUint;
+
+use crate::cell::{ArcCell, Cell, CellBuilder, CellParser, TonCellError};
+use crate::message::{verify_opcode, TonMessage, TonMessageError};
+use crate::TonAddress;
+
+/// Op code of a swap of TON on a DeDust native vault
+pub const DEDUST_SWAP: u32 = 0xea06185d;
+
+/// Op code of a swap of jettons on a DeDust jetton vault, in the forward payload of the transfer
+pub const DEDUST_JETTON_SWAP: u32 = 0xe3a0d482;
+
+/// Whether `DedustSwapStep::limit` bounds the amount received or the amount given.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DedustSwapKind {
+    GivenIn,
+    GivenOut,
+}
+
+/// Swap in a single pool, according to TL-B schema:
+///
+/// ```raw
+/// given_in$0 = SwapKind;
+/// given_out$1 = SwapKind;
+/// step_params#_ kind:SwapKind limit:Coins next:(Maybe ^SwapStep) = SwapStepParams;
+/// step#_ pool_addr:MsgAddressInt params:SwapStepParams = SwapStep;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct DedustSwapStep {
+    pub pool: TonAddress,
+    pub kind: DedustSwapKind,
+    /// zero for no limit.
+    pub limit: BigUint,
+    /// swap of the received asset in another pool.
+    pub next: Option<Box<DedustSwapStep>>,
+}
+
+impl DedustSwapStep {
+    fn store(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        builder.store_address(&self.pool)?;
+        builder.store_bit(self.kind == DedustSwapKind::GivenOut)?;
+        builder.store_coins(&self.limit)?;
+        let next = match &self.next {
+            Some(next) => {
+                let mut next_builder = CellBuilder::new();
+                next.store(&mut next_builder)?;
+                Some(Arc::new(next_builder.build()?))
+            }
+            None => None,
+        };
+        builder.store_maybe_cell_ref(&next)?;
+        Ok(())
+    }
+
+    fn load(parser: &mut CellParser) -> Result<Self, TonCellError> {
+        let pool = parser.load_address()?;
+        let kind = if parser.load_bit()? {
+            DedustSwapKind::GivenOut
+        } else {
+            DedustSwapKind::GivenIn
+        };
+        let limit = parser.load_coins()?;
+        let next = match parser.load_maybe_cell_ref()? {
+            Some(cell) => Some(Box::new(DedustSwapStep::load(&mut cell.parser())?)),
+            None => None,
+        };
+        Ok(DedustSwapStep {
+            pool,
+            kind,
+            limit,
+            next,
+        })
+    }
+}
+
+/// Parameters of a swap, according to TL-B schema:
+///
+/// ```raw
+/// swap_params#_ deadline:Timestamp recipient_addr:MsgAddressInt referral_addr:MsgAddress
+///   fulfill_payload:(Maybe ^Cell) reject_payload:(Maybe ^Cell) = SwapParams;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct DedustSwapParams {
+    /// unix time after which the swap is rejected, zero for no deadline.
+    pub deadline: u32,
+    /// receiver of the swapped asset, null for the sender.
+    pub recipient: TonAddress,
+    pub referral: TonAddress,
+    /// payload sent to the recipient along with the swapped asset.
+    pub fulfill_payload: Option<ArcCell>,
+    /// payload sent back to the sender if the swap is rejected.
+    pub reject_payload: Option<ArcCell>,
+}
+
+impl DedustSwapParams {
+    fn to_cell(&self) -> Result<Cell, TonCellError> {
+        CellBuilder::new()
+            .store_u32(32, self.deadline)?
+            .store_address(&self.recipient)?
+            .store_address(&self.referral)?
+            .store_maybe_cell_ref(&self.fulfill_payload)?
+            .store_maybe_cell_ref(&self.reject_payload)?
+            .build()
+    }
+
+    fn from_cell(cell: &Cell) -> Result<Self, TonCellError> {
+        let mut parser = cell.parser();
+        let deadline = parser.load_u32(32)?;
+        let recipient = parser.load_address()?;
+        let referral = parser.load_address()?;
+        let fulfill_payload = parser.load_maybe_cell_ref()?;
+        let reject_payload = parser.load_maybe_cell_ref()?;
+        Ok(DedustSwapParams {
+            deadline,
+            recipient,
+            referral,
+            fulfill_payload,
+            reject_payload,
+        })
+    }
+}
+
+/// Swap of TON sent to a DeDust native vault, according to TL-B schema:
+///
+/// ```raw
+/// swap#ea06185d query_id:uint64 amount:Coins _:SwapStep swap_params:^SwapParams = InMsgBody;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct DedustSwapMessage {
+    pub query_id: u64,
+    /// amount of TON to swap.
+    pub amount: BigUint,
+    pub step: DedustSwapStep,
+    pub params: DedustSwapParams,
+}
+
+impl TonMessage for DedustSwapMessage {
+    fn build(&self) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        builder.store_u32(32, DEDUST_SWAP)?;
+        builder.store_u64(64, self.query_id)?;
+        builder.store_coins(&self.amount)?;
+        self.step.store(&mut builder)?;
+        builder.store_child(self.params.to_cell()?)?;
+        Ok(builder.build()?)
+    }
+
+    fn parse(cell: &Cell) -> Result<Self, TonMessageError> {
+        let mut parser = cell.parser();
+        let opcode = parser.load_u32(32)?;
+        verify_opcode(opcode, DEDUST_SWAP)?;
+        let query_id = parser.load_u64(64)?;
+        let amount = parser.load_coins()?;
+        let step = DedustSwapStep::load(&mut parser)?;
+        let params = DedustSwapParams::from_cell(parser.next_reference()?.as_ref())?;
+        parser.ensure_empty()?;
+        Ok(DedustSwapMessage {
+            query_id,
+            amount,
+            step,
+            params,
+        })
+    }
+}
+
+/// Forward payload of a jetton transfer to a DeDust jetton vault swapping the jettons,
+/// according to TL-B schema:
+///
+/// ```raw
+/// swap#e3a0d482 _:SwapStep swap_params:^SwapParams = ForwardPayload;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct DedustJettonSwapPayload {
+    pub step: DedustSwapStep,
+    pub params: DedustSwapParams,
+}
+
+impl TonMessage for DedustJettonSwapPayload {
+    fn build(&self) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        builder.store_u32(32, DEDUST_JETTON_SWAP)?;
+        self.step.store(&mut builder)?;
+        builder.store_child(self.params.to_cell()?)?;
+        Ok(builder.build()?)
+    }
+
+    fn parse(cell: &Cell) -> Result<Self, TonMessageError> {
+        let mut parser = cell.parser();
+        let opcode = parser.load_u32(32)?;
+        verify_opcode(opcode, DEDUST_JETTON_SWAP)?;
+        let step = DedustSwapStep::load(&mut parser)?;
+        let params = DedustSwapParams::from_cell(parser.next_reference()?.as_ref())?;
+        parser.ensure_empty()?;
+        Ok(DedustJettonSwapPayload { step, params })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use num_bigint::BigUint;
+
+    use crate::cell::CellBuilder;
+    use crate::message::{
+        decode_message_body, DedustJettonSwapPayload, DedustSwapKind, DedustSwapMessage,
+        DedustSwapParams, DedustSwapStep, MessageBody, TonMessage, TonMessageError,
+    };
+    use crate::TonAddress;
+
+    #[test]
+    fn test_dedust_swap_round_trip() -> Result<(), TonMessageError> {
+        let step = DedustSwapStep {
+            pool: TonAddress::new(0, &[1; 32]),
+            kind: DedustSwapKind::GivenIn,
+            limit: BigUint::from(100u32),
+            next: Some(Box::new(DedustSwapStep {
+                pool: TonAddress::new(0, &[2; 32]),
+                kind: DedustSwapKind::GivenOut,
+                limit: BigUint::from(0u32),
+                next: None,
+            })),
+        };
+        let params = DedustSwapParams {
+            deadline: 1_700_000_000,
+            recipient: TonAddress::new(0, &[3; 32]),
+            referral: TonAddress::NULL,
+            fulfill_payload: Some(Arc::new(CellBuilder::new().store_u8(8, 1)?.build()?)),
+            reject_payload: None,
+        };
+        let swap = DedustSwapMessage {
+            query_id: 42,
+            amount: BigUint::from(1_000_000_000u64),
+            step: step.clone(),
+            params: params.clone(),
+        };
+        assert_eq!(
+            decode_message_body(&swap.build()?)?,
+            MessageBody::DedustSwap(swap)
+        );
+
+        let jetton_swap = DedustJettonSwapPayload { step, params };
+        assert_eq!(
+            decode_message_body(&jetton_swap.build()?)?,
+            MessageBody::DedustJettonSwap(jetton_swap)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,151 @@
+use num_bigint::BigUint;
+
+use crate::cell::{Cell, CellBuilder};
+use crate::message::{verify_opcode, TonMessage, TonMessageError};
+use crate::TonAddress;
+
+/// Op code of a swap on a STON.fi v1 router
+pub const STONFI_SWAP: u32 = 0x25938561;
+
+/// Op code of providing liquidity on a STON.fi v1 router
+pub const STONFI_PROVIDE_LP: u32 = 0xfcf9e58f;
+
+/// Forward payload of a jetton transfer to a STON.fi v1 router swapping the jettons,
+/// according to TL-B schema:
+///
+/// ```raw
+/// swap#25938561 token_wallet:MsgAddress min_out:Coins to_address:MsgAddress
+///   has_ref:Bool ref_address:has_ref?MsgAddress = ForwardPayload;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct StonfiSwapPayload {
+    /// jetton wallet of the router for the jetton to receive.
+    pub token_wallet: TonAddress,
+    /// minimum amount of jettons to receive, the swap fails otherwise.
+    pub min_out: BigUint,
+    /// receiver of the swapped jettons.
+    pub to_address: TonAddress,
+    /// referral receiving a part of the fees.
+    pub referral_address: Option<TonAddress>,
+}
+
+impl TonMessage for StonfiSwapPayload {
+    fn build(&self) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        builder.store_u32(32, STONFI_SWAP)?;
+        builder.store_address(&self.token_wallet)?;
+        builder.store_coins(&self.min_out)?;
+        builder.store_address(&self.to_address)?;
+        builder.store_bit(self.referral_address.is_some())?;
+        if let Some(referral_address) = &self.referral_address {
+            builder.store_address(referral_address)?;
+        }
+        Ok(builder.build()?)
+    }
+
+    fn parse(cell: &Cell) -> Result<Self, TonMessageError> {
+        let mut parser = cell.parser();
+        let opcode = parser.load_u32(32)?;
+        verify_opcode(opcode, STONFI_SWAP)?;
+        let token_wallet = parser.load_address()?;
+        let min_out = parser.load_coins()?;
+        let to_address = parser.load_address()?;
+        let referral_address = if parser.load_bit()? {
+            Some(parser.load_address()?)
+        } else {
+            None
+        };
+        parser.ensure_empty()?;
+        Ok(StonfiSwapPayload {
+            token_wallet,
+            min_out,
+            to_address,
+            referral_address,
+        })
+    }
+}
+
+/// Forward payload of a jetton transfer to a STON.fi v1 router providing liquidity,
+/// according to TL-B schema:
+///
+/// ```raw
+/// provide_lp#fcf9e58f token_wallet:MsgAddress min_lp_out:Coins = ForwardPayload;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct StonfiProvideLpPayload {
+    /// jetton wallet of the router for the other jetton of the pool.
+    pub token_wallet: TonAddress,
+    /// minimum amount of LP tokens to receive.
+    pub min_lp_out: BigUint,
+}
+
+impl TonMessage for StonfiProvideLpPayload {
+    fn build(&self) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        builder.store_u32(32, STONFI_PROVIDE_LP)?;
+        builder.store_address(&self.token_wallet)?;
+        builder.store_coins(&self.min_lp_out)?;
+        Ok(builder.build()?)
+    }
+
+    fn parse(cell: &Cell) -> Result<Self, TonMessageError> {
+        let mut parser = cell.parser();
+        let opcode = parser.load_u32(32)?;
+        verify_opcode(opcode, STONFI_PROVIDE_LP)?;
+        let token_wallet = parser.load_address()?;
+        let min_lp_out = parser.load_coins()?;
+        parser.ensure_empty()?;
+        Ok(StonfiProvideLpPayload {
+            token_wallet,
+            min_lp_out,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use num_bigint::BigUint;
+
+    use crate::cell::Cell;
+    use crate::message::{
+        decode_message_body, MessageBody, StonfiProvideLpPayload, StonfiSwapPayload, TonMessage,
+        TonMessageError,
+    };
+    use crate::TonAddress;
+
+    // Forward payload of a transfer notification to a STON.fi router
+    const STONFI_SWAP_PAYLOAD: &str = "25938561800f2465b65c76b1b562f32423676970b431319419d5f45ffd2eeb2155ce6ab7eacc78ee0250ef0300077c4112a8039b0a72e83d6f02babcc766852028031afcbef001bc2d5309e4ee700257a672371a90e149b7d25864dbfd44827cc1e8a30df1b1e0c4338502ade2ad94";
+
+    #[test]
+    fn test_stonfi_swap_payload() -> Result<(), TonMessageError> {
+        let cell = Cell::new(
+            hex::decode(STONFI_SWAP_PAYLOAD).unwrap(),
+            886,
+            vec![],
+            false,
+        )?;
+        let payload = match decode_message_body(&cell)? {
+            MessageBody::StonfiSwap(payload) => payload,
+            body => panic!("Unexpected body: {:?}", body),
+        };
+        assert_eq!(
+            payload.to_address,
+            TonAddress::from_str("EQAd8QRKoA5sKcug9bwK6vMdmhSAoAxr8vvABvC1TCeTude5").unwrap()
+        );
+        assert!(payload.referral_address.is_some());
+        assert_eq!(payload.build()?, cell);
+
+        let provide_lp = StonfiProvideLpPayload {
+            token_wallet: payload.token_wallet.clone(),
+            min_lp_out: BigUint::from(1u32),
+        };
+        assert_eq!(
+            StonfiProvideLpPayload::parse(&provide_lp.build()?)?,
+            provide_lp
+        );
+        assert!(StonfiSwapPayload::parse(&provide_lp.build()?).is_err());
+        Ok(())
+    }
+}
@@ -1,18 +1,23 @@
+mod derivation;
 mod error;
 
 use std::cmp;
 use std::collections::HashMap;
 
+pub use derivation::*;
 pub use error::*;
 use hmac::{Hmac, Mac};
 use lazy_static::lazy_static;
 use nacl::sign::generate_keypair;
 use pbkdf2::password_hash::Output;
 use pbkdf2::{pbkdf2_hmac, Params};
+use rand::rngs::OsRng;
+use rand::Rng;
 use sha2::Sha512;
 
 const WORDLIST_EN: &str = include_str!("../resources/mnemonic/wordlist.EN");
 const PBKDF_ITERATIONS: u32 = 100000;
+const WORD_COUNT: usize = 24;
 
 lazy_static! {
     pub static ref WORDLIST_EN_SET: HashMap<&'static str, usize> = {
@@ -24,6 +29,11 @@ lazy_static! {
             .collect();
         words
     };
+    pub static ref WORDLIST_EN_WORDS: Vec<&'static str> = WORDLIST_EN
+        .split('\n')
+        .map(|w| w.trim())
+        .filter(|w| !w.is_empty())
+        .collect();
 }
 
 /// A Rust port of https://github.com/tonwhales/ton-crypto/blob/master/src/mnemonic/mnemonic.ts
@@ -38,12 +48,23 @@ pub struct KeyPair {
     pub secret_key: Vec<u8>,
 }
 
+impl KeyPair {
+    /// Creates the ed25519 key pair of the 32-byte private key `seed`.
+    pub fn from_seed(seed: &[u8]) -> KeyPair {
+        let key_pair = generate_keypair(seed);
+        KeyPair {
+            public_key: key_pair.pkey.to_vec(),
+            secret_key: key_pair.skey.to_vec(),
+        }
+    }
+}
+
 impl Mnemonic {
     pub fn new(words: Vec<&str>, password: &Option<String>) -> Result<Mnemonic, MnemonicError> {
         let normalized_words: Vec<String> = words.iter().map(|w| w.trim().to_lowercase()).collect();
 
         // Check words
-        if normalized_words.len() != 24 {
+        if normalized_words.len() != WORD_COUNT {
             return Err(MnemonicError::UnexpectedWordCount(normalized_words.len()));
         }
         for word in &normalized_words {
@@ -52,39 +73,7 @@ impl Mnemonic {
             }
         }
 
-        // Check password validity
-        match password {
-            Some(s) if !s.is_empty() => {
-                let passless_entropy = to_entropy(&normalized_words, &None)?;
-                let seed = pbkdf2_sha512(passless_entropy, "TON fast seed version", 1, 64)?;
-                if seed[0] != 1 {
-                    return Err(MnemonicError::InvalidFirstByte(seed[0]));
-                }
-                // Make that this also is not a valid passwordless mnemonic
-                let entropy = to_entropy(&normalized_words, password)?;
-                let seed = pbkdf2_sha512(
-                    entropy,
-                    "TON seed version",
-                    cmp::max(1, PBKDF_ITERATIONS / 256),
-                    64,
-                )?;
-                if seed[0] == 0 {
-                    return Err(MnemonicError::InvalidFirstByte(seed[0]));
-                }
-            }
-            _ => {
-                let entropy = to_entropy(&normalized_words, &None)?;
-                let seed = pbkdf2_sha512(
-                    entropy,
-                    "TON seed version",
-                    cmp::max(1, PBKDF_ITERATIONS / 256),
-                    64,
-                )?;
-                if seed[0] != 0 {
-                    return Err(MnemonicError::InvalidPasswordlessMenmonicFirstByte(seed[0]));
-                }
-            }
-        }
+        validate_seed(&normalized_words, password)?;
 
         let mnemonic = Mnemonic {
             words: normalized_words,
@@ -102,15 +91,113 @@ impl Mnemonic {
         Mnemonic::new(words, password)
     }
 
+    /// Generates a new mnemonic from the OS random number generator, protected with `password`
+    /// if it's not empty.
+    ///
+    /// Random words are drawn until they form a valid mnemonic, as `mnemonicNew` of ton-crypto does.
+    pub fn generate(password: &Option<String>) -> Result<Mnemonic, MnemonicError> {
+        let mut rng = OsRng;
+        loop {
+            let words: Vec<String> = (0..WORD_COUNT)
+                .map(|_| WORDLIST_EN_WORDS[rng.gen_range(0..WORDLIST_EN_WORDS.len())].to_string())
+                .collect();
+            match validate_seed(&words, password) {
+                Ok(()) => {
+                    return Ok(Mnemonic {
+                        words,
+                        password: password.clone(),
+                    })
+                }
+                Err(MnemonicError::InvalidFirstByte(_))
+                | Err(MnemonicError::InvalidPasswordlessMenmonicFirstByte(_))
+                | Err(MnemonicError::PasswordNotNeeded) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    pub fn words(&self) -> &[String] {
+        &self.words
+    }
+
+    pub fn has_password(&self) -> bool {
+        self.password.as_ref().is_some_and(|p| !p.is_empty())
+    }
+
     pub fn to_key_pair(&self) -> Result<KeyPair, MnemonicError> {
+        let seed = self.to_seed()?;
+        Ok(KeyPair::from_seed(&seed.as_slice()[0..32]))
+    }
+
+    /// Derives the key pair of `path` from the seed of the mnemonic according to SLIP-0010,
+    /// which allows to use multiple wallets with one mnemonic.
+    pub fn to_key_pair_with_path(&self, path: &DerivationPath) -> Result<KeyPair, MnemonicError> {
+        let seed = self.to_seed()?;
+        let secret = path.derive_key(&seed)?;
+        Ok(KeyPair::from_seed(&secret))
+    }
+
+    /// Derives the key pair of the account with the given index, i.e. of the path `m/44'/607'/{account}'`.
+    pub fn to_key_pair_for_account(&self, account: u32) -> Result<KeyPair, MnemonicError> {
+        self.to_key_pair_with_path(&DerivationPath::for_account(account)?)
+    }
+
+    fn to_seed(&self) -> Result<Vec<u8>, MnemonicError> {
         let entropy = to_entropy(&self.words, &self.password)?;
-        let seed = pbkdf2_sha512(entropy, "TON default seed", PBKDF_ITERATIONS, 64)?;
-        let key_pair = generate_keypair(&seed.as_slice()[0..32]);
-        Ok(KeyPair {
-            public_key: key_pair.pkey.to_vec(),
-            secret_key: key_pair.skey.to_vec(),
-        })
+        pbkdf2_sha512(entropy, "TON default seed", PBKDF_ITERATIONS, 64)
+    }
+}
+
+/// Returns `true` if the words form a mnemonic which can be used only with a password.
+pub fn is_password_needed(words: &[String]) -> Result<bool, MnemonicError> {
+    let passless_entropy = to_entropy(words, &None)?;
+    Ok(is_password_seed(&passless_entropy)? && !is_basic_seed(&passless_entropy)?)
+}
+
+fn validate_seed(words: &[String], password: &Option<String>) -> Result<(), MnemonicError> {
+    match password {
+        Some(s) if !s.is_empty() => {
+            let passless_entropy = to_entropy(words, &None)?;
+            let seed = pbkdf2_sha512(passless_entropy.clone(), "TON fast seed version", 1, 64)?;
+            if seed[0] != 1 {
+                return Err(MnemonicError::InvalidFirstByte(seed[0]));
+            }
+            // Make sure that this is not also a valid passwordless mnemonic
+            if is_basic_seed(&passless_entropy)? {
+                return Err(MnemonicError::PasswordNotNeeded);
+            }
+            let entropy = to_entropy(words, password)?;
+            let seed = basic_seed(entropy)?;
+            if seed[0] != 0 {
+                return Err(MnemonicError::InvalidFirstByte(seed[0]));
+            }
+        }
+        _ => {
+            let entropy = to_entropy(words, &None)?;
+            let seed = basic_seed(entropy)?;
+            if seed[0] != 0 {
+                return Err(MnemonicError::InvalidPasswordlessMenmonicFirstByte(seed[0]));
+            }
+        }
     }
+    Ok(())
+}
+
+fn basic_seed(entropy: Vec<u8>) -> Result<Vec<u8>, MnemonicError> {
+    pbkdf2_sha512(
+        entropy,
+        "TON seed version",
+        cmp::max(1, PBKDF_ITERATIONS / 256),
+        64,
+    )
+}
+
+fn is_basic_seed(entropy: &[u8]) -> Result<bool, MnemonicError> {
+    Ok(basic_seed(entropy.to_vec())?[0] == 0)
+}
+
+fn is_password_seed(entropy: &[u8]) -> Result<bool, MnemonicError> {
+    Ok(pbkdf2_sha512(entropy.to_vec(), "TON fast seed version", 1, 64)?[0] == 1)
 }
 
 fn to_entropy(words: &[String], password: &Option<String>) -> Result<Vec<u8>, MnemonicError> {
@@ -146,7 +233,7 @@ fn pbkdf2_sha512(
 #[cfg(test)]
 mod tests {
     use super::MnemonicError;
-    use crate::mnemonic::Mnemonic;
+    use crate::mnemonic::{is_password_needed, DerivationPath, Mnemonic};
 
     #[test]
     fn mnemonic_parse_works() -> Result<(), MnemonicError> {
@@ -198,4 +285,42 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn mnemonic_generate_works() -> Result<(), MnemonicError> {
+        let mnemonic = Mnemonic::generate(&None)?;
+        assert!(!mnemonic.has_password());
+        let parsed = Mnemonic::from_str(&mnemonic.words().join(" "), &None)?;
+        assert!(parsed.to_key_pair()? == mnemonic.to_key_pair()?);
+
+        let password = Some("secret".to_string());
+        let mnemonic = Mnemonic::generate(&password)?;
+        assert!(mnemonic.has_password());
+        assert!(is_password_needed(mnemonic.words())?);
+        let words: Vec<&str> = mnemonic.words().iter().map(|w| w.as_str()).collect();
+        assert!(Mnemonic::new(words.clone(), &password).is_ok());
+        assert!(Mnemonic::new(words, &None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn mnemonic_password_not_needed() -> Result<(), MnemonicError> {
+        let words = "dose ice enrich trigger test dove century still betray gas diet dune use other base gym mad law immense village world example praise game";
+        let mnemonic = Mnemonic::from_str(words, &Some("secret".to_string()));
+        assert!(mnemonic.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn mnemonic_to_key_pair_with_path_works() -> Result<(), MnemonicError> {
+        let words = "dose ice enrich trigger test dove century still betray gas diet dune use other base gym mad law immense village world example praise game";
+        let mnemonic = Mnemonic::from_str(words, &None)?;
+        let first = mnemonic.to_key_pair_for_account(0)?;
+        let second = mnemonic.to_key_pair_for_account(1)?;
+        assert!(first != second);
+        assert!(first != mnemonic.to_key_pair()?);
+        let path: DerivationPath = "m/44'/607'/0'".parse()?;
+        assert!(mnemonic.to_key_pair_with_path(&path)? == first);
+        Ok(())
+    }
 }
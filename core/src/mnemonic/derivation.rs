@@ -0,0 +1,127 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha512;
+
+use super::MnemonicError;
+
+/// Coin type of TON according to SLIP-0044.
+pub const TON_COIN_TYPE: u32 = 607;
+
+const HARDENED_OFFSET: u32 = 0x8000_0000;
+
+/// Path of SLIP-0010 key derivation for ed25519, e.g. `m/44'/607'/0'`.
+///
+/// Ed25519 supports only hardened derivation, so all indices are hardened.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DerivationPath {
+    indices: Vec<u32>,
+}
+
+impl DerivationPath {
+    /// Creates a path of hardened `indices`, each of which must be less than 2^31.
+    pub fn new(indices: &[u32]) -> Result<DerivationPath, MnemonicError> {
+        if let Some(index) = indices.iter().find(|i| **i >= HARDENED_OFFSET) {
+            return Err(MnemonicError::InvalidDerivationPath(format!(
+                "index {index} is out of range"
+            )));
+        }
+        Ok(DerivationPath {
+            indices: indices.to_vec(),
+        })
+    }
+
+    /// Path `m/44'/607'/{account}'` of the account with the given index.
+    pub fn for_account(account: u32) -> Result<DerivationPath, MnemonicError> {
+        DerivationPath::new(&[44, TON_COIN_TYPE, account])
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    /// Derives the ed25519 private key of the path from `seed`.
+    pub(crate) fn derive_key(&self, seed: &[u8]) -> Result<[u8; 32], MnemonicError> {
+        let (mut key, mut chain_code) = hmac_sha512(b"ed25519 seed", &[seed])?;
+        for index in &self.indices {
+            let index_bytes = (index | HARDENED_OFFSET).to_be_bytes();
+            (key, chain_code) = hmac_sha512(&chain_code, &[&[0], &key, &index_bytes])?;
+        }
+        Ok(key)
+    }
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> Result<([u8; 32], [u8; 32]), MnemonicError> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key)?;
+    for chunk in data {
+        mac.update(chunk);
+    }
+    let result = mac.finalize().into_bytes();
+    let mut left = [0; 32];
+    let mut right = [0; 32];
+    left.copy_from_slice(&result[..32]);
+    right.copy_from_slice(&result[32..]);
+    Ok((left, right))
+}
+
+impl FromStr for DerivationPath {
+    type Err = MnemonicError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || MnemonicError::InvalidDerivationPath(s.to_string());
+        let mut parts = s.split('/');
+        if parts.next() != Some("m") {
+            return Err(invalid());
+        }
+        let indices = parts
+            .map(|part| {
+                let index = part
+                    .strip_suffix('\'')
+                    .or_else(|| part.strip_suffix('h'))
+                    .ok_or_else(invalid)?;
+                index.parse::<u32>().map_err(|_| invalid())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        DerivationPath::new(&indices).map_err(|_| invalid())
+    }
+}
+
+impl Display for DerivationPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("m")?;
+        for index in &self.indices {
+            write!(f, "/{index}'")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DerivationPath;
+    use crate::mnemonic::MnemonicError;
+
+    #[test]
+    fn derivation_path_works() -> Result<(), MnemonicError> {
+        let path: DerivationPath = "m/44'/607'/3'".parse()?;
+        assert_eq!(path, DerivationPath::for_account(3)?);
+        assert_eq!(path.to_string(), "m/44'/607'/3'");
+        assert_eq!("m/0h/1h".parse::<DerivationPath>()?.indices(), &[0, 1]);
+        assert!("m/44'/607".parse::<DerivationPath>().is_err());
+        assert!("44'/607'".parse::<DerivationPath>().is_err());
+        assert!("m/2147483648'".parse::<DerivationPath>().is_err());
+
+        // test vector 1 for ed25519 of SLIP-0010
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        assert_eq!(
+            hex::encode(DerivationPath::new(&[])?.derive_key(&seed)?),
+            "2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7"
+        );
+        assert_eq!(
+            hex::encode(DerivationPath::new(&[0])?.derive_key(&seed)?),
+            "68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3"
+        );
+        Ok(())
+    }
+}
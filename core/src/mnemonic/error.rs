@@ -14,6 +14,12 @@ pub enum MnemonicError {
     #[error("Invalid passwordless mnemonic (first byte: {0:#X})")]
     InvalidPasswordlessMenmonicFirstByte(u8),
 
+    #[error("Mnemonic is not protected with a password")]
+    PasswordNotNeeded,
+
+    #[error("Invalid derivation path (path: {0})")]
+    InvalidDerivationPath(String),
+
     #[error("Invalid password (hash: {0})")]
     PasswordHashError(pbkdf2::password_hash::Error),
 
@@ -0,0 +1,281 @@
+//! Verification of Merkle proofs returned by liteservers.
+//!
+//! A Merkle proof contains the part of a tree of cells needed to prove some data, with all other
+//! subtrees replaced by pruned branches holding only their hashes. Since the hash of the tree
+//! doesn't change by pruning, checking the hash of the proof against a trusted hash, e.g. a root
+//! hash of a block, proves all the data it contains.
+
+mod account;
+mod block;
+mod error;
+mod lookup;
+mod transaction;
+
+pub use account::*;
+pub use block::*;
+pub use error::*;
+pub use transaction::*;
+
+use crate::cell::{ArcCell, Cell, CellParser, CellType};
+use crate::TonHash;
+
+/// Merkle proof exotic cell, proving the cells of `root` to be a part of the tree
+/// with root hash `hash`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MerkleProof {
+    pub hash: TonHash,
+    pub depth: u16,
+    pub root: ArcCell,
+}
+
+impl MerkleProof {
+    pub fn parse(cell: &Cell) -> Result<Self, TonProofError> {
+        let mut parser = exotic_parser(cell, CellType::MerkleProof, "Merkle proof")?;
+        let hash = load_hash(&mut parser)?;
+        let depth = parser.load_u16(16)?;
+        let root = parser.next_reference()?;
+        Ok(MerkleProof { hash, depth, root })
+    }
+
+    /// Checks that the proof is made for the tree with root hash `expected_hash`, returning the
+    /// root of the proved tree.
+    pub fn verify(&self, expected_hash: &TonHash) -> Result<&ArcCell, TonProofError> {
+        check_hash("Merkle proof", expected_hash, &self.hash)?;
+        check_hash("Merkle proof root", &self.hash, &self.root.get_hash(0))?;
+        Ok(&self.root)
+    }
+
+    /// Parses the Merkle proof `cell` and checks it against `expected_hash`.
+    pub fn verify_cell(cell: &Cell, expected_hash: &TonHash) -> Result<ArcCell, TonProofError> {
+        Ok(Self::parse(cell)?.verify(expected_hash)?.clone())
+    }
+}
+
+/// Merkle update exotic cell, proving the transition of the tree with root hash `old_hash`
+/// to the tree with root hash `new_hash`, e.g. the `state_update` of a block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MerkleUpdate {
+    pub old_hash: TonHash,
+    pub new_hash: TonHash,
+    pub old_depth: u16,
+    pub new_depth: u16,
+    pub old: ArcCell,
+    pub new: ArcCell,
+}
+
+impl MerkleUpdate {
+    pub fn parse(cell: &Cell) -> Result<Self, TonProofError> {
+        let mut parser = exotic_parser(cell, CellType::MerkleUpdate, "Merkle update")?;
+        let old_hash = load_hash(&mut parser)?;
+        let new_hash = load_hash(&mut parser)?;
+        let old_depth = parser.load_u16(16)?;
+        let new_depth = parser.load_u16(16)?;
+        let old = parser.next_reference()?;
+        let new = parser.next_reference()?;
+        Ok(MerkleUpdate {
+            old_hash,
+            new_hash,
+            old_depth,
+            new_depth,
+            old,
+            new,
+        })
+    }
+}
+
+/// Loads the next reference of a proved tree, failing if it is pruned.
+pub(crate) fn load_proved_ref(
+    parser: &mut CellParser,
+    what: &str,
+) -> Result<ArcCell, TonProofError> {
+    let cell = parser.next_reference()?;
+    ensure_not_pruned(&cell, what)?;
+    Ok(cell)
+}
+
+pub(crate) fn ensure_not_pruned(cell: &Cell, what: &str) -> Result<(), TonProofError> {
+    if cell.cell_type() == CellType::PrunedBranch {
+        Err(TonProofError::PrunedData(what.to_string()))
+    } else {
+        Ok(())
+    }
+}
+
+pub(crate) fn check_hash(
+    what: &str,
+    expected: &TonHash,
+    actual: &TonHash,
+) -> Result<(), TonProofError> {
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(TonProofError::HashMismatch {
+            what: what.to_string(),
+            expected: *expected,
+            actual: *actual,
+        })
+    }
+}
+
+pub(crate) fn check_tag(
+    parser: &mut CellParser,
+    bit_len: usize,
+    expected: u32,
+    what: &str,
+) -> Result<(), TonProofError> {
+    let tag = parser.load_u32(bit_len)?;
+    if tag != expected {
+        return Err(TonProofError::InvalidProof(format!(
+            "Invalid tag of {what}: {tag:#x}, expected {expected:#x}"
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn load_hash(parser: &mut CellParser) -> Result<TonHash, TonProofError> {
+    let mut hash = [0u8; 32];
+    parser.load_slice(&mut hash)?;
+    Ok(hash)
+}
+
+/// Skips `CurrencyCollection`, the extra value of most augmented dictionaries of a block.
+pub(crate) fn skip_currency_collection(parser: &mut CellParser) -> Result<(), TonProofError> {
+    parser.load_coins()?;
+    if parser.load_bit()? {
+        parser.next_reference()?;
+    }
+    Ok(())
+}
+
+fn exotic_parser<'a>(
+    cell: &'a Cell,
+    cell_type: CellType,
+    what: &str,
+) -> Result<CellParser<'a>, TonProofError> {
+    if cell.cell_type() != cell_type {
+        return Err(TonProofError::InvalidProof(format!("Expected {what} cell")));
+    }
+    let mut parser = cell.parser();
+    parser.skip_bits(8)?;
+    Ok(parser)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{MerkleProof, MerkleUpdate, TonProofError};
+    use crate::cell::{ArcCell, Cell, CellBuilder, CellType, TonCellError};
+
+    pub(crate) fn merkle_proof(root: &ArcCell) -> Cell {
+        Cell::new_merkle_proof(root).unwrap()
+    }
+
+    #[test]
+    fn merkle_proof_works() -> Result<(), TonCellError> {
+        let secret = CellBuilder::new().store_u64(64, 42)?.build()?;
+        let public = CellBuilder::new().store_u32(32, 7)?.build()?;
+        let tree = CellBuilder::new()
+            .store_u8(8, 1)?
+            .store_child(secret.clone())?
+            .store_child(public.clone())?
+            .build()?;
+        let proved = Arc::new(
+            CellBuilder::new()
+                .store_u8(8, 1)?
+                .store_reference(&Arc::new(Cell::new_pruned_branch(&secret, 1)?))?
+                .store_child(public)?
+                .build()?,
+        );
+        assert_ne!(proved.cell_hash(), tree.cell_hash());
+        assert_eq!(proved.get_hash(0), tree.cell_hash());
+
+        let proof_cell = merkle_proof(&proved);
+        let proof = MerkleProof::parse(&proof_cell).unwrap();
+        assert_eq!(proof.depth, tree.cell_depth());
+        let root = proof.verify(&tree.cell_hash()).unwrap();
+        assert_eq!(root.reference(0)?.cell_type(), CellType::PrunedBranch);
+        assert_eq!(root.reference(1)?.cell_type(), CellType::Ordinary);
+        assert!(matches!(
+            proof.verify(&secret.cell_hash()),
+            Err(TonProofError::HashMismatch { .. })
+        ));
+        assert!(MerkleProof::parse(&tree).is_err());
+
+        let new = Arc::new(CellBuilder::new().store_u32(32, 8)?.build()?);
+        let update = MerkleUpdate::parse(&Cell::new_merkle_update(&proved, &new)?).unwrap();
+        assert_eq!(update.old_hash, tree.cell_hash());
+        assert_eq!(update.new_hash, new.cell_hash());
+        Ok(())
+    }
+
+    /// Creates a block of the shard with `shard_prefix_bits` zero bits and `ShardStateUnsplit`
+    /// after it, in which only `accounts` and `custom` are filled in.
+    pub(crate) fn block_with_state(
+        workchain: i32,
+        shard_prefix_bits: u8,
+        seqno: u32,
+        key_block: bool,
+        accounts: ArcCell,
+        custom: Option<ArcCell>,
+        extra: ArcCell,
+    ) -> Result<(Cell, Cell), TonCellError> {
+        let empty = Arc::new(Cell::default());
+        let state = CellBuilder::new()
+            .store_u32(32, 0x9023afe2)?
+            .store_i32(32, -239)?
+            .store_u8(8, shard_prefix_bits)?
+            .store_i32(32, workchain)?
+            .store_u64(64, 0)?
+            .store_u32(32, seqno)?
+            .store_u32(32, 0)?
+            .store_u32(32, 1_700_000_000)?
+            .store_u64(64, 1_000_000)?
+            .store_u32(32, 0)?
+            .store_reference(&empty)?
+            .store_bit(false)?
+            .store_reference(&accounts)?
+            .store_reference(&empty)?
+            .store_maybe_cell_ref(&custom)?
+            .build()?;
+        let info = CellBuilder::new()
+            .store_u32(32, 0x9bc7a987)?
+            .store_u32(32, 0)?
+            .store_u8(6, 0)?
+            .store_bit(key_block)?
+            .store_bit(false)?
+            .store_u8(8, 0)?
+            .store_u32(32, seqno)?
+            .store_u32(32, 0)?
+            .store_u8(8, shard_prefix_bits)?
+            .store_i32(32, workchain)?
+            .store_u64(64, 0)?
+            .store_u32(32, 1_700_000_000)?
+            .store_u64(64, 1_000_000)?
+            .store_u64(64, 1_000_005)?
+            .store_u32(32, 0)?
+            .store_u32(32, 0)?
+            .store_u32(32, 0)?
+            .store_u32(32, 0)?
+            .build()?;
+        let value_flow = Arc::new(CellBuilder::new().store_u32(32, 0xb8e48dfb)?.build()?);
+        let state_update = Arc::new(Cell::new_merkle_update(
+            &Arc::new(Cell::new_pruned_branch(&empty, 1)?),
+            &Arc::new(Cell::new_pruned_branch(&state, 1)?),
+        )?);
+        let block = CellBuilder::new()
+            .store_u32(32, 0x11ef55aa)?
+            .store_i32(32, -239)?
+            .store_child(info)?
+            .store_reference(&value_flow)?
+            .store_reference(&state_update)?
+            .store_reference(&extra)?
+            .build()?;
+        Ok((block, state))
+    }
+
+    /// Replaces the cells at the given reference indexes of `cell` with pruned branches.
+    pub(crate) fn prune_refs(cell: &Cell, indexes: &[usize]) -> ArcCell {
+        Arc::new(cell.with_pruned_references(indexes, 1).unwrap())
+    }
+}
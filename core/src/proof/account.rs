@@ -0,0 +1,430 @@
+use super::block::{load_shard_ident, verify_state_proof};
+use super::lookup::dict_lookup;
+use super::{
+    check_hash, check_tag, ensure_not_pruned, load_hash, load_proved_ref, skip_currency_collection,
+    BlockHeader, TonProofError,
+};
+use crate::cell::{ArcCell, Cell};
+use crate::{TonAddress, TonHash};
+
+const SHARD_STATE_TAG: u32 = 0x9023afe2;
+const SPLIT_STATE_TAG: u32 = 0x5f327da5;
+const MC_STATE_EXTRA_TAG: u32 = 0xcc26;
+
+/// State of an account, proved to be the state after a block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProvedAccountState {
+    pub block: BlockHeader,
+    /// Hash of the `Account` cell, equal to the hash of the verified state.
+    pub account_hash: TonHash,
+    pub last_transaction_hash: TonHash,
+    pub last_transaction_lt: u64,
+}
+
+/// Verifies the proof of the state of `address` after the block with `block_root_hash`,
+/// i.e. the `proof` of `liteServer.getAccountState`.
+///
+/// `proof` must contain two roots: the Merkle proofs of the block and of the shard state after
+/// it. `state` is the `Account` cell received along with the proof, or `None` if the account
+/// doesn't exist. `None` is returned if the proof shows that the account doesn't exist.
+pub fn verify_account_state_proof(
+    block_root_hash: &TonHash,
+    proof: &[ArcCell],
+    address: &TonAddress,
+    state: Option<&Cell>,
+) -> Result<Option<ProvedAccountState>, TonProofError> {
+    let (block_proof, state_proof) = two_roots(proof)?;
+    let (block, shard_state) = verify_state_proof(block_proof, state_proof, block_root_hash)?;
+    if block.workchain != address.workchain {
+        return Err(TonProofError::InvalidProof(format!(
+            "Block of workchain {} can't contain account {}",
+            block.workchain, address
+        )));
+    }
+    let (shard, accounts, _) = parse_shard_state(&shard_state)?;
+    if shard.0 != address.workchain || !shard_contains(shard.1, address) {
+        return Err(TonProofError::InvalidProof(format!(
+            "State of shard {}:{:016x} can't contain account {}",
+            shard.0, shard.1, address
+        )));
+    }
+    ensure_not_pruned(&accounts, "shard accounts")?;
+
+    let mut parser = accounts.parser();
+    let account = if parser.load_bit()? {
+        let root = load_proved_ref(&mut parser, "shard accounts")?;
+        dict_lookup(&mut root.parser(), &address.hash_part, 256, |parser| {
+            // depth_balance$_ split_depth:(#<= 30) balance:CurrencyCollection
+            parser.skip_bits(5)?;
+            skip_currency_collection(parser)?;
+            // account_descr$_ account:^Account last_trans_hash:bits256 last_trans_lt:uint64
+            let account_hash = parser.next_reference()?.get_hash(0);
+            let last_transaction_hash = load_hash(parser)?;
+            let last_transaction_lt = parser.load_u64(64)?;
+            Ok((account_hash, last_transaction_hash, last_transaction_lt))
+        })?
+    } else {
+        None
+    };
+
+    match (account, state) {
+        (Some((account_hash, last_transaction_hash, last_transaction_lt)), Some(state)) => {
+            check_hash("account state", &account_hash, &state.cell_hash())?;
+            Ok(Some(ProvedAccountState {
+                block,
+                account_hash,
+                last_transaction_hash,
+                last_transaction_lt,
+            }))
+        }
+        (None, None) => Ok(None),
+        (Some(_), None) => Err(TonProofError::InvalidProof(format!(
+            "Account {address} exists, but no state is provided"
+        ))),
+        (None, Some(_)) => Err(TonProofError::InvalidProof(format!(
+            "Account {address} doesn't exist, but a state is provided"
+        ))),
+    }
+}
+
+/// Shard block, proved to be the latest block of its shard known to a masterchain block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProvedShardBlock {
+    pub workchain: i32,
+    /// Shard id in the format of `BlockIdExt`, i.e. the shard prefix followed by a 1 bit.
+    pub shard: i64,
+    pub seqno: u32,
+    pub root_hash: TonHash,
+    pub file_hash: TonHash,
+    pub start_lt: u64,
+    pub end_lt: u64,
+}
+
+/// Verifies the proof of the shard block, whose shard contains `address`, referred by the
+/// masterchain block with `mc_block_root_hash`, e.g. the `shard_proof` of
+/// `liteServer.getAccountState` for accounts outside of the masterchain.
+///
+/// `proof` must contain two roots: the Merkle proofs of the masterchain block and of the
+/// masterchain state after it. The returned block can be used to verify the account state
+/// proof with `verify_account_state_proof`.
+pub fn verify_shard_proof(
+    mc_block_root_hash: &TonHash,
+    proof: &[ArcCell],
+    address: &TonAddress,
+) -> Result<ProvedShardBlock, TonProofError> {
+    let (block_proof, state_proof) = two_roots(proof)?;
+    let (block, mc_state) = verify_state_proof(block_proof, state_proof, mc_block_root_hash)?;
+    if block.workchain != -1 {
+        return Err(TonProofError::InvalidProof(format!(
+            "Block of workchain {} is not a masterchain block",
+            block.workchain
+        )));
+    }
+    let custom = parse_shard_state(&mc_state)?
+        .2
+        .ok_or_else(|| TonProofError::InvalidProof("No masterchain state extra".to_string()))?;
+
+    let mut parser = custom.parser();
+    check_tag(
+        &mut parser,
+        16,
+        MC_STATE_EXTRA_TAG,
+        "masterchain state extra",
+    )?;
+    // shard_hashes:(HashmapE 32 ^(BinTree ShardDescr))
+    let not_found =
+        || TonProofError::InvalidProof(format!("No shards of workchain {}", address.workchain));
+    if !parser.load_bit()? {
+        return Err(not_found());
+    }
+    let shard_hashes = load_proved_ref(&mut parser, "shard hashes")?;
+    let workchain_key = address.workchain.to_be_bytes();
+    let mut node = dict_lookup(&mut shard_hashes.parser(), &workchain_key, 32, |parser| {
+        load_proved_ref(parser, "shard tree")
+    })?
+    .ok_or_else(not_found)?;
+
+    let mut depth = 0;
+    loop {
+        let mut parser = node.parser();
+        if !parser.load_bit()? {
+            // bt_leaf$0
+            return parse_shard_descr(node.as_ref(), depth, address);
+        }
+        // bt_fork$1
+        let bit = address.hash_part[depth / 8] & (0x80 >> (depth % 8)) != 0;
+        let left = parser.next_reference()?;
+        let right = parser.next_reference()?;
+        let next = if bit { right } else { left };
+        ensure_not_pruned(&next, "shard tree")?;
+        node = next;
+        depth += 1;
+    }
+}
+
+fn parse_shard_descr(
+    leaf: &Cell,
+    depth: usize,
+    address: &TonAddress,
+) -> Result<ProvedShardBlock, TonProofError> {
+    let mut parser = leaf.parser();
+    parser.skip_bits(1)?;
+    let tag = parser.load_u8(4)?;
+    if tag != 0xa && tag != 0xb {
+        return Err(TonProofError::InvalidProof(format!(
+            "Invalid tag of shard descr: {tag:#x}"
+        )));
+    }
+    let seqno = parser.load_u32(32)?;
+    let _reg_mc_seqno = parser.load_u32(32)?;
+    let start_lt = parser.load_u64(64)?;
+    let end_lt = parser.load_u64(64)?;
+    let root_hash = load_hash(&mut parser)?;
+    let file_hash = load_hash(&mut parser)?;
+
+    let mut prefix_bytes = [0u8; 8];
+    prefix_bytes.copy_from_slice(&address.hash_part[..8]);
+    let prefix = match depth {
+        0 => 0,
+        depth => u64::from_be_bytes(prefix_bytes) & (u64::MAX << (64 - depth)),
+    };
+    Ok(ProvedShardBlock {
+        workchain: address.workchain,
+        shard: (prefix | (1 << (63 - depth))) as i64,
+        seqno,
+        root_hash,
+        file_hash,
+        start_lt,
+        end_lt,
+    })
+}
+
+/// Returns whether the shard with id `shard`, in the format of `BlockIdExt`, contains `address`.
+fn shard_contains(shard: i64, address: &TonAddress) -> bool {
+    let shard = shard as u64;
+    let mut prefix_bytes = [0u8; 8];
+    prefix_bytes.copy_from_slice(&address.hash_part[..8]);
+    let prefix = u64::from_be_bytes(prefix_bytes);
+    let lowest_bit = shard & shard.wrapping_neg();
+    (prefix ^ shard) & (lowest_bit.wrapping_neg() << 1) == 0
+}
+
+/// Returns the shard ident, `accounts` and `custom` of `ShardStateUnsplit`.
+#[allow(clippy::type_complexity)]
+fn parse_shard_state(
+    state: &Cell,
+) -> Result<((i32, i64), ArcCell, Option<ArcCell>), TonProofError> {
+    let mut parser = state.parser();
+    let tag = parser.load_u32(32)?;
+    if tag == SPLIT_STATE_TAG {
+        return Err(TonProofError::InvalidProof(
+            "Proofs of split shard states are not supported".to_string(),
+        ));
+    }
+    if tag != SHARD_STATE_TAG {
+        return Err(TonProofError::InvalidProof(format!(
+            "Invalid tag of shard state: {tag:#x}"
+        )));
+    }
+    let _global_id = parser.load_i32(32)?;
+    let shard = load_shard_ident(&mut parser)?;
+    // seq_no, vert_seq_no, gen_utime, gen_lt, min_ref_mc_seqno
+    parser.skip_bits(32 + 32 + 32 + 64 + 32)?;
+    let _out_msg_queue_info = parser.next_reference()?;
+    let _before_split = parser.load_bit()?;
+    let accounts = parser.next_reference()?;
+    let _extra = parser.next_reference()?;
+    let custom = if parser.load_bit()? {
+        Some(load_proved_ref(&mut parser, "masterchain state extra")?)
+    } else {
+        None
+    };
+    Ok((shard, accounts, custom))
+}
+
+fn two_roots(proof: &[ArcCell]) -> Result<(&Cell, &Cell), TonProofError> {
+    match proof {
+        [first, second] => Ok((first.as_ref(), second.as_ref())),
+        _ => Err(TonProofError::InvalidProof(format!(
+            "Expected 2 proof roots, got {}",
+            proof.len()
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use super::{verify_account_state_proof, verify_shard_proof};
+    use crate::cell::dict::predefined_writers::val_writer_ref_cell;
+    use crate::cell::{ArcCell, Cell, CellBuilder, TonCellError};
+    use crate::proof::tests::{block_with_state, merkle_proof, prune_refs};
+    use crate::proof::TonProofError;
+    use crate::TonAddress;
+
+    /// Creates `ShardAccounts` with the single account `address`.
+    fn shard_accounts(address: &TonAddress, account: &Cell) -> Result<ArcCell, TonCellError> {
+        let root = CellBuilder::new()
+            // hml_long$10 n:(#<= 256) s:(n * Bit)
+            .store_u8(2, 0b10)?
+            .store_u32(9, 256)?
+            .store_slice(&address.hash_part)?
+            // depth_balance$_ split_depth:(#<= 30) balance:CurrencyCollection
+            .store_u8(5, 0)?
+            .store_coins(&100u32.into())?
+            .store_bit(false)?
+            // account_descr$_ account:^Account last_trans_hash:bits256 last_trans_lt:uint64
+            .store_child(account.clone())?
+            .store_slice(&[7; 32])?
+            .store_u64(64, 1_000_003)?
+            .build()?;
+        Ok(Arc::new(
+            CellBuilder::new()
+                .store_bit(true)?
+                .store_child(root)?
+                .store_u8(5, 0)?
+                .store_coins(&100u32.into())?
+                .store_bit(false)?
+                .build()?,
+        ))
+    }
+
+    #[test]
+    fn account_state_proof_works() -> Result<(), TonCellError> {
+        let address =
+            TonAddress::from_base64_url("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR")
+                .unwrap();
+        let account = CellBuilder::new().store_u32(32, 0xacc)?.build()?;
+        let accounts = shard_accounts(&address, &account)?;
+        let extra = Arc::new(Cell::default());
+        let (block, state) =
+            block_with_state(0, 0, 5, false, accounts.clone(), None, extra.clone())?;
+        let block_hash = block.cell_hash();
+
+        // Only the path to the account in the state is included in the proof
+        let state_proof = prune_refs(&state, &[0, 2]);
+        let accounts_proof = prune_refs(state_proof.reference(1)?, &[]);
+        let dict_root = prune_refs(accounts_proof.reference(0)?, &[0]);
+        let accounts_proof = Arc::new(Cell::new(
+            accounts_proof.data().to_vec(),
+            accounts_proof.bit_len(),
+            vec![dict_root],
+            false,
+        )?);
+        let state_proof = Arc::new(Cell::new(
+            state_proof.data().to_vec(),
+            state_proof.bit_len(),
+            vec![
+                state_proof.reference(0)?.clone(),
+                accounts_proof,
+                state_proof.reference(2)?.clone(),
+            ],
+            false,
+        )?);
+        let proof = [
+            Arc::new(merkle_proof(&prune_refs(&block, &[1, 3]))),
+            Arc::new(merkle_proof(&state_proof)),
+        ];
+
+        let proved = verify_account_state_proof(&block_hash, &proof, &address, Some(&account))
+            .unwrap()
+            .unwrap();
+        assert_eq!(proved.account_hash, account.cell_hash());
+        assert_eq!(proved.last_transaction_hash, [7; 32]);
+        assert_eq!(proved.last_transaction_lt, 1_000_003);
+        assert_eq!(proved.block.seqno, 5);
+        assert_eq!(proved.block.shard, i64::MIN);
+
+        let other_account = CellBuilder::new().store_u32(32, 0xbad)?.build()?;
+        assert!(matches!(
+            verify_account_state_proof(&block_hash, &proof, &address, Some(&other_account)),
+            Err(TonProofError::HashMismatch { .. })
+        ));
+        assert!(verify_account_state_proof(&[0; 32], &proof, &address, Some(&account)).is_err());
+        let mut absent = address.clone();
+        absent.hash_part[31] ^= 1;
+        assert_eq!(
+            verify_account_state_proof(&block_hash, &proof, &absent, None).unwrap(),
+            None
+        );
+
+        // The account belongs to shard 0:c000000000000000, not to 0:4000000000000000
+        let (block, state) = block_with_state(0, 1, 5, false, accounts, None, extra)?;
+        let proof = [
+            Arc::new(merkle_proof(&prune_refs(&block, &[1, 3]))),
+            Arc::new(merkle_proof(&prune_refs(&state, &[0, 1, 2]))),
+        ];
+        let result = verify_account_state_proof(&block.cell_hash(), &proof, &address, None);
+        assert!(matches!(result, Err(TonProofError::InvalidProof(e)) if e.contains("shard")));
+        Ok(())
+    }
+
+    #[test]
+    fn shard_proof_works() -> Result<(), TonCellError> {
+        let shard_descr = |seqno: u32| -> Result<ArcCell, TonCellError> {
+            Ok(Arc::new(
+                CellBuilder::new()
+                    .store_bit(false)?
+                    .store_u8(4, 0xb)?
+                    .store_u32(32, seqno)?
+                    .store_u32(32, 3)?
+                    .store_u64(64, 1_000_000)?
+                    .store_u64(64, 1_000_005)?
+                    .store_slice(&[seqno as u8; 32])?
+                    .store_slice(&[0xff; 32])?
+                    .build()?,
+            ))
+        };
+        let shard_tree = CellBuilder::new()
+            .store_bit(true)?
+            .store_reference(&shard_descr(10)?)?
+            .store_reference(&shard_descr(11)?)?
+            .build()?;
+        let shard_hashes = CellBuilder::new()
+            .store_dict(
+                32,
+                val_writer_ref_cell,
+                HashMap::from([(0u32, Arc::new(shard_tree))]),
+            )?
+            .build()?;
+        let mc_state_extra = CellBuilder::new()
+            .store_u32(16, 0xcc26)?
+            .store_bit(true)?
+            .store_child(shard_hashes)?
+            .build()?;
+        let accounts = Arc::new(CellBuilder::new().store_bit(false)?.build()?);
+        let (block, state) = block_with_state(
+            -1,
+            0,
+            3,
+            false,
+            accounts,
+            Some(Arc::new(mc_state_extra)),
+            Arc::new(Cell::default()),
+        )?;
+        let proof = [
+            Arc::new(merkle_proof(&prune_refs(&block, &[1, 3]))),
+            Arc::new(merkle_proof(&prune_refs(&state, &[0, 1, 2]))),
+        ];
+
+        let address =
+            TonAddress::from_base64_url("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR")
+                .unwrap();
+        let shard_block = verify_shard_proof(&block.cell_hash(), &proof, &address).unwrap();
+        assert_eq!(shard_block.seqno, 11);
+        assert_eq!(shard_block.root_hash, [11; 32]);
+        assert_eq!(shard_block.shard as u64, 0xc000000000000000);
+
+        let mut address = address;
+        address.hash_part[0] = 0x12;
+        let shard_block = verify_shard_proof(&block.cell_hash(), &proof, &address).unwrap();
+        assert_eq!(shard_block.seqno, 10);
+        assert_eq!(shard_block.shard as u64, 0x4000000000000000);
+
+        address.workchain = 1;
+        assert!(verify_shard_proof(&block.cell_hash(), &proof, &address).is_err());
+        Ok(())
+    }
+}
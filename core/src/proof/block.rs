@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+
+use super::{check_tag, load_proved_ref, MerkleProof, MerkleUpdate, TonProofError};
+use crate::cell::{ArcCell, Cell, CellParser};
+use crate::TonHash;
+
+pub(crate) const BLOCK_TAG: u32 = 0x11ef55aa;
+const BLOCK_INFO_TAG: u32 = 0x9bc7a987;
+
+/// Header of a block, proved by a Merkle proof of the block, e.g. the `header_proof` of
+/// `liteServer.getBlockHeader`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockHeader {
+    pub root_hash: TonHash,
+    pub global_id: i32,
+    pub workchain: i32,
+    /// Shard id in the format of `BlockIdExt`, i.e. the shard prefix followed by a 1 bit.
+    pub shard: i64,
+    pub seqno: u32,
+    pub key_block: bool,
+    pub gen_utime: u32,
+    pub start_lt: u64,
+    pub end_lt: u64,
+    pub prev_key_block_seqno: u32,
+    /// Hash of the shard state after the block, if the state update is included in the proof.
+    pub state_hash: Option<TonHash>,
+}
+
+impl BlockHeader {
+    /// Verifies the Merkle proof of the block with `root_hash` and parses the header from it.
+    ///
+    /// The caller is responsible for checking that workchain, shard and seqno of the returned
+    /// header are those of the requested block.
+    pub fn from_proof(proof: &Cell, root_hash: &TonHash) -> Result<Self, TonProofError> {
+        let block = MerkleProof::verify_cell(proof, root_hash)?;
+        Self::parse(&block, root_hash)
+    }
+
+    /// Verifies the Merkle proof of a key block against `key_block_hashes`, the trusted root
+    /// hashes of key blocks by their seqno, e.g. the init block and hardfork blocks of the
+    /// network config.
+    pub fn from_key_block_proof(
+        proof: &Cell,
+        key_block_hashes: &HashMap<u32, TonHash>,
+    ) -> Result<Self, TonProofError> {
+        let proof = MerkleProof::parse(proof)?;
+        // The seqno is read from the unverified proof only to choose the trusted hash
+        let seqno = Self::parse(&proof.root, &proof.hash)?.seqno;
+        let root_hash = key_block_hashes.get(&seqno).ok_or_else(|| {
+            TonProofError::InvalidProof(format!("Block {seqno} is not a known key block"))
+        })?;
+        let header = Self::parse(proof.verify(root_hash)?, root_hash)?;
+        if header.workchain != -1 || !(header.key_block || header.seqno == 0) {
+            return Err(TonProofError::InvalidProof(format!(
+                "Block {seqno} is not a masterchain key block"
+            )));
+        }
+        Ok(header)
+    }
+
+    pub(crate) fn parse(block: &ArcCell, root_hash: &TonHash) -> Result<Self, TonProofError> {
+        let mut parser = block.parser();
+        check_tag(&mut parser, 32, BLOCK_TAG, "block")?;
+        let global_id = parser.load_i32(32)?;
+        let info = load_proved_ref(&mut parser, "block info")?;
+        let _value_flow = parser.next_reference()?;
+        let state_update = parser.next_reference()?;
+        let state_hash = match MerkleUpdate::parse(&state_update) {
+            Ok(update) => Some(update.new_hash),
+            Err(_) => None,
+        };
+
+        let mut parser = info.parser();
+        check_tag(&mut parser, 32, BLOCK_INFO_TAG, "block info")?;
+        let _version = parser.load_u32(32)?;
+        let _flags = parser.load_u8(6)?;
+        let key_block = parser.load_bit()?;
+        let _vert_seqno_incr = parser.load_bit()?;
+        let _flags = parser.load_u8(8)?;
+        let seqno = parser.load_u32(32)?;
+        let _vert_seqno = parser.load_u32(32)?;
+        let (workchain, shard) = load_shard_ident(&mut parser)?;
+        let gen_utime = parser.load_u32(32)?;
+        let start_lt = parser.load_u64(64)?;
+        let end_lt = parser.load_u64(64)?;
+        let _gen_validator_list_hash_short = parser.load_u32(32)?;
+        let _gen_catchain_seqno = parser.load_u32(32)?;
+        let _min_ref_mc_seqno = parser.load_u32(32)?;
+        let prev_key_block_seqno = parser.load_u32(32)?;
+        Ok(BlockHeader {
+            root_hash: *root_hash,
+            global_id,
+            workchain,
+            shard,
+            seqno,
+            key_block,
+            gen_utime,
+            start_lt,
+            end_lt,
+            prev_key_block_seqno,
+            state_hash,
+        })
+    }
+
+    /// Hash of the shard state after the block, failing if it is not included in the proof.
+    pub fn proved_state_hash(&self) -> Result<TonHash, TonProofError> {
+        self.state_hash
+            .ok_or_else(|| TonProofError::PrunedData("block state update".to_string()))
+    }
+}
+
+/// Reads `ShardIdent`, returning the workchain and the shard id in the format of `BlockIdExt`.
+pub(crate) fn load_shard_ident(parser: &mut CellParser) -> Result<(i32, i64), TonProofError> {
+    check_tag(parser, 2, 0, "shard ident")?;
+    let prefix_bits = parser.load_u8(6)?;
+    if prefix_bits > 60 {
+        return Err(TonProofError::InvalidProof(format!(
+            "Invalid shard prefix length: {prefix_bits}"
+        )));
+    }
+    let workchain = parser.load_i32(32)?;
+    let prefix = parser.load_u64(64)?;
+    let shard = prefix | (1 << (63 - prefix_bits));
+    Ok((workchain, shard as i64))
+}
+
+/// Verifies the Merkle proof of the block with `root_hash` and returns the root of the
+/// shard state after it, proved by `state_proof`.
+pub(crate) fn verify_state_proof(
+    block_proof: &Cell,
+    state_proof: &Cell,
+    root_hash: &TonHash,
+) -> Result<(BlockHeader, ArcCell), TonProofError> {
+    let header = BlockHeader::from_proof(block_proof, root_hash)?;
+    let state = MerkleProof::verify_cell(state_proof, &header.proved_state_hash()?)?;
+    Ok((header, state))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use super::BlockHeader;
+    use crate::cell::{Cell, TonCellError};
+    use crate::proof::tests::{block_with_state, merkle_proof, prune_refs};
+    use crate::proof::TonProofError;
+
+    #[test]
+    fn block_header_proof_works() -> Result<(), TonCellError> {
+        let empty = Arc::new(Cell::default());
+        let (block, state) = block_with_state(-1, 0, 7, true, empty.clone(), None, empty)?;
+        let hash = block.cell_hash();
+        let proof = merkle_proof(&prune_refs(&block, &[1, 2, 3]));
+
+        let header = BlockHeader::from_proof(&proof, &hash).unwrap();
+        assert_eq!(header.workchain, -1);
+        assert_eq!(header.seqno, 7);
+        assert!(header.key_block);
+        assert_eq!(header.end_lt, 1_000_005);
+        assert_eq!(header.state_hash, None);
+        assert!(BlockHeader::from_proof(&proof, &state.cell_hash()).is_err());
+
+        let proof = merkle_proof(&prune_refs(&block, &[1, 3]));
+        let header = BlockHeader::from_proof(&proof, &hash).unwrap();
+        assert_eq!(header.state_hash, Some(state.cell_hash()));
+
+        let known = HashMap::from([(7, hash)]);
+        assert_eq!(
+            BlockHeader::from_key_block_proof(&proof, &known).unwrap(),
+            header
+        );
+        let unknown = HashMap::from([(8, hash)]);
+        assert!(matches!(
+            BlockHeader::from_key_block_proof(&proof, &unknown),
+            Err(TonProofError::InvalidProof(_))
+        ));
+        Ok(())
+    }
+}
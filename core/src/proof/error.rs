@@ -0,0 +1,27 @@
+use thiserror::Error;
+
+use crate::cell::TonCellError;
+use crate::TonHash;
+
+#[derive(Error, Debug)]
+pub enum TonProofError {
+    #[error("TonCellError ({0})")]
+    TonCellError(#[from] TonCellError),
+
+    #[error("Invalid proof ({0})")]
+    InvalidProof(String),
+
+    #[error(
+        "Hash mismatch of {what} (expected: {}, actual: {})",
+        hex::encode(.expected),
+        hex::encode(.actual)
+    )]
+    HashMismatch {
+        what: String,
+        expected: TonHash,
+        actual: TonHash,
+    },
+
+    #[error("Pruned data ({0} is not included in the proof)")]
+    PrunedData(String),
+}
@@ -0,0 +1,126 @@
+use super::{ensure_not_pruned, TonProofError};
+use crate::cell::CellParser;
+
+/// Looks up the value with `key` of `key_len` bits in a dictionary of a proved tree without
+/// parsing the pruned branches of it.
+///
+/// `parser` must be positioned at the root edge of the dictionary, i.e. `Hashmap` or
+/// `HashmapAug`, not `HashmapE`. `read_leaf` is called with the parser positioned right after
+/// the label of the leaf, so for augmented dictionaries it has to skip the extra value before
+/// reading the value. `None` is returned if the proof shows that there is no such key.
+pub(crate) fn dict_lookup<T, F>(
+    parser: &mut CellParser,
+    key: &[u8],
+    key_len: usize,
+    read_leaf: F,
+) -> Result<Option<T>, TonProofError>
+where
+    F: FnOnce(&mut CellParser) -> Result<T, TonProofError>,
+{
+    lookup_impl(parser, key, 0, key_len, read_leaf)
+}
+
+fn lookup_impl<T, F>(
+    parser: &mut CellParser,
+    key: &[u8],
+    offset: usize,
+    key_len: usize,
+    read_leaf: F,
+) -> Result<Option<T>, TonProofError>
+where
+    F: FnOnce(&mut CellParser) -> Result<T, TonProofError>,
+{
+    let label_len = match match_label(parser, key, offset, key_len - offset)? {
+        Some(label_len) => label_len,
+        None => return Ok(None),
+    };
+    let offset = offset + label_len;
+    if offset == key_len {
+        return read_leaf(parser).map(Some);
+    }
+    let left = parser.next_reference()?;
+    let right = parser.next_reference()?;
+    let next = if key_bit(key, offset) { right } else { left };
+    ensure_not_pruned(&next, "dictionary edge")?;
+    lookup_impl(&mut next.parser(), key, offset + 1, key_len, read_leaf)
+}
+
+/// Reads `HmLabel` of at most `max_len` bits, returning its length if it is the prefix of
+/// the key at `offset`.
+fn match_label(
+    parser: &mut CellParser,
+    key: &[u8],
+    offset: usize,
+    max_len: usize,
+) -> Result<Option<usize>, TonProofError> {
+    let (len, same_bit) = if !parser.load_bit()? {
+        // hml_short$0
+        (parser.load_unary_length()?, None)
+    } else if !parser.load_bit()? {
+        // hml_long$10
+        (load_label_len(parser, max_len)?, None)
+    } else {
+        // hml_same$11
+        let bit = parser.load_bit()?;
+        (load_label_len(parser, max_len)?, Some(bit))
+    };
+    if len > max_len {
+        return Err(TonProofError::InvalidProof(format!(
+            "Dictionary label of {len} bits exceeds remaining key length {max_len}"
+        )));
+    }
+    for i in 0..len {
+        let bit = match same_bit {
+            Some(bit) => bit,
+            None => parser.load_bit()?,
+        };
+        if bit != key_bit(key, offset + i) {
+            return Ok(None);
+        }
+    }
+    Ok(Some(len))
+}
+
+/// Reads `#<= max_len`, i.e. a number of `ceil(log2(max_len + 1))` bits.
+fn load_label_len(parser: &mut CellParser, max_len: usize) -> Result<usize, TonProofError> {
+    let bit_len = (usize::BITS - max_len.leading_zeros()) as usize;
+    if bit_len == 0 {
+        return Ok(0);
+    }
+    Ok(parser.load_u32(bit_len)? as usize)
+}
+
+fn key_bit(key: &[u8], idx: usize) -> bool {
+    key[idx / 8] & (0x80 >> (idx % 8)) != 0
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::dict_lookup;
+    use crate::cell::dict::predefined_writers::val_writer_unsigned_min_size;
+    use crate::cell::{CellBuilder, TonCellError};
+
+    #[test]
+    fn dict_lookup_works() -> Result<(), TonCellError> {
+        let data = HashMap::from([(0u64, 3u64), (1, 5), (7, 11), (200, 13), (201, 17)]);
+        let cell = CellBuilder::new()
+            .store_dict(64, val_writer_unsigned_min_size, data.clone())?
+            .build()?;
+        for (key, value) in data {
+            let found = dict_lookup(&mut cell.parser(), &key.to_be_bytes(), 64, |parser| {
+                let bits = parser.remaining_bits();
+                Ok(parser.load_u64(bits)?)
+            })
+            .unwrap();
+            assert_eq!(found, Some(value));
+        }
+        for key in [2u64, 8, 202, u64::MAX] {
+            let found =
+                dict_lookup(&mut cell.parser(), &key.to_be_bytes(), 64, |_| Ok(())).unwrap();
+            assert_eq!(found, None);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+use super::block::BLOCK_TAG;
+use super::lookup::dict_lookup;
+use super::{
+    check_hash, check_tag, ensure_not_pruned, load_proved_ref, skip_currency_collection,
+    MerkleProof, TonProofError,
+};
+use crate::cell::Cell;
+use crate::{TonAddress, TonHash};
+
+const BLOCK_EXTRA_TAG: u32 = 0x4a33f6fd;
+const ACCOUNT_BLOCK_TAG: u32 = 0x5;
+
+/// Verifies the proof of inclusion of `transaction` of `address` with `lt` into the block
+/// with `block_root_hash`, e.g. the `proof` of `liteServer.getOneTransaction`.
+pub fn verify_transaction_proof(
+    block_root_hash: &TonHash,
+    proof: &Cell,
+    address: &TonAddress,
+    lt: u64,
+    transaction: &Cell,
+) -> Result<(), TonProofError> {
+    let block = MerkleProof::verify_cell(proof, block_root_hash)?;
+    let mut parser = block.parser();
+    check_tag(&mut parser, 32, BLOCK_TAG, "block")?;
+    let extra = block.reference(3)?;
+    ensure_not_pruned(extra, "block extra")?;
+
+    let mut parser = extra.parser();
+    check_tag(&mut parser, 32, BLOCK_EXTRA_TAG, "block extra")?;
+    let _in_msg_descr = parser.next_reference()?;
+    let _out_msg_descr = parser.next_reference()?;
+    let account_blocks = load_proved_ref(&mut parser, "account blocks")?;
+
+    let not_found = || {
+        TonProofError::InvalidProof(format!("Transaction {lt} of {address} is not in the block"))
+    };
+    // ShardAccountBlocks = HashmapAugE 256 AccountBlock CurrencyCollection
+    let mut parser = account_blocks.parser();
+    if !parser.load_bit()? {
+        return Err(not_found());
+    }
+    let root = load_proved_ref(&mut parser, "account blocks")?;
+    let tx_hash = dict_lookup(&mut root.parser(), &address.hash_part, 256, |parser| {
+        skip_currency_collection(parser)?;
+        // acc_trans#5 account_addr:bits256
+        //   transactions:(HashmapAug 64 ^Transaction CurrencyCollection)
+        //   state_update:^(HASH_UPDATE Account)
+        check_tag(parser, 4, ACCOUNT_BLOCK_TAG, "account block")?;
+        parser.skip_bits(256)?;
+        dict_lookup(parser, &lt.to_be_bytes(), 64, |parser| {
+            skip_currency_collection(parser)?;
+            Ok(parser.next_reference()?.get_hash(0))
+        })
+    })?
+    .flatten()
+    .ok_or_else(not_found)?;
+    check_hash("transaction", &tx_hash, &transaction.cell_hash())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::verify_transaction_proof;
+    use crate::cell::{Cell, CellBuilder, TonCellError};
+    use crate::proof::tests::{block_with_state, merkle_proof, prune_refs};
+    use crate::TonAddress;
+
+    #[test]
+    fn transaction_proof_works() -> Result<(), TonCellError> {
+        let address =
+            TonAddress::from_base64_url("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR")
+                .unwrap();
+        let transaction = CellBuilder::new()
+            .store_u32(32, 0x7)?
+            .store_u64(64, 1_000_003)?
+            .build()?;
+        let empty = Arc::new(Cell::default());
+        let account_block = CellBuilder::new()
+            // hml_long$10 n:(#<= 256) s:(n * Bit)
+            .store_u8(2, 0b10)?
+            .store_u32(9, 256)?
+            .store_slice(&address.hash_part)?
+            // currency collection
+            .store_coins(&0u32.into())?
+            .store_bit(false)?
+            // acc_trans#5 account_addr:bits256
+            .store_u8(4, 0x5)?
+            .store_slice(&address.hash_part)?
+            // hml_long$10 n:(#<= 64) s:(n * Bit)
+            .store_u8(2, 0b10)?
+            .store_u32(7, 64)?
+            .store_u64(64, 1_000_003)?
+            .store_coins(&0u32.into())?
+            .store_bit(false)?
+            .store_child(transaction.clone())?
+            .store_reference(&empty)?
+            .build()?;
+        let account_blocks = CellBuilder::new()
+            .store_bit(true)?
+            .store_child(account_block)?
+            .store_coins(&0u32.into())?
+            .store_bit(false)?
+            .build()?;
+        let block_extra = CellBuilder::new()
+            .store_u32(32, 0x4a33f6fd)?
+            .store_reference(&empty)?
+            .store_reference(&empty)?
+            .store_child(account_blocks)?
+            .store_slice(&[0; 64])?
+            .store_bit(false)?
+            .build()?;
+        let (block, _) =
+            block_with_state(0, 0, 5, false, empty.clone(), None, Arc::new(block_extra))?;
+        let extra_proof = prune_refs(block.reference(3)?, &[0, 1]);
+        let block_proof = prune_refs(&block, &[0, 1, 2]);
+        let block_proof = Arc::new(Cell::new(
+            block_proof.data().to_vec(),
+            block_proof.bit_len(),
+            vec![
+                block_proof.reference(0)?.clone(),
+                block_proof.reference(1)?.clone(),
+                block_proof.reference(2)?.clone(),
+                extra_proof,
+            ],
+            false,
+        )?);
+        let proof = merkle_proof(&block_proof);
+        let hash = block.cell_hash();
+
+        verify_transaction_proof(&hash, &proof, &address, 1_000_003, &transaction).unwrap();
+        assert!(
+            verify_transaction_proof(&hash, &proof, &address, 1_000_004, &transaction).is_err()
+        );
+        let other = CellBuilder::new().store_u32(32, 0x8)?.build()?;
+        assert!(verify_transaction_proof(&hash, &proof, &address, 1_000_003, &other).is_err());
+        let pruned = merkle_proof(&prune_refs(&block, &[3]));
+        assert!(
+            verify_transaction_proof(&hash, &pruned, &address, 1_000_003, &transaction).is_err()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,471 @@
+//! (De)serialization of Rust types mirroring TL-B schemas.
+//!
+//! A type implementing [`TlbType`] can be read from and written to cells. Instead of writing
+//! [`CellParser`] and [`CellBuilder`] code by hand, the implementation can be derived by
+//! [`Tlb`], e.g. for `transfer#0f8a7ea5 query_id:uint64 amount:(VarUInteger 16)
+//! destination:MsgAddress response_destination:MsgAddress custom_payload:(Maybe ^Cell)
+//! forward_ton_amount:(VarUInteger 16) forward_payload:(Either Cell ^Cell) = JettonMsg`:
+//!
+//! ```
+//! use num_bigint::BigUint;
+//! use tonlib_core::cell::ArcCell;
+//! use tonlib_core::tlb::{Tlb, TlbType};
+//! use tonlib_core::TonAddress;
+//!
+//! #[derive(Debug, PartialEq, Tlb)]
+//! #[tlb(tag = "#0f8a7ea5")]
+//! struct JettonTransfer {
+//!     query_id: u64,
+//!     #[tlb(coins)]
+//!     amount: BigUint,
+//!     destination: TonAddress,
+//!     response_destination: TonAddress,
+//!     #[tlb(ref)]
+//!     custom_payload: Option<ArcCell>,
+//!     #[tlb(coins)]
+//!     forward_ton_amount: BigUint,
+//!     #[tlb(either_ref)]
+//!     forward_payload: ArcCell,
+//! }
+//!
+//! let transfer = JettonTransfer {
+//!     query_id: 1,
+//!     amount: BigUint::from(100u32),
+//!     destination: TonAddress::NULL,
+//!     response_destination: TonAddress::NULL,
+//!     custom_payload: None,
+//!     forward_ton_amount: BigUint::from(1u32),
+//!     forward_payload: Default::default(),
+//! };
+//! let cell = transfer.to_cell().unwrap();
+//! assert_eq!(JettonTransfer::from_cell(&cell).unwrap(), transfer);
+//! ```
+
+use std::sync::Arc;
+
+use num_bigint::BigUint;
+pub use tonlib_derive::Tlb;
+
+use crate::cell::{
+    ArcCell, BagOfCells, Cell, CellBuilder, CellParser, EitherCellLayout, TonCellError,
+};
+use crate::{TonAddress, TonHash};
+
+/// Type that can be read from and written to cells.
+pub trait TlbType: Sized {
+    fn read(parser: &mut CellParser) -> Result<Self, TonCellError>;
+
+    fn write(&self, builder: &mut CellBuilder) -> Result<(), TonCellError>;
+
+    /// Reads the value stored in the next reference, i.e. `^T`.
+    fn read_ref(parser: &mut CellParser) -> Result<Self, TonCellError> {
+        Self::from_cell(&*parser.next_reference()?)
+    }
+
+    /// Writes the value to a new cell stored as a reference, i.e. `^T`.
+    fn write_ref(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        builder.store_child(self.to_cell()?)?;
+        Ok(())
+    }
+
+    /// Reads the value from `cell`, failing if any data remains unread.
+    fn from_cell(cell: &Cell) -> Result<Self, TonCellError> {
+        cell.parse_fully(Self::read)
+    }
+
+    fn to_cell(&self) -> Result<Cell, TonCellError> {
+        let mut builder = CellBuilder::new();
+        self.write(&mut builder)?;
+        builder.build()
+    }
+
+    /// Reads the value from the single root of a serialized bag of cells.
+    fn from_boc(boc: &[u8]) -> Result<Self, TonCellError> {
+        Self::from_cell(BagOfCells::parse(boc)?.single_root()?)
+    }
+
+    fn to_boc(&self, has_crc32: bool) -> Result<Vec<u8>, TonCellError> {
+        BagOfCells::from_root(self.to_cell()?).serialize(has_crc32)
+    }
+}
+
+/// Integer that can be read from and written to cells with an arbitrary number of bits,
+/// i.e. `uintN` or `intN`.
+pub trait TlbBits: Sized {
+    fn read_bits(parser: &mut CellParser, bit_len: usize) -> Result<Self, TonCellError>;
+
+    fn write_bits(&self, builder: &mut CellBuilder, bit_len: usize) -> Result<(), TonCellError>;
+}
+
+macro_rules! impl_tlb_int {
+    ($($ty:ty;)*) => {
+        $(
+            /// Integer of its full size, e.g. `uint32` for `u32`.
+            impl TlbType for $ty {
+                fn read(parser: &mut CellParser) -> Result<Self, TonCellError> {
+                    Self::read_bits(parser, <$ty>::BITS as usize)
+                }
+
+                fn write(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+                    self.write_bits(builder, <$ty>::BITS as usize)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_tlb_bits_unsigned {
+    ($($ty:ty => $load:ident, $store:ident($store_ty:ty);)*) => {
+        $(
+            impl TlbBits for $ty {
+                fn read_bits(parser: &mut CellParser, bit_len: usize) -> Result<Self, TonCellError> {
+                    parser.$load(bit_len)
+                }
+
+                fn write_bits(
+                    &self,
+                    builder: &mut CellBuilder,
+                    bit_len: usize,
+                ) -> Result<(), TonCellError> {
+                    builder.$store(bit_len, <$store_ty>::from(*self))?;
+                    Ok(())
+                }
+            }
+        )*
+        impl_tlb_int! { $($ty;)* }
+    };
+}
+
+macro_rules! impl_tlb_bits_signed {
+    ($($ty:ty;)*) => {
+        $(
+            impl TlbBits for $ty {
+                fn read_bits(parser: &mut CellParser, bit_len: usize) -> Result<Self, TonCellError> {
+                    let value = load_signed(parser, bit_len)?;
+                    <$ty>::try_from(value).map_err(|_| {
+                        TonCellError::CellParserError(format!(
+                            "Value {value} doesn't fit in {}",
+                            stringify!($ty)
+                        ))
+                    })
+                }
+
+                fn write_bits(
+                    &self,
+                    builder: &mut CellBuilder,
+                    bit_len: usize,
+                ) -> Result<(), TonCellError> {
+                    store_signed(builder, bit_len, i64::from(*self))
+                }
+            }
+        )*
+        impl_tlb_int! { $($ty;)* }
+    };
+}
+
+impl_tlb_bits_unsigned! {
+    u8 => load_u8, store_u8(u8);
+    u16 => load_u16, store_u32(u32);
+    u32 => load_u32, store_u32(u32);
+    u64 => load_u64, store_u64(u64);
+}
+
+impl_tlb_bits_signed! {
+    i8;
+    i16;
+    i32;
+    i64;
+}
+
+/// Loads a two's complement integer of `bit_len` bits.
+fn load_signed(parser: &mut CellParser, bit_len: usize) -> Result<i64, TonCellError> {
+    if bit_len == 0 || bit_len > 64 {
+        return Err(TonCellError::CellParserError(format!(
+            "Invalid signed integer length: {bit_len}"
+        )));
+    }
+    let shift = 64 - bit_len;
+    Ok(((parser.load_u64(bit_len)? << shift) as i64) >> shift)
+}
+
+/// Stores `value` as a two's complement integer of `bit_len` bits.
+fn store_signed(builder: &mut CellBuilder, bit_len: usize, value: i64) -> Result<(), TonCellError> {
+    if bit_len == 0 || bit_len > 64 {
+        return Err(TonCellError::CellBuilderError(format!(
+            "Invalid signed integer length: {bit_len}"
+        )));
+    }
+    let shift = 64 - bit_len;
+    if ((value << shift) >> shift) != value {
+        return Err(TonCellError::CellBuilderError(format!(
+            "Value {value} doesn't fit in {bit_len} bits"
+        )));
+    }
+    builder.store_u64(bit_len, (value as u64) << shift >> shift)?;
+    Ok(())
+}
+
+impl TlbBits for BigUint {
+    fn read_bits(parser: &mut CellParser, bit_len: usize) -> Result<Self, TonCellError> {
+        parser.load_uint(bit_len)
+    }
+
+    fn write_bits(&self, builder: &mut CellBuilder, bit_len: usize) -> Result<(), TonCellError> {
+        builder.store_uint(bit_len, self)?;
+        Ok(())
+    }
+}
+
+impl TlbType for bool {
+    fn read(parser: &mut CellParser) -> Result<Self, TonCellError> {
+        parser.load_bit()
+    }
+
+    fn write(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        builder.store_bit(*self)?;
+        Ok(())
+    }
+}
+
+/// `bits256`, e.g. a hash or a public key.
+impl TlbType for TonHash {
+    fn read(parser: &mut CellParser) -> Result<Self, TonCellError> {
+        let mut hash = [0; 32];
+        parser.load_slice(&mut hash)?;
+        Ok(hash)
+    }
+
+    fn write(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        builder.store_slice(self)?;
+        Ok(())
+    }
+}
+
+/// `MsgAddress`, with `addr_none` read as [`TonAddress::NULL`].
+impl TlbType for TonAddress {
+    fn read(parser: &mut CellParser) -> Result<Self, TonCellError> {
+        parser.load_address()
+    }
+
+    fn write(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        builder.store_address(self)?;
+        Ok(())
+    }
+}
+
+/// `Cell`, i.e. all the remaining data and references, or `^Cell` when read as a reference.
+impl TlbType for ArcCell {
+    fn read(parser: &mut CellParser) -> Result<Self, TonCellError> {
+        Ok(Arc::new(parser.load_remaining()?))
+    }
+
+    fn write(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        builder.store_cell(self)?;
+        Ok(())
+    }
+
+    fn read_ref(parser: &mut CellParser) -> Result<Self, TonCellError> {
+        parser.next_reference()
+    }
+
+    fn write_ref(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        builder.store_reference(self)?;
+        Ok(())
+    }
+}
+
+/// `Maybe T`, or `Maybe ^T` when read as a reference.
+impl<T: TlbType> TlbType for Option<T> {
+    fn read(parser: &mut CellParser) -> Result<Self, TonCellError> {
+        if parser.load_bit()? {
+            Ok(Some(T::read(parser)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn write(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        builder.store_bit(self.is_some())?;
+        if let Some(value) = self {
+            value.write(builder)?;
+        }
+        Ok(())
+    }
+
+    fn read_ref(parser: &mut CellParser) -> Result<Self, TonCellError> {
+        if parser.load_bit()? {
+            Ok(Some(T::read_ref(parser)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn write_ref(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        builder.store_bit(self.is_some())?;
+        if let Some(value) = self {
+            value.write_ref(builder)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads `Either T ^T`. When stored inline, the value is read from all the remaining data
+/// and references.
+pub fn read_either_ref<T: TlbType>(parser: &mut CellParser) -> Result<T, TonCellError> {
+    T::from_cell(&*parser.load_either_cell_or_cell_ref()?)
+}
+
+/// Writes `Either T ^T`, storing the value inline if it fits into the remaining bits.
+pub fn write_either_ref<T: TlbType>(
+    value: &T,
+    builder: &mut CellBuilder,
+) -> Result<(), TonCellError> {
+    builder.store_either_cell_or_cell_ref(&Arc::new(value.to_cell()?), EitherCellLayout::Native)?;
+    Ok(())
+}
+
+/// Loads a constructor tag of `bit_len` bits, failing if it isn't `expected`.
+pub fn check_tag(
+    parser: &mut CellParser,
+    bit_len: usize,
+    expected: u64,
+    type_name: &str,
+) -> Result<(), TonCellError> {
+    let tag = parser.load_u64(bit_len)?;
+    if tag != expected {
+        return Err(TonCellError::CellParserError(format!(
+            "Invalid tag of {type_name}: {tag:#x}, expected {expected:#x}"
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use num_bigint::BigUint;
+
+    use super::{Tlb, TlbType};
+    use crate::cell::{ArcCell, Cell, CellBuilder, TonCellError};
+    use crate::TonAddress;
+
+    #[derive(Debug, Clone, PartialEq, Tlb)]
+    #[tlb(tag = "#0f8a7ea5")]
+    struct JettonTransfer {
+        query_id: u64,
+        #[tlb(coins)]
+        amount: BigUint,
+        destination: TonAddress,
+        response_destination: TonAddress,
+        #[tlb(ref)]
+        custom_payload: Option<ArcCell>,
+        #[tlb(coins)]
+        forward_ton_amount: BigUint,
+        #[tlb(either_ref)]
+        forward_payload: ArcCell,
+    }
+
+    #[derive(Debug, Clone, PartialEq, Tlb)]
+    struct Point(#[tlb(bits = 10)] u16, #[tlb(bits = 10)] i32);
+
+    #[derive(Debug, Clone, PartialEq, Tlb)]
+    enum Shape {
+        #[tlb(tag = "$00")]
+        Empty,
+        #[tlb(tag = "$01")]
+        Dot(Point),
+        #[tlb(tag = "$10")]
+        Line {
+            from: Point,
+            #[tlb(ref)]
+            to: Point,
+            visible: Option<bool>,
+        },
+    }
+
+    #[test]
+    fn derived_tlb_type_matches_builder() -> Result<(), TonCellError> {
+        let address =
+            TonAddress::from_base64_url("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR")
+                .unwrap();
+        let payload = Arc::new(
+            CellBuilder::new()
+                .store_u32(32, 0)?
+                .store_string("hi")?
+                .build()?,
+        );
+        let transfer = JettonTransfer {
+            query_id: 7,
+            amount: BigUint::from(1_000_000u32),
+            destination: address.clone(),
+            response_destination: TonAddress::NULL,
+            custom_payload: None,
+            forward_ton_amount: BigUint::from(1u32),
+            forward_payload: payload.clone(),
+        };
+        let cell = transfer.to_cell()?;
+        let expected = CellBuilder::new()
+            .store_u32(32, 0x0f8a7ea5)?
+            .store_u64(64, 7)?
+            .store_coins(&BigUint::from(1_000_000u32))?
+            .store_address(&address)?
+            .store_address(&TonAddress::NULL)?
+            .store_maybe_cell_ref(&None)?
+            .store_coins(&BigUint::from(1u32))?
+            .store_bit(false)?
+            .store_cell(&payload)?
+            .build()?;
+        assert_eq!(cell, expected);
+        assert_eq!(JettonTransfer::from_cell(&cell)?, transfer);
+        assert_eq!(JettonTransfer::from_boc(&transfer.to_boc(true)?)?, transfer);
+
+        let with_payload = JettonTransfer {
+            custom_payload: Some(payload.clone()),
+            forward_payload: Arc::new(Cell::new(vec![0; 120], 960, vec![], false)?),
+            ..transfer
+        };
+        let cell = with_payload.to_cell()?;
+        assert_eq!(cell.references().len(), 2);
+        assert_eq!(JettonTransfer::from_cell(&cell)?, with_payload);
+
+        let wrong_tag = CellBuilder::new().store_u32(32, 0x178d4519)?.build()?;
+        assert!(JettonTransfer::from_cell(&wrong_tag).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn derived_tlb_enum_works() -> Result<(), TonCellError> {
+        let line = Shape::Line {
+            from: Point(1, -1),
+            to: Point(1023, 511),
+            visible: Some(true),
+        };
+        let cell = line.to_cell()?;
+        let expected = CellBuilder::new()
+            .store_u8(2, 0b10)?
+            .store_u32(10, 1)?
+            .store_u32(10, 0x3ff)?
+            .store_child(
+                CellBuilder::new()
+                    .store_u32(10, 1023)?
+                    .store_u32(10, 511)?
+                    .build()?,
+            )?
+            .store_bit(true)?
+            .store_bit(true)?
+            .build()?;
+        assert_eq!(cell, expected);
+        assert_eq!(Shape::from_cell(&cell)?, line);
+
+        for shape in [Shape::Empty, Shape::Dot(Point(0, 0))] {
+            assert_eq!(Shape::from_cell(&shape.to_cell()?)?, shape);
+        }
+        assert!(Point(0, 512).to_cell().is_err());
+        let unknown = CellBuilder::new().store_u8(2, 0b11)?.build()?;
+        assert!(Shape::from_cell(&unknown).is_err());
+        let extra_data = CellBuilder::new().store_u8(3, 0)?.build()?;
+        assert!(Shape::from_cell(&extra_data).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,901 @@
+//! Typed transactions, e.g. decoded from the `data` of `raw.transaction` returned by tonlib.
+//!
+//! See https://github.com/ton-blockchain/ton/blob/master/crypto/block/block.tlb for the schemas.
+
+use std::collections::HashMap;
+
+use num_bigint::BigUint;
+
+use crate::cell::dict::predefined_readers::{key_reader_u16, val_reader_ref_cell};
+use crate::cell::{ArcCell, BagOfCells, Cell, CellParser, TonCellError};
+use crate::tlb::{check_tag, TlbType};
+use crate::TonHash;
+
+/// Transaction of an account, according to TL-B schema:
+///
+/// ```raw
+/// transaction$0111 account_addr:bits256 lt:uint64
+///   prev_trans_hash:bits256 prev_trans_lt:uint64 now:uint32
+///   outmsg_cnt:uint15
+///   orig_status:AccountStatus end_status:AccountStatus
+///   ^[ in_msg:(Maybe ^(Message Any)) out_msgs:(HashmapE 15 ^(Message Any)) ]
+///   total_fees:CurrencyCollection state_update:^(HASH_UPDATE Account)
+///   description:^TransactionDescr = Transaction;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transaction {
+    /// Address of the account within its workchain.
+    pub account_addr: TonHash,
+    pub lt: u64,
+    pub prev_trans_hash: TonHash,
+    pub prev_trans_lt: u64,
+    /// Unix time of the block.
+    pub now: u32,
+    pub outmsg_cnt: u16,
+    pub orig_status: AccountStatus,
+    pub end_status: AccountStatus,
+    pub in_msg: Option<ArcCell>,
+    /// Messages created by the transaction, ordered by their index.
+    pub out_msgs: Vec<ArcCell>,
+    pub total_fees: CurrencyCollection,
+    pub state_update: HashUpdate,
+    pub description: TransactionDescr,
+}
+
+impl Transaction {
+    const TAG: u64 = 0b0111;
+
+    pub fn parse(cell: &Cell) -> Result<Transaction, TonCellError> {
+        cell.parse_fully(Self::read)
+    }
+
+    /// Parses the transaction from the single root of a serialized bag of cells.
+    pub fn from_boc(boc: &[u8]) -> Result<Transaction, TonCellError> {
+        Self::parse(BagOfCells::parse(boc)?.single_root()?)
+    }
+
+    pub fn read(parser: &mut CellParser) -> Result<Transaction, TonCellError> {
+        check_tag(parser, 4, Self::TAG, "Transaction")?;
+        let account_addr = TonHash::read(parser)?;
+        let lt = parser.load_u64(64)?;
+        let prev_trans_hash = TonHash::read(parser)?;
+        let prev_trans_lt = parser.load_u64(64)?;
+        let now = parser.load_u32(32)?;
+        let outmsg_cnt = parser.load_u16(15)?;
+        let orig_status = AccountStatus::read(parser)?;
+        let end_status = AccountStatus::read(parser)?;
+        let (in_msg, out_msgs) = parser.next_reference()?.parse_fully(|parser| {
+            let in_msg = parser.load_maybe_cell_ref()?;
+            let out_msgs = parser.load_maybe_dict(15, key_reader_u16, val_reader_ref_cell)?;
+            Ok((in_msg, out_msgs))
+        })?;
+        let total_fees = CurrencyCollection::read(parser)?;
+        let state_update = parser.next_reference()?.parse_fully(HashUpdate::read)?;
+        let description = parser
+            .next_reference()?
+            .parse_fully(TransactionDescr::read)?;
+        Ok(Transaction {
+            account_addr,
+            lt,
+            prev_trans_hash,
+            prev_trans_lt,
+            now,
+            outmsg_cnt,
+            orig_status,
+            end_status,
+            in_msg,
+            out_msgs: sorted_values(out_msgs),
+            total_fees,
+            state_update,
+            description,
+        })
+    }
+
+    /// Returns the exit code of the compute phase, `None` if it was skipped or there's none.
+    pub fn exit_code(&self) -> Option<i32> {
+        match self.description.compute_phase()? {
+            ComputePhase::Vm(vm) => Some(vm.exit_code),
+            ComputePhase::Skipped(_) => None,
+        }
+    }
+
+    /// Returns `true` if the transaction isn't aborted and its compute and action phases,
+    /// if any, succeeded.
+    pub fn is_success(&self) -> bool {
+        let compute_success = match self.description.compute_phase() {
+            Some(ComputePhase::Vm(vm)) => vm.success,
+            Some(ComputePhase::Skipped(_)) => false,
+            None => true,
+        };
+        let action_success = self
+            .description
+            .action_phase()
+            .is_none_or(|action| action.success);
+        !self.description.aborted() && compute_success && action_success
+    }
+}
+
+/// Returns the values of a dictionary ordered by their keys.
+pub(crate) fn sorted_values<K: Ord, V>(map: HashMap<K, V>) -> Vec<V> {
+    let mut entries: Vec<_> = map.into_iter().collect();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    entries.into_iter().map(|(_, value)| value).collect()
+}
+
+/// ```raw
+/// acc_state_uninit$00 = AccountStatus;
+/// acc_state_frozen$01 = AccountStatus;
+/// acc_state_active$10 = AccountStatus;
+/// acc_state_nonexist$11 = AccountStatus;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccountStatus {
+    Uninit,
+    Frozen,
+    Active,
+    Nonexistent,
+}
+
+impl AccountStatus {
+    pub fn read(parser: &mut CellParser) -> Result<AccountStatus, TonCellError> {
+        let status = match parser.load_u8(2)? {
+            0b00 => AccountStatus::Uninit,
+            0b01 => AccountStatus::Frozen,
+            0b10 => AccountStatus::Active,
+            _ => AccountStatus::Nonexistent,
+        };
+        Ok(status)
+    }
+}
+
+/// Amount of TON and, if any, of extra currencies:
+///
+/// ```raw
+/// currencies$_ grams:Grams other:ExtraCurrencyCollection = CurrencyCollection;
+/// extra_currencies$_ dict:(HashmapE 32 (VarUInteger 32)) = ExtraCurrencyCollection;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurrencyCollection {
+    pub grams: BigUint,
+    /// Root of the dictionary of extra currencies, `None` if there are none.
+    pub other: Option<ArcCell>,
+}
+
+impl CurrencyCollection {
+    pub fn read(parser: &mut CellParser) -> Result<CurrencyCollection, TonCellError> {
+        let grams = parser.load_coins()?;
+        let other = parser.load_maybe_cell_ref()?;
+        Ok(CurrencyCollection { grams, other })
+    }
+}
+
+/// ```raw
+/// update_hashes#72 {X:Type} old_hash:bits256 new_hash:bits256 = HASH_UPDATE X;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashUpdate {
+    pub old_hash: TonHash,
+    pub new_hash: TonHash,
+}
+
+impl HashUpdate {
+    const TAG: u64 = 0x72;
+
+    pub fn read(parser: &mut CellParser) -> Result<HashUpdate, TonCellError> {
+        check_tag(parser, 8, Self::TAG, "HASH_UPDATE")?;
+        let old_hash = TonHash::read(parser)?;
+        let new_hash = TonHash::read(parser)?;
+        Ok(HashUpdate { old_hash, new_hash })
+    }
+}
+
+/// Description of a transaction, i.e. its kind and phases, according to TL-B schema:
+///
+/// ```raw
+/// trans_ord$0000 credit_first:Bool
+///   storage_ph:(Maybe TrStoragePhase) credit_ph:(Maybe TrCreditPhase)
+///   compute_ph:TrComputePhase action:(Maybe ^TrActionPhase)
+///   aborted:Bool bounce:(Maybe TrBouncePhase) destroyed:Bool = TransactionDescr;
+/// trans_storage$0001 storage_ph:TrStoragePhase = TransactionDescr;
+/// trans_tick_tock$001 is_tock:Bool storage_ph:TrStoragePhase compute_ph:TrComputePhase
+///   action:(Maybe ^TrActionPhase) aborted:Bool destroyed:Bool = TransactionDescr;
+/// split_prepare$0100 split_info:SplitMergeInfo storage_ph:(Maybe TrStoragePhase)
+///   compute_ph:TrComputePhase action:(Maybe ^TrActionPhase)
+///   aborted:Bool destroyed:Bool = TransactionDescr;
+/// split_install$0101 split_info:SplitMergeInfo prepare_transaction:^Transaction
+///   installed:Bool = TransactionDescr;
+/// merge_prepare$0110 split_info:SplitMergeInfo storage_ph:TrStoragePhase
+///   aborted:Bool = TransactionDescr;
+/// merge_install$0111 split_info:SplitMergeInfo prepare_transaction:^Transaction
+///   storage_ph:(Maybe TrStoragePhase) credit_ph:(Maybe TrCreditPhase)
+///   compute_ph:TrComputePhase action:(Maybe ^TrActionPhase)
+///   aborted:Bool destroyed:Bool = TransactionDescr;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransactionDescr {
+    Ordinary(OrdinaryTransaction),
+    Storage(StoragePhase),
+    TickTock(TickTockTransaction),
+    SplitPrepare(SplitPrepareTransaction),
+    SplitInstall {
+        split_info: SplitMergeInfo,
+        /// Cell of the `Transaction` preparing the split.
+        prepare_transaction: ArcCell,
+        installed: bool,
+    },
+    MergePrepare {
+        split_info: SplitMergeInfo,
+        storage_ph: StoragePhase,
+        aborted: bool,
+    },
+    MergeInstall(MergeInstallTransaction),
+}
+
+/// Ordinary transaction, i.e. processing of an inbound message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrdinaryTransaction {
+    /// Whether the credit phase precedes the storage phase, i.e. the inbound message is bounceable.
+    pub credit_first: bool,
+    pub storage_ph: Option<StoragePhase>,
+    pub credit_ph: Option<CreditPhase>,
+    pub compute_ph: ComputePhase,
+    pub action: Option<ActionPhase>,
+    pub aborted: bool,
+    pub bounce: Option<BouncePhase>,
+    pub destroyed: bool,
+}
+
+/// Tick or tock transaction of a special account, run by validators in every masterchain block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TickTockTransaction {
+    pub is_tock: bool,
+    pub storage_ph: StoragePhase,
+    pub compute_ph: ComputePhase,
+    pub action: Option<ActionPhase>,
+    pub aborted: bool,
+    pub destroyed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitPrepareTransaction {
+    pub split_info: SplitMergeInfo,
+    pub storage_ph: Option<StoragePhase>,
+    pub compute_ph: ComputePhase,
+    pub action: Option<ActionPhase>,
+    pub aborted: bool,
+    pub destroyed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeInstallTransaction {
+    pub split_info: SplitMergeInfo,
+    /// Cell of the `Transaction` preparing the merge.
+    pub prepare_transaction: ArcCell,
+    pub storage_ph: Option<StoragePhase>,
+    pub credit_ph: Option<CreditPhase>,
+    pub compute_ph: ComputePhase,
+    pub action: Option<ActionPhase>,
+    pub aborted: bool,
+    pub destroyed: bool,
+}
+
+impl TransactionDescr {
+    pub fn read(parser: &mut CellParser) -> Result<TransactionDescr, TonCellError> {
+        // trans_tick_tock$001 is checked before trans_storage$0001 and trans_ord$0000
+        let descr = match parser.load_u8(2)? {
+            0b00 if parser.load_bit()? => TransactionDescr::TickTock(TickTockTransaction {
+                is_tock: parser.load_bit()?,
+                storage_ph: StoragePhase::read(parser)?,
+                compute_ph: ComputePhase::read(parser)?,
+                action: load_action_phase(parser)?,
+                aborted: parser.load_bit()?,
+                destroyed: parser.load_bit()?,
+            }),
+            0b00 if parser.load_bit()? => TransactionDescr::Storage(StoragePhase::read(parser)?),
+            0b00 => TransactionDescr::Ordinary(OrdinaryTransaction {
+                credit_first: parser.load_bit()?,
+                storage_ph: load_maybe(parser, StoragePhase::read)?,
+                credit_ph: load_maybe(parser, CreditPhase::read)?,
+                compute_ph: ComputePhase::read(parser)?,
+                action: load_action_phase(parser)?,
+                aborted: parser.load_bit()?,
+                bounce: load_maybe(parser, BouncePhase::read)?,
+                destroyed: parser.load_bit()?,
+            }),
+            0b01 => Self::read_split_merge(parser)?,
+            tag => {
+                return Err(TonCellError::CellParserError(format!(
+                    "Invalid tag of TransactionDescr: {tag:#04b}"
+                )))
+            }
+        };
+        Ok(descr)
+    }
+
+    /// Reads the descriptions of split and merge transactions, after the first 2 bits of the tag.
+    fn read_split_merge(parser: &mut CellParser) -> Result<TransactionDescr, TonCellError> {
+        let descr = match parser.load_u8(2)? {
+            0b00 => TransactionDescr::SplitPrepare(SplitPrepareTransaction {
+                split_info: SplitMergeInfo::read(parser)?,
+                storage_ph: load_maybe(parser, StoragePhase::read)?,
+                compute_ph: ComputePhase::read(parser)?,
+                action: load_action_phase(parser)?,
+                aborted: parser.load_bit()?,
+                destroyed: parser.load_bit()?,
+            }),
+            0b01 => TransactionDescr::SplitInstall {
+                split_info: SplitMergeInfo::read(parser)?,
+                prepare_transaction: parser.next_reference()?,
+                installed: parser.load_bit()?,
+            },
+            0b10 => TransactionDescr::MergePrepare {
+                split_info: SplitMergeInfo::read(parser)?,
+                storage_ph: StoragePhase::read(parser)?,
+                aborted: parser.load_bit()?,
+            },
+            _ => TransactionDescr::MergeInstall(MergeInstallTransaction {
+                split_info: SplitMergeInfo::read(parser)?,
+                prepare_transaction: parser.next_reference()?,
+                storage_ph: load_maybe(parser, StoragePhase::read)?,
+                credit_ph: load_maybe(parser, CreditPhase::read)?,
+                compute_ph: ComputePhase::read(parser)?,
+                action: load_action_phase(parser)?,
+                aborted: parser.load_bit()?,
+                destroyed: parser.load_bit()?,
+            }),
+        };
+        Ok(descr)
+    }
+
+    pub fn storage_phase(&self) -> Option<&StoragePhase> {
+        match self {
+            TransactionDescr::Ordinary(t) => t.storage_ph.as_ref(),
+            TransactionDescr::Storage(storage_ph) => Some(storage_ph),
+            TransactionDescr::TickTock(t) => Some(&t.storage_ph),
+            TransactionDescr::SplitPrepare(t) => t.storage_ph.as_ref(),
+            TransactionDescr::SplitInstall { .. } => None,
+            TransactionDescr::MergePrepare { storage_ph, .. } => Some(storage_ph),
+            TransactionDescr::MergeInstall(t) => t.storage_ph.as_ref(),
+        }
+    }
+
+    pub fn credit_phase(&self) -> Option<&CreditPhase> {
+        match self {
+            TransactionDescr::Ordinary(t) => t.credit_ph.as_ref(),
+            TransactionDescr::MergeInstall(t) => t.credit_ph.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn compute_phase(&self) -> Option<&ComputePhase> {
+        match self {
+            TransactionDescr::Ordinary(t) => Some(&t.compute_ph),
+            TransactionDescr::TickTock(t) => Some(&t.compute_ph),
+            TransactionDescr::SplitPrepare(t) => Some(&t.compute_ph),
+            TransactionDescr::MergeInstall(t) => Some(&t.compute_ph),
+            _ => None,
+        }
+    }
+
+    pub fn action_phase(&self) -> Option<&ActionPhase> {
+        match self {
+            TransactionDescr::Ordinary(t) => t.action.as_ref(),
+            TransactionDescr::TickTock(t) => t.action.as_ref(),
+            TransactionDescr::SplitPrepare(t) => t.action.as_ref(),
+            TransactionDescr::MergeInstall(t) => t.action.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn bounce_phase(&self) -> Option<&BouncePhase> {
+        match self {
+            TransactionDescr::Ordinary(t) => t.bounce.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn aborted(&self) -> bool {
+        match self {
+            TransactionDescr::Ordinary(t) => t.aborted,
+            TransactionDescr::Storage(_) => false,
+            TransactionDescr::TickTock(t) => t.aborted,
+            TransactionDescr::SplitPrepare(t) => t.aborted,
+            TransactionDescr::SplitInstall { .. } => false,
+            TransactionDescr::MergePrepare { aborted, .. } => *aborted,
+            TransactionDescr::MergeInstall(t) => t.aborted,
+        }
+    }
+}
+
+/// ```raw
+/// split_merge_info$_ cur_shard_pfx_len:(## 6) acc_split_depth:(## 6)
+///   this_addr:bits256 sibling_addr:bits256 = SplitMergeInfo;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitMergeInfo {
+    pub cur_shard_pfx_len: u8,
+    pub acc_split_depth: u8,
+    pub this_addr: TonHash,
+    pub sibling_addr: TonHash,
+}
+
+impl SplitMergeInfo {
+    pub fn read(parser: &mut CellParser) -> Result<SplitMergeInfo, TonCellError> {
+        Ok(SplitMergeInfo {
+            cur_shard_pfx_len: parser.load_u8(6)?,
+            acc_split_depth: parser.load_u8(6)?,
+            this_addr: TonHash::read(parser)?,
+            sibling_addr: TonHash::read(parser)?,
+        })
+    }
+}
+
+/// ```raw
+/// tr_phase_storage$_ storage_fees_collected:Grams storage_fees_due:(Maybe Grams)
+///   status_change:AccStatusChange = TrStoragePhase;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoragePhase {
+    pub storage_fees_collected: BigUint,
+    /// Storage fees the account couldn't pay.
+    pub storage_fees_due: Option<BigUint>,
+    pub status_change: AccStatusChange,
+}
+
+impl StoragePhase {
+    pub fn read(parser: &mut CellParser) -> Result<StoragePhase, TonCellError> {
+        Ok(StoragePhase {
+            storage_fees_collected: parser.load_coins()?,
+            storage_fees_due: load_maybe(parser, |parser| parser.load_coins())?,
+            status_change: AccStatusChange::read(parser)?,
+        })
+    }
+}
+
+/// ```raw
+/// acst_unchanged$0 = AccStatusChange;
+/// acst_frozen$10 = AccStatusChange;
+/// acst_deleted$11 = AccStatusChange;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AccStatusChange {
+    Unchanged,
+    Frozen,
+    Deleted,
+}
+
+impl AccStatusChange {
+    pub fn read(parser: &mut CellParser) -> Result<AccStatusChange, TonCellError> {
+        let change = if !parser.load_bit()? {
+            AccStatusChange::Unchanged
+        } else if !parser.load_bit()? {
+            AccStatusChange::Frozen
+        } else {
+            AccStatusChange::Deleted
+        };
+        Ok(change)
+    }
+}
+
+/// ```raw
+/// tr_phase_credit$_ due_fees_collected:(Maybe Grams) credit:CurrencyCollection = TrCreditPhase;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreditPhase {
+    pub due_fees_collected: Option<BigUint>,
+    pub credit: CurrencyCollection,
+}
+
+impl CreditPhase {
+    pub fn read(parser: &mut CellParser) -> Result<CreditPhase, TonCellError> {
+        Ok(CreditPhase {
+            due_fees_collected: load_maybe(parser, |parser| parser.load_coins())?,
+            credit: CurrencyCollection::read(parser)?,
+        })
+    }
+}
+
+/// ```raw
+/// tr_phase_compute_skipped$0 reason:ComputeSkipReason = TrComputePhase;
+/// tr_phase_compute_vm$1 success:Bool msg_state_used:Bool account_activated:Bool gas_fees:Grams
+///   ^[ gas_used:(VarUInteger 7) gas_limit:(VarUInteger 7) gas_credit:(Maybe (VarUInteger 3))
+///   mode:int8 exit_code:int32 exit_arg:(Maybe int32) vm_steps:uint32
+///   vm_init_state_hash:bits256 vm_final_state_hash:bits256 ] = TrComputePhase;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComputePhase {
+    Skipped(ComputeSkipReason),
+    Vm(ComputePhaseVm),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputePhaseVm {
+    pub success: bool,
+    pub msg_state_used: bool,
+    pub account_activated: bool,
+    pub gas_fees: BigUint,
+    pub gas_used: u64,
+    pub gas_limit: u64,
+    /// Gas credited to an external message to check its acceptance.
+    pub gas_credit: Option<u64>,
+    pub mode: i8,
+    pub exit_code: i32,
+    pub exit_arg: Option<i32>,
+    pub vm_steps: u32,
+    pub vm_init_state_hash: TonHash,
+    pub vm_final_state_hash: TonHash,
+}
+
+impl ComputePhase {
+    pub fn read(parser: &mut CellParser) -> Result<ComputePhase, TonCellError> {
+        if !parser.load_bit()? {
+            return Ok(ComputePhase::Skipped(ComputeSkipReason::read(parser)?));
+        }
+        let success = parser.load_bit()?;
+        let msg_state_used = parser.load_bit()?;
+        let account_activated = parser.load_bit()?;
+        let gas_fees = parser.load_coins()?;
+        let vm = parser.next_reference()?.parse_fully(|parser| {
+            Ok(ComputePhaseVm {
+                success,
+                msg_state_used,
+                account_activated,
+                gas_fees,
+                gas_used: load_var_uint(parser, 3)?,
+                gas_limit: load_var_uint(parser, 3)?,
+                gas_credit: load_maybe(parser, |parser| load_var_uint(parser, 2))?,
+                mode: i8::read(parser)?,
+                exit_code: i32::read(parser)?,
+                exit_arg: Option::<i32>::read(parser)?,
+                vm_steps: parser.load_u32(32)?,
+                vm_init_state_hash: TonHash::read(parser)?,
+                vm_final_state_hash: TonHash::read(parser)?,
+            })
+        })?;
+        Ok(ComputePhase::Vm(vm))
+    }
+}
+
+/// ```raw
+/// cskip_no_state$00 = ComputeSkipReason;
+/// cskip_bad_state$01 = ComputeSkipReason;
+/// cskip_no_gas$10 = ComputeSkipReason;
+/// cskip_suspended$110 = ComputeSkipReason;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ComputeSkipReason {
+    NoState,
+    BadState,
+    NoGas,
+    Suspended,
+}
+
+impl ComputeSkipReason {
+    pub fn read(parser: &mut CellParser) -> Result<ComputeSkipReason, TonCellError> {
+        let reason = match parser.load_u8(2)? {
+            0b00 => ComputeSkipReason::NoState,
+            0b01 => ComputeSkipReason::BadState,
+            0b10 => ComputeSkipReason::NoGas,
+            _ => {
+                if parser.load_bit()? {
+                    return Err(TonCellError::CellParserError(
+                        "Invalid tag of ComputeSkipReason: 0b111".to_string(),
+                    ));
+                }
+                ComputeSkipReason::Suspended
+            }
+        };
+        Ok(reason)
+    }
+}
+
+/// ```raw
+/// tr_phase_action$_ success:Bool valid:Bool no_funds:Bool status_change:AccStatusChange
+///   total_fwd_fees:(Maybe Grams) total_action_fees:(Maybe Grams)
+///   result_code:int32 result_arg:(Maybe int32) tot_actions:uint16
+///   spec_actions:uint16 skipped_actions:uint16 msgs_created:uint16
+///   action_list_hash:bits256 tot_msg_size:StorageUsed = TrActionPhase;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActionPhase {
+    pub success: bool,
+    pub valid: bool,
+    pub no_funds: bool,
+    pub status_change: AccStatusChange,
+    pub total_fwd_fees: Option<BigUint>,
+    pub total_action_fees: Option<BigUint>,
+    pub result_code: i32,
+    pub result_arg: Option<i32>,
+    pub tot_actions: u16,
+    pub spec_actions: u16,
+    pub skipped_actions: u16,
+    pub msgs_created: u16,
+    pub action_list_hash: TonHash,
+    pub tot_msg_size: StorageUsed,
+}
+
+impl ActionPhase {
+    pub fn read(parser: &mut CellParser) -> Result<ActionPhase, TonCellError> {
+        Ok(ActionPhase {
+            success: parser.load_bit()?,
+            valid: parser.load_bit()?,
+            no_funds: parser.load_bit()?,
+            status_change: AccStatusChange::read(parser)?,
+            total_fwd_fees: load_maybe(parser, |parser| parser.load_coins())?,
+            total_action_fees: load_maybe(parser, |parser| parser.load_coins())?,
+            result_code: i32::read(parser)?,
+            result_arg: Option::<i32>::read(parser)?,
+            tot_actions: parser.load_u16(16)?,
+            spec_actions: parser.load_u16(16)?,
+            skipped_actions: parser.load_u16(16)?,
+            msgs_created: parser.load_u16(16)?,
+            action_list_hash: TonHash::read(parser)?,
+            tot_msg_size: StorageUsed::read(parser)?,
+        })
+    }
+}
+
+fn load_action_phase(parser: &mut CellParser) -> Result<Option<ActionPhase>, TonCellError> {
+    match parser.load_maybe_cell_ref()? {
+        Some(cell) => Ok(Some(cell.parse_fully(ActionPhase::read)?)),
+        None => Ok(None),
+    }
+}
+
+/// ```raw
+/// tr_phase_bounce_negfunds$00 = TrBouncePhase;
+/// tr_phase_bounce_nofunds$01 msg_size:StorageUsed req_fwd_fees:Grams = TrBouncePhase;
+/// tr_phase_bounce_ok$1 msg_size:StorageUsed msg_fees:Grams fwd_fees:Grams = TrBouncePhase;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum BouncePhase {
+    NegativeFunds,
+    NoFunds {
+        msg_size: StorageUsed,
+        req_fwd_fees: BigUint,
+    },
+    Ok {
+        msg_size: StorageUsed,
+        msg_fees: BigUint,
+        fwd_fees: BigUint,
+    },
+}
+
+impl BouncePhase {
+    pub fn read(parser: &mut CellParser) -> Result<BouncePhase, TonCellError> {
+        if parser.load_bit()? {
+            return Ok(BouncePhase::Ok {
+                msg_size: StorageUsed::read(parser)?,
+                msg_fees: parser.load_coins()?,
+                fwd_fees: parser.load_coins()?,
+            });
+        }
+        if !parser.load_bit()? {
+            return Ok(BouncePhase::NegativeFunds);
+        }
+        Ok(BouncePhase::NoFunds {
+            msg_size: StorageUsed::read(parser)?,
+            req_fwd_fees: parser.load_coins()?,
+        })
+    }
+}
+
+/// ```raw
+/// storage_used$_ cells:(VarUInteger 7) bits:(VarUInteger 7) = StorageUsed;
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StorageUsed {
+    pub cells: u64,
+    pub bits: u64,
+}
+
+impl StorageUsed {
+    pub fn read(parser: &mut CellParser) -> Result<StorageUsed, TonCellError> {
+        Ok(StorageUsed {
+            cells: load_var_uint(parser, 3)?,
+            bits: load_var_uint(parser, 3)?,
+        })
+    }
+}
+
+/// Loads `VarUInteger n` with the length of `len_bits` bits, i.e. `(#< n)`.
+fn load_var_uint(parser: &mut CellParser, len_bits: usize) -> Result<u64, TonCellError> {
+    match parser.load_u8(len_bits)? as usize {
+        0 => Ok(0),
+        len => parser.load_u64(len * 8),
+    }
+}
+
+pub(crate) fn load_maybe<T>(
+    parser: &mut CellParser,
+    read: impl FnOnce(&mut CellParser) -> Result<T, TonCellError>,
+) -> Result<Option<T>, TonCellError> {
+    if parser.load_bit()? {
+        Ok(Some(read(parser)?))
+    } else {
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use num_bigint::BigUint;
+
+    use super::{
+        AccStatusChange, AccountStatus, BouncePhase, ComputePhase, ComputeSkipReason, Transaction,
+        TransactionDescr,
+    };
+    use crate::cell::dict::predefined_writers::val_writer_ref_cell;
+    use crate::cell::{Cell, CellBuilder, TonCellError};
+
+    pub(crate) fn transaction(lt: u64, description: Cell) -> Result<Cell, TonCellError> {
+        let message = |n| CellBuilder::new().store_u8(8, n)?.build().map(Arc::new);
+        let out_msgs = HashMap::from([(1u16, message(2)?), (0u16, message(1)?)]);
+        let messages = CellBuilder::new()
+            .store_maybe_cell_ref(&Some(message(0)?))?
+            .store_maybe_dict(15, val_writer_ref_cell, out_msgs)?
+            .build()?;
+        let state_update = CellBuilder::new()
+            .store_u8(8, 0x72)?
+            .store_slice(&[1; 32])?
+            .store_slice(&[2; 32])?
+            .build()?;
+        CellBuilder::new()
+            .store_u8(4, 0b0111)?
+            .store_slice(&[3; 32])?
+            .store_u64(64, lt)?
+            .store_slice(&[4; 32])?
+            .store_u64(64, 1_000_001)?
+            .store_u32(32, 1_700_000_000)?
+            .store_u32(15, 2)?
+            .store_u8(2, 0b10)?
+            .store_u8(2, 0b10)?
+            .store_child(messages)?
+            .store_coins(&BigUint::from(42u32))?
+            .store_bit(false)?
+            .store_child(state_update)?
+            .store_child(description)?
+            .build()
+    }
+
+    #[test]
+    fn test_parse_ordinary_transaction() -> Result<(), TonCellError> {
+        let compute = CellBuilder::new()
+            // gas_used:(VarUInteger 7) gas_limit:(VarUInteger 7) gas_credit:(Maybe (VarUInteger 3))
+            .store_u8(3, 2)?
+            .store_u32(16, 3308)?
+            .store_u8(3, 0)?
+            .store_bit(true)?
+            .store_u8(2, 2)?
+            .store_u32(16, 10_000)?
+            // mode:int8 exit_code:int32 exit_arg:(Maybe int32) vm_steps:uint32
+            .store_u8(8, 0)?
+            .store_u32(32, 0)?
+            .store_bit(false)?
+            .store_u32(32, 68)?
+            .store_slice(&[5; 32])?
+            .store_slice(&[6; 32])?
+            .build()?;
+        let action = CellBuilder::new()
+            .store_bit(false)?
+            .store_bit(true)?
+            .store_bit(true)?
+            .store_bit(false)?
+            .store_bit(false)?
+            .store_bit(false)?
+            // result_code:int32 result_arg:(Maybe int32)
+            .store_u32(32, 37)?
+            .store_bit(true)?
+            .store_u32(32, 1)?
+            .store_u32(16, 2)?
+            .store_u32(16, 0)?
+            .store_u32(16, 0)?
+            .store_u32(16, 2)?
+            .store_slice(&[7; 32])?
+            .store_u8(3, 1)?
+            .store_u8(8, 2)?
+            .store_u8(3, 1)?
+            .store_u32(8, 240)?
+            .build()?;
+        let description = CellBuilder::new()
+            .store_u8(4, 0b0000)?
+            .store_bit(true)?
+            // storage_ph
+            .store_bit(true)?
+            .store_coins(&BigUint::from(5u32))?
+            .store_bit(false)?
+            .store_bit(false)?
+            // credit_ph
+            .store_bit(false)?
+            // compute_ph, tr_phase_compute_vm$1
+            .store_bit(true)?
+            .store_bit(true)?
+            .store_bit(false)?
+            .store_bit(false)?
+            .store_coins(&BigUint::from(1_323_200u32))?
+            .store_child(compute)?
+            .store_bit(true)?
+            .store_child(action)?
+            // aborted, bounce: tr_phase_bounce_negfunds$00, destroyed
+            .store_bit(true)?
+            .store_bit(true)?
+            .store_u8(2, 0b00)?
+            .store_bit(false)?
+            .build()?;
+
+        let tx = Transaction::parse(&transaction(1_000_002, description)?)?;
+        assert_eq!(tx.account_addr, [3; 32]);
+        assert_eq!(tx.lt, 1_000_002);
+        assert_eq!(tx.prev_trans_lt, 1_000_001);
+        assert_eq!(tx.outmsg_cnt, 2);
+        assert_eq!(tx.orig_status, AccountStatus::Active);
+        assert_eq!(tx.in_msg.as_ref().map(|m| m.data().to_vec()), Some(vec![0]));
+        let out_msgs: Vec<_> = tx.out_msgs.iter().map(|m| m.data()[0]).collect();
+        assert_eq!(out_msgs, vec![1, 2]);
+        assert_eq!(tx.total_fees.grams, BigUint::from(42u32));
+        assert_eq!(tx.state_update.new_hash, [2; 32]);
+
+        let storage = tx.description.storage_phase().unwrap();
+        assert_eq!(storage.storage_fees_collected, BigUint::from(5u32));
+        assert_eq!(storage.status_change, AccStatusChange::Unchanged);
+        let vm = match tx.description.compute_phase() {
+            Some(ComputePhase::Vm(vm)) => vm,
+            phase => panic!("Unexpected compute phase: {:?}", phase),
+        };
+        assert!(vm.success);
+        assert_eq!(vm.gas_used, 3308);
+        assert_eq!(vm.gas_limit, 0);
+        assert_eq!(vm.gas_credit, Some(10_000));
+        assert_eq!(vm.vm_steps, 68);
+        assert_eq!(tx.exit_code(), Some(0));
+        let action = tx.description.action_phase().unwrap();
+        assert!(!action.success);
+        assert_eq!(action.result_code, 37);
+        assert_eq!(action.result_arg, Some(1));
+        assert_eq!(action.msgs_created, 2);
+        assert_eq!(action.tot_msg_size.cells, 2);
+        assert_eq!(action.tot_msg_size.bits, 240);
+        assert_eq!(
+            tx.description.bounce_phase(),
+            Some(&BouncePhase::NegativeFunds)
+        );
+        assert!(!tx.is_success());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_tick_tock_transaction() -> Result<(), TonCellError> {
+        let description = CellBuilder::new()
+            .store_u8(3, 0b001)?
+            .store_bit(true)?
+            // storage_ph
+            .store_coins(&BigUint::from(0u32))?
+            .store_bit(false)?
+            .store_bit(false)?
+            // tr_phase_compute_skipped$0 cskip_no_gas$10
+            .store_bit(false)?
+            .store_u8(2, 0b10)?
+            // action, aborted, destroyed
+            .store_bit(false)?
+            .store_bit(false)?
+            .store_bit(false)?
+            .build()?;
+        let tx = Transaction::parse(&transaction(1_000_002, description)?)?;
+        match &tx.description {
+            TransactionDescr::TickTock(tick_tock) => {
+                assert!(tick_tock.is_tock);
+                assert_eq!(
+                    tick_tock.compute_ph,
+                    ComputePhase::Skipped(ComputeSkipReason::NoGas)
+                );
+            }
+            descr => panic!("Unexpected description: {:?}", descr),
+        }
+        assert_eq!(tx.exit_code(), None);
+        assert!(!tx.is_success());
+        Ok(())
+    }
+}
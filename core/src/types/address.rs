@@ -5,7 +5,9 @@ use base64::engine::general_purpose::{STANDARD_NO_PAD, URL_SAFE_NO_PAD};
 use base64::Engine;
 use crc::Crc;
 use lazy_static::lazy_static;
+#[cfg(feature = "serde")]
 use serde::de::{Error, Visitor};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
 use super::{TonAddressParseError, TonHash, TON_HASH_BYTES};
@@ -14,6 +16,11 @@ lazy_static! {
     pub static ref CRC_16_XMODEM: Crc<u16> = Crc::<u16>::new(&crc::CRC_16_XMODEM);
 }
 
+/// Computes CRC16-CCITT (XMODEM) checksum used in user-friendly addresses.
+pub fn crc16_ccitt(bytes: &[u8]) -> u16 {
+    CRC_16_XMODEM.checksum(bytes)
+}
+
 #[derive(PartialEq, Eq, Clone, Hash)]
 pub struct TonAddress {
     pub workchain: i32,
@@ -188,7 +195,7 @@ impl TonAddress {
             }
         };
         let workchain = bytes[1] as i8 as i32;
-        let calc_crc = CRC_16_XMODEM.checksum(&bytes[0..34]);
+        let calc_crc = crc16_ccitt(&bytes[0..34]);
         let addr_crc = ((bytes[34] as u16) << 8) | bytes[35] as u16;
         if calc_crc != addr_crc {
             return Err(TonAddressParseError::new(
@@ -205,6 +212,26 @@ impl TonAddress {
         Ok((addr, non_bounceable, non_production))
     }
 
+    /// Verifies checksum of user-friendly (base64 or base64url) address representation
+    /// without parsing the address itself.
+    ///
+    /// Returns `false` if the string is not a well-formed user-friendly address.
+    pub fn verify_checksum(s: &str) -> bool {
+        if s.len() != 48 {
+            return false;
+        }
+        let maybe_bytes = URL_SAFE_NO_PAD
+            .decode(s)
+            .or_else(|_| STANDARD_NO_PAD.decode(s));
+        match maybe_bytes {
+            Ok(bytes) if bytes.len() == 36 => {
+                let addr_crc = ((bytes[34] as u16) << 8) | bytes[35] as u16;
+                crc16_ccitt(&bytes[0..34]) == addr_crc
+            }
+            _ => false,
+        }
+    }
+
     pub fn to_hex(&self) -> String {
         format!("{}:{}", self.workchain, hex::encode(self.hash_part))
     }
@@ -213,6 +240,8 @@ impl TonAddress {
         self.to_base64_url_flags(false, false)
     }
 
+    /// Formats the address with url-safe base64, see [`TonAddress::formatter`] for
+    /// setting the flags by name.
     pub fn to_base64_url_flags(&self, non_bounceable: bool, non_production: bool) -> String {
         let mut buf: [u8; 36] = [0; 36];
         self.to_base64_src(&mut buf, non_bounceable, non_production);
@@ -223,6 +252,8 @@ impl TonAddress {
         self.to_base64_std_flags(false, false)
     }
 
+    /// Formats the address with standard base64, see [`TonAddress::formatter`] for
+    /// setting the flags by name.
     pub fn to_base64_std_flags(&self, non_bounceable: bool, non_production: bool) -> String {
         let mut buf: [u8; 36] = [0; 36];
         self.to_base64_src(&mut buf, non_bounceable, non_production);
@@ -239,10 +270,34 @@ impl TonAddress {
         bytes[0] = tag;
         bytes[1] = (self.workchain & 0xff) as u8;
         bytes[2..34].clone_from_slice(&self.hash_part);
-        let crc = CRC_16_XMODEM.checksum(&bytes[0..34]);
+        let crc = crc16_ccitt(&bytes[0..34]);
         bytes[34] = ((crc >> 8) & 0xff) as u8;
         bytes[35] = (crc & 0xff) as u8;
     }
+
+    /// Starts formatting the address with explicit flags and encoding,
+    /// e.g. `addr.formatter().with_bounceable(false).format()`.
+    pub fn formatter(&self) -> TonAddressFormatter<'_> {
+        TonAddressFormatter {
+            address: self,
+            format: TonAddressFormat::Base64Url,
+            flags: TonAddressFlags::default(),
+        }
+    }
+
+    /// Replaces the first `depth` bits of the hash part with the lowest `depth` bits
+    /// of `rewrite_pfx`, as specified by `Anycast` of `MsgAddressInt`.
+    pub(crate) fn rewrite_prefix(&mut self, depth: u8, rewrite_pfx: u32) {
+        for i in 0..depth as usize {
+            let bit = (rewrite_pfx >> (depth as usize - 1 - i)) & 1;
+            let mask = 0x80u8 >> (i % 8);
+            if bit == 1 {
+                self.hash_part[i / 8] |= mask;
+            } else {
+                self.hash_part[i / 8] &= !mask;
+            }
+        }
+    }
 }
 
 impl Display for TonAddress {
@@ -261,16 +316,152 @@ impl FromStr for TonAddress {
     type Err = TonAddressParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.len() == 48 {
-            // Some form of base64 address, check which one
+        Ok(s.parse::<ParsedTonAddress>()?.address)
+    }
+}
+
+/// String representation of an address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TonAddressFormat {
+    /// Raw form `workchain:hex`, e.g. `0:e4d954ef...`, which has no flags.
+    Hex,
+    /// User-friendly form encoded with url-safe base64.
+    Base64Url,
+    /// User-friendly form encoded with standard base64.
+    Base64Std,
+}
+
+/// Flags of user-friendly address representation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TonAddressFlags {
+    /// Whether a message should be bounced back if the destination fails to process it.
+    /// Wallets use non-bounceable addresses to send coins to uninitialized accounts.
+    pub bounceable: bool,
+    /// Whether the address is meant to be used only in testnet.
+    pub testnet: bool,
+}
+
+impl TonAddressFlags {
+    pub const fn new(bounceable: bool, testnet: bool) -> TonAddressFlags {
+        TonAddressFlags {
+            bounceable,
+            testnet,
+        }
+    }
+}
+
+impl Default for TonAddressFlags {
+    /// Bounceable mainnet address, the representation of [`TonAddress::to_base64_url`].
+    fn default() -> Self {
+        TonAddressFlags::new(true, false)
+    }
+}
+
+/// Builder of address string representation, see [`TonAddress::formatter`].
+#[derive(Debug, Clone)]
+pub struct TonAddressFormatter<'a> {
+    address: &'a TonAddress,
+    format: TonAddressFormat,
+    flags: TonAddressFlags,
+}
+
+impl TonAddressFormatter<'_> {
+    pub fn with_format(&mut self, format: TonAddressFormat) -> &mut Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_flags(&mut self, flags: TonAddressFlags) -> &mut Self {
+        self.flags = flags;
+        self
+    }
+
+    pub fn with_bounceable(&mut self, bounceable: bool) -> &mut Self {
+        self.flags.bounceable = bounceable;
+        self
+    }
+
+    pub fn with_testnet(&mut self, testnet: bool) -> &mut Self {
+        self.flags.testnet = testnet;
+        self
+    }
+
+    /// Formats the address, flags are ignored by [`TonAddressFormat::Hex`].
+    pub fn format(&self) -> String {
+        let TonAddressFlags {
+            bounceable,
+            testnet,
+        } = self.flags;
+        match self.format {
+            TonAddressFormat::Hex => self.address.to_hex(),
+            TonAddressFormat::Base64Url => self.address.to_base64_url_flags(!bounceable, testnet),
+            TonAddressFormat::Base64Std => self.address.to_base64_std_flags(!bounceable, testnet),
+        }
+    }
+}
+
+impl Display for TonAddressFormatter<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.format().as_str())
+    }
+}
+
+/// Address parsed from a string along with the detected format and flags.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ParsedTonAddress {
+    pub address: TonAddress,
+    pub format: TonAddressFormat,
+    /// Flags of user-friendly representations, `None` for [`TonAddressFormat::Hex`].
+    pub flags: Option<TonAddressFlags>,
+}
+
+impl ParsedTonAddress {
+    /// Formats the address the way it was parsed.
+    pub fn to_original_format(&self) -> String {
+        let mut formatter = self.address.formatter();
+        formatter.with_format(self.format);
+        if let Some(flags) = self.flags {
+            formatter.with_flags(flags);
+        }
+        formatter.format()
+    }
+}
+
+impl FromStr for ParsedTonAddress {
+    type Err = TonAddressParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 48 {
+            return Ok(ParsedTonAddress {
+                address: TonAddress::from_hex_str(s)?,
+                format: TonAddressFormat::Hex,
+                flags: None,
+            });
+        }
+        // Some form of base64 address, check which one
+        let (format, (address, non_bounceable, non_production)) =
             if s.contains('-') || s.contains('_') {
-                TonAddress::from_base64_url(s)
+                (
+                    TonAddressFormat::Base64Url,
+                    TonAddress::from_base64_url_flags(s)?,
+                )
+            } else if s.contains('+') || s.contains('/') {
+                (
+                    TonAddressFormat::Base64Std,
+                    TonAddress::from_base64_std_flags(s)?,
+                )
             } else {
-                TonAddress::from_base64_std(s)
-            }
-        } else {
-            TonAddress::from_hex_str(s)
-        }
+                // Both encodings are the same, prefer the url-safe one used by Display
+                (
+                    TonAddressFormat::Base64Url,
+                    TonAddress::from_base64_url_flags(s)?,
+                )
+            };
+        Ok(ParsedTonAddress {
+            address,
+            format,
+            flags: Some(TonAddressFlags::new(!non_bounceable, non_production)),
+        })
     }
 }
 
@@ -282,6 +473,7 @@ impl TryFrom<String> for TonAddress {
     }
 }
 
+#[cfg(feature = "serde")]
 impl Serialize for TonAddress {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -291,8 +483,10 @@ impl Serialize for TonAddress {
     }
 }
 
+#[cfg(feature = "serde")]
 struct TonAddressVisitor;
 
+#[cfg(feature = "serde")]
 impl<'de> Visitor<'de> for TonAddressVisitor {
     type Value = TonAddress;
 
@@ -308,6 +502,7 @@ impl<'de> Visitor<'de> for TonAddressVisitor {
     }
 }
 
+#[cfg(feature = "serde")]
 impl<'de> Deserialize<'de> for TonAddress {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -320,9 +515,12 @@ impl<'de> Deserialize<'de> for TonAddress {
 #[cfg(test)]
 mod tests {
 
+    #[cfg(feature = "serde")]
     use serde_json::Value;
 
-    use super::TonAddressParseError;
+    use super::{
+        crc16_ccitt, ParsedTonAddress, TonAddressFlags, TonAddressFormat, TonAddressParseError,
+    };
     use crate::{TonAddress, TonHash};
 
     #[test]
@@ -423,6 +621,132 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn flags_round_trip_works() -> Result<(), TonAddressParseError> {
+        let addr: TonAddress =
+            "0:e4d954ef9f4e1250a26b5bbad76a1cdd17cfd08babad6f4c23e372270aef6f76".parse()?;
+        let cases = [
+            (
+                "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR",
+                false,
+                false,
+            ),
+            (
+                "UQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdmcU",
+                true,
+                false,
+            ),
+            (
+                "kQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdoFb",
+                false,
+                true,
+            ),
+            (
+                "0QDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdtye",
+                true,
+                true,
+            ),
+        ];
+        for (base64_url, non_bounceable, non_production) in cases {
+            assert_eq!(
+                addr.to_base64_url_flags(non_bounceable, non_production),
+                base64_url
+            );
+            assert_eq!(
+                TonAddress::from_base64_url_flags(base64_url)?,
+                (addr.clone(), non_bounceable, non_production)
+            );
+            let base64_std = addr.to_base64_std_flags(non_bounceable, non_production);
+            assert_eq!(base64_std, base64_url.replace('_', "/").replace('-', "+"));
+            assert_eq!(
+                TonAddress::from_base64_std_flags(&base64_std)?,
+                (addr.clone(), non_bounceable, non_production)
+            );
+            assert_eq!(base64_url.parse::<TonAddress>()?, addr);
+        }
+        assert!("UQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR"
+            .parse::<TonAddress>()
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn formatter_works() -> Result<(), TonAddressParseError> {
+        let addr: TonAddress =
+            "0:e4d954ef9f4e1250a26b5bbad76a1cdd17cfd08babad6f4c23e372270aef6f76".parse()?;
+        assert_eq!(addr.formatter().format(), addr.to_base64_url());
+        assert_eq!(
+            addr.formatter().with_bounceable(false).format(),
+            "UQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdmcU"
+        );
+        assert_eq!(
+            addr.formatter()
+                .with_format(TonAddressFormat::Base64Std)
+                .with_flags(TonAddressFlags::new(true, true))
+                .to_string(),
+            "kQDk2VTvn04SUKJrW7rXahzdF8/Qi6utb0wj43InCu9vdoFb"
+        );
+        assert_eq!(
+            addr.formatter()
+                .with_format(TonAddressFormat::Hex)
+                .with_testnet(true)
+                .format(),
+            addr.to_hex()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn parsed_address_reports_format() -> Result<(), TonAddressParseError> {
+        let cases = [
+            (
+                "0:e4d954ef9f4e1250a26b5bbad76a1cdd17cfd08babad6f4c23e372270aef6f76",
+                TonAddressFormat::Hex,
+                None,
+            ),
+            (
+                "0QDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdtye",
+                TonAddressFormat::Base64Url,
+                Some(TonAddressFlags::new(false, true)),
+            ),
+            (
+                "EQDk2VTvn04SUKJrW7rXahzdF8/Qi6utb0wj43InCu9vdjrR",
+                TonAddressFormat::Base64Std,
+                Some(TonAddressFlags::new(true, false)),
+            ),
+        ];
+        for (s, format, flags) in cases {
+            let parsed: ParsedTonAddress = s.parse()?;
+            assert_eq!(parsed.format, format);
+            assert_eq!(parsed.flags, flags);
+            assert_eq!(parsed.to_original_format(), s);
+            assert_eq!(parsed.address, s.parse::<TonAddress>()?);
+        }
+        assert!("0:e4d9".parse::<ParsedTonAddress>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn verify_checksum_works() {
+        assert!(TonAddress::verify_checksum(
+            "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR"
+        ));
+        assert!(TonAddress::verify_checksum(
+            "EQDk2VTvn04SUKJrW7rXahzdF8/Qi6utb0wj43InCu9vdjrR"
+        ));
+        assert!(!TonAddress::verify_checksum(
+            "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjra"
+        ));
+        assert!(!TonAddress::verify_checksum(
+            "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdj"
+        ));
+        assert!(!TonAddress::verify_checksum(
+            "0:e4d954ef9f4e1250a26b5bbad76a1cdd17cfd08babad6f4c23e372270aef6f76"
+        ));
+        assert_eq!(crc16_ccitt(b"123456789"), 0x31c3);
+    }
+
+    #[cfg(feature = "serde")]
     #[test]
     fn serialization_works() -> Result<(), TonAddressParseError> {
         let expected = "\"EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR\"";
@@ -446,6 +770,7 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(feature = "serde")]
     #[test]
     fn deserialization_works() -> Result<(), TonAddressParseError> {
         let address = "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR";
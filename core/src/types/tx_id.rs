@@ -6,12 +6,14 @@ use base64::engine::general_purpose::{NO_PAD, PAD};
 use base64::engine::GeneralPurpose;
 use base64::Engine;
 use lazy_static::lazy_static;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 use super::{TonHash, TransactionIdParseError};
 use crate::types::TON_HASH_BYTES;
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TonTxId {
     pub lt: i64,
     pub hash: TonHash,
@@ -1,10 +1,21 @@
+mod expiration;
+mod highload_v3;
+mod signer;
+mod transaction_builder;
 mod types;
+mod vanity;
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
+pub use expiration::*;
+pub use highload_v3::*;
 use lazy_static::lazy_static;
 use nacl::sign::signature;
+pub use signer::*;
+pub use transaction_builder::*;
 pub use types::*;
+pub use vanity::*;
 
 use crate::cell::{
     ArcCell, BagOfCells, Cell, CellBuilder, StateInit, StateInitBuilder, TonCellError,
@@ -14,6 +25,19 @@ use crate::mnemonic::KeyPair;
 use crate::{TonAddress, TonHash};
 
 pub const DEFAULT_WALLET_ID: i32 = 0x29a9a317;
+/// Maximum number of internal messages a highload wallet can send in a single external message
+pub const HIGHLOAD_MAX_MESSAGES: usize = 254;
+/// Wallet id of wallet v5r1 in workchain 0 of mainnet with subwallet number 0,
+/// see `wallet_v5r1_id`
+pub const DEFAULT_WALLET_V5R1_ID: i32 = 0x7fffff11;
+/// Maximum number of internal messages wallet v5 can send in a single request
+pub const WALLET_V5_MAX_MESSAGES: usize = 255;
+/// Opcode of a wallet v5 request signed with the wallet key, sent with an external message
+pub const WALLET_V5_EXTERNAL_SIGNED_OP: u32 = 0x7369676e;
+/// Opcode of a wallet v5 request signed with the wallet key, sent with an internal message,
+/// e.g. by a relayer paying the fees of a gasless transfer
+pub const WALLET_V5_INTERNAL_SIGNED_OP: u32 = 0x73696e74;
+const WALLET_V5_ACTION_SEND_MSG_OP: u32 = 0x0ec3c86d;
 
 lazy_static! {
     pub static ref WALLET_V1R1_CODE: BagOfCells = {
@@ -52,6 +76,10 @@ lazy_static! {
         let code = include_str!("../resources/wallet/wallet_v4r2.code");
         BagOfCells::parse_base64(code).unwrap()
     };
+    pub static ref WALLET_V5R1_CODE: BagOfCells = {
+        let code = include_str!("../resources/wallet/wallet_v5r1.code");
+        BagOfCells::parse_base64(code).unwrap()
+    };
     pub static ref HIGHLOAD_V1R1_CODE: BagOfCells = {
         let code = include_str!("../resources/wallet/highload_v1r1.code");
         BagOfCells::parse_base64(code).unwrap()
@@ -74,7 +102,7 @@ lazy_static! {
     };
 }
 
-#[derive(PartialEq, Eq, Clone, Hash)]
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub enum WalletVersion {
     V1R1,
     V1R2,
@@ -85,6 +113,7 @@ pub enum WalletVersion {
     V3R2,
     V4R1,
     V4R2,
+    V5R1,
     HighloadV1R1,
     HighloadV1R2,
     HighloadV2,
@@ -93,6 +122,33 @@ pub enum WalletVersion {
 }
 
 impl WalletVersion {
+    pub const ALL: [WalletVersion; 15] = [
+        WalletVersion::V1R1,
+        WalletVersion::V1R2,
+        WalletVersion::V1R3,
+        WalletVersion::V2R1,
+        WalletVersion::V2R2,
+        WalletVersion::V3R1,
+        WalletVersion::V3R2,
+        WalletVersion::V4R1,
+        WalletVersion::V4R2,
+        WalletVersion::V5R1,
+        WalletVersion::HighloadV1R1,
+        WalletVersion::HighloadV1R2,
+        WalletVersion::HighloadV2,
+        WalletVersion::HighloadV2R1,
+        WalletVersion::HighloadV2R2,
+    ];
+
+    /// Returns the version with the code of hash `code_hash`, if any.
+    pub fn from_code_hash(code_hash: &TonHash) -> Option<WalletVersion> {
+        WalletVersion::ALL.into_iter().find(|version| {
+            version
+                .code()
+                .is_ok_and(|code| &code.cell_hash() == code_hash)
+        })
+    }
+
     pub fn code(&self) -> Result<&ArcCell, TonCellError> {
         let code: &BagOfCells = match self {
             WalletVersion::V1R1 => &WALLET_V1R1_CODE,
@@ -104,6 +160,7 @@ impl WalletVersion {
             WalletVersion::V3R2 => &WALLET_V3R2_CODE,
             WalletVersion::V4R1 => &WALLET_V4R1_CODE,
             WalletVersion::V4R2 => &WALLET_V4R2_CODE,
+            WalletVersion::V5R1 => &WALLET_V5R1_CODE,
             WalletVersion::HighloadV1R1 => &HIGHLOAD_V1R1_CODE,
             WalletVersion::HighloadV1R2 => &HIGHLOAD_V1R2_CODE,
             WalletVersion::HighloadV2 => &HIGHLOAD_V2_CODE,
@@ -146,6 +203,14 @@ impl WalletVersion {
                 public_key,
             }
             .try_into()?,
+            WalletVersion::V5R1 => WalletDataV5 {
+                signature_allowed: true,
+                seqno: 0,
+                wallet_id,
+                public_key,
+                extensions: vec![],
+            }
+            .try_into()?,
             WalletVersion::HighloadV2R2 => WalletDataHighloadV2R2 {
                 wallet_id,
                 last_cleaned_time: 0,
@@ -168,6 +233,23 @@ impl WalletVersion {
     pub fn has_op(&self) -> bool {
         matches!(self, WalletVersion::V4R2)
     }
+
+    /// Wallet id used by `TonWallet::derive_default`.
+    pub fn default_wallet_id(&self) -> i32 {
+        match self {
+            WalletVersion::V5R1 => DEFAULT_WALLET_V5R1_ID,
+            _ => DEFAULT_WALLET_ID,
+        }
+    }
+}
+
+/// Computes the wallet id of wallet v5r1 from the global id of the network
+/// (-239 for mainnet, -3 for testnet), the workchain of the wallet and the subwallet number.
+pub fn wallet_v5r1_id(network_global_id: i32, workchain: i32, subwallet_number: u32) -> i32 {
+    let context = (1u32 << 31) // client context
+        | ((workchain as u8 as u32) << 23)
+        | (subwallet_number & 0x7fff); // wallet version 0
+    network_global_id ^ context as i32
 }
 
 #[derive(PartialEq, Eq, Clone, Hash)]
@@ -209,7 +291,7 @@ impl TonWallet {
         version: WalletVersion,
         key_pair: &KeyPair,
     ) -> Result<TonWallet, TonCellError> {
-        let wallet_id = DEFAULT_WALLET_ID;
+        let wallet_id = version.default_wallet_id();
         let data = version.initial_data(key_pair, wallet_id)?;
         let code = version.code()?;
         let state_init_hash = StateInit::create_account_id(code, &data)?;
@@ -243,6 +325,35 @@ impl TonWallet {
         Ok(wrapped)
     }
 
+    /// Same as `create_external_message`, but signs the message with `signer` instead of
+    /// the key pair of the wallet, which then only needs the public key.
+    pub async fn create_external_message_with_signer<S, T>(
+        &self,
+        signer: &S,
+        expire_at: u32,
+        seqno: u32,
+        internal_messages: T,
+        state_init: bool,
+    ) -> Result<Cell, TonMessageError>
+    where
+        S: Signer + ?Sized,
+        T: AsRef<[ArcCell]>,
+    {
+        let body = self.create_external_body(expire_at, seqno, internal_messages)?;
+        let signed = self.sign_external_body_with_signer(signer, &body).await?;
+        self.wrap_signed_body(signed, state_init)
+    }
+
+    /// Creates the unsigned body of an external message.
+    ///
+    /// For highload wallets internal messages are stored in a `HashmapE 16` dictionary
+    /// (at most `HIGHLOAD_MAX_MESSAGES`). There's no seqno in highload wallets v2, so
+    /// `expire_at` and `seqno` are combined into the query id as `expire_at << 32 | seqno`, where
+    /// `seqno` serves as a counter that must be unique among queries with the same `expire_at`.
+    ///
+    /// For wallet v5 internal messages are stored in an action list (at most
+    /// `WALLET_V5_MAX_MESSAGES`), see `create_external_body_with_actions` to also change
+    /// extensions of the wallet.
     pub fn create_external_body<T: AsRef<[ArcCell]>>(
         &self,
         expire_at: u32,
@@ -250,27 +361,155 @@ impl TonWallet {
         internal_messages: T,
     ) -> Result<Cell, TonCellError> {
         let mut builder = CellBuilder::new();
+        match self.version {
+            WalletVersion::HighloadV2
+            | WalletVersion::HighloadV2R1
+            | WalletVersion::HighloadV2R2 => {
+                let query_id = ((expire_at as u64) << 32) | seqno as u64;
+                builder
+                    .store_i32(32, self.wallet_id)?
+                    .store_u64(64, query_id)?;
+                store_highload_messages(&mut builder, internal_messages.as_ref())?;
+            }
+            WalletVersion::HighloadV1R1 | WalletVersion::HighloadV1R2 => {
+                builder
+                    .store_i32(32, self.wallet_id)?
+                    .store_u32(32, expire_at)?
+                    .store_u32(32, seqno)?;
+                store_highload_messages(&mut builder, internal_messages.as_ref())?;
+            }
+            WalletVersion::V5R1 => {
+                self.store_v5_request(
+                    &mut builder,
+                    WALLET_V5_EXTERNAL_SIGNED_OP,
+                    expire_at,
+                    seqno,
+                    internal_messages.as_ref(),
+                    &[],
+                )?;
+            }
+            _ => {
+                builder
+                    .store_i32(32, self.wallet_id)?
+                    .store_u32(32, expire_at)?
+                    .store_u32(32, seqno)?;
+                if self.version.has_op() {
+                    builder.store_u8(8, 0)?;
+                }
+                for internal_message in internal_messages.as_ref() {
+                    builder.store_u8(8, 3)?; // send_mode
+                    builder.store_reference(internal_message)?;
+                }
+            }
+        }
+        builder.build()
+    }
+
+    /// Creates the unsigned body of an external message of wallet v5 sending `internal_messages`
+    /// and applying `extended_actions` in order.
+    pub fn create_external_body_with_actions<T: AsRef<[ArcCell]>>(
+        &self,
+        expire_at: u32,
+        seqno: u32,
+        internal_messages: T,
+        extended_actions: &[WalletV5ExtendedAction],
+    ) -> Result<Cell, TonCellError> {
+        let mut builder = CellBuilder::new();
+        self.store_v5_request(
+            &mut builder,
+            WALLET_V5_EXTERNAL_SIGNED_OP,
+            expire_at,
+            seqno,
+            internal_messages.as_ref(),
+            extended_actions,
+        )?;
+        builder.build()
+    }
+
+    /// Creates the signed body of an internal message to wallet v5 sending `internal_messages`.
+    ///
+    /// Any account can deliver it to the wallet, so a relayer can pay the fees instead of
+    /// the wallet owner, e.g. for gasless jetton transfers.
+    pub fn create_internal_signed_body<T: AsRef<[ArcCell]>>(
+        &self,
+        expire_at: u32,
+        seqno: u32,
+        internal_messages: T,
+    ) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        self.store_v5_request(
+            &mut builder,
+            WALLET_V5_INTERNAL_SIGNED_OP,
+            expire_at,
+            seqno,
+            internal_messages.as_ref(),
+            &[],
+        )?;
+        self.sign_external_body(&builder.build()?)
+    }
+
+    fn store_v5_request(
+        &self,
+        builder: &mut CellBuilder,
+        op: u32,
+        expire_at: u32,
+        seqno: u32,
+        internal_messages: &[ArcCell],
+        extended_actions: &[WalletV5ExtendedAction],
+    ) -> Result<(), TonCellError> {
+        if self.version != WalletVersion::V5R1 {
+            return Err(TonCellError::InvalidInput(
+                "Request format is only supported by wallet v5".to_string(),
+            ));
+        }
         builder
+            .store_u32(32, op)?
             .store_i32(32, self.wallet_id)?
             .store_u32(32, expire_at)?
             .store_u32(32, seqno)?;
-        if self.version.has_op() {
-            builder.store_u8(8, 0)?;
-        }
-        for internal_message in internal_messages.as_ref() {
-            builder.store_u8(8, 3)?; // send_mode
-            builder.store_reference(internal_message)?;
-        }
-        builder.build()
+        store_v5_actions(builder, internal_messages, extended_actions)
     }
 
+    /// Signs the body, placing the signature in front of it, or at its end for wallet v5.
     pub fn sign_external_body(&self, external_body: &Cell) -> Result<Cell, TonMessageError> {
         let message_hash = external_body.cell_hash();
         let sig = signature(message_hash.as_slice(), self.key_pair.secret_key.as_slice())
             .map_err(|e| TonMessageError::NaclCryptographicError(e.message))?;
+        self.attach_signature(external_body, &sig)
+    }
+
+    /// Same as `sign_external_body`, but signs the body with `signer`.
+    pub async fn sign_external_body_with_signer<S: Signer + ?Sized>(
+        &self,
+        signer: &S,
+        external_body: &Cell,
+    ) -> Result<Cell, TonMessageError> {
+        let sig = signer.sign(&external_body.cell_hash()).await?;
+        self.attach_signature(external_body, &sig)
+    }
+
+    /// Combines the body with its signature made elsewhere, e.g. on an air-gapped machine.
+    ///
+    /// `signature` is the ed25519 signature of the `cell_hash` of `external_body`.
+    pub fn attach_signature(
+        &self,
+        external_body: &Cell,
+        signature: &[u8],
+    ) -> Result<Cell, TonMessageError> {
+        if signature.len() != 64 {
+            return Err(TonMessageError::NaclCryptographicError(format!(
+                "Invalid signature size: {}",
+                signature.len()
+            )));
+        }
         let mut body_builder = CellBuilder::new();
-        body_builder.store_slice(sig.as_slice())?;
-        body_builder.store_cell(external_body)?;
+        if self.version == WalletVersion::V5R1 {
+            body_builder.store_cell(external_body)?;
+            body_builder.store_slice(signature)?;
+        } else {
+            body_builder.store_slice(signature)?;
+            body_builder.store_cell(external_body)?;
+        }
         Ok(body_builder.build()?)
     }
 
@@ -289,7 +528,7 @@ impl TonWallet {
             wrap_builder.store_bit(true)?; // state init present
             wrap_builder.store_bit(true)?; // state init in ref
             let initial_data = self.version.initial_data(&self.key_pair, self.wallet_id)?;
-            let code = WALLET_V4R2_CODE.single_root()?.clone();
+            let code = self.version.code()?.clone();
             let state_init = StateInitBuilder::new(&code, &initial_data).build()?;
             wrap_builder.store_child(state_init)?;
         } else {
@@ -301,10 +540,109 @@ impl TonWallet {
     }
 }
 
+fn store_highload_messages(
+    builder: &mut CellBuilder,
+    internal_messages: &[ArcCell],
+) -> Result<(), TonCellError> {
+    if internal_messages.len() > HIGHLOAD_MAX_MESSAGES {
+        return Err(TonCellError::InvalidInput(format!(
+            "Highload wallet can send at most {} messages, got {}",
+            HIGHLOAD_MAX_MESSAGES,
+            internal_messages.len()
+        )));
+    }
+    let data: HashMap<u16, (u8, ArcCell)> = internal_messages
+        .iter()
+        .enumerate()
+        .map(|(i, msg)| (i as u16, (3, msg.clone()))) // send_mode 3
+        .collect();
+    builder.store_maybe_dict(16, val_writer_highload_message, data)?;
+    Ok(())
+}
+
+fn store_v5_actions(
+    builder: &mut CellBuilder,
+    internal_messages: &[ArcCell],
+    extended_actions: &[WalletV5ExtendedAction],
+) -> Result<(), TonCellError> {
+    if internal_messages.len() > WALLET_V5_MAX_MESSAGES {
+        return Err(TonCellError::InvalidInput(format!(
+            "Wallet v5 can send at most {} messages, got {}",
+            WALLET_V5_MAX_MESSAGES,
+            internal_messages.len()
+        )));
+    }
+    if internal_messages.is_empty() {
+        builder.store_bit(false)?; // no out list
+    } else {
+        // Out list is a linked list ending with an empty cell, the first action is the deepest
+        let mut out_list = CellBuilder::new().build()?;
+        for internal_message in internal_messages {
+            out_list = CellBuilder::new()
+                .store_child(out_list)?
+                .store_u32(32, WALLET_V5_ACTION_SEND_MSG_OP)?
+                .store_u8(8, 3)? // send_mode
+                .store_reference(internal_message)?
+                .build()?;
+        }
+        builder.store_bit(true)?;
+        builder.store_child(out_list)?;
+    }
+    match extended_actions.split_first() {
+        None => {
+            builder.store_bit(false)?; // no extended actions
+        }
+        Some((first, rest)) => {
+            // The first extended action is stored inline, every next one in a ref of the previous
+            builder.store_bit(true)?;
+            first.store(builder)?;
+            if let Some(rest) = store_v5_extended_actions(rest)? {
+                builder.store_child(rest)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn store_v5_extended_actions(
+    extended_actions: &[WalletV5ExtendedAction],
+) -> Result<Option<Cell>, TonCellError> {
+    let mut next: Option<Cell> = None;
+    for action in extended_actions.iter().rev() {
+        let mut builder = CellBuilder::new();
+        action.store(&mut builder)?;
+        if let Some(next) = next {
+            builder.store_child(next)?;
+        }
+        next = Some(builder.build()?);
+    }
+    Ok(next)
+}
+
+fn val_writer_highload_message(
+    builder: &mut CellBuilder,
+    val: (u8, ArcCell),
+) -> Result<(), TonCellError> {
+    let (send_mode, msg) = val;
+    builder.store_u8(8, send_mode)?;
+    builder.store_reference(&msg)?;
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
+    use nacl::sign::signature;
+
+    use crate::cell::dict::predefined_readers::key_reader_u16;
+    use crate::cell::{Cell, CellBuilder, CellParser, TonCellError};
     use crate::mnemonic::{Mnemonic, MnemonicError};
-    use crate::wallet::{TonWallet, WalletVersion};
+    use crate::wallet::{
+        wallet_v5r1_id, TonWallet, WalletDataV5, WalletV5ExtendedAction, WalletVersion,
+        DEFAULT_WALLET_V5R1_ID, HIGHLOAD_MAX_MESSAGES, WALLET_V5_EXTERNAL_SIGNED_OP,
+        WALLET_V5_INTERNAL_SIGNED_OP,
+    };
     use crate::TonAddress;
 
     #[test]
@@ -331,4 +669,168 @@ mod tests {
         assert_eq!(wallet_v4r2.address, expected_v4r2);
         Ok(())
     }
+
+    #[test]
+    fn highload_external_body_works() -> Result<(), TonCellError> {
+        let mnemonic_str = "fancy carpet hello mandate penalty trial consider \
+        property top vicious exit rebuild tragic profit urban major total month holiday \
+        sudden rib gather media vicious";
+        let key_pair = Mnemonic::from_str(mnemonic_str, &None)
+            .unwrap()
+            .to_key_pair()
+            .unwrap();
+        let wallet = TonWallet::derive_default(WalletVersion::HighloadV2R2, &key_pair)?;
+        let messages: Vec<_> = (0..3u32)
+            .map(|i| Ok(Arc::new(CellBuilder::new().store_u32(32, i)?.build()?)))
+            .collect::<Result<_, TonCellError>>()?;
+
+        let body = wallet.create_external_body(1_700_000_000, 42, &messages)?;
+        let mut parser = body.parser();
+        assert_eq!(parser.load_i32(32)?, wallet.wallet_id);
+        assert_eq!(parser.load_u64(64)?, (1_700_000_000u64 << 32) | 42);
+        let dict = parser.load_maybe_dict(16, key_reader_u16, |p: &mut CellParser| {
+            Ok((p.load_u8(8)?, p.next_reference()?))
+        })?;
+        assert_eq!(dict.len(), 3);
+        for (i, msg) in messages.iter().enumerate() {
+            assert_eq!(dict[&(i as u16)], (3, msg.clone()));
+        }
+
+        let too_many = vec![messages[0].clone(); HIGHLOAD_MAX_MESSAGES + 1];
+        assert!(wallet
+            .create_external_body(1_700_000_000, 42, too_many)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn wallet_version_from_code_hash_works() -> Result<(), TonCellError> {
+        for version in WalletVersion::ALL {
+            let code_hash = version.code()?.cell_hash();
+            assert_eq!(WalletVersion::from_code_hash(&code_hash), Some(version));
+        }
+        assert_eq!(WalletVersion::from_code_hash(&[0; 32]), None);
+        Ok(())
+    }
+
+    #[test]
+    fn wallet_v5r1_derive_works() -> Result<(), TonCellError> {
+        let code_hash = WalletVersion::V5R1.code()?.cell_hash();
+        assert_eq!(
+            hex::encode(code_hash),
+            "20834b7b72b112147e1b2fb457b84e74d1a30f04f737d4f62a668e9552d2b72f"
+        );
+        assert_eq!(wallet_v5r1_id(-239, 0, 0), DEFAULT_WALLET_V5R1_ID);
+        assert_eq!(wallet_v5r1_id(-3, 0, 0), 0x7ffffffd);
+        assert_eq!(wallet_v5r1_id(-239, -1, 1), 0x007fff10);
+
+        let mnemonic_str = "fancy carpet hello mandate penalty trial consider \
+        property top vicious exit rebuild tragic profit urban major total month holiday \
+        sudden rib gather media vicious";
+        let key_pair = Mnemonic::from_str(mnemonic_str, &None)
+            .unwrap()
+            .to_key_pair()
+            .unwrap();
+        let wallet = TonWallet::derive_default(WalletVersion::V5R1, &key_pair)?;
+        assert_eq!(wallet.wallet_id, DEFAULT_WALLET_V5R1_ID);
+        let data: WalletDataV5 = WalletVersion::V5R1
+            .initial_data(&key_pair, wallet.wallet_id)?
+            .as_ref()
+            .clone()
+            .try_into()?;
+        assert!(data.signature_allowed);
+        assert_eq!(data.seqno, 0);
+        assert_eq!(data.wallet_id, DEFAULT_WALLET_V5R1_ID);
+        assert_eq!(data.public_key.as_slice(), key_pair.public_key.as_slice());
+        assert!(data.extensions.is_empty());
+
+        let extensions = vec![[1u8; 32], [2u8; 32]];
+        let cell: Cell = WalletDataV5 {
+            extensions: extensions.clone(),
+            ..data
+        }
+        .try_into()?;
+        let data: WalletDataV5 = cell.try_into()?;
+        assert_eq!(data.extensions, extensions);
+        assert_eq!(data.wallet_id, DEFAULT_WALLET_V5R1_ID);
+        Ok(())
+    }
+
+    #[test]
+    fn wallet_v5r1_external_body_works() -> Result<(), TonCellError> {
+        let mnemonic_str = "fancy carpet hello mandate penalty trial consider \
+        property top vicious exit rebuild tragic profit urban major total month holiday \
+        sudden rib gather media vicious";
+        let key_pair = Mnemonic::from_str(mnemonic_str, &None)
+            .unwrap()
+            .to_key_pair()
+            .unwrap();
+        let wallet = TonWallet::derive_default(WalletVersion::V5R1, &key_pair)?;
+        let messages: Vec<_> = (0..2u32)
+            .map(|i| Ok(Arc::new(CellBuilder::new().store_u32(32, i)?.build()?)))
+            .collect::<Result<_, TonCellError>>()?;
+        let extension: TonAddress = "EQBiMfDMivebQb052Z6yR3jHrmwNhw1kQ5bcAUOBYsK_VPuK"
+            .parse()
+            .unwrap();
+        let actions = [
+            WalletV5ExtendedAction::AddExtension(extension.clone()),
+            WalletV5ExtendedAction::SetSignatureAllowed(false),
+        ];
+
+        let body =
+            wallet.create_external_body_with_actions(1_700_000_000, 42, &messages, &actions)?;
+        let mut parser = body.parser();
+        assert_eq!(parser.load_u32(32)?, WALLET_V5_EXTERNAL_SIGNED_OP);
+        assert_eq!(parser.load_i32(32)?, wallet.wallet_id);
+        assert_eq!(parser.load_u32(32)?, 1_700_000_000);
+        assert_eq!(parser.load_u32(32)?, 42);
+        // out list: the last message comes first
+        let mut out_list = parser.load_maybe_cell_ref()?.unwrap();
+        for msg in messages.iter().rev() {
+            let mut action_parser = out_list.parser();
+            let prev = action_parser.next_reference()?;
+            assert_eq!(action_parser.load_u32(32)?, 0x0ec3c86d);
+            assert_eq!(action_parser.load_u8(8)?, 3);
+            assert_eq!(&action_parser.next_reference()?, msg);
+            out_list = prev;
+        }
+        assert_eq!(out_list.bit_len(), 0);
+        assert!(parser.load_bit()?);
+        assert_eq!(parser.load_u8(8)?, 2);
+        assert_eq!(parser.load_address()?, extension);
+        let next_action = parser.next_reference()?;
+        let mut action_parser = next_action.parser();
+        assert_eq!(action_parser.load_u8(8)?, 4);
+        assert!(!action_parser.load_bit()?);
+        assert_eq!(action_parser.remaining_bits(), 0);
+
+        let signed = wallet.sign_external_body(&body).unwrap();
+        assert_eq!(signed.bit_len(), body.bit_len() + 512);
+        assert_eq!(signed.references(), body.references());
+        let mut signed_parser = signed.parser();
+        signed_parser.skip_bits(body.bit_len())?;
+        let expected_sig = signature(body.cell_hash().as_slice(), &key_pair.secret_key).unwrap();
+        assert_eq!(signed_parser.load_bits(512)?, expected_sig);
+
+        let plain_body = wallet.create_external_body(1_700_000_000, 42, &messages)?;
+        let mut parser = plain_body.parser();
+        parser.skip_bits(128)?;
+        assert!(parser.load_maybe_cell_ref()?.is_some());
+        assert!(!parser.load_bit()?);
+        assert_eq!(parser.remaining_bits(), 0);
+
+        let internal = wallet
+            .create_internal_signed_body(1_700_000_000, 42, &messages)
+            .unwrap();
+        assert_eq!(
+            internal.parser().load_u32(32)?,
+            WALLET_V5_INTERNAL_SIGNED_OP
+        );
+
+        let wallet_v4 = TonWallet::derive_default(WalletVersion::V4R2, &key_pair)?;
+        assert!(wallet_v4
+            .create_external_body_with_actions(1_700_000_000, 42, &messages, &actions)
+            .is_err());
+        Ok(())
+    }
 }
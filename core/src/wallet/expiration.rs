@@ -0,0 +1,157 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Time to live of wallet messages used by `ExpirationPolicy::default`
+pub const DEFAULT_MESSAGE_TTL: Duration = Duration::from_secs(60);
+/// Clock skew between the local clock and validators tolerated by `ExpirationPolicy::default`
+pub const DEFAULT_CLOCK_SKEW_TOLERANCE: Duration = Duration::from_secs(10);
+
+/// How long an external message of a wallet is valid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Expiration {
+    /// Valid for the duration since the current time.
+    Ttl(Duration),
+    /// Valid until the unix time.
+    Deadline(u32),
+    /// Never expires by time, replay is prevented by the seqno alone, or by the query id
+    /// for highload wallets, such as highload wallet v3 where the `timeout` of the wallet
+    /// limits the validity of a message.
+    ///
+    /// For seqno wallets `valid_until` is `u32::MAX`: a message that wasn't delivered stays
+    /// valid until the seqno of the wallet advances, so it may be executed at any time later,
+    /// and the only way to cancel it is to send another message with the same seqno.
+    SeqnoBound,
+}
+
+/// Computes `valid_until` of wallet messages, and `created_at` of highload wallet v3 messages.
+///
+/// Validators compare these values with their own time, which may differ from the local clock.
+/// The difference measured elsewhere, e.g. with `liteServer.getInfo` of a lite server,
+/// is applied with `with_clock_offset`. The remaining uncertainty is covered by the skew
+/// tolerance: a TTL is extended by it and `created_at` is moved back by it, so that messages
+/// created right before a send neither expire early nor look like created in the future.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExpirationPolicy {
+    expiration: Expiration,
+    clock_offset: i64,
+    skew_tolerance: Duration,
+}
+
+impl ExpirationPolicy {
+    pub fn new(expiration: Expiration) -> ExpirationPolicy {
+        ExpirationPolicy {
+            expiration,
+            clock_offset: 0,
+            skew_tolerance: DEFAULT_CLOCK_SKEW_TOLERANCE,
+        }
+    }
+
+    pub fn ttl(ttl: Duration) -> ExpirationPolicy {
+        Self::new(Expiration::Ttl(ttl))
+    }
+
+    pub fn deadline(valid_until: u32) -> ExpirationPolicy {
+        Self::new(Expiration::Deadline(valid_until))
+    }
+
+    /// See `Expiration::SeqnoBound`: meant for highload wallet v3, with seqno wallets
+    /// a lost message may be executed at any time later.
+    pub fn seqno_bound() -> ExpirationPolicy {
+        Self::new(Expiration::SeqnoBound)
+    }
+
+    /// Sets the number of seconds the network time is ahead of the local clock,
+    /// negative if it's behind.
+    pub fn with_clock_offset(&mut self, clock_offset: i64) -> &mut Self {
+        self.clock_offset = clock_offset;
+        self
+    }
+
+    pub fn with_skew_tolerance(&mut self, skew_tolerance: Duration) -> &mut Self {
+        self.skew_tolerance = skew_tolerance;
+        self
+    }
+
+    pub fn expiration(&self) -> Expiration {
+        self.expiration
+    }
+
+    /// Returns the current network time, i.e. the local time adjusted by the clock offset.
+    pub fn now(&self) -> u32 {
+        let local = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
+        clamp_u32(local.saturating_add(self.clock_offset))
+    }
+
+    /// Returns `valid_until` of a message created now.
+    pub fn valid_until(&self) -> u32 {
+        self.valid_until_at(self.now())
+    }
+
+    /// Returns `valid_until` of a message created at the network time `now`.
+    pub fn valid_until_at(&self, now: u32) -> u32 {
+        match self.expiration {
+            Expiration::Ttl(ttl) => {
+                let ttl = ttl.saturating_add(self.skew_tolerance).as_secs();
+                clamp_u32((now as i64).saturating_add(ttl.min(u32::MAX as u64) as i64))
+            }
+            Expiration::Deadline(valid_until) => valid_until,
+            Expiration::SeqnoBound => u32::MAX,
+        }
+    }
+
+    /// Returns `created_at` of a highload wallet v3 message created now.
+    pub fn created_at(&self) -> u64 {
+        self.created_at_for(self.now())
+    }
+
+    /// Returns `created_at` of a highload wallet v3 message created at the network time `now`.
+    pub fn created_at_for(&self, now: u32) -> u64 {
+        (now as u64).saturating_sub(self.skew_tolerance.as_secs())
+    }
+}
+
+impl Default for ExpirationPolicy {
+    fn default() -> Self {
+        Self::ttl(DEFAULT_MESSAGE_TTL)
+    }
+}
+
+fn clamp_u32(value: i64) -> u32 {
+    value.clamp(0, u32::MAX as i64) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Expiration, ExpirationPolicy};
+
+    #[test]
+    fn test_expiration_policy() {
+        let now = 1_700_000_000;
+        let mut policy = ExpirationPolicy::ttl(Duration::from_secs(60));
+        policy.with_skew_tolerance(Duration::from_secs(5));
+        assert_eq!(policy.valid_until_at(now), now + 65);
+        assert_eq!(policy.created_at_for(now), now as u64 - 5);
+        assert_eq!(policy.created_at_for(3), 0);
+        assert_eq!(policy.valid_until_at(u32::MAX - 1), u32::MAX);
+
+        assert_eq!(
+            ExpirationPolicy::deadline(now).valid_until_at(now + 100),
+            now
+        );
+        assert_eq!(ExpirationPolicy::seqno_bound().valid_until(), u32::MAX);
+        assert_eq!(
+            ExpirationPolicy::default().expiration(),
+            Expiration::Ttl(Duration::from_secs(60))
+        );
+
+        let local = ExpirationPolicy::default().now();
+        let ahead = *ExpirationPolicy::default().with_clock_offset(3600);
+        assert!(ahead.now() >= local + 3600);
+        let behind = *ExpirationPolicy::default().with_clock_offset(-i64::MAX);
+        assert_eq!(behind.now(), 0);
+    }
+}
@@ -0,0 +1,549 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use nacl::sign::signature;
+use num_bigint::BigUint;
+
+use crate::cell::dict::predefined_readers::{key_reader_u16, val_reader_ref_cell};
+use crate::cell::dict::predefined_writers::val_writer_ref_cell;
+use crate::cell::{
+    ArcCell, Cell, CellBuilder, CellParser, StateInit, StateInitBuilder, TonCellError,
+};
+use crate::message::{
+    CommonMsgInfo, ExternalInMessage, TonMessage, TonMessageError, TransferMessage,
+};
+use crate::mnemonic::KeyPair;
+use crate::wallet::Signer;
+use crate::{TonAddress, TonHash};
+
+/// Maximum number of internal messages sent by a single batch of highload wallet v3,
+/// one more action of the 255 allowed is used by the wallet to restore its code
+pub const HIGHLOAD_V3_MAX_MESSAGES: usize = 254;
+/// Subwallet id used by the reference wrappers of highload wallet v3
+pub const HIGHLOAD_V3_DEFAULT_SUBWALLET_ID: u32 = 0x10ad;
+/// Opcode of the internal message a highload wallet v3 sends to itself to execute a batch
+pub const HIGHLOAD_V3_INTERNAL_TRANSFER_OP: u32 = 0xae42e5a4;
+/// Maximum value of the `timeout` of highload wallet v3, stored in 22 bits
+pub const HIGHLOAD_V3_MAX_TIMEOUT: u32 = (1 << 22) - 1;
+const OUT_ACTION_SEND_MSG_OP: u32 = 0x0ec3c86d;
+const QUERY_SHIFT_BITS: usize = 13;
+
+/// Query id of highload wallet v3, consisting of a 13-bit `shift` and a 10-bit `bit_number`.
+///
+/// The wallet remembers processed query ids for at least `timeout`, so a query id must not
+/// be reused within that period. `next` walks through all query ids in order, the last one
+/// (`shift` 8191, `bit_number` 1022) is never returned and can be kept for emergencies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct HighloadQueryId {
+    shift: u16,
+    bit_number: u16,
+}
+
+impl HighloadQueryId {
+    pub const MAX_SHIFT: u16 = 8191;
+    pub const MAX_BIT_NUMBER: u16 = 1022;
+
+    pub fn new(shift: u16, bit_number: u16) -> Result<Self, TonCellError> {
+        if shift > Self::MAX_SHIFT || bit_number > Self::MAX_BIT_NUMBER {
+            return Err(TonCellError::InvalidInput(format!(
+                "Invalid highload query id: shift {}, bit number {}",
+                shift, bit_number
+            )));
+        }
+        Ok(HighloadQueryId { shift, bit_number })
+    }
+
+    /// Creates a query id from its 23-bit representation `shift << 10 | bit_number`.
+    pub fn from_u32(query_id: u32) -> Result<Self, TonCellError> {
+        if query_id >> 23 != 0 {
+            return Err(TonCellError::InvalidInput(format!(
+                "Highload query id {} exceeds 23 bits",
+                query_id
+            )));
+        }
+        Self::new((query_id >> 10) as u16, (query_id & 0x3ff) as u16)
+    }
+
+    pub fn as_u32(&self) -> u32 {
+        ((self.shift as u32) << 10) | self.bit_number as u32
+    }
+
+    pub fn shift(&self) -> u16 {
+        self.shift
+    }
+
+    pub fn bit_number(&self) -> u16 {
+        self.bit_number
+    }
+
+    /// Returns the query id following this one, `None` once all of them are used up.
+    pub fn next(&self) -> Option<Self> {
+        let (shift, bit_number) = if self.bit_number == Self::MAX_BIT_NUMBER {
+            (self.shift + 1, 0)
+        } else {
+            (self.shift, self.bit_number + 1)
+        };
+        if shift > Self::MAX_SHIFT
+            || (shift == Self::MAX_SHIFT && bit_number == Self::MAX_BIT_NUMBER)
+        {
+            return None;
+        }
+        Some(HighloadQueryId { shift, bit_number })
+    }
+}
+
+/// Data of highload wallet v3.
+///
+/// The wallet moves `queries` to `old_queries` once `timeout` passes since `last_clean_time`
+/// and drops `old_queries` after another `timeout`.
+pub struct WalletDataHighloadV3 {
+    pub public_key: TonHash,
+    pub subwallet_id: u32,
+    /// Query ids processed before `last_clean_time`, in order.
+    pub old_queries: Vec<HighloadQueryId>,
+    /// Query ids processed since `last_clean_time`, in order.
+    pub queries: Vec<HighloadQueryId>,
+    pub last_clean_time: u64,
+    pub timeout: u32,
+}
+
+impl WalletDataHighloadV3 {
+    /// Checks if the wallet has processed `query_id` and would reject it.
+    pub fn is_processed(&self, query_id: &HighloadQueryId) -> bool {
+        self.old_queries.binary_search(query_id).is_ok()
+            || self.queries.binary_search(query_id).is_ok()
+    }
+}
+
+impl TryFrom<Cell> for WalletDataHighloadV3 {
+    type Error = TonCellError;
+
+    fn try_from(value: Cell) -> Result<Self, Self::Error> {
+        let mut parser = value.parser();
+        let mut public_key = [0u8; 32];
+        parser.load_slice(&mut public_key)?;
+        let subwallet_id = parser.load_u32(32)?;
+        let old_queries = load_queries(&mut parser)?;
+        let queries = load_queries(&mut parser)?;
+        let last_clean_time = parser.load_u64(64)?;
+        let timeout = parser.load_u32(22)?;
+        Ok(Self {
+            public_key,
+            subwallet_id,
+            old_queries,
+            queries,
+            last_clean_time,
+            timeout,
+        })
+    }
+}
+
+impl TryFrom<WalletDataHighloadV3> for Cell {
+    type Error = TonCellError;
+
+    fn try_from(value: WalletDataHighloadV3) -> Result<Self, Self::Error> {
+        CellBuilder::new()
+            .store_slice(&value.public_key)?
+            .store_u32(32, value.subwallet_id)?
+            .store_maybe_dict(
+                QUERY_SHIFT_BITS,
+                val_writer_ref_cell,
+                queries_dict(&value.old_queries)?,
+            )?
+            .store_maybe_dict(
+                QUERY_SHIFT_BITS,
+                val_writer_ref_cell,
+                queries_dict(&value.queries)?,
+            )?
+            .store_u64(64, value.last_clean_time)?
+            .store_u32(22, value.timeout)?
+            .build()
+    }
+}
+
+/// Loads a dict of processed query ids, mapping a `shift` to a cell of 1023 bits
+/// in which the bit at `bit_number` is set for every processed query id.
+fn load_queries(parser: &mut CellParser) -> Result<Vec<HighloadQueryId>, TonCellError> {
+    let dict = parser.load_maybe_dict(QUERY_SHIFT_BITS, key_reader_u16, val_reader_ref_cell)?;
+    let mut queries = vec![];
+    for (shift, bitmap) in dict {
+        let mut parser = bitmap.parser();
+        let bit_len = parser.remaining_bits();
+        for bit_number in 0..bit_len.min(HighloadQueryId::MAX_BIT_NUMBER as usize + 1) {
+            if parser.load_bit()? {
+                queries.push(HighloadQueryId::new(shift, bit_number as u16)?);
+            }
+        }
+    }
+    queries.sort();
+    Ok(queries)
+}
+
+fn queries_dict(queries: &[HighloadQueryId]) -> Result<HashMap<u16, ArcCell>, TonCellError> {
+    let mut bitmaps: HashMap<u16, Vec<bool>> = HashMap::new();
+    for query_id in queries {
+        let bitmap = bitmaps
+            .entry(query_id.shift())
+            .or_insert_with(|| vec![false; HighloadQueryId::MAX_BIT_NUMBER as usize + 1]);
+        bitmap[query_id.bit_number() as usize] = true;
+    }
+    bitmaps
+        .into_iter()
+        .map(|(shift, bitmap)| {
+            let mut builder = CellBuilder::new();
+            for bit in bitmap {
+                builder.store_bit(bit)?;
+            }
+            Ok((shift, Arc::new(builder.build()?)))
+        })
+        .collect()
+}
+
+/// Highload wallet v3, sending a single internal message or a batch of up to
+/// `HIGHLOAD_V3_MAX_MESSAGES` internal messages per external message.
+///
+/// Instead of a seqno, every external message carries a `HighloadQueryId` and its creation
+/// time. The wallet accepts a message created within its `timeout` with a query id that
+/// wasn't processed yet, so many messages can be in flight at the same time.
+///
+/// The contract code isn't bundled with the crate, pass the code of the deployed contract
+/// to `derive` to compute the address or deploy the wallet.
+#[derive(PartialEq, Eq, Clone, Hash)]
+pub struct HighloadWalletV3 {
+    pub key_pair: KeyPair,
+    pub address: TonAddress,
+    pub subwallet_id: u32,
+    pub timeout: u32,
+    code: Option<ArcCell>,
+}
+
+impl HighloadWalletV3 {
+    /// Creates a wallet deployed at `address`.
+    pub fn new(key_pair: &KeyPair, address: &TonAddress, subwallet_id: u32, timeout: u32) -> Self {
+        HighloadWalletV3 {
+            key_pair: key_pair.clone(),
+            address: address.clone(),
+            subwallet_id,
+            timeout,
+            code: None,
+        }
+    }
+
+    /// Computes the address of the wallet with the contract `code`, which also allows
+    /// to attach its state init to external messages.
+    pub fn derive(
+        workchain: i32,
+        code: &ArcCell,
+        key_pair: &KeyPair,
+        subwallet_id: u32,
+        timeout: u32,
+    ) -> Result<Self, TonCellError> {
+        let mut wallet = HighloadWalletV3 {
+            key_pair: key_pair.clone(),
+            address: TonAddress::NULL,
+            subwallet_id,
+            timeout,
+            code: Some(code.clone()),
+        };
+        let data = wallet.initial_data()?;
+        let state_init_hash = StateInit::create_account_id(code, &data)?;
+        let hash_part = state_init_hash.as_slice().try_into().map_err(|_| {
+            TonCellError::InternalError("StateInit returned hash of wrong size".to_string())
+        })?;
+        wallet.address = TonAddress::new(workchain, &hash_part);
+        Ok(wallet)
+    }
+
+    pub fn initial_data(&self) -> Result<ArcCell, TonCellError> {
+        if self.timeout > HIGHLOAD_V3_MAX_TIMEOUT {
+            return Err(TonCellError::InvalidInput(format!(
+                "Highload wallet timeout {} exceeds {}",
+                self.timeout, HIGHLOAD_V3_MAX_TIMEOUT
+            )));
+        }
+        let public_key: TonHash = self
+            .key_pair
+            .public_key
+            .clone()
+            .try_into()
+            .map_err(|_| TonCellError::InternalError("Invalid public key size".to_string()))?;
+        let data: Cell = WalletDataHighloadV3 {
+            public_key,
+            subwallet_id: self.subwallet_id,
+            old_queries: vec![],
+            queries: vec![],
+            last_clean_time: 0,
+            timeout: self.timeout,
+        }
+        .try_into()?;
+        Ok(Arc::new(data))
+    }
+
+    /// Creates an external message making the wallet send `internal_message` with `send_mode`.
+    ///
+    /// `created_at` must not be in the future and not older than `timeout`, taking
+    /// the clock drift of validators into account, e.g. the current time minus a few seconds
+    /// as returned by `ExpirationPolicy::created_at`.
+    /// The state init is attached if `state_init` is set, which requires a wallet
+    /// created with `derive`.
+    pub fn create_external_message(
+        &self,
+        internal_message: &ArcCell,
+        send_mode: u8,
+        query_id: HighloadQueryId,
+        created_at: u64,
+        state_init: bool,
+    ) -> Result<Cell, TonMessageError> {
+        let body = self.create_external_body(internal_message, send_mode, query_id, created_at)?;
+        let signed = self.sign_external_body(&body)?;
+        self.wrap_signed_body(signed, state_init)
+    }
+
+    /// Same as `create_external_message`, but sends all `internal_messages` with send mode 3.
+    ///
+    /// The messages are sent by an internal message the wallet sends to itself carrying
+    /// `value` to cover them, so `value` must be at least the sum of their values plus fees.
+    pub fn create_batch_external_message<T: AsRef<[ArcCell]>>(
+        &self,
+        internal_messages: T,
+        value: &BigUint,
+        query_id: HighloadQueryId,
+        created_at: u64,
+        state_init: bool,
+    ) -> Result<Cell, TonMessageError> {
+        let batch = self.create_batch_message(internal_messages, value, query_id)?;
+        self.create_external_message(&Arc::new(batch), 3, query_id, created_at, state_init)
+    }
+
+    /// Creates the internal message to the wallet itself executing a batch of
+    /// `internal_messages`, see `create_batch_external_message`.
+    pub fn create_batch_message<T: AsRef<[ArcCell]>>(
+        &self,
+        internal_messages: T,
+        value: &BigUint,
+        query_id: HighloadQueryId,
+    ) -> Result<Cell, TonMessageError> {
+        let internal_messages = internal_messages.as_ref();
+        if internal_messages.is_empty() || internal_messages.len() > HIGHLOAD_V3_MAX_MESSAGES {
+            return Err(TonCellError::InvalidInput(format!(
+                "Highload wallet v3 batch must have 1 to {} messages, got {}",
+                HIGHLOAD_V3_MAX_MESSAGES,
+                internal_messages.len()
+            ))
+            .into());
+        }
+        // Out list is a linked list ending with an empty cell, the first action is the deepest
+        let mut out_list = CellBuilder::new().build()?;
+        for internal_message in internal_messages {
+            out_list = CellBuilder::new()
+                .store_child(out_list)?
+                .store_u32(32, OUT_ACTION_SEND_MSG_OP)?
+                .store_u8(8, 3)? // send_mode
+                .store_reference(internal_message)?
+                .build()?;
+        }
+        let body = CellBuilder::new()
+            .store_u32(32, HIGHLOAD_V3_INTERNAL_TRANSFER_OP)?
+            .store_u64(64, query_id.as_u32() as u64)?
+            .store_child(out_list)?
+            .build()?;
+        TransferMessage::new(CommonMsgInfo::new_default_internal(&self.address, value))
+            .with_data(Arc::new(body))
+            .build()
+    }
+
+    /// Creates the unsigned body of an external message.
+    pub fn create_external_body(
+        &self,
+        internal_message: &ArcCell,
+        send_mode: u8,
+        query_id: HighloadQueryId,
+        created_at: u64,
+    ) -> Result<Cell, TonCellError> {
+        CellBuilder::new()
+            .store_u32(32, self.subwallet_id)?
+            .store_reference(internal_message)?
+            .store_u8(8, send_mode)?
+            .store_u32(23, query_id.as_u32())?
+            .store_u64(64, created_at)?
+            .store_u32(22, self.timeout)?
+            .build()
+    }
+
+    /// Signs the body, the signature is followed by a reference to the body.
+    pub fn sign_external_body(&self, external_body: &Cell) -> Result<Cell, TonMessageError> {
+        let message_hash = external_body.cell_hash();
+        let sig = signature(message_hash.as_slice(), self.key_pair.secret_key.as_slice())
+            .map_err(|e| TonMessageError::NaclCryptographicError(e.message))?;
+        attach_signature(external_body, &sig)
+    }
+
+    /// Same as `sign_external_body`, but signs the body with `signer`.
+    ///
+    /// Combined with `create_external_body` and `wrap_signed_body` this allows to create
+    /// messages of a wallet with a key held outside of the process.
+    pub async fn sign_external_body_with_signer<S: Signer + ?Sized>(
+        &self,
+        signer: &S,
+        external_body: &Cell,
+    ) -> Result<Cell, TonMessageError> {
+        let sig = signer.sign(&external_body.cell_hash()).await?;
+        attach_signature(external_body, &sig)
+    }
+
+    /// Creates an external message with the signed body, see `create_external_message`.
+    pub fn wrap_signed_body(
+        &self,
+        signed_body: Cell,
+        state_init: bool,
+    ) -> Result<Cell, TonMessageError> {
+        let mut message = ExternalInMessage::new(&self.address, &Arc::new(signed_body));
+        if state_init {
+            let code = self.code.as_ref().ok_or_else(|| {
+                TonCellError::InvalidInput(
+                    "Contract code is required to create a state init".to_string(),
+                )
+            })?;
+            let state_init = StateInitBuilder::new(code, &self.initial_data()?).build()?;
+            message.with_state_init(&Arc::new(state_init));
+        }
+        message.build()
+    }
+}
+
+fn attach_signature(external_body: &Cell, signature: &[u8]) -> Result<Cell, TonMessageError> {
+    let mut body_builder = CellBuilder::new();
+    body_builder.store_slice(signature)?;
+    body_builder.store_child(external_body.clone())?;
+    Ok(body_builder.build()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use num_bigint::BigUint;
+
+    use super::{
+        HighloadQueryId, HighloadWalletV3, WalletDataHighloadV3, HIGHLOAD_V3_DEFAULT_SUBWALLET_ID,
+        HIGHLOAD_V3_INTERNAL_TRANSFER_OP, HIGHLOAD_V3_MAX_MESSAGES,
+    };
+    use crate::cell::{Cell, CellBuilder, TonCellError};
+    use crate::message::{ExternalInMessage, TonMessage};
+    use crate::mnemonic::Mnemonic;
+
+    #[test]
+    fn highload_query_id_works() -> Result<(), TonCellError> {
+        let query_id = HighloadQueryId::new(3, 1021)?;
+        assert_eq!(query_id.as_u32(), 3 << 10 | 1021);
+        assert_eq!(HighloadQueryId::from_u32(query_id.as_u32())?, query_id);
+        let next = query_id.next().unwrap();
+        assert_eq!((next.shift(), next.bit_number()), (3, 1022));
+        let next = next.next().unwrap();
+        assert_eq!((next.shift(), next.bit_number()), (4, 0));
+
+        let last = HighloadQueryId::new(HighloadQueryId::MAX_SHIFT, 1021)?;
+        assert!(last.next().is_none());
+        assert!(HighloadQueryId::new(0, 1023).is_err());
+        assert!(HighloadQueryId::from_u32(1 << 23).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn highload_v3_data_works() -> Result<(), TonCellError> {
+        let old_queries = vec![HighloadQueryId::new(0, 5)?, HighloadQueryId::new(7, 0)?];
+        let queries = vec![
+            HighloadQueryId::new(7, 1)?,
+            HighloadQueryId::new(7, HighloadQueryId::MAX_BIT_NUMBER)?,
+        ];
+        let cell: Cell = WalletDataHighloadV3 {
+            public_key: [1; 32],
+            subwallet_id: HIGHLOAD_V3_DEFAULT_SUBWALLET_ID,
+            old_queries: old_queries.clone(),
+            queries: queries.clone(),
+            last_clean_time: 1_700_000_000,
+            timeout: 3600,
+        }
+        .try_into()?;
+        let data = WalletDataHighloadV3::try_from(cell)?;
+        assert_eq!(data.old_queries, old_queries);
+        assert_eq!(data.queries, queries);
+        assert_eq!(data.last_clean_time, 1_700_000_000);
+        assert_eq!(data.timeout, 3600);
+        assert!(data.is_processed(&HighloadQueryId::new(7, 0)?));
+        assert!(!data.is_processed(&HighloadQueryId::new(7, 2)?));
+        Ok(())
+    }
+
+    #[test]
+    fn highload_v3_batch_message_works() -> Result<(), TonCellError> {
+        let mnemonic_str = "fancy carpet hello mandate penalty trial consider \
+        property top vicious exit rebuild tragic profit urban major total month holiday \
+        sudden rib gather media vicious";
+        let key_pair = Mnemonic::from_str(mnemonic_str, &None)
+            .unwrap()
+            .to_key_pair()
+            .unwrap();
+        let code = Arc::new(CellBuilder::new().store_u32(32, 0xdeadbeef)?.build()?);
+        let wallet =
+            HighloadWalletV3::derive(0, &code, &key_pair, HIGHLOAD_V3_DEFAULT_SUBWALLET_ID, 3600)?;
+        let messages: Vec<_> = (0..3u32)
+            .map(|i| Ok(Arc::new(CellBuilder::new().store_u32(32, i)?.build()?)))
+            .collect::<Result<_, TonCellError>>()?;
+        let query_id = HighloadQueryId::new(1, 2)?;
+        let value = BigUint::from(1_000_000_000u64);
+
+        let external = wallet
+            .create_batch_external_message(&messages, &value, query_id, 1_700_000_000, true)
+            .unwrap();
+        let external = ExternalInMessage::parse(&external).unwrap();
+        assert_eq!(external.dest, wallet.address);
+        assert!(external.state_init.is_some());
+
+        let mut parser = external.body.parser();
+        parser.skip_bits(512)?;
+        let msg_inner = parser.next_reference()?;
+        let mut parser = msg_inner.parser();
+        assert_eq!(parser.load_u32(32)?, HIGHLOAD_V3_DEFAULT_SUBWALLET_ID);
+        let batch = parser.next_reference()?;
+        assert_eq!(parser.load_u8(8)?, 3);
+        assert_eq!(parser.load_u32(23)?, query_id.as_u32());
+        assert_eq!(parser.load_u64(64)?, 1_700_000_000);
+        assert_eq!(parser.load_u32(22)?, 3600);
+
+        let expected_batch = wallet
+            .create_batch_message(&messages, &value, query_id)
+            .unwrap();
+        assert_eq!(batch.as_ref(), &expected_batch);
+        // state init is absent, so the only reference is the body
+        let body = expected_batch.references()[0].clone();
+        let mut parser = body.parser();
+        assert_eq!(parser.load_u32(32)?, HIGHLOAD_V3_INTERNAL_TRANSFER_OP);
+        assert_eq!(parser.load_u64(64)?, query_id.as_u32() as u64);
+        let mut out_list = parser.next_reference()?;
+        for msg in messages.iter().rev() {
+            let mut action_parser = out_list.parser();
+            let prev = action_parser.next_reference()?;
+            assert_eq!(action_parser.load_u32(32)?, 0x0ec3c86d);
+            assert_eq!(action_parser.load_u8(8)?, 3);
+            assert_eq!(&action_parser.next_reference()?, msg);
+            out_list = prev;
+        }
+        assert_eq!(out_list.bit_len(), 0);
+
+        let too_many = vec![messages[0].clone(); HIGHLOAD_V3_MAX_MESSAGES + 1];
+        assert!(wallet
+            .create_batch_message(too_many, &value, query_id)
+            .is_err());
+        let undeployable = HighloadWalletV3::new(
+            &key_pair,
+            &wallet.address,
+            HIGHLOAD_V3_DEFAULT_SUBWALLET_ID,
+            3600,
+        );
+        assert!(undeployable
+            .create_external_message(&messages[0], 3, query_id, 1_700_000_000, true)
+            .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use nacl::sign::signature;
+
+use crate::message::TonMessageError;
+use crate::mnemonic::KeyPair;
+use crate::TonHash;
+
+/// Ed25519 signature.
+pub type Signature = [u8; 64];
+
+/// Source of ed25519 signatures for wallet messages, e.g. a KMS, HSM, hardware wallet
+/// or multi-party signing service holding the secret key outside of the process.
+#[async_trait]
+pub trait Signer: Send + Sync {
+    /// Signs `hash`, the cell hash of an unsigned message body.
+    async fn sign(&self, hash: &TonHash) -> Result<Signature, TonMessageError>;
+}
+
+/// Signs with the secret key held in memory.
+#[async_trait]
+impl Signer for KeyPair {
+    async fn sign(&self, hash: &TonHash) -> Result<Signature, TonMessageError> {
+        let sig = signature(hash.as_slice(), self.secret_key.as_slice())
+            .map_err(|e| TonMessageError::NaclCryptographicError(e.message))?;
+        sig.try_into().map_err(|sig: Vec<u8>| {
+            TonMessageError::NaclCryptographicError(format!(
+                "Invalid signature size: {}",
+                sig.len()
+            ))
+        })
+    }
+}
+
+#[async_trait]
+impl<S: Signer + ?Sized> Signer for Arc<S> {
+    async fn sign(&self, hash: &TonHash) -> Result<Signature, TonMessageError> {
+        self.as_ref().sign(hash).await
+    }
+}
+
+#[async_trait]
+impl<S: Signer + ?Sized> Signer for Box<S> {
+    async fn sign(&self, hash: &TonHash) -> Result<Signature, TonMessageError> {
+        self.as_ref().sign(hash).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+
+    use super::{Signature, Signer};
+    use crate::cell::{CellBuilder, TonCellError};
+    use crate::message::TonMessageError;
+    use crate::mnemonic::{KeyPair, Mnemonic};
+    use crate::wallet::{TonTransactionBuilder, TonWallet, WalletVersion};
+    use crate::TonHash;
+
+    /// Stands in for a signer holding the key elsewhere, counting requests.
+    struct RemoteSigner {
+        key_pair: KeyPair,
+        requests: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl Signer for RemoteSigner {
+        async fn sign(&self, hash: &TonHash) -> Result<Signature, TonMessageError> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            self.key_pair.sign(hash).await
+        }
+    }
+
+    #[test]
+    fn signer_works() -> Result<(), TonCellError> {
+        let mnemonic_str = "fancy carpet hello mandate penalty trial consider \
+        property top vicious exit rebuild tragic profit urban major total month holiday \
+        sudden rib gather media vicious";
+        let key_pair = Mnemonic::from_str(mnemonic_str, &None)
+            .unwrap()
+            .to_key_pair()
+            .unwrap();
+        let signer = RemoteSigner {
+            key_pair: key_pair.clone(),
+            requests: AtomicUsize::new(0),
+        };
+        let watch_only = KeyPair {
+            public_key: key_pair.public_key.clone(),
+            secret_key: vec![],
+        };
+        let internal_message = Arc::new(CellBuilder::new().store_u32(32, 7)?.build()?);
+        for version in [WalletVersion::V3R2, WalletVersion::V5R1] {
+            let wallet = TonWallet::derive_default(version.clone(), &key_pair)?;
+            let expected = wallet
+                .create_external_message(1_700_000_000, 3, [internal_message.clone()], true)
+                .unwrap();
+
+            let watch_only_wallet = TonWallet::derive_default(version, &watch_only)?;
+            let message =
+                tokio_test::block_on(watch_only_wallet.create_external_message_with_signer(
+                    &signer,
+                    1_700_000_000,
+                    3,
+                    [internal_message.clone()],
+                    true,
+                ))
+                .unwrap();
+            assert_eq!(message, expected);
+
+            let boxed: Box<dyn Signer> = Box::new(key_pair.clone());
+            let unsigned = TonTransactionBuilder::new(&watch_only_wallet, 3, 1_700_000_000)
+                .with_internal_message(&internal_message)
+                .with_state_init(true)
+                .build_unsigned()
+                .unwrap();
+            let message = tokio_test::block_on(unsigned.sign_with_signer(&boxed)).unwrap();
+            assert_eq!(message, expected);
+        }
+        assert_eq!(signer.requests.load(Ordering::SeqCst), 2);
+        Ok(())
+    }
+}
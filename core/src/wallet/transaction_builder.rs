@@ -0,0 +1,211 @@
+use std::sync::Arc;
+
+use crate::cell::{ArcCell, BagOfCells, Cell};
+use crate::message::TonMessageError;
+use crate::wallet::{ExpirationPolicy, Signer, TonWallet};
+use crate::TonHash;
+
+/// Builds external messages of a `TonWallet` entirely offline.
+///
+/// Seqno and expiration time are passed explicitly instead of being fetched from a liteserver,
+/// so messages can be created on machines without network access. The message is either
+/// signed with the key pair of the wallet by `build`, or returned unsigned by `build_unsigned`
+/// to be signed elsewhere, in which case the wallet may be derived from a key pair with an
+/// empty `secret_key`.
+pub struct TonTransactionBuilder<'a> {
+    wallet: &'a TonWallet,
+    seqno: u32,
+    valid_until: u32,
+    internal_messages: Vec<ArcCell>,
+    state_init: bool,
+}
+
+impl<'a> TonTransactionBuilder<'a> {
+    /// Creates a builder of a message with `seqno` of the wallet, valid until the unix
+    /// time `valid_until`.
+    pub fn new(wallet: &'a TonWallet, seqno: u32, valid_until: u32) -> Self {
+        TonTransactionBuilder {
+            wallet,
+            seqno,
+            valid_until,
+            internal_messages: vec![],
+            state_init: false,
+        }
+    }
+
+    /// Creates a builder of a message with `seqno` of the wallet, valid until the time
+    /// computed by `expiration` at the moment of the call.
+    pub fn with_expiration_policy(
+        wallet: &'a TonWallet,
+        seqno: u32,
+        expiration: &ExpirationPolicy,
+    ) -> Self {
+        Self::new(wallet, seqno, expiration.valid_until())
+    }
+
+    pub fn with_internal_message(&mut self, internal_message: &ArcCell) -> &mut Self {
+        self.internal_messages.push(internal_message.clone());
+        self
+    }
+
+    pub fn with_internal_messages<T: AsRef<[ArcCell]>>(
+        &mut self,
+        internal_messages: T,
+    ) -> &mut Self {
+        self.internal_messages
+            .extend(internal_messages.as_ref().iter().cloned());
+        self
+    }
+
+    /// Attaches the state init of the wallet, required for the first message of an
+    /// undeployed wallet.
+    pub fn with_state_init(&mut self, state_init: bool) -> &mut Self {
+        self.state_init = state_init;
+        self
+    }
+
+    /// Creates the message signed with the key pair of the wallet.
+    pub fn build(&self) -> Result<Cell, TonMessageError> {
+        self.build_unsigned()?.sign()
+    }
+
+    /// Creates the message signed with the key pair of the wallet, serialized to a BoC
+    /// ready to be sent with `sendBoc`.
+    pub fn build_boc(&self) -> Result<Vec<u8>, TonMessageError> {
+        to_boc(self.build()?)
+    }
+
+    /// Creates the message without signature, see `UnsignedExternalMessage`.
+    pub fn build_unsigned(&self) -> Result<UnsignedExternalMessage, TonMessageError> {
+        let body = self.wallet.create_external_body(
+            self.valid_until,
+            self.seqno,
+            &self.internal_messages,
+        )?;
+        Ok(UnsignedExternalMessage {
+            wallet: self.wallet.clone(),
+            body,
+            state_init: self.state_init,
+        })
+    }
+}
+
+/// External message of a wallet awaiting its signature.
+pub struct UnsignedExternalMessage {
+    wallet: TonWallet,
+    body: Cell,
+    state_init: bool,
+}
+
+impl UnsignedExternalMessage {
+    /// Unsigned body of the message.
+    pub fn body(&self) -> &Cell {
+        &self.body
+    }
+
+    /// Hash the ed25519 signature must be made for.
+    pub fn hash(&self) -> TonHash {
+        self.body.cell_hash()
+    }
+
+    /// Signs the message with the key pair of the wallet.
+    pub fn sign(&self) -> Result<Cell, TonMessageError> {
+        let signed = self.wallet.sign_external_body(&self.body)?;
+        self.wallet.wrap_signed_body(signed, self.state_init)
+    }
+
+    /// Signs the message with `signer`.
+    pub async fn sign_with_signer<S: Signer + ?Sized>(
+        &self,
+        signer: &S,
+    ) -> Result<Cell, TonMessageError> {
+        let signed = self
+            .wallet
+            .sign_external_body_with_signer(signer, &self.body)
+            .await?;
+        self.wallet.wrap_signed_body(signed, self.state_init)
+    }
+
+    /// Creates the message with `signature` of `hash` made elsewhere.
+    pub fn with_signature(&self, signature: &[u8]) -> Result<Cell, TonMessageError> {
+        let signed = self.wallet.attach_signature(&self.body, signature)?;
+        self.wallet.wrap_signed_body(signed, self.state_init)
+    }
+
+    /// Same as `with_signature`, but serializes the message to a BoC.
+    pub fn with_signature_boc(&self, signature: &[u8]) -> Result<Vec<u8>, TonMessageError> {
+        to_boc(self.with_signature(signature)?)
+    }
+}
+
+fn to_boc(message: Cell) -> Result<Vec<u8>, TonMessageError> {
+    Ok(BagOfCells::new(&[Arc::new(message)]).serialize(true)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use nacl::sign::signature;
+
+    use super::TonTransactionBuilder;
+    use crate::cell::{BagOfCells, CellBuilder, TonCellError};
+    use crate::message::{ExternalInMessage, TonMessage};
+    use crate::mnemonic::{KeyPair, Mnemonic};
+    use crate::wallet::{ExpirationPolicy, TonWallet, WalletVersion};
+
+    #[test]
+    fn offline_transaction_builder_works() -> Result<(), TonCellError> {
+        let mnemonic_str = "fancy carpet hello mandate penalty trial consider \
+        property top vicious exit rebuild tragic profit urban major total month holiday \
+        sudden rib gather media vicious";
+        let key_pair = Mnemonic::from_str(mnemonic_str, &None)
+            .unwrap()
+            .to_key_pair()
+            .unwrap();
+        let internal_message = Arc::new(CellBuilder::new().store_u32(32, 7)?.build()?);
+        for version in [WalletVersion::V4R2, WalletVersion::V5R1] {
+            let wallet = TonWallet::derive_default(version.clone(), &key_pair)?;
+            let boc = TonTransactionBuilder::new(&wallet, 3, 1_700_000_000)
+                .with_internal_message(&internal_message)
+                .with_state_init(true)
+                .build_boc()
+                .unwrap();
+            let message = BagOfCells::parse(&boc)?.single_root()?.clone();
+            assert_eq!(
+                message.as_ref(),
+                &wallet
+                    .create_external_message(1_700_000_000, 3, [internal_message.clone()], true)
+                    .unwrap()
+            );
+            let deadline = ExpirationPolicy::deadline(1_700_000_000);
+            let policy_boc = TonTransactionBuilder::with_expiration_policy(&wallet, 3, &deadline)
+                .with_internal_message(&internal_message)
+                .with_state_init(true)
+                .build_boc()
+                .unwrap();
+            assert_eq!(policy_boc, boc);
+
+            // Build knowing only the public key and sign elsewhere
+            let watch_only = KeyPair {
+                public_key: key_pair.public_key.clone(),
+                secret_key: vec![],
+            };
+            let watch_only_wallet = TonWallet::derive_default(version, &watch_only)?;
+            assert_eq!(watch_only_wallet.address, wallet.address);
+            let unsigned = TonTransactionBuilder::new(&watch_only_wallet, 3, 1_700_000_000)
+                .with_internal_messages([internal_message.clone()])
+                .with_state_init(true)
+                .build_unsigned()
+                .unwrap();
+            let sig = signature(unsigned.hash().as_slice(), &key_pair.secret_key).unwrap();
+            let signed_boc = unsigned.with_signature_boc(&sig).unwrap();
+            assert_eq!(signed_boc, boc);
+            assert!(unsigned.with_signature(&sig[..63]).is_err());
+
+            let parsed = ExternalInMessage::parse(&message).unwrap();
+            assert_eq!(parsed.dest, wallet.address);
+        }
+        Ok(())
+    }
+}
@@ -1,5 +1,10 @@
-use crate::cell::{Cell, CellBuilder, TonCellError};
-use crate::TonHash;
+use std::collections::HashMap;
+
+use num_bigint::BigUint;
+
+use crate::cell::dict::predefined_readers::key_reader_256bit;
+use crate::cell::{Cell, CellBuilder, CellParser, TonCellError};
+use crate::{TonAddress, TonHash};
 
 /// WalletVersion::V1R1 | WalletVersion::V1R2 | WalletVersion::V1R3 | WalletVersion::V2R1 | WalletVersion::V2R2
 pub struct WalletDataV1V2 {
@@ -144,3 +149,97 @@ impl TryFrom<WalletDataHighloadV2R2> for Cell {
             .build()
     }
 }
+
+/// WalletVersion::V5R1
+pub struct WalletDataV5 {
+    /// Whether the wallet accepts requests signed with `public_key`,
+    /// otherwise only extensions can use it.
+    pub signature_allowed: bool,
+    pub seqno: u32,
+    pub wallet_id: i32,
+    pub public_key: TonHash,
+    /// Hash parts of the addresses of the extensions, which are in the workchain of the wallet.
+    pub extensions: Vec<TonHash>,
+}
+
+impl TryFrom<Cell> for WalletDataV5 {
+    type Error = TonCellError;
+
+    fn try_from(value: Cell) -> Result<Self, Self::Error> {
+        let mut parser = value.parser();
+        let signature_allowed = parser.load_bit()?;
+        let seqno = parser.load_u32(32)?;
+        let wallet_id = parser.load_i32(32)?;
+        let mut public_key = [0u8; 32];
+        parser.load_slice(&mut public_key)?;
+        let mut extensions: Vec<TonHash> = parser
+            .load_maybe_dict(256, key_reader_256bit, val_reader_extension)?
+            .into_keys()
+            .collect();
+        extensions.sort();
+        Ok(Self {
+            signature_allowed,
+            seqno,
+            wallet_id,
+            public_key,
+            extensions,
+        })
+    }
+}
+
+impl TryFrom<WalletDataV5> for Cell {
+    type Error = TonCellError;
+
+    fn try_from(value: WalletDataV5) -> Result<Self, Self::Error> {
+        let extensions: HashMap<BigUint, bool> = value
+            .extensions
+            .iter()
+            .map(|hash| (BigUint::from_bytes_be(hash), true))
+            .collect();
+        CellBuilder::new()
+            .store_bit(value.signature_allowed)?
+            .store_u32(32, value.seqno)?
+            .store_i32(32, value.wallet_id)?
+            .store_slice(&value.public_key)?
+            .store_maybe_dict(256, val_writer_extension, extensions)?
+            .build()
+    }
+}
+
+/// Extensions are stored as `HashmapE 256 int1` with `-1` values.
+fn val_reader_extension(parser: &mut CellParser) -> Result<bool, TonCellError> {
+    parser.load_bit()
+}
+
+fn val_writer_extension(builder: &mut CellBuilder, val: bool) -> Result<(), TonCellError> {
+    builder.store_bit(val)?;
+    Ok(())
+}
+
+/// Action of wallet v5 changing the wallet itself, sent along with internal messages.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum WalletV5ExtendedAction {
+    /// Allows the contract at the address to send requests to the wallet without signature.
+    AddExtension(TonAddress),
+    DeleteExtension(TonAddress),
+    /// Enables or disables requests signed with the wallet key.
+    /// The wallet refuses to disable them while it has no extensions.
+    SetSignatureAllowed(bool),
+}
+
+impl WalletV5ExtendedAction {
+    pub(crate) fn store(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        match self {
+            WalletV5ExtendedAction::AddExtension(address) => {
+                builder.store_u8(8, 2)?.store_address(address)?;
+            }
+            WalletV5ExtendedAction::DeleteExtension(address) => {
+                builder.store_u8(8, 3)?.store_address(address)?;
+            }
+            WalletV5ExtendedAction::SetSignatureAllowed(allowed) => {
+                builder.store_u8(8, 4)?.store_bit(*allowed)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,371 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use rand::RngCore;
+
+use crate::cell::TonCellError;
+use crate::mnemonic::{KeyPair, Mnemonic};
+use crate::wallet::{wallet_v5r1_id, TonWallet, WalletVersion};
+
+/// Number of attempts between calls of the progress callback of `VanitySearch::search`.
+pub const VANITY_PROGRESS_INTERVAL: u64 = 1024;
+
+const BASE64_URL_ALPHABET: &str =
+    "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+const MAINNET_GLOBAL_ID: i32 = -239;
+/// Number of subwallets of wallet v5r1, the subwallet number takes 15 bits of the wallet id.
+const WALLET_V5R1_SUBWALLETS: u64 = 1 << 15;
+
+/// Candidates tried by `VanitySearch::search`.
+pub enum VanitySource {
+    /// Key pairs of fresh mnemonics. Each attempt runs PBKDF2 with 100000 iterations,
+    /// so this only suits patterns of one or two characters.
+    Mnemonics,
+    /// Key pairs of random 32-byte private keys, the fastest way to search for a key pair.
+    Seeds,
+    /// Wallet ids of an existing key pair, keeping the mnemonic of the key pair. Wallet ids
+    /// start from the default wallet id of the version; for wallet v5r1 the subwallet number
+    /// goes from 0 to 32767, so at most 32768 wallet ids are tried. Not supported by wallets
+    /// v1 and v2.
+    WalletIds(KeyPair),
+}
+
+/// Wallet with an address matching the pattern of `VanitySearch`.
+pub struct VanityMatch {
+    pub wallet: TonWallet,
+    /// Mnemonic of the key pair, for `VanitySource::Mnemonics`.
+    pub mnemonic: Option<Mnemonic>,
+    /// Private key the key pair is created from with `KeyPair::from_seed`, for `VanitySource::Seeds`.
+    pub seed: Option<[u8; 32]>,
+    /// Number of attempts made by all threads until the match was found.
+    pub attempts: u64,
+}
+
+/// Brute-force search of wallets with a user-friendly address starting with a prefix and/or
+/// ending with a suffix.
+///
+/// The pattern is matched against the base64url mainnet address, so the prefix includes
+/// the tag, e.g. `EQ` for bounceable or `UQ` for non-bounceable addresses in workchain 0.
+/// Every extra character makes the search 64 times longer, or about 32 times longer
+/// for case-insensitive search.
+pub struct VanitySearch {
+    version: WalletVersion,
+    workchain: i32,
+    network_global_id: i32,
+    prefix: String,
+    suffix: String,
+    case_sensitive: bool,
+    non_bounceable: bool,
+    threads: usize,
+    max_attempts: Option<u64>,
+}
+
+impl VanitySearch {
+    /// Creates a search of mainnet wallets of `version` in workchain 0 using all available cores.
+    pub fn new(version: WalletVersion) -> VanitySearch {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        VanitySearch {
+            version,
+            workchain: 0,
+            network_global_id: MAINNET_GLOBAL_ID,
+            prefix: String::new(),
+            suffix: String::new(),
+            case_sensitive: true,
+            non_bounceable: false,
+            threads,
+            max_attempts: None,
+        }
+    }
+
+    pub fn with_workchain(&mut self, workchain: i32) -> &mut Self {
+        self.workchain = workchain;
+        self
+    }
+
+    /// Sets the global id of the network wallet v5r1 ids are computed for, -239 for mainnet
+    /// or -3 for testnet, see `wallet_v5r1_id`.
+    pub fn with_network_global_id(&mut self, network_global_id: i32) -> &mut Self {
+        self.network_global_id = network_global_id;
+        self
+    }
+
+    pub fn with_prefix(&mut self, prefix: &str) -> &mut Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    pub fn with_suffix(&mut self, suffix: &str) -> &mut Self {
+        self.suffix = suffix.to_string();
+        self
+    }
+
+    pub fn with_case_sensitive(&mut self, case_sensitive: bool) -> &mut Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Matches the non-bounceable representation of addresses, the one used for wallets by
+    /// most wallet apps.
+    pub fn with_non_bounceable(&mut self, non_bounceable: bool) -> &mut Self {
+        self.non_bounceable = non_bounceable;
+        self
+    }
+
+    pub fn with_threads(&mut self, threads: usize) -> &mut Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// Gives up after `max_attempts` attempts of all threads.
+    pub fn with_max_attempts(&mut self, max_attempts: u64) -> &mut Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Returns whether the address of `wallet` matches the pattern.
+    pub fn matches(&self, wallet: &TonWallet) -> bool {
+        let address = wallet
+            .address
+            .to_base64_url_flags(self.non_bounceable, false);
+        if self.case_sensitive {
+            address.starts_with(&self.prefix) && address.ends_with(&self.suffix)
+        } else {
+            let address = address.to_lowercase();
+            address.starts_with(&self.prefix.to_lowercase())
+                && address.ends_with(&self.suffix.to_lowercase())
+        }
+    }
+
+    /// Tries candidates of `source` in parallel until a wallet matches the pattern, returns
+    /// `None` if none matches within the maximum number of attempts.
+    ///
+    /// `progress` is called from the worker threads with the total number of attempts
+    /// every `VANITY_PROGRESS_INTERVAL` attempts.
+    pub fn search<F>(
+        &self,
+        source: &VanitySource,
+        progress: F,
+    ) -> Result<Option<VanityMatch>, TonCellError>
+    where
+        F: Fn(u64) + Sync,
+    {
+        self.validate(source)?;
+        let attempts = AtomicU64::new(0);
+        let stop = AtomicBool::new(false);
+        let result = Mutex::new(None);
+        thread::scope(|scope| {
+            for _ in 0..self.threads {
+                scope.spawn(|| {
+                    let found = self.run_worker(source, &attempts, &stop, &progress);
+                    if let Some(found) = found {
+                        result.lock().unwrap().get_or_insert(found);
+                    }
+                });
+            }
+        });
+        result.into_inner().unwrap().transpose()
+    }
+
+    fn validate(&self, source: &VanitySource) -> Result<(), TonCellError> {
+        let pattern = format!("{}{}", self.prefix, self.suffix);
+        if let Some(c) = pattern.chars().find(|c| !BASE64_URL_ALPHABET.contains(*c)) {
+            return Err(TonCellError::InvalidInput(format!(
+                "Character '{}' never occurs in base64url addresses",
+                c
+            )));
+        }
+        if self.prefix.len() + self.suffix.len() > 48 {
+            return Err(TonCellError::InvalidInput(format!(
+                "Pattern {}..{} is longer than an address",
+                self.prefix, self.suffix
+            )));
+        }
+        if let VanitySource::WalletIds(_) = source {
+            if matches!(
+                self.version,
+                WalletVersion::V1R1
+                    | WalletVersion::V1R2
+                    | WalletVersion::V1R3
+                    | WalletVersion::V2R1
+                    | WalletVersion::V2R2
+            ) {
+                return Err(TonCellError::InvalidInput(format!(
+                    "Wallet {:?} has no wallet id",
+                    self.version
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs attempts until a match is found or the search is stopped, returns the match
+    /// or the error of the attempt which failed.
+    fn run_worker<F>(
+        &self,
+        source: &VanitySource,
+        attempts: &AtomicU64,
+        stop: &AtomicBool,
+        progress: &F,
+    ) -> Option<Result<VanityMatch, TonCellError>>
+    where
+        F: Fn(u64) + Sync,
+    {
+        while !stop.load(Ordering::Relaxed) {
+            let attempt = attempts.fetch_add(1, Ordering::Relaxed);
+            let exhausted =
+                matches!(source, VanitySource::WalletIds(_)) && attempt >= self.wallet_id_count();
+            if exhausted || self.max_attempts.is_some_and(|max| attempt >= max) {
+                stop.store(true, Ordering::Relaxed);
+                return None;
+            }
+            if attempt > 0 && attempt.is_multiple_of(VANITY_PROGRESS_INTERVAL) {
+                progress(attempt);
+            }
+            match self.attempt(source, attempt) {
+                Ok(Some(mut found)) => {
+                    stop.store(true, Ordering::Relaxed);
+                    found.attempts = attempt + 1;
+                    return Some(Ok(found));
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    stop.store(true, Ordering::Relaxed);
+                    return Some(Err(e));
+                }
+            }
+        }
+        None
+    }
+
+    fn attempt(
+        &self,
+        source: &VanitySource,
+        attempt: u64,
+    ) -> Result<Option<VanityMatch>, TonCellError> {
+        let (key_pair, wallet_id, mnemonic, seed) = match source {
+            VanitySource::Mnemonics => {
+                let mnemonic = Mnemonic::generate(&None)
+                    .map_err(|e| TonCellError::InternalError(e.to_string()))?;
+                let key_pair = mnemonic
+                    .to_key_pair()
+                    .map_err(|e| TonCellError::InternalError(e.to_string()))?;
+                (key_pair, self.wallet_id(0), Some(mnemonic), None)
+            }
+            VanitySource::Seeds => {
+                let mut seed = [0u8; 32];
+                rand::thread_rng().fill_bytes(&mut seed);
+                let key_pair = KeyPair::from_seed(&seed);
+                (key_pair, self.wallet_id(0), None, Some(seed))
+            }
+            VanitySource::WalletIds(key_pair) => {
+                (key_pair.clone(), self.wallet_id(attempt), None, None)
+            }
+        };
+        let wallet = TonWallet::derive(self.workchain, self.version.clone(), &key_pair, wallet_id)?;
+        if !self.matches(&wallet) {
+            return Ok(None);
+        }
+        Ok(Some(VanityMatch {
+            wallet,
+            mnemonic,
+            seed,
+            attempts: 0,
+        }))
+    }
+
+    /// Returns the `index`-th wallet id of the version in the workchain of the search,
+    /// the default wallet id for index 0.
+    fn wallet_id(&self, index: u64) -> i32 {
+        match self.version {
+            WalletVersion::V5R1 => {
+                wallet_v5r1_id(self.network_global_id, self.workchain, index as u32)
+            }
+            _ => self.version.default_wallet_id().wrapping_add(index as i32),
+        }
+    }
+
+    fn wallet_id_count(&self) -> u64 {
+        match self.version {
+            WalletVersion::V5R1 => WALLET_V5R1_SUBWALLETS,
+            _ => 1 << 32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    use super::{VanitySearch, VanitySource};
+    use crate::cell::TonCellError;
+    use crate::mnemonic::KeyPair;
+    use crate::wallet::{wallet_v5r1_id, TonWallet, WalletVersion};
+
+    #[test]
+    fn test_vanity_search() -> Result<(), TonCellError> {
+        let key_pair = KeyPair::from_seed(&[7; 32]);
+        let source = VanitySource::WalletIds(key_pair.clone());
+        let mut search = VanitySearch::new(WalletVersion::V4R2);
+        search
+            .with_prefix("uq")
+            .with_suffix("x")
+            .with_case_sensitive(false)
+            .with_non_bounceable(true)
+            .with_threads(4);
+        let found = search.search(&source, |_| {})?.unwrap();
+        let address = found.wallet.address.to_base64_url_flags(true, false);
+        assert!(address.starts_with("UQ"));
+        assert!(address.ends_with('x') || address.ends_with('X'));
+        assert!(found.attempts > 0);
+        let wallet = TonWallet::derive(0, WalletVersion::V4R2, &key_pair, found.wallet.wallet_id)?;
+        assert_eq!(wallet.address, found.wallet.address);
+
+        let found = VanitySearch::new(WalletVersion::V5R1)
+            .with_workchain(-1)
+            .with_prefix("Ef")
+            .search(&VanitySource::Seeds, |_| {})?
+            .unwrap();
+        let key_pair = KeyPair::from_seed(&found.seed.unwrap());
+        assert!(found.wallet.key_pair == key_pair);
+        assert_eq!(found.wallet.address.workchain, -1);
+        assert_eq!(found.wallet.wallet_id, wallet_v5r1_id(-239, -1, 0));
+
+        let found = VanitySearch::new(WalletVersion::V5R1)
+            .with_workchain(-1)
+            .with_suffix("a")
+            .with_case_sensitive(false)
+            .search(&source, |_| {})?
+            .unwrap();
+        let subwallet_number = (found.attempts - 1) as u32;
+        let wallet_id = wallet_v5r1_id(-239, -1, subwallet_number);
+        assert_eq!(found.wallet.wallet_id, wallet_id);
+        let key_pair = KeyPair::from_seed(&[7; 32]);
+        let wallet = TonWallet::derive(-1, WalletVersion::V5R1, &key_pair, wallet_id)?;
+        assert_eq!(wallet.address, found.wallet.address);
+
+        // All 32768 subwallets of wallet v5r1 are tried
+        let found = VanitySearch::new(WalletVersion::V5R1)
+            .with_suffix("AAAAAAAAAA")
+            .search(&source, |_| {})?;
+        assert!(found.is_none());
+
+        let progress = AtomicU64::new(0);
+        let found = VanitySearch::new(WalletVersion::V3R2)
+            .with_suffix("AAAAAAAAAA")
+            .with_max_attempts(2048)
+            .search(&source, |attempts| {
+                progress.fetch_max(attempts, Ordering::Relaxed);
+            })?;
+        assert!(found.is_none());
+        assert_eq!(progress.load(Ordering::Relaxed), 1024);
+
+        let result = VanitySearch::new(WalletVersion::V4R2)
+            .with_prefix("EQ+")
+            .search(&source, |_| {});
+        assert!(matches!(result, Err(TonCellError::InvalidInput(_))));
+        let result = VanitySearch::new(WalletVersion::V2R2).search(&source, |_| {});
+        assert!(matches!(result, Err(TonCellError::InvalidInput(_))));
+        Ok(())
+    }
+}
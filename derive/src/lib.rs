@@ -0,0 +1,27 @@
+//! Derive macros for `tonlib-core`, re-exported by it.
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+mod tlb;
+
+/// Derives `tonlib_core::tlb::TlbType` for a struct or an enum mirroring a TL-B schema.
+///
+/// Fields are read and written in declaration order, each by its own `TlbType` implementation
+/// unless overridden by a field attribute:
+///
+/// * `#[tlb(bits = N)]` - integer of `N` bits, `uintN` or `intN`, see `TlbBits`;
+/// * `#[tlb(coins)]` - `Grams` amount stored to `BigUint`;
+/// * `#[tlb(ref)]` - value stored in a reference, `^T`, or `Maybe ^T` for `Option<T>`;
+/// * `#[tlb(either_ref)]` - value stored either inline or in a reference, `Either T ^T`.
+///
+/// A constructor tag, in TL-B notation, is set by `#[tlb(tag = "#0f8a7ea5")]` or
+/// `#[tlb(tag = "$01")]` on a struct or on every variant of an enum. All tags of an enum must be
+/// of the same length.
+#[proc_macro_derive(Tlb, attributes(tlb))]
+pub fn derive_tlb(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    tlb::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,274 @@
+use proc_macro2::{Literal, TokenStream};
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{Attribute, Data, DeriveInput, Error, Fields, Ident, LitInt, LitStr, Result};
+
+const MAX_TAG_BITS: usize = 64;
+
+struct Tag {
+    value: u64,
+    bit_len: usize,
+}
+
+impl Tag {
+    fn parse(lit: &LitStr) -> Result<Tag> {
+        let tag = lit.value();
+        let (radix, digit_bits, digits) = if let Some(digits) = tag.strip_prefix('#') {
+            (16, 4, digits)
+        } else if let Some(digits) = tag.strip_prefix('$') {
+            (2, 1, digits)
+        } else {
+            return Err(Error::new(lit.span(), "Tag must start with '#' or '$'"));
+        };
+        let bit_len = digits.len() * digit_bits;
+        if bit_len == 0 || bit_len > MAX_TAG_BITS {
+            return Err(Error::new(
+                lit.span(),
+                format!("Tag must be of 1 to {MAX_TAG_BITS} bits"),
+            ));
+        }
+        let value = u64::from_str_radix(digits, radix)
+            .map_err(|e| Error::new(lit.span(), format!("Invalid tag: {e}")))?;
+        Ok(Tag { value, bit_len })
+    }
+
+    fn parse_attrs(attrs: &[Attribute]) -> Result<Option<Tag>> {
+        let mut tag = None;
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("tlb")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("tag") {
+                    tag = Some(Tag::parse(&meta.value()?.parse()?)?);
+                    Ok(())
+                } else {
+                    Err(meta.error("Unsupported tlb attribute, expected `tag`"))
+                }
+            })?;
+        }
+        Ok(tag)
+    }
+
+    fn read(&self, name: &str) -> TokenStream {
+        let Tag { value, bit_len } = self;
+        quote!(::tonlib_core::tlb::check_tag(parser, #bit_len, #value, #name)?;)
+    }
+
+    fn write(&self) -> TokenStream {
+        let Tag { value, bit_len } = self;
+        quote!(builder.store_u64(#bit_len, #value)?;)
+    }
+}
+
+enum FieldKind {
+    Plain,
+    Bits(LitInt),
+    Coins,
+    Ref,
+    EitherRef,
+}
+
+impl FieldKind {
+    fn parse_attrs(attrs: &[Attribute]) -> Result<FieldKind> {
+        let mut kind = FieldKind::Plain;
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("tlb")) {
+            attr.parse_nested_meta(|meta| {
+                if !matches!(kind, FieldKind::Plain) {
+                    return Err(meta.error("Only one tlb attribute is allowed per field"));
+                }
+                kind = if meta.path.is_ident("bits") {
+                    FieldKind::Bits(meta.value()?.parse()?)
+                } else if meta.path.is_ident("coins") {
+                    FieldKind::Coins
+                } else if meta.path.is_ident("ref") {
+                    FieldKind::Ref
+                } else if meta.path.is_ident("either_ref") {
+                    FieldKind::EitherRef
+                } else {
+                    return Err(meta.error(
+                        "Unsupported tlb attribute, expected `bits`, `coins`, `ref` or `either_ref`",
+                    ));
+                };
+                Ok(())
+            })?;
+        }
+        Ok(kind)
+    }
+
+    fn read(&self) -> TokenStream {
+        match self {
+            FieldKind::Plain => quote!(::tonlib_core::tlb::TlbType::read(parser)?),
+            FieldKind::Bits(bits) => quote!(::tonlib_core::tlb::TlbBits::read_bits(parser, #bits)?),
+            FieldKind::Coins => quote!(parser.load_coins()?),
+            FieldKind::Ref => quote!(::tonlib_core::tlb::TlbType::read_ref(parser)?),
+            FieldKind::EitherRef => quote!(::tonlib_core::tlb::read_either_ref(parser)?),
+        }
+    }
+
+    fn write(&self, value: &TokenStream) -> TokenStream {
+        match self {
+            FieldKind::Plain => quote!(::tonlib_core::tlb::TlbType::write(#value, builder)?;),
+            FieldKind::Bits(bits) => {
+                quote!(::tonlib_core::tlb::TlbBits::write_bits(#value, builder, #bits)?;)
+            }
+            FieldKind::Coins => quote!(builder.store_coins(#value)?;),
+            FieldKind::Ref => quote!(::tonlib_core::tlb::TlbType::write_ref(#value, builder)?;),
+            FieldKind::EitherRef => quote!(::tonlib_core::tlb::write_either_ref(#value, builder)?;),
+        }
+    }
+}
+
+/// Reading and writing of the fields of a struct or an enum variant.
+struct FieldsCode {
+    /// Constructs the value from the fields read, e.g. `{ a: read_a, b: read_b }`.
+    construct: TokenStream,
+    /// Destructures the value into bindings of the fields, e.g. `{ a: field_0, b: field_1 }`.
+    destructure: TokenStream,
+    /// Writes the fields from the bindings.
+    write: TokenStream,
+}
+
+impl FieldsCode {
+    fn new(fields: &Fields) -> Result<FieldsCode> {
+        let kinds = fields
+            .iter()
+            .map(|field| FieldKind::parse_attrs(&field.attrs))
+            .collect::<Result<Vec<_>>>()?;
+        let reads = kinds.iter().map(FieldKind::read);
+        let bindings: Vec<Ident> = (0..fields.len())
+            .map(|idx| format_ident!("field_{}", idx))
+            .collect();
+        let write = kinds
+            .iter()
+            .zip(&bindings)
+            .map(|(kind, binding)| kind.write(&quote!(#binding)))
+            .collect();
+        let (construct, destructure) = match fields {
+            Fields::Named(_) => {
+                let idents = fields.iter().map(|field| &field.ident);
+                let idents_ = idents.clone();
+                (
+                    quote!({ #(#idents: #reads),* }),
+                    quote!({ #(#idents_: #bindings),* }),
+                )
+            }
+            Fields::Unnamed(_) => (quote!(( #(#reads),* )), quote!(( #(#bindings),* ))),
+            Fields::Unit => (quote!(), quote!()),
+        };
+        Ok(FieldsCode {
+            construct,
+            destructure,
+            write,
+        })
+    }
+}
+
+pub(crate) fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let name = &input.ident;
+    let type_name = name.to_string();
+    let (read, write) = match &input.data {
+        Data::Struct(data) => {
+            let tag = Tag::parse_attrs(&input.attrs)?;
+            let read_tag = tag.as_ref().map(|tag| tag.read(&type_name));
+            let write_tag = tag.as_ref().map(Tag::write);
+            let FieldsCode {
+                construct,
+                destructure,
+                write,
+            } = FieldsCode::new(&data.fields)?;
+            (
+                quote! {
+                    #read_tag
+                    ::core::result::Result::Ok(Self #construct)
+                },
+                quote! {
+                    let Self #destructure = self;
+                    #write_tag
+                    #write
+                    ::core::result::Result::Ok(())
+                },
+            )
+        }
+        Data::Enum(data) => {
+            if Tag::parse_attrs(&input.attrs)?.is_some() {
+                return Err(Error::new(
+                    name.span(),
+                    "Tags of an enum are set on its variants",
+                ));
+            }
+            let mut bit_len = None;
+            let mut tags: Vec<u64> = vec![];
+            let mut read_arms = vec![];
+            let mut write_arms = vec![];
+            for variant in &data.variants {
+                let Some(tag) = Tag::parse_attrs(&variant.attrs)? else {
+                    return Err(Error::new(variant.span(), "Variant must have a tlb tag"));
+                };
+                if *bit_len.get_or_insert(tag.bit_len) != tag.bit_len {
+                    return Err(Error::new(
+                        variant.span(),
+                        "All tags of an enum must be of the same length",
+                    ));
+                }
+                if tags.contains(&tag.value) {
+                    return Err(Error::new(variant.span(), "Duplicate tag"));
+                }
+                tags.push(tag.value);
+
+                let ident = &variant.ident;
+                let value = Literal::u64_unsuffixed(tag.value);
+                let write_tag = tag.write();
+                let FieldsCode {
+                    construct,
+                    destructure,
+                    write,
+                } = FieldsCode::new(&variant.fields)?;
+                read_arms
+                    .push(quote!(#value => ::core::result::Result::Ok(Self::#ident #construct),));
+                write_arms.push(quote! {
+                    Self::#ident #destructure => {
+                        #write_tag
+                        #write
+                    }
+                });
+            }
+            let Some(bit_len) = bit_len else {
+                return Err(Error::new(name.span(), "Enum must have variants"));
+            };
+            (
+                quote! {
+                    match parser.load_u64(#bit_len)? {
+                        #(#read_arms)*
+                        tag => ::core::result::Result::Err(::tonlib_core::cell::TonCellError::CellParserError(format!(
+                            "Invalid tag of {}: {:#x}",
+                            #type_name, tag
+                        ))),
+                    }
+                },
+                quote! {
+                    match self {
+                        #(#write_arms)*
+                    }
+                    ::core::result::Result::Ok(())
+                },
+            )
+        }
+        Data::Union(_) => return Err(Error::new(name.span(), "Tlb can't be derived for unions")),
+    };
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::tonlib_core::tlb::TlbType for #name #ty_generics #where_clause {
+            fn read(
+                parser: &mut ::tonlib_core::cell::CellParser,
+            ) -> ::core::result::Result<Self, ::tonlib_core::cell::TonCellError> {
+                #read
+            }
+
+            fn write(
+                &self,
+                builder: &mut ::tonlib_core::cell::CellBuilder,
+            ) -> ::core::result::Result<(), ::tonlib_core::cell::TonCellError> {
+                #write
+            }
+        }
+    })
+}